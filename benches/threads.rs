@@ -0,0 +1,131 @@
+//! Thread building and flattening on synthetic groups.
+//!
+//! `build_threads_from_overview` converts each `OverviewEntry` and hands the
+//! result to `build_threads_from_hdr`, so the benches drive the latter
+//! directly with generated header data.
+
+use criterion::{black_box, criterion_group, criterion_main, BatchSize, Criterion};
+use september::nntp::{build_threads_from_hdr, HdrArticleData, ThreadNodeView, ThreadView};
+
+/// Articles per dataset
+const ARTICLES: usize = 10_000;
+
+/// Collapse threshold and page size used when flattening, as in the default config
+const COLLAPSE_THRESHOLD: usize = 5;
+const PER_PAGE: usize = 20;
+
+fn article(i: usize, references: &[String]) -> HdrArticleData {
+    HdrArticleData {
+        message_id: message_id(i),
+        references: (!references.is_empty()).then(|| references.join(" ")),
+        subject: format!("Subject {}", i / 10),
+        from: "Bench <bench@example.com>".to_string(),
+        date: format!(
+            "Mon, 01 Jan 2024 {:02}:{:02}:{:02} +0000",
+            (i / 3600) % 24,
+            (i / 60) % 60,
+            i % 60
+        ),
+    }
+}
+
+fn message_id(i: usize) -> String {
+    format!("<{}@bench.example.com>", i)
+}
+
+/// Many short threads: a root with nine direct replies each
+fn wide() -> Vec<HdrArticleData> {
+    (0..ARTICLES)
+        .map(|i| match i % 10 {
+            0 => article(i, &[]),
+            _ => article(i, &[message_id(i - i % 10)]),
+        })
+        .collect()
+}
+
+/// Fifty threads, each a single reply chain 200 articles deep
+fn deep() -> Vec<HdrArticleData> {
+    const DEPTH: usize = 200;
+    let mut articles = Vec::with_capacity(ARTICLES);
+    let mut chain = Vec::new();
+    for i in 0..ARTICLES {
+        if i % DEPTH == 0 {
+            chain.clear();
+        }
+        articles.push(article(i, &chain));
+        chain.push(message_id(i));
+    }
+    articles
+}
+
+/// One thread where every article replies to a pseudo-randomly chosen
+/// earlier one, giving a mix of depth and fan-out
+fn bushy() -> Vec<HdrArticleData> {
+    let mut state: u64 = 0x2545_f491_4f6c_dd1d;
+    let mut chains: Vec<Vec<String>> = Vec::with_capacity(ARTICLES);
+    let mut articles = Vec::with_capacity(ARTICLES);
+    for i in 0..ARTICLES {
+        let chain = if i == 0 {
+            Vec::new()
+        } else {
+            // xorshift64, fixed seed so runs are comparable
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            // Favour recent articles, as replies usually do
+            let parent = i - 1 - (state as usize % i.min(50));
+            let mut chain = chains[parent].clone();
+            chain.push(message_id(parent));
+            // Keep References bounded like real clients do
+            if chain.len() > 20 {
+                chain.drain(1..chain.len() - 19);
+            }
+            chain
+        };
+        articles.push(article(i, &chain));
+        chains.push(chain);
+    }
+    articles
+}
+
+fn datasets() -> [(&'static str, Vec<HdrArticleData>); 3] {
+    [("wide", wide()), ("deep", deep()), ("bushy", bushy())]
+}
+
+fn largest_thread(threads: &[ThreadView]) -> &ThreadNodeView {
+    &threads
+        .iter()
+        .max_by_key(|thread| thread.article_count)
+        .expect("dataset has threads")
+        .root
+}
+
+fn bench_build_threads(c: &mut Criterion) {
+    let mut group = c.benchmark_group("build_threads_from_hdr");
+    group.sample_size(20);
+    for (name, articles) in datasets() {
+        group.bench_function(name, |b| {
+            b.iter_batched(
+                || articles.clone(),
+                |articles| build_threads_from_hdr(black_box(articles)),
+                BatchSize::LargeInput,
+            )
+        });
+    }
+    group.finish();
+}
+
+fn bench_flatten_paginated(c: &mut Criterion) {
+    let mut group = c.benchmark_group("flatten_paginated");
+    for (name, articles) in datasets() {
+        let threads = build_threads_from_hdr(articles);
+        let root = largest_thread(&threads);
+        group.bench_function(name, |b| {
+            b.iter(|| root.flatten_paginated(black_box(1), PER_PAGE, COLLAPSE_THRESHOLD))
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_build_threads, bench_flatten_paginated);
+criterion_main!(benches);
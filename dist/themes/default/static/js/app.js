@@ -35,6 +35,46 @@ document.addEventListener('DOMContentLoaded', function() {
         return descendants;
     }
 
+    // Bodies of comments hidden under a collapsed comment are left out of the
+    // page; fetch them from the subtree fragment when it is expanded
+    var commentsContainer = document.querySelector('.thread-comments[data-subtree-url]');
+    function loadDeferred(comment) {
+        if (!commentsContainer) return;
+        var deferred = getDescendants(comment, commentsArray).filter(function(desc) {
+            return desc.dataset.deferred === 'true';
+        });
+        if (deferred.length === 0) return;
+        deferred.forEach(function(desc) {
+            desc.dataset.deferred = 'loading';
+        });
+
+        var xhr = new XMLHttpRequest();
+        xhr.open('GET', commentsContainer.dataset.subtreeUrl +
+            '&expand=' + encodeURIComponent(comment.dataset.messageId));
+        xhr.onload = function() {
+            if (xhr.status === 200) {
+                var fragment = document.createElement('template');
+                fragment.innerHTML = xhr.responseText;
+                fragment.content.querySelectorAll('[data-message-id]').forEach(function(loaded) {
+                    var target = commentsContainer.querySelector(
+                        '.comment[data-message-id="' + CSS.escape(loaded.dataset.messageId) + '"]');
+                    var body = target && target.querySelector('.comment-body');
+                    var replacement = loaded.querySelector('.comment-body');
+                    if (body && replacement) {
+                        body.parentNode.replaceChild(replacement, body);
+                        target.dataset.deferred = 'false';
+                    }
+                });
+            }
+            // Let a later expand retry whatever did not load
+            deferred.forEach(function(desc) {
+                if (desc.dataset.deferred === 'loading') desc.dataset.deferred = 'true';
+            });
+        };
+        xhr.onerror = xhr.onload;
+        xhr.send();
+    }
+
     // Initialize collapsed state
     commentsArray.forEach(function(comment) {
         if (comment.dataset.collapsed === 'true') {
@@ -54,6 +94,7 @@ document.addEventListener('DOMContentLoaded', function() {
             var count = this.dataset.count;
 
             if (isCollapsed) {
+                loadDeferred(comment);
                 // Expand: show descendants (but respect their own collapsed state)
                 descendants.forEach(function(desc) {
                     desc.classList.remove('collapsed-hidden');
@@ -94,6 +135,7 @@ document.addEventListener('DOMContentLoaded', function() {
                 var isHidden = descendants[0].classList.contains('collapsed-hidden');
                 if (isHidden) {
                     // Expand
+                    loadDeferred(comment);
                     descendants.forEach(function(desc) {
                         desc.classList.remove('collapsed-hidden');
                     });
@@ -107,13 +149,49 @@ document.addEventListener('DOMContentLoaded', function() {
         });
     });
 
+    // Poll delivery status of just-posted articles until the server confirms them
+    var pendingContainer = document.querySelector('.thread-comments[data-pending-url]');
+    if (pendingContainer) {
+        var pendingUrl = pendingContainer.dataset.pendingUrl;
+        var pollsLeft = 30;
+
+        var pollPending = function() {
+            var xhr = new XMLHttpRequest();
+            xhr.open('GET', pendingUrl);
+            xhr.onload = function() {
+                if (xhr.status !== 200) return;
+                JSON.parse(xhr.responseText).forEach(function(post) {
+                    var badge = pendingContainer.querySelector(
+                        '.delivery-status[data-message-id="' + CSS.escape(post.message_id) + '"]');
+                    if (!badge) return;
+                    if (post.status === 'confirmed') {
+                        badge.parentNode.removeChild(badge);
+                    } else if (post.status === 'unconfirmed') {
+                        badge.dataset.status = 'unconfirmed';
+                        badge.textContent = 'Not yet visible on the server';
+                    }
+                });
+                var stillPending = pendingContainer.querySelector('.delivery-status[data-status="pending"]');
+                if (stillPending && --pollsLeft > 0) {
+                    setTimeout(pollPending, 2000);
+                }
+            };
+            xhr.send();
+        };
+
+        if (pendingContainer.querySelector('.delivery-status[data-status="pending"]')) {
+            setTimeout(pollPending, 1000);
+        }
+    }
+
     // Group search/filter functionality (home page)
     var searchInput = document.getElementById('group-search');
     var cardsView = document.getElementById('cards-view');
     var searchResults = document.getElementById('search-results');
 
-    if (searchInput && cardsView && searchResults) {
-        var cards = cardsView.querySelectorAll('.group-card');
+    // The group tree block may be left out of the home page layout
+    if (searchInput && searchResults) {
+        var cards = cardsView ? cardsView.querySelectorAll('.group-card') : [];
         var resultItems = searchResults.querySelectorAll('.search-result-item');
 
         searchInput.addEventListener('input', function() {
@@ -121,12 +199,14 @@ document.addEventListener('DOMContentLoaded', function() {
 
             if (query === '') {
                 // Show cards, hide search results
-                cardsView.style.display = 'flex';
+                if (cardsView) {
+                    cardsView.style.display = 'flex';
+                }
                 searchResults.style.display = 'none';
                 cards.forEach(function(card) {
                     card.classList.remove('hidden');
                 });
-            } else if (query.length < 3) {
+            } else if (query.length < 3 && cardsView) {
                 // Filter cards by name for short queries
                 cardsView.style.display = 'flex';
                 searchResults.style.display = 'none';
@@ -140,7 +220,9 @@ document.addEventListener('DOMContentLoaded', function() {
                 });
             } else {
                 // Show full group search results for longer queries
-                cardsView.style.display = 'none';
+                if (cardsView) {
+                    cardsView.style.display = 'none';
+                }
                 searchResults.style.display = 'block';
 
                 var hasResults = false;
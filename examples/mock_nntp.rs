@@ -0,0 +1,63 @@
+//! Standalone mock NNTP server for the browser tests in `tests/integration/`.
+//!
+//! Runs the in-process mock from `tests/support/mock_nntp.rs` on a fixed
+//! address, with empty groups that posted articles are filed into, so the
+//! Docker environment can seed and browse it like a real news server.
+//!
+//! Usage: `cargo run --example mock_nntp -- [--listen ADDR] GROUP...`
+//! (default address 0.0.0.0:119)
+
+// The example uses only part of the test harness
+#[allow(dead_code)]
+#[path = "../tests/support/mock_nntp.rs"]
+mod mock_nntp;
+
+use std::net::SocketAddr;
+
+use mock_nntp::MockNntp;
+
+#[tokio::main]
+async fn main() {
+    let mut listen = SocketAddr::from(([0, 0, 0, 0], 119));
+    let mut groups = Vec::new();
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        if arg == "--listen" {
+            listen = args
+                .next()
+                .and_then(|addr| addr.parse().ok())
+                .expect("--listen needs an address such as 0.0.0.0:119");
+        } else {
+            groups.push(arg);
+        }
+    }
+
+    let mut builder = MockNntp::builder().keep_posts().listen(listen);
+    for group in &groups {
+        builder = builder.group(group, "", Vec::new());
+    }
+    let server = builder.start().await;
+    println!("Mock NNTP server listening on {}", server.addr());
+
+    let ctrl_c = async {
+        tokio::signal::ctrl_c()
+            .await
+            .expect("Failed to install Ctrl+C handler");
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())
+            .expect("Failed to install SIGTERM handler")
+            .recv()
+            .await;
+    };
+
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {}
+        _ = terminate => {}
+    }
+}
@@ -0,0 +1,17 @@
+//! Raw HEAD responses: charset detection, header lookup and unfolding, and
+//! the display processing the extracted fields go through.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use september::nntp::{compute_timeago, parse_date, parse_head, sanitize_header};
+
+fuzz_target!(|data: &[u8]| {
+    if let Some(article) = parse_head(data) {
+        assert!(!article.message_id.is_empty());
+        let _ = parse_date(&article.date);
+        let _ = compute_timeago(&article.date);
+        let _ = sanitize_header(&article.subject);
+        let _ = sanitize_header(&article.from);
+    }
+});
@@ -0,0 +1,12 @@
+//! Article bodies: quote stripping and preview truncation.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use september::nntp::compute_preview;
+
+fuzz_target!(|data: &[u8]| {
+    let body = String::from_utf8_lossy(data);
+    let (preview, _) = compute_preview(&body);
+    assert!(preview.len() <= body.len());
+});
@@ -0,0 +1,16 @@
+//! References headers. The first line is the article's own Message-ID, the
+//! rest is the header value.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use september::nntp::parse_references;
+
+fuzz_target!(|data: &str| {
+    let (message_id, references) = data.split_once('\n').unwrap_or((data, ""));
+    for id in parse_references(Some(references), message_id) {
+        assert!(!id.is_empty());
+        assert_ne!(id, message_id);
+        assert!(!id.contains(char::is_whitespace));
+    }
+});
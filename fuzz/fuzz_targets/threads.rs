@@ -0,0 +1,41 @@
+//! Thread building from HDR data. Each input line is one article:
+//! `message-id TAB references TAB subject TAB from TAB date`, like an
+//! overview line without the number and byte counts.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use september::nntp::{build_threads_from_hdr, HdrArticleData, ThreadNodeView};
+
+fuzz_target!(|data: &str| {
+    let articles: Vec<HdrArticleData> = data
+        .lines()
+        .map(|line| {
+            let mut fields = line.split('\t').map(str::to_string);
+            HdrArticleData {
+                message_id: fields.next().unwrap_or_default(),
+                references: fields.next(),
+                subject: fields.next().unwrap_or_default(),
+                from: fields.next().unwrap_or_default(),
+                date: fields.next().unwrap_or_default(),
+            }
+        })
+        .collect();
+    let count = articles.len();
+
+    let threads = build_threads_from_hdr(articles);
+    assert_eq!(
+        threads.iter().map(|t| t.article_count).sum::<usize>(),
+        count
+    );
+    for thread in &threads {
+        check_counts(&thread.root);
+    }
+});
+
+/// Every node's descendant count matches its subtree
+fn check_counts(node: &ThreadNodeView) -> usize {
+    let descendants: usize = node.replies.iter().map(|r| 1 + check_counts(r)).sum();
+    assert_eq!(node.descendant_count, descendants);
+    descendants
+}
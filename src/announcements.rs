@@ -0,0 +1,372 @@
+//! Site-wide announcement banners.
+//!
+//! When `[ui] announcements_path` is set, announcements are read from that
+//! TOML file, one `[[announcement]]` table each, and shown at the top of
+//! every page between their optional `starts_at` and `ends_at`. The file is
+//! the persistent store: the operator edits it and it is read again within
+//! `ANNOUNCEMENT_RELOAD_SECS`, without a restart. A file that fails to parse
+//! is logged and the previous announcements stay up.
+//!
+//! Visitors can dismiss an announcement; the IDs they dismissed are kept in
+//! the `ANNOUNCEMENT_DISMISS_COOKIE` cookie. `announcement_layer` picks the
+//! announcements to show for each request and runs the handler in `scope`,
+//! so `insert_auth_context` can add them to every page without each handler
+//! reading the cookie.
+
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, SystemTime};
+
+use chrono::{DateTime, Utc};
+use pulldown_cmark::{html, Options, Parser};
+use serde::{Deserialize, Serialize};
+
+use crate::config::{ConfigError, ANNOUNCEMENT_RELOAD_SECS};
+
+tokio::task_local! {
+    static VISIBLE: Arc<Vec<AnnouncementView>>;
+}
+
+/// Run `future` with `visible` as the announcements of the current request
+pub async fn scope<F: Future>(visible: Vec<AnnouncementView>, future: F) -> F::Output {
+    VISIBLE.scope(Arc::new(visible), future).await
+}
+
+/// Announcements to show on the page being rendered, if any
+pub fn current() -> Option<Arc<Vec<AnnouncementView>>> {
+    VISIBLE.try_with(Arc::clone).ok().filter(|v| !v.is_empty())
+}
+
+/// How prominently an announcement is shown
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AnnouncementLevel {
+    #[default]
+    Info,
+    Warning,
+    Maintenance,
+}
+
+/// One `[[announcement]]` table
+#[derive(Debug, Clone, Deserialize)]
+struct Announcement {
+    /// Stable identifier, remembered in the dismissal cookie
+    id: String,
+    #[serde(default)]
+    level: AnnouncementLevel,
+    /// Markdown text
+    message: String,
+    #[serde(default)]
+    starts_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    ends_at: Option<DateTime<Utc>>,
+    /// Whether visitors can hide it
+    #[serde(default = "Announcement::default_dismissible")]
+    dismissible: bool,
+}
+
+impl Announcement {
+    fn default_dismissible() -> bool {
+        true
+    }
+
+    fn is_active(&self, now: DateTime<Utc>) -> bool {
+        self.starts_at.is_none_or(|start| start <= now) && self.ends_at.is_none_or(|end| now < end)
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct AnnouncementFile {
+    #[serde(default, rename = "announcement")]
+    announcements: Vec<Announcement>,
+}
+
+/// An announcement as passed to templates
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct AnnouncementView {
+    pub id: String,
+    pub level: AnnouncementLevel,
+    /// Rendered HTML, trusted (it comes from the operator's file)
+    pub html: String,
+    pub dismissible: bool,
+}
+
+/// A loaded announcement with its rendered message
+struct Loaded {
+    announcement: Announcement,
+    html: String,
+}
+
+struct Contents {
+    /// Modification time of the file when it was read, `None` if it was missing
+    modified: Option<SystemTime>,
+    items: Vec<Loaded>,
+}
+
+/// The announcements file and its last good contents
+pub struct Announcements {
+    path: Option<PathBuf>,
+    contents: RwLock<Contents>,
+}
+
+impl Announcements {
+    /// No announcements, for instances without `announcements_path`
+    pub fn disabled() -> Self {
+        Self {
+            path: None,
+            contents: RwLock::new(Contents {
+                modified: None,
+                items: Vec::new(),
+            }),
+        }
+    }
+
+    /// Read the announcements at `path`. A missing file means no
+    /// announcements yet; one that doesn't parse is a configuration error.
+    pub fn load(path: &str) -> Result<Self, ConfigError> {
+        let path = PathBuf::from(path);
+        let (modified, items) = read(&path).map_err(|e| {
+            ConfigError::Validation(format!("Announcements '{}': {}", path.display(), e))
+        })?;
+        Ok(Self {
+            path: Some(path),
+            contents: RwLock::new(Contents { modified, items }),
+        })
+    }
+
+    /// Whether an announcement with this ID exists and can be dismissed
+    pub fn is_dismissible(&self, id: &str) -> bool {
+        let contents = self.contents.read().unwrap_or_else(|e| e.into_inner());
+        contents
+            .items
+            .iter()
+            .any(|item| item.announcement.id == id && item.announcement.dismissible)
+    }
+
+    /// IDs of all announcements in the file
+    pub fn ids(&self) -> Vec<String> {
+        let contents = self.contents.read().unwrap_or_else(|e| e.into_inner());
+        contents
+            .items
+            .iter()
+            .map(|item| item.announcement.id.clone())
+            .collect()
+    }
+
+    /// Announcements running at `now` that the visitor hasn't dismissed
+    pub fn visible(&self, now: DateTime<Utc>, dismissed: &[&str]) -> Vec<AnnouncementView> {
+        let contents = self.contents.read().unwrap_or_else(|e| e.into_inner());
+        contents
+            .items
+            .iter()
+            .filter(|item| item.announcement.is_active(now))
+            .filter(|item| {
+                !(item.announcement.dismissible
+                    && dismissed.contains(&item.announcement.id.as_str()))
+            })
+            .map(|item| AnnouncementView {
+                id: item.announcement.id.clone(),
+                level: item.announcement.level,
+                html: item.html.clone(),
+                dismissible: item.announcement.dismissible,
+            })
+            .collect()
+    }
+
+    /// Read the file again if it changed since it was last read
+    fn reload_if_changed(&self) {
+        let Some(path) = &self.path else {
+            return;
+        };
+        let modified = std::fs::metadata(path).and_then(|m| m.modified()).ok();
+        if modified
+            == self
+                .contents
+                .read()
+                .unwrap_or_else(|e| e.into_inner())
+                .modified
+        {
+            return;
+        }
+
+        match read(path) {
+            Ok((modified, items)) => {
+                tracing::info!(
+                    path = %path.display(),
+                    count = items.len(),
+                    "Reloaded announcements"
+                );
+                *self.contents.write().unwrap_or_else(|e| e.into_inner()) =
+                    Contents { modified, items };
+            }
+            Err(e) => {
+                tracing::warn!(
+                    path = %path.display(),
+                    error = %e,
+                    "Failed to reload announcements, keeping the previous ones"
+                );
+                // Don't log the same error every interval
+                self.contents
+                    .write()
+                    .unwrap_or_else(|e| e.into_inner())
+                    .modified = modified;
+            }
+        }
+    }
+
+    /// Check the file for changes every `ANNOUNCEMENT_RELOAD_SECS`
+    pub fn spawn_reload(self: Arc<Self>) {
+        if self.path.is_none() {
+            return;
+        }
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(Duration::from_secs(ANNOUNCEMENT_RELOAD_SECS));
+            interval.tick().await;
+            loop {
+                interval.tick().await;
+                let announcements = self.clone();
+                // Reading the file blocks; keep it off the runtime threads
+                let _ =
+                    tokio::task::spawn_blocking(move || announcements.reload_if_changed()).await;
+            }
+        });
+    }
+}
+
+/// Read and validate the announcements file. A missing file has none.
+fn read(path: &Path) -> Result<(Option<SystemTime>, Vec<Loaded>), String> {
+    let modified = match std::fs::metadata(path) {
+        Ok(metadata) => metadata.modified().ok(),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok((None, Vec::new())),
+        Err(e) => return Err(e.to_string()),
+    };
+    let source = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+    Ok((modified, parse(&source)?))
+}
+
+fn parse(source: &str) -> Result<Vec<Loaded>, String> {
+    let file: AnnouncementFile = toml::from_str(source).map_err(|e| e.to_string())?;
+    let mut ids: Vec<&str> = Vec::new();
+    for announcement in &file.announcements {
+        let id = announcement.id.as_str();
+        // IDs are stored comma-separated in a cookie and used in URLs
+        if id.is_empty()
+            || !id
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+        {
+            return Err(format!(
+                "announcement id '{}' may only contain letters, digits, '-' and '_'",
+                id
+            ));
+        }
+        if ids.contains(&id) {
+            return Err(format!("duplicate announcement id '{}'", id));
+        }
+        ids.push(id);
+    }
+
+    Ok(file
+        .announcements
+        .into_iter()
+        .map(|announcement| {
+            let mut html = String::with_capacity(announcement.message.len() * 3 / 2);
+            html::push_html(
+                &mut html,
+                Parser::new_ext(&announcement.message, Options::all()),
+            );
+            Loaded { announcement, html }
+        })
+        .collect())
+}
+
+/// Dismissed IDs from the cookie value
+pub fn parse_dismissed(cookie: &str) -> Vec<&str> {
+    cookie.split(',').filter(|id| !id.is_empty()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn announcements(source: &str) -> Announcements {
+        let announcements = Announcements::disabled();
+        announcements.contents.write().unwrap().items = parse(source).unwrap();
+        announcements
+    }
+
+    fn at(timestamp: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(timestamp)
+            .unwrap()
+            .with_timezone(&Utc)
+    }
+
+    #[test]
+    fn test_visible_follows_schedule_and_dismissals() {
+        let announcements = announcements(
+            r#"
+            [[announcement]]
+            id = "welcome"
+            message = "Welcome to the **new** server"
+
+            [[announcement]]
+            id = "upgrade"
+            level = "maintenance"
+            message = "Down for an upgrade on Saturday"
+            starts_at = "2026-03-01T00:00:00Z"
+            ends_at = "2026-03-08T00:00:00Z"
+            dismissible = false
+            "#,
+        );
+
+        let before = announcements.visible(at("2026-02-28T12:00:00Z"), &[]);
+        assert_eq!(before.len(), 1);
+        assert_eq!(before[0].id, "welcome");
+        assert!(before[0].html.contains("<strong>new</strong>"));
+        assert_eq!(before[0].level, AnnouncementLevel::Info);
+
+        let during = announcements.visible(at("2026-03-01T00:00:00Z"), &["welcome", "upgrade"]);
+        assert_eq!(during.len(), 1);
+        assert_eq!(during[0].id, "upgrade");
+        assert_eq!(during[0].level, AnnouncementLevel::Maintenance);
+
+        assert!(announcements
+            .visible(at("2026-03-08T00:00:00Z"), &["welcome"])
+            .is_empty());
+        assert!(announcements.is_dismissible("welcome"));
+        assert!(!announcements.is_dismissible("upgrade"));
+        assert!(!announcements.is_dismissible("missing"));
+    }
+
+    #[test]
+    fn test_parse_rejects_bad_ids() {
+        assert!(parse("[[announcement]]\nid = \"a,b\"\nmessage = \"x\"\n").is_err());
+        assert!(parse(
+            "[[announcement]]\nid = \"a\"\nmessage = \"x\"\n\
+             [[announcement]]\nid = \"a\"\nmessage = \"y\"\n"
+        )
+        .is_err());
+        assert!(parse("").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_parse_dismissed() {
+        assert_eq!(parse_dismissed("a,,b"), vec!["a", "b"]);
+        assert!(parse_dismissed("").is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_current_only_inside_scope() {
+        assert!(current().is_none());
+        let view = AnnouncementView {
+            id: "a".to_string(),
+            level: AnnouncementLevel::Warning,
+            html: "<p>a</p>".to_string(),
+            dismissible: true,
+        };
+        let inside = scope(vec![view.clone()], async { current() }).await;
+        assert_eq!(inside.as_deref(), Some(&vec![view]));
+        assert!(scope(Vec::new(), async { current() }).await.is_none());
+    }
+}
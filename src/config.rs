@@ -6,7 +6,13 @@
 
 use const_format::formatcp;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use regex::Regex;
+
+use crate::nntp::wildmat;
 
 // =============================================================================
 // HTTP Response Cache Control
@@ -93,6 +99,26 @@ pub const DEFAULT_PREVIEW_LINES: usize = 10;
 /// Default word count for truncate_words filter
 pub const DEFAULT_TRUNCATE_WORDS: usize = 50;
 
+/// Bytes of a passage's SHA-256 used in its fragment ID (10 hex digits)
+pub const PASSAGE_ID_HASH_BYTES: usize = 5;
+
+/// Shortest quoted text, in characters, matched against the parent's
+/// passages; anything shorter could come from almost anywhere
+pub const QUOTE_MATCH_MIN_CHARS: usize = 12;
+
+/// Renders taking at least this many milliseconds are logged as slow
+pub const SLOW_RENDER_MS: u64 = 100;
+
+/// Thread pages with at least this many comments are streamed to the client
+/// as they render, unless their comments come from the render cache
+pub const STREAM_RENDER_MIN_COMMENTS: usize = 50;
+
+/// Size of the chunks a streamed render sends
+pub const STREAM_RENDER_CHUNK_BYTES: usize = 16 * 1024;
+
+/// Chunks a streamed render may get ahead of the client before it waits
+pub const STREAM_RENDER_BUFFERED_CHUNKS: usize = 4;
+
 // Time unit constants (in seconds) for timeago filter
 /// Seconds in a minute
 pub const SECONDS_PER_MINUTE: i64 = 60;
@@ -112,6 +138,39 @@ pub const SECONDS_PER_YEAR: i64 = 31536000;
 /// Pagination window size (pages shown on each side of current page)
 pub const PAGINATION_WINDOW: usize = 2;
 
+/// Theme overlaid on the active theme for the no-JavaScript basic HTML mode
+pub const BASIC_MODE_THEME: &str = "basic";
+
+/// Theme overlaid on the active theme for text browsers (Lynx, w3m, ELinks)
+pub const TEXT_MODE_THEME: &str = "text";
+
+/// Cookie that remembers the visitor's rendering mode choice
+pub const RENDER_MODE_COOKIE: &str = "september_mode";
+
+/// Lifetime of the rendering mode cookie (1 year)
+pub const RENDER_MODE_COOKIE_MAX_AGE_SECS: i64 = 31536000;
+
+/// Cookie listing the announcement IDs the visitor dismissed
+pub const ANNOUNCEMENT_DISMISS_COOKIE: &str = "september_dismissed";
+
+/// Lifetime of the dismissal cookie (1 year)
+pub const ANNOUNCEMENT_DISMISS_MAX_AGE_SECS: i64 = 31536000;
+
+/// How often the announcements file is checked for changes (seconds)
+pub const ANNOUNCEMENT_RELOAD_SECS: u64 = 30;
+
+/// Posters shown in a thread list entry's avatar stack
+pub const THREAD_PARTICIPANTS_SHOWN: usize = 5;
+
+/// Hours after which a reply counts half as much toward thread hotness
+pub const HOT_THREAD_HALF_LIFE_HOURS: f64 = 6.0;
+
+/// Replies older than this many hours don't count toward thread hotness
+pub const HOT_THREAD_WINDOW_HOURS: i64 = 48;
+
+/// Hotness at which a thread is badged as hot
+pub const HOT_THREAD_MIN_SCORE: f64 = 3.0;
+
 // =============================================================================
 // NNTP Channel and Queue Constants
 // =============================================================================
@@ -129,19 +188,93 @@ pub const NNTP_LOW_PRIORITY_QUEUE_CAPACITY: usize = 100;
 /// of starvation to prevent indefinite delays under sustained high load
 pub const NNTP_PRIORITY_AGING_SECS: u64 = 10;
 
+/// Capacity of each of a worker's affinity queues (normal and low priority
+/// requests for the groups routed to it); requests overflow to the shared queue
+pub const NNTP_AFFINITY_QUEUE_CAPACITY: usize = 8;
+
+/// Points each worker owns on the consistent hash ring that assigns groups
+/// to workers; more points spread groups more evenly
+pub const NNTP_RING_POINTS_PER_WORKER: usize = 64;
+
+/// Seconds a worker trusts its selected group before issuing GROUP again.
+/// Some servers report the high-water mark as of the last GROUP, so the
+/// selection is refreshed now and then to pick up new articles.
+pub const NNTP_GROUP_RESELECT_SECS: u64 = 30;
+
+/// How often a pool that may grow (`max_workers` above `worker_count`)
+/// checks its load, in milliseconds. It adds at most one worker per check.
+pub const NNTP_SCALE_INTERVAL_MS: u64 = 500;
+
+/// Queue wait in milliseconds that makes such a pool add a worker
+pub const NNTP_SCALE_UP_WAIT_MS: u64 = 250;
+
+/// Seconds a worker added under load may sit idle before it disconnects
+pub const NNTP_SCALED_WORKER_IDLE_SECS: u64 = 60;
+
 /// Capacity of broadcast channels for request coalescing
 pub const BROADCAST_CHANNEL_CAPACITY: usize = 16;
 
+/// Days ahead of each request that security.txt expires when no `expires`
+/// is configured (RFC 9116 recommends less than a year)
+pub const SECURITY_TXT_DEFAULT_EXPIRY_DAYS: i64 = 180;
+
 // =============================================================================
 // NNTP Retry and Timeout Constants
 // =============================================================================
 
-/// Delay in seconds before reconnecting after connection failure
-pub const NNTP_RECONNECT_DELAY_SECS: u64 = 5;
+/// Delay in seconds before a worker reconnects after a failed attempt,
+/// doubled for each further consecutive failure
+pub const NNTP_RECONNECT_BASE_DELAY_SECS: u64 = 2;
+
+/// Upper bound in seconds on the delay between a worker's connection attempts
+pub const NNTP_RECONNECT_MAX_DELAY_SECS: u64 = 120;
+
+/// Consecutive failed attempts by a worker, with no worker connected, after
+/// which its server is marked unhealthy and tried after the others
+pub const NNTP_UNHEALTHY_AFTER_FAILURES: u32 = 3;
+
+/// Head start in milliseconds each connection attempt gets before the next
+/// resolved address is tried alongside it (RFC 8305 Connection Attempt Delay)
+pub const NNTP_CONNECTION_ATTEMPT_DELAY_MS: u64 = 250;
+
+/// Timeout in seconds for a TCP connection attempt to a single address
+pub const NNTP_CONNECTION_ATTEMPT_TIMEOUT_SECS: u64 = 10;
+
+/// Longest response line read while negotiating STARTTLS (RFC 3977 limit)
+pub const NNTP_STARTTLS_MAX_LINE_LENGTH: usize = 512;
+
+/// Bounds in seconds on how long a DNS answer for a server is cached,
+/// whatever its record TTL says
+pub const DNS_MIN_TTL_SECS: u64 = 10;
+pub const DNS_MAX_TTL_SECS: u64 = 300;
+
+/// Seconds a DNS answer is cached when the system resolver reports no TTL
+pub const DNS_FALLBACK_TTL_SECS: u64 = 60;
+
+/// Seconds past its expiry an answer is still used if the resolver fails
+pub const DNS_STALE_SECS: u64 = 3600;
+
+/// Attempts, including the first, for idempotent reads of each priority.
+/// Transient failures (timeouts, lost connections, 400/403) are retried.
+pub const NNTP_RETRY_ATTEMPTS_HIGH: u32 = 2;
+pub const NNTP_RETRY_ATTEMPTS_NORMAL: u32 = 2;
+pub const NNTP_RETRY_ATTEMPTS_LOW: u32 = 3;
+
+/// Backoff in milliseconds before the first retry, doubled for each further one
+pub const NNTP_RETRY_BASE_DELAY_MS: u64 = 200;
+
+/// Upper bound in milliseconds on a single retry backoff
+pub const NNTP_RETRY_MAX_DELAY_MS: u64 = 5000;
 
 /// TTL in seconds for negative cache (article not found)
 pub const NNTP_NEGATIVE_CACHE_TTL_SECS: u64 = 30;
 
+/// TTL in seconds for the negative group cache (every server replied 411)
+pub const NNTP_GROUP_NEGATIVE_CACHE_TTL_SECS: u64 = 60;
+
+/// Maximum groups held in the negative group cache
+pub const NNTP_GROUP_NEGATIVE_CACHE_MAX_ENTRIES: u64 = 10_000;
+
 // =============================================================================
 // NNTP Article Fetch Limits
 // =============================================================================
@@ -152,12 +285,56 @@ pub const NNTP_MAX_ARTICLES_PER_REQUEST: u64 = 10000;
 /// Maximum articles for HEAD fallback method (slowest path)
 pub const NNTP_MAX_ARTICLES_HEAD_FALLBACK: u64 = 1000;
 
+/// Articles back from the newest whose date is sampled to estimate a
+/// group's posts per day
+pub const NNTP_TRAFFIC_SAMPLE_ARTICLES: u64 = 100;
+
+/// Default for `[nntp] max_per_page` when no configured page size
+/// is larger
+pub const DEFAULT_MAX_PER_PAGE: usize = 100;
+
+/// Default for `[nntp] body_fetch_concurrency`: article fetches one page
+/// load keeps in flight per server
+pub const DEFAULT_BODY_FETCH_CONCURRENCY: usize = 4;
+
 /// Multiplier for individual thread cache capacity (relative to thread_lists)
 pub const THREAD_CACHE_MULTIPLIER: u64 = 10;
 
+/// Default soft TTL for thread lists, group stats and the group list, as a
+/// fraction of the cache TTL. Past the soft TTL a cached value is still
+/// served while a background fetch replaces it.
+pub const CACHE_SOFT_TTL_DIVISOR: u64 = 3;
+
 /// Divisor for negative cache size (relative to article cache)
 pub const NEGATIVE_CACHE_SIZE_DIVISOR: u64 = 4;
 
+/// TTL for thread list snapshots that keep pagination stable (10 minutes)
+/// A user paging through a group keeps seeing the ordering they started with
+pub const THREAD_LIST_SNAPSHOT_TTL_SECS: u64 = 600;
+
+/// Threads thread list snapshots may hold per `max_thread_lists`.
+/// Snapshots are weighed by thread count, so a busy group's large list
+/// uses up more of the budget than a quiet group's.
+pub const THREAD_LIST_SNAPSHOT_THREADS_PER_LIST: u64 = 2000;
+
+/// Maximum number of rendered thread comment pages kept
+pub const THREAD_FRAGMENT_CACHE_MAX_ENTRIES: u64 = 1000;
+
+/// Maximum number of article bodies kept with footer rules applied
+pub const BODY_FOOTER_CACHE_MAX_ENTRIES: u64 = 10_000;
+
+/// TTL for stripped bodies (1 hour). Articles don't change, so this only
+/// bounds how long unread ones hold memory.
+pub const BODY_FOOTER_CACHE_TTL_SECS: u64 = 3600;
+
+/// Most `[[body.footer]]` rules, so the rules applying to an article fit in
+/// one bitmask
+pub const BODY_FOOTER_MAX_RULES: usize = 64;
+
+/// TTL for rendered thread comment pages (5 minutes). Their keys change with
+/// the thread, so this only bounds how long unread pages hold memory.
+pub const THREAD_FRAGMENT_CACHE_TTL_SECS: u64 = 300;
+
 // =============================================================================
 // Incremental Update Constants
 // =============================================================================
@@ -174,6 +351,14 @@ pub const BACKGROUND_REFRESH_MIN_PERIOD_SECS: u64 = 1;
 /// Any activity at all = refresh every 30 seconds
 pub const BACKGROUND_REFRESH_MAX_PERIOD_SECS: u64 = 30;
 
+/// Maximum number of background incremental refreshes running at once,
+/// across all groups
+pub const BACKGROUND_REFRESH_CONCURRENCY: usize = 8;
+
+/// Random spread applied to each background refresh period, as a fraction
+/// of the period (0.2 = +/-20%), so group refreshes don't line up
+pub const BACKGROUND_REFRESH_JITTER: f64 = 0.2;
+
 /// Moving average window for request rate calculation (seconds)
 pub const ACTIVITY_WINDOW_SECS: u64 = 300; // 5 minutes
 
@@ -185,9 +370,28 @@ pub const ACTIVITY_BUCKET_COUNT: u64 = 150;
 /// High request rate threshold (requests/second) for minimum refresh period
 pub const ACTIVITY_HIGH_RPS: f64 = 10000.0;
 
+/// Maximum number of groups with tracked activity (and refresh tasks).
+/// Past this, inactive groups are dropped first, then the least recently seen.
+pub const ACTIVITY_MAX_TRACKED_GROUPS: usize = 1000;
+
 /// Interval between group stats background refreshes (1 hour)
 pub const GROUP_STATS_REFRESH_INTERVAL_SECS: u64 = 3600;
 
+/// Interval between upstream availability samples (1 minute)
+pub const AVAILABILITY_PROBE_INTERVAL_SECS: u64 = 60;
+
+/// Days of upstream availability history kept per server
+pub const AVAILABILITY_HISTORY_DAYS: usize = 30;
+
+/// Most active groups rewarmed when a server becomes reachable again
+pub const RECOVERY_WARMUP_MAX_GROUPS: usize = 20;
+
+/// Busiest groups whose cached thread lists feed the home page's top threads
+pub const HOME_TOP_THREADS_MAX_GROUPS: usize = 20;
+
+/// Groups rewarmed concurrently after recovery, to avoid a burst against the recovered server
+pub const RECOVERY_WARMUP_CONCURRENCY: usize = 4;
+
 /// Maximum polling attempts when waiting for a posted article to appear.
 /// After posting, we poll the NNTP server until the article is found.
 pub const POST_POLL_MAX_ATTEMPTS: u32 = 15;
@@ -196,6 +400,87 @@ pub const POST_POLL_MAX_ATTEMPTS: u32 = 15;
 /// Total max wait time = POST_POLL_MAX_ATTEMPTS * POST_POLL_INTERVAL_MS
 pub const POST_POLL_INTERVAL_MS: u64 = 10;
 
+/// How long delivery status is kept for posted articles (5 minutes).
+/// Long enough for the poster's browser to see the final status.
+pub const PENDING_POST_TTL_SECS: u64 = 300;
+
+/// Maximum number of posted articles tracked for delivery status
+pub const PENDING_POST_MAX_ENTRIES: u64 = 10_000;
+
+// =============================================================================
+// Spam Filtering Constants
+// =============================================================================
+
+/// Minimum words in a root body before it is fingerprinted.
+/// Short posts ("+1", "thanks") collide too easily to compare.
+pub const DUPLICATE_MIN_WORDS: usize = 20;
+
+/// Words per shingle when computing body simhashes
+pub const DUPLICATE_SHINGLE_WORDS: usize = 3;
+
+/// Maximum number of root fingerprints remembered within the duplicate window
+pub const DUPLICATE_MAX_FINGERPRINTS: u64 = 20_000;
+
+// =============================================================================
+// Vote Tally Constants
+// =============================================================================
+
+/// Maximum characters kept from a "Vote: <choice>" line
+pub const VOTE_MAX_CHOICE_LEN: usize = 32;
+
+// =============================================================================
+// Header Sanitization Constants
+// =============================================================================
+
+/// Maximum consecutive combining marks kept on one character in header values.
+/// Longer runs ("Zalgo" text) overflow into neighbouring lines.
+pub const HEADER_MAX_COMBINING_MARKS: usize = 4;
+
+// =============================================================================
+// Path Parameter Limits
+// =============================================================================
+
+/// Maximum length of a newsgroup name in a URL
+pub const GROUP_NAME_MAX_LEN: usize = 255;
+
+/// Maximum length of a message ID including angle brackets (RFC 3977 limit)
+pub const MESSAGE_ID_MAX_LEN: usize = 250;
+
+// =============================================================================
+// Posting Identity Limits
+// =============================================================================
+
+/// Maximum characters in a display name chosen for posts
+pub const POSTING_NAME_MAX_LEN: usize = 64;
+
+/// Maximum length of a Reply-To address (RFC 5321 path limit)
+pub const REPLY_TO_MAX_LEN: usize = 254;
+
+// =============================================================================
+// Post Review Constants
+// =============================================================================
+
+/// Maximum posts one user may have waiting for review at a time
+pub const REVIEW_MAX_HELD_PER_POSTER: usize = 5;
+
+/// Months of review decisions shown in the transparency report
+pub const TRANSPARENCY_REPORT_MONTHS: usize = 12;
+
+// =============================================================================
+// Peering Listener Constants
+// =============================================================================
+
+/// Idle timeout for downstream reader connections.
+/// RFC 3977 requires servers to wait at least 3 minutes before dropping idle clients.
+pub const PEERING_IDLE_TIMEOUT_SECS: u64 = 600;
+
+/// Maximum command line length accepted from downstream readers (RFC 3977 limit)
+pub const PEERING_MAX_LINE_LENGTH: usize = 512;
+
+/// Message-IDs the reader listener keeps article numbers for, across all
+/// groups. Least recently used groups are forgotten beyond this.
+pub const PEERING_NUMBERED_ARTICLES_MAX: u64 = 100_000;
+
 // =============================================================================
 // Default Paths and Strings
 // =============================================================================
@@ -229,17 +514,48 @@ pub struct AppConfig {
     #[serde(default)]
     pub server: Vec<NntpServerConfig>,
     pub ui: UiConfig,
+    /// Who runs this instance, as announced to users, servers and clients
+    #[serde(default)]
+    pub instance: InstanceConfig,
+    /// Blocks making up the home page, in order
+    #[serde(default)]
+    pub home: HomeConfig,
     #[serde(default)]
     pub cache: CacheConfig,
     /// Logging configuration
     #[serde(default)]
     pub logging: LoggingConfig,
+    /// Spam filtering configuration
+    #[serde(default)]
+    pub spam: SpamConfig,
+    /// Subject normalization rules for display and subject-based grouping
+    #[serde(default)]
+    pub subjects: SubjectConfig,
+    /// Who may post (open or invite-only)
+    #[serde(default)]
+    pub posting: PostingConfig,
+    /// Groups whose thread views show a vote tally
+    #[serde(default)]
+    pub votes: VoteConfig,
+    /// Per-group background refresh periods
+    #[serde(default)]
+    pub refresh: RefreshConfig,
+    /// Signatures and gateway footers stripped from displayed bodies
+    #[serde(default)]
+    pub body: BodyConfig,
     /// Theme configuration
     #[serde(default)]
     pub theme: ThemeConfig,
     /// OpenID Connect authentication (optional)
     #[serde(default)]
     pub oidc: Option<OidcConfig>,
+    /// Read-only NNTP listener for downstream newsreaders (optional)
+    #[serde(default)]
+    pub peering: Option<PeeringConfig>,
+    /// Renamed groups (old name -> current name).
+    /// Aliased names redirect to the current name and share its caches.
+    #[serde(default)]
+    pub group_aliases: HashMap<String, String>,
 }
 
 /// HTTP server configuration
@@ -247,6 +563,10 @@ pub struct AppConfig {
 pub struct HttpServerConfig {
     pub host: String,
     pub port: u16,
+    /// Seconds a request may take before it is answered with 504; NNTP
+    /// requests queued for it are skipped once this has passed
+    #[serde(default = "default_http_request_timeout")]
+    pub request_timeout_seconds: u64,
     /// TLS configuration (ACME by default for secure-by-default)
     #[serde(default)]
     pub tls: TlsConfig,
@@ -265,6 +585,11 @@ pub enum TlsMode {
     None,
 }
 
+/// Default HTTP request deadline
+fn default_http_request_timeout() -> u64 {
+    60
+}
+
 /// Default ACME cache directory
 fn default_acme_cache_dir() -> String {
     "./acme-cache".to_string()
@@ -384,6 +709,14 @@ pub struct NntpSettings {
     /// Request timeout in seconds (can be overridden per-server)
     #[serde(default = "NntpSettings::default_request_timeout")]
     pub request_timeout_seconds: u64,
+    /// Seconds an idle connection waits before a keepalive command, 0 to
+    /// send none (can be overridden per-server)
+    #[serde(default = "NntpSettings::default_keepalive")]
+    pub keepalive_seconds: u64,
+    /// Milliseconds an article fetch waits on one server before asking the
+    /// next as well, taking the first answer; unset to ask in turn
+    #[serde(default)]
+    pub hedge_delay_ms: Option<u64>,
     /// Default newsgroup and display settings
     pub defaults: NntpDefaults,
 
@@ -407,6 +740,10 @@ impl NntpSettings {
     fn default_request_timeout() -> u64 {
         30
     }
+
+    fn default_keepalive() -> u64 {
+        60
+    }
 }
 
 /// Configuration for a single NNTP server
@@ -422,8 +759,14 @@ pub struct NntpServerConfig {
     pub timeout_seconds: Option<u64>,
     /// Request timeout (overrides global setting)
     pub request_timeout_seconds: Option<u64>,
+    /// Idle time before a keepalive command, 0 for none (overrides global
+    /// setting)
+    pub keepalive_seconds: Option<u64>,
     /// Number of worker connections for this server (default: 4)
     pub worker_count: Option<usize>,
+    /// Most worker connections the pool may grow to under load (default:
+    /// `worker_count`, so the pool stays fixed)
+    pub max_workers: Option<usize>,
     /// Username for NNTP authentication (requires TLS unless allow_insecure_auth is set)
     pub username: Option<String>,
     /// Password for NNTP authentication (requires TLS unless allow_insecure_auth is set)
@@ -431,6 +774,29 @@ pub struct NntpServerConfig {
     /// Allow authentication over plaintext connections (INSECURE - only for testing)
     #[serde(default)]
     pub allow_insecure_auth: bool,
+    /// Limit on bytes read from this server across all workers, in kilobits
+    /// per second (unlimited if unset)
+    pub max_kbps: Option<u32>,
+    /// SOCKS5 proxy to reach this server through (direct if unset)
+    pub proxy: Option<ProxyConfig>,
+    /// How the connection is secured. Unset tries implicit TLS and falls
+    /// back to plain TCP when there are no credentials.
+    pub tls_mode: Option<NntpTlsMode>,
+    /// PEM file of CA certificates trusted for this server, in addition to
+    /// the public roots
+    pub ca_file: Option<String>,
+    /// SHA-256 fingerprints of server certificates to accept (hex, colons
+    /// optional). When set, a certificate matching one is accepted whoever
+    /// signed it, and no other certificate is.
+    #[serde(default)]
+    pub pinned_certificates: Vec<String>,
+    /// Servers with higher priority are tried first; equal ones keep their
+    /// configured order (default: 0)
+    #[serde(default)]
+    pub priority: i32,
+    /// Never send posts to this server, even if it accepts them
+    #[serde(default)]
+    pub read_only: bool,
 }
 
 impl NntpServerConfig {
@@ -445,11 +811,30 @@ impl NntpServerConfig {
             .unwrap_or(global.request_timeout_seconds)
     }
 
+    /// How long a connection may sit idle before a keepalive command (server-
+    /// specific or global default), `None` when keepalives are off
+    pub fn keepalive(&self, global: &NntpSettings) -> Option<Duration> {
+        match self.keepalive_seconds.unwrap_or(global.keepalive_seconds) {
+            0 => None,
+            secs => Some(Duration::from_secs(secs)),
+        }
+    }
+
     /// Get worker count (default: 4)
     pub fn worker_count(&self) -> usize {
         self.worker_count.unwrap_or(4)
     }
 
+    /// Most workers the pool may scale to (default: `worker_count`)
+    pub fn max_worker_count(&self) -> usize {
+        self.max_workers.unwrap_or(self.worker_count())
+    }
+
+    /// `host:port` for connecting, with IPv6 literals in brackets
+    pub fn address(&self) -> String {
+        host_port(&self.host, self.port)
+    }
+
     /// Check if credentials are configured (both username and password)
     pub fn has_credentials(&self) -> bool {
         self.username.is_some() && self.password.is_some()
@@ -461,6 +846,23 @@ impl NntpServerConfig {
         self.has_credentials() && !self.allow_insecure_auth
     }
 
+    /// Pinned certificate fingerprints. Invalid ones were rejected when the
+    /// configuration was loaded.
+    pub fn pinned_fingerprints(&self) -> Vec<[u8; 32]> {
+        self.pinned_certificates
+            .iter()
+            .filter_map(|pin| parse_fingerprint(pin))
+            .collect()
+    }
+
+    /// Servers in the order requests fall back through them: by descending
+    /// priority, configured order among equals
+    pub fn by_priority(servers: &[Self]) -> Vec<&Self> {
+        let mut servers: Vec<&Self> = servers.iter().collect();
+        servers.sort_by_key(|server| std::cmp::Reverse(server.priority));
+        servers
+    }
+
     /// Create from legacy NntpSettings (backward compatibility)
     fn from_legacy(settings: &NntpSettings) -> Option<Self> {
         let server = settings.legacy_server.as_ref()?;
@@ -472,25 +874,150 @@ impl NntpServerConfig {
             port,
             timeout_seconds: Some(settings.timeout_seconds),
             request_timeout_seconds: Some(settings.request_timeout_seconds),
+            keepalive_seconds: None,
             worker_count: settings.legacy_worker_count,
+            max_workers: None,
             username: settings.legacy_username.clone(),
             password: settings.legacy_password.clone(),
             allow_insecure_auth: false,
+            max_kbps: None,
+            proxy: None,
+            tls_mode: None,
+            ca_file: None,
+            pinned_certificates: Vec::new(),
+            priority: 0,
+            read_only: false,
         })
     }
 }
 
+/// How a connection to an NNTP server is secured
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum NntpTlsMode {
+    /// TLS from the first byte, usually on port 563
+    Implicit,
+    /// Plain TCP upgraded with STARTTLS (RFC 4642), usually on port 119
+    Starttls,
+    /// No TLS
+    Plain,
+}
+
+/// SOCKS5 proxy for an NNTP server, e.g. Tor or an egress proxy
+#[derive(Debug, Clone, Deserialize)]
+pub struct ProxyConfig {
+    /// Proxy hostname
+    pub host: String,
+    /// Proxy port
+    pub port: u16,
+    /// Username for the proxy (RFC 1929), with `password`
+    pub username: Option<String>,
+    /// Password for the proxy
+    pub password: Option<String>,
+}
+
+impl ProxyConfig {
+    /// `host:port` of the proxy, with IPv6 literals in brackets
+    pub fn address(&self) -> String {
+        host_port(&self.host, self.port)
+    }
+
+    /// Username and password, if both are configured
+    pub fn credentials(&self) -> Option<(&str, &str)> {
+        Some((self.username.as_deref()?, self.password.as_deref()?))
+    }
+}
+
+/// `host:port`, bracketing IPv6 literals whether or not they already are
+fn host_port(host: &str, port: u16) -> String {
+    let host = host.trim_start_matches('[').trim_end_matches(']');
+    if host.contains(':') {
+        format!("[{}]:{}", host, port)
+    } else {
+        format!("{}:{}", host, port)
+    }
+}
+
 #[derive(Debug, Clone, Deserialize)]
 pub struct NntpDefaults {
     pub threads_per_page: usize,
     #[serde(default = "NntpDefaults::default_articles_per_page")]
     pub articles_per_page: usize,
-    /// Maximum number of articles to fetch per group (default: 500)
+    /// Number of articles to fetch per group while its traffic is unknown,
+    /// and the fewest any group fetches (default: 500)
     #[serde(default = "NntpDefaults::default_max_articles_per_group")]
     pub max_articles_per_group: u64,
+    /// Most articles a busy group's fetch window grows to (default: 2000)
+    #[serde(default = "NntpDefaults::default_max_articles_per_busy_group")]
+    pub max_articles_per_busy_group: u64,
+    /// Days of posts the fetch window aims to cover, between the two
+    /// article counts above (default: 90)
+    #[serde(default = "NntpDefaults::default_fetch_window_days")]
+    pub fetch_window_days: u64,
+    /// Upper bound for `?per_page=` overrides on paginated views (default:
+    /// 100, or the larger configured page size)
+    #[serde(default)]
+    pub max_per_page: Option<usize>,
+    /// Article bodies one page load fetches from a server at a time
+    /// (default: 4)
+    #[serde(default = "NntpDefaults::default_body_fetch_concurrency")]
+    pub body_fetch_concurrency: usize,
 }
 
 impl NntpDefaults {
+    /// Resolve the page size for a request, honoring a `?per_page=` override
+    /// bounded to `1..=max_per_page`.
+    pub fn per_page(&self, requested: Option<usize>, default: usize) -> usize {
+        requested
+            .unwrap_or(default)
+            .clamp(1, self.max_per_page().max(1))
+    }
+
+    /// The configured `max_per_page`, or by default 100 raised to the
+    /// configured page sizes so they are never cut down
+    pub fn max_per_page(&self) -> usize {
+        self.max_per_page.unwrap_or_else(|| {
+            DEFAULT_MAX_PER_PAGE
+                .max(self.threads_per_page)
+                .max(self.articles_per_page)
+        })
+    }
+
+    /// Page sizes must be at least 1, and the configured ones may not exceed
+    /// an explicit `max_per_page`, which would otherwise silently cut them down.
+    fn validate(&self) -> Result<(), ConfigError> {
+        if self.max_per_page == Some(0) {
+            return Err(ConfigError::Validation(
+                "nntp.defaults.max_per_page must be greater than 0".to_string(),
+            ));
+        }
+        for (name, value) in [
+            ("threads_per_page", self.threads_per_page),
+            ("articles_per_page", self.articles_per_page),
+        ] {
+            if value == 0 || value > self.max_per_page() {
+                return Err(ConfigError::Validation(format!(
+                    "nntp.defaults.{} ({}) must be between 1 and max_per_page ({})",
+                    name,
+                    value,
+                    self.max_per_page()
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// Adaptive overview fetch window built from these settings
+    pub fn fetch_window(&self) -> FetchWindow {
+        FetchWindow {
+            min_articles: self.max_articles_per_group,
+            max_articles: self
+                .max_articles_per_busy_group
+                .max(self.max_articles_per_group),
+            days: self.fetch_window_days,
+        }
+    }
+
     fn default_articles_per_page() -> usize {
         20
     }
@@ -498,6 +1025,66 @@ impl NntpDefaults {
     fn default_max_articles_per_group() -> u64 {
         500
     }
+
+    fn default_max_articles_per_busy_group() -> u64 {
+        2000
+    }
+
+    fn default_fetch_window_days() -> u64 {
+        90
+    }
+
+    fn default_body_fetch_concurrency() -> usize {
+        DEFAULT_BODY_FETCH_CONCURRENCY
+    }
+}
+
+/// Number of articles to fetch when loading a group's thread list.
+///
+/// Sized to cover `days` of posts at the group's observed rate. Quiet groups
+/// keep the minimum, which already reaches back further than `days`, and
+/// busy groups grow toward the maximum instead of showing only a few days.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FetchWindow {
+    pub min_articles: u64,
+    pub max_articles: u64,
+    pub days: u64,
+}
+
+impl FetchWindow {
+    /// A window that always fetches `articles`, ignoring traffic
+    pub fn fixed(articles: u64) -> Self {
+        Self {
+            min_articles: articles,
+            max_articles: articles,
+            days: 0,
+        }
+    }
+
+    /// Articles to fetch for a group posting `posts_per_day`, or the minimum
+    /// when the rate is unknown
+    pub fn articles(&self, posts_per_day: Option<f64>) -> u64 {
+        match posts_per_day {
+            Some(rate) if rate.is_finite() && rate >= 0.0 => {
+                let wanted = (rate * self.days as f64).ceil() as u64;
+                wanted.clamp(self.min_articles, self.max_articles)
+            }
+            _ => self.min_articles,
+        }
+    }
+}
+
+/// How poster addresses are shown in From headers.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FromDisplay {
+    /// The From header as posted (default)
+    #[default]
+    Full,
+    /// Addresses cut after the "@" ("user@…")
+    Masked,
+    /// Only the display name, or the address's local part if there is none
+    Name,
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
@@ -505,6 +1092,15 @@ pub struct UiConfig {
     /// Site title shown in header and page titles. Defaults to NNTP server name.
     pub site_name: Option<String>,
     pub collapse_threshold: usize,
+    /// How poster addresses appear in displayed From headers
+    #[serde(default)]
+    pub from_display: FromDisplay,
+    /// Publish upstream availability history at /about/status
+    #[serde(default = "UiConfig::default_status_page")]
+    pub status_page: bool,
+    /// TOML file of site-wide announcements, checked for changes while running
+    #[serde(default)]
+    pub announcements_path: Option<String>,
     /// Version string, populated at runtime
     #[serde(skip_deserializing, default = "UiConfig::default_version")]
     pub version: String,
@@ -514,6 +1110,194 @@ impl UiConfig {
     fn default_version() -> String {
         env!("CARGO_PKG_VERSION").to_string()
     }
+
+    fn default_status_page() -> bool {
+        true
+    }
+}
+
+/// A section of the home page
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HomeBlock {
+    /// Top-level group hierarchy with statistics
+    GroupTree,
+    /// The groups listed in `pinned_groups`
+    PinnedGroups,
+    /// Most recently active threads in the busiest groups
+    TopThreads,
+    /// Site announcements, here instead of above the page
+    Announcements,
+    /// Group name filter
+    Search,
+}
+
+/// Home page layout.
+///
+/// The home page renders `blocks` in order. Top threads come from thread
+/// lists already in the cache, so the home page never waits on upstream
+/// servers for them; right after startup the block may be empty.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct HomeConfig {
+    /// Blocks in display order (default: search, then the group tree)
+    #[serde(default = "HomeConfig::default_blocks")]
+    pub blocks: Vec<HomeBlock>,
+    /// Groups shown by the `pinned_groups` block, in order
+    #[serde(default)]
+    pub pinned_groups: Vec<String>,
+    /// Threads shown by the `top_threads` block
+    #[serde(default = "HomeConfig::default_top_threads")]
+    pub top_threads: usize,
+}
+
+impl Default for HomeConfig {
+    fn default() -> Self {
+        Self {
+            blocks: Self::default_blocks(),
+            pinned_groups: Vec::new(),
+            top_threads: Self::default_top_threads(),
+        }
+    }
+}
+
+impl HomeConfig {
+    fn default_blocks() -> Vec<HomeBlock> {
+        vec![HomeBlock::Search, HomeBlock::GroupTree]
+    }
+
+    fn default_top_threads() -> usize {
+        10
+    }
+
+    /// Reject blocks listed twice
+    fn validate(&self) -> Result<(), ConfigError> {
+        for (i, block) in self.blocks.iter().enumerate() {
+            if self.blocks[..i].contains(block) {
+                return Err(ConfigError::Validation(format!(
+                    "[home] blocks lists {:?} more than once",
+                    block
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Instance identity, used in posted articles' User-Agent header, the HTTP
+/// Server header and the /about pages.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct InstanceConfig {
+    /// Instance name (default: `[ui] site_name`)
+    pub name: Option<String>,
+    /// Public base URL, e.g. "https://news.example.com"
+    pub url: Option<String>,
+    /// How to reach the administrator: an email address or URL
+    pub admin_contact: Option<String>,
+    /// Replaces the generated User-Agent header on posted articles
+    pub user_agent: Option<String>,
+    /// Introduction at the top of /about, in Markdown
+    pub description: Option<String>,
+    /// Instance rules, listed in order on /about
+    #[serde(default)]
+    pub rules: Vec<String>,
+    /// Which upstream servers are used and how they are chosen, shown on /about
+    pub upstream_policy: Option<String>,
+    /// What is kept and for how long, shown on /about above the cache lifetimes
+    pub retention: Option<String>,
+    /// Warrant canary statement in Markdown, shown on /about/transparency
+    /// with the time the page was generated
+    pub warrant_canary: Option<String>,
+    /// Fields of /.well-known/security.txt
+    #[serde(default)]
+    pub security_txt: SecurityTxtConfig,
+}
+
+/// Fields of /.well-known/security.txt (RFC 9116). The file is served when
+/// there is a contact, here or in `[instance] admin_contact`.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct SecurityTxtConfig {
+    /// Contact URIs; bare email addresses get `mailto:` (default: `admin_contact`)
+    #[serde(default)]
+    pub contact: Vec<String>,
+    /// RFC 3339 expiry (default: `SECURITY_TXT_DEFAULT_EXPIRY_DAYS` from each request)
+    pub expires: Option<String>,
+    /// URI of a key for encrypted reports
+    pub encryption: Option<String>,
+    /// URI of the vulnerability disclosure policy
+    pub policy: Option<String>,
+    /// URI of a page thanking reporters
+    pub acknowledgments: Option<String>,
+    /// Comma-separated language tags, e.g. "en, de"
+    pub preferred_languages: Option<String>,
+}
+
+impl InstanceConfig {
+    /// `September/<version>`, followed by the instance URL when configured
+    pub fn product(&self) -> String {
+        let product = format!("September/{}", env!("CARGO_PKG_VERSION"));
+        match &self.url {
+            Some(url) => format!("{} (+{})", product, url),
+            None => product,
+        }
+    }
+
+    /// User-Agent header for posted articles
+    pub fn user_agent(&self) -> String {
+        self.user_agent.clone().unwrap_or_else(|| self.product())
+    }
+
+    fn validate(&self) -> Result<(), ConfigError> {
+        if let Some(url) = &self.url {
+            if !(url.starts_with("https://") || url.starts_with("http://")) {
+                return Err(ConfigError::Validation(format!(
+                    "[instance] url '{}' must start with http:// or https://",
+                    url
+                )));
+            }
+        }
+        if let Some(expires) = &self.security_txt.expires {
+            if chrono::DateTime::parse_from_rfc3339(expires).is_err() {
+                return Err(ConfigError::Validation(format!(
+                    "[instance.security_txt] expires '{}' is not an RFC 3339 timestamp",
+                    expires
+                )));
+            }
+        }
+        // These end up in HTTP and article headers, or one per line in security.txt
+        let security_txt = &self.security_txt;
+        let values = [
+            ("name", self.name.as_ref()),
+            ("url", self.url.as_ref()),
+            ("admin_contact", self.admin_contact.as_ref()),
+            ("user_agent", self.user_agent.as_ref()),
+            ("security_txt.encryption", security_txt.encryption.as_ref()),
+            ("security_txt.policy", security_txt.policy.as_ref()),
+            (
+                "security_txt.acknowledgments",
+                security_txt.acknowledgments.as_ref(),
+            ),
+            (
+                "security_txt.preferred_languages",
+                security_txt.preferred_languages.as_ref(),
+            ),
+        ]
+        .into_iter()
+        .chain(
+            security_txt
+                .contact
+                .iter()
+                .map(|c| ("security_txt.contact", Some(c))),
+        );
+        for (field, value) in values {
+            if value.is_some_and(|v| v.chars().any(|c| c.is_control())) {
+                return Err(ConfigError::Validation(format!(
+                    "[instance] {} must not contain control characters",
+                    field
+                )));
+            }
+        }
+        Ok(())
+    }
 }
 
 #[derive(Debug, Clone, Deserialize)]
@@ -536,6 +1320,16 @@ pub struct CacheConfig {
     /// Maximum number of cached group stats (default: 1000)
     #[serde(default = "CacheConfig::default_max_group_stats")]
     pub max_group_stats: u64,
+    /// Age in seconds after which a cached article is refetched in the
+    /// background while still being served (default: off)
+    #[serde(default)]
+    pub article_soft_ttl_seconds: Option<u64>,
+    /// Soft TTL for thread lists and group stats (default: a third of the TTL)
+    #[serde(default)]
+    pub threads_soft_ttl_seconds: Option<u64>,
+    /// Soft TTL for the group list (default: a third of the TTL)
+    #[serde(default)]
+    pub groups_soft_ttl_seconds: Option<u64>,
 }
 
 impl Default for CacheConfig {
@@ -547,6 +1341,9 @@ impl Default for CacheConfig {
             max_articles: Self::default_max_articles(),
             max_thread_lists: Self::default_max_thread_lists(),
             max_group_stats: Self::default_max_group_stats(),
+            article_soft_ttl_seconds: None,
+            threads_soft_ttl_seconds: None,
+            groups_soft_ttl_seconds: None,
         }
     }
 }
@@ -564,34 +1361,597 @@ impl CacheConfig {
     fn default_max_articles() -> u64 {
         10000
     }
-    fn default_max_thread_lists() -> u64 {
-        100
+    fn default_max_thread_lists() -> u64 {
+        100
+    }
+    fn default_max_group_stats() -> u64 {
+        1000
+    }
+
+    /// Age after which cached articles are refreshed in the background
+    pub fn article_soft_ttl(&self) -> Duration {
+        soft_ttl(
+            self.article_soft_ttl_seconds,
+            self.article_ttl_seconds,
+            self.article_ttl_seconds,
+        )
+    }
+
+    /// Age after which cached thread lists and group stats are refreshed in the background
+    pub fn threads_soft_ttl(&self) -> Duration {
+        soft_ttl(
+            self.threads_soft_ttl_seconds,
+            self.threads_ttl_seconds / CACHE_SOFT_TTL_DIVISOR,
+            self.threads_ttl_seconds,
+        )
+    }
+
+    /// Age after which the cached group list is refreshed in the background
+    pub fn groups_soft_ttl(&self) -> Duration {
+        soft_ttl(
+            self.groups_soft_ttl_seconds,
+            self.groups_ttl_seconds / CACHE_SOFT_TTL_DIVISOR,
+            self.groups_ttl_seconds,
+        )
+    }
+}
+
+/// Soft TTL from an optional setting, capped at the (hard) TTL.
+/// A soft TTL equal to the TTL turns background refresh off.
+fn soft_ttl(configured: Option<u64>, default: u64, ttl: u64) -> Duration {
+    Duration::from_secs(configured.unwrap_or(default).min(ttl))
+}
+
+/// Logging configuration
+#[derive(Debug, Clone, Deserialize)]
+pub struct LoggingConfig {
+    /// Log format: "text" (human-readable, default) or "json" (structured)
+    #[serde(default = "LoggingConfig::default_format")]
+    pub format: String,
+}
+
+impl Default for LoggingConfig {
+    fn default() -> Self {
+        Self {
+            format: DEFAULT_LOG_FORMAT.to_string(),
+        }
+    }
+}
+
+impl LoggingConfig {
+    fn default_format() -> String {
+        DEFAULT_LOG_FORMAT.to_string()
+    }
+}
+
+/// Spam filtering configuration
+#[derive(Debug, Clone, Deserialize)]
+pub struct SpamConfig {
+    /// Maximum simhash Hamming distance (0-64) for two thread root bodies to
+    /// count as near-duplicates. 0 disables duplicate detection.
+    #[serde(default = "SpamConfig::default_duplicate_distance")]
+    pub duplicate_distance: u32,
+    /// How long root fingerprints are remembered, in hours
+    #[serde(default = "SpamConfig::default_duplicate_window_hours")]
+    pub duplicate_window_hours: u64,
+    /// Collapse duplicate threads in listings instead of only flagging them
+    #[serde(default)]
+    pub collapse_duplicates: bool,
+}
+
+impl Default for SpamConfig {
+    fn default() -> Self {
+        Self {
+            duplicate_distance: Self::default_duplicate_distance(),
+            duplicate_window_hours: Self::default_duplicate_window_hours(),
+            collapse_duplicates: false,
+        }
+    }
+}
+
+impl SpamConfig {
+    fn default_duplicate_distance() -> u32 {
+        3
+    }
+
+    fn default_duplicate_window_hours() -> u64 {
+        24
+    }
+}
+
+/// Subject normalization configuration.
+///
+/// Normalized subjects drop reply prefixes ("Re:", "AW:", "SV:", also with
+/// counters like "Re[2]:") and leading "[list-tag]" brackets. They are shown
+/// in thread listings and used to group replies whose References were lost.
+#[derive(Debug, Clone, Deserialize)]
+pub struct SubjectConfig {
+    /// Reply prefixes to strip, matched case-insensitively before a colon
+    #[serde(default = "SubjectConfig::default_reply_prefixes")]
+    pub reply_prefixes: Vec<String>,
+    /// Strip leading "[tag]" brackets added by mailing list gateways
+    #[serde(default = "SubjectConfig::default_strip_tags")]
+    pub strip_tags: bool,
+    /// Fold threads started by a reply without References (e.g. "Re: foo")
+    /// into the thread with the same normalized subject
+    #[serde(default = "SubjectConfig::default_group_by_subject")]
+    pub group_by_subject: bool,
+}
+
+impl Default for SubjectConfig {
+    fn default() -> Self {
+        Self {
+            reply_prefixes: Self::default_reply_prefixes(),
+            strip_tags: Self::default_strip_tags(),
+            group_by_subject: Self::default_group_by_subject(),
+        }
+    }
+}
+
+impl SubjectConfig {
+    fn default_reply_prefixes() -> Vec<String> {
+        ["Re", "AW", "SV", "Antw", "Odp", "VS", "Ynt"]
+            .into_iter()
+            .map(String::from)
+            .collect()
+    }
+
+    fn default_strip_tags() -> bool {
+        true
+    }
+
+    fn default_group_by_subject() -> bool {
+        true
+    }
+}
+
+/// Vote tally configuration.
+///
+/// In matching groups, replies that vote ("+1", "-1", "Vote: yes") are
+/// counted and the tally is shown on the thread header. Off by default:
+/// reading every reply body costs NNTP traffic, and "+1" means agreement
+/// rather than a vote in most groups.
+#[derive(Debug, Clone, Deserialize)]
+pub struct VoteConfig {
+    /// Group wildmat where tallies are shown (e.g. "*.proposals,news.groups.votes").
+    /// Empty disables tallies.
+    #[serde(default)]
+    pub groups: String,
+    /// Maximum replies per thread whose bodies are read for votes
+    #[serde(default = "VoteConfig::default_max_replies")]
+    pub max_replies: usize,
+}
+
+impl Default for VoteConfig {
+    fn default() -> Self {
+        Self {
+            groups: String::new(),
+            max_replies: Self::default_max_replies(),
+        }
+    }
+}
+
+impl VoteConfig {
+    fn default_max_replies() -> usize {
+        200
+    }
+}
+
+/// Background refresh policy per group.
+///
+/// Groups are refreshed at a period between the minimum (busiest) and
+/// maximum (barely active) according to their request rate. Rules can
+/// narrow or widen that range for groups matching a pattern, or turn
+/// background refresh off for them.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct RefreshConfig {
+    /// Per-group rules; the first rule whose pattern matches applies
+    #[serde(default, rename = "group")]
+    pub groups: Vec<GroupRefreshRule>,
+}
+
+/// Refresh periods for groups matching a wildmat pattern
+#[derive(Debug, Clone, Deserialize)]
+pub struct GroupRefreshRule {
+    /// Group wildmat (e.g. "local.*")
+    pub pattern: String,
+    /// Set to false to turn background refresh off for these groups
+    #[serde(default = "GroupRefreshRule::default_enabled")]
+    pub enabled: bool,
+    /// Period at the highest request rates, in seconds
+    #[serde(default)]
+    pub min_period_seconds: Option<u64>,
+    /// Period for barely active groups, in seconds
+    #[serde(default)]
+    pub max_period_seconds: Option<u64>,
+}
+
+impl GroupRefreshRule {
+    /// Periods for matching groups, or `None` when refresh is off for them
+    pub fn policy(&self) -> Option<RefreshPolicy> {
+        if !self.enabled {
+            return None;
+        }
+        let default = RefreshPolicy::default();
+        Some(RefreshPolicy {
+            min_period: self
+                .min_period_seconds
+                .map_or(default.min_period, Duration::from_secs),
+            max_period: self
+                .max_period_seconds
+                .map_or(default.max_period, Duration::from_secs),
+        })
+    }
+
+    fn default_enabled() -> bool {
+        true
+    }
+}
+
+/// Refresh period range for one group
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RefreshPolicy {
+    pub min_period: Duration,
+    pub max_period: Duration,
+}
+
+impl Default for RefreshPolicy {
+    fn default() -> Self {
+        Self {
+            min_period: Duration::from_secs(BACKGROUND_REFRESH_MIN_PERIOD_SECS),
+            max_period: Duration::from_secs(BACKGROUND_REFRESH_MAX_PERIOD_SECS),
+        }
+    }
+}
+
+impl RefreshConfig {
+    /// Refresh periods for a group, or `None` when background refresh is off for it
+    pub fn policy_for(&self, group: &str) -> Option<RefreshPolicy> {
+        match self
+            .groups
+            .iter()
+            .find(|rule| wildmat(&rule.pattern, group))
+        {
+            Some(rule) => rule.policy(),
+            None => Some(RefreshPolicy::default()),
+        }
+    }
+
+    /// Reject rules whose periods are zero or out of order
+    fn validate(&self) -> Result<(), ConfigError> {
+        for rule in &self.groups {
+            let Some(policy) = rule.policy() else {
+                continue;
+            };
+            if policy.min_period.is_zero() || policy.min_period > policy.max_period {
+                return Err(ConfigError::Validation(format!(
+                    "Refresh rule '{}': min_period_seconds must be at least 1 and \
+                     no more than max_period_seconds",
+                    rule.pattern
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Display cleanup of article bodies.
+///
+/// Mailing list gateways append trailers ("To unsubscribe...", list
+/// addresses) to every message, which crowd thread pages. Footer rules
+/// remove such text, and optionally signatures, from bodies shown in
+/// matching groups. Articles are never changed on the server.
+///
+/// In source groups, where patches are posted, unified diffs in bodies are
+/// highlighted and offered as a `.patch` download. Link rules turn
+/// references such as "RFC 3977" or "bug #123" into links, with targets
+/// that can differ per hierarchy.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct BodyConfig {
+    /// Group wildmat where diffs are highlighted (e.g. "comp.lang.*,*.patches").
+    /// Empty disables highlighting.
+    #[serde(default)]
+    pub diff_groups: String,
+    /// Per-group footer rules; every rule whose pattern matches applies
+    #[serde(default, rename = "footer")]
+    pub footers: Vec<FooterRule>,
+    /// Reference link rules; where several match, the earliest match wins
+    #[serde(default, rename = "link")]
+    pub links: Vec<LinkRule>,
+}
+
+/// What to strip from bodies in groups matching a wildmat pattern
+#[derive(Debug, Clone, Deserialize)]
+pub struct FooterRule {
+    /// Group wildmat (e.g. "gmane.*,!gmane.test")
+    pub pattern: String,
+    /// Text to remove wherever it matches, e.g. a list trailer. Use
+    /// `(?m)` for `^`/`$` at line boundaries and `(?s)` to match across
+    /// lines.
+    #[serde(default, deserialize_with = "deserialize_optional_regex")]
+    pub matches: Option<Regex>,
+    /// Also drop the signature: the last "-- " line and all after it
+    #[serde(default)]
+    pub strip_signature: bool,
+}
+
+/// References in bodies to turn into links, in groups matching a wildmat
+#[derive(Debug, Clone, Deserialize)]
+pub struct LinkRule {
+    /// Group wildmat (e.g. "gmane.comp.python.*")
+    pub pattern: String,
+    /// The reference to link (e.g. `\bRFC ?(\d{1,5})\b`)
+    #[serde(deserialize_with = "deserialize_regex")]
+    pub matches: Regex,
+    /// Link target, where `$1` or `${name}` stand for the match's groups
+    /// (e.g. "https://www.rfc-editor.org/rfc/rfc$1")
+    pub url: String,
+}
+
+impl BodyConfig {
+    /// Whether pages in `group` highlight diffs and link to patches
+    pub fn shows_diffs(&self, group: &str) -> bool {
+        !self.diff_groups.is_empty() && wildmat(&self.diff_groups, group)
+    }
+
+    /// Reject footer rules that strip nothing or are more than `FooterRules`
+    /// can track per article, and link rules whose targets aren't web links
+    fn validate(&self) -> Result<(), ConfigError> {
+        if self.footers.len() > BODY_FOOTER_MAX_RULES {
+            return Err(ConfigError::Validation(format!(
+                "At most {} body footer rules are supported",
+                BODY_FOOTER_MAX_RULES
+            )));
+        }
+        for rule in &self.footers {
+            if rule.matches.is_none() && !rule.strip_signature {
+                return Err(ConfigError::Validation(format!(
+                    "Body footer rule '{}': set matches, strip_signature or both",
+                    rule.pattern
+                )));
+            }
+        }
+        // Only the captures come from the article, and never the scheme
+        for rule in &self.links {
+            if !["https://", "http://", "/"]
+                .iter()
+                .any(|prefix| rule.url.starts_with(prefix))
+            {
+                return Err(ConfigError::Validation(format!(
+                    "Body link rule '{}': url must start with https://, http:// or /",
+                    rule.url
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+fn deserialize_optional_regex<'de, D: serde::Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<Regex>, D::Error> {
+    deserialize_regex(deserializer).map(Some)
+}
+
+/// Posting access configuration.
+///
+/// With `invite_only` set, reading stays public but posting is limited to
+/// allowlisted OIDC subjects and users who have redeemed an invite code on
+/// their account page (see `crate::invites`). With `guidelines_path` set,
+/// users must also accept the posting guidelines, which is remembered in
+/// the session cookie. With
+/// `review_first_posts` set, each user's first posts wait for a moderator
+/// before they are sent (see `crate::review`).
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct PostingConfig {
+    /// Require an allowlisted subject or a redeemed invite code to post
+    #[serde(default)]
+    pub invite_only: bool,
+    /// Subjects allowed to post without a code, as "provider:sub"
+    #[serde(default)]
+    pub allowed_subjects: Vec<String>,
+    /// Accepted invite codes. Each entry supports env:VAR_NAME, file:/path,
+    /// or a literal value.
+    #[serde(default)]
+    pub invite_codes: Vec<String>,
+    /// How many users can redeem each of `invite_codes`
+    #[serde(default = "PostingConfig::default_invite_code_uses")]
+    pub invite_code_uses: u32,
+    /// JSON file keeping redemptions, code uses and the subjects and codes
+    /// added on `/admin/invites`. Required with `invite_only`.
+    #[serde(default)]
+    pub invites_path: Option<String>,
+    /// Posting guidelines users must accept once before posting.
+    /// `.md` files are rendered as Markdown, anything else is used as HTML.
+    #[serde(default)]
+    pub guidelines_path: Option<String>,
+    /// Per-group compose rules; the first rule whose pattern matches applies
+    #[serde(default, rename = "group")]
+    pub groups: Vec<GroupPostingRule>,
+    /// Hold each user's posts for moderator review until this many of them
+    /// have been approved (0 = off)
+    #[serde(default)]
+    pub review_first_posts: usize,
+    /// Subjects who review held posts, as "provider:sub". Their own posts
+    /// are never held.
+    #[serde(default)]
+    pub moderators: Vec<String>,
+    /// JSON file keeping the review queue and approval counts across restarts
+    #[serde(default)]
+    pub review_queue_path: Option<String>,
+}
+
+/// Compose rules for groups matching a wildmat pattern
+#[derive(Debug, Clone, Deserialize)]
+pub struct GroupPostingRule {
+    /// Group wildmat (e.g. "comp.lang.*,!comp.lang.c")
+    pub pattern: String,
+    /// Body skeleton pre-filled in the compose form
+    #[serde(default)]
+    pub body_template: Option<String>,
+    /// New thread subjects must contain one of these tags (case-insensitive)
+    #[serde(default)]
+    pub required_tags: Vec<String>,
+    /// Short guideline text shown above the compose form
+    #[serde(default)]
+    pub guidelines: Option<String>,
+    /// Regex rules that require, suggest or add subject tags
+    #[serde(default, rename = "tag")]
+    pub tag_rules: Vec<TagRule>,
+}
+
+/// A subject tag tied to a regex over the new thread's subject and body
+#[derive(Debug, Clone, Deserialize)]
+pub struct TagRule {
+    /// Tag text, e.g. "[BUG]"
+    pub tag: String,
+    /// When this regex matches the subject or body, the tag applies
+    #[serde(rename = "matches", deserialize_with = "deserialize_regex")]
+    pub regex: Regex,
+    /// What to do when the tag applies but is missing
+    #[serde(default)]
+    pub mode: TagRuleMode,
+}
+
+/// How a tag rule is enforced
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TagRuleMode {
+    /// Reject the post until the tag is added
+    #[default]
+    Require,
+    /// Show the form again with a suggestion; a second submit posts as is
+    Suggest,
+    /// Prepend the tag to the subject
+    Auto,
+}
+
+/// Why a subject failed the tag rules
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TagRejection {
+    pub message: String,
+    /// Only a suggestion: resubmitting with confirmation posts anyway
+    pub suggestion: bool,
+}
+
+fn deserialize_regex<'de, D: serde::Deserializer<'de>>(deserializer: D) -> Result<Regex, D::Error> {
+    let pattern = String::deserialize(deserializer)?;
+    Regex::new(&pattern).map_err(serde::de::Error::custom)
+}
+
+impl GroupPostingRule {
+    /// Every tag this rule knows about, for the thread list tag filter
+    pub fn known_tags(&self) -> Vec<&str> {
+        let mut tags: Vec<&str> = self.required_tags.iter().map(String::as_str).collect();
+        for rule in &self.tag_rules {
+            if !tags.iter().any(|t| t.eq_ignore_ascii_case(&rule.tag)) {
+                tags.push(&rule.tag);
+            }
+        }
+        tags
+    }
+
+    /// Apply tag rules to a new thread's subject, returning the (possibly
+    /// auto-tagged) subject. `confirmed` skips suggestions the poster has
+    /// already seen.
+    pub fn apply_tag_rules(
+        &self,
+        subject: &str,
+        body: &str,
+        confirmed: bool,
+    ) -> Result<String, TagRejection> {
+        let mut subject = subject.trim().to_string();
+        for rule in &self.tag_rules {
+            let has_tag = subject.to_lowercase().contains(&rule.tag.to_lowercase());
+            if has_tag || !(rule.regex.is_match(&subject) || rule.regex.is_match(body)) {
+                continue;
+            }
+            match rule.mode {
+                TagRuleMode::Require => {
+                    return Err(TagRejection {
+                        message: format!("This post needs the {} tag in its subject", rule.tag),
+                        suggestion: false,
+                    });
+                }
+                TagRuleMode::Suggest if !confirmed => {
+                    return Err(TagRejection {
+                        message: format!(
+                            "This post looks like it should be tagged {}. Add the tag, \
+                             or submit again to post without it.",
+                            rule.tag
+                        ),
+                        suggestion: true,
+                    });
+                }
+                TagRuleMode::Suggest => {}
+                TagRuleMode::Auto => subject = format!("{} {}", rule.tag, subject),
+            }
+        }
+        Ok(subject)
+    }
+
+    /// Check a new thread's subject and body against this rule.
+    /// Returns a message for the poster when the post is rejected.
+    pub fn validate(&self, subject: &str, body: &str) -> Result<(), String> {
+        if !self.required_tags.is_empty() {
+            let subject = subject.to_lowercase();
+            if !self
+                .required_tags
+                .iter()
+                .any(|tag| subject.contains(&tag.to_lowercase()))
+            {
+                return Err(format!(
+                    "Subject must include one of: {}",
+                    self.required_tags.join(", ")
+                ));
+            }
+        }
+        if let Some(template) = &self.body_template {
+            if body.trim() == template.trim() {
+                return Err("Please fill in the message template before posting".to_string());
+            }
+        }
+        Ok(())
+    }
+}
+
+impl PostingConfig {
+    fn default_invite_code_uses() -> u32 {
+        1
     }
-    fn default_max_group_stats() -> u64 {
-        1000
+
+    /// Whether a subject from the given provider may review held posts
+    pub fn is_moderator(&self, provider: &str, sub: &str) -> bool {
+        subject_listed(&self.moderators, provider, sub)
     }
-}
 
-/// Logging configuration
-#[derive(Debug, Clone, Deserialize)]
-pub struct LoggingConfig {
-    /// Log format: "text" (human-readable, default) or "json" (structured)
-    #[serde(default = "LoggingConfig::default_format")]
-    pub format: String,
-}
+    /// Compose rule for a group, if any pattern matches
+    pub fn rule_for(&self, group: &str) -> Option<&GroupPostingRule> {
+        self.groups
+            .iter()
+            .find(|rule| wildmat(&rule.pattern, group))
+    }
 
-impl Default for LoggingConfig {
-    fn default() -> Self {
-        Self {
-            format: DEFAULT_LOG_FORMAT.to_string(),
+    /// Resolve invite codes from env/file/literal, dropping empty ones
+    fn resolve_invite_codes(&self) -> Result<Vec<String>, ConfigError> {
+        let mut codes = Vec::with_capacity(self.invite_codes.len());
+        for code in &self.invite_codes {
+            let code = resolve_secret(code)?;
+            if !code.is_empty() {
+                codes.push(code);
+            }
         }
+        Ok(codes)
     }
 }
 
-impl LoggingConfig {
-    fn default_format() -> String {
-        DEFAULT_LOG_FORMAT.to_string()
-    }
+/// Whether "provider:sub" entries include this subject
+pub(crate) fn subject_listed(entries: &[String], provider: &str, sub: &str) -> bool {
+    entries.iter().any(|entry| {
+        entry
+            .split_once(':')
+            .is_some_and(|(p, s)| p == provider && s == sub)
+    })
 }
 
 /// Theme configuration for templates and static assets.
@@ -610,6 +1970,10 @@ pub struct ThemeConfig {
     /// Development: typically "dist/themes"
     #[serde(default = "ThemeConfig::default_themes_dir")]
     pub themes_dir: String,
+
+    /// Per-group overrides; the first rule whose pattern matches applies
+    #[serde(default, rename = "group")]
+    pub groups: Vec<GroupThemeRule>,
 }
 
 impl Default for ThemeConfig {
@@ -617,10 +1981,31 @@ impl Default for ThemeConfig {
         Self {
             name: Self::default_name(),
             themes_dir: Self::default_themes_dir(),
+            groups: Vec::new(),
         }
     }
 }
 
+/// Look of group and thread pages for groups matching a wildmat pattern.
+///
+/// Passed to templates as `group_theme`. The stylesheet is loaded after the
+/// theme's own, and the banner is operator-supplied HTML inserted unescaped
+/// below the page header.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct GroupThemeRule {
+    /// Group wildmat (e.g. "local.*")
+    pub pattern: String,
+    /// Extra stylesheet URL, e.g. "/static/css/local.css"
+    #[serde(default)]
+    pub stylesheet: Option<String>,
+    /// Class added to `<body>` so theme CSS can target these groups
+    #[serde(default)]
+    pub body_class: Option<String>,
+    /// HTML snippet shown below the page header
+    #[serde(default)]
+    pub banner: Option<String>,
+}
+
 impl ThemeConfig {
     fn default_name() -> String {
         "default".to_string()
@@ -630,6 +2015,13 @@ impl ThemeConfig {
         "/usr/share/september/themes".to_string()
     }
 
+    /// Override for a group's pages, if any pattern matches
+    pub fn rule_for(&self, group: &str) -> Option<&GroupThemeRule> {
+        self.groups
+            .iter()
+            .find(|rule| wildmat(&rule.pattern, group))
+    }
+
     /// Get path to templates for a specific theme.
     pub fn templates_path(&self, theme_name: &str) -> PathBuf {
         Path::new(&self.themes_dir)
@@ -684,6 +2076,22 @@ impl ThemeConfig {
             }
         }
 
+        // Body classes go into a class attribute unescaped
+        for rule in &self.groups {
+            if let Some(class) = &rule.body_class {
+                if class.is_empty()
+                    || !class
+                        .chars()
+                        .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+                {
+                    return Err(ConfigError::Validation(format!(
+                        "Theme rule '{}': body_class may only contain letters, digits, '-' and '_'",
+                        rule.pattern
+                    )));
+                }
+            }
+        }
+
         Ok(())
     }
 }
@@ -708,6 +2116,45 @@ impl AppConfig {
             ));
         }
 
+        // Validate per-server bandwidth limits
+        if let Some(server) = config.server.iter().find(|s| s.max_kbps == Some(0)) {
+            return Err(ConfigError::Validation(format!(
+                "Server '{}': max_kbps must be greater than 0; omit it for no limit",
+                server.name
+            )));
+        }
+
+        // Credentials are never sent in plain text unless explicitly allowed
+        if let Some(server) = config
+            .server
+            .iter()
+            .find(|s| s.tls_mode == Some(NntpTlsMode::Plain) && s.requires_tls_for_credentials())
+        {
+            return Err(ConfigError::Validation(format!(
+                "Server '{}': tls_mode = \"plain\" sends credentials unencrypted; set allow_insecure_auth to allow it",
+                server.name
+            )));
+        }
+
+        // Validate page sizes
+        config.nntp.defaults.validate()?;
+
+        // Validate pool limits, SOCKS5 proxies and TLS trust settings
+        for server in &config.server {
+            let in_server = |e| ConfigError::Validation(format!("Server '{}': {}", server.name, e));
+            if server.max_worker_count() < server.worker_count() {
+                return Err(in_server(format!(
+                    "max_workers ({}) must be at least worker_count ({})",
+                    server.max_worker_count(),
+                    server.worker_count()
+                )));
+            }
+            if let Some(proxy) = &server.proxy {
+                validate_proxy(proxy).map_err(in_server)?;
+            }
+            validate_server_tls(server).map_err(in_server)?;
+        }
+
         // Validate OIDC providers if configured
         if let Some(ref oidc) = config.oidc {
             if oidc.providers.is_empty() {
@@ -721,16 +2168,145 @@ impl AppConfig {
             }
         }
 
+        // Validate posting access and resolve invite codes
+        if let Some(entry) = config
+            .posting
+            .allowed_subjects
+            .iter()
+            .chain(&config.posting.moderators)
+            .find(|entry| !entry.contains(':'))
+        {
+            return Err(ConfigError::Validation(format!(
+                "Posting subject '{}' must be written as provider:sub",
+                entry
+            )));
+        }
+        if config.posting.review_first_posts > 0
+            && (config.posting.moderators.is_empty() || config.posting.review_queue_path.is_none())
+        {
+            return Err(ConfigError::Validation(
+                "posting.review_first_posts requires posting.moderators and posting.review_queue_path"
+                    .to_string(),
+            ));
+        }
+        if config.posting.invite_only && config.posting.invites_path.is_none() {
+            return Err(ConfigError::Validation(
+                "posting.invite_only requires posting.invites_path".to_string(),
+            ));
+        }
+        if config.posting.invite_code_uses == 0 && !config.posting.invite_codes.is_empty() {
+            return Err(ConfigError::Validation(
+                "posting.invite_code_uses must be at least 1".to_string(),
+            ));
+        }
+        config.posting.invite_codes = config.posting.resolve_invite_codes()?;
+
+        // Validate instance identity
+        config.instance.validate()?;
+
         // Validate TLS configuration
         config.http.tls.validate()?;
 
         // Validate theme configuration
         config.theme.validate()?;
 
+        // Validate group aliases
+        validate_group_aliases(&config.group_aliases)?;
+
+        // Validate per-group refresh periods
+        config.refresh.validate()?;
+
+        // Validate body footer rules
+        config.body.validate()?;
+
+        // Validate the home page layout
+        config.home.validate()?;
+
         Ok(config)
     }
 }
 
+/// A proxy needs both or neither of username and password, each of 1 to
+/// 255 bytes as RFC 1929 encodes their length in one byte
+fn validate_proxy(proxy: &ProxyConfig) -> Result<(), String> {
+    if proxy.username.is_some() != proxy.password.is_some() {
+        return Err("proxy username and password must be set together".to_string());
+    }
+    let bad_length = |value: &Option<String>| {
+        value
+            .as_ref()
+            .is_some_and(|v| v.is_empty() || v.len() > 255)
+    };
+    if bad_length(&proxy.username) || bad_length(&proxy.password) {
+        return Err("proxy username and password must be 1 to 255 bytes".to_string());
+    }
+    Ok(())
+}
+
+/// Pinned fingerprints must parse and a CA file must be readable, so a typo
+/// fails at startup rather than on every connection. Neither may be combined
+/// with plain TCP, which would silently ignore them.
+fn validate_server_tls(server: &NntpServerConfig) -> Result<(), String> {
+    if let Some(pin) = server
+        .pinned_certificates
+        .iter()
+        .find(|pin| parse_fingerprint(pin).is_none())
+    {
+        return Err(format!(
+            "pinned certificate '{}' is not a SHA-256 fingerprint in hex",
+            pin
+        ));
+    }
+    if server.tls_mode == Some(NntpTlsMode::Plain)
+        && (!server.pinned_certificates.is_empty() || server.ca_file.is_some())
+    {
+        return Err(
+            "pinned_certificates and ca_file need TLS; they can't be used with tls_mode = \"plain\""
+                .to_string(),
+        );
+    }
+    if let Some(ca_file) = &server.ca_file {
+        std::fs::File::open(ca_file)
+            .map_err(|e| format!("failed to read ca_file '{}': {}", ca_file, e))?;
+    }
+    Ok(())
+}
+
+/// Parse a SHA-256 fingerprint: 64 hex digits, optionally separated by
+/// colons as `openssl x509 -fingerprint -sha256` prints them
+pub fn parse_fingerprint(value: &str) -> Option<[u8; 32]> {
+    let hex: String = value.chars().filter(|c| *c != ':').collect();
+    if hex.len() != 64 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    let mut fingerprint = [0u8; 32];
+    for (byte, pair) in fingerprint.iter_mut().zip(hex.as_bytes().chunks(2)) {
+        *byte = u8::from_str_radix(std::str::from_utf8(pair).ok()?, 16).ok()?;
+    }
+    Some(fingerprint)
+}
+
+/// Ensure group aliases resolve in a single hop.
+/// An alias may not point at itself or at another alias, which rules out
+/// chains and cycles.
+fn validate_group_aliases(aliases: &HashMap<String, String>) -> Result<(), ConfigError> {
+    for (alias, target) in aliases {
+        if alias == target {
+            return Err(ConfigError::Validation(format!(
+                "Group alias '{}' points to itself",
+                alias
+            )));
+        }
+        if aliases.contains_key(target) {
+            return Err(ConfigError::Validation(format!(
+                "Group alias '{}' points to '{}', which is itself an alias",
+                alias, target
+            )));
+        }
+    }
+    Ok(())
+}
+
 #[derive(Debug, thiserror::Error)]
 pub enum ConfigError {
     #[error("Failed to read config file: {0}")]
@@ -770,6 +2346,35 @@ pub fn resolve_secret(value: &str) -> Result<String, ConfigError> {
     }
 }
 
+/// Read-only NNTP listener configuration (optional section).
+/// Lets traditional newsreaders use September's caches as their server.
+#[derive(Debug, Clone, Deserialize)]
+pub struct PeeringConfig {
+    /// Address to bind (default: 127.0.0.1)
+    #[serde(default = "PeeringConfig::default_host")]
+    pub host: String,
+    /// Port to listen on (default: 1119)
+    #[serde(default = "PeeringConfig::default_port")]
+    pub port: u16,
+    /// Maximum concurrent reader connections (default: 50)
+    #[serde(default = "PeeringConfig::default_max_connections")]
+    pub max_connections: usize,
+}
+
+impl PeeringConfig {
+    fn default_host() -> String {
+        "127.0.0.1".to_string()
+    }
+
+    fn default_port() -> u16 {
+        1119
+    }
+
+    fn default_max_connections() -> usize {
+        50
+    }
+}
+
 /// OpenID Connect configuration (optional section)
 #[derive(Debug, Clone, Deserialize)]
 pub struct OidcConfig {
@@ -963,6 +2568,77 @@ mod tests {
         assert!(matches!(err, ConfigError::SecretResolution(_)));
     }
 
+    // =============================================================================
+    // InstanceConfig tests
+    // =============================================================================
+
+    #[test]
+    fn test_instance_user_agent() {
+        let mut instance = InstanceConfig::default();
+        let version = env!("CARGO_PKG_VERSION");
+        assert_eq!(instance.user_agent(), format!("September/{}", version));
+
+        instance.url = Some("https://news.example.com".to_string());
+        assert_eq!(
+            instance.user_agent(),
+            format!("September/{} (+https://news.example.com)", version)
+        );
+
+        instance.user_agent = Some("ExampleNews/2.0".to_string());
+        assert_eq!(instance.user_agent(), "ExampleNews/2.0");
+        assert!(instance.product().starts_with("September/"));
+    }
+
+    #[test]
+    fn test_home_config_blocks() {
+        assert_eq!(
+            HomeConfig::default().blocks,
+            vec![HomeBlock::Search, HomeBlock::GroupTree]
+        );
+
+        let home: HomeConfig = toml::from_str(
+            r#"
+            blocks = ["announcements", "pinned_groups", "top_threads", "group_tree"]
+            pinned_groups = ["local.general"]
+            "#,
+        )
+        .unwrap();
+        assert_eq!(home.blocks[2], HomeBlock::TopThreads);
+        assert_eq!(home.top_threads, 10);
+        assert!(home.validate().is_ok());
+
+        let home: HomeConfig = toml::from_str(r#"blocks = ["search", "search"]"#).unwrap();
+        assert!(home.validate().is_err());
+        assert!(toml::from_str::<HomeConfig>(r#"blocks = ["sidebar"]"#).is_err());
+    }
+
+    #[test]
+    fn test_instance_validate() {
+        let mut instance = InstanceConfig {
+            url: Some("https://news.example.com".to_string()),
+            admin_contact: Some("admin@example.com".to_string()),
+            ..Default::default()
+        };
+        assert!(instance.validate().is_ok());
+
+        instance.url = Some("news.example.com".to_string());
+        assert!(instance.validate().is_err());
+
+        instance.url = None;
+        instance.user_agent = Some("Evil\r\nX-Injected: 1".to_string());
+        assert!(instance.validate().is_err());
+
+        instance.user_agent = None;
+        instance.security_txt.contact = vec!["mailto:a@example.com\nPolicy: x".to_string()];
+        assert!(instance.validate().is_err());
+
+        instance.security_txt.contact.clear();
+        instance.security_txt.expires = Some("next year".to_string());
+        assert!(instance.validate().is_err());
+        instance.security_txt.expires = Some("2027-01-01T00:00:00Z".to_string());
+        assert!(instance.validate().is_ok());
+    }
+
     // =============================================================================
     // TlsConfig validation tests
     // =============================================================================
@@ -1202,12 +2878,22 @@ mod tests {
             port: 119,
             timeout_seconds: None,
             request_timeout_seconds: None,
+            keepalive_seconds: None,
             worker_count: None,
+            max_workers: None,
             username: None,
             password: None,
             allow_insecure_auth: false,
+            max_kbps: None,
+            proxy: None,
+            tls_mode: None,
+            ca_file: None,
+            pinned_certificates: Vec::new(),
+            priority: 0,
+            read_only: false,
         };
         assert_eq!(config.worker_count(), 4);
+        assert_eq!(config.max_worker_count(), 4);
     }
 
     #[test]
@@ -1218,12 +2904,155 @@ mod tests {
             port: 119,
             timeout_seconds: None,
             request_timeout_seconds: None,
+            keepalive_seconds: None,
             worker_count: Some(8),
+            max_workers: None,
             username: None,
             password: None,
             allow_insecure_auth: false,
+            max_kbps: None,
+            proxy: None,
+            tls_mode: None,
+            ca_file: None,
+            pinned_certificates: Vec::new(),
+            priority: 0,
+            read_only: false,
         };
         assert_eq!(config.worker_count(), 8);
+        assert_eq!(config.max_worker_count(), 8);
+
+        let scaling = NntpServerConfig {
+            max_workers: Some(16),
+            ..config
+        };
+        assert_eq!(scaling.max_worker_count(), 16);
+    }
+
+    #[test]
+    fn test_nntp_server_config_by_priority() {
+        let servers: Vec<NntpServerConfig> = [
+            ("mirror", 0, true),
+            ("primary", 10, false),
+            ("backup", 0, false),
+        ]
+        .into_iter()
+        .map(|(name, priority, read_only)| {
+            toml::from_str(&format!(
+                "name = \"{name}\"\nhost = \"news.example.com\"\nport = 119\n\
+                 priority = {priority}\nread_only = {read_only}"
+            ))
+            .unwrap()
+        })
+        .collect();
+        let names: Vec<&str> = NntpServerConfig::by_priority(&servers)
+            .into_iter()
+            .map(|server| server.name.as_str())
+            .collect();
+        assert_eq!(names, ["primary", "mirror", "backup"]);
+        assert!(servers[0].read_only);
+
+        let default: NntpServerConfig =
+            toml::from_str("name = \"a\"\nhost = \"h\"\nport = 119").unwrap();
+        assert_eq!(default.priority, 0);
+        assert!(!default.read_only);
+    }
+
+    #[test]
+    fn test_nntp_server_config_address_brackets_ipv6() {
+        let mut config = NntpServerConfig {
+            name: "test".to_string(),
+            host: "news.example.com".to_string(),
+            port: 119,
+            timeout_seconds: None,
+            request_timeout_seconds: None,
+            keepalive_seconds: None,
+            worker_count: None,
+            max_workers: None,
+            username: None,
+            password: None,
+            allow_insecure_auth: false,
+            max_kbps: None,
+            proxy: None,
+            tls_mode: None,
+            ca_file: None,
+            pinned_certificates: Vec::new(),
+            priority: 0,
+            read_only: false,
+        };
+        assert_eq!(config.address(), "news.example.com:119");
+
+        config.host = "2001:db8::1".to_string();
+        assert_eq!(config.address(), "[2001:db8::1]:119");
+        config.host = "[2001:db8::1]".to_string();
+        assert_eq!(config.address(), "[2001:db8::1]:119");
+    }
+
+    #[test]
+    fn test_nntp_server_config_tls_mode() {
+        let config: NntpServerConfig = toml::from_str(
+            r#"
+            name = "test"
+            host = "news.example.com"
+            port = 119
+            tls_mode = "starttls"
+            "#,
+        )
+        .unwrap();
+        assert_eq!(config.tls_mode, Some(NntpTlsMode::Starttls));
+
+        let config: NntpServerConfig =
+            toml::from_str("name = \"test\"\nhost = \"news.example.com\"\nport = 563").unwrap();
+        assert_eq!(config.tls_mode, None);
+    }
+
+    #[test]
+    fn test_parse_fingerprint() {
+        let hex = "3A".repeat(32);
+        let colons = vec!["3a"; 32].join(":");
+        assert_eq!(parse_fingerprint(&hex), Some([0x3a; 32]));
+        assert_eq!(parse_fingerprint(&colons), Some([0x3a; 32]));
+        assert_eq!(parse_fingerprint(&hex[2..]), None);
+        assert_eq!(parse_fingerprint(&"zz".repeat(32)), None);
+        assert_eq!(parse_fingerprint(""), None);
+    }
+
+    #[test]
+    fn test_validate_server_tls_rejects_trust_over_plain() {
+        let mut server: NntpServerConfig = toml::from_str(&format!(
+            "name = \"test\"\nhost = \"news.example.com\"\nport = 563\npinned_certificates = [\"{}\"]",
+            "3a".repeat(32)
+        ))
+        .unwrap();
+        assert!(validate_server_tls(&server).is_ok());
+
+        server.tls_mode = Some(NntpTlsMode::Plain);
+        assert!(validate_server_tls(&server).is_err());
+
+        server.tls_mode = Some(NntpTlsMode::Starttls);
+        assert!(validate_server_tls(&server).is_ok());
+    }
+
+    #[test]
+    fn test_proxy_config_validation() {
+        let mut proxy = ProxyConfig {
+            host: "127.0.0.1".to_string(),
+            port: 9050,
+            username: None,
+            password: None,
+        };
+        assert!(validate_proxy(&proxy).is_ok());
+        assert_eq!(proxy.address(), "127.0.0.1:9050");
+        assert_eq!(proxy.credentials(), None);
+
+        proxy.username = Some("user".to_string());
+        assert!(validate_proxy(&proxy).is_err());
+
+        proxy.password = Some("secret".to_string());
+        assert!(validate_proxy(&proxy).is_ok());
+        assert_eq!(proxy.credentials(), Some(("user", "secret")));
+
+        proxy.password = Some("x".repeat(256));
+        assert!(validate_proxy(&proxy).is_err());
     }
 
     #[test]
@@ -1234,10 +3063,19 @@ mod tests {
             port: 119,
             timeout_seconds: None,
             request_timeout_seconds: None,
+            keepalive_seconds: None,
             worker_count: None,
+            max_workers: None,
             username: None,
             password: None,
             allow_insecure_auth: false,
+            max_kbps: None,
+            proxy: None,
+            tls_mode: None,
+            ca_file: None,
+            pinned_certificates: Vec::new(),
+            priority: 0,
+            read_only: false,
         };
 
         assert!(!config.has_credentials());
@@ -1257,10 +3095,19 @@ mod tests {
             port: 119,
             timeout_seconds: None,
             request_timeout_seconds: None,
+            keepalive_seconds: None,
             worker_count: None,
+            max_workers: None,
             username: Some("user".to_string()),
             password: Some("pass".to_string()),
             allow_insecure_auth: false,
+            max_kbps: None,
+            proxy: None,
+            tls_mode: None,
+            ca_file: None,
+            pinned_certificates: Vec::new(),
+            priority: 0,
+            read_only: false,
         };
 
         assert!(config.requires_tls_for_credentials());
@@ -1274,10 +3121,16 @@ mod tests {
         let global = NntpSettings {
             timeout_seconds: 30,
             request_timeout_seconds: 60,
+            keepalive_seconds: 60,
+            hedge_delay_ms: None,
             defaults: NntpDefaults {
                 threads_per_page: 25,
                 articles_per_page: 20,
                 max_articles_per_group: 500,
+                max_articles_per_busy_group: 2000,
+                fetch_window_days: 90,
+                max_per_page: None,
+                body_fetch_concurrency: 4,
             },
             legacy_server: None,
             legacy_port: None,
@@ -1291,10 +3144,19 @@ mod tests {
             port: 119,
             timeout_seconds: None,
             request_timeout_seconds: Some(120), // Override
+            keepalive_seconds: None,
             worker_count: None,
+            max_workers: None,
             username: None,
             password: None,
             allow_insecure_auth: false,
+            max_kbps: None,
+            proxy: None,
+            tls_mode: None,
+            ca_file: None,
+            pinned_certificates: Vec::new(),
+            priority: 0,
+            read_only: false,
         };
         assert_eq!(config.request_timeout_seconds(&global), 120);
     }
@@ -1304,10 +3166,16 @@ mod tests {
         let global = NntpSettings {
             timeout_seconds: 30,
             request_timeout_seconds: 60,
+            keepalive_seconds: 60,
+            hedge_delay_ms: None,
             defaults: NntpDefaults {
                 threads_per_page: 25,
                 articles_per_page: 20,
                 max_articles_per_group: 500,
+                max_articles_per_busy_group: 2000,
+                fetch_window_days: 90,
+                max_per_page: None,
+                body_fetch_concurrency: 4,
             },
             legacy_server: None,
             legacy_port: None,
@@ -1321,12 +3189,29 @@ mod tests {
             port: 119,
             timeout_seconds: None,
             request_timeout_seconds: None, // No override
+            keepalive_seconds: None,
             worker_count: None,
+            max_workers: None,
             username: None,
             password: None,
             allow_insecure_auth: false,
+            max_kbps: None,
+            proxy: None,
+            tls_mode: None,
+            ca_file: None,
+            pinned_certificates: Vec::new(),
+            priority: 0,
+            read_only: false,
         };
         assert_eq!(config.request_timeout_seconds(&global), 60);
+        assert_eq!(config.keepalive(&global), Some(Duration::from_secs(60)));
+
+        // 0 turns keepalives off for one server
+        let quiet = NntpServerConfig {
+            keepalive_seconds: Some(0),
+            ..config
+        };
+        assert_eq!(quiet.keepalive(&global), None);
     }
 
     // =============================================================================
@@ -1420,6 +3305,108 @@ mod tests {
         assert_eq!(config.max_group_stats, 1000);
     }
 
+    #[test]
+    fn test_cache_config_default_soft_ttls() {
+        let config = CacheConfig::default();
+        assert_eq!(config.article_soft_ttl(), Duration::from_secs(86400)); // off
+        assert_eq!(config.threads_soft_ttl(), Duration::from_secs(600));
+        assert_eq!(config.groups_soft_ttl(), Duration::from_secs(1200));
+    }
+
+    #[test]
+    fn test_cache_config_soft_ttl_capped_at_ttl() {
+        let config = CacheConfig {
+            article_soft_ttl_seconds: Some(600),
+            threads_soft_ttl_seconds: Some(7200),
+            ..CacheConfig::default()
+        };
+        assert_eq!(config.article_soft_ttl(), Duration::from_secs(600));
+        assert_eq!(config.threads_soft_ttl(), Duration::from_secs(1800));
+    }
+
+    // =============================================================================
+    // NntpDefaults tests
+    // =============================================================================
+
+    fn test_nntp_defaults() -> NntpDefaults {
+        NntpDefaults {
+            threads_per_page: 25,
+            articles_per_page: 20,
+            max_articles_per_group: 500,
+            max_articles_per_busy_group: 2000,
+            fetch_window_days: 90,
+            max_per_page: None,
+            body_fetch_concurrency: 4,
+        }
+    }
+
+    #[test]
+    fn test_nntp_defaults_per_page_uses_default() {
+        let defaults = test_nntp_defaults();
+        assert_eq!(defaults.per_page(None, 25), 25);
+    }
+
+    #[test]
+    fn test_nntp_defaults_per_page_honors_override() {
+        let defaults = test_nntp_defaults();
+        assert_eq!(defaults.per_page(Some(50), 25), 50);
+    }
+
+    #[test]
+    fn test_nntp_defaults_per_page_is_bounded() {
+        let defaults = test_nntp_defaults();
+        assert_eq!(defaults.per_page(Some(10_000), 25), 100);
+        assert_eq!(defaults.per_page(Some(0), 25), 1);
+    }
+
+    #[test]
+    fn test_nntp_defaults_validate_rejects_page_size_above_max() {
+        assert!(test_nntp_defaults().validate().is_ok());
+
+        // The default max_per_page grows to fit the page sizes
+        let mut defaults = test_nntp_defaults();
+        defaults.threads_per_page = 150;
+        assert!(defaults.validate().is_ok());
+        assert_eq!(defaults.per_page(Some(10_000), 25), 150);
+
+        let mut defaults = test_nntp_defaults();
+        defaults.threads_per_page = 150;
+        defaults.max_per_page = Some(100);
+        assert!(defaults.validate().is_err());
+
+        let mut defaults = test_nntp_defaults();
+        defaults.articles_per_page = 0;
+        assert!(defaults.validate().is_err());
+
+        let mut defaults = test_nntp_defaults();
+        defaults.max_per_page = Some(0);
+        assert!(defaults.validate().is_err());
+    }
+
+    #[test]
+    fn test_fetch_window_adapts_to_traffic() {
+        let window = test_nntp_defaults().fetch_window();
+        // Unknown traffic keeps the old fixed window
+        assert_eq!(window.articles(None), 500);
+        assert_eq!(window.articles(Some(f64::NAN)), 500);
+        // Busy groups fetch 90 days of posts, up to the maximum
+        assert_eq!(window.articles(Some(10.0)), 900);
+        assert_eq!(window.articles(Some(400.0)), 2000);
+        assert_eq!(FetchWindow::fixed(300).articles(Some(2.5)), 300);
+    }
+
+    #[test]
+    fn test_fetch_window_never_shrinks_quiet_groups() {
+        // A config from before the adaptive window, with its own fixed count
+        let mut defaults = test_nntp_defaults();
+        defaults.max_articles_per_group = 800;
+        let window = defaults.fetch_window();
+        for rate in [0.0, 0.2, 2.5] {
+            assert!(window.articles(Some(rate)) >= 800);
+        }
+        assert!(test_nntp_defaults().fetch_window().articles(Some(0.2)) >= 500);
+    }
+
     // =============================================================================
     // NNTP constant tests
     // =============================================================================
@@ -1449,4 +3436,250 @@ mod tests {
         let config = ThemeConfig::default();
         assert_eq!(config.themes_dir, "/usr/share/september/themes");
     }
+
+    #[test]
+    fn test_theme_rule_for_uses_first_match() {
+        let theme: ThemeConfig = toml::from_str(
+            r#"
+            [[group]]
+            pattern = "local.announce"
+            banner = "<p>Read-only announcements</p>"
+
+            [[group]]
+            pattern = "local.*"
+            stylesheet = "/static/css/local.css"
+            body_class = "local"
+            "#,
+        )
+        .unwrap();
+
+        let rule = theme.rule_for("local.announce").unwrap();
+        assert_eq!(
+            rule.banner.as_deref(),
+            Some("<p>Read-only announcements</p>")
+        );
+        assert_eq!(rule.stylesheet, None);
+        let rule = theme.rule_for("local.general").unwrap();
+        assert_eq!(rule.body_class.as_deref(), Some("local"));
+        assert!(theme.rule_for("comp.lang.rust").is_none());
+    }
+
+    // =============================================================================
+    // Group alias tests
+    // =============================================================================
+
+    #[test]
+    fn test_validate_group_aliases_accepts_single_hop() {
+        let mut aliases = HashMap::new();
+        aliases.insert("comp.lang.old".to_string(), "comp.lang.new".to_string());
+        aliases.insert("alt.legacy".to_string(), "comp.lang.new".to_string());
+        assert!(validate_group_aliases(&aliases).is_ok());
+    }
+
+    #[test]
+    fn test_validate_group_aliases_rejects_self_alias() {
+        let mut aliases = HashMap::new();
+        aliases.insert("comp.lang.old".to_string(), "comp.lang.old".to_string());
+        assert!(validate_group_aliases(&aliases).is_err());
+    }
+
+    #[test]
+    fn test_validate_group_aliases_rejects_chains() {
+        let mut aliases = HashMap::new();
+        aliases.insert("a.old".to_string(), "a.mid".to_string());
+        aliases.insert("a.mid".to_string(), "a.new".to_string());
+        assert!(validate_group_aliases(&aliases).is_err());
+    }
+
+    // =============================================================================
+    // Posting rule tests
+    // =============================================================================
+
+    #[test]
+    fn test_posting_rule_for_uses_first_match() {
+        let posting: PostingConfig = toml::from_str(
+            r#"
+            [[group]]
+            pattern = "comp.lang.rust"
+            required_tags = ["[ANN]"]
+
+            [[group]]
+            pattern = "comp.*"
+            guidelines = "Be on topic."
+            "#,
+        )
+        .unwrap();
+        let rule = posting.rule_for("comp.lang.rust").unwrap();
+        assert_eq!(rule.required_tags, vec!["[ANN]"]);
+        let rule = posting.rule_for("comp.os.linux").unwrap();
+        assert_eq!(rule.guidelines.as_deref(), Some("Be on topic."));
+        assert!(posting.rule_for("alt.test").is_none());
+    }
+
+    #[test]
+    fn test_posting_rule_validate() {
+        let rule = GroupPostingRule {
+            pattern: "*".to_string(),
+            body_template: Some("Version:\nProblem:\n".to_string()),
+            required_tags: vec!["[Q]".to_string(), "[ANN]".to_string()],
+            guidelines: None,
+            tag_rules: Vec::new(),
+        };
+        assert!(rule
+            .validate("[q] How do I?", "Version: 1\nProblem: it breaks")
+            .is_ok());
+        assert!(rule.validate("How do I?", "Version: 1").is_err());
+        assert!(rule.validate("[ANN] v1.0", "Version:\nProblem:").is_err());
+    }
+
+    #[test]
+    fn test_tag_rules_require_suggest_and_auto() {
+        let posting: PostingConfig = toml::from_str(
+            r#"
+            [[group]]
+            pattern = "*"
+
+            [[group.tag]]
+            tag = "[BUG]"
+            matches = "(?i)\\b(crash|panic)"
+
+            [[group.tag]]
+            tag = "[ANN]"
+            matches = "(?i)^released?\\b"
+            mode = "auto"
+
+            [[group.tag]]
+            tag = "[Q]"
+            matches = "\\?$"
+            mode = "suggest"
+            "#,
+        )
+        .unwrap();
+        let rule = posting.rule_for("any.group").unwrap();
+        assert_eq!(rule.known_tags(), vec!["[BUG]", "[ANN]", "[Q]"]);
+
+        let err = rule.apply_tag_rules("It crashed", "panic at startup", false);
+        assert!(!err.unwrap_err().suggestion);
+        assert_eq!(
+            rule.apply_tag_rules("[bug] It crashed", "panic", false)
+                .unwrap(),
+            "[bug] It crashed"
+        );
+
+        assert_eq!(
+            rule.apply_tag_rules("Released v2", "Notes", false).unwrap(),
+            "[ANN] Released v2"
+        );
+
+        assert!(
+            rule.apply_tag_rules("Why?", "", false)
+                .unwrap_err()
+                .suggestion
+        );
+        assert_eq!(rule.apply_tag_rules("Why?", "", true).unwrap(), "Why?");
+    }
+
+    // =============================================================================
+    // Refresh policy tests
+    // =============================================================================
+
+    #[test]
+    fn test_refresh_policy_per_pattern() {
+        let refresh: RefreshConfig = toml::from_str(
+            r#"
+            [[group]]
+            pattern = "local.*"
+            max_period_seconds = 5
+
+            [[group]]
+            pattern = "alt.binaries.*"
+            enabled = false
+            "#,
+        )
+        .unwrap();
+        refresh.validate().unwrap();
+
+        let local = refresh.policy_for("local.general").unwrap();
+        assert_eq!(
+            local.min_period,
+            Duration::from_secs(BACKGROUND_REFRESH_MIN_PERIOD_SECS)
+        );
+        assert_eq!(local.max_period, Duration::from_secs(5));
+        assert!(refresh.policy_for("alt.binaries.pictures").is_none());
+        assert_eq!(
+            refresh.policy_for("comp.lang.rust"),
+            Some(RefreshPolicy::default())
+        );
+    }
+
+    #[test]
+    fn test_refresh_policy_rejects_inverted_periods() {
+        let refresh: RefreshConfig = toml::from_str(
+            r#"
+            [[group]]
+            pattern = "local.*"
+            min_period_seconds = 60
+            max_period_seconds = 10
+            "#,
+        )
+        .unwrap();
+        assert!(refresh.validate().is_err());
+    }
+
+    #[test]
+    fn test_tag_rules_reject_invalid_regex() {
+        let result: Result<PostingConfig, _> = toml::from_str(
+            r#"
+            [[group]]
+            pattern = "*"
+
+            [[group.tag]]
+            tag = "[BUG]"
+            matches = "(unclosed"
+            "#,
+        );
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_body_footer_rules_must_strip_something() {
+        let body: BodyConfig = toml::from_str(
+            r#"
+            [[footer]]
+            pattern = "gmane.*"
+            matches = '(?m)^_{5,}$'
+
+            [[footer]]
+            pattern = "comp.*"
+            "#,
+        )
+        .unwrap();
+        assert!(body.footers[0].matches.is_some());
+        assert!(body.validate().is_err());
+    }
+
+    #[test]
+    fn test_body_link_rules_need_web_urls() {
+        let body: BodyConfig = toml::from_str(
+            r#"
+            [[link]]
+            pattern = "*"
+            matches = '\bRFC ?(\d{1,5})\b'
+            url = "https://www.rfc-editor.org/rfc/rfc$1"
+            "#,
+        )
+        .unwrap();
+        assert!(body.validate().is_ok());
+
+        let body: BodyConfig = toml::from_str(
+            r#"
+            [[link]]
+            pattern = "*"
+            matches = '(\S+)'
+            url = "$1"
+            "#,
+        )
+        .unwrap();
+        assert!(body.validate().is_err());
+    }
 }
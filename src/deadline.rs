@@ -0,0 +1,41 @@
+//! Per-request deadlines.
+//!
+//! The HTTP layer gives every request a deadline and runs its handler inside
+//! `scope`. NNTP calls made while serving the request read it with `current`
+//! and attach it to the requests they queue, so workers can skip requests
+//! nobody is waiting for any more. The deadline lives in a task-local, so
+//! tasks spawned from a handler (background refreshes) don't inherit it.
+
+use std::future::Future;
+use std::time::Instant;
+
+tokio::task_local! {
+    static DEADLINE: Instant;
+}
+
+/// Run `future` with `deadline` as the current deadline
+pub async fn scope<F: Future>(deadline: Instant, future: F) -> F::Output {
+    DEADLINE.scope(deadline, future).await
+}
+
+/// Deadline of the request being served, if any
+pub fn current() -> Option<Instant> {
+    DEADLINE.try_with(|deadline| *deadline).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn test_deadline_visible_only_inside_scope() {
+        assert_eq!(current(), None);
+        let deadline = Instant::now() + Duration::from_secs(5);
+        assert_eq!(scope(deadline, async { current() }).await, Some(deadline));
+
+        // Spawned tasks don't inherit the deadline
+        let spawned = scope(deadline, async { tokio::spawn(async { current() }).await }).await;
+        assert_eq!(spawned.unwrap(), None);
+    }
+}
@@ -37,6 +37,18 @@ pub enum AppError {
     #[error("Group not found: {0}")]
     GroupNotFound(String),
 
+    /// Malformed input in the request, such as an invalid group name.
+    #[error("Bad request: {0}")]
+    BadRequest(String),
+
+    /// The user may not do this, such as a non-moderator opening the review queue.
+    #[error("Forbidden: {0}")]
+    Forbidden(String),
+
+    /// The request ran past its deadline.
+    #[error("Request timed out")]
+    Timeout,
+
     /// File system or I/O errors.
     #[error("IO error: {0}")]
     Io(#[from] io::Error),
@@ -105,6 +117,9 @@ impl IntoResponse for AppErrorResponse {
         let (status, message) = match &self.error {
             AppError::ArticleNotFound(_) => (StatusCode::NOT_FOUND, self.error.to_string()),
             AppError::GroupNotFound(_) => (StatusCode::NOT_FOUND, self.error.to_string()),
+            AppError::BadRequest(_) => (StatusCode::BAD_REQUEST, self.error.to_string()),
+            AppError::Forbidden(_) => (StatusCode::FORBIDDEN, self.error.to_string()),
+            AppError::Timeout => (StatusCode::GATEWAY_TIMEOUT, self.error.to_string()),
             AppError::NntpConnection(_) => (
                 StatusCode::SERVICE_UNAVAILABLE,
                 "NNTP server unavailable".to_string(),
@@ -0,0 +1,75 @@
+//! Posting guidelines document.
+//!
+//! When `[posting] guidelines_path` is set, the document is loaded once at
+//! startup and users must accept it before they can post. Files ending in
+//! `.md` are rendered as Markdown; anything else is used as HTML. The
+//! version is a hash of the source, so editing the document asks everyone
+//! to accept it again.
+
+use pulldown_cmark::{html, Options, Parser};
+use sha2::{Digest, Sha256};
+
+use crate::config::ConfigError;
+
+/// A loaded guidelines document
+#[derive(Debug, Clone)]
+pub struct Guidelines {
+    /// Rendered HTML, trusted (it comes from the operator's config)
+    pub html: String,
+    /// Short hash of the source, stored in the session on acceptance
+    pub version: String,
+}
+
+impl Guidelines {
+    /// Load and render the document at `path`.
+    pub fn load(path: &str) -> Result<Self, ConfigError> {
+        let source = std::fs::read_to_string(path).map_err(|e| {
+            ConfigError::Validation(format!("Failed to read guidelines '{}': {}", path, e))
+        })?;
+        let markdown = path.ends_with(".md") || path.ends_with(".markdown");
+        Ok(Self::from_source(&source, markdown))
+    }
+
+    fn from_source(source: &str, markdown: bool) -> Self {
+        let html = if markdown {
+            let mut out = String::with_capacity(source.len() * 3 / 2);
+            html::push_html(&mut out, Parser::new_ext(source, Options::all()));
+            out
+        } else {
+            source.to_string()
+        };
+
+        let digest = Sha256::digest(source.as_bytes());
+        let version = digest[..8].iter().map(|b| format!("{:02x}", b)).collect();
+
+        Self { html, version }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_guidelines_render_markdown() {
+        let guidelines = Guidelines::from_source("# Rules\n\nBe *kind*.\n", true);
+        assert!(guidelines.html.contains("<h1>Rules</h1>"));
+        assert!(guidelines.html.contains("<em>kind</em>"));
+    }
+
+    #[test]
+    fn test_guidelines_keep_html() {
+        let source = "<p>Be kind.</p>";
+        assert_eq!(Guidelines::from_source(source, false).html, source);
+    }
+
+    #[test]
+    fn test_guidelines_version_tracks_content() {
+        let a = Guidelines::from_source("Be kind.", false);
+        let b = Guidelines::from_source("Be kind.", true);
+        let c = Guidelines::from_source("Be very kind.", false);
+        assert_eq!(a.version.len(), 16);
+        assert_eq!(a.version, b.version);
+        assert_ne!(a.version, c.version);
+    }
+}
@@ -10,6 +10,7 @@
 //! - Graceful shutdown on SIGTERM/SIGINT
 //! - Certificate hot-reload via SIGHUP (manual mode)
 
+pub mod range;
 mod redirect;
 mod server;
 mod shutdown;
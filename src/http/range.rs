@@ -0,0 +1,182 @@
+//! Byte-range responses for downloads.
+//!
+//! Downloads built in memory go through [`ranged`], which honours a single
+//! `Range: bytes=...` request (RFC 9110 section 14) so an interrupted
+//! download can resume where it stopped. The ETag is a SHA-256 of the body:
+//! when `If-Range` carries a different one, the content changed and the whole
+//! body is sent again. Requests for several ranges also get the whole body,
+//! which the RFC allows. Static files get the same support from `ServeDir`.
+
+use axum::{
+    body::Body,
+    http::{
+        header::{ACCEPT_RANGES, CONTENT_RANGE, CONTENT_TYPE, ETAG, IF_RANGE, RANGE},
+        HeaderMap, HeaderValue, StatusCode,
+    },
+    response::{IntoResponse, Response},
+};
+use sha2::{Digest, Sha256};
+
+/// The part of a body a `Range` header asks for
+#[derive(Debug, PartialEq, Eq)]
+enum ByteRange {
+    /// No usable range: send everything
+    Full,
+    /// First and last byte, inclusive
+    Partial(usize, usize),
+    /// Starts past the end of the body
+    Unsatisfiable,
+}
+
+/// Respond with `body`, or the part of it the request's `Range` asks for
+pub fn ranged(headers: &HeaderMap, body: Vec<u8>, content_type: &'static str) -> Response {
+    let etag = etag(&body);
+    let range = match headers.get(RANGE).and_then(|v| v.to_str().ok()) {
+        Some(range) if if_range_matches(headers, &etag) => parse_range(range, body.len()),
+        _ => ByteRange::Full,
+    };
+
+    let len = body.len();
+    let (status, content_range, body) = match range {
+        ByteRange::Full => (StatusCode::OK, None, body),
+        ByteRange::Partial(start, end) => (
+            StatusCode::PARTIAL_CONTENT,
+            Some(format!("bytes {}-{}/{}", start, end, len)),
+            body[start..=end].to_vec(),
+        ),
+        ByteRange::Unsatisfiable => (
+            StatusCode::RANGE_NOT_SATISFIABLE,
+            Some(format!("bytes */{}", len)),
+            Vec::new(),
+        ),
+    };
+
+    let mut response = (status, Body::from(body)).into_response();
+    let response_headers = response.headers_mut();
+    response_headers.insert(CONTENT_TYPE, HeaderValue::from_static(content_type));
+    response_headers.insert(ACCEPT_RANGES, HeaderValue::from_static("bytes"));
+    if let Ok(value) = HeaderValue::from_str(&etag) {
+        response_headers.insert(ETAG, value);
+    }
+    if let Some(value) = content_range.and_then(|v| HeaderValue::from_str(&v).ok()) {
+        response_headers.insert(CONTENT_RANGE, value);
+    }
+    response
+}
+
+/// Strong ETag for `body`
+fn etag(body: &[u8]) -> String {
+    let digest = Sha256::digest(body);
+    let hex: String = digest[..16].iter().map(|b| format!("{:02x}", b)).collect();
+    format!("\"{}\"", hex)
+}
+
+/// Whether a range may be served: no `If-Range`, or one naming the current
+/// ETag. There is no Last-Modified, so a date never matches.
+fn if_range_matches(headers: &HeaderMap, etag: &str) -> bool {
+    match headers.get(IF_RANGE) {
+        None => true,
+        Some(value) => value.to_str().is_ok_and(|v| v.trim() == etag),
+    }
+}
+
+/// Parse a `Range` value against a body of `len` bytes. Anything malformed,
+/// in another unit or with several ranges is ignored.
+fn parse_range(value: &str, len: usize) -> ByteRange {
+    let Some((unit, spec)) = value.trim().split_once('=') else {
+        return ByteRange::Full;
+    };
+    if !unit.trim().eq_ignore_ascii_case("bytes") || spec.contains(',') {
+        return ByteRange::Full;
+    }
+    let Some((first, last)) = spec.trim().split_once('-') else {
+        return ByteRange::Full;
+    };
+
+    if first.is_empty() {
+        // Suffix range: the last N bytes
+        return match last.parse::<usize>() {
+            Ok(0) => ByteRange::Unsatisfiable,
+            Ok(_) if len == 0 => ByteRange::Unsatisfiable,
+            Ok(n) => ByteRange::Partial(len.saturating_sub(n), len - 1),
+            Err(_) => ByteRange::Full,
+        };
+    }
+
+    let Ok(start) = first.parse::<usize>() else {
+        return ByteRange::Full;
+    };
+    let end = if last.is_empty() {
+        None
+    } else {
+        match last.parse::<usize>() {
+            Ok(end) if end >= start => Some(end),
+            _ => return ByteRange::Full,
+        }
+    };
+    if start >= len {
+        return ByteRange::Unsatisfiable;
+    }
+    ByteRange::Partial(start, end.map_or(len - 1, |end| end.min(len - 1)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_range() {
+        assert_eq!(parse_range("bytes=0-99", 1000), ByteRange::Partial(0, 99));
+        assert_eq!(
+            parse_range("bytes=500-", 1000),
+            ByteRange::Partial(500, 999)
+        );
+        assert_eq!(
+            parse_range("bytes=-100", 1000),
+            ByteRange::Partial(900, 999)
+        );
+        assert_eq!(parse_range("bytes=-5000", 1000), ByteRange::Partial(0, 999));
+        assert_eq!(
+            parse_range("bytes=900-5000", 1000),
+            ByteRange::Partial(900, 999)
+        );
+        assert_eq!(parse_range("bytes=1000-", 1000), ByteRange::Unsatisfiable);
+        assert_eq!(parse_range("bytes=-0", 1000), ByteRange::Unsatisfiable);
+        // Ignored: several ranges, other units, malformed or reversed
+        assert_eq!(parse_range("bytes=0-1,5-6", 1000), ByteRange::Full);
+        assert_eq!(parse_range("items=0-1", 1000), ByteRange::Full);
+        assert_eq!(parse_range("bytes=abc", 1000), ByteRange::Full);
+        assert_eq!(parse_range("bytes=10-5", 1000), ByteRange::Full);
+    }
+
+    #[test]
+    fn test_ranged_response() {
+        let body = b"0123456789".to_vec();
+        let mut headers = HeaderMap::new();
+
+        let response = ranged(&headers, body.clone(), "text/plain");
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()[ACCEPT_RANGES], "bytes");
+        let etag = response.headers()[ETAG].clone();
+
+        headers.insert(RANGE, HeaderValue::from_static("bytes=4-"));
+        let response = ranged(&headers, body.clone(), "text/plain");
+        assert_eq!(response.status(), StatusCode::PARTIAL_CONTENT);
+        assert_eq!(response.headers()[CONTENT_RANGE], "bytes 4-9/10");
+
+        // Resuming against the same content
+        headers.insert(IF_RANGE, etag);
+        let response = ranged(&headers, body.clone(), "text/plain");
+        assert_eq!(response.status(), StatusCode::PARTIAL_CONTENT);
+
+        // The content changed since: start over
+        let response = ranged(&headers, b"changed".to_vec(), "text/plain");
+        assert_eq!(response.status(), StatusCode::OK);
+
+        headers.remove(IF_RANGE);
+        headers.insert(RANGE, HeaderValue::from_static("bytes=10-"));
+        let response = ranged(&headers, body, "text/plain");
+        assert_eq!(response.status(), StatusCode::RANGE_NOT_SATISFIABLE);
+        assert_eq!(response.headers()[CONTENT_RANGE], "bytes */10");
+    }
+}
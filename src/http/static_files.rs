@@ -39,6 +39,7 @@ mod tests {
         let theme = ThemeConfig {
             name: "default".to_string(),
             themes_dir: "/usr/share/september/themes".to_string(),
+            groups: Vec::new(),
         };
         // Just verify it doesn't panic - actual file serving tested in integration
         let _service = create_static_service(&theme);
@@ -49,6 +50,7 @@ mod tests {
         let theme = ThemeConfig {
             name: "dark".to_string(),
             themes_dir: "/usr/share/september/themes".to_string(),
+            groups: Vec::new(),
         };
         let _service = create_static_service(&theme);
     }
@@ -0,0 +1,400 @@
+//! Invite-only posting access.
+//!
+//! With `[posting] invite_only` set, a user may post when their subject is
+//! in `[posting] allowed_subjects`, when a moderator has allowed it on
+//! `/admin/invites`, or when they have redeemed an invite code on their
+//! account page. Codes come from `[posting] invite_codes`, each usable
+//! `invite_code_uses` times, or are created on the admin page with their
+//! own number of uses. A redemption is tied to the account, not the
+//! session, so it survives logging out.
+//!
+//! Subjects and codes added on the admin page, redemptions and the uses
+//! left on each code are kept in the JSON file at `invites_path`,
+//! rewritten after every change. A change is made to a copy and only
+//! replaces the contents once the file is written, so permission checks
+//! never wait for the disk.
+
+use std::collections::{BTreeMap, HashMap};
+use std::io;
+use std::path::PathBuf;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use tokio::sync::{Mutex, RwLock};
+use uuid::Uuid;
+
+use crate::config::{subject_listed, ConfigError, PostingConfig};
+use crate::review::poster_id;
+
+/// An invite code created on the admin page
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct InviteCode {
+    pub code: String,
+    /// Redemptions left before the code stops working
+    pub uses_left: u32,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Contents of the invites file
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct InviteFile {
+    /// Subjects allowed on the admin page, as "provider:sub"
+    #[serde(default)]
+    allowed: Vec<String>,
+    /// Codes created on the admin page
+    #[serde(default)]
+    codes: Vec<InviteCode>,
+    /// When each "provider:sub" redeemed a code
+    #[serde(default)]
+    redeemed: BTreeMap<String, DateTime<Utc>>,
+    /// Times each `[posting] invite_codes` entry has been redeemed
+    #[serde(default)]
+    config_code_uses: HashMap<String, u32>,
+}
+
+/// Everything the admin page lists
+#[derive(Debug, Clone, Serialize)]
+pub struct InviteOverview {
+    /// Subjects allowed in the config file, which the page can't change
+    pub configured_subjects: Vec<String>,
+    pub allowed: Vec<String>,
+    pub codes: Vec<InviteCode>,
+    /// Redeemed "provider:sub" with the redemption time
+    pub redeemed: Vec<(String, DateTime<Utc>)>,
+    /// Number of `[posting] invite_codes`, whose values are not shown
+    pub configured_codes: usize,
+    pub invite_code_uses: u32,
+}
+
+/// Posting access and its file
+pub struct InviteStore {
+    invite_only: bool,
+    /// `[posting] allowed_subjects`
+    configured_subjects: Vec<String>,
+    /// `[posting] invite_codes`, resolved
+    configured_codes: Vec<String>,
+    /// Redemptions allowed per configured code
+    code_uses: u32,
+    path: Option<PathBuf>,
+    contents: RwLock<InviteFile>,
+    /// Held from reading the contents to be changed until the change is
+    /// saved, so writes can't overtake or undo each other
+    writing: Mutex<()>,
+}
+
+impl InviteStore {
+    /// Everyone may post, for instances without `invite_only`
+    pub fn disabled() -> Self {
+        Self {
+            invite_only: false,
+            configured_subjects: Vec::new(),
+            configured_codes: Vec::new(),
+            code_uses: 0,
+            path: None,
+            contents: RwLock::default(),
+            writing: Mutex::default(),
+        }
+    }
+
+    /// Read the invites at `path`. A missing file has no invites yet; one
+    /// that doesn't parse is a configuration error.
+    pub fn load(path: &str, posting: &PostingConfig) -> Result<Self, ConfigError> {
+        let path = PathBuf::from(path);
+        let contents = match std::fs::read_to_string(&path) {
+            Ok(source) => serde_json::from_str(&source).map_err(|e| {
+                ConfigError::Validation(format!("Invites file '{}': {}", path.display(), e))
+            })?,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => InviteFile::default(),
+            Err(e) => {
+                return Err(ConfigError::Validation(format!(
+                    "Failed to read invites file '{}': {}",
+                    path.display(),
+                    e
+                )))
+            }
+        };
+        Ok(Self {
+            path: Some(path),
+            contents: RwLock::new(contents),
+            ..Self::from_config(posting)
+        })
+    }
+
+    /// Access as the config grants it, without a file
+    fn from_config(posting: &PostingConfig) -> Self {
+        Self {
+            invite_only: posting.invite_only,
+            configured_subjects: posting.allowed_subjects.clone(),
+            configured_codes: posting.invite_codes.clone(),
+            code_uses: posting.invite_code_uses,
+            path: None,
+            contents: RwLock::default(),
+            writing: Mutex::default(),
+        }
+    }
+
+    /// Whether posting is invite-only
+    pub fn is_enabled(&self) -> bool {
+        self.invite_only
+    }
+
+    /// Whether the subject from the given provider may post. Open instances
+    /// let everyone post; invite-only ones need an allowed subject or a
+    /// redeemed invite code.
+    pub async fn can_post(&self, provider: &str, sub: &str) -> bool {
+        if !self.invite_only || subject_listed(&self.configured_subjects, provider, sub) {
+            return true;
+        }
+        let poster = poster_id(provider, sub);
+        let contents = self.contents.read().await;
+        contents.allowed.contains(&poster) || contents.redeemed.contains_key(&poster)
+    }
+
+    /// Whether `poster` ("provider:sub") redeemed an invite code
+    pub async fn has_redeemed(&self, poster: &str) -> bool {
+        self.contents.read().await.redeemed.contains_key(poster)
+    }
+
+    /// Redeem `code` for `poster`, using up one of its uses. Returns false
+    /// when the code is unknown or used up. Redeeming again once access is
+    /// granted uses nothing.
+    pub async fn redeem(&self, code: &str, poster: &str) -> io::Result<bool> {
+        let code = code.trim();
+        if code.is_empty() {
+            return Ok(false);
+        }
+        let _writing = self.writing.lock().await;
+        let mut contents = self.contents.read().await.clone();
+        if contents.redeemed.contains_key(poster) {
+            return Ok(true);
+        }
+
+        if let Some(index) = contents.codes.iter().position(|c| c.code == code) {
+            if contents.codes[index].uses_left > 1 {
+                contents.codes[index].uses_left -= 1;
+            } else {
+                contents.codes.remove(index);
+            }
+        } else if self.configured_codes.iter().any(|c| c == code) {
+            let uses = contents.config_code_uses.get(code).copied().unwrap_or(0);
+            if uses >= self.code_uses {
+                return Ok(false);
+            }
+            contents.config_code_uses.insert(code.to_string(), uses + 1);
+        } else {
+            return Ok(false);
+        }
+
+        contents.redeemed.insert(poster.to_string(), Utc::now());
+        self.commit(contents).await?;
+        Ok(true)
+    }
+
+    /// Drop `poster`'s redemption, when they delete their account data.
+    /// The code's use stays spent.
+    pub async fn forget(&self, poster: &str) -> io::Result<()> {
+        let _writing = self.writing.lock().await;
+        let mut contents = self.contents.read().await.clone();
+        if contents.redeemed.remove(poster).is_none() {
+            return Ok(());
+        }
+        self.commit(contents).await
+    }
+
+    /// Let `subject` ("provider:sub") post without a code
+    pub async fn allow(&self, subject: &str) -> io::Result<()> {
+        let _writing = self.writing.lock().await;
+        let mut contents = self.contents.read().await.clone();
+        if contents.allowed.iter().any(|s| s == subject) {
+            return Ok(());
+        }
+        contents.allowed.push(subject.to_string());
+        self.commit(contents).await
+    }
+
+    /// Take posting access from `subject`, whether allowed on the admin page
+    /// or through a redeemed code. Subjects allowed in the config file keep
+    /// it.
+    pub async fn revoke(&self, subject: &str) -> io::Result<()> {
+        let _writing = self.writing.lock().await;
+        let mut contents = self.contents.read().await.clone();
+        contents.allowed.retain(|s| s != subject);
+        contents.redeemed.remove(subject);
+        self.commit(contents).await
+    }
+
+    /// Create a code that can be redeemed `uses` times
+    pub async fn create_code(&self, uses: u32) -> io::Result<InviteCode> {
+        let code = InviteCode {
+            code: Uuid::new_v4().simple().to_string(),
+            uses_left: uses.max(1),
+            created_at: Utc::now(),
+        };
+        let _writing = self.writing.lock().await;
+        let mut contents = self.contents.read().await.clone();
+        contents.codes.push(code.clone());
+        self.commit(contents).await?;
+        Ok(code)
+    }
+
+    /// Delete a code created on the admin page. Access already granted
+    /// through it stays.
+    pub async fn delete_code(&self, code: &str) -> io::Result<()> {
+        let _writing = self.writing.lock().await;
+        let mut contents = self.contents.read().await.clone();
+        let Some(index) = contents.codes.iter().position(|c| c.code == code) else {
+            return Ok(());
+        };
+        contents.codes.remove(index);
+        self.commit(contents).await
+    }
+
+    /// Subjects, codes and redemptions for the admin page
+    pub async fn overview(&self) -> InviteOverview {
+        let contents = self.contents.read().await;
+        InviteOverview {
+            configured_subjects: self.configured_subjects.clone(),
+            allowed: contents.allowed.clone(),
+            codes: contents.codes.clone(),
+            redeemed: contents
+                .redeemed
+                .iter()
+                .map(|(subject, at)| (subject.clone(), *at))
+                .collect(),
+            configured_codes: self.configured_codes.len(),
+            invite_code_uses: self.code_uses,
+        }
+    }
+
+    /// Save changed contents, then make them current. Callers hold
+    /// `writing` from reading the contents they changed.
+    async fn commit(&self, contents: InviteFile) -> io::Result<()> {
+        self.save(&contents).await?;
+        *self.contents.write().await = contents;
+        Ok(())
+    }
+
+    /// Replace the file with `contents`, through a temporary file so a
+    /// crash never leaves it half written
+    async fn save(&self, contents: &InviteFile) -> io::Result<()> {
+        let Some(path) = self.path.clone() else {
+            return Ok(());
+        };
+        let json = serde_json::to_vec_pretty(contents)?;
+        tokio::task::spawn_blocking(move || {
+            let tmp = path.with_extension("json.tmp");
+            std::fs::write(&tmp, json)?;
+            std::fs::rename(&tmp, &path)
+        })
+        .await
+        .map_err(io::Error::other)?
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn posting() -> PostingConfig {
+        PostingConfig {
+            invite_only: true,
+            invite_codes: vec!["welcome".to_string()],
+            invite_code_uses: 2,
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_can_post_when_invite_only() {
+        let mut config = PostingConfig::default();
+        assert!(
+            InviteStore::from_config(&config)
+                .can_post("google", "sub123")
+                .await
+        );
+
+        config.invite_only = true;
+        assert!(
+            !InviteStore::from_config(&config)
+                .can_post("google", "sub123")
+                .await
+        );
+
+        config.allowed_subjects = vec!["github:sub123".to_string()];
+        assert!(
+            !InviteStore::from_config(&config)
+                .can_post("google", "sub123")
+                .await
+        );
+        config.allowed_subjects = vec!["google:sub123".to_string()];
+        assert!(
+            InviteStore::from_config(&config)
+                .can_post("google", "sub123")
+                .await
+        );
+
+        config.allowed_subjects.clear();
+        let store = InviteStore::from_config(&config);
+        store.allow("google:sub123").await.unwrap();
+        assert!(store.can_post("google", "sub123").await);
+        store.revoke("google:sub123").await.unwrap();
+        assert!(!store.can_post("google", "sub123").await);
+    }
+
+    #[tokio::test]
+    async fn test_codes_run_out_of_uses() {
+        let store = InviteStore::from_config(&posting());
+        let first = poster_id("google", "1");
+        let second = poster_id("google", "2");
+        let third = poster_id("google", "3");
+
+        assert!(!store.redeem("nope", &first).await.unwrap());
+        assert!(store.redeem(" welcome ", &first).await.unwrap());
+        // Redeeming again costs nothing
+        assert!(store.redeem("welcome", &first).await.unwrap());
+        assert!(store.redeem("welcome", &second).await.unwrap());
+        assert!(!store.redeem("welcome", &third).await.unwrap());
+        assert!(store.can_post("google", "2").await);
+        assert!(!store.can_post("google", "3").await);
+
+        let code = store.create_code(1).await.unwrap();
+        assert!(store.redeem(&code.code, &third).await.unwrap());
+        assert!(store.overview().await.codes.is_empty());
+
+        store.forget(&third).await.unwrap();
+        assert!(!store.can_post("google", "3").await);
+        assert!(!store.redeem(&code.code, &third).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_redemptions_survive_reload() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("invites.json");
+        let path_str = path.to_str().unwrap();
+
+        let store = InviteStore::load(path_str, &posting()).unwrap();
+        let code = store.create_code(3).await.unwrap();
+        assert!(store.redeem(&code.code, "github:42").await.unwrap());
+        assert!(store.redeem("welcome", "github:43").await.unwrap());
+
+        let reloaded = InviteStore::load(path_str, &posting()).unwrap();
+        assert!(reloaded.can_post("github", "42").await);
+        assert!(reloaded.has_redeemed("github:43").await);
+        assert_eq!(reloaded.overview().await.codes[0].uses_left, 2);
+        assert!(reloaded.redeem("welcome", "github:44").await.unwrap());
+        assert!(!reloaded.redeem("welcome", "github:45").await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_failed_write_changes_nothing() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("missing").join("invites.json");
+        let store = InviteStore::load(path.to_str().unwrap(), &posting()).unwrap();
+
+        assert!(store.allow("github:42").await.is_err());
+        assert!(store.redeem("welcome", "github:43").await.is_err());
+        assert!(!store.can_post("github", "42").await);
+        assert!(!store.has_redeemed("github:43").await);
+        assert!(store.overview().await.allowed.is_empty());
+    }
+}
@@ -0,0 +1,22 @@
+//! September: an NNTP web interface.
+//!
+//! The `september` binary (`main.rs`) loads the configuration and wires
+//! these modules together. They are built as a library as well so the fuzz
+//! targets under `fuzz/` can call the parsing and thread-building code
+//! directly.
+
+pub mod announcements;
+pub mod config;
+pub mod deadline;
+pub mod error;
+pub mod guidelines;
+pub mod http;
+pub mod invites;
+pub mod metrics;
+pub mod middleware;
+pub mod nntp;
+pub mod oidc;
+pub mod review;
+pub mod routes;
+pub mod state;
+pub mod templates;
@@ -2,22 +2,16 @@
 //!
 //! This is the application entry point. It initializes tracing, loads configuration
 //! from TOML files, creates the NNTP federated service, spawns worker connections,
-//! sets up the Axum router with all routes, and starts the HTTP server.
-
-mod config;
-mod error;
-mod http;
-mod middleware;
-mod nntp;
-mod oidc;
-mod routes;
-mod state;
-mod templates;
+//! sets up the Axum router with all routes, and starts the HTTP server. The
+//! modules themselves live in the library crate (`lib.rs`).
 
 use clap::Parser;
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 
-use config::{AppConfig, TlsMode, DEFAULT_CONFIG_PATH, DEFAULT_LOG_FILTER};
+use september::config::{
+    AppConfig, TlsMode, BASIC_MODE_THEME, DEFAULT_CONFIG_PATH, DEFAULT_LOG_FILTER, TEXT_MODE_THEME,
+};
+use september::http;
 
 /// September: A web interface to NNTP servers
 #[derive(Parser, Debug)]
@@ -37,11 +31,15 @@ struct Args {
 }
 use std::sync::Arc;
 
-use nntp::NntpFederatedService;
-use oidc::OidcManager;
-use routes::create_router;
-use state::AppState;
-use templates::init_templates;
+use september::announcements::Announcements;
+use september::guidelines::Guidelines;
+use september::invites::InviteStore;
+use september::nntp::{spawn_listener, NntpFederatedService};
+use september::oidc::OidcManager;
+use september::review::ReviewQueue;
+use september::routes::create_router;
+use september::state::AppState;
+use september::templates::{init_mode_templates, init_templates};
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
@@ -60,6 +58,9 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     if config.ui.site_name.is_none() {
         config.ui.site_name = config.server.first().map(|s| s.name.clone());
     }
+    if config.instance.name.is_none() {
+        config.instance.name = config.ui.site_name.clone();
+    }
 
     // Initialize tracing with priority: CLI > config > env > default
     let log_filter = args
@@ -100,7 +101,9 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     }
 
     // Initialize Tera templates with theme support
-    let tera = init_templates(&config.theme)?;
+    let tera = init_templates(&config.theme, &config.body)?;
+    let basic_tera = init_mode_templates(&config.theme, &config.body, BASIC_MODE_THEME)?;
+    let text_tera = init_mode_templates(&config.theme, &config.body, TEXT_MODE_THEME)?;
     tracing::info!(
         theme = %config.theme.name,
         themes_dir = %config.theme.themes_dir,
@@ -130,6 +133,13 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     Arc::new(nntp_service.clone()).spawn_background_refresh();
     tracing::info!("Spawned background refresh task");
 
+    // Start the read-only NNTP listener if configured
+    if let Some(ref peering) = config.peering {
+        spawn_listener(peering, nntp_service.clone(), config.ui.from_display).await?;
+    } else {
+        tracing::info!("NNTP listener not configured, peering disabled");
+    }
+
     // Initialize OIDC if configured
     let oidc = if let Some(ref oidc_config) = config.oidc {
         match OidcManager::new(oidc_config).await {
@@ -150,8 +160,75 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         None
     };
 
+    // Load posting guidelines if configured
+    let guidelines = match config.posting.guidelines_path {
+        Some(ref path) => {
+            let guidelines = Guidelines::load(path)?;
+            tracing::info!(
+                path = %path,
+                version = %guidelines.version,
+                "Loaded posting guidelines"
+            );
+            Some(guidelines)
+        }
+        None => None,
+    };
+
+    // Load announcements if configured, and pick up edits while running
+    let announcements = match config.ui.announcements_path {
+        Some(ref path) => {
+            let announcements = Arc::new(Announcements::load(path)?);
+            tracing::info!(
+                path = %path,
+                count = announcements.ids().len(),
+                "Loaded announcements"
+            );
+            announcements.clone().spawn_reload();
+            announcements
+        }
+        None => Arc::new(Announcements::disabled()),
+    };
+
+    // Load the queue of posts held for review if enabled
+    let review = match config.posting.review_queue_path {
+        Some(ref path) if config.posting.review_first_posts > 0 => {
+            let review = ReviewQueue::load(path, config.posting.review_first_posts)?;
+            tracing::info!(
+                path = %path,
+                held = review.held_count().await,
+                first_posts = config.posting.review_first_posts,
+                "Loaded review queue"
+            );
+            review
+        }
+        _ => ReviewQueue::disabled(),
+    };
+
+    // Load invite redemptions if posting is invite-only
+    let invites = match config.posting.invites_path {
+        Some(ref path) if config.posting.invite_only => {
+            let invites = InviteStore::load(path, &config.posting)?;
+            tracing::info!(path = %path, "Loaded invites");
+            invites
+        }
+        _ => InviteStore::disabled(),
+    };
+
     // Create application state
-    let state = AppState::new(config.clone(), tera, nntp_service, oidc);
+    let mut state = AppState::new(
+        config.clone(),
+        tera,
+        basic_tera,
+        text_tera,
+        nntp_service,
+        oidc,
+    )
+    .with_announcements(announcements)
+    .with_review_queue(review)
+    .with_invites(invites);
+    if let Some(guidelines) = guidelines {
+        state = state.with_guidelines(guidelines);
+    }
 
     // Create router
     let app = create_router(state);
@@ -0,0 +1,244 @@
+//! In-process metrics registry.
+//!
+//! Counters are process-wide atomics, cheap enough to bump on every request
+//! and cache lookup. They start at zero on each restart and are read by the
+//! `/about/stats` page and the NodeInfo document; there is no exporter.
+//! Template render times are kept per template name, behind a lock taken
+//! once per render.
+
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+
+/// The process-wide registry
+static METRICS: Metrics = Metrics::new();
+
+/// When the registry was first used, as the start of the counting period
+static STARTED: OnceLock<Instant> = OnceLock::new();
+
+/// Access the process-wide registry
+pub fn metrics() -> &'static Metrics {
+    STARTED.get_or_init(Instant::now);
+    &METRICS
+}
+
+/// Counters kept by the registry
+pub struct Metrics {
+    /// HTTP requests served, of any status
+    pub requests: AtomicU64,
+    /// Articles posted through the gateway
+    pub posts: AtomicU64,
+    /// Article lookups (positive and negative cache)
+    pub article_cache: CacheCounters,
+    /// Thread list lookups per group
+    pub threads_cache: CacheCounters,
+    /// Single thread lookups
+    pub thread_cache: CacheCounters,
+    /// Group list lookups
+    pub groups_cache: CacheCounters,
+    /// Rendered thread comment lookups, for pages that may use them
+    pub thread_fragments: CacheCounters,
+    /// Render times per template name
+    renders: Mutex<BTreeMap<String, RenderTiming>>,
+}
+
+impl Metrics {
+    const fn new() -> Self {
+        Self {
+            requests: AtomicU64::new(0),
+            posts: AtomicU64::new(0),
+            article_cache: CacheCounters::new(),
+            threads_cache: CacheCounters::new(),
+            thread_cache: CacheCounters::new(),
+            groups_cache: CacheCounters::new(),
+            thread_fragments: CacheCounters::new(),
+            renders: Mutex::new(BTreeMap::new()),
+        }
+    }
+
+    /// Count one served HTTP request
+    pub fn record_request(&self) {
+        self.requests.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Requests served since the process started
+    pub fn requests_served(&self) -> u64 {
+        self.requests.load(Ordering::Relaxed)
+    }
+
+    /// Count one article accepted by an upstream server
+    pub fn record_post(&self) {
+        self.posts.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Articles posted since the process started
+    pub fn posts_submitted(&self) -> u64 {
+        self.posts.load(Ordering::Relaxed)
+    }
+
+    /// Seconds since counting started
+    pub fn uptime_secs(&self) -> u64 {
+        STARTED
+            .get()
+            .map_or(0, |started| started.elapsed().as_secs())
+    }
+
+    /// Count one render of `template` that took `elapsed`
+    pub fn record_render(&self, template: &str, elapsed: Duration) {
+        let mut renders = self.renders.lock().unwrap_or_else(|e| e.into_inner());
+        match renders.get_mut(template) {
+            Some(timing) => timing.record(elapsed),
+            None => {
+                let mut timing = RenderTiming::default();
+                timing.record(elapsed);
+                renders.insert(template.to_string(), timing);
+            }
+        }
+    }
+
+    /// Render times per template, the most total time first
+    pub fn render_stats(&self) -> Vec<RenderStats> {
+        let renders = self.renders.lock().unwrap_or_else(|e| e.into_inner());
+        let ms = |d: Duration| d.as_secs_f64() * 1000.0;
+        let mut stats: Vec<RenderStats> = renders
+            .iter()
+            .map(|(template, timing)| RenderStats {
+                template: template.clone(),
+                renders: timing.renders,
+                mean_ms: ms(timing.total) / timing.renders as f64,
+                max_ms: ms(timing.max),
+                total_ms: ms(timing.total),
+            })
+            .collect();
+        stats.sort_by(|a, b| b.total_ms.total_cmp(&a.total_ms));
+        stats
+    }
+
+    /// Hit/miss counts for every cache, in display order
+    pub fn cache_stats(&self) -> Vec<CacheStats> {
+        vec![
+            self.article_cache.snapshot("Articles"),
+            self.threads_cache.snapshot("Thread lists"),
+            self.thread_cache.snapshot("Threads"),
+            self.groups_cache.snapshot("Group list"),
+            self.thread_fragments.snapshot("Rendered comments"),
+        ]
+    }
+}
+
+/// Render count and times for one template
+#[derive(Debug, Default, Clone, Copy)]
+struct RenderTiming {
+    renders: u64,
+    total: Duration,
+    max: Duration,
+}
+
+impl RenderTiming {
+    fn record(&mut self, elapsed: Duration) {
+        self.renders += 1;
+        self.total += elapsed;
+        self.max = self.max.max(elapsed);
+    }
+}
+
+/// Point-in-time render times for one template
+#[derive(Debug, Clone, Serialize)]
+pub struct RenderStats {
+    pub template: String,
+    pub renders: u64,
+    /// Mean render time in milliseconds
+    pub mean_ms: f64,
+    /// Longest render in milliseconds
+    pub max_ms: f64,
+    /// Time spent on all renders in milliseconds
+    pub total_ms: f64,
+}
+
+/// Hit and miss counters for one cache
+pub struct CacheCounters {
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+impl CacheCounters {
+    const fn new() -> Self {
+        Self {
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+        }
+    }
+
+    /// Count one lookup
+    pub fn record(&self, hit: bool) {
+        let counter = if hit { &self.hits } else { &self.misses };
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn snapshot(&self, name: &'static str) -> CacheStats {
+        CacheStats::new(
+            name,
+            self.hits.load(Ordering::Relaxed),
+            self.misses.load(Ordering::Relaxed),
+        )
+    }
+}
+
+/// Point-in-time hit/miss counts for one cache
+#[derive(Debug, Clone, Serialize)]
+pub struct CacheStats {
+    pub name: &'static str,
+    pub hits: u64,
+    pub misses: u64,
+    /// Hit rate in whole percent, `None` before the first lookup
+    pub hit_rate: Option<u64>,
+}
+
+impl CacheStats {
+    fn new(name: &'static str, hits: u64, misses: u64) -> Self {
+        let total = hits + misses;
+        Self {
+            name,
+            hits,
+            misses,
+            hit_rate: (total > 0).then(|| (hits * 100 + total / 2) / total),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cache_counters_hit_rate() {
+        let counters = CacheCounters::new();
+        assert_eq!(counters.snapshot("Articles").hit_rate, None);
+
+        counters.record(true);
+        counters.record(true);
+        counters.record(false);
+        let stats = counters.snapshot("Articles");
+        assert_eq!((stats.hits, stats.misses), (2, 1));
+        assert_eq!(stats.hit_rate, Some(67));
+    }
+
+    #[test]
+    fn test_render_stats_sorted_by_total_time() {
+        let metrics = Metrics::new();
+        metrics.record_render("home.html", Duration::from_millis(2));
+        metrics.record_render("threads/view.html", Duration::from_millis(10));
+        metrics.record_render("threads/view.html", Duration::from_millis(30));
+
+        let stats = metrics.render_stats();
+        assert_eq!(stats.len(), 2);
+        assert_eq!(stats[0].template, "threads/view.html");
+        assert_eq!(stats[0].renders, 2);
+        assert_eq!(stats[0].mean_ms, 20.0);
+        assert_eq!(stats[0].max_ms, 30.0);
+        assert_eq!(stats[1].template, "home.html");
+    }
+}
@@ -2,9 +2,13 @@
 //!
 //! Provides:
 //! - Request ID generation for log correlation
+//! - Request deadlines, shared with the NNTP requests made for a page
 //! - Session extraction and refresh (sliding window)
-//! - RequireAuthWithEmail extractor for posting routes
+//! - Rendering mode selection (standard, basic HTML, or text browser)
+//! - Permanent redirects from aliased (renamed) group names
+//! - RequireAuth and RequireAuthWithEmail extractors for account and posting routes
 
+use std::collections::HashMap;
 use std::sync::Arc;
 use std::time::Duration;
 use std::time::Instant;
@@ -12,16 +16,28 @@ use std::time::Instant;
 use axum::{
     extract::{FromRequestParts, Request, State},
     middleware::Next,
-    response::{Html, IntoResponse, Response},
+    response::{Html, IntoResponse, Redirect, Response},
+};
+use axum_extra::extract::cookie::{Cookie, CookieJar, PrivateCookieJar, SameSite};
+use http::{
+    header::{HeaderValue, LOCATION, SET_COOKIE, USER_AGENT, VARY},
+    request::Parts,
+    Method, StatusCode, Uri,
 };
-use axum_extra::extract::cookie::{Cookie, PrivateCookieJar, SameSite};
-use http::{header::SET_COOKIE, request::Parts, StatusCode};
 use tera::Tera;
 use time::Duration as TimeDuration;
 
-use crate::config::UiConfig;
+use crate::announcements;
+use crate::config::{
+    UiConfig, ANNOUNCEMENT_DISMISS_COOKIE, RENDER_MODE_COOKIE, RENDER_MODE_COOKIE_MAX_AGE_SECS,
+};
+use crate::deadline;
+use crate::error::{AppError, AppErrorResponse};
+use crate::metrics::metrics;
+use crate::nntp::with_from_display;
 use crate::oidc::session::{cookie_names, User};
 use crate::state::AppState;
+use crate::templates::render;
 use tracing::Instrument;
 use uuid::Uuid;
 
@@ -35,10 +51,54 @@ pub struct RequestId(pub Uuid);
 #[derive(Clone, Debug)]
 pub struct CurrentUser(pub Option<User>);
 
+/// Extension type for the rendering mode selected for this request.
+/// Inserted by render_mode_layer from the query string, cookie, or User-Agent.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum RenderMode {
+    /// Full theme with JavaScript enhancements
+    #[default]
+    Standard,
+    /// Accessible basic HTML: no JavaScript, large text, simplified layout
+    Basic,
+    /// Minimal markup with numbered navigation links for text browsers (Lynx, w3m)
+    Text,
+}
+
+impl RenderMode {
+    /// Parse a mode name as used in the `mode` query parameter and cookie.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "standard" => Some(RenderMode::Standard),
+            "basic" => Some(RenderMode::Basic),
+            "text" => Some(RenderMode::Text),
+            _ => None,
+        }
+    }
+
+    /// Name of this mode as used in the `mode` query parameter and cookie.
+    pub fn name(self) -> &'static str {
+        match self {
+            RenderMode::Standard => "standard",
+            RenderMode::Basic => "basic",
+            RenderMode::Text => "text",
+        }
+    }
+}
+
+/// Extractor that requires an authenticated, unexpired session.
+///
+/// Use this for account routes that need a user but not an email address.
+#[derive(Clone, Debug)]
+pub struct RequireAuth {
+    pub user: User,
+}
+
 /// Extractor that requires authentication with a valid email.
 ///
 /// Use this for posting routes that require both authentication and an email address.
-/// Returns appropriate errors if not authenticated or if email is missing.
+/// Returns appropriate errors if not authenticated, if email is missing, or if
+/// the instance is invite-only and the user has no posting access. Users who
+/// have not accepted the current posting guidelines are redirected to them.
 ///
 /// # Example
 /// ```ignore
@@ -62,6 +122,11 @@ pub enum AuthErrorKind {
     NotAuthenticated,
     /// User is authenticated but missing required email
     MissingEmail,
+    /// Posting is invite-only and the user is neither allowlisted nor invited
+    NotInvited,
+    /// Posting guidelines are configured and the user has not accepted them.
+    /// Carries the page to return to after accepting.
+    GuidelinesNotAccepted { return_to: Option<String> },
 }
 
 /// Authentication error with template rendering context
@@ -87,6 +152,7 @@ impl AuthError {
 
 impl IntoResponse for AuthError {
     fn into_response(self) -> Response {
+        let show_account = matches!(self.kind, AuthErrorKind::NotInvited);
         let (status, title, message, show_login) = match self.kind {
             AuthErrorKind::NotAuthenticated => (
                 StatusCode::UNAUTHORIZED,
@@ -100,6 +166,19 @@ impl IntoResponse for AuthError {
                 "Your account does not have an email address, which is required for posting.",
                 false,
             ),
+            AuthErrorKind::NotInvited => (
+                StatusCode::FORBIDDEN,
+                "Invitation Required",
+                "Posting on this site is invite-only. Redeem an invite code on your account page.",
+                false,
+            ),
+            AuthErrorKind::GuidelinesNotAccepted { return_to } => {
+                let location = match return_to {
+                    Some(path) => format!("/guidelines?return_to={}", urlencoding::encode(&path)),
+                    None => "/guidelines".to_string(),
+                };
+                return Redirect::to(&location).into_response();
+            }
         };
 
         let mut context = tera::Context::new();
@@ -107,8 +186,9 @@ impl IntoResponse for AuthError {
         context.insert("title", title);
         context.insert("message", message);
         context.insert("show_login", &show_login);
+        context.insert("show_account", &show_account);
 
-        match self.tera.render("auth/error.html", &context) {
+        match render(&self.tera, "auth/error.html", &context) {
             Ok(html) => (status, Html(html)).into_response(),
             Err(e) => {
                 tracing::error!(error = %e, "Failed to render auth error template");
@@ -127,6 +207,36 @@ impl IntoResponse for AuthError {
     }
 }
 
+impl FromRequestParts<AppState> for RequireAuth {
+    type Rejection = AuthError;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &AppState,
+    ) -> Result<Self, Self::Rejection> {
+        let render_mode = parts
+            .extensions
+            .get::<RenderMode>()
+            .copied()
+            .unwrap_or_default();
+
+        let current_user = parts
+            .extensions
+            .get::<CurrentUser>()
+            .cloned()
+            .unwrap_or(CurrentUser(None));
+
+        match current_user.0 {
+            Some(user) if !user.is_expired() => Ok(RequireAuth { user }),
+            _ => Err(AuthError::new(
+                AuthErrorKind::NotAuthenticated,
+                state.tera_for(render_mode).clone(),
+                Arc::new(state.config.ui.clone()),
+            )),
+        }
+    }
+}
+
 impl FromRequestParts<AppState> for RequireAuthWithEmail {
     type Rejection = AuthError;
 
@@ -134,7 +244,12 @@ impl FromRequestParts<AppState> for RequireAuthWithEmail {
         parts: &mut Parts,
         state: &AppState,
     ) -> Result<Self, Self::Rejection> {
-        let tera = state.tera.clone();
+        let render_mode = parts
+            .extensions
+            .get::<RenderMode>()
+            .copied()
+            .unwrap_or_default();
+        let tera = state.tera_for(render_mode).clone();
         let config = Arc::new(state.config.ui.clone());
 
         let current_user = parts
@@ -148,6 +263,17 @@ impl FromRequestParts<AppState> for RequireAuthWithEmail {
                 let email = user.email.clone().ok_or_else(|| {
                     AuthError::new(AuthErrorKind::MissingEmail, tera.clone(), config.clone())
                 })?;
+                if !state.can_post(&user).await {
+                    return Err(AuthError::new(AuthErrorKind::NotInvited, tera, config));
+                }
+                if state.needs_guidelines_acceptance(&user) {
+                    // Only GET pages can be returned to; form posts go back to the home page
+                    let return_to = (parts.method == Method::GET)
+                        .then(|| parts.uri.path_and_query().map(|pq| pq.to_string()))
+                        .flatten();
+                    let kind = AuthErrorKind::GuidelinesNotAccepted { return_to };
+                    return Err(AuthError::new(kind, tera, config));
+                }
                 Ok(RequireAuthWithEmail { user, email })
             }
             _ => Err(AuthError::new(
@@ -188,6 +314,7 @@ pub async fn request_id_layer(request: Request, next: Next) -> Response {
     async move {
         let response = next.run(request).await;
         let duration_ms = start.elapsed().as_millis() as u64;
+        metrics().record_request();
 
         // Record duration and log completion with status code
         tracing::Span::current().record("duration_ms", duration_ms);
@@ -203,6 +330,251 @@ pub async fn request_id_layer(request: Request, next: Next) -> Response {
     .await
 }
 
+/// Privacy middleware: articles serialized while serving the request show
+/// poster addresses according to `[ui] from_display`.
+pub async fn from_display_layer(
+    State(state): State<AppState>,
+    request: Request,
+    next: Next,
+) -> Response {
+    with_from_display(state.config.ui.from_display, next.run(request)).await
+}
+
+/// Deadline middleware: answers 504 once `request_timeout_seconds` have
+/// passed, and makes the deadline visible to the NNTP layer so requests
+/// queued for this page are dropped instead of sent upstream after it.
+pub async fn deadline_layer(
+    State(state): State<AppState>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let timeout = Duration::from_secs(state.config.http.request_timeout_seconds);
+    let request_id = request.extensions().get::<RequestId>().map(|id| id.0);
+
+    let handler = deadline::scope(Instant::now() + timeout, next.run(request));
+    match tokio::time::timeout(timeout, handler).await {
+        Ok(response) => response,
+        Err(_) => {
+            tracing::warn!(
+                timeout_secs = timeout.as_secs(),
+                "Request deadline exceeded"
+            );
+            AppErrorResponse::new(AppError::Timeout, request_id).into_response()
+        }
+    }
+}
+
+/// Middleware that selects the rendering mode for the request.
+///
+/// An explicit `?mode=` (or `?plain=1`) query parameter wins and is remembered
+/// in a cookie; otherwise the cookie decides, and text browsers are detected
+/// from their User-Agent. Responses carry `Vary: Cookie, User-Agent` so shared
+/// caches don't serve one mode's HTML to another.
+pub async fn render_mode_layer(jar: CookieJar, mut request: Request, next: Next) -> Response {
+    let requested = request.uri().query().and_then(mode_from_query);
+    let render_mode = requested
+        .or_else(|| {
+            jar.get(RENDER_MODE_COOKIE)
+                .and_then(|c| RenderMode::from_name(c.value()))
+        })
+        .or_else(|| {
+            request
+                .headers()
+                .get(USER_AGENT)
+                .and_then(|v| v.to_str().ok())
+                .filter(|ua| is_text_browser(ua))
+                .map(|_| RenderMode::Text)
+        })
+        .unwrap_or_default();
+
+    request.extensions_mut().insert(render_mode);
+
+    let mut response = next.run(request).await;
+    response
+        .headers_mut()
+        .append(VARY, HeaderValue::from_static("Cookie, User-Agent"));
+
+    if let Some(mode) = requested {
+        let cookie = Cookie::build((RENDER_MODE_COOKIE, mode.name()))
+            .path("/")
+            .same_site(SameSite::Lax)
+            .max_age(TimeDuration::seconds(RENDER_MODE_COOKIE_MAX_AGE_SECS))
+            .build();
+        if let Ok(value) = cookie.to_string().parse() {
+            response.headers_mut().append(SET_COOKIE, value);
+        }
+    }
+
+    response
+}
+
+/// Middleware that picks the announcements to show on this request's page:
+/// those running now, minus the ones dismissed in the visitor's cookie.
+/// `insert_auth_context` adds them to the template context.
+pub async fn announcement_layer(
+    State(state): State<AppState>,
+    jar: CookieJar,
+    request: Request,
+    next: Next,
+) -> Response {
+    let dismissed = jar
+        .get(ANNOUNCEMENT_DISMISS_COOKIE)
+        .map(|c| announcements::parse_dismissed(c.value()))
+        .unwrap_or_default();
+    let visible = state.announcements.visible(chrono::Utc::now(), &dismissed);
+    announcements::scope(visible, next.run(request)).await
+}
+
+/// Find a recognised `mode=` value (or the `plain=1` shorthand) in a raw query string.
+fn mode_from_query(query: &str) -> Option<RenderMode> {
+    query.split('&').find_map(|pair| {
+        if pair == "plain=1" {
+            Some(RenderMode::Text)
+        } else {
+            pair.strip_prefix("mode=").and_then(RenderMode::from_name)
+        }
+    })
+}
+
+/// Check whether a User-Agent belongs to a text-mode browser.
+fn is_text_browser(user_agent: &str) -> bool {
+    const TEXT_BROWSERS: [&str; 4] = ["lynx", "links", "elinks", "w3m"];
+    let product = user_agent
+        .split(['/', ' ', '('])
+        .next()
+        .unwrap_or_default()
+        .to_ascii_lowercase();
+    TEXT_BROWSERS.contains(&product.as_str())
+}
+
+/// Middleware that redirects alternate forms of page URLs to the canonical one.
+///
+/// - Trailing slashes are dropped (`/g/comp.lang.rust/` -> `/g/comp.lang.rust`)
+/// - Group names under `/g/` and prefixes under `/browse/` are lowercased,
+///   but only when the servers carry the lowercase name and not the one
+///   asked for, as newsgroup names are case-sensitive
+/// - Aliased group names under `/g/` are replaced by the current name
+///
+/// The query string is kept, and everything is fixed in one redirect. GET
+/// and HEAD use 301; other methods use 308 so form submissions are replayed
+/// with their body.
+pub async fn canonical_redirect_layer(
+    State(state): State<AppState>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let aliases = &state.config.group_aliases;
+    let fold_case = match group_segment(request.uri().path()) {
+        Some((prefix, name)) => folds_to_lowercase(&state, prefix, name).await,
+        None => false,
+    };
+    if let Some(location) = canonical_redirect_location(aliases, request.uri(), fold_case) {
+        let status = if matches!(*request.method(), Method::GET | Method::HEAD) {
+            StatusCode::MOVED_PERMANENTLY
+        } else {
+            StatusCode::PERMANENT_REDIRECT
+        };
+        tracing::debug!(from = %request.uri(), to = %location, "Redirecting to canonical URL");
+        return (status, [(LOCATION, location)]).into_response();
+    }
+
+    next.run(request).await
+}
+
+/// Whether the group or browse prefix `name` only exists in lowercase, so a
+/// request for it is redirected there rather than answered as missing.
+async fn folds_to_lowercase(state: &AppState, prefix: &str, name: &str) -> bool {
+    let lower = name.to_ascii_lowercase();
+    if lower == name {
+        return false;
+    }
+    if prefix == "/g/" {
+        knows_group(state, &lower).await && !knows_group(state, name).await
+    } else {
+        state.nntp.carries_hierarchy(&lower).await && !state.nntp.carries_hierarchy(name).await
+    }
+}
+
+/// A group some server carries, or an old name in `group_aliases`
+async fn knows_group(state: &AppState, group: &str) -> bool {
+    state.config.group_aliases.contains_key(group) || state.nntp.carries_group(group).await
+}
+
+/// Build the redirect target for a path that isn't in canonical form.
+/// `fold_case` lowercases the group or browse prefix in it.
+fn canonical_redirect_location(
+    aliases: &HashMap<String, String>,
+    uri: &Uri,
+    fold_case: bool,
+) -> Option<String> {
+    let original = uri.path();
+    // Static files are served as they are named
+    if original.starts_with("/static/") {
+        return None;
+    }
+
+    let trimmed = original.trim_end_matches('/');
+    let mut path = if trimmed.is_empty() {
+        "/".to_string()
+    } else if fold_case {
+        lowercase_group(trimmed)
+    } else {
+        trimmed.to_string()
+    };
+    if let Some(aliased) = alias_path(aliases, &path) {
+        path = aliased;
+    }
+    if path == original {
+        return None;
+    }
+
+    if let Some(query) = uri.query() {
+        path.push('?');
+        path.push_str(query);
+    }
+    Some(path)
+}
+
+/// The group in `/g/{group}/...` or the prefix in `/browse/{prefix}`, with
+/// the route prefix it follows.
+fn group_segment(path: &str) -> Option<(&'static str, &str)> {
+    ["/g/", "/browse/"].into_iter().find_map(|prefix| {
+        let rest = path.strip_prefix(prefix)?;
+        Some((prefix, rest.split('/').next().unwrap_or(rest)))
+    })
+}
+
+/// Lowercase the group in `/g/{group}/...` and the prefix in `/browse/{prefix}`,
+/// leaving the rest (message ID segments are case-sensitive) alone.
+fn lowercase_group(path: &str) -> String {
+    match group_segment(path) {
+        Some((prefix, group)) => format!(
+            "{}{}{}",
+            prefix,
+            group.to_ascii_lowercase(),
+            &path[prefix.len() + group.len()..]
+        ),
+        None => path.to_string(),
+    }
+}
+
+/// The same `/g/{group}` path under the current name, if the group is an alias.
+fn alias_path(aliases: &HashMap<String, String>, path: &str) -> Option<String> {
+    let rest = path.strip_prefix("/g/")?;
+    let (group, tail) = match rest.split_once('/') {
+        Some((group, tail)) => (group, Some(tail)),
+        None => (rest, None),
+    };
+    let target = aliases.get(group)?;
+
+    let mut location = format!("/g/{}", target);
+    if let Some(tail) = tail {
+        location.push('/');
+        location.push_str(tail);
+    }
+    Some(location)
+}
+
 /// Middleware that extracts user session from signed cookie.
 ///
 /// This reads the session cookie, validates it, injects CurrentUser into
@@ -227,8 +599,17 @@ pub async fn auth_layer(
     // Process the request
     let response = next.run(request).await;
 
+    // Handlers that set or remove the session cookie themselves (logout,
+    // guideline acceptance) must not be overridden by the refreshed copy
+    let session_prefix = format!("{}=", cookie_names::SESSION);
+    let handler_set_session = response
+        .headers()
+        .get_all(SET_COOKIE)
+        .iter()
+        .any(|v| v.as_bytes().starts_with(session_prefix.as_bytes()));
+
     // If session needs refresh, update the cookie
-    if let (Some(mut user), true) = (user, needs_refresh) {
+    if let (Some(mut user), true, false) = (user, needs_refresh, handler_set_session) {
         user.refresh(session_lifetime);
 
         if let Ok(user_json) = serde_json::to_string(&user) {
@@ -281,3 +662,155 @@ fn extract_user_from_cookie(
 
     (Some(user), needs_refresh)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mode_from_query_basic() {
+        assert_eq!(mode_from_query("mode=basic"), Some(RenderMode::Basic));
+        assert_eq!(
+            mode_from_query("page=2&mode=basic"),
+            Some(RenderMode::Basic)
+        );
+    }
+
+    #[test]
+    fn test_mode_from_query_standard() {
+        assert_eq!(mode_from_query("mode=standard"), Some(RenderMode::Standard));
+    }
+
+    #[test]
+    fn test_mode_from_query_ignores_unknown() {
+        assert_eq!(mode_from_query("mode=fancy"), None);
+        assert_eq!(mode_from_query("page=2"), None);
+        assert_eq!(mode_from_query("xmode=basic"), None);
+    }
+
+    #[test]
+    fn test_mode_from_query_plain_shorthand() {
+        assert_eq!(mode_from_query("plain=1"), Some(RenderMode::Text));
+        assert_eq!(mode_from_query("page=3&plain=1"), Some(RenderMode::Text));
+        assert_eq!(mode_from_query("plain=0"), None);
+    }
+
+    #[test]
+    fn test_is_text_browser() {
+        assert!(is_text_browser("Lynx/2.9.0 libwww-FM/2.14 SSL-MM/1.4.1"));
+        assert!(is_text_browser("w3m/0.5.3+git20230121"));
+        assert!(is_text_browser("ELinks/0.16.1.1 (textmode; Linux)"));
+        assert!(is_text_browser(
+            "Links (2.29; Linux 6.1 x86_64; GNU C; text)"
+        ));
+        assert!(!is_text_browser(
+            "Mozilla/5.0 (X11; Linux x86_64; rv:128.0) Gecko/20100101 Firefox/128.0"
+        ));
+        assert!(!is_text_browser(""));
+    }
+
+    fn test_aliases() -> HashMap<String, String> {
+        let mut aliases = HashMap::new();
+        aliases.insert("comp.lang.old".to_string(), "comp.lang.new".to_string());
+        aliases
+    }
+
+    #[test]
+    fn test_alias_redirect_location_group_root() {
+        let uri: Uri = "/g/comp.lang.old".parse().unwrap();
+        assert_eq!(
+            canonical_redirect_location(&test_aliases(), &uri, false),
+            Some("/g/comp.lang.new".to_string())
+        );
+    }
+
+    #[test]
+    fn test_alias_redirect_location_keeps_path_and_query() {
+        let uri: Uri = "/g/comp.lang.old/thread/abc@example.com?page=2"
+            .parse()
+            .unwrap();
+        assert_eq!(
+            canonical_redirect_location(&test_aliases(), &uri, false),
+            Some("/g/comp.lang.new/thread/abc@example.com?page=2".to_string())
+        );
+    }
+
+    #[test]
+    fn test_alias_redirect_location_ignores_other_paths() {
+        let aliases = test_aliases();
+        let canonical: Uri = "/g/comp.lang.new".parse().unwrap();
+        let prefix: Uri = "/g/comp.lang.older".parse().unwrap();
+        let browse: Uri = "/browse/comp.lang.old".parse().unwrap();
+        assert_eq!(
+            canonical_redirect_location(&aliases, &canonical, false),
+            None
+        );
+        assert_eq!(canonical_redirect_location(&aliases, &prefix, false), None);
+        assert_eq!(canonical_redirect_location(&aliases, &browse, false), None);
+    }
+
+    #[test]
+    fn test_canonical_redirect_location_trailing_slash_and_case() {
+        let aliases = test_aliases();
+        let uri: Uri = "/g/Comp.Lang.Rust/".parse().unwrap();
+        assert_eq!(
+            canonical_redirect_location(&aliases, &uri, true),
+            Some("/g/comp.lang.rust".to_string())
+        );
+
+        // Message ID segments keep their case
+        let uri: Uri = "/g/comp.lang.rust/thread/YWJjQEV4YW1wbGU/?page=2"
+            .parse()
+            .unwrap();
+        assert_eq!(
+            canonical_redirect_location(&aliases, &uri, true),
+            Some("/g/comp.lang.rust/thread/YWJjQEV4YW1wbGU?page=2".to_string())
+        );
+
+        // Case, slash and alias are fixed in one hop
+        let uri: Uri = "/g/COMP.LANG.OLD/".parse().unwrap();
+        assert_eq!(
+            canonical_redirect_location(&aliases, &uri, true),
+            Some("/g/comp.lang.new".to_string())
+        );
+
+        let uri: Uri = "/browse/Comp.Lang".parse().unwrap();
+        assert_eq!(
+            canonical_redirect_location(&aliases, &uri, true),
+            Some("/browse/comp.lang".to_string())
+        );
+    }
+
+    #[test]
+    fn test_canonical_redirect_location_keeps_case_unless_folding() {
+        let aliases = test_aliases();
+        let uri: Uri = "/g/alt.Binaries.Misc".parse().unwrap();
+        assert_eq!(canonical_redirect_location(&aliases, &uri, false), None);
+
+        let uri: Uri = "/g/alt.Binaries.Misc/".parse().unwrap();
+        assert_eq!(
+            canonical_redirect_location(&aliases, &uri, false),
+            Some("/g/alt.Binaries.Misc".to_string())
+        );
+    }
+
+    #[test]
+    fn test_canonical_redirect_location_leaves_canonical_paths() {
+        let aliases = test_aliases();
+        for path in [
+            "/",
+            "/a/YWJjQEV4YW1wbGU",
+            "/about",
+            "/static/css/",
+            "/g/comp.lang.rust",
+        ] {
+            let uri: Uri = path.parse().unwrap();
+            assert_eq!(
+                canonical_redirect_location(&aliases, &uri, false),
+                None,
+                "{}",
+                path
+            );
+        }
+    }
+}
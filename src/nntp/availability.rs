@@ -0,0 +1,157 @@
+//! Upstream availability history.
+//!
+//! Each server is sampled every `AVAILABILITY_PROBE_INTERVAL_SECS` and counts
+//! as up when at least one of its workers holds a live connection. Workers
+//! reconnect on their own, so sampling costs no NNTP traffic. Samples are
+//! summed into daily buckets kept in a ring buffer of
+//! `AVAILABILITY_HISTORY_DAYS` days, which is lost on restart.
+
+use std::collections::{HashMap, VecDeque};
+
+use chrono::{Days, NaiveDate};
+use serde::Serialize;
+
+use crate::config::AVAILABILITY_HISTORY_DAYS;
+
+/// Samples taken on one day
+#[derive(Debug, Clone, Copy)]
+struct DayBucket {
+    day: NaiveDate,
+    up: u32,
+    total: u32,
+}
+
+/// Per-server ring buffers of daily samples
+#[derive(Debug, Default)]
+pub struct AvailabilityHistory {
+    servers: HashMap<String, VecDeque<DayBucket>>,
+}
+
+/// Availability of one server on one day
+#[derive(Debug, Clone, Serialize)]
+pub struct DayAvailability {
+    /// Day in YYYY-MM-DD form (UTC)
+    pub date: String,
+    /// Percentage of samples that were up, `None` if nothing was sampled
+    pub percent: Option<f64>,
+}
+
+/// Availability of one server over the history window
+#[derive(Debug, Clone, Serialize)]
+pub struct ServerAvailability {
+    pub name: String,
+    /// Percentage over all samples in the window, `None` if nothing was sampled
+    pub percent: Option<f64>,
+    /// One entry per day, oldest first, ending today
+    pub days: Vec<DayAvailability>,
+}
+
+impl AvailabilityHistory {
+    /// Record one sample for a server
+    pub fn record(&mut self, server: &str, up: bool, today: NaiveDate) {
+        let buckets = self.servers.entry(server.to_string()).or_default();
+        match buckets.back_mut() {
+            Some(bucket) if bucket.day == today => {
+                bucket.up += u32::from(up);
+                bucket.total += 1;
+            }
+            _ => buckets.push_back(DayBucket {
+                day: today,
+                up: u32::from(up),
+                total: 1,
+            }),
+        }
+        while buckets.len() > AVAILABILITY_HISTORY_DAYS {
+            buckets.pop_front();
+        }
+    }
+
+    /// Availability of the given servers for the window ending `today`
+    pub fn report(&self, servers: &[&str], today: NaiveDate) -> Vec<ServerAvailability> {
+        let first_day = today
+            .checked_sub_days(Days::new(AVAILABILITY_HISTORY_DAYS as u64 - 1))
+            .unwrap_or(today);
+
+        servers
+            .iter()
+            .map(|&name| {
+                let buckets = self.servers.get(name);
+                let bucket_for = |day: NaiveDate| {
+                    buckets.and_then(|b| b.iter().find(|bucket| bucket.day == day))
+                };
+
+                let (mut up, mut total) = (0, 0);
+                let days = first_day
+                    .iter_days()
+                    .take_while(|day| *day <= today)
+                    .map(|day| {
+                        let bucket = bucket_for(day);
+                        if let Some(bucket) = bucket {
+                            up += bucket.up;
+                            total += bucket.total;
+                        }
+                        DayAvailability {
+                            date: day.format("%Y-%m-%d").to_string(),
+                            percent: bucket.map(|b| percent(b.up, b.total)),
+                        }
+                    })
+                    .collect();
+
+                ServerAvailability {
+                    name: name.to_string(),
+                    percent: (total > 0).then(|| percent(up, total)),
+                    days,
+                }
+            })
+            .collect()
+    }
+}
+
+/// Share of `up` in `total` as a percentage with one decimal
+fn percent(up: u32, total: u32) -> f64 {
+    (u64::from(up) * 1000 / u64::from(total)) as f64 / 10.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn day(d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2024, 3, d).unwrap()
+    }
+
+    #[test]
+    fn test_availability_daily_percentages() {
+        let mut history = AvailabilityHistory::default();
+        for up in [true, true, true, false] {
+            history.record("primary", up, day(1));
+        }
+        history.record("primary", true, day(3));
+
+        let report = history.report(&["primary", "backup"], day(3));
+        let primary = &report[0];
+        assert_eq!(primary.days.len(), AVAILABILITY_HISTORY_DAYS);
+        assert_eq!(primary.days.last().unwrap().date, "2024-03-03");
+        let recent: Vec<Option<f64>> = primary
+            .days
+            .iter()
+            .rev()
+            .take(3)
+            .map(|d| d.percent)
+            .collect();
+        assert_eq!(recent, vec![Some(100.0), None, Some(75.0)]);
+        assert_eq!(primary.percent, Some(80.0));
+
+        assert_eq!(report[1].percent, None);
+    }
+
+    #[test]
+    fn test_availability_ring_buffer_drops_old_days() {
+        let mut history = AvailabilityHistory::default();
+        let start = day(1);
+        for offset in 0..(AVAILABILITY_HISTORY_DAYS as u64 + 5) {
+            history.record("primary", false, start + Days::new(offset));
+        }
+        assert_eq!(history.servers["primary"].len(), AVAILABILITY_HISTORY_DAYS);
+    }
+}
@@ -0,0 +1,64 @@
+//! Cleanup for calls dropped before they finish.
+//!
+//! When an HTTP client disconnects, axum drops the handler future and with it
+//! every NNTP call the handler was awaiting. Dropping a call drops the oneshot
+//! receiver for its response, and workers skip queued requests whose receiver
+//! is gone. A call that registered itself as the leader of a coalesced fetch
+//! also has to remove that entry, or the callers that joined it would wait on
+//! a result that never comes. [`OnCancel`] runs that cleanup.
+
+use std::future::Future;
+use std::pin::Pin;
+
+/// Spawns a cleanup task when dropped, unless disarmed first
+pub struct OnCancel {
+    cleanup: Option<Pin<Box<dyn Future<Output = ()> + Send>>>,
+}
+
+impl OnCancel {
+    /// `cleanup` only runs if the guard is dropped while still armed
+    pub fn new(cleanup: impl Future<Output = ()> + Send + 'static) -> Self {
+        Self {
+            cleanup: Some(Box::pin(cleanup)),
+        }
+    }
+
+    /// The call finished and did its own cleanup
+    pub fn disarm(mut self) {
+        self.cleanup = None;
+    }
+}
+
+impl Drop for OnCancel {
+    fn drop(&mut self) {
+        // Drop can't await a lock, so the cleanup runs as its own task
+        if let (Some(cleanup), Ok(runtime)) =
+            (self.cleanup.take(), tokio::runtime::Handle::try_current())
+        {
+            runtime.spawn(cleanup);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::sync::oneshot;
+
+    #[tokio::test]
+    async fn test_on_cancel_runs_only_while_armed() {
+        let (tx, rx) = oneshot::channel();
+        drop(OnCancel::new(async move {
+            let _ = tx.send(());
+        }));
+        assert!(rx.await.is_ok());
+
+        // Disarming drops the cleanup without running it
+        let (tx, rx) = oneshot::channel::<()>();
+        OnCancel::new(async move {
+            let _ = tx.send(());
+        })
+        .disarm();
+        assert!(rx.await.is_err());
+    }
+}
@@ -0,0 +1,281 @@
+//! Character set conversion for article headers and bodies.
+//!
+//! Many legacy posts are in ISO-8859-x, KOI8-R or Windows code pages rather
+//! than UTF-8. Text is decoded with the charset declared in the Content-Type
+//! header when there is one; otherwise (or when the declared charset does not
+//! fit the bytes) valid UTF-8 is taken as-is and anything else is decoded with
+//! the encoding guessed by `chardetng`.
+
+use std::borrow::Cow;
+
+use base64::alphabet;
+use base64::engine::general_purpose::{GeneralPurpose, GeneralPurposeConfig};
+use base64::engine::DecodePaddingMode;
+use base64::Engine as _;
+use chardetng::EncodingDetector;
+use encoding_rs::Encoding;
+
+/// Base64 for encoded-words; some posting software leaves out the padding
+const ENCODED_WORD_BASE64: GeneralPurpose = GeneralPurpose::new(
+    &alphabet::STANDARD,
+    GeneralPurposeConfig::new().with_decode_padding_mode(DecodePaddingMode::Indifferent),
+);
+
+/// Find the charset declared in the Content-Type header of a raw header block.
+///
+/// Returns None when there is no Content-Type, no charset parameter, or the
+/// label is not one encoding_rs recognizes.
+pub fn declared_charset(headers: &[u8]) -> Option<&'static Encoding> {
+    let headers = String::from_utf8_lossy(headers);
+    let content_type = header_value(&headers, "content-type")?;
+    content_type.split(';').skip(1).find_map(|param| {
+        let (name, value) = param.split_once('=')?;
+        if !name.trim().eq_ignore_ascii_case("charset") {
+            return None;
+        }
+        Encoding::for_label(value.trim().trim_matches('"').as_bytes())
+    })
+}
+
+/// Decode text using the declared charset, falling back to detection.
+pub fn decode(bytes: &[u8], declared: Option<&'static Encoding>) -> String {
+    if let Some(encoding) = declared {
+        let (text, had_errors) = encoding.decode_without_bom_handling(bytes);
+        if !had_errors {
+            return text.into_owned();
+        }
+        tracing::debug!(
+            charset = encoding.name(),
+            "Text does not match declared charset, detecting"
+        );
+    }
+
+    if let Ok(text) = std::str::from_utf8(bytes) {
+        return text.to_string();
+    }
+
+    let mut detector = EncodingDetector::new();
+    detector.feed(bytes, true);
+    let encoding = detector.guess(None, true);
+    let (text, _) = encoding.decode_without_bom_handling(bytes);
+    text.into_owned()
+}
+
+/// Decode a raw header block (HEAD response or article headers).
+///
+/// 8-bit header text is usually in the same charset as the body, so the
+/// block's own Content-Type is honoured.
+pub fn decode_headers(headers: &[u8]) -> String {
+    decode(headers, declared_charset(headers))
+}
+
+/// Replace a header value that was lossily decoded upstream.
+///
+/// Values from the NNTP library are kept unless they contain U+FFFD, in
+/// which case the value is taken from the charset-decoded header block and
+/// its RFC 2047 encoded-words are decoded.
+pub fn repair_header(value: Option<String>, headers: Option<&str>, name: &str) -> Option<String> {
+    match value {
+        Some(v) if v.contains(char::REPLACEMENT_CHARACTER) => headers
+            .and_then(|h| header_value(h, name))
+            .map(|raw| decode_encoded_words(&raw).into_owned())
+            .or(Some(v)),
+        other => other,
+    }
+}
+
+/// Decode RFC 2047 encoded-words (`=?charset?B?...?=` and `=?charset?Q?...?=`)
+/// in a header value.
+///
+/// Whitespace between two adjacent encoded-words is dropped, as the RFC
+/// requires. Words with an unknown charset or malformed text are left as
+/// they are.
+pub fn decode_encoded_words(value: &str) -> Cow<'_, str> {
+    if !value.contains("=?") {
+        return Cow::Borrowed(value);
+    }
+
+    let mut out = String::with_capacity(value.len());
+    let mut rest = value;
+    let mut after_word = false;
+    while let Some(start) = rest.find("=?") {
+        let Some((decoded, len)) = encoded_word(&rest[start..]) else {
+            out.push_str(&rest[..start + 2]);
+            rest = &rest[start + 2..];
+            after_word = false;
+            continue;
+        };
+        let between = &rest[..start];
+        if !(after_word && between.chars().all(char::is_whitespace)) {
+            out.push_str(between);
+        }
+        out.push_str(&decoded);
+        rest = &rest[start + len..];
+        after_word = true;
+    }
+    out.push_str(rest);
+    Cow::Owned(out)
+}
+
+/// Decode the encoded-word at the start of `s`, returning its text and the
+/// number of bytes it spans.
+fn encoded_word(s: &str) -> Option<(String, usize)> {
+    let inner = s.strip_prefix("=?")?;
+    let (charset, inner) = inner.split_once('?')?;
+    let (kind, inner) = inner.split_once('?')?;
+    let text = &inner[..inner.find("?=")?];
+    if charset.is_empty()
+        || charset.contains(char::is_whitespace)
+        || text.contains(char::is_whitespace)
+    {
+        return None;
+    }
+
+    // RFC 2231 allows a language after the charset: "utf-8*en"
+    let label = charset.split('*').next().unwrap_or(charset);
+    let encoding = Encoding::for_label(label.as_bytes())?;
+    let bytes = match kind {
+        "B" | "b" => ENCODED_WORD_BASE64.decode(text).ok()?,
+        "Q" | "q" => decode_q(text)?,
+        _ => return None,
+    };
+    let (decoded, _) = encoding.decode_without_bom_handling(&bytes);
+    let len = s.len() - inner.len() + text.len() + 2;
+    Some((decoded.into_owned(), len))
+}
+
+/// Decode the "Q" encoding: `_` is a space and `=XX` a hex byte.
+fn decode_q(text: &str) -> Option<Vec<u8>> {
+    let mut bytes = Vec::with_capacity(text.len());
+    let mut iter = text.bytes();
+    while let Some(b) = iter.next() {
+        match b {
+            b'_' => bytes.push(b' '),
+            b'=' => {
+                let hex = [iter.next()?, iter.next()?];
+                bytes.push(u8::from_str_radix(std::str::from_utf8(&hex).ok()?, 16).ok()?);
+            }
+            b => bytes.push(b),
+        }
+    }
+    Some(bytes)
+}
+
+/// Find a header's value in a header block, unfolding continuation lines.
+/// The name is matched case-insensitively.
+pub(super) fn header_value<'a>(headers: &'a str, name: &str) -> Option<Cow<'a, str>> {
+    let mut lines = headers.lines();
+    while let Some(line) = lines.next() {
+        let Some((field, value)) = line.split_once(':') else {
+            continue;
+        };
+        if !field.eq_ignore_ascii_case(name) {
+            continue;
+        }
+
+        let mut value = Cow::Borrowed(value.trim());
+        for continuation in lines.by_ref() {
+            if !continuation.starts_with([' ', '\t']) {
+                break;
+            }
+            let folded = value.to_mut();
+            folded.push(' ');
+            folded.push_str(continuation.trim());
+        }
+        return Some(value);
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_declared_charset_parses_content_type() {
+        let headers = b"Content-Type: text/plain; format=flowed;\r\n charset=\"KOI8-R\"\r\n";
+        assert_eq!(declared_charset(headers), Some(encoding_rs::KOI8_R));
+
+        let headers = b"content-type: text/plain; CHARSET=iso-8859-1\r\n";
+        assert_eq!(declared_charset(headers), Some(encoding_rs::WINDOWS_1252));
+
+        assert_eq!(declared_charset(b"Content-Type: text/plain\r\n"), None);
+        assert_eq!(declared_charset(b"Subject: hi\r\n"), None);
+    }
+
+    #[test]
+    fn test_decode_uses_declared_charset() {
+        let koi8 = [0xF0, 0xD2, 0xC9, 0xD7, 0xC5, 0xD4];
+        assert_eq!(decode(&koi8, Some(encoding_rs::KOI8_R)), "Привет");
+    }
+
+    #[test]
+    fn test_decode_passes_utf8_through() {
+        assert_eq!(decode("café".as_bytes(), None), "café");
+    }
+
+    #[test]
+    fn test_decode_detects_undeclared_latin1() {
+        let latin1 = b"Le caf\xe9 est tr\xe8s bon, \xe0 bient\xf4t pour un d\xe9jeuner";
+        assert_eq!(
+            decode(latin1, None),
+            "Le café est très bon, à bientôt pour un déjeuner"
+        );
+    }
+
+    #[test]
+    fn test_decode_falls_back_when_declared_utf8_is_wrong() {
+        let latin1 = b"Le caf\xe9 est tr\xe8s bon, \xe0 bient\xf4t pour un d\xe9jeuner";
+        assert!(!decode(latin1, Some(encoding_rs::UTF_8)).contains(char::REPLACEMENT_CHARACTER));
+    }
+
+    #[test]
+    fn test_repair_header_replaces_lossy_values() {
+        let headers = "Subject: Caf\u{e9}\r\nFrom: a@b\r\n";
+        assert_eq!(
+            repair_header(Some("Caf\u{FFFD}".to_string()), Some(headers), "subject"),
+            Some("Caf\u{e9}".to_string())
+        );
+        assert_eq!(
+            repair_header(Some("Fine".to_string()), Some(headers), "subject"),
+            Some("Fine".to_string())
+        );
+    }
+
+    #[test]
+    fn test_repair_header_decodes_encoded_words_under_wrong_charset() {
+        // Declared UTF-8, but the raw subject is Latin-1 next to an encoded-word
+        let raw = b"Content-Type: text/plain; charset=utf-8\r\n\
+            Subject: Caf\xe9 =?ISO-8859-1?Q?cr=E8me_br=FBl=E9e?=\r\n";
+        let headers = decode_headers(raw);
+        assert_eq!(
+            repair_header(
+                Some("Caf\u{FFFD} cr\u{e8}me br\u{fb}l\u{e9}e".to_string()),
+                Some(&headers),
+                "subject"
+            ),
+            Some("Caf\u{e9} cr\u{e8}me br\u{fb}l\u{e9}e".to_string())
+        );
+    }
+
+    #[test]
+    fn test_decode_encoded_words() {
+        assert_eq!(
+            decode_encoded_words("=?UTF-8?B?0J/RgNC40LLQtdGC?= world"),
+            "\u{41f}\u{440}\u{438}\u{432}\u{435}\u{442} world"
+        );
+        // Whitespace between adjacent words is dropped
+        assert_eq!(
+            decode_encoded_words("=?utf-8?q?caf=C3=A9?= =?utf-8?q?_cr=C3=A8me?="),
+            "caf\u{e9} cr\u{e8}me"
+        );
+        // Unpadded base64 and a language suffix
+        assert_eq!(decode_encoded_words("=?utf-8*en?B?aGk?="), "hi");
+        // Anything that isn't an encoded-word is left alone
+        assert_eq!(decode_encoded_words("a =? b"), "a =? b");
+        assert_eq!(
+            decode_encoded_words("=?x-unknown?Q?hi?="),
+            "=?x-unknown?Q?hi?="
+        );
+    }
+}
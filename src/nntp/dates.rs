@@ -0,0 +1,239 @@
+//! Lenient parsing of article Date headers.
+//!
+//! Old posts (and some current software) produce Date headers that are not
+//! valid RFC 2822: asctime layout, missing weekdays or seconds, hyphenated
+//! dates, two- or three-digit years, timezone names instead of offsets, and
+//! trailing comments. Strict parsing is tried first; anything else is broken
+//! into tokens and reassembled. A missing or unknown timezone is taken as UTC.
+
+use chrono::{DateTime, FixedOffset, NaiveDate, NaiveTime, TimeZone};
+
+/// Parse a Date header, accepting common non-RFC 2822 variants.
+pub fn parse_date(date: &str) -> Option<DateTime<FixedOffset>> {
+    let date = date.trim();
+    DateTime::parse_from_rfc2822(date)
+        .or_else(|_| DateTime::parse_from_rfc3339(date))
+        .ok()
+        .or_else(|| parse_lenient(date))
+}
+
+/// Parse by classifying tokens (day, month, year, time, zone) in any order.
+fn parse_lenient(date: &str) -> Option<DateTime<FixedOffset>> {
+    // Drop comments such as "(GMT)" and treat commas as separators
+    let mut cleaned = String::with_capacity(date.len());
+    let mut depth = 0usize;
+    for c in date.chars() {
+        match c {
+            '(' => depth += 1,
+            ')' => depth = depth.saturating_sub(1),
+            _ if depth > 0 => {}
+            ',' => cleaned.push(' '),
+            _ => cleaned.push(c),
+        }
+    }
+
+    if let Some(parsed) = parse_iso_like(cleaned.trim()) {
+        return Some(parsed);
+    }
+
+    let mut day = None;
+    let mut month = None;
+    let mut year = None;
+    let mut time = None;
+    let mut offset = None;
+
+    // "2-Jan-2006" splits into parts; signed tokens are zone offsets
+    let tokens = cleaned.split_whitespace().flat_map(|token| {
+        if token.starts_with(['+', '-']) {
+            vec![token]
+        } else {
+            token.split(['-', '/']).filter(|t| !t.is_empty()).collect()
+        }
+    });
+
+    for token in tokens {
+        if token.starts_with(['+', '-']) {
+            if time.is_some() && offset.is_none() {
+                offset = parse_numeric_offset(token);
+            }
+        } else if token.contains(':') {
+            time = time.or_else(|| parse_time(token));
+        } else if token.chars().all(|c| c.is_ascii_digit()) {
+            let value: u32 = token.parse().ok()?;
+            if token.len() >= 3 || value > 31 || day.is_some() {
+                year = year.or(Some(expand_year(value, token.len())));
+            } else {
+                day = Some(value);
+            }
+        } else if let Some(number) = month_number(token) {
+            month = month.or(Some(number));
+        } else if time.is_some() && offset.is_none() {
+            offset = Some(zone_offset(token));
+        }
+        // Anything else (weekday names, stray words) is ignored
+    }
+
+    let date = NaiveDate::from_ymd_opt(year?, month?, day?)?;
+    let time = time.unwrap_or(NaiveTime::MIN);
+    let offset = FixedOffset::east_opt(offset.unwrap_or(0))?;
+    offset.from_local_datetime(&date.and_time(time)).single()
+}
+
+/// Parse "2006-01-02 15:04:05 +0000" and "2006-01-02 15:04:05" (assumed UTC).
+fn parse_iso_like(date: &str) -> Option<DateTime<FixedOffset>> {
+    DateTime::parse_from_str(date, "%Y-%m-%d %H:%M:%S %z")
+        .ok()
+        .or_else(|| {
+            chrono::NaiveDateTime::parse_from_str(date, "%Y-%m-%d %H:%M:%S")
+                .ok()
+                .map(|naive| naive.and_utc().fixed_offset())
+        })
+}
+
+/// Parse "15:04:05" or "15:04"
+fn parse_time(token: &str) -> Option<NaiveTime> {
+    let mut parts = token.split(':').map(|p| p.parse::<u32>().ok());
+    let hour = parts.next()??;
+    let minute = parts.next()??;
+    let second = parts.next().unwrap_or(Some(0))?;
+    NaiveTime::from_hms_opt(hour, minute, second.min(59))
+}
+
+/// Parse "+0100", "+01:00", "+01" or "-5" into seconds east of UTC.
+fn parse_numeric_offset(token: &str) -> Option<i32> {
+    let sign = if token.starts_with('-') { -1 } else { 1 };
+    let digits: String = token[1..].chars().filter(|c| *c != ':').collect();
+    if digits.is_empty() || !digits.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    let (hours, minutes) = match digits.len() {
+        1 | 2 => (digits.parse::<i32>().ok()?, 0),
+        3 | 4 => {
+            let split = digits.len() - 2;
+            (
+                digits[..split].parse::<i32>().ok()?,
+                digits[split..].parse::<i32>().ok()?,
+            )
+        }
+        _ => return None,
+    };
+    if hours > 23 || minutes > 59 {
+        return None;
+    }
+    Some(sign * (hours * 3600 + minutes * 60))
+}
+
+/// Map obsolete two- and three-digit years as RFC 2822 section 4.3 does.
+fn expand_year(value: u32, digits: usize) -> i32 {
+    let value = value as i32;
+    match digits {
+        1 | 2 if value < 50 => 2000 + value,
+        1..=3 => 1900 + value,
+        _ => value,
+    }
+}
+
+/// Month number from an English month name or abbreviation
+fn month_number(token: &str) -> Option<u32> {
+    const MONTHS: [&str; 12] = [
+        "jan", "feb", "mar", "apr", "may", "jun", "jul", "aug", "sep", "oct", "nov", "dec",
+    ];
+    if token.len() < 3 || !token.is_char_boundary(3) {
+        return None;
+    }
+    let prefix = token[..3].to_ascii_lowercase();
+    MONTHS
+        .iter()
+        .position(|m| *m == prefix)
+        .map(|i| i as u32 + 1)
+}
+
+/// Offset in seconds for common timezone names; unknown names are UTC.
+fn zone_offset(token: &str) -> i32 {
+    let hours = match token.to_ascii_uppercase().as_str() {
+        "UT" | "UTC" | "GMT" | "Z" | "WET" => 0,
+        "BST" | "CET" | "MET" | "MEZ" | "WEST" => 1,
+        "CEST" | "MEST" | "MESZ" | "EET" => 2,
+        "EEST" | "MSK" => 3,
+        "JST" | "KST" => 9,
+        "AEST" => 10,
+        "AEDT" => 11,
+        "NZST" => 12,
+        "NZDT" => 13,
+        "EDT" => -4,
+        "EST" | "CDT" => -5,
+        "CST" | "MDT" => -6,
+        "MST" | "PDT" => -7,
+        "PST" | "AKDT" => -8,
+        "AKST" => -9,
+        "HST" => -10,
+        _ => 0,
+    };
+    hours * 3600
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn utc(date: &str) -> String {
+        parse_date(date)
+            .unwrap_or_else(|| panic!("failed to parse {:?}", date))
+            .naive_utc()
+            .to_string()
+    }
+
+    #[test]
+    fn test_parse_date_rfc2822() {
+        assert_eq!(
+            utc("Mon, 02 Jan 2006 15:04:05 -0700"),
+            "2006-01-02 22:04:05"
+        );
+        assert_eq!(utc("2 Jan 2006 15:04:05 +0000"), "2006-01-02 15:04:05");
+    }
+
+    #[test]
+    fn test_parse_date_real_world_corpus() {
+        let corpus = [
+            // Zone names, with and without comments
+            ("Mon, 2 Jan 2006 15:04:05 GMT", "2006-01-02 15:04:05"),
+            (
+                "Mon, 2 Jan 2006 15:04:05 +0000 (GMT)",
+                "2006-01-02 15:04:05",
+            ),
+            ("Mon, 2 Jan 2006 10:04:05 EST", "2006-01-02 15:04:05"),
+            ("Mon, 2 Jan 2006 16:04:05 MET DST", "2006-01-02 15:04:05"),
+            ("2 Jan 2006 17:04:05 CEST", "2006-01-02 15:04:05"),
+            // Sloppy offsets and missing zones
+            ("Mon, 2 Jan 2006 16:04:05 +01:00", "2006-01-02 15:04:05"),
+            ("Mon, 2 Jan 2006 16:04:05 +1", "2006-01-02 15:04:05"),
+            ("Mon, 2 Jan 2006 15:04:05", "2006-01-02 15:04:05"),
+            ("Mon, 2 Jan 2006 15:04:05 XYZ", "2006-01-02 15:04:05"),
+            // Missing seconds, full names, odd separators
+            ("Mon, 2 Jan 2006 15:04 GMT", "2006-01-02 15:04:00"),
+            ("Monday, 2 January 2006 15:04:05 GMT", "2006-01-02 15:04:05"),
+            ("Mon,2 Jan 2006 15:04:05 GMT", "2006-01-02 15:04:05"),
+            ("2-Jan-2006 15:04:05 GMT", "2006-01-02 15:04:05"),
+            // asctime and ctime layouts
+            ("Mon Jan  2 15:04:05 2006", "2006-01-02 15:04:05"),
+            ("Mon Jan 2 15:04:05 GMT 2006", "2006-01-02 15:04:05"),
+            // Obsolete years
+            ("2 Jan 95 15:04:05 GMT", "1995-01-02 15:04:05"),
+            ("2 Jan 06 15:04:05 GMT", "2006-01-02 15:04:05"),
+            ("2 Jan 100 15:04:05 GMT", "2000-01-02 15:04:05"),
+            // ISO-ish
+            ("2006-01-02 15:04:05", "2006-01-02 15:04:05"),
+            ("2006-01-02T15:04:05Z", "2006-01-02 15:04:05"),
+        ];
+        for (input, expected) in corpus {
+            assert_eq!(utc(input), expected, "parsing {:?}", input);
+        }
+    }
+
+    #[test]
+    fn test_parse_date_rejects_garbage() {
+        assert_eq!(parse_date(""), None);
+        assert_eq!(parse_date("yesterday"), None);
+        assert_eq!(parse_date("31 Feb 2006 10:00:00 GMT"), None);
+    }
+}
@@ -0,0 +1,237 @@
+//! TCP connection establishment with Happy Eyeballs (RFC 8305).
+//!
+//! A server name often resolves to several IPv6 and IPv4 addresses, and any
+//! of them may be unreachable. Instead of connecting to the first address
+//! the resolver returns, every address is tried: they are interleaved by
+//! family starting with IPv6, a new attempt starts when the previous one
+//! fails or has had `NNTP_CONNECTION_ATTEMPT_DELAY_MS` to itself, and the
+//! first connection to succeed wins. Each attempt has its own timeout, so a
+//! blackholed address can't use up the worker's whole connect timeout.
+//! Host names are resolved through the cache in [`super::dns`].
+
+use std::io;
+use std::net::SocketAddr;
+use std::time::Duration;
+
+use futures::stream::{FuturesUnordered, StreamExt};
+use tokio::net::TcpStream;
+use tokio::time::{sleep, timeout};
+
+use crate::config::{NNTP_CONNECTION_ATTEMPT_DELAY_MS, NNTP_CONNECTION_ATTEMPT_TIMEOUT_SECS};
+
+use super::dns;
+
+/// Resolve `addr` (`host:port`, IPv6 literals in brackets) and connect to the
+/// first address that answers
+pub async fn connect(addr: &str) -> io::Result<TcpStream> {
+    let (host, port) = split_host_port(addr)?;
+    let addrs = sort_addresses(dns::resolve(host, port).await?);
+    let result = connect_any(
+        addrs,
+        Duration::from_millis(NNTP_CONNECTION_ATTEMPT_DELAY_MS),
+        Duration::from_secs(NNTP_CONNECTION_ATTEMPT_TIMEOUT_SECS),
+    )
+    .await;
+    if result.is_err() {
+        // The provider may have moved; look the name up again next time
+        dns::invalidate(host);
+    }
+    result
+}
+
+/// Split `host:port`, removing brackets from IPv6 literals
+pub fn split_host_port(addr: &str) -> io::Result<(&str, u16)> {
+    addr.rsplit_once(':')
+        .and_then(|(host, port)| {
+            let host = host.trim_start_matches('[').trim_end_matches(']');
+            Some((host, port.parse().ok()?))
+        })
+        .ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("invalid address {}", addr),
+            )
+        })
+}
+
+/// Order addresses for connection attempts: duplicates removed, then
+/// alternating between families starting with IPv6, keeping the resolver's
+/// order within each family (RFC 8305 section 4)
+fn sort_addresses(addrs: Vec<SocketAddr>) -> Vec<SocketAddr> {
+    let mut v6 = Vec::new();
+    let mut v4 = Vec::new();
+    for addr in addrs {
+        let family = if addr.is_ipv6() { &mut v6 } else { &mut v4 };
+        if !family.contains(&addr) {
+            family.push(addr);
+        }
+    }
+
+    let mut sorted = Vec::with_capacity(v6.len() + v4.len());
+    let mut v6 = v6.into_iter();
+    let mut v4 = v4.into_iter();
+    loop {
+        match (v6.next(), v4.next()) {
+            (None, None) => break,
+            (first, second) => sorted.extend(first.into_iter().chain(second)),
+        }
+    }
+    sorted
+}
+
+/// What woke the attempt loop
+enum Event {
+    Finished(SocketAddr, io::Result<TcpStream>),
+    DelayElapsed,
+}
+
+/// Race staggered connection attempts to `addrs` in order, returning the
+/// first stream to connect. Attempts still in flight are dropped, which
+/// closes their sockets.
+async fn connect_any(
+    addrs: Vec<SocketAddr>,
+    attempt_delay: Duration,
+    attempt_timeout: Duration,
+) -> io::Result<TcpStream> {
+    let attempt = |addr: SocketAddr| async move {
+        let result = match timeout(attempt_timeout, TcpStream::connect(addr)).await {
+            Ok(result) => result,
+            Err(_) => Err(io::Error::new(
+                io::ErrorKind::TimedOut,
+                format!("connection to {} timed out", addr),
+            )),
+        };
+        (addr, result)
+    };
+
+    let mut remaining = addrs.into_iter();
+    let mut attempts = FuturesUnordered::new();
+    let mut last_error = None;
+
+    loop {
+        if attempts.is_empty() {
+            match remaining.next() {
+                Some(addr) => attempts.push(attempt(addr)),
+                None => break,
+            }
+        }
+
+        let event = tokio::select! {
+            Some((addr, result)) = attempts.next() => Event::Finished(addr, result),
+            _ = sleep(attempt_delay), if !remaining.as_slice().is_empty() => Event::DelayElapsed,
+        };
+
+        match event {
+            Event::Finished(addr, Ok(stream)) => {
+                tracing::debug!(%addr, "Connected");
+                return Ok(stream);
+            }
+            Event::Finished(addr, Err(e)) => {
+                tracing::debug!(%addr, error = %e, "Connection attempt failed");
+                last_error = Some(e);
+                // A failure starts the next attempt straight away
+                if let Some(next) = remaining.next() {
+                    attempts.push(attempt(next));
+                }
+            }
+            Event::DelayElapsed => {
+                if let Some(next) = remaining.next() {
+                    attempts.push(attempt(next));
+                }
+            }
+        }
+    }
+
+    Err(last_error.unwrap_or_else(|| {
+        io::Error::new(io::ErrorKind::NotFound, "host resolved to no addresses")
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::net::TcpListener;
+
+    fn addr(s: &str) -> SocketAddr {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn test_split_host_port() {
+        assert_eq!(
+            split_host_port("news.example.com:119").unwrap(),
+            ("news.example.com", 119)
+        );
+        assert_eq!(
+            split_host_port("[2001:db8::1]:563").unwrap(),
+            ("2001:db8::1", 563)
+        );
+        assert!(split_host_port("news.example.com").is_err());
+    }
+
+    #[test]
+    fn test_sort_addresses_interleaves_families_v6_first() {
+        let sorted = sort_addresses(vec![
+            addr("192.0.2.1:119"),
+            addr("192.0.2.2:119"),
+            addr("192.0.2.3:119"),
+            addr("[2001:db8::1]:119"),
+            addr("192.0.2.1:119"),
+            addr("[2001:db8::2]:119"),
+        ]);
+        assert_eq!(
+            sorted,
+            vec![
+                addr("[2001:db8::1]:119"),
+                addr("192.0.2.1:119"),
+                addr("[2001:db8::2]:119"),
+                addr("192.0.2.2:119"),
+                addr("192.0.2.3:119"),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_connect_any_falls_back_past_refused_address() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let live = listener.local_addr().unwrap();
+        // Bound then dropped, so nothing listens there
+        let dead = TcpListener::bind("127.0.0.1:0")
+            .await
+            .unwrap()
+            .local_addr()
+            .unwrap();
+
+        let stream = connect_any(
+            vec![dead, live],
+            Duration::from_secs(60),
+            Duration::from_secs(5),
+        )
+        .await
+        .unwrap();
+        assert_eq!(stream.peer_addr().unwrap(), live);
+    }
+
+    #[tokio::test]
+    async fn test_connect_any_reports_last_error() {
+        let dead = TcpListener::bind("127.0.0.1:0")
+            .await
+            .unwrap()
+            .local_addr()
+            .unwrap();
+        assert!(connect_any(
+            vec![dead],
+            Duration::from_millis(10),
+            Duration::from_secs(5)
+        )
+        .await
+        .is_err());
+        assert_eq!(
+            connect_any(Vec::new(), Duration::ZERO, Duration::ZERO)
+                .await
+                .unwrap_err()
+                .kind(),
+            io::ErrorKind::NotFound
+        );
+    }
+}
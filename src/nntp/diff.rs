@@ -0,0 +1,256 @@
+//! Unified diffs in article bodies.
+//!
+//! Patches posted to source groups are usually a commit message, a diff,
+//! and perhaps a signature. [`find_diff`] locates the diff: file headers
+//! ("diff --git", "Index:", then "---" and "+++") followed by hunks, whose
+//! `@@` line counts say exactly how many lines belong to them. Counting
+//! rather than matching line prefixes keeps a removed "-- comment" line
+//! from being taken for a file header, and ends the diff where its last
+//! hunk does, before any signature or trailer.
+//!
+//! Pages in groups matching `[body] diff_groups` render the diff's passages
+//! with per-line highlighting, and `/a/{message_id}/patch` serves the diff
+//! on its own.
+
+use std::ops::Range;
+
+use serde::Serialize;
+
+/// Lines git and Subversion put between a file's first header line and
+/// its "---" line
+const EXTENDED_HEADERS: [&str; 11] = [
+    "index ",
+    "old mode ",
+    "new mode ",
+    "new file mode ",
+    "deleted file mode ",
+    "similarity index ",
+    "dissimilarity index ",
+    "rename from ",
+    "rename to ",
+    "copy from ",
+    "copy to ",
+];
+
+/// What a line of a diff is, for highlighting
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DiffLineKind {
+    /// A file header: "diff --git", "Index:", "---", "+++" and the like
+    File,
+    /// A hunk header ("@@ -1,4 +1,5 @@")
+    Hunk,
+    Add,
+    Remove,
+    Context,
+}
+
+/// One line of a diff with its kind
+#[derive(Debug, PartialEq, Serialize)]
+pub struct DiffLine<'a> {
+    pub kind: DiffLineKind,
+    pub text: &'a str,
+}
+
+/// Byte range of the first unified diff in `body`, through the end of the
+/// last hunk of the files that follow it without a break
+pub fn find_diff(body: &str) -> Option<Range<usize>> {
+    let mut lines = Vec::new();
+    let mut offset = 0;
+    for raw in body.split_inclusive('\n') {
+        lines.push((offset, raw.trim_end_matches(['\r', '\n'])));
+        offset += raw.len();
+    }
+
+    let mut diff: Option<Range<usize>> = None;
+    let mut i = 0;
+    while i < lines.len() {
+        let hunks = file_header(&lines, i).and_then(|first| hunks_end(&lines, first));
+        match hunks {
+            Some(end) => {
+                let end_offset = lines.get(end).map_or(body.len(), |(at, _)| *at);
+                let start = diff.as_ref().map_or(lines[i].0, |diff| diff.start);
+                diff = Some(start..end_offset);
+                i = end;
+            }
+            None if diff.is_some() => break,
+            None => i += 1,
+        }
+    }
+    diff
+}
+
+/// The diff portion of `body`, as a patch file would hold it
+pub fn extract_diff(body: &str) -> Option<&str> {
+    find_diff(body).map(|range| &body[range])
+}
+
+/// Classify each line of diff text. Without hunk counts to go by, a "---"
+/// line is a file header only when a "+++" line follows it.
+pub fn diff_lines(text: &str) -> Vec<DiffLine<'_>> {
+    let lines: Vec<&str> = text.lines().collect();
+    lines
+        .iter()
+        .enumerate()
+        .map(|(i, &line)| {
+            let next = lines.get(i + 1).copied().unwrap_or_default();
+            let kind = if line.starts_with("@@ ") {
+                DiffLineKind::Hunk
+            } else if line.starts_with("+++ ")
+                || (line.starts_with("--- ") && next.starts_with("+++ "))
+                || is_file_start(line)
+                || EXTENDED_HEADERS.iter().any(|h| line.starts_with(h))
+            {
+                DiffLineKind::File
+            } else if line.starts_with('+') {
+                DiffLineKind::Add
+            } else if line.starts_with('-') {
+                DiffLineKind::Remove
+            } else {
+                DiffLineKind::Context
+            };
+            DiffLine { kind, text: line }
+        })
+        .collect()
+}
+
+fn is_file_start(line: &str) -> bool {
+    line.starts_with("diff ") || line.starts_with("Index: ")
+}
+
+/// If a file's headers start at line `i`, the index of its first hunk line
+fn file_header(lines: &[(usize, &str)], i: usize) -> Option<usize> {
+    let text = |j: usize| lines.get(j).map_or("", |(_, line)| *line);
+    let mut j = i;
+    if is_file_start(text(j)) {
+        j += 1;
+        while EXTENDED_HEADERS.iter().any(|h| text(j).starts_with(h)) || text(j).starts_with("===")
+        {
+            j += 1;
+        }
+    }
+    (text(j).starts_with("--- ")
+        && text(j + 1).starts_with("+++ ")
+        && hunk_counts(text(j + 2)).is_some())
+    .then_some(j + 2)
+}
+
+/// Index of the line after the hunks starting at line `i`, if at least one
+/// is complete
+fn hunks_end(lines: &[(usize, &str)], mut i: usize) -> Option<usize> {
+    let mut end = None;
+    while let Some((mut old, mut new)) = lines.get(i).and_then(|(_, line)| hunk_counts(line)) {
+        let mut j = i + 1;
+        while old > 0 || new > 0 {
+            let Some((_, line)) = lines.get(j) else {
+                return end;
+            };
+            let counts = match line.bytes().next() {
+                // Mail often strips the space from blank context lines
+                Some(b' ') | None => old.checked_sub(1).zip(new.checked_sub(1)),
+                Some(b'-') => old.checked_sub(1).map(|old| (old, new)),
+                Some(b'+') => new.checked_sub(1).map(|new| (old, new)),
+                Some(b'\\') => Some((old, new)),
+                _ => None,
+            };
+            let Some(counts) = counts else {
+                return end;
+            };
+            (old, new) = counts;
+            j += 1;
+        }
+        if lines.get(j).is_some_and(|(_, line)| line.starts_with('\\')) {
+            j += 1;
+        }
+        end = Some(j);
+        i = j;
+    }
+    end
+}
+
+/// Old and new line counts from a hunk header ("@@ -12,7 +12,8 @@ fn x")
+fn hunk_counts(line: &str) -> Option<(usize, usize)> {
+    let (ranges, _) = line.strip_prefix("@@ -")?.split_once(" @@")?;
+    let (old, new) = ranges.split_once(" +")?;
+    Some((range_len(old)?, range_len(new)?))
+}
+
+/// Length of a hunk range ("12,7"), which is 1 when left out ("12")
+fn range_len(range: &str) -> Option<usize> {
+    match range.split_once(',') {
+        Some((start, len)) => {
+            start.parse::<usize>().ok()?;
+            len.parse().ok()
+        }
+        None => range.parse::<usize>().ok().map(|_| 1),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PATCH: &str = concat!(
+        "Reword the comment.\n",
+        "\n",
+        "---\n",
+        " q.sql | 2 +-\n",
+        "\n",
+        "diff --git a/q.sql b/q.sql\n",
+        "index 1234567..89abcde 100644\n",
+        "--- a/q.sql\n",
+        "+++ b/q.sql\n",
+        "@@ -1,3 +1,3 @@\n",
+        " SELECT 1;\n",
+        "--- old comment\n",
+        "+-- new comment\n",
+        "\n",
+        "-- \n",
+        "2.43.0\n",
+    );
+
+    #[test]
+    fn test_find_diff_stops_at_last_hunk() {
+        let diff = extract_diff(PATCH).unwrap();
+        assert!(diff.starts_with("diff --git"));
+        // The removed "-- old comment" is counted, not taken for a header,
+        // and the signature after the hunk is left out
+        assert!(diff.ends_with("+-- new comment\n\n"));
+        assert!(!diff.contains("2.43.0"));
+        assert_eq!(find_diff("No patch here.\n--- \n+++ \nJust dashes\n"), None);
+    }
+
+    #[test]
+    fn test_find_diff_without_git_headers() {
+        let body = "Try this:\n\n--- old.c\n+++ new.c\n@@ -3 +3,2 @@\n-a\n+b\n+c\n\
+            @@ -9 +10 @@\n-x\n+y\n\\ No newline at end of file\nThanks";
+        let diff = extract_diff(body).unwrap();
+        assert!(diff.starts_with("--- old.c"));
+        assert!(diff.ends_with("\\ No newline at end of file\n"));
+        // A hunk that runs out before its counts do isn't a diff
+        assert_eq!(
+            find_diff("--- a\n+++ b\n@@ -1,3 +1,3 @@\n a\nnot diff\n"),
+            None
+        );
+    }
+
+    #[test]
+    fn test_diff_lines() {
+        let kinds: Vec<DiffLineKind> =
+            diff_lines("--- a/x\n+++ b/x\n@@ -1 +1 @@\n-old\n+new\n same")
+                .into_iter()
+                .map(|line| line.kind)
+                .collect();
+        assert_eq!(
+            kinds,
+            [
+                DiffLineKind::File,
+                DiffLineKind::File,
+                DiffLineKind::Hunk,
+                DiffLineKind::Remove,
+                DiffLineKind::Add,
+                DiffLineKind::Context,
+            ]
+        );
+    }
+}
@@ -0,0 +1,253 @@
+//! Cached DNS resolution for upstream servers.
+//!
+//! Server host names are resolved with hickory-resolver and the answer is
+//! kept for its record TTL, clamped to `DNS_MIN_TTL_SECS..=DNS_MAX_TTL_SECS`.
+//! Workers resolve again on every reconnect, so once the TTL has run out a
+//! changed record (DNS-based failover at the provider) takes effect without a
+//! restart. When none of the cached addresses accept a connection the entry is
+//! expired early, and the next connect asks the resolver instead of retrying
+//! the same addresses until the TTL ends. If the resolver fails, the last
+//! known answer is used for up to `DNS_STALE_SECS` past its expiry.
+
+use std::collections::HashMap;
+use std::io;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+use hickory_resolver::TokioAsyncResolver;
+
+use crate::config::{DNS_FALLBACK_TTL_SECS, DNS_MAX_TTL_SECS, DNS_MIN_TTL_SECS, DNS_STALE_SECS};
+
+static CACHE: OnceLock<DnsCache> = OnceLock::new();
+
+fn cache() -> &'static DnsCache {
+    CACHE.get_or_init(DnsCache::new)
+}
+
+/// Socket addresses for `host`, from the cache while its TTL lasts.
+/// IP literals are returned as they are.
+pub async fn resolve(host: &str, port: u16) -> io::Result<Vec<SocketAddr>> {
+    if let Ok(ip) = host.parse::<IpAddr>() {
+        return Ok(vec![SocketAddr::new(ip, port)]);
+    }
+    let ips = cache().resolve(host).await?;
+    Ok(ips
+        .into_iter()
+        .map(|ip| SocketAddr::new(ip, port))
+        .collect())
+}
+
+/// Expire the cached answer for `host`, e.g. after none of its addresses
+/// accepted a connection. It stays available as a stale fallback.
+pub fn invalidate(host: &str) {
+    cache().invalidate(host, Instant::now());
+}
+
+struct Entry {
+    addrs: Vec<IpAddr>,
+    expires: Instant,
+}
+
+/// State of a host in the cache at some instant
+#[derive(Debug, PartialEq)]
+enum Cached {
+    Fresh(Vec<IpAddr>),
+    /// Expired, but usable if the resolver fails
+    Stale(Vec<IpAddr>),
+    Missing,
+}
+
+struct DnsCache {
+    /// None when the system DNS configuration can't be read; lookups then go
+    /// through the operating system resolver, which reports no TTLs
+    resolver: Option<TokioAsyncResolver>,
+    entries: Mutex<HashMap<String, Entry>>,
+}
+
+impl DnsCache {
+    fn new() -> Self {
+        let resolver = match TokioAsyncResolver::tokio_from_system_conf() {
+            Ok(resolver) => Some(resolver),
+            Err(e) => {
+                tracing::warn!(
+                    error = %e,
+                    "Could not read system DNS configuration, caching lookups for {}s",
+                    DNS_FALLBACK_TTL_SECS
+                );
+                None
+            }
+        };
+        Self {
+            resolver,
+            entries: Mutex::default(),
+        }
+    }
+
+    async fn resolve(&self, host: &str) -> io::Result<Vec<IpAddr>> {
+        let stale = match self.cached(host, Instant::now()) {
+            Cached::Fresh(addrs) => return Ok(addrs),
+            Cached::Stale(addrs) => Some(addrs),
+            Cached::Missing => None,
+        };
+
+        match self.lookup(host).await {
+            Ok((addrs, ttl)) => {
+                tracing::debug!(host, ?addrs, ttl_secs = ttl.as_secs(), "Resolved server");
+                self.insert(host, addrs.clone(), ttl, Instant::now());
+                Ok(addrs)
+            }
+            Err(e) => match stale {
+                Some(addrs) => {
+                    tracing::warn!(
+                        host,
+                        error = %e,
+                        "DNS lookup failed, using last known addresses"
+                    );
+                    Ok(addrs)
+                }
+                None => Err(e),
+            },
+        }
+    }
+
+    /// Ask the resolver, returning the addresses and how long to keep them
+    async fn lookup(&self, host: &str) -> io::Result<(Vec<IpAddr>, Duration)> {
+        let (addrs, ttl): (Vec<IpAddr>, Duration) = match &self.resolver {
+            Some(resolver) => {
+                let lookup = resolver.lookup_ip(host).await.map_err(io::Error::other)?;
+                let ttl = lookup
+                    .valid_until()
+                    .saturating_duration_since(Instant::now());
+                (lookup.iter().collect(), ttl)
+            }
+            None => {
+                let addrs = tokio::net::lookup_host((host, 0)).await?;
+                (
+                    addrs.map(|addr| addr.ip()).collect(),
+                    Duration::from_secs(DNS_FALLBACK_TTL_SECS),
+                )
+            }
+        };
+        if addrs.is_empty() {
+            return Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("{} has no addresses", host),
+            ));
+        }
+        Ok((addrs, clamp_ttl(ttl)))
+    }
+
+    fn cached(&self, host: &str, now: Instant) -> Cached {
+        let entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        match entries.get(host) {
+            Some(entry) if now < entry.expires => Cached::Fresh(entry.addrs.clone()),
+            Some(entry) if now < entry.expires + Duration::from_secs(DNS_STALE_SECS) => {
+                Cached::Stale(entry.addrs.clone())
+            }
+            _ => Cached::Missing,
+        }
+    }
+
+    fn insert(&self, host: &str, addrs: Vec<IpAddr>, ttl: Duration, now: Instant) {
+        let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        entries.insert(
+            host.to_string(),
+            Entry {
+                addrs,
+                expires: now + ttl,
+            },
+        );
+    }
+
+    fn invalidate(&self, host: &str, now: Instant) {
+        let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(entry) = entries.get_mut(host) {
+            entry.expires = entry.expires.min(now);
+        }
+        drop(entries);
+        // The resolver keeps its own TTL cache, which would hand back the same answer
+        if let Some(resolver) = &self.resolver {
+            resolver.clear_cache();
+        }
+    }
+}
+
+fn clamp_ttl(ttl: Duration) -> Duration {
+    ttl.clamp(
+        Duration::from_secs(DNS_MIN_TTL_SECS),
+        Duration::from_secs(DNS_MAX_TTL_SECS),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cache_without_resolver() -> DnsCache {
+        DnsCache {
+            resolver: None,
+            entries: Mutex::default(),
+        }
+    }
+
+    #[test]
+    fn test_cache_entry_goes_stale_then_missing() {
+        let cache = cache_without_resolver();
+        let addrs: Vec<IpAddr> = vec!["192.0.2.1".parse().unwrap()];
+        let now = Instant::now();
+        let ttl = Duration::from_secs(60);
+        cache.insert("news.example.com", addrs.clone(), ttl, now);
+
+        assert_eq!(
+            cache.cached("news.example.com", now),
+            Cached::Fresh(addrs.clone())
+        );
+        assert_eq!(
+            cache.cached("news.example.com", now + ttl),
+            Cached::Stale(addrs.clone())
+        );
+        assert_eq!(
+            cache.cached(
+                "news.example.com",
+                now + ttl + Duration::from_secs(DNS_STALE_SECS)
+            ),
+            Cached::Missing
+        );
+        assert_eq!(cache.cached("other.example.com", now), Cached::Missing);
+    }
+
+    #[test]
+    fn test_invalidate_keeps_stale_fallback() {
+        let cache = cache_without_resolver();
+        let addrs: Vec<IpAddr> = vec!["2001:db8::1".parse().unwrap()];
+        let now = Instant::now();
+        cache.insert(
+            "news.example.com",
+            addrs.clone(),
+            Duration::from_secs(300),
+            now,
+        );
+
+        cache.invalidate("news.example.com", now);
+        assert_eq!(cache.cached("news.example.com", now), Cached::Stale(addrs));
+    }
+
+    #[test]
+    fn test_clamp_ttl() {
+        assert_eq!(clamp_ttl(Duration::ZERO).as_secs(), DNS_MIN_TTL_SECS);
+        assert_eq!(
+            clamp_ttl(Duration::from_secs(86400)).as_secs(),
+            DNS_MAX_TTL_SECS
+        );
+        assert_eq!(clamp_ttl(Duration::from_secs(120)).as_secs(), 120);
+    }
+
+    #[tokio::test]
+    async fn test_resolve_passes_ip_literals_through() {
+        assert_eq!(
+            resolve("2001:db8::1", 563).await.unwrap(),
+            vec!["[2001:db8::1]:563".parse::<SocketAddr>().unwrap()]
+        );
+    }
+}
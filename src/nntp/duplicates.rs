@@ -0,0 +1,290 @@
+//! Near-duplicate thread detection for link-spam.
+//!
+//! Spam runs often post the same body as separate threads in many groups.
+//! Each thread root's body is reduced to a 64-bit simhash over word shingles;
+//! roots whose fingerprints are within a configured Hamming distance of an
+//! earlier root (in any group) are reported as duplicates of it.
+//!
+//! Fingerprints are learned as root bodies pass through the caches (thread
+//! views, article views, and posts made through September), so detection
+//! costs no extra NNTP traffic.
+
+use std::collections::HashMap;
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::time::Duration;
+
+use chrono::{DateTime, FixedOffset};
+use moka::future::Cache;
+
+use super::{parse_date, ArticleView};
+use crate::config::{
+    SpamConfig, DUPLICATE_MAX_FINGERPRINTS, DUPLICATE_MIN_WORDS, DUPLICATE_SHINGLE_WORDS,
+};
+
+/// Fingerprint of a thread root body
+#[derive(Clone)]
+struct RootFingerprint {
+    hash: u64,
+    /// Posting date, used to decide which of two duplicates came first
+    posted: Option<DateTime<FixedOffset>>,
+}
+
+/// Tracks root fingerprints within a time window and the duplicates found among them.
+#[derive(Clone)]
+pub struct DuplicateDetector {
+    /// Maximum Hamming distance for a match (0 = disabled)
+    max_distance: u32,
+    /// Root fingerprints (key: root message_id)
+    fingerprints: Cache<String, RootFingerprint>,
+    /// Duplicate roots (key: duplicate root message_id, value: original root message_id)
+    duplicates: Cache<String, String>,
+}
+
+impl DuplicateDetector {
+    /// Create a detector from spam filtering configuration
+    pub fn new(config: &SpamConfig) -> Self {
+        let window = Duration::from_secs(config.duplicate_window_hours * 3600);
+        Self {
+            max_distance: config.duplicate_distance,
+            fingerprints: Cache::builder()
+                .max_capacity(DUPLICATE_MAX_FINGERPRINTS)
+                .time_to_live(window)
+                .build(),
+            duplicates: Cache::builder()
+                .max_capacity(DUPLICATE_MAX_FINGERPRINTS)
+                .time_to_live(window)
+                .build(),
+        }
+    }
+
+    /// Fingerprint a thread root and compare it with roots seen in the window.
+    ///
+    /// Callers must only pass thread roots. Articles without a body, or with
+    /// too few words to compare reliably, are ignored.
+    pub async fn observe_root(&self, article: &ArticleView) {
+        if self.max_distance == 0 || self.fingerprints.contains_key(&article.message_id) {
+            return;
+        }
+        let Some(hash) = article.body.as_deref().and_then(simhash) else {
+            return;
+        };
+        let fingerprint = RootFingerprint {
+            hash,
+            posted: parse_date(&article.date),
+        };
+
+        // Closest earlier match wins; ties go to the oldest post
+        let matched = self
+            .fingerprints
+            .iter()
+            .filter(|(_, other)| hamming_distance(hash, other.hash) <= self.max_distance)
+            .min_by_key(|(_, other)| (hamming_distance(hash, other.hash), other.posted))
+            .map(|(message_id, other)| (message_id.to_string(), other.posted));
+
+        self.fingerprints
+            .insert(article.message_id.clone(), fingerprint.clone())
+            .await;
+
+        if let Some((other_id, other_posted)) = matched {
+            // Whichever was posted later is the duplicate
+            let (duplicate, original) = match (fingerprint.posted, other_posted) {
+                (Some(posted), Some(other)) if posted < other => {
+                    (other_id, article.message_id.clone())
+                }
+                _ => (article.message_id.clone(), other_id),
+            };
+            tracing::debug!(%duplicate, %original, "Detected near-duplicate thread root");
+            self.duplicates.insert(duplicate, original).await;
+        }
+    }
+
+    /// Drop a root's fingerprint and any duplicate pairs it is part of, for
+    /// posts that never reached the server.
+    pub async fn forget_root(&self, message_id: &str) {
+        self.fingerprints.invalidate(message_id).await;
+        self.duplicates.invalidate(message_id).await;
+        let copies: Vec<String> = self
+            .duplicates
+            .iter()
+            .filter(|(_, original)| original == message_id)
+            .map(|(duplicate, _)| duplicate.to_string())
+            .collect();
+        for duplicate in copies {
+            self.duplicates.invalidate(&duplicate).await;
+        }
+    }
+
+    /// Look up which of the given roots are duplicates.
+    /// Returns a map of duplicate root message_id -> original root message_id.
+    pub async fn duplicates_of<'a>(
+        &self,
+        root_message_ids: impl IntoIterator<Item = &'a str>,
+    ) -> HashMap<String, String> {
+        let mut found = HashMap::new();
+        if self.max_distance == 0 {
+            return found;
+        }
+        for message_id in root_message_ids {
+            if let Some(original) = self.duplicates.get(message_id).await {
+                found.insert(message_id.to_string(), original);
+            }
+        }
+        found
+    }
+}
+
+/// Compute a 64-bit simhash of a body over lowercase word shingles.
+///
+/// Quoted lines and the signature are skipped so replies quoting spam and
+/// shared signatures don't make unrelated posts look alike. Returns None when
+/// the body has fewer than DUPLICATE_MIN_WORDS words.
+pub fn simhash(body: &str) -> Option<u64> {
+    let words: Vec<String> = body
+        .lines()
+        .take_while(|line| *line != "-- ")
+        .filter(|line| !line.trim_start().starts_with('>'))
+        .flat_map(str::split_whitespace)
+        .map(|word| {
+            word.chars()
+                .filter(|c| c.is_alphanumeric())
+                .flat_map(char::to_lowercase)
+                .collect::<String>()
+        })
+        .filter(|word| !word.is_empty())
+        .collect();
+
+    if words.len() < DUPLICATE_MIN_WORDS {
+        return None;
+    }
+
+    let mut weights = [0i32; 64];
+    for shingle in words.windows(DUPLICATE_SHINGLE_WORDS) {
+        let mut hasher = DefaultHasher::new();
+        shingle.hash(&mut hasher);
+        let hash = hasher.finish();
+        for (bit, weight) in weights.iter_mut().enumerate() {
+            if hash & (1 << bit) != 0 {
+                *weight += 1;
+            } else {
+                *weight -= 1;
+            }
+        }
+    }
+
+    Some(
+        weights
+            .iter()
+            .enumerate()
+            .filter(|(_, weight)| **weight > 0)
+            .fold(0u64, |acc, (bit, _)| acc | (1 << bit)),
+    )
+}
+
+/// Number of differing bits between two fingerprints
+fn hamming_distance(a: u64, b: u64) -> u32 {
+    (a ^ b).count_ones()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SPAM: &str = "Amazing offer for everyone reading this group today only \
+        visit our site to claim your free prize before the deal expires \
+        and tell all your friends about this incredible opportunity now";
+
+    fn root(message_id: &str, date: &str, body: &str) -> ArticleView {
+        ArticleView {
+            message_id: message_id.to_string(),
+            subject: "Offer".to_string(),
+            from: "spammer@example.com".to_string(),
+            date: date.to_string(),
+            date_relative: String::new(),
+            body: Some(body.to_string()),
+            body_preview: None,
+            has_more_content: false,
+            headers: None,
+        }
+    }
+
+    #[test]
+    fn test_simhash_ignores_case_and_punctuation() {
+        let shouted = SPAM.to_uppercase().replace(' ', " ! ");
+        assert_eq!(simhash(SPAM), simhash(&shouted));
+    }
+
+    #[test]
+    fn test_simhash_near_duplicate_is_close() {
+        let variant = SPAM.replace("opportunity now", "opportunity today");
+        let distance = hamming_distance(simhash(SPAM).unwrap(), simhash(&variant).unwrap());
+        assert!(
+            distance < 16,
+            "expected near-duplicate, distance {}",
+            distance
+        );
+    }
+
+    #[test]
+    fn test_simhash_skips_short_bodies() {
+        assert_eq!(simhash("+1, thanks"), None);
+    }
+
+    #[test]
+    fn test_simhash_ignores_quotes_and_signature() {
+        let with_extras = format!("> quoted text from someone else\n{}\n-- \nsig line", SPAM);
+        assert_eq!(simhash(SPAM), simhash(&with_extras));
+    }
+
+    #[tokio::test]
+    async fn test_detector_flags_later_post_as_duplicate() {
+        let detector = DuplicateDetector::new(&SpamConfig::default());
+        detector
+            .observe_root(&root("<b@x>", "Tue, 2 Jan 2024 10:00:00 +0000", SPAM))
+            .await;
+        detector
+            .observe_root(&root("<a@x>", "Mon, 1 Jan 2024 10:00:00 +0000", SPAM))
+            .await;
+
+        let found = detector.duplicates_of(["<a@x>", "<b@x>"]).await;
+        assert_eq!(found.len(), 1);
+        assert_eq!(found.get("<b@x>").map(String::as_str), Some("<a@x>"));
+    }
+
+    #[tokio::test]
+    async fn test_detector_forgets_root() {
+        let detector = DuplicateDetector::new(&SpamConfig::default());
+        detector
+            .observe_root(&root("<a@x>", "Mon, 1 Jan 2024 10:00:00 +0000", SPAM))
+            .await;
+        detector
+            .observe_root(&root("<b@x>", "Tue, 2 Jan 2024 10:00:00 +0000", SPAM))
+            .await;
+
+        detector.forget_root("<a@x>").await;
+        assert!(detector.duplicates_of(["<b@x>"]).await.is_empty());
+
+        // A later copy now counts as a duplicate of the remaining root
+        detector
+            .observe_root(&root("<c@x>", "Wed, 3 Jan 2024 10:00:00 +0000", SPAM))
+            .await;
+        let found = detector.duplicates_of(["<c@x>"]).await;
+        assert_eq!(found.get("<c@x>").map(String::as_str), Some("<b@x>"));
+    }
+
+    #[tokio::test]
+    async fn test_detector_disabled_with_zero_distance() {
+        let config = SpamConfig {
+            duplicate_distance: 0,
+            ..SpamConfig::default()
+        };
+        let detector = DuplicateDetector::new(&config);
+        detector
+            .observe_root(&root("<a@x>", "Mon, 1 Jan 2024 10:00:00 +0000", SPAM))
+            .await;
+        detector
+            .observe_root(&root("<b@x>", "Tue, 2 Jan 2024 10:00:00 +0000", SPAM))
+            .await;
+
+        assert!(detector.duplicates_of(["<b@x>"]).await.is_empty());
+    }
+}
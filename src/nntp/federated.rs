@@ -7,32 +7,54 @@
 
 use std::collections::hash_map::Entry;
 use std::collections::{HashMap, HashSet};
-use std::sync::Arc;
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::sync::{Arc, Mutex};
 use std::time::{Duration, Instant};
 
-use chrono::DateTime;
+use chrono::{DateTime, Utc};
+use futures::stream::FuturesUnordered;
+use futures::StreamExt;
 use moka::future::Cache;
-use tokio::sync::{broadcast, RwLock};
+use moka::notification::RemovalCause;
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::{broadcast, RwLock, Semaphore};
 use tokio::task::JoinHandle;
 
 use tracing::instrument;
 
 use crate::config::{
-    AppConfig, CacheConfig, ACTIVITY_BUCKET_COUNT, ACTIVITY_HIGH_RPS, ACTIVITY_WINDOW_SECS,
-    BACKGROUND_REFRESH_MAX_PERIOD_SECS, BACKGROUND_REFRESH_MIN_PERIOD_SECS,
-    BROADCAST_CHANNEL_CAPACITY, GROUP_STATS_REFRESH_INTERVAL_SECS, INCREMENTAL_DEBOUNCE_MS,
-    NEGATIVE_CACHE_SIZE_DIVISOR, NNTP_NEGATIVE_CACHE_TTL_SECS, POST_POLL_INTERVAL_MS,
-    POST_POLL_MAX_ATTEMPTS, THREAD_CACHE_MULTIPLIER,
+    AppConfig, BodyConfig, CacheConfig, FetchWindow, NntpServerConfig, RefreshConfig,
+    RefreshPolicy, SpamConfig, SubjectConfig, VoteConfig, ACTIVITY_BUCKET_COUNT, ACTIVITY_HIGH_RPS,
+    ACTIVITY_MAX_TRACKED_GROUPS, ACTIVITY_WINDOW_SECS, AVAILABILITY_PROBE_INTERVAL_SECS,
+    BACKGROUND_REFRESH_CONCURRENCY, BACKGROUND_REFRESH_JITTER, BACKGROUND_REFRESH_MAX_PERIOD_SECS,
+    BACKGROUND_REFRESH_MIN_PERIOD_SECS, BROADCAST_CHANNEL_CAPACITY, DEFAULT_BODY_FETCH_CONCURRENCY,
+    GROUP_STATS_REFRESH_INTERVAL_SECS, HOME_TOP_THREADS_MAX_GROUPS, INCREMENTAL_DEBOUNCE_MS,
+    NEGATIVE_CACHE_SIZE_DIVISOR, NNTP_GROUP_NEGATIVE_CACHE_MAX_ENTRIES,
+    NNTP_GROUP_NEGATIVE_CACHE_TTL_SECS, NNTP_NEGATIVE_CACHE_TTL_SECS, PENDING_POST_MAX_ENTRIES,
+    PENDING_POST_TTL_SECS, POST_POLL_INTERVAL_MS, POST_POLL_MAX_ATTEMPTS,
+    RECOVERY_WARMUP_CONCURRENCY, RECOVERY_WARMUP_MAX_GROUPS, THREAD_CACHE_MULTIPLIER,
+    THREAD_LIST_SNAPSHOT_THREADS_PER_LIST, THREAD_LIST_SNAPSHOT_TTL_SECS,
 };
 use crate::error::AppError;
+use crate::metrics::metrics;
 
 use nntp_rs::OverviewEntry;
 
+use super::availability::{AvailabilityHistory, ServerAvailability};
+use super::cancel::OnCancel;
+use super::duplicates::DuplicateDetector;
+use super::footers::FooterRules;
 use super::messages::GroupStatsView;
+use super::quotes;
+use super::retry::random_unit;
 use super::service::NntpService;
+use super::subject::SubjectRules;
+use super::votes::{self, VoteTally};
 use super::{
-    add_reply_to_node, compute_timeago, merge_articles_into_thread, merge_articles_into_threads,
-    ArticleView, FlatComment, GroupView, PaginationInfo, ThreadNodeView, ThreadView,
+    add_reply_to_node, collapsed_ancestors, compute_timeago, merge_articles_into_thread,
+    merge_articles_into_threads, parse_date, ArticleView, BodyFetch, CachedStatus, DeliveryStatus,
+    FlatComment, GroupView, PaginationInfo, PendingPostView, ThreadFilter, ThreadNodeView,
+    ThreadView, TopThreadView,
 };
 
 /// Type alias for pending group stats broadcast senders
@@ -42,6 +64,11 @@ type PendingGroupStats = HashMap<String, broadcast::Sender<Result<GroupStatsView
 type PendingIncremental =
     HashMap<String, broadcast::Sender<Result<Arc<Vec<OverviewEntry>>, String>>>;
 
+/// Type alias for pending full thread list fetches (key: group name).
+/// Errors carry `None` for "group not found" so waiters can report it as such.
+type PendingThreads =
+    HashMap<String, broadcast::Sender<Result<Arc<Vec<ThreadView>>, Option<String>>>>;
+
 /// Type alias for pending groups list broadcast sender (single global request)
 type PendingGroups = Option<broadcast::Sender<Result<Vec<GroupView>, String>>>;
 
@@ -56,10 +83,21 @@ struct GroupActivity {
     bucket_start_idx: u64,
     /// Total requests in all buckets (for fast average calculation)
     total_requests: u64,
+    /// Seconds since the tracker epoch of the latest request (for LRU eviction)
+    last_seen_secs: u64,
     /// Handle to the group's refresh task (for cancellation on activity change)
     refresh_task: Option<tokio::task::JoinHandle<()>>,
 }
 
+impl Drop for GroupActivity {
+    /// A group that is no longer tracked must not keep refreshing
+    fn drop(&mut self) {
+        if let Some(task) = self.refresh_task.take() {
+            task.abort();
+        }
+    }
+}
+
 /// Seconds per bucket = window size / bucket count
 const BUCKET_GRANULARITY_SECS: u64 = ACTIVITY_WINDOW_SECS / ACTIVITY_BUCKET_COUNT;
 
@@ -70,6 +108,7 @@ impl GroupActivity {
             current_bucket: 0,
             bucket_start_idx: 0,
             total_requests: 0,
+            last_seen_secs: 0,
             refresh_task: None,
         }
     }
@@ -83,6 +122,7 @@ impl GroupActivity {
     /// `now_secs` is seconds since an arbitrary epoch (we use Instant-based).
     fn record_request(&mut self, now_secs: u64) {
         self.advance_to(now_secs);
+        self.last_seen_secs = now_secs;
         self.buckets[self.current_bucket] = self.buckets[self.current_bucket].saturating_add(1);
         self.total_requests += 1;
     }
@@ -129,18 +169,25 @@ impl GroupActivity {
     }
 }
 
-/// Tracks activity for all groups
-#[derive(Default)]
+/// Tracks activity for all groups, up to a fixed number of groups.
+/// Dropping a group's entry aborts its refresh task.
 struct ActivityTracker {
     groups: HashMap<String, GroupActivity>,
+    /// Maximum number of tracked groups
+    max_groups: usize,
     /// Epoch for calculating seconds (set on first use)
     epoch: Option<Instant>,
 }
 
 impl ActivityTracker {
     fn new() -> Self {
+        Self::with_max_groups(ACTIVITY_MAX_TRACKED_GROUPS)
+    }
+
+    fn with_max_groups(max_groups: usize) -> Self {
         Self {
             groups: HashMap::new(),
+            max_groups: max_groups.max(1),
             epoch: None,
         }
     }
@@ -160,12 +207,38 @@ impl ActivityTracker {
     /// Record a request for a group
     fn record_request(&mut self, group: &str) {
         let now_secs = self.now_secs();
+        self.record_request_at(group, now_secs);
+    }
+
+    /// Record a request at a given time, making room for a new group if needed
+    fn record_request_at(&mut self, group: &str, now_secs: u64) {
+        if !self.groups.contains_key(group) && self.groups.len() >= self.max_groups {
+            self.evict(now_secs);
+        }
         self.groups
             .entry(group.to_string())
             .or_insert_with(GroupActivity::new)
             .record_request(now_secs);
     }
 
+    /// Free one slot: drop inactive groups, or failing that the least recently seen
+    fn evict(&mut self, now_secs: u64) {
+        self.groups
+            .retain(|_, activity| !activity.is_inactive(now_secs));
+        if self.groups.len() < self.max_groups {
+            return;
+        }
+        let oldest = self
+            .groups
+            .iter()
+            .min_by_key(|(_, activity)| activity.last_seen_secs)
+            .map(|(group, _)| group.clone());
+        if let Some(group) = oldest {
+            tracing::debug!(%group, "Activity tracker full, evicting least recent group");
+            self.groups.remove(&group);
+        }
+    }
+
     /// Get the requests per second for a group
     fn requests_per_second(&mut self, group: &str) -> f64 {
         let now_secs = self.now_secs();
@@ -175,6 +248,23 @@ impl ActivityTracker {
             .unwrap_or(0.0)
     }
 
+    /// Get up to `limit` active groups, busiest first
+    fn busiest_groups(&mut self, limit: usize) -> Vec<String> {
+        let now_secs = self.now_secs();
+        let mut rates: Vec<(String, f64)> = self
+            .groups
+            .iter_mut()
+            .map(|(group, activity)| (group.clone(), activity.requests_per_second(now_secs)))
+            .filter(|(_, rps)| *rps > 0.0)
+            .collect();
+        rates.sort_by(|a, b| b.1.total_cmp(&a.1));
+        rates
+            .into_iter()
+            .take(limit)
+            .map(|(group, _)| group)
+            .collect()
+    }
+
     /// Get all active groups (with any activity in the window)
     fn active_groups(&mut self) -> Vec<String> {
         let now_secs = self.now_secs();
@@ -191,6 +281,9 @@ impl ActivityTracker {
                 old_task.abort();
             }
             activity.refresh_task = Some(task);
+        } else {
+            // Evicted while the task was being spawned; nothing would stop it
+            task.abort();
         }
     }
 
@@ -204,12 +297,51 @@ impl ActivityTracker {
     }
 }
 
+/// Spread `period` by up to `BACKGROUND_REFRESH_JITTER` either way;
+/// `unit` in [0, 1) picks the point
+fn jittered(period: Duration, unit: f64) -> Duration {
+    period.mul_f64(1.0 + BACKGROUND_REFRESH_JITTER * (2.0 * unit - 1.0))
+}
+
 /// Cached thread data with high water mark for incremental updates
 #[derive(Clone)]
 struct CachedThreads {
     threads: Vec<ThreadView>,
     /// Last article number when this cache was populated (high water mark)
     last_article_number: u64,
+    /// When the list was last fetched in full. Incremental merges keep it, so
+    /// a list past the soft TTL is refetched even if it has been kept current.
+    fetched_at: Instant,
+}
+
+/// A cached value with the time it was fetched, for soft-TTL checks
+#[derive(Clone)]
+struct Stamped<T> {
+    value: T,
+    fetched_at: Instant,
+}
+
+impl<T> Stamped<T> {
+    fn new(value: T) -> Self {
+        Self {
+            value,
+            fetched_at: Instant::now(),
+        }
+    }
+
+    /// Whether the value is past its soft TTL and due for a background refresh
+    fn is_stale(&self, soft_ttl: Duration) -> bool {
+        self.fetched_at.elapsed() >= soft_ttl
+    }
+}
+
+/// Ages after which cached values are refreshed in the background.
+/// Until the cache's own (hard) TTL they are still served meanwhile.
+#[derive(Clone, Copy)]
+struct SoftTtls {
+    article: Duration,
+    threads: Duration,
+    groups: Duration,
 }
 
 /// Cached single thread data with group info for incremental updates
@@ -221,6 +353,31 @@ struct CachedThread {
     group: String,
 }
 
+/// Delivery tracking for a posted article
+#[derive(Clone)]
+struct PendingPost {
+    /// Thread the article belongs to ("group:root_message_id")
+    thread_key: String,
+    /// Who posted it, as "provider:sub"; only they see its status
+    poster: String,
+    status: DeliveryStatus,
+}
+
+/// Drop a posted article from the pending posts index
+fn unindex_pending_post(
+    index: &Mutex<HashMap<String, Vec<String>>>,
+    thread_key: &str,
+    message_id: &str,
+) {
+    let mut index = index.lock().unwrap_or_else(|e| e.into_inner());
+    if let Entry::Occupied(mut entry) = index.entry(thread_key.to_string()) {
+        entry.get_mut().retain(|id| id != message_id);
+        if entry.get().is_empty() {
+            entry.remove();
+        }
+    }
+}
+
 /// Federated NNTP Service that presents multiple servers as one unified source
 #[derive(Clone)]
 pub struct NntpFederatedService {
@@ -228,18 +385,32 @@ pub struct NntpFederatedService {
     services: Vec<NntpService>,
 
     /// Cache for individual articles
-    article_cache: Cache<String, ArticleView>,
+    article_cache: Cache<String, Stamped<ArticleView>>,
     /// Cache for not-found articles (negative cache with short TTL)
     article_not_found_cache: Cache<String, ()>,
+    /// Cache for groups no server carries (key: canonical group name, short TTL)
+    group_not_found_cache: Cache<String, ()>,
     /// Cache for thread lists (key: group name)
     /// Stores threads with high water mark for incremental updates
     threads_cache: Cache<String, CachedThreads>,
     /// Cache for single threads (key: "group:message_id")
     thread_cache: Cache<String, CachedThread>,
+    /// Sorted thread list snapshots for stable pagination (key: "group:token")
+    thread_list_snapshots: Cache<String, Arc<Vec<ThreadView>>>,
+    /// Vote tallies of threads in `[votes]` groups
+    /// (key: "group:root_message_id:article_count")
+    vote_tallies: Cache<String, Option<VoteTally>>,
     /// Cache for group list (merged from all servers)
-    groups_cache: Cache<String, Vec<GroupView>>,
+    groups_cache: Cache<String, Stamped<Vec<GroupView>>>,
     /// Cache for group stats (article count and last article date)
-    group_stats_cache: Cache<String, GroupStatsView>,
+    group_stats_cache: Cache<String, Stamped<GroupStatsView>>,
+    /// Delivery status of recently posted articles (key: message_id)
+    pending_posts: Cache<String, PendingPost>,
+    /// Message IDs in `pending_posts` by thread ("group:root_message_id"),
+    /// kept in step by the cache's eviction listener
+    pending_posts_by_thread: Arc<Mutex<HashMap<String, Vec<String>>>>,
+    /// Near-duplicate thread root detection (link-spam)
+    duplicates: DuplicateDetector,
 
     /// Maps group name -> server indices that carry it
     /// Used for smart dispatch of group-specific requests
@@ -252,9 +423,22 @@ pub struct NntpFederatedService {
     /// Pending group stats requests for coalescing at federated level
     pending_group_stats: Arc<RwLock<PendingGroupStats>>,
 
+    /// Pending full thread list fetches, so concurrent misses share one fetch
+    pending_threads: Arc<RwLock<PendingThreads>>,
+
+    /// Per-cache soft TTLs for stale-while-revalidate
+    soft_ttl: SoftTtls,
+
+    /// Articles being refreshed in the background after their soft TTL
+    refreshing_articles: Arc<RwLock<HashSet<String>>>,
+
     /// Per-group high water mark (last known article number)
     group_hwm: Arc<RwLock<HashMap<String, u64>>>,
 
+    /// Per-group cache version, part of every per-group cache key.
+    /// Bumping it purges the group's entries without enumerating keys.
+    group_versions: Arc<RwLock<HashMap<String, u64>>>,
+
     /// Last incremental check time per group (for debouncing)
     last_incremental_check: Arc<RwLock<HashMap<String, Instant>>>,
 
@@ -267,22 +451,51 @@ pub struct NntpFederatedService {
     /// Task handles for per-group stats refresh (for cleanup when groups are removed)
     group_stats_tasks: Arc<RwLock<HashMap<String, JoinHandle<()>>>>,
 
-    /// Maximum number of articles to fetch per group (from config)
-    max_articles_per_group: u64,
+    /// Number of articles to fetch per group, adapted to its traffic (from config)
+    fetch_window: FetchWindow,
+
+    /// Article fetches one `get_articles` call keeps in flight per server
+    /// (from config)
+    body_fetch_concurrency: usize,
+
+    /// How long `get_article` waits on one server before asking the next as
+    /// well; `None` tries servers one after another
+    hedge_delay: Option<Duration>,
 
     /// Last time we refreshed the groups list (for stale-while-revalidate debouncing)
     last_groups_refresh: Arc<RwLock<Option<Instant>>>,
 
     /// Pending groups list request for coalescing (only one can be in flight)
     pending_groups: Arc<RwLock<PendingGroups>>,
+
+    /// Permits for background incremental refreshes, shared by all groups
+    refresh_permits: Arc<Semaphore>,
+
+    /// Renamed groups (old name -> current name), so both names share cache entries
+    group_aliases: Arc<HashMap<String, String>>,
+
+    /// Subject normalization and subject-based grouping rules
+    subject_rules: Arc<SubjectRules>,
+
+    /// Groups with vote tallies and the per-thread reply limit
+    votes: Arc<VoteConfig>,
+
+    /// Per-group background refresh periods
+    refresh: Arc<RefreshConfig>,
+
+    /// Signature and footer stripping for displayed bodies
+    footers: FooterRules,
+
+    /// Daily upstream availability samples per server
+    availability: Arc<RwLock<AvailabilityHistory>>,
 }
 
 impl NntpFederatedService {
     /// Create a new federated service from configuration
     pub fn new(config: &AppConfig) -> Self {
-        let services: Vec<NntpService> = config
-            .server
-            .iter()
+        // Fallbacks try servers in this order, so it follows their priority
+        let services: Vec<NntpService> = NntpServerConfig::by_priority(&config.server)
+            .into_iter()
             .map(|server_config| NntpService::new(server_config.clone(), config.nntp.clone()))
             .collect();
 
@@ -291,6 +504,15 @@ impl NntpFederatedService {
             &config.cache,
             config.nntp.defaults.max_articles_per_group,
         )
+        .with_group_aliases(config.group_aliases.clone())
+        .with_spam_config(&config.spam)
+        .with_subject_config(&config.subjects)
+        .with_vote_config(&config.votes)
+        .with_fetch_window(config.nntp.defaults.fetch_window())
+        .with_body_fetch_concurrency(config.nntp.defaults.body_fetch_concurrency)
+        .with_hedge_delay(config.nntp.hedge_delay_ms.map(Duration::from_millis))
+        .with_refresh_config(&config.refresh)
+        .with_body_config(&config.body)
     }
 
     /// Create a federated service with explicit services and cache config
@@ -315,6 +537,20 @@ impl NntpFederatedService {
             .time_to_live(Duration::from_secs(cache_config.threads_ttl_seconds))
             .build();
 
+        // Weighed by thread count: each snapshot is a full copy of a list
+        let thread_list_snapshots = Cache::builder()
+            .max_capacity(cache_config.max_thread_lists * THREAD_LIST_SNAPSHOT_THREADS_PER_LIST)
+            .weigher(|_, threads: &Arc<Vec<ThreadView>>| {
+                u32::try_from(threads.len()).unwrap_or(u32::MAX).max(1)
+            })
+            .time_to_live(Duration::from_secs(THREAD_LIST_SNAPSHOT_TTL_SECS))
+            .build();
+
+        let vote_tallies = Cache::builder()
+            .max_capacity(cache_config.max_thread_lists * THREAD_CACHE_MULTIPLIER)
+            .time_to_live(Duration::from_secs(cache_config.threads_ttl_seconds))
+            .build();
+
         let groups_cache = Cache::builder()
             .max_capacity(1) // Only one merged groups list
             .time_to_live(Duration::from_secs(cache_config.groups_ttl_seconds))
@@ -325,34 +561,181 @@ impl NntpFederatedService {
             .time_to_live(Duration::from_secs(cache_config.threads_ttl_seconds))
             .build();
 
+        let pending_posts_by_thread: Arc<Mutex<HashMap<String, Vec<String>>>> = Arc::default();
+        let pending_posts = Cache::builder()
+            .max_capacity(PENDING_POST_MAX_ENTRIES)
+            .time_to_live(Duration::from_secs(PENDING_POST_TTL_SECS))
+            .eviction_listener({
+                let index = pending_posts_by_thread.clone();
+                move |message_id: Arc<String>, post: PendingPost, cause| {
+                    // A status update replaces the entry but keeps it indexed
+                    if cause != RemovalCause::Replaced {
+                        unindex_pending_post(&index, &post.thread_key, &message_id);
+                    }
+                }
+            })
+            .build();
+
         // Negative cache for not-found articles with short TTL
         let article_not_found_cache = Cache::builder()
             .max_capacity(cache_config.max_articles / NEGATIVE_CACHE_SIZE_DIVISOR) // Quarter the size of positive cache
             .time_to_live(Duration::from_secs(NNTP_NEGATIVE_CACHE_TTL_SECS))
             .build();
 
+        // Negative cache for groups every server reported missing
+        let group_not_found_cache = Cache::builder()
+            .max_capacity(NNTP_GROUP_NEGATIVE_CACHE_MAX_ENTRIES)
+            .time_to_live(Duration::from_secs(NNTP_GROUP_NEGATIVE_CACHE_TTL_SECS))
+            .build();
+
         Self {
             services,
             article_cache,
             article_not_found_cache,
+            group_not_found_cache,
             threads_cache,
             thread_cache,
+            thread_list_snapshots,
+            vote_tallies,
             groups_cache,
             group_stats_cache,
+            pending_posts,
+            pending_posts_by_thread,
+            duplicates: DuplicateDetector::new(&SpamConfig::default()),
             group_servers: Arc::new(RwLock::new(HashMap::new())),
             posting_servers: Arc::new(RwLock::new(HashMap::new())),
             pending_group_stats: Arc::new(RwLock::new(HashMap::new())),
+            pending_threads: Arc::new(RwLock::new(HashMap::new())),
+            soft_ttl: SoftTtls {
+                article: cache_config.article_soft_ttl(),
+                threads: cache_config.threads_soft_ttl(),
+                groups: cache_config.groups_soft_ttl(),
+            },
+            refreshing_articles: Arc::new(RwLock::new(HashSet::new())),
             group_hwm: Arc::new(RwLock::new(HashMap::new())),
+            group_versions: Arc::new(RwLock::new(HashMap::new())),
             last_incremental_check: Arc::new(RwLock::new(HashMap::new())),
             pending_incremental: Arc::new(RwLock::new(HashMap::new())),
             activity_tracker: Arc::new(RwLock::new(ActivityTracker::new())),
             group_stats_tasks: Arc::new(RwLock::new(HashMap::new())),
-            max_articles_per_group,
+            fetch_window: FetchWindow::fixed(max_articles_per_group),
+            body_fetch_concurrency: DEFAULT_BODY_FETCH_CONCURRENCY,
+            hedge_delay: None,
             last_groups_refresh: Arc::new(RwLock::new(None)),
             pending_groups: Arc::new(RwLock::new(None)),
+            refresh_permits: Arc::new(Semaphore::new(BACKGROUND_REFRESH_CONCURRENCY)),
+            group_aliases: Arc::new(HashMap::new()),
+            subject_rules: Arc::new(SubjectRules::new(&SubjectConfig::default())),
+            votes: Arc::new(VoteConfig::default()),
+            refresh: Arc::new(RefreshConfig::default()),
+            footers: FooterRules::new(&BodyConfig::default()),
+            availability: Arc::new(RwLock::new(AvailabilityHistory::default())),
+        }
+    }
+
+    /// Set group aliases (old name -> current name)
+    pub fn with_group_aliases(mut self, aliases: HashMap<String, String>) -> Self {
+        self.group_aliases = Arc::new(aliases);
+        self
+    }
+
+    /// Set spam filtering configuration (duplicate detection thresholds)
+    pub fn with_spam_config(mut self, config: &SpamConfig) -> Self {
+        self.duplicates = DuplicateDetector::new(config);
+        self
+    }
+
+    /// Set subject normalization rules
+    pub fn with_subject_config(mut self, config: &SubjectConfig) -> Self {
+        self.subject_rules = Arc::new(SubjectRules::new(config));
+        self
+    }
+
+    /// Set the groups that show vote tallies
+    pub fn with_vote_config(mut self, config: &VoteConfig) -> Self {
+        self.votes = Arc::new(config.clone());
+        self
+    }
+
+    /// Set per-group background refresh periods
+    pub fn with_refresh_config(mut self, config: &RefreshConfig) -> Self {
+        self.refresh = Arc::new(config.clone());
+        self
+    }
+
+    /// Set footer rules for displayed bodies
+    pub fn with_body_config(mut self, config: &BodyConfig) -> Self {
+        self.footers = FooterRules::new(config);
+        self
+    }
+
+    /// Size thread list fetches by each group's observed traffic
+    pub fn with_fetch_window(mut self, window: FetchWindow) -> Self {
+        self.fetch_window = window;
+        self
+    }
+
+    /// Limit how many article bodies a page load fetches at once
+    pub fn with_body_fetch_concurrency(mut self, fetches: usize) -> Self {
+        self.body_fetch_concurrency = fetches.max(1);
+        self
+    }
+
+    /// Ask the next server for an article when one hasn't answered within
+    /// `delay`, taking whichever answers first
+    pub fn with_hedge_delay(mut self, delay: Option<Duration>) -> Self {
+        self.hedge_delay = delay;
+        self
+    }
+
+    /// Resolve a group name through the alias table.
+    /// Returns the current name for aliased groups, or the name unchanged.
+    pub fn canonical_group<'a>(&'a self, group: &'a str) -> &'a str {
+        self.group_aliases
+            .get(group)
+            .map(String::as_str)
+            .unwrap_or(group)
+    }
+
+    /// Cache key for a canonical group name: the name, plus the group's
+    /// version once it has been purged. Thread and snapshot keys extend it
+    /// with ":message_id" / ":token". Newsgroup names can't contain '#'.
+    async fn group_key(&self, group: &str) -> String {
+        match self.group_versions.read().await.get(group) {
+            Some(version) => format!("{}#{}", group, version),
+            None => group.to_string(),
         }
     }
 
+    /// The group's cache version, bumped by `purge_group`. Caches kept
+    /// outside this service put it in their keys to be purged with the group.
+    pub async fn group_version(&self, group: &str) -> u64 {
+        let group = self.canonical_group(group);
+        self.group_versions
+            .read()
+            .await
+            .get(group)
+            .copied()
+            .unwrap_or(0)
+    }
+
+    /// Invalidate everything cached for a group: its thread list, single
+    /// threads, pagination snapshots, vote tallies and stats. Bumping the
+    /// group's version changes all their keys at once; orphaned entries age
+    /// out through the caches' TTL and capacity limits, and the next request
+    /// fetches afresh.
+    pub async fn purge_group(&self, group: &str) {
+        let group = self.canonical_group(group);
+        let version = {
+            let mut versions = self.group_versions.write().await;
+            let version = versions.entry(group.to_string()).or_default();
+            *version += 1;
+            *version
+        };
+        self.group_not_found_cache.invalidate(group).await;
+        tracing::info!(%group, version, "Purged group caches");
+    }
+
     /// Spawn workers for all servers
     pub fn spawn_workers(&self) {
         for service in &self.services {
@@ -365,10 +748,24 @@ impl NntpFederatedService {
         self.services.iter().map(|s| s.name()).collect()
     }
 
-    /// Get server indices for a group, or all servers if group is unknown
+    /// Number of groups in the cached group list (0 before the first fetch)
+    pub async fn cached_group_count(&self) -> usize {
+        self.groups_cache
+            .get("groups")
+            .await
+            .map_or(0, |groups| groups.value.len())
+    }
+
+    /// Approximate number of articles held in the article cache
+    pub fn cached_article_count(&self) -> u64 {
+        self.article_cache.entry_count()
+    }
+
+    /// Get server indices for a group, or all servers if group is unknown,
+    /// unhealthy servers last
     async fn get_servers_for_group(&self, group: &str) -> Vec<usize> {
         let mapping = self.group_servers.read().await;
-        if let Some(indices) = mapping.get(group) {
+        let mut indices = if let Some(indices) = mapping.get(group) {
             tracing::debug!(
                 %group,
                 servers = ?indices,
@@ -382,36 +779,42 @@ impl NntpFederatedService {
                 "Group not in mapping, trying all servers"
             );
             (0..self.services.len()).collect()
-        }
+        };
+        // Stable, so priority order holds among healthy servers
+        indices.sort_by_key(|&idx| !self.services[idx].is_healthy());
+        indices
     }
 
-    /// Check if an error indicates a definitive "not found" condition
-    /// Returns true for errors that should be negatively cached
-    fn is_not_found_error(error: &super::messages::NntpError) -> bool {
-        let error_msg = error.0.to_lowercase();
-        // NNTP 430 = "No such article"
-        // NNTP 423 = "No such article in this group"
-        error_msg.contains("430")
-            || error_msg.contains("423")
-            || error_msg.contains("no such article")
-            || error_msg.contains("article not found")
+    /// Servers in priority order, except that unhealthy ones come last
+    fn services_by_health(&self) -> impl Iterator<Item = &NntpService> {
+        let (healthy, unhealthy): (Vec<_>, Vec<_>) =
+            self.services.iter().partition(|s| s.is_healthy());
+        healthy.into_iter().chain(unhealthy)
     }
 
-    /// Check if an error indicates a "group not found" condition
-    /// NNTP 411 = "No such newsgroup"
-    fn is_group_not_found_error(error: &super::messages::NntpError) -> bool {
-        let error_msg = error.0.to_lowercase();
-        error_msg.contains("411")
-            || error_msg.contains("no such newsgroup")
-            || error_msg.contains("group not found")
+    /// Fail fast for a group that every server recently reported missing
+    async fn check_group_not_found(&self, group: &str) -> Result<(), AppError> {
+        if self.group_not_found_cache.contains_key(group) {
+            tracing::debug!(%group, "Group in negative cache");
+            return Err(AppError::GroupNotFound(group.to_string()));
+        }
+        Ok(())
+    }
+
+    /// Record that every server asked reported `group` missing
+    async fn cache_group_not_found(&self, group: &str) {
+        tracing::debug!(%group, "All servers returned 'no such group' - caching negative result");
+        self.group_not_found_cache
+            .insert(group.to_string(), ())
+            .await;
     }
 
     /// Convert an NNTP error to an appropriate AppError
     fn nntp_error_to_app_error(error: super::messages::NntpError, group: &str) -> AppError {
-        if Self::is_group_not_found_error(&error) {
+        if error.is_group_missing() {
             AppError::GroupNotFound(group.to_string())
         } else {
-            AppError::Internal(error.0)
+            AppError::Internal(error.to_string())
         }
     }
 
@@ -508,6 +911,9 @@ impl NntpFederatedService {
                 let result = match rx.recv().await {
                     Ok(Ok(entries)) => Ok((*entries).clone()),
                     Ok(Err(e)) => Err(AppError::Internal(e)),
+                    // The caller we joined went away before the HWM moved;
+                    // the next check picks up whatever it would have found
+                    Err(RecvError::Closed) => Ok(Vec::new()),
                     Err(_) => Err(AppError::Internal("Broadcast channel closed".into())),
                 };
                 tracing::Span::current().record("duration_ms", start.elapsed().as_millis() as u64);
@@ -527,6 +933,9 @@ impl NntpFederatedService {
                 let result = match rx.recv().await {
                     Ok(Ok(entries)) => Ok((*entries).clone()),
                     Ok(Err(e)) => Err(AppError::Internal(e)),
+                    // The caller we joined went away before the HWM moved;
+                    // the next check picks up whatever it would have found
+                    Err(RecvError::Closed) => Ok(Vec::new()),
                     Err(_) => Err(AppError::Internal("Broadcast channel closed".into())),
                 };
                 tracing::Span::current().record("duration_ms", start.elapsed().as_millis() as u64);
@@ -534,6 +943,13 @@ impl NntpFederatedService {
             }
             pending.insert(group.to_string(), tx.clone());
         }
+        let cancel = {
+            let pending = self.pending_incremental.clone();
+            let group = group.to_string();
+            OnCancel::new(async move {
+                pending.write().await.remove(&group);
+            })
+        };
 
         // Perform the actual fetch
         let result = self.get_new_articles(group, hwm).await;
@@ -551,6 +967,7 @@ impl NntpFederatedService {
             let mut pending = self.pending_incremental.write().await;
             pending.remove(group);
         }
+        cancel.disarm();
 
         let broadcast_result = result
             .as_ref()
@@ -573,13 +990,24 @@ impl NntpFederatedService {
     /// - 10,000 requests/second -> 1 second refresh period
     /// - Any activity at all -> 30 second refresh period  
     /// - Scales logarithmically between these extremes
+    #[cfg(test)]
     fn calculate_refresh_period(requests_per_second: f64) -> Duration {
+        Self::refresh_period_within(requests_per_second, &RefreshPolicy::default())
+    }
+
+    /// Refresh period for a request rate, scaled between a group's own
+    /// minimum and maximum periods instead of the global defaults.
+    fn refresh_period_within(requests_per_second: f64, policy: &RefreshPolicy) -> Duration {
+        let (min, max) = (
+            policy.min_period.as_secs_f64(),
+            policy.max_period.as_secs_f64(),
+        );
         if requests_per_second <= 0.0 {
-            return Duration::from_secs(BACKGROUND_REFRESH_MAX_PERIOD_SECS);
+            return policy.max_period;
         }
 
-        // log10(10000) = 4 -> 1s
-        // log10(1/300) ≈ -2.48 -> 30s (minimum activity = 1 request in 5 minutes)
+        // log10(10000) = 4 -> min (1s by default)
+        // log10(1/300) ≈ -2.48 -> max (30s by default; 1 request in 5 minutes)
         // We use the formula: period = max - (max - min) * (log10(rps) - log_min) / (log_max - log_min)
 
         let log_rps = requests_per_second.log10();
@@ -591,21 +1019,29 @@ impl NntpFederatedService {
 
         // Linear interpolation in log space
         let ratio = (log_clamped - log_min) / (log_max - log_min);
-        let period_secs = BACKGROUND_REFRESH_MAX_PERIOD_SECS as f64
-            - ratio
-                * (BACKGROUND_REFRESH_MAX_PERIOD_SECS - BACKGROUND_REFRESH_MIN_PERIOD_SECS) as f64;
+        let period_secs = max - ratio * (max - min);
 
-        Duration::from_secs_f64(period_secs.max(BACKGROUND_REFRESH_MIN_PERIOD_SECS as f64))
+        Duration::from_secs_f64(period_secs.max(min))
     }
 
     /// Spawn a per-group refresh task that runs at an activity-proportional rate.
+    ///
+    /// The first refresh lands at a random point within the period and later
+    /// ones are jittered, so groups activated together don't refresh in step.
+    /// Each refresh waits for one of the shared `refresh_permits`.
+    /// Groups whose `[[refresh.group]]` rule turns refresh off get no task.
     async fn spawn_group_refresh_task(&self, group: String) {
+        let Some(policy) = self.refresh.policy_for(&group) else {
+            tracing::trace!(%group, "Background refresh disabled for group");
+            return;
+        };
         let this = self.clone();
         let group_clone = group.clone();
 
         tracing::debug!(group = %group, "Spawning background refresh task");
 
         let task = tokio::spawn(async move {
+            let mut first = true;
             loop {
                 // Get current request rate and calculate refresh period
                 let rps = {
@@ -613,7 +1049,7 @@ impl NntpFederatedService {
                     tracker.requests_per_second(&group_clone)
                 };
 
-                let period = Self::calculate_refresh_period(rps);
+                let period = Self::refresh_period_within(rps, &policy);
 
                 tracing::debug!(
                     group = %group_clone,
@@ -622,7 +1058,13 @@ impl NntpFederatedService {
                     "Group refresh scheduled"
                 );
 
-                tokio::time::sleep(period).await;
+                let delay = if first {
+                    period.mul_f64(random_unit())
+                } else {
+                    jittered(period, random_unit())
+                };
+                first = false;
+                tokio::time::sleep(delay).await;
 
                 // Check if group is still active before refreshing
                 let still_active = {
@@ -636,7 +1078,10 @@ impl NntpFederatedService {
                     break;
                 }
 
-                // Perform the refresh
+                // Perform the refresh, once a global permit is free
+                let Ok(_permit) = this.refresh_permits.acquire().await else {
+                    break;
+                };
                 this.trigger_incremental_update(&group_clone).await;
             }
         });
@@ -662,7 +1107,8 @@ impl NntpFederatedService {
                 tracing::debug!(%group, count = new_entries.len(), "Found new articles");
 
                 // Update threads cache if it exists
-                if let Some(cached) = self.threads_cache.get(group).await {
+                let cache_key = self.group_key(group).await;
+                if let Some(cached) = self.threads_cache.get(&cache_key).await {
                     let new_hwm = new_entries
                         .iter()
                         .filter_map(|e| e.number())
@@ -673,10 +1119,11 @@ impl NntpFederatedService {
 
                     self.threads_cache
                         .insert(
-                            group.to_string(),
+                            cache_key,
                             CachedThreads {
                                 threads: merged,
                                 last_article_number: new_hwm,
+                                fetched_at: cached.fetched_at,
                             },
                         )
                         .await;
@@ -692,7 +1139,7 @@ impl NntpFederatedService {
     ///
     /// This is faster than get_article as it doesn't transfer content.
     async fn check_article_exists(&self, message_id: &str) -> bool {
-        for service in &self.services {
+        for service in self.services_by_health() {
             if service
                 .check_article_exists(message_id)
                 .await
@@ -704,46 +1151,75 @@ impl NntpFederatedService {
         false
     }
 
-    /// Inject a pre-built article into cache after confirming server-side existence.
+    /// Inject a freshly posted article into caches so the poster sees it immediately.
     ///
-    /// Polls with STAT command until article exists, then injects the pre-built
-    /// ArticleView into caches. This is faster than fetching the full article
-    /// since we already have all the article data from the post submission.
+    /// The pre-built ArticleView is inserted into the article cache and merged
+    /// into the threads/thread caches right away, without waiting for the server
+    /// to make it visible. Confirmation then happens in the background: the
+    /// article is polled with STAT, and if it never appears it is evicted from
+    /// the shared caches again (see `discard_unconfirmed_post`).
     ///
     /// # Arguments
     /// * `group` - The newsgroup the article was posted to
     /// * `article` - Pre-built ArticleView from post data
+    /// * `poster` - Who posted it, as "provider:sub"
     /// * `root_message_id` - For replies, the root thread's message ID (for cache key)
     /// * `parent_message_id` - For replies, the direct parent's message ID (for tree insertion)
     pub async fn inject_posted_article(
         &self,
         group: &str,
         article: ArticleView,
+        poster: &str,
         root_message_id: Option<&str>,
         parent_message_id: Option<&str>,
     ) {
-        let message_id = &article.message_id;
+        let group = self.canonical_group(group).to_string();
+        let message_id = article.message_id.clone();
+        let pending = PendingPost {
+            thread_key: format!("{}:{}", group, root_message_id.unwrap_or(&message_id)),
+            poster: poster.to_string(),
+            status: DeliveryStatus::Pending,
+        };
+        self.set_delivery_status(&message_id, &pending, DeliveryStatus::Pending)
+            .await;
 
-        for attempt in 1..=POST_POLL_MAX_ATTEMPTS {
-            // Invalidate negative cache before each attempt
-            self.article_not_found_cache.invalidate(message_id).await;
+        // Cache the article for future fetches, clearing any earlier not-found result
+        self.article_not_found_cache.invalidate(&message_id).await;
+        self.article_cache
+            .insert(message_id.clone(), Stamped::new(article.clone()))
+            .await;
+
+        // New threads are fingerprinted for duplicate detection
+        if root_message_id.is_none() {
+            self.duplicates.observe_root(&article).await;
+        }
+
+        // Merge into threads/thread caches
+        self.inject_article_into_caches(&group, article, root_message_id, parent_message_id)
+            .await;
+
+        tracing::debug!(%group, %message_id, "Injected posted article into caches");
+
+        let this = self.clone();
+        tokio::spawn(async move {
+            this.confirm_posted_article(&group, &message_id, &pending)
+                .await;
+        });
+    }
 
+    /// Poll with STAT until a posted article is visible on the server.
+    /// Discards the injected copy if it doesn't appear in time.
+    async fn confirm_posted_article(&self, group: &str, message_id: &str, pending: &PendingPost) {
+        for attempt in 1..=POST_POLL_MAX_ATTEMPTS {
             if self.check_article_exists(message_id).await {
+                self.set_delivery_status(message_id, pending, DeliveryStatus::Confirmed)
+                    .await;
                 tracing::debug!(
                     %group,
                     %message_id,
                     attempt,
-                    "Article confirmed via STAT, injecting into cache"
+                    "Posted article confirmed via STAT"
                 );
-
-                // Cache the article for future fetches
-                self.article_cache
-                    .insert(message_id.to_string(), article.clone())
-                    .await;
-
-                // Inject into threads/thread caches
-                self.inject_article_into_caches(group, article, root_message_id, parent_message_id)
-                    .await;
                 return;
             }
 
@@ -753,21 +1229,93 @@ impl NntpFederatedService {
         }
 
         // Timeout - article didn't appear within polling window
-        // Fall back to triggering incremental update
         tracing::warn!(
             %group,
             %message_id,
             max_attempts = POST_POLL_MAX_ATTEMPTS,
-            "Article not confirmed via STAT after polling, falling back to incremental update"
+            "Article not confirmed via STAT after polling, discarding injected copy"
         );
 
-        // Invalidate thread cache if this was a reply so next request fetches fresh
-        if let Some(root_id) = root_message_id {
-            let cache_key = format!("{}:{}", group, root_id);
-            self.thread_cache.invalidate(&cache_key).await;
+        self.set_delivery_status(message_id, pending, DeliveryStatus::Unconfirmed)
+            .await;
+        self.discard_unconfirmed_post(group, message_id, pending)
+            .await;
+    }
+
+    /// Remove an injected article the server never confirmed from the shared
+    /// caches, so other readers don't keep seeing a post that may not exist.
+    /// The group's thread list and the thread are dropped rather than edited,
+    /// and are fetched from the server again on the next page load.
+    async fn discard_unconfirmed_post(&self, group: &str, message_id: &str, pending: &PendingPost) {
+        let group_key = self.group_key(group).await;
+        self.article_cache.invalidate(message_id).await;
+        self.threads_cache.invalidate(&group_key).await;
+        if let Some((_, root_message_id)) = pending.thread_key.split_once(':') {
+            self.thread_cache
+                .invalidate(&format!("{}:{}", group_key, root_message_id))
+                .await;
+        }
+        self.duplicates.forget_root(message_id).await;
+    }
+
+    /// Record the delivery status of a posted article.
+    async fn set_delivery_status(
+        &self,
+        message_id: &str,
+        pending: &PendingPost,
+        status: DeliveryStatus,
+    ) {
+        {
+            let mut index = self
+                .pending_posts_by_thread
+                .lock()
+                .unwrap_or_else(|e| e.into_inner());
+            let ids = index.entry(pending.thread_key.clone()).or_default();
+            if !ids.iter().any(|id| id == message_id) {
+                ids.push(message_id.to_string());
+            }
         }
+        self.pending_posts
+            .insert(
+                message_id.to_string(),
+                PendingPost {
+                    status,
+                    ..pending.clone()
+                },
+            )
+            .await;
+    }
 
-        self.trigger_incremental_update(group).await;
+    /// Delivery status of articles `poster` recently posted to a thread.
+    /// Entries expire after PENDING_POST_TTL_SECS.
+    pub async fn pending_posts(
+        &self,
+        group: &str,
+        root_message_id: &str,
+        poster: &str,
+    ) -> Vec<PendingPostView> {
+        let thread_key = format!("{}:{}", self.canonical_group(group), root_message_id);
+        let message_ids = self
+            .pending_posts_by_thread
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .get(&thread_key)
+            .cloned()
+            .unwrap_or_default();
+
+        let mut posts = Vec::new();
+        for message_id in message_ids {
+            if let Some(post) = self.pending_posts.get(&message_id).await {
+                if post.poster == poster {
+                    posts.push(PendingPostView {
+                        message_id,
+                        status: post.status,
+                    });
+                }
+            }
+        }
+        posts.sort_by(|a, b| a.message_id.cmp(&b.message_id));
+        posts
     }
 
     /// Inject a fetched article into threads_cache and thread_cache.
@@ -806,8 +1354,9 @@ impl NntpFederatedService {
         let date_relative = Some(compute_timeago(&article.date));
 
         // Create a new ThreadView for this article
-        let new_thread = ThreadView {
+        let mut new_thread = ThreadView {
             subject: article.subject.clone(),
+            normalized_subject: self.subject_rules.normalize(&article.subject).to_string(),
             root_message_id: article.message_id.clone(),
             article_count: 1,
             root: ThreadNodeView {
@@ -818,17 +1367,27 @@ impl NntpFederatedService {
             },
             last_post_date: Some(article.date.clone()),
             last_post_date_relative: date_relative,
+            participants: Vec::new(),
+            participant_count: 0,
+            hotness: 0.0,
+            hot: false,
         };
+        new_thread.update_stats();
 
         // Get existing cache or create empty base
-        let (mut threads, last_article_number) =
-            if let Some(cached) = self.threads_cache.get(group).await {
-                (cached.threads.clone(), cached.last_article_number)
+        let cache_key = self.group_key(group).await;
+        let (mut threads, last_article_number, fetched_at) =
+            if let Some(cached) = self.threads_cache.get(&cache_key).await {
+                (
+                    cached.threads.clone(),
+                    cached.last_article_number,
+                    cached.fetched_at,
+                )
             } else {
                 // No cache exists - start fresh with just this thread
                 // Note: last_article_number of 0 will trigger a full refresh on next incremental check,
                 // which is fine since we're bootstrapping the cache
-                (Vec::new(), 0)
+                (Vec::new(), 0, Instant::now())
             };
 
         // Prepend to thread list (newest first)
@@ -842,10 +1401,11 @@ impl NntpFederatedService {
 
         self.threads_cache
             .insert(
-                group.to_string(),
+                cache_key,
                 CachedThreads {
                     threads,
                     last_article_number,
+                    fetched_at,
                 },
             )
             .await;
@@ -867,13 +1427,15 @@ impl NntpFederatedService {
         };
 
         // Update thread_cache
-        let cache_key = format!("{}:{}", group, root_msg_id);
+        let group_key = self.group_key(group).await;
+        let cache_key = format!("{}:{}", group_key, root_msg_id);
         if let Some(cached) = self.thread_cache.get(&cache_key).await {
             let mut thread = cached.thread.clone();
 
             // Add reply to the appropriate parent node
             if add_reply_to_node(&mut thread.root, parent_msg_id, new_node.clone()) {
                 thread.article_count += 1;
+                thread.update_stats();
                 thread.last_post_date = Some(article.date.clone());
                 thread.last_post_date_relative = Some(compute_timeago(&article.date));
 
@@ -893,11 +1455,16 @@ impl NntpFederatedService {
                         },
                     )
                     .await;
+            } else {
+                // The cached thread is behind the server; drop it so the
+                // next view refetches it with the reply
+                tracing::debug!(%group, %root_msg_id, %parent_msg_id, "Reply parent not cached");
+                self.thread_cache.invalidate(&cache_key).await;
             }
         }
 
         // Update threads_cache (for reply count/last post date in list view)
-        if let Some(cached) = self.threads_cache.get(group).await {
+        if let Some(cached) = self.threads_cache.get(&group_key).await {
             let mut threads = cached.threads.clone();
 
             if let Some(thread) = threads
@@ -907,6 +1474,7 @@ impl NntpFederatedService {
                 // Add reply to thread tree
                 if add_reply_to_node(&mut thread.root, parent_msg_id, new_node) {
                     thread.article_count += 1;
+                    thread.update_stats();
                     thread.last_post_date = Some(article.date.clone());
                     thread.last_post_date_relative = Some(compute_timeago(&article.date));
 
@@ -921,10 +1489,11 @@ impl NntpFederatedService {
 
             self.threads_cache
                 .insert(
-                    group.to_string(),
+                    group_key,
                     CachedThreads {
                         threads,
                         last_article_number: cached.last_article_number,
+                        fetched_at: cached.fetched_at,
                     },
                 )
                 .await;
@@ -944,10 +1513,102 @@ impl NntpFederatedService {
         );
         // Per-group refresh tasks are spawned on-demand in mark_group_active()
 
+        // Spawn upstream availability sampling
+        self.spawn_availability_probe();
+
         // Spawn hourly group stats refresh
         self.spawn_group_stats_refresh();
     }
 
+    /// Spawn a periodic task sampling whether each server is reachable.
+    /// A server that was down at the previous sample and is up now gets its
+    /// most active groups rewarmed.
+    fn spawn_availability_probe(&self) {
+        let this = self.clone();
+        tokio::spawn(async move {
+            // Unknown until the first sample, so startup is not taken for a recovery
+            let mut was_connected: Vec<Option<bool>> = vec![None; this.services.len()];
+            // First sample after one interval, once workers have had time to connect
+            loop {
+                tokio::time::sleep(Duration::from_secs(AVAILABILITY_PROBE_INTERVAL_SECS)).await;
+                let today = Utc::now().date_naive();
+                let mut recovered = Vec::new();
+                {
+                    let mut history = this.availability.write().await;
+                    for (idx, service) in this.services.iter().enumerate() {
+                        let connected = service.is_connected();
+                        history.record(service.name(), connected, today);
+                        if was_connected[idx] == Some(false) && connected {
+                            recovered.push(idx);
+                        }
+                        was_connected[idx] = Some(connected);
+                    }
+                }
+                for idx in recovered {
+                    let this = this.clone();
+                    tokio::spawn(async move { this.warm_up_after_recovery(idx).await });
+                }
+            }
+        });
+    }
+
+    /// Rewarm caches for a server that just became reachable again.
+    ///
+    /// Requests during the outage failed over to other servers or failed, so
+    /// the busiest groups this server carries may have stale stats, missing
+    /// articles or no cached thread list. For each, group stats are refetched
+    /// and the thread list is either incrementally updated from the high water
+    /// mark or, if it was evicted, fetched again (which resets the mark).
+    /// Groups are processed a few at a time so the recovered server isn't
+    /// flooded.
+    async fn warm_up_after_recovery(&self, server_idx: usize) {
+        let server = self.services[server_idx].name();
+        let busiest = self
+            .activity_tracker
+            .write()
+            .await
+            .busiest_groups(RECOVERY_WARMUP_MAX_GROUPS);
+        let groups: Vec<String> = {
+            let mapping = self.group_servers.read().await;
+            busiest
+                .into_iter()
+                .filter(|group| {
+                    mapping
+                        .get(group)
+                        .is_none_or(|indices| indices.contains(&server_idx))
+                })
+                .collect()
+        };
+
+        tracing::info!(%server, groups = groups.len(), "Server recovered, rewarming caches");
+        futures::stream::iter(groups)
+            .for_each_concurrent(RECOVERY_WARMUP_CONCURRENCY, |group| async move {
+                if let Err(e) = self.fetch_group_stats_coalesced(&group).await {
+                    tracing::debug!(%group, error = %e, "Warmup stats fetch failed");
+                }
+
+                // Catching up from the current HWM picks up articles posted
+                // during the outage and advances the HWM past them
+                if self
+                    .threads_cache
+                    .contains_key(&self.group_key(&group).await)
+                {
+                    self.trigger_incremental_update(&group).await;
+                } else if let Err(e) = self.get_threads(&group, 0).await {
+                    tracing::debug!(%group, error = %e, "Warmup thread fetch failed");
+                }
+            })
+            .await;
+        tracing::info!(%server, "Recovery warmup complete");
+    }
+
+    /// Availability history of every server for the status page
+    pub async fn availability_report(&self) -> Vec<ServerAvailability> {
+        let names = self.server_names();
+        let today = Utc::now().date_naive();
+        self.availability.read().await.report(&names, today)
+    }
+
     /// Spawn a periodic task to refresh stats for a single group.
     /// Runs forever, refreshing once per hour.
     fn spawn_group_stats_refresh_task(&self, group: String) -> JoinHandle<()> {
@@ -971,9 +1632,12 @@ impl NntpFederatedService {
 
                     let mut tasks = self.group_stats_tasks.write().await;
 
-                    // Abort tasks for removed groups
+                    // Abort tasks for removed groups and drop finished ones,
+                    // which are respawned below if the group still exists
                     tasks.retain(|name, handle| {
-                        if current_names.contains(name) {
+                        if handle.is_finished() {
+                            false
+                        } else if current_names.contains(name) {
                             true
                         } else {
                             handle.abort();
@@ -997,6 +1661,12 @@ impl NntpFederatedService {
         });
     }
 
+    /// Apply footer rules to an article shown on its own page, for `group`
+    /// when known, else for the groups in its Newsgroups header
+    pub async fn strip_footers(&self, group: Option<&str>, article: &mut ArticleView) {
+        self.footers.apply_to_article(group, article).await;
+    }
+
     /// Fetch an article by message ID
     /// Tries each server in order until the article is found
     #[instrument(
@@ -1007,7 +1677,8 @@ impl NntpFederatedService {
     pub async fn get_article(&self, message_id: &str) -> Result<ArticleView, AppError> {
         let start = Instant::now();
         // Check positive cache first
-        if let Some(article) = self.article_cache.get(message_id).await {
+        if let Some(article) = self.cached_article(message_id).await {
+            metrics().article_cache.record(true);
             tracing::Span::current().record("cache_hit", true);
             tracing::Span::current().record("duration_ms", start.elapsed().as_millis() as u64);
             return Ok(article);
@@ -1015,29 +1686,103 @@ impl NntpFederatedService {
 
         // Check negative cache - if we recently determined this article doesn't exist, fail fast
         if self.article_not_found_cache.get(message_id).await.is_some() {
+            metrics().article_cache.record(true);
             tracing::Span::current().record("cache_hit", true);
             tracing::Span::current().record("duration_ms", start.elapsed().as_millis() as u64);
             return Err(AppError::ArticleNotFound(message_id.to_string()));
         }
+        metrics().article_cache.record(false);
+
+        let result = self.fetch_article_from_servers(message_id).await;
+        tracing::Span::current().record("duration_ms", start.elapsed().as_millis() as u64);
+        result
+    }
+
+    /// Look up an article in the cache. Past the soft TTL the cached copy is
+    /// still returned while a background fetch replaces it.
+    async fn cached_article(&self, message_id: &str) -> Option<ArticleView> {
+        let cached = self.article_cache.get(message_id).await?;
+        if cached.is_stale(self.soft_ttl.article) {
+            self.spawn_article_refresh(message_id).await;
+        }
+        Some(cached.value)
+    }
+
+    /// Refetch a cached article in the background, at most once at a time.
+    /// An article that has since disappeared (e.g. cancelled) is dropped.
+    async fn spawn_article_refresh(&self, message_id: &str) {
+        if !self
+            .refreshing_articles
+            .write()
+            .await
+            .insert(message_id.to_string())
+        {
+            return;
+        }
+
+        let this = self.clone();
+        let message_id = message_id.to_string();
+        tokio::spawn(async move {
+            match this.fetch_article_from_servers(&message_id).await {
+                Err(AppError::ArticleNotFound(_)) => {
+                    this.article_cache.invalidate(&message_id).await;
+                }
+                Err(e) => {
+                    tracing::debug!(%message_id, error = %e, "Article refresh failed");
+                }
+                Ok(_) => {}
+            }
+            this.refreshing_articles.write().await.remove(&message_id);
+        });
+    }
 
-        // Try each server in priority order
+    /// Fetch an article from the servers in priority order and cache the result.
+    /// Articles every server reports missing go into the negative cache.
+    ///
+    /// A server that fails passes the request on to the next one. With a
+    /// hedge delay, so does one that hasn't answered in time, but its request
+    /// keeps running: the first article to arrive wins and the requests
+    /// still waiting are cancelled.
+    async fn fetch_article_from_servers(&self, message_id: &str) -> Result<ArticleView, AppError> {
         let mut last_error = None;
         let mut all_not_found = true;
 
-        for service in &self.services {
-            match service.get_article(message_id).await {
+        let mut servers = self.services_by_health().peekable();
+        let mut in_flight = FuturesUnordered::new();
+        let mut outcomes = Vec::new();
+        while let Some(service) = servers.next() {
+            in_flight.push(service.get_article(message_id));
+            let hedge = self.hedge_delay.filter(|_| servers.peek().is_some());
+            tokio::select! {
+                Some(result) = in_flight.next() => outcomes.push(result),
+                _ = tokio::time::sleep(hedge.unwrap_or_default()), if hedge.is_some() => {
+                    tracing::debug!(%message_id, "Article fetch slow, asking the next server too");
+                }
+            }
+            if outcomes.last().is_some_and(Result::is_ok) {
+                break;
+            }
+        }
+        // No servers left to ask: wait for those still working on it
+        while outcomes.last().is_none_or(Result::is_err) {
+            let Some(result) = in_flight.next().await else {
+                break;
+            };
+            outcomes.push(result);
+        }
+
+        for outcome in outcomes {
+            match outcome {
                 Ok(article) => {
                     // Cache positive result and return
                     self.article_cache
-                        .insert(message_id.to_string(), article.clone())
+                        .insert(message_id.to_string(), Stamped::new(article.clone()))
                         .await;
-                    tracing::Span::current()
-                        .record("duration_ms", start.elapsed().as_millis() as u64);
                     return Ok(article);
                 }
                 Err(e) => {
                     // Track if we've seen any non-"not found" errors
-                    if !Self::is_not_found_error(&e) {
+                    if !e.is_article_missing() {
                         all_not_found = false;
                     }
 
@@ -1055,37 +1800,161 @@ impl NntpFederatedService {
             self.article_not_found_cache
                 .insert(message_id.to_string(), ())
                 .await;
-            tracing::Span::current().record("duration_ms", start.elapsed().as_millis() as u64);
             return Err(AppError::ArticleNotFound(message_id.to_string()));
         }
 
         // Had some transient errors - don't cache, just return the error
-        tracing::Span::current().record("duration_ms", start.elapsed().as_millis() as u64);
         Err(last_error
-            .map(|e| AppError::Internal(e.0))
+            .map(|e| AppError::Internal(e.to_string()))
             .unwrap_or_else(|| AppError::Internal("No NNTP servers configured".into())))
     }
 
-    /// Fetch recent threads from a newsgroup with incremental update support.
-    /// On cache hit, checks for new articles and fetches only the delta.
-    /// The count parameter is ignored; uses max_articles_per_group from config.
-    #[instrument(
-        name = "nntp.federated.get_threads",
-        skip(self),
-        fields(cache_hit = false, duration_ms)
-    )]
-    pub async fn get_threads(&self, group: &str, _count: u64) -> Result<Vec<ThreadView>, AppError> {
-        let start = Instant::now();
-        let cache_key = group.to_string();
-        let max_articles = self.max_articles_per_group;
+    /// Fetch several articles, for pages that need many bodies at once.
+    /// Cached articles are served from the cache. The rest go to each server in
+    /// priority order as one `GetArticle` each, at most `body_fetch_concurrency`
+    /// at a time; whatever a server lacks moves on to the next one. Articles that could not be fetched are left out of the
+    /// returned map. The returned set holds those that failed for a reason
+    /// other than every server lacking them, so a page can tell "try again"
+    /// from "gone".
+    pub async fn get_articles(
+        &self,
+        message_ids: &[String],
+    ) -> (HashMap<String, ArticleView>, HashSet<String>) {
+        self.fetch_articles(message_ids, false).await
+    }
 
-        // Check cache first
-        if let Some(cached) = self.threads_cache.get(&cache_key).await {
-            tracing::Span::current().record("cache_hit", true);
+    /// `get_articles`, or with `prefetch` the same fetch sent to each server's
+    /// low-priority queue to warm the cache. Prefetches stay out of the cache
+    /// hit-rate metrics, which describe what readers waited for.
+    async fn fetch_articles(
+        &self,
+        message_ids: &[String],
+        prefetch: bool,
+    ) -> (HashMap<String, ArticleView>, HashSet<String>) {
+        let mut articles = HashMap::new();
+        let mut missing = Vec::new();
+        for message_id in message_ids {
+            let hit = if let Some(article) = self.cached_article(message_id).await {
+                articles.insert(message_id.clone(), article);
+                true
+            } else if self.article_not_found_cache.contains_key(message_id) {
+                true
+            } else {
+                missing.push(message_id.clone());
+                false
+            };
+            if !prefetch {
+                metrics().article_cache.record(hit);
+            }
+        }
 
-            // Stale-while-revalidate: return cached data immediately,
-            // trigger background refresh if debounce period has elapsed
-            if self.should_check_incremental(group).await {
+        // IDs some server failed on for a reason other than "not found"
+        let mut failed: HashSet<String> = HashSet::new();
+        for service in self.services_by_health() {
+            if missing.is_empty() {
+                break;
+            }
+            // Only a few fetches in flight, so one long page can't fill the
+            // server's request queue ahead of other visitors
+            let mut fetches = futures::stream::iter(std::mem::take(&mut missing))
+                .map(|message_id: String| async move {
+                    let result = if prefetch {
+                        service.prefetch_article(&message_id).await
+                    } else {
+                        service.get_article(&message_id).await
+                    };
+                    (message_id, result)
+                })
+                .buffer_unordered(self.body_fetch_concurrency);
+
+            while let Some((message_id, result)) = fetches.next().await {
+                match result {
+                    Ok(article) => {
+                        self.article_cache
+                            .insert(message_id.clone(), Stamped::new(article.clone()))
+                            .await;
+                        articles.insert(message_id, article);
+                    }
+                    Err(e) => {
+                        if !e.is_article_missing() {
+                            tracing::debug!(server = service.name(), %message_id, error = %e, "Article fetch failed");
+                            failed.insert(message_id.clone());
+                        }
+                        missing.push(message_id);
+                    }
+                }
+            }
+        }
+
+        // Negative-cache articles every server reported missing
+        let mut unavailable = HashSet::new();
+        for message_id in missing {
+            if failed.contains(&message_id) {
+                tracing::warn!(%message_id, "Failed to fetch article body");
+                unavailable.insert(message_id);
+            } else {
+                self.article_not_found_cache.insert(message_id, ()).await;
+            }
+        }
+
+        (articles, unavailable)
+    }
+
+    /// Warm the article cache with `message_ids` in the background, at low
+    /// priority so the fetch never holds up a page someone is waiting for
+    fn spawn_prefetch(&self, message_ids: Vec<String>) {
+        if message_ids.is_empty() {
+            return;
+        }
+        let this = self.clone();
+        tokio::spawn(async move {
+            let (articles, _) = this.fetch_articles(&message_ids, true).await;
+            tracing::debug!(
+                requested = message_ids.len(),
+                fetched = articles.len(),
+                "Prefetched article bodies"
+            );
+        });
+    }
+
+    /// Fetch recent threads from a newsgroup with incremental update support.
+    /// On cache hit, checks for new articles and fetches only the delta.
+    /// The count parameter is ignored; the group's fetch window sets the size.
+    #[instrument(
+        name = "nntp.federated.get_threads",
+        skip(self),
+        fields(cache_hit = false, duration_ms)
+    )]
+    pub async fn get_threads(&self, group: &str, _count: u64) -> Result<Vec<ThreadView>, AppError> {
+        let group = self.canonical_group(group);
+        let start = Instant::now();
+        let cache_key = self.group_key(group).await;
+
+        // Check cache first
+        let cached = self.threads_cache.get(&cache_key).await;
+        metrics().threads_cache.record(cached.is_some());
+        if let Some(cached) = cached {
+            tracing::Span::current().record("cache_hit", true);
+
+            // Stale-while-revalidate: return cached data immediately. Past the
+            // TTL, replace the list with one shared full fetch, unless one is
+            // already running; otherwise check for new articles if the
+            // debounce period has elapsed.
+            if cached.fetched_at.elapsed() >= self.soft_ttl.threads {
+                if !self.pending_threads.read().await.contains_key(group) {
+                    let self_clone = self.clone();
+                    let group_clone = group.to_string();
+                    tokio::spawn(async move {
+                        if let Err(e) = self_clone.fetch_threads_single_flight(&group_clone).await {
+                            tracing::debug!(
+                                group = %group_clone,
+                                error = %e,
+                                "Thread list refresh failed"
+                            );
+                        }
+                    });
+                }
+            } else if self.should_check_incremental(group).await {
                 // Spawn background task to check for new articles
                 let self_clone = self.clone();
                 let group_clone = group.to_string();
@@ -1115,6 +1984,7 @@ impl NntpFederatedService {
                                         CachedThreads {
                                             threads: merged,
                                             last_article_number: new_hwm,
+                                            fetched_at: current.fetched_at,
                                         },
                                     )
                                     .await;
@@ -1133,15 +2003,87 @@ impl NntpFederatedService {
             return Ok(cached.threads);
         }
 
-        // Cache miss - full fetch
+        // Cache miss - full fetch, shared with any concurrent misses
+        if let Err(e) = self.check_group_not_found(group).await {
+            tracing::Span::current().record("duration_ms", start.elapsed().as_millis() as u64);
+            return Err(e);
+        }
+        let result = self.fetch_threads_single_flight(group).await;
+        tracing::Span::current().record("duration_ms", start.elapsed().as_millis() as u64);
+        result
+    }
+
+    /// Fetch a group's full thread list, sharing one in-flight fetch among
+    /// all callers. On startup, or when a popular group's list expires, many
+    /// requests miss at once; without this each would fan out to the servers.
+    async fn fetch_threads_single_flight(&self, group: &str) -> Result<Vec<ThreadView>, AppError> {
+        let (tx, _) = broadcast::channel(BROADCAST_CHANNEL_CAPACITY);
+        let waiting = {
+            let mut pending = self.pending_threads.write().await;
+            match pending.get(group) {
+                Some(existing_tx) => Some(existing_tx.subscribe()),
+                None => {
+                    pending.insert(group.to_string(), tx.clone());
+                    None
+                }
+            }
+        };
+
+        if let Some(mut rx) = waiting {
+            tracing::debug!(%group, "Joining in-flight thread list fetch");
+            return match rx.recv().await {
+                Ok(Ok(threads)) => Ok((*threads).clone()),
+                Ok(Err(None)) => Err(AppError::GroupNotFound(group.to_string())),
+                Ok(Err(Some(e))) => Err(AppError::Internal(e)),
+                // The caller we joined went away; fetch it ourselves
+                Err(RecvError::Closed) => self.fetch_threads_from_servers(group).await,
+                Err(_) => Err(AppError::Internal("Broadcast channel closed".into())),
+            };
+        }
+        let cancel = {
+            let pending = self.pending_threads.clone();
+            let group = group.to_string();
+            OnCancel::new(async move {
+                pending.write().await.remove(&group);
+            })
+        };
+
+        let result = self.fetch_threads_from_servers(group).await;
+
+        // Broadcast result to waiters and cleanup
+        {
+            let mut pending = self.pending_threads.write().await;
+            pending.remove(group);
+        }
+        cancel.disarm();
+        let broadcast_result = match &result {
+            Ok(threads) => Ok(Arc::new(threads.clone())),
+            Err(AppError::GroupNotFound(_)) => Err(None),
+            Err(AppError::Internal(e)) => Err(Some(e.clone())),
+            Err(e) => Err(Some(e.to_string())),
+        };
+        let _ = tx.send(broadcast_result);
+
+        result
+    }
+
+    /// Fetch a group's full thread list from the first server that has it
+    /// and cache it. Callers should go through `fetch_threads_single_flight`.
+    async fn fetch_threads_from_servers(&self, group: &str) -> Result<Vec<ThreadView>, AppError> {
+        // Taken before fetching, so a list fetched across a purge is orphaned
+        let cache_key = self.group_key(group).await;
+
+        let fetch_count = self.fetch_count(group).await;
+
         // Get servers for this group (smart dispatch)
         let server_indices = self.get_servers_for_group(group).await;
 
         // Try only relevant servers
         let mut last_error = None;
+        let mut all_not_found = true;
         for idx in server_indices {
             let service = &self.services[idx];
-            match service.get_threads(group, max_articles).await {
+            match service.get_threads(group, fetch_count).await {
                 Ok(threads) => {
                     // Get the high water mark from cached group stats (non-blocking).
                     // If not cached, use 0 and trigger async prefetch.
@@ -1168,22 +2110,26 @@ impl NntpFederatedService {
                             CachedThreads {
                                 threads: threads.clone(),
                                 last_article_number,
+                                fetched_at: Instant::now(),
                             },
                         )
                         .await;
 
-                    tracing::Span::current()
-                        .record("duration_ms", start.elapsed().as_millis() as u64);
                     return Ok(threads);
                 }
                 Err(e) => {
+                    if !e.is_group_missing() {
+                        all_not_found = false;
+                    }
                     last_error = Some(e);
                 }
             }
         }
 
-        // All servers failed
-        tracing::Span::current().record("duration_ms", start.elapsed().as_millis() as u64);
+        // All servers failed - cache negative result if every one lacks the group
+        if all_not_found && last_error.is_some() {
+            self.cache_group_not_found(group).await;
+        }
         Err(last_error
             .map(|e| Self::nntp_error_to_app_error(e, group))
             .unwrap_or_else(|| AppError::GroupNotFound(group.to_string())))
@@ -1225,7 +2171,7 @@ impl NntpFederatedService {
         }
 
         Err(last_error
-            .map(|e| AppError::Internal(e.0))
+            .map(|e| AppError::Internal(e.to_string()))
             .unwrap_or_else(|| AppError::Internal("Failed to fetch new articles".into())))
     }
 
@@ -1233,12 +2179,27 @@ impl NntpFederatedService {
     /// Returns None if stats are not cached. Does NOT fetch from server to avoid
     /// blocking high-priority operations on low-priority group stats requests.
     async fn get_last_article_number_cached(&self, group: &str) -> Option<u64> {
-        if let Some(stats) = self.group_stats_cache.get(group).await {
-            return Some(stats.last_article_number);
+        let cache_key = self.group_key(group).await;
+        if let Some(stats) = self.group_stats_cache.get(&cache_key).await {
+            return Some(stats.value.last_article_number);
         }
         None
     }
 
+    /// Articles to fetch for a group's thread list, from the posting rate in
+    /// cached group stats. Uses the minimum until stats have been fetched.
+    async fn fetch_count(&self, group: &str) -> u64 {
+        let cache_key = self.group_key(group).await;
+        let posts_per_day = self
+            .group_stats_cache
+            .get(&cache_key)
+            .await
+            .and_then(|stats| stats.value.posts_per_day);
+        let count = self.fetch_window.articles(posts_per_day);
+        tracing::debug!(%group, ?posts_per_day, count, "Sized thread list fetch");
+        count
+    }
+
     /// Trigger async prefetch of group stats if not cached.
     /// Used to populate the high water mark for incremental updates.
     fn prefetch_group_stats_if_needed(&self, group: &str) {
@@ -1246,7 +2207,8 @@ impl NntpFederatedService {
         let this = self.clone();
         tokio::spawn(async move {
             // Check cache first to avoid unnecessary work
-            if this.group_stats_cache.get(&group).await.is_none() {
+            let cache_key = this.group_key(&group).await;
+            if this.group_stats_cache.get(&cache_key).await.is_none() {
                 let _ = this.get_group_stats(&group).await;
             }
         });
@@ -1255,14 +2217,97 @@ impl NntpFederatedService {
     /// Fetch paginated threads from a newsgroup.
     /// Fetches a larger batch and returns the requested page slice.
     /// Threads are sorted in reverse-chronological order by last reply date.
+    ///
+    /// Each sorted list is kept as a snapshot identified by a content token,
+    /// returned alongside the page. Passing that token back serves later pages
+    /// from the same snapshot, so background merges can't shift threads between
+    /// pages while a user is paging. Unknown or expired tokens fall back to the
+    /// current list (and a new token).
+    ///
+    /// The filter is applied to the snapshot before paginating, so page counts
+    /// reflect only matching threads.
     pub async fn get_threads_paginated(
         &self,
         group: &str,
         page: usize,
         per_page: usize,
-    ) -> Result<(Vec<ThreadView>, PaginationInfo), AppError> {
-        // Fetch using configured max_articles_per_group
-        let mut all_threads = self.get_threads(group, self.max_articles_per_group).await?;
+        snapshot: Option<&str>,
+        filter: &ThreadFilter,
+    ) -> Result<(Vec<ThreadView>, PaginationInfo, String), AppError> {
+        let group = self.canonical_group(group);
+        let group_key = self.group_key(group).await;
+        let cached_snapshot = match snapshot {
+            Some(token) => self
+                .thread_list_snapshots
+                .get(&format!("{}:{}", group_key, token))
+                .await
+                .map(|threads| (token.to_string(), threads)),
+            None => None,
+        };
+
+        let (token, all_threads) = match cached_snapshot {
+            Some(hit) => {
+                // Snapshot reads still count as activity for background refresh
+                self.mark_group_active(group).await;
+                hit
+            }
+            None => {
+                let threads = Arc::new(self.get_sorted_threads(group).await?);
+                let token = thread_list_fingerprint(&threads);
+                self.thread_list_snapshots
+                    .insert(format!("{}:{}", group_key, token), threads.clone())
+                    .await;
+                (token, threads)
+            }
+        };
+
+        let matching: Vec<&ThreadView> = if filter.is_active() {
+            all_threads.iter().filter(|t| filter.matches(t)).collect()
+        } else {
+            all_threads.iter().collect()
+        };
+
+        let total = matching.len();
+        let pagination = PaginationInfo::new(page, total, per_page);
+
+        // Slice for current page
+        let start = (page - 1) * per_page;
+        let end = (start + per_page).min(total);
+
+        let page_threads = if start < total {
+            matching[start..end].iter().map(|t| (*t).clone()).collect()
+        } else {
+            Vec::new()
+        };
+
+        Ok((page_threads, pagination, token))
+    }
+
+    /// Find near-duplicate roots among a page of threads.
+    /// Roots whose bodies are already cached are fingerprinted first, so no
+    /// extra articles are fetched.
+    /// Returns a map of duplicate root message_id -> original root message_id.
+    pub async fn duplicate_roots(&self, threads: &[ThreadView]) -> HashMap<String, String> {
+        for thread in threads {
+            if let Some(article) = self.cached_article(&thread.root_message_id).await {
+                self.duplicates.observe_root(&article).await;
+            }
+        }
+        // Collected first, as a lazy iterator held across the await would
+        // keep the future from being Send
+        let roots: Vec<&str> = threads.iter().map(|t| t.root_message_id.as_str()).collect();
+        self.duplicates.duplicates_of(roots).await
+    }
+
+    /// Fetch all threads for a group sorted newest-first by last reply date.
+    async fn get_sorted_threads(&self, group: &str) -> Result<Vec<ThreadView>, AppError> {
+        // Fetch size comes from the group's fetch window
+        let all_threads = self
+            .get_threads(group, self.fetch_window.max_articles)
+            .await?;
+
+        // Normalize subjects and fold in replies that lost their References
+        let all_threads = self.subject_rules.group_threads(all_threads);
 
         // Sort threads by last_post_date in reverse-chronological order (newest first)
         // Pre-parse RFC 2822 dates once to avoid O(N log N) parsing overhead
@@ -1270,10 +2315,7 @@ impl NntpFederatedService {
             .iter()
             .enumerate()
             .map(|(i, thread)| {
-                let parsed = thread
-                    .last_post_date
-                    .as_ref()
-                    .and_then(|d| DateTime::parse_from_rfc2822(d).ok());
+                let parsed = thread.last_post_date.as_deref().and_then(parse_date);
                 (i, parsed)
             })
             .collect();
@@ -1291,22 +2333,8 @@ impl NntpFederatedService {
             .into_iter()
             .map(|(i, _)| all_threads[i].clone())
             .collect();
-        all_threads = sorted_threads;
-
-        let total = all_threads.len();
-        let pagination = PaginationInfo::new(page, total, per_page);
-
-        // Slice for current page
-        let start = (page - 1) * per_page;
-        let end = (start + per_page).min(total);
 
-        let page_threads = if start < total {
-            all_threads[start..end].to_vec()
-        } else {
-            Vec::new()
-        };
-
-        Ok((page_threads, pagination))
+        Ok(sorted_threads)
     }
 
     /// Fetch a single thread by group and root message ID
@@ -1317,11 +2345,15 @@ impl NntpFederatedService {
         fields(cache_hit = false, duration_ms)
     )]
     pub async fn get_thread(&self, group: &str, message_id: &str) -> Result<ThreadView, AppError> {
+        let group = self.canonical_group(group);
         let start = Instant::now();
-        let cache_key = format!("{}:{}", group, message_id);
+        let group_key = self.group_key(group).await;
+        let cache_key = format!("{}:{}", group_key, message_id);
 
         // Check cache first
-        if let Some(cached) = self.thread_cache.get(&cache_key).await {
+        let cached = self.thread_cache.get(&cache_key).await;
+        metrics().thread_cache.record(cached.is_some());
+        if let Some(cached) = cached {
             tracing::Span::current().record("cache_hit", true);
 
             // Stale-while-revalidate: return cached data immediately,
@@ -1373,14 +2405,14 @@ impl NntpFederatedService {
         // Ensure threads_cache is populated for this group.
         // This blocks on first access but subsequent requests use cache,
         // and background refresh handles incremental updates.
-        if self.threads_cache.get(group).await.is_none() {
+        if self.threads_cache.get(&group_key).await.is_none() {
             self.get_threads(group, 0).await?;
         }
 
         // Look up the thread from threads_cache
         let cached_threads = self
             .threads_cache
-            .get(group)
+            .get(&group_key)
             .await
             .ok_or_else(|| AppError::Internal("Failed to populate threads cache".into()))?;
 
@@ -1412,7 +2444,9 @@ impl NntpFederatedService {
     }
 
     /// Fetch a thread with paginated article bodies.
-    /// Only fetches bodies for articles on the current page.
+    /// Only fetches bodies for articles on the current page, and with
+    /// `BodyFetch::Visible` not for those hidden under a collapsed comment:
+    /// they are marked `deferred_under` and loaded by `get_thread_subtree`.
     pub async fn get_thread_paginated(
         &self,
         group: &str,
@@ -1420,71 +2454,165 @@ impl NntpFederatedService {
         page: usize,
         per_page: usize,
         collapse_threshold: usize,
+        fetch: BodyFetch<'_>,
     ) -> Result<(ThreadView, Vec<FlatComment>, PaginationInfo), AppError> {
-        // Get thread metadata (uses existing cache)
-        let thread = self.get_thread(group, message_id).await?;
+        let thread = self.get_thread_with_orphans(group, message_id).await?;
 
-        // Flatten and determine which message IDs need bodies
-        let (mut comments, pagination, page_msg_ids) =
+        let (mut comments, pagination, _) =
             thread
                 .root
                 .flatten_paginated(page, per_page, collapse_threshold);
 
-        // Collect bodies: check article cache first, then fetch missing ones
-        let mut bodies: HashMap<String, ArticleView> = HashMap::new();
-        let mut needed_ids: Vec<String> = Vec::new();
-
-        for msg_id in &page_msg_ids {
-            if let Some(article) = self.article_cache.get(msg_id).await {
-                bodies.insert(msg_id.clone(), article);
-            } else {
-                needed_ids.push(msg_id.clone());
+        // Bodies for the current page only
+        let start = ((page - 1) * per_page).min(comments.len());
+        let end = (start + per_page).min(comments.len());
+        let page_comments = &mut comments[start..end];
+        if let BodyFetch::Visible { expand } = fetch {
+            let hidden = collapsed_ancestors(page_comments, expand);
+            for (comment, under) in page_comments.iter_mut().zip(hidden) {
+                comment.deferred_under = under;
             }
         }
-
-        // Fetch missing bodies concurrently across the worker pool
-        // Map each message ID to a fetch future
-        let fetch_futures: Vec<_> = needed_ids
-            .into_iter()
-            .map(|msg_id| async move {
-                let result = self.get_article(&msg_id).await;
-                (msg_id, result)
-            })
+        let page_msg_ids: Vec<String> = page_comments
+            .iter()
+            .filter(|c| c.deferred_under.is_none())
+            .map(|c| c.message_id.clone())
             .collect();
 
-        // Execute all fetches concurrently and collect results
-        let fetch_results = futures::future::join_all(fetch_futures).await;
+        // Collect bodies from the article cache, fetching missing ones in parallel
+        let (bodies, unavailable) = self.get_articles(&page_msg_ids).await;
 
-        // Process results and populate the bodies map
-        for (msg_id, result) in fetch_results {
-            match result {
-                Ok(article) => {
-                    bodies.insert(msg_id, article);
-                }
-                Err(e) => {
-                    tracing::warn!(%msg_id, error = %e, "Failed to fetch article body");
-                }
-            }
+        // Fingerprint the root body for duplicate detection
+        if let Some(root) = bodies.get(&thread.root_message_id) {
+            self.duplicates.observe_root(root).await;
         }
 
-        // Populate bodies in the flattened comments for current page only
-        let page_ids_set: std::collections::HashSet<String> = page_msg_ids.into_iter().collect();
-        let start = (page - 1) * per_page;
+        fill_bodies(page_comments, &bodies, &unavailable);
+        self.footers
+            .apply_to_comments(group, &mut comments[start..end])
+            .await;
+        quotes::link_quotes(&mut comments[..end], start);
+
+        // The reader is likely to go on to the next page: fetch its bodies
+        // while they read this one
+        if end < comments.len() {
+            let next_comments = &comments[end..(end + per_page).min(comments.len())];
+            let next_ids = match fetch {
+                BodyFetch::All => next_comments.iter().map(|c| c.message_id.clone()).collect(),
+                BodyFetch::Visible { .. } => next_comments
+                    .iter()
+                    .zip(collapsed_ancestors(next_comments, None))
+                    .filter(|(_, under)| under.is_none())
+                    .map(|(c, _)| c.message_id.clone())
+                    .collect(),
+            };
+            self.spawn_prefetch(next_ids);
+        }
+
+        Ok((thread, comments, pagination))
+    }
+
+    /// The comments under `expand` on one page of a thread, with bodies:
+    /// what a page with deferred bodies loads when that comment is expanded.
+    /// Empty if `expand` is not on the page.
+    pub async fn get_thread_subtree(
+        &self,
+        group: &str,
+        message_id: &str,
+        expand: &str,
+        page: usize,
+        per_page: usize,
+    ) -> Result<Vec<FlatComment>, AppError> {
+        let thread = self.get_thread_with_orphans(group, message_id).await?;
+        let comments = thread.root.flatten(usize::MAX);
+
+        let start = ((page - 1) * per_page).min(comments.len());
         let end = (start + per_page).min(comments.len());
+        let mut page_comments = comments[start..end].iter();
+        let Some(depth) = page_comments
+            .by_ref()
+            .find(|c| c.message_id == expand)
+            .map(|c| c.depth)
+        else {
+            return Ok(Vec::new());
+        };
+        let mut subtree: Vec<FlatComment> = page_comments
+            .take_while(|c| c.depth > depth)
+            .cloned()
+            .collect();
 
-        for (i, comment) in comments.iter_mut().enumerate() {
-            if i >= start && i < end && page_ids_set.contains(&comment.message_id) {
-                if let Some(fetched) = bodies.get(&comment.message_id) {
-                    if let Some(ref mut article) = comment.article {
-                        article.body = fetched.body.clone();
-                        article.body_preview = fetched.body_preview.clone();
-                        article.has_more_content = fetched.has_more_content;
-                    }
-                }
+        let ids: Vec<String> = subtree.iter().map(|c| c.message_id.clone()).collect();
+        let (bodies, unavailable) = self.get_articles(&ids).await;
+        fill_bodies(&mut subtree, &bodies, &unavailable);
+        self.footers.apply_to_comments(group, &mut subtree).await;
+        quotes::link_quotes(&mut subtree, 0);
+        Ok(subtree)
+    }
+
+    /// A thread with replies that lost their References folded in, as the
+    /// thread list does
+    async fn get_thread_with_orphans(
+        &self,
+        group: &str,
+        message_id: &str,
+    ) -> Result<ThreadView, AppError> {
+        // Get thread metadata (uses existing cache)
+        let mut thread = self.get_thread(group, message_id).await?;
+
+        let candidates = self
+            .threads_cache
+            .get(&self.group_key(self.canonical_group(group)).await)
+            .await
+            .map(|cached| cached.threads)
+            .unwrap_or_default();
+        self.subject_rules.attach_orphans(&mut thread, &candidates);
+        Ok(thread)
+    }
+
+    /// Tally "+1"/"-1" and "Vote:" replies in a thread, for groups enabled
+    /// in `[votes]`. Reads up to `max_replies` bodies in parallel, from the
+    /// article cache where possible, and keeps the tally until the thread
+    /// gains a reply. Returns `None` for other groups or when nobody voted.
+    pub async fn vote_tally(&self, group: &str, thread: &ThreadView) -> Option<VoteTally> {
+        let group = self.canonical_group(group);
+        if self.votes.groups.is_empty() || !super::wildmat(&self.votes.groups, group) {
+            return None;
+        }
+        let cache_key = format!(
+            "{}:{}:{}",
+            self.group_key(group).await,
+            thread.root_message_id,
+            thread.article_count
+        );
+        if let Some(tally) = self.vote_tallies.get(&cache_key).await {
+            return tally;
+        }
+
+        // Replies in thread order; the root is the proposal, not a vote
+        let mut reply_ids: Vec<String> = Vec::new();
+        let mut stack: Vec<&ThreadNodeView> = thread.root.replies.iter().rev().collect();
+        while let Some(node) = stack.pop() {
+            if reply_ids.len() >= self.votes.max_replies {
+                break;
             }
+            reply_ids.push(node.message_id.clone());
+            stack.extend(node.replies.iter().rev());
         }
 
-        Ok((thread, comments, pagination))
+        let (mut articles, failed) = self.get_articles(&reply_ids).await;
+        let replies: Vec<ArticleView> = reply_ids
+            .iter()
+            .filter_map(|msg_id| articles.remove(msg_id))
+            .collect();
+
+        let tally = votes::tally(&replies);
+        // A failed fetch may leave votes out; count again on the next view
+        if failed.is_empty() {
+            self.vote_tallies.insert(cache_key, tally.clone()).await;
+        } else {
+            tracing::debug!(failed = failed.len(), "Vote tally left uncached");
+        }
+        tally
     }
 
     /// Check if we should refresh the groups list (debounced).
@@ -1506,20 +2634,26 @@ impl NntpFederatedService {
         true
     }
 
-    /// Fetch groups from all servers and update caches.
+    /// Fetch groups from all servers concurrently and update caches.
     /// This is the actual fetch logic, separated for reuse in background refresh.
     async fn fetch_groups_from_servers(&self) -> Result<Vec<GroupView>, AppError> {
         let cache_key = "groups".to_string();
 
         // Collect groups from all servers AND track server associations
         let mut all_groups: Vec<GroupView> = Vec::new();
-        let mut seen_names: HashSet<String> = HashSet::new();
+        let mut group_index: HashMap<String, usize> = HashMap::new();
         let mut group_to_servers: HashMap<String, Vec<usize>> = HashMap::new();
         let mut posting_to_servers: HashMap<String, Vec<usize>> = HashMap::new();
         let mut any_success = false;
 
-        for (server_idx, service) in self.services.iter().enumerate() {
-            match service.get_groups().await {
+        // Ask every server at once; merging in server order keeps the first
+        // description any server gives for a group several carry
+        let results =
+            futures::future::join_all(self.services.iter().map(|service| service.get_groups()))
+                .await;
+
+        for (server_idx, (service, result)) in self.services.iter().zip(results).enumerate() {
+            match result {
                 Ok(groups) => {
                     any_success = true;
                     let server_allows_posting = service.is_posting_allowed();
@@ -1540,9 +2674,19 @@ impl NntpFederatedService {
                                 .push(server_idx);
                         }
 
-                        // Add to all_groups if first time seeing this group
-                        if seen_names.insert(group.name.clone()) {
-                            all_groups.push(group);
+                        // Add to all_groups if first time seeing this group,
+                        // else fill in a description earlier servers lacked
+                        match group_index.get(&group.name) {
+                            Some(&i) => {
+                                let merged = &mut all_groups[i];
+                                if merged.description.is_none() {
+                                    merged.description = group.description;
+                                }
+                            }
+                            None => {
+                                group_index.insert(group.name.clone(), all_groups.len());
+                                all_groups.push(group);
+                            }
                         }
                     }
 
@@ -1587,7 +2731,7 @@ impl NntpFederatedService {
 
         // Cache the result
         self.groups_cache
-            .insert(cache_key, all_groups.clone())
+            .insert(cache_key, Stamped::new(all_groups.clone()))
             .await;
 
         Ok(all_groups)
@@ -1605,12 +2749,14 @@ impl NntpFederatedService {
         let cache_key = "groups".to_string();
 
         // Check cache first
-        if let Some(groups) = self.groups_cache.get(&cache_key).await {
+        let groups = self.groups_cache.get(&cache_key).await;
+        metrics().groups_cache.record(groups.is_some());
+        if let Some(groups) = groups {
             tracing::Span::current().record("cache_hit", true);
 
-            // Stale-while-revalidate: return cached data immediately,
-            // trigger background refresh if debounce period has elapsed
-            if self.should_refresh_groups().await {
+            // Stale-while-revalidate: return cached data immediately and,
+            // past the soft TTL, refresh in the background (debounced)
+            if groups.is_stale(self.soft_ttl.groups) && self.should_refresh_groups().await {
                 let self_clone = self.clone();
                 tokio::spawn(async move {
                     if let Err(e) = self_clone.fetch_groups_from_servers().await {
@@ -1620,7 +2766,7 @@ impl NntpFederatedService {
             }
 
             tracing::Span::current().record("duration_ms", start.elapsed().as_millis() as u64);
-            return Ok(groups);
+            return Ok(groups.value);
         }
 
         // Cache miss - check for pending request (coalesce if one is already in flight)
@@ -1642,6 +2788,13 @@ impl NntpFederatedService {
                             .record("duration_ms", start.elapsed().as_millis() as u64);
                         Err(AppError::Internal(e))
                     }
+                    // The caller we joined went away; fetch it ourselves
+                    Err(RecvError::Closed) => {
+                        let result = self.fetch_groups_from_servers().await;
+                        tracing::Span::current()
+                            .record("duration_ms", start.elapsed().as_millis() as u64);
+                        result
+                    }
                     Err(_) => {
                         tracing::Span::current()
                             .record("duration_ms", start.elapsed().as_millis() as u64);
@@ -1659,7 +2812,7 @@ impl NntpFederatedService {
             if let Some(groups) = self.groups_cache.get(&cache_key).await {
                 tracing::Span::current().record("cache_hit", true);
                 tracing::Span::current().record("duration_ms", start.elapsed().as_millis() as u64);
-                return Ok(groups);
+                return Ok(groups.value);
             }
             if let Some(ref existing_tx) = *pending {
                 let mut rx = existing_tx.subscribe();
@@ -1676,6 +2829,13 @@ impl NntpFederatedService {
                             .record("duration_ms", start.elapsed().as_millis() as u64);
                         Err(AppError::Internal(e))
                     }
+                    // The caller we joined went away; fetch it ourselves
+                    Err(RecvError::Closed) => {
+                        let result = self.fetch_groups_from_servers().await;
+                        tracing::Span::current()
+                            .record("duration_ms", start.elapsed().as_millis() as u64);
+                        result
+                    }
                     Err(_) => {
                         tracing::Span::current()
                             .record("duration_ms", start.elapsed().as_millis() as u64);
@@ -1685,6 +2845,12 @@ impl NntpFederatedService {
             }
             *pending = Some(tx.clone());
         }
+        let cancel = {
+            let pending = self.pending_groups.clone();
+            OnCancel::new(async move {
+                *pending.write().await = None;
+            })
+        };
 
         // Fetch from servers
         let result = self.fetch_groups_from_servers().await;
@@ -1694,6 +2860,7 @@ impl NntpFederatedService {
             let mut pending = self.pending_groups.write().await;
             *pending = None;
         }
+        cancel.disarm();
 
         match &result {
             Ok(groups) => {
@@ -1716,55 +2883,95 @@ impl NntpFederatedService {
         fields(cache_hit = false, coalesced = false, duration_ms)
     )]
     pub async fn get_group_stats(&self, group: &str) -> Result<GroupStatsView, AppError> {
+        let group = self.canonical_group(group);
         let start = Instant::now();
-        // Check cache first
-        if let Some(stats) = self.group_stats_cache.get(group).await {
+        // Check cache first, refreshing in the background past the soft TTL
+        if let Some(stats) = self
+            .group_stats_cache
+            .get(&self.group_key(group).await)
+            .await
+        {
             tracing::Span::current().record("cache_hit", true);
+            if stats.is_stale(self.soft_ttl.threads) {
+                let this = self.clone();
+                let group = group.to_string();
+                tokio::spawn(async move {
+                    if let Err(e) = this.fetch_group_stats_coalesced(&group).await {
+                        tracing::debug!(%group, error = %e, "Group stats refresh failed");
+                    }
+                });
+            }
             tracing::Span::current().record("duration_ms", start.elapsed().as_millis() as u64);
-            return Ok(stats);
+            return Ok(stats.value);
         }
 
-        // Check for pending request (coalesce if one is already in flight)
-        {
-            let pending = self.pending_group_stats.read().await;
-            if let Some(tx) = pending.get(group) {
-                let mut rx = tx.subscribe();
-                drop(pending); // Release lock while waiting
-
-                tracing::Span::current().record("coalesced", true);
-                return match rx.recv().await {
-                    Ok(Ok(stats)) => Ok(stats),
-                    Ok(Err(e)) => Err(AppError::Internal(e)),
-                    Err(_) => Err(AppError::Internal("Broadcast channel closed".into())),
-                };
-            }
+        if let Err(e) = self.check_group_not_found(group).await {
+            tracing::Span::current().record("duration_ms", start.elapsed().as_millis() as u64);
+            return Err(e);
         }
+        let result = self.fetch_group_stats_coalesced(group).await;
+        tracing::Span::current().record("duration_ms", start.elapsed().as_millis() as u64);
+        result
+    }
 
-        // Register pending request
-        let (tx, _) = broadcast::channel(BROADCAST_CHANNEL_CAPACITY);
-        {
-            let mut pending = self.pending_group_stats.write().await;
-            // Double-check cache and pending after acquiring write lock
-            if let Some(stats) = self.group_stats_cache.get(group).await {
-                return Ok(stats);
-            }
-            if let Some(existing_tx) = pending.get(group) {
-                let mut rx = existing_tx.subscribe();
-                drop(pending);
-                return match rx.recv().await {
-                    Ok(Ok(stats)) => Ok(stats),
-                    Ok(Err(e)) => Err(AppError::Internal(e)),
-                    Err(_) => Err(AppError::Internal("Broadcast channel closed".into())),
-                };
+    /// Fetch group stats from the servers carrying the group and cache them.
+    /// Concurrent callers for the same group share one fetch.
+    async fn fetch_group_stats_coalesced(&self, group: &str) -> Result<GroupStatsView, AppError> {
+        let cache_key = self.group_key(group).await;
+
+        // Join a fetch already in flight, or register our own. If the caller
+        // we joined goes away before finishing, start over.
+        let tx = loop {
+            let in_flight = self
+                .pending_group_stats
+                .read()
+                .await
+                .get(group)
+                .map(|tx| tx.subscribe());
+            let mut rx = match in_flight {
+                Some(rx) => rx,
+                None => {
+                    let mut pending = self.pending_group_stats.write().await;
+                    // Double-check cache and pending after acquiring write lock.
+                    // Stale entries don't count, so background refreshes go ahead.
+                    if let Some(stats) = self.group_stats_cache.get(&cache_key).await {
+                        if !stats.is_stale(self.soft_ttl.threads) {
+                            return Ok(stats.value);
+                        }
+                    }
+                    match pending.get(group) {
+                        Some(existing_tx) => existing_tx.subscribe(),
+                        None => {
+                            let (tx, _) = broadcast::channel(BROADCAST_CHANNEL_CAPACITY);
+                            pending.insert(group.to_string(), tx.clone());
+                            break tx;
+                        }
+                    }
+                }
+            };
+
+            tracing::Span::current().record("coalesced", true);
+            match rx.recv().await {
+                Ok(Ok(stats)) => return Ok(stats),
+                Ok(Err(e)) => return Err(AppError::Internal(e)),
+                Err(RecvError::Closed) => continue,
+                Err(_) => return Err(AppError::Internal("Broadcast channel closed".into())),
             }
-            pending.insert(group.to_string(), tx.clone());
-        }
+        };
+        let cancel = {
+            let pending = self.pending_group_stats.clone();
+            let group = group.to_string();
+            OnCancel::new(async move {
+                pending.write().await.remove(&group);
+            })
+        };
 
         // Get servers for this group (smart dispatch)
         let server_indices = self.get_servers_for_group(group).await;
 
         // Try only relevant servers
         let mut last_error = None;
+        let mut all_not_found = true;
         let mut result: Option<GroupStatsView> = None;
 
         for idx in server_indices {
@@ -1773,12 +2980,15 @@ impl NntpFederatedService {
                 Ok(stats) => {
                     // Cache the result
                     self.group_stats_cache
-                        .insert(group.to_string(), stats.clone())
+                        .insert(cache_key, Stamped::new(stats.clone()))
                         .await;
                     result = Some(stats);
                     break;
                 }
                 Err(e) => {
+                    if !e.is_group_missing() {
+                        all_not_found = false;
+                    }
                     last_error = Some(e);
                 }
             }
@@ -1789,19 +2999,23 @@ impl NntpFederatedService {
             let mut pending = self.pending_group_stats.write().await;
             pending.remove(group);
         }
+        cancel.disarm();
 
         match result {
             Some(stats) => {
                 let _ = tx.send(Ok(stats.clone()));
-                tracing::Span::current().record("duration_ms", start.elapsed().as_millis() as u64);
                 Ok(stats)
             }
+            None if all_not_found && last_error.is_some() => {
+                self.cache_group_not_found(group).await;
+                let _ = tx.send(Err(format!("No such newsgroup: {}", group)));
+                Err(AppError::GroupNotFound(group.to_string()))
+            }
             None => {
                 let err_msg = last_error
-                    .map(|e| e.0)
+                    .map(|e| e.to_string())
                     .unwrap_or_else(|| "Group stats not available".into());
                 let _ = tx.send(Err(err_msg.clone()));
-                tracing::Span::current().record("duration_ms", start.elapsed().as_millis() as u64);
                 Err(AppError::Internal(err_msg))
             }
         }
@@ -1809,11 +3023,15 @@ impl NntpFederatedService {
 
     /// Check if group stats are cached (non-blocking, does not fetch)
     pub async fn get_cached_group_stats(&self, group: &str) -> Option<GroupStatsView> {
-        self.group_stats_cache.get(group).await
+        let group = self.canonical_group(group);
+        self.group_stats_cache
+            .get(&self.group_key(group).await)
+            .await
+            .map(|stats| stats.value)
     }
 
     /// Get cached group stats for multiple groups in parallel.
-    /// Returns: (map of group name -> stats, list of uncached groups)
+    /// Returns: (map of group name -> stats, list of uncached or stale groups)
     pub async fn get_all_cached_group_stats(
         &self,
         group_names: &[String],
@@ -1824,7 +3042,7 @@ impl NntpFederatedService {
                 let cache = &self.group_stats_cache;
                 let name = name.clone();
                 async move {
-                    let stats = cache.get(&name).await;
+                    let stats = cache.get(&self.group_key(&name).await).await;
                     (name, stats)
                 }
             })
@@ -1836,10 +3054,15 @@ impl NntpFederatedService {
         let mut needs_prefetch: Vec<String> = Vec::new();
 
         for (name, stats) in results {
-            if let Some(s) = stats {
-                group_stats.insert(name, s.last_article_date);
-            } else {
-                needs_prefetch.push(name);
+            match stats {
+                Some(s) => {
+                    // Stale stats are shown while the prefetch refreshes them
+                    if s.is_stale(self.soft_ttl.threads) {
+                        needs_prefetch.push(name.clone());
+                    }
+                    group_stats.insert(name, s.value.last_article_date);
+                }
+                None => needs_prefetch.push(name),
             }
         }
 
@@ -1871,7 +3094,10 @@ impl NntpFederatedService {
                 let cache = &self.threads_cache;
                 let name = name.clone();
                 async move {
-                    let count = cache.get(&name).await.map(|c| c.threads.len());
+                    let count = cache
+                        .get(&self.group_key(&name).await)
+                        .await
+                        .map(|c| c.threads.len());
                     (name, count)
                 }
             })
@@ -1885,10 +3111,118 @@ impl NntpFederatedService {
             .collect()
     }
 
+    /// Hottest threads across the busiest groups, then the most recently
+    /// active. Only thread lists already in the cache are read, so this never
+    /// waits on a server.
+    pub async fn cached_top_threads(&self, limit: usize) -> Vec<TopThreadView> {
+        let groups = self
+            .activity_tracker
+            .write()
+            .await
+            .busiest_groups(HOME_TOP_THREADS_MAX_GROUPS);
+
+        let mut threads = Vec::new();
+        for group in groups {
+            let Some(cached) = self.threads_cache.get(&self.group_key(&group).await).await else {
+                continue;
+            };
+            threads.extend(cached.threads.iter().map(|thread| {
+                let last_post = thread.last_post_date.as_deref().and_then(parse_date);
+                let view = TopThreadView {
+                    group: group.clone(),
+                    normalized_subject: thread.normalized_subject.clone(),
+                    root_message_id: thread.root_message_id.clone(),
+                    article_count: thread.article_count,
+                    last_post_date: thread.last_post_date.clone(),
+                    hot: thread.hot,
+                };
+                (thread.hotness, last_post, view)
+            }));
+        }
+
+        // Threads with no recent replies score 0 and go by date, undated last
+        threads.sort_by(|a, b| b.0.total_cmp(&a.0).then_with(|| b.1.cmp(&a.1)));
+        threads
+            .into_iter()
+            .take(limit)
+            .map(|(_, _, view)| view)
+            .collect()
+    }
+
+    /// Cache status of an article, or `None` if only the servers can tell
+    pub async fn cached_article_status(&self, message_id: &str) -> Option<CachedStatus> {
+        if let Some(cached) = self.article_cache.get(message_id).await {
+            return Some(CachedStatus::Present {
+                last_modified: parse_date(&cached.value.date).map(|d| d.with_timezone(&Utc)),
+            });
+        }
+        self.article_not_found_cache
+            .get(message_id)
+            .await
+            .map(|_| CachedStatus::Missing)
+    }
+
+    /// Cache status of a thread, dated by its latest post
+    pub async fn cached_thread_status(
+        &self,
+        group: &str,
+        message_id: &str,
+    ) -> Option<CachedStatus> {
+        let group = self.canonical_group(group);
+        let cache_key = format!("{}:{}", self.group_key(group).await, message_id);
+        let cached = self.thread_cache.get(&cache_key).await?;
+        Some(CachedStatus::Present {
+            last_modified: cached
+                .thread
+                .last_post_date
+                .as_deref()
+                .and_then(parse_date)
+                .map(|d| d.with_timezone(&Utc)),
+        })
+    }
+
+    /// A group's thread list if it is cached, without fetching it
+    pub async fn cached_threads(&self, group: &str) -> Option<Vec<ThreadView>> {
+        let group = self.canonical_group(group);
+        self.threads_cache
+            .get(&self.group_key(group).await)
+            .await
+            .map(|cached| cached.threads)
+    }
+
+    /// Cache status of a group's thread list, dated by its most recent post
+    pub async fn cached_threads_status(&self, group: &str) -> Option<CachedStatus> {
+        let group = self.canonical_group(group);
+        let cached = self.threads_cache.get(&self.group_key(group).await).await?;
+        Some(CachedStatus::Present {
+            last_modified: cached
+                .threads
+                .iter()
+                .filter_map(|thread| thread.last_post_date.as_deref().and_then(parse_date))
+                .max()
+                .map(|d| d.with_timezone(&Utc)),
+        })
+    }
+
+    /// Whether a server is known to carry `group`, from the last group list
+    pub async fn carries_group(&self, group: &str) -> bool {
+        self.group_servers.read().await.contains_key(group)
+    }
+
+    /// Whether a known group is `prefix` or sits under it in the hierarchy
+    pub async fn carries_hierarchy(&self, prefix: &str) -> bool {
+        self.group_servers.read().await.keys().any(|group| {
+            group
+                .strip_prefix(prefix)
+                .is_some_and(|rest| rest.is_empty() || rest.starts_with('.'))
+        })
+    }
+
     /// Check if posting is allowed for a group
-    /// Returns true if at least one server carries this group
-    /// (actual POST capability is checked at post time)
+    /// Returns true if at least one server that isn't read-only carries this
+    /// group (actual POST capability is checked at post time)
     pub async fn can_post_to_group(&self, group: &str) -> bool {
+        let group = self.canonical_group(group);
         // First check if we have explicit posting servers
         let posting = self.posting_servers.read().await;
         if posting.get(group).map(|v| !v.is_empty()).unwrap_or(false) {
@@ -1896,9 +3230,13 @@ impl NntpFederatedService {
         }
         drop(posting);
 
-        // Fall back to checking if any server carries this group
+        // Fall back to checking if any writable server carries this group
         let servers = self.group_servers.read().await;
-        servers.get(group).map(|v| !v.is_empty()).unwrap_or(false)
+        servers.get(group).is_some_and(|indices| {
+            indices
+                .iter()
+                .any(|&idx| !self.services[idx].is_read_only())
+        })
     }
 
     /// Post a new article or reply
@@ -1914,6 +3252,7 @@ impl NntpFederatedService {
         headers: Vec<(String, String)>,
         body: String,
     ) -> Result<(), AppError> {
+        let group = self.canonical_group(group);
         let start = Instant::now();
 
         // Get servers that support posting to this group
@@ -1923,11 +3262,13 @@ impl NntpFederatedService {
         };
 
         // If no posting servers known, fall back to all servers for this group
-        let server_indices = if server_indices.is_empty() {
+        let mut server_indices = if server_indices.is_empty() {
             self.get_servers_for_group(group).await
         } else {
             server_indices
         };
+        // Read-only servers never get posts, whatever they advertise
+        server_indices.retain(|&idx| !self.services[idx].is_read_only());
 
         if server_indices.is_empty() {
             return Err(AppError::Internal(
@@ -1964,11 +3305,42 @@ impl NntpFederatedService {
 
         tracing::Span::current().record("duration_ms", start.elapsed().as_millis() as u64);
         Err(last_error
-            .map(|e| AppError::Internal(format!("Failed to post article: {}", e.0)))
+            .map(|e| AppError::Internal(format!("Failed to post article: {}", e)))
             .unwrap_or_else(|| AppError::Internal("Failed to post article".into())))
     }
 }
 
+/// Compute a stable token identifying the ordering and size of a thread list.
+/// Identical lists always produce the same token, so repeated page-1 loads
+/// reuse one snapshot instead of creating a new one per request.
+fn thread_list_fingerprint(threads: &[ThreadView]) -> String {
+    let mut hasher = DefaultHasher::new();
+    for thread in threads {
+        thread.root_message_id.hash(&mut hasher);
+        thread.article_count.hash(&mut hasher);
+    }
+    format!("{:016x}", hasher.finish())
+}
+
+/// Copy fetched bodies into the comments that have them, and mark those
+/// whose fetch failed
+fn fill_bodies(
+    comments: &mut [FlatComment],
+    bodies: &HashMap<String, ArticleView>,
+    unavailable: &HashSet<String>,
+) {
+    for comment in comments {
+        comment.body_unavailable = unavailable.contains(&comment.message_id);
+        if let (Some(article), Some(fetched)) =
+            (comment.article.as_mut(), bodies.get(&comment.message_id))
+        {
+            article.body = fetched.body.clone();
+            article.body_preview = fetched.body_preview.clone();
+            article.has_more_content = fetched.has_more_content;
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -1976,6 +3348,7 @@ mod tests {
         ACTIVITY_HIGH_RPS, ACTIVITY_WINDOW_SECS, BACKGROUND_REFRESH_MAX_PERIOD_SECS,
         BACKGROUND_REFRESH_MIN_PERIOD_SECS,
     };
+    use tokio::sync::oneshot;
 
     // =============================================================================
     // calculate_refresh_period tests
@@ -2059,6 +3432,35 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_refresh_period_within_group_policy() {
+        let policy = RefreshPolicy {
+            min_period: Duration::from_secs(2),
+            max_period: Duration::from_secs(5),
+        };
+        let busiest = NntpFederatedService::refresh_period_within(ACTIVITY_HIGH_RPS, &policy);
+        assert_eq!(busiest, Duration::from_secs(2));
+        let idle = NntpFederatedService::refresh_period_within(0.0, &policy);
+        assert_eq!(idle, Duration::from_secs(5));
+        let moderate = NntpFederatedService::refresh_period_within(1.0, &policy);
+        assert!(moderate > busiest && moderate < idle);
+    }
+
+    #[test]
+    fn test_jittered_refresh_period_bounds() {
+        let period = Duration::from_secs(10);
+        let secs = |unit| jittered(period, unit).as_secs_f64();
+        let spread = 10.0 * BACKGROUND_REFRESH_JITTER;
+        assert!((secs(0.0) - (10.0 - spread)).abs() < 1e-6);
+        assert!((secs(0.5) - 10.0).abs() < 1e-6);
+        assert!(secs(0.999) < 10.0 + spread);
+
+        for _ in 0..100 {
+            let unit = random_unit();
+            assert!((0.0..1.0).contains(&unit));
+        }
+    }
+
     // =============================================================================
     // GroupActivity tests
     // =============================================================================
@@ -2153,4 +3555,419 @@ mod tests {
             "Should be inactive after window elapses"
         );
     }
+
+    // =============================================================================
+    // ActivityTracker tests
+    // =============================================================================
+
+    #[test]
+    fn test_activity_tracker_bounded_while_crawled() {
+        let mut tracker = ActivityTracker::with_max_groups(3);
+
+        // A crawler touching a new group every second
+        for n in 0..50 {
+            tracker.record_request_at(&format!("alt.crawl.{}", n), n);
+            assert!(tracker.groups.len() <= 3);
+        }
+        let mut tracked: Vec<_> = tracker.groups.keys().cloned().collect();
+        tracked.sort();
+        assert_eq!(
+            tracked,
+            vec!["alt.crawl.47", "alt.crawl.48", "alt.crawl.49"]
+        );
+    }
+
+    #[test]
+    fn test_activity_tracker_drops_all_inactive_groups_when_full() {
+        let mut tracker = ActivityTracker::with_max_groups(3);
+        tracker.record_request_at("alt.old.1", 0);
+        tracker.record_request_at("alt.old.2", 1);
+        tracker.record_request_at("comp.lang.rust", ACTIVITY_WINDOW_SECS + 100);
+
+        // Both quiet groups go at once, not just the least recent one
+        tracker.record_request_at("alt.new", ACTIVITY_WINDOW_SECS + 101);
+        let mut tracked: Vec<_> = tracker.groups.keys().cloned().collect();
+        tracked.sort();
+        assert_eq!(tracked, vec!["alt.new", "comp.lang.rust"]);
+    }
+
+    #[tokio::test]
+    async fn test_activity_tracker_eviction_aborts_refresh_task() {
+        let mut tracker = ActivityTracker::with_max_groups(1);
+        tracker.record_request_at("comp.lang.rust", 0);
+
+        let (alive_tx, alive_rx) = oneshot::channel::<()>();
+        let task = tokio::spawn(async move {
+            let _alive = alive_tx;
+            std::future::pending::<()>().await;
+        });
+        tracker.set_refresh_task("comp.lang.rust", task);
+        assert!(tracker.has_refresh_task("comp.lang.rust"));
+
+        tracker.record_request_at("comp.lang.c", 1);
+        assert!(!tracker.has_refresh_task("comp.lang.rust"));
+        // The aborted task drops its sender
+        assert!(alive_rx.await.is_err());
+
+        // A task for a group that is no longer tracked is aborted straight away
+        let (alive_tx, alive_rx) = oneshot::channel::<()>();
+        let task = tokio::spawn(async move {
+            let _alive = alive_tx;
+            std::future::pending::<()>().await;
+        });
+        tracker.set_refresh_task("comp.lang.rust", task);
+        assert!(alive_rx.await.is_err());
+    }
+
+    // =============================================================================
+    // thread_list_fingerprint tests
+    // =============================================================================
+
+    fn thread(root: &str, article_count: usize) -> ThreadView {
+        ThreadView {
+            subject: "Subject".to_string(),
+            normalized_subject: "Subject".to_string(),
+            root_message_id: root.to_string(),
+            article_count,
+            root: ThreadNodeView {
+                message_id: root.to_string(),
+                article: None,
+                replies: Vec::new(),
+                descendant_count: 0,
+            },
+            last_post_date: None,
+            last_post_date_relative: None,
+            participants: Vec::new(),
+            participant_count: 0,
+            hotness: 0.0,
+            hot: false,
+        }
+    }
+
+    #[test]
+    fn test_thread_list_fingerprint_is_stable() {
+        let threads = vec![thread("<a@x>", 1), thread("<b@x>", 3)];
+        assert_eq!(
+            thread_list_fingerprint(&threads),
+            thread_list_fingerprint(&threads.clone())
+        );
+    }
+
+    #[test]
+    fn test_thread_list_fingerprint_changes_with_order() {
+        let ab = vec![thread("<a@x>", 1), thread("<b@x>", 1)];
+        let ba = vec![thread("<b@x>", 1), thread("<a@x>", 1)];
+        assert_ne!(thread_list_fingerprint(&ab), thread_list_fingerprint(&ba));
+    }
+
+    #[test]
+    fn test_thread_list_fingerprint_changes_with_new_reply() {
+        let before = vec![thread("<a@x>", 1)];
+        let after = vec![thread("<a@x>", 2)];
+        assert_ne!(
+            thread_list_fingerprint(&before),
+            thread_list_fingerprint(&after)
+        );
+    }
+
+    // =============================================================================
+    // Group alias tests
+    // =============================================================================
+
+    #[test]
+    fn test_canonical_group_resolves_aliases() {
+        let mut aliases = HashMap::new();
+        aliases.insert("comp.lang.old".to_string(), "comp.lang.new".to_string());
+        let service = NntpFederatedService::with_services(Vec::new(), &CacheConfig::default(), 100)
+            .with_group_aliases(aliases);
+
+        assert_eq!(service.canonical_group("comp.lang.old"), "comp.lang.new");
+        assert_eq!(service.canonical_group("comp.lang.new"), "comp.lang.new");
+        assert_eq!(service.canonical_group("alt.test"), "alt.test");
+    }
+
+    // =============================================================================
+    // Group purge tests
+    // =============================================================================
+
+    #[tokio::test]
+    async fn test_purge_group_orphans_cached_entries() {
+        let service = NntpFederatedService::with_services(Vec::new(), &CacheConfig::default(), 100);
+        let cached = |threads| CachedThreads {
+            threads,
+            last_article_number: 10,
+            fetched_at: Instant::now(),
+        };
+        for group in ["comp.lang.rust", "alt.test"] {
+            let key = service.group_key(group).await;
+            service
+                .threads_cache
+                .insert(key, cached(vec![thread("<a@x>", 1)]))
+                .await;
+        }
+        let names = vec!["comp.lang.rust".to_string(), "alt.test".to_string()];
+        assert_eq!(
+            service.get_all_cached_thread_counts_for(&names).await.len(),
+            2
+        );
+
+        service.purge_group("comp.lang.rust").await;
+        let counts = service.get_all_cached_thread_counts_for(&names).await;
+        assert_eq!(counts.len(), 1);
+        assert!(counts.contains_key("alt.test"));
+        assert_eq!(
+            service.group_key("comp.lang.rust").await,
+            "comp.lang.rust#1"
+        );
+    }
+
+    // =============================================================================
+    // Vote tally tests
+    // =============================================================================
+
+    #[tokio::test]
+    async fn test_vote_tally_is_cached_until_the_thread_gains_a_reply() {
+        let service = NntpFederatedService::with_services(Vec::new(), &CacheConfig::default(), 100)
+            .with_vote_config(&VoteConfig {
+                groups: "alt.vote".to_string(),
+                ..VoteConfig::default()
+            });
+        let vote = ArticleView {
+            message_id: "<vote@x>".to_string(),
+            subject: "Re: Proposal".to_string(),
+            from: "voter@example.com".to_string(),
+            date: "Mon, 1 Jan 2024 00:00:00 +0000".to_string(),
+            date_relative: String::new(),
+            body: Some("+1".to_string()),
+            body_preview: None,
+            has_more_content: false,
+            headers: None,
+        };
+        service
+            .article_cache
+            .insert(vote.message_id.clone(), Stamped::new(vote))
+            .await;
+        let mut proposal = thread("<root@x>", 2);
+        proposal.root.replies.push(ThreadNodeView {
+            message_id: "<vote@x>".to_string(),
+            article: None,
+            replies: Vec::new(),
+            descendant_count: 0,
+        });
+
+        let tally = service.vote_tally("alt.vote", &proposal).await.unwrap();
+        assert_eq!(tally.voters, 1);
+
+        // Served from the tally cache without reading the reply again
+        service.article_cache.invalidate("<vote@x>").await;
+        let tally = service.vote_tally("alt.vote", &proposal).await.unwrap();
+        assert_eq!(tally.voters, 1);
+
+        // A new reply changes the key, so the thread is counted afresh
+        proposal.article_count = 3;
+        assert!(service.vote_tally("alt.vote", &proposal).await.is_none());
+        assert!(service.vote_tally("alt.other", &proposal).await.is_none());
+    }
+
+    // =============================================================================
+    // Negative group cache tests
+    // =============================================================================
+
+    #[tokio::test]
+    async fn test_group_not_found_cache_fails_fast_until_purged() {
+        let service = NntpFederatedService::with_services(Vec::new(), &CacheConfig::default(), 100);
+
+        // No server answered, so nothing is known to be missing
+        assert!(matches!(
+            service.get_threads("alt.nonexistent", 0).await,
+            Err(AppError::GroupNotFound(_))
+        ));
+        assert!(!service
+            .group_not_found_cache
+            .contains_key("alt.nonexistent"));
+
+        service.cache_group_not_found("alt.nonexistent").await;
+        assert!(matches!(
+            service.get_group_stats("alt.nonexistent").await,
+            Err(AppError::GroupNotFound(_))
+        ));
+        assert!(service.check_group_not_found("alt.test").await.is_ok());
+
+        service.purge_group("alt.nonexistent").await;
+        assert!(service
+            .check_group_not_found("alt.nonexistent")
+            .await
+            .is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_pending_posts_are_indexed_by_thread_and_shown_to_their_poster() {
+        let service = NntpFederatedService::with_services(Vec::new(), &CacheConfig::default(), 100);
+        let pending = |thread_key: &str, poster: &str| PendingPost {
+            thread_key: thread_key.to_string(),
+            poster: poster.to_string(),
+            status: DeliveryStatus::Pending,
+        };
+        let alice = pending("alt.test:<root@x>", "oidc:alice");
+        service
+            .set_delivery_status("<a@x>", &alice, DeliveryStatus::Pending)
+            .await;
+        service
+            .set_delivery_status(
+                "<b@x>",
+                &pending("alt.test:<root@x>", "oidc:bob"),
+                DeliveryStatus::Pending,
+            )
+            .await;
+        service
+            .set_delivery_status(
+                "<c@x>",
+                &pending("alt.test:<other@x>", "oidc:alice"),
+                DeliveryStatus::Pending,
+            )
+            .await;
+
+        let posts = service
+            .pending_posts("alt.test", "<root@x>", "oidc:alice")
+            .await;
+        assert_eq!(posts.len(), 1);
+        assert_eq!(posts[0].message_id, "<a@x>");
+        assert!(service
+            .pending_posts("alt.test", "<root@x>", "oidc:carol")
+            .await
+            .is_empty());
+
+        // A status update keeps the article indexed
+        service
+            .set_delivery_status("<a@x>", &alice, DeliveryStatus::Confirmed)
+            .await;
+        service.pending_posts.run_pending_tasks().await;
+        let posts = service
+            .pending_posts("alt.test", "<root@x>", "oidc:alice")
+            .await;
+        assert_eq!(posts[0].status, DeliveryStatus::Confirmed);
+
+        // Removing an entry from the cache removes it from the index
+        service.pending_posts.invalidate("<b@x>").await;
+        service.pending_posts.run_pending_tasks().await;
+        let index = service.pending_posts_by_thread.lock().unwrap();
+        assert_eq!(index["alt.test:<root@x>"], ["<a@x>"]);
+    }
+
+    #[tokio::test]
+    async fn test_thread_list_snapshots_are_weighed_by_thread_count() {
+        let service = NntpFederatedService::with_services(Vec::new(), &CacheConfig::default(), 100);
+        let threads = vec![thread("<a@x>", 1), thread("<b@x>", 1), thread("<c@x>", 1)];
+        service
+            .thread_list_snapshots
+            .insert("alt.test:token".to_string(), Arc::new(threads))
+            .await;
+        service
+            .thread_list_snapshots
+            .insert("alt.empty:token".to_string(), Arc::new(Vec::new()))
+            .await;
+        service.thread_list_snapshots.run_pending_tasks().await;
+        assert_eq!(service.thread_list_snapshots.weighted_size(), 4);
+    }
+
+    #[tokio::test]
+    async fn test_unconfirmed_post_is_discarded_from_shared_caches() {
+        let service = NntpFederatedService::with_services(Vec::new(), &CacheConfig::default(), 100);
+        let article = ArticleView {
+            message_id: "<new@x>".to_string(),
+            subject: "Hello".to_string(),
+            from: "poster@example.com".to_string(),
+            date: "Mon, 1 Jan 2024 00:00:00 +0000".to_string(),
+            date_relative: String::new(),
+            body: Some("Hello everyone".to_string()),
+            body_preview: None,
+            has_more_content: false,
+            headers: None,
+        };
+        service
+            .article_cache
+            .insert(article.message_id.clone(), Stamped::new(article.clone()))
+            .await;
+        service
+            .inject_article_into_caches("alt.test", article, None, None)
+            .await;
+        assert!(service.threads_cache.get("alt.test").await.is_some());
+
+        let pending = PendingPost {
+            thread_key: "alt.test:<new@x>".to_string(),
+            poster: "oidc:alice".to_string(),
+            status: DeliveryStatus::Unconfirmed,
+        };
+        service
+            .discard_unconfirmed_post("alt.test", "<new@x>", &pending)
+            .await;
+        assert!(service.article_cache.get("<new@x>").await.is_none());
+        assert!(service.threads_cache.get("alt.test").await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_thread_list_waiter_takes_over_from_dropped_leader() {
+        let service = NntpFederatedService::with_services(Vec::new(), &CacheConfig::default(), 100);
+
+        // A leader whose HTTP client disconnects mid-fetch
+        let leader = {
+            let (tx, _) = broadcast::channel(BROADCAST_CHANNEL_CAPACITY);
+            service
+                .pending_threads
+                .write()
+                .await
+                .insert("alt.test".to_string(), tx);
+            let pending = service.pending_threads.clone();
+            OnCancel::new(async move {
+                pending.write().await.remove("alt.test");
+            })
+        };
+        let waiter = tokio::spawn({
+            let service = service.clone();
+            async move { service.fetch_threads_single_flight("alt.test").await }
+        });
+        tokio::task::yield_now().await;
+        drop(leader);
+
+        // No servers, so the waiter's own fetch fails, but it doesn't hang
+        let result = tokio::time::timeout(Duration::from_secs(5), waiter)
+            .await
+            .expect("waiter should not wait on a dropped leader")
+            .unwrap();
+        assert!(result.is_err());
+        assert!(service.pending_threads.read().await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_coalesced_waiter_takes_over_from_dropped_leader() {
+        let service = NntpFederatedService::with_services(Vec::new(), &CacheConfig::default(), 100);
+
+        // A leader whose HTTP client disconnects mid-fetch
+        let leader = {
+            let (tx, _) = broadcast::channel(BROADCAST_CHANNEL_CAPACITY);
+            service
+                .pending_group_stats
+                .write()
+                .await
+                .insert("alt.test".to_string(), tx);
+            let pending = service.pending_group_stats.clone();
+            OnCancel::new(async move {
+                pending.write().await.remove("alt.test");
+            })
+        };
+        let waiter = tokio::spawn({
+            let service = service.clone();
+            async move { service.fetch_group_stats_coalesced("alt.test").await }
+        });
+        tokio::task::yield_now().await;
+        drop(leader);
+
+        // No servers, so the waiter's own fetch fails, but it doesn't hang
+        let result = tokio::time::timeout(Duration::from_secs(5), waiter)
+            .await
+            .expect("waiter should not wait on a dropped leader")
+            .unwrap();
+        assert!(result.is_err());
+        assert!(service.pending_group_stats.read().await.is_empty());
+    }
 }
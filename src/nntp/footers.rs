@@ -0,0 +1,201 @@
+//! Operator rules stripping signatures and gateway footers from bodies.
+//!
+//! `[[body.footer]]` rules name groups by wildmat and say what to remove
+//! from bodies shown there: text matching a regex (a mailing list trailer,
+//! say), the signature after the last "-- " line, or both. Every matching
+//! rule applies, in configuration order. Stripping happens when bodies are
+//! put on a page, not in the article cache, because an article cross-posted
+//! to several groups may need different rules in each.
+//!
+//! Results are cached by message ID and the set of rules applied, so a busy
+//! thread runs each regex once per article rather than once per view.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use moka::future::Cache;
+
+use super::{compute_preview, wildmat, ArticleView, FlatComment};
+use crate::config::{
+    BodyConfig, FooterRule, BODY_FOOTER_CACHE_MAX_ENTRIES, BODY_FOOTER_CACHE_TTL_SECS,
+};
+
+/// A body with the rules applied, and the preview made from it
+#[derive(Debug)]
+struct StrippedBody {
+    body: String,
+    preview: String,
+    has_more_content: bool,
+}
+
+/// Configured footer rules and their results
+#[derive(Clone)]
+pub struct FooterRules {
+    rules: Arc<Vec<FooterRule>>,
+    /// Keyed by message ID and a bitmask of the rules applied
+    cache: Cache<(String, u64), Arc<StrippedBody>>,
+}
+
+impl FooterRules {
+    /// Build rules from configuration, which caps their number at 64
+    pub fn new(config: &BodyConfig) -> Self {
+        Self {
+            rules: Arc::new(config.footers.clone()),
+            cache: Cache::builder()
+                .max_capacity(BODY_FOOTER_CACHE_MAX_ENTRIES)
+                .time_to_live(Duration::from_secs(BODY_FOOTER_CACHE_TTL_SECS))
+                .build(),
+        }
+    }
+
+    /// Strip the bodies of `comments` for display in `group`
+    pub async fn apply_to_comments(&self, group: &str, comments: &mut [FlatComment]) {
+        if self.rules.is_empty() {
+            return;
+        }
+        for comment in comments {
+            if let Some(article) = comment.article.as_mut() {
+                self.apply(&[group], article).await;
+            }
+        }
+    }
+
+    /// Strip an article's body for its own page: by the rules for `group`
+    /// when the reader came from one, else for the groups it was posted to
+    pub async fn apply_to_article(&self, group: Option<&str>, article: &mut ArticleView) {
+        if self.rules.is_empty() {
+            return;
+        }
+        let newsgroups = article.newsgroups();
+        let groups: Vec<&str> = match group {
+            Some(group) => vec![group],
+            None => newsgroups.iter().map(String::as_str).collect(),
+        };
+        self.apply(&groups, article).await;
+    }
+
+    /// Apply every rule matching any of `groups` to the article's body
+    async fn apply(&self, groups: &[&str], article: &mut ArticleView) {
+        let mask = self.matching(groups);
+        let Some(body) = article.body.as_deref().filter(|_| mask != 0) else {
+            return;
+        };
+        let key = (article.message_id.clone(), mask);
+        let stripped = match self.cache.get(&key).await {
+            Some(stripped) => stripped,
+            None => {
+                let body = self.strip(body, mask);
+                let (preview, has_more_content) = compute_preview(&body);
+                let stripped = Arc::new(StrippedBody {
+                    body,
+                    preview,
+                    has_more_content,
+                });
+                self.cache.insert(key, stripped.clone()).await;
+                stripped
+            }
+        };
+        article.body = Some(stripped.body.clone());
+        article.body_preview = Some(stripped.preview.clone());
+        article.has_more_content = stripped.has_more_content;
+    }
+
+    /// Bitmask of the rules whose pattern matches one of `groups`
+    fn matching(&self, groups: &[&str]) -> u64 {
+        self.rules
+            .iter()
+            .enumerate()
+            .filter(|(_, rule)| groups.iter().any(|group| wildmat(&rule.pattern, group)))
+            .fold(0, |mask, (i, _)| mask | 1 << i)
+    }
+
+    /// `body` with the rules in `mask` applied, trailing blank lines removed
+    fn strip(&self, body: &str, mask: u64) -> String {
+        let mut body = body.to_string();
+        for (i, rule) in self.rules.iter().enumerate() {
+            if mask & 1 << i == 0 {
+                continue;
+            }
+            if rule.strip_signature {
+                let signature = body
+                    .match_indices("\n-- \n")
+                    .last()
+                    .map(|(at, _)| at + 1)
+                    .or_else(|| body.starts_with("-- \n").then_some(0));
+                if let Some(at) = signature {
+                    body.truncate(at);
+                }
+            }
+            if let Some(regex) = &rule.matches {
+                body = regex.replace_all(&body, "").into_owned();
+            }
+        }
+        body.truncate(body.trim_end().len());
+        body
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rules(toml: &str) -> FooterRules {
+        let config: BodyConfig = toml::from_str(toml).unwrap();
+        FooterRules::new(&config)
+    }
+
+    #[test]
+    fn test_strip_trailer_and_signature() {
+        let rules = rules(
+            r#"
+            [[footer]]
+            pattern = "gmane.*"
+            matches = '(?ms)^_{5,}\n.*mailing list.*\z'
+
+            [[footer]]
+            pattern = "gmane.comp.*,!gmane.comp.keep"
+            strip_signature = true
+            "#,
+        );
+        let body = "Hello\n\n-- \nAnn\n_____\nfoo mailing list\nhttps://lists.example/foo\n";
+        assert_eq!(rules.matching(&["gmane.comp.foo"]), 0b11);
+        assert_eq!(rules.strip(body, 0b11), "Hello");
+        assert_eq!(rules.strip(body, 0b01), "Hello\n\n-- \nAnn");
+        assert_eq!(rules.matching(&["gmane.comp.keep"]), 0b01);
+        assert_eq!(rules.matching(&["comp.lang.rust"]), 0);
+        // Only the last delimiter starts the signature
+        assert_eq!(
+            rules.strip("Quoting:\n-- \nold sig\n\nReply\n-- \nnew sig", 0b10),
+            "Quoting:\n-- \nold sig\n\nReply"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_apply_to_article_uses_newsgroups_without_group() {
+        let rules = rules(
+            r#"
+            [[footer]]
+            pattern = "lists.*"
+            matches = '(?m)^--- list footer ---$'
+            "#,
+        );
+        let mut article = ArticleView {
+            message_id: "<a@example.com>".to_string(),
+            subject: String::new(),
+            from: String::new(),
+            date: String::new(),
+            date_relative: String::new(),
+            body: Some("Text\n--- list footer ---".to_string()),
+            body_preview: None,
+            has_more_content: false,
+            headers: Some("Newsgroups: comp.misc, lists.foo\n".to_string()),
+        };
+        rules
+            .apply_to_article(Some("comp.misc"), &mut article)
+            .await;
+        assert_eq!(article.body.as_deref(), Some("Text\n--- list footer ---"));
+        rules.apply_to_article(None, &mut article).await;
+        assert_eq!(article.body.as_deref(), Some("Text"));
+        assert_eq!(article.body_preview.as_deref(), Some("Text"));
+    }
+}
@@ -0,0 +1,87 @@
+//! Hot-thread scores.
+//!
+//! A thread's hotness is its recent reply velocity. Each reply counts 1 when
+//! just posted and half as much every `HOT_THREAD_HALF_LIFE_HOURS`, and
+//! replies older than `HOT_THREAD_WINDOW_HOURS` count nothing: ten replies
+//! in the last hour score nearly 10, ten spread over yesterday well under 1.
+//! Threads scoring at least `HOT_THREAD_MIN_SCORE` are badged as hot in the
+//! thread list, and the home page's top threads are ranked by score.
+//!
+//! Scores are taken when a thread is built or gains articles, so they are
+//! as of the last merge or refresh; a thread that has gone quiet cools off
+//! when its group's list is next fetched.
+
+use chrono::{DateTime, Duration, Utc};
+
+use super::{parse_date, ThreadNodeView};
+use crate::config::{HOT_THREAD_HALF_LIFE_HOURS, HOT_THREAD_WINDOW_HOURS};
+
+/// Hotness of the replies under `root` as of `now`
+pub fn score(root: &ThreadNodeView, now: DateTime<Utc>) -> f64 {
+    let window = Duration::hours(HOT_THREAD_WINDOW_HOURS);
+    let mut score = 0.0;
+    let mut stack: Vec<&ThreadNodeView> = root.replies.iter().collect();
+    while let Some(node) = stack.pop() {
+        stack.extend(&node.replies);
+        let Some(posted) = node.article.as_ref().and_then(|a| parse_date(&a.date)) else {
+            continue;
+        };
+        // Posts dated in the future (clock skew) count as just posted
+        let age = now.signed_duration_since(posted).max(Duration::zero());
+        if age <= window {
+            let hours = age.num_seconds() as f64 / 3600.0;
+            score += 0.5_f64.powf(hours / HOT_THREAD_HALF_LIFE_HOURS);
+        }
+    }
+    score
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::nntp::ArticleView;
+
+    fn node(date: String, replies: Vec<ThreadNodeView>) -> ThreadNodeView {
+        ThreadNodeView {
+            message_id: String::new(),
+            article: Some(ArticleView {
+                message_id: String::new(),
+                subject: String::new(),
+                from: String::new(),
+                date,
+                date_relative: String::new(),
+                body: None,
+                body_preview: None,
+                has_more_content: false,
+                headers: None,
+            }),
+            replies,
+            descendant_count: 0,
+        }
+    }
+
+    #[test]
+    fn test_score_decays_with_age() {
+        let now = Utc::now();
+        let ago = |hours: i64| (now - Duration::hours(hours)).to_rfc2822();
+        let half_life = HOT_THREAD_HALF_LIFE_HOURS as i64;
+
+        // The root doesn't count; a reply one half-life old counts half
+        let root = node(
+            ago(0),
+            vec![node(ago(half_life), vec![node(ago(0), vec![])])],
+        );
+        assert!((score(&root, now) - 1.5).abs() < 0.01);
+
+        // Replies past the window count nothing
+        let stale = node(ago(0), vec![node(ago(HOT_THREAD_WINDOW_HOURS + 1), vec![])]);
+        assert_eq!(score(&stale, now), 0.0);
+
+        // Undated and future-dated replies
+        let odd = node(
+            ago(0),
+            vec![node("garbage".to_string(), vec![]), node(ago(-2), vec![])],
+        );
+        assert!((score(&odd, now) - 1.0).abs() < 0.01);
+    }
+}
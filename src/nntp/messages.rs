@@ -6,6 +6,7 @@
 //! tasks (like refreshing group statistics).
 
 use std::fmt;
+use std::time::Instant;
 
 use tokio::sync::oneshot;
 
@@ -37,18 +38,142 @@ impl fmt::Display for Priority {
     }
 }
 
-/// Error type for NNTP operations that can be sent across channels
-#[derive(Debug, Clone)]
-pub struct NntpError(pub String);
+/// Error type for NNTP operations that can be sent across channels.
+///
+/// Upstream failures are classified once, where the worker receives them, so
+/// callers decide on retries, negative caching and reconnects by variant
+/// rather than by matching error text.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NntpError {
+    /// The server has no such article or group (411, 420-423, 430)
+    NotFound { code: u16, message: String },
+    /// The server wants authentication or refused it (480-483, 502)
+    AuthRequired(String),
+    /// No answer within the request timeout
+    Timeout(String),
+    /// The connection or the worker serving it went away
+    ConnectionLost(String),
+    /// Any other failure reply, or a response that could not be understood
+    ProtocolError { code: Option<u16>, message: String },
+}
+
+impl NntpError {
+    /// Classify an upstream error message by the reply code it carries.
+    /// Messages without a reply code come from the transport, not the server,
+    /// unless their text names a missing article or group.
+    pub fn from_reply(message: impl Into<String>) -> Self {
+        let message = message.into();
+        let lower = message.to_lowercase();
+        let code = reply_code(&message).or_else(|| {
+            if lower.contains("no such article") || lower.contains("article not found") {
+                Some(430)
+            } else if lower.contains("no such newsgroup") || lower.contains("group not found") {
+                Some(411)
+            } else {
+                None
+            }
+        });
+        match code {
+            Some(code @ (411 | 420..=423 | 430)) => NntpError::NotFound { code, message },
+            Some(480..=483 | 502) => NntpError::AuthRequired(message),
+            Some(code) => NntpError::ProtocolError {
+                code: Some(code),
+                message,
+            },
+            None if lower.contains("timed out") => NntpError::Timeout(message),
+            None => NntpError::ConnectionLost(message),
+        }
+    }
+
+    /// Failure reply with no NNTP reply code, e.g. an unexpected response shape
+    pub fn protocol(message: impl Into<String>) -> Self {
+        NntpError::ProtocolError {
+            code: None,
+            message: message.into(),
+        }
+    }
+
+    /// The article does not exist (430, or 420-423 within a group)
+    pub fn is_article_missing(&self) -> bool {
+        matches!(self, NntpError::NotFound { code, .. } if *code != 411)
+    }
+
+    /// The newsgroup does not exist (411)
+    pub fn is_group_missing(&self) -> bool {
+        matches!(self, NntpError::NotFound { code: 411, .. })
+    }
+
+    /// Whether trying again may succeed: timeouts, lost connections, and the
+    /// 400 (service temporarily unavailable) and 403 (internal fault) replies
+    pub fn is_transient(&self) -> bool {
+        matches!(
+            self,
+            NntpError::Timeout(_)
+                | NntpError::ConnectionLost(_)
+                | NntpError::ProtocolError {
+                    code: Some(400 | 403),
+                    ..
+                }
+        )
+    }
+
+    /// Whether the connection can no longer be trusted and must be replaced
+    pub fn requires_reconnect(&self) -> bool {
+        matches!(self, NntpError::Timeout(_) | NntpError::ConnectionLost(_))
+    }
+
+    /// Short label for logs and metrics
+    pub fn kind(&self) -> &'static str {
+        match self {
+            NntpError::NotFound { .. } => "not_found",
+            NntpError::AuthRequired(_) => "auth_required",
+            NntpError::Timeout(_) => "timeout",
+            NntpError::ConnectionLost(_) => "connection_lost",
+            NntpError::ProtocolError { .. } => "protocol_error",
+        }
+    }
+}
+
+impl From<nntp_rs::Error> for NntpError {
+    fn from(error: nntp_rs::Error) -> Self {
+        NntpError::from_reply(error.to_string())
+    }
+}
 
 impl std::fmt::Display for NntpError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}", self.0)
+        match self {
+            NntpError::NotFound { message, .. }
+            | NntpError::AuthRequired(message)
+            | NntpError::Timeout(message)
+            | NntpError::ConnectionLost(message)
+            | NntpError::ProtocolError { message, .. } => write!(f, "{}", message),
+        }
     }
 }
 
 impl std::error::Error for NntpError {}
 
+/// NNTP reply code (100-599) opening the server's reply in an error message.
+///
+/// The reply is either the whole message or follows a "context: " prefix.
+/// Digits anywhere else, like an OS error number, a port or a group name
+/// such as comp.sys.486, are not a reply code.
+fn reply_code(message: &str) -> Option<u16> {
+    std::iter::once(message)
+        .chain(message.match_indices(": ").map(|(i, _)| &message[i + 2..]))
+        .find_map(|reply| {
+            let code = reply.get(..3)?;
+            let rest = &reply[3..];
+            if !code.bytes().all(|b| b.is_ascii_digit())
+                || !(rest.is_empty() || rest.starts_with(' '))
+            {
+                return None;
+            }
+            code.parse().ok().filter(|code| (100..600).contains(code))
+        })
+}
+
 /// Group statistics including last article date
 #[derive(Debug, Clone)]
 pub struct GroupStatsView {
@@ -56,6 +181,8 @@ pub struct GroupStatsView {
     pub last_article_date: Option<String>,
     /// Last article number (high water mark for incremental updates)
     pub last_article_number: u64,
+    /// Estimated posting rate, used to size the overview fetch window
+    pub posts_per_day: Option<f64>,
 }
 
 /// Request messages sent to NNTP workers
@@ -74,6 +201,9 @@ pub enum NntpRequest {
     /// Fetch a single article by message ID
     GetArticle {
         message_id: String,
+        /// Warming the cache ahead of the reader (low priority) rather than
+        /// filling a page someone is waiting for
+        prefetch: bool,
         response: oneshot::Sender<Result<ArticleView, NntpError>>,
     },
     /// Fetch group statistics including last article date
@@ -109,8 +239,10 @@ impl NntpRequest {
     /// - High: User clicked something and is waiting (GetArticle, PostArticle)
     /// - Normal: Page load operations (GetThreads, GetGroups)
     /// - Low: Background refresh operations (GetGroupStats, GetNewArticles)
+    ///   and prefetches of the next page's articles
     pub fn priority(&self) -> Priority {
         match self {
+            NntpRequest::GetArticle { prefetch: true, .. } => Priority::Low,
             NntpRequest::GetArticle { .. }
             | NntpRequest::PostArticle { .. }
             | NntpRequest::CheckArticleExists { .. } => Priority::High,
@@ -119,6 +251,43 @@ impl NntpRequest {
         }
     }
 
+    /// Whether sending this request twice is harmless, so it may be retried.
+    /// Everything but posting only reads.
+    pub fn is_idempotent(&self) -> bool {
+        !matches!(self, NntpRequest::PostArticle { .. })
+    }
+
+    /// Whether the caller has stopped waiting for the response, e.g. because
+    /// its HTTP client disconnected. Posts never count: the user already
+    /// submitted them, so they go out either way.
+    pub fn is_abandoned(&self) -> bool {
+        match self {
+            NntpRequest::GetGroups { response } => response.is_closed(),
+            NntpRequest::GetThreads { response, .. } => response.is_closed(),
+            NntpRequest::GetArticle { response, .. } => response.is_closed(),
+            NntpRequest::GetGroupStats { response, .. } => response.is_closed(),
+            NntpRequest::GetNewArticles { response, .. } => response.is_closed(),
+            NntpRequest::PostArticle { .. } => false,
+            NntpRequest::CheckArticleExists { response, .. } => response.is_closed(),
+        }
+    }
+
+    /// Group whose worker should serve this request.
+    ///
+    /// Group listings and background refreshes are routed by group so they
+    /// reuse a connection that has the group selected. Thread lists are
+    /// routed only to an idle worker (see `NntpService`), and other
+    /// user-facing requests are not routed, so a busy worker never holds
+    /// them up.
+    pub fn affinity_group(&self) -> Option<&str> {
+        match self {
+            NntpRequest::GetThreads { group, .. }
+            | NntpRequest::GetGroupStats { group, .. }
+            | NntpRequest::GetNewArticles { group, .. } => Some(group),
+            _ => None,
+        }
+    }
+
     /// Send the response for this request
     pub fn respond(self, result: Result<NntpResponse, NntpError>) {
         match self {
@@ -175,6 +344,30 @@ impl NntpRequest {
     }
 }
 
+/// A request waiting in a worker queue, with the deadline of the HTTP
+/// request it serves (none for background work)
+pub struct QueuedRequest {
+    pub request: NntpRequest,
+    pub deadline: Option<Instant>,
+    /// When the request was queued, to measure how long it waited
+    pub queued_at: Instant,
+}
+
+impl QueuedRequest {
+    pub fn new(request: NntpRequest, deadline: Option<Instant>) -> Self {
+        Self {
+            request,
+            deadline,
+            queued_at: Instant::now(),
+        }
+    }
+
+    /// Whether the caller's deadline has passed, so the work would be wasted
+    pub fn is_expired(&self, now: Instant) -> bool {
+        self.deadline.is_some_and(|deadline| now >= deadline)
+    }
+}
+
 /// Response types from NNTP operations
 pub enum NntpResponse {
     Groups(Vec<GroupView>),
@@ -196,11 +389,23 @@ mod tests {
         let (tx, _rx) = oneshot::channel();
         let req = NntpRequest::GetArticle {
             message_id: "test@example.com".to_string(),
+            prefetch: false,
             response: tx,
         };
         assert_eq!(req.priority(), Priority::High);
     }
 
+    #[test]
+    fn test_priority_prefetch_article_is_low() {
+        let (tx, _rx) = oneshot::channel();
+        let req = NntpRequest::GetArticle {
+            message_id: "a@example.com".to_string(),
+            prefetch: true,
+            response: tx,
+        };
+        assert_eq!(req.priority(), Priority::Low);
+    }
+
     #[test]
     fn test_priority_post_article_is_high() {
         let (tx, _rx) = oneshot::channel();
@@ -261,6 +466,87 @@ mod tests {
         assert_eq!(req.priority(), Priority::Low);
     }
 
+    #[test]
+    fn test_affinity_group_only_for_group_requests() {
+        let (tx, _rx) = oneshot::channel();
+        let req = NntpRequest::GetNewArticles {
+            group: "test.group".to_string(),
+            since_article_number: 100,
+            response: tx,
+        };
+        assert_eq!(req.affinity_group(), Some("test.group"));
+
+        let (tx, _rx) = oneshot::channel();
+        let req = NntpRequest::GetThreads {
+            group: "test.group".to_string(),
+            count: 100,
+            response: tx,
+        };
+        assert_eq!(req.affinity_group(), Some("test.group"));
+
+        let (tx, _rx) = oneshot::channel();
+        let req = NntpRequest::GetArticle {
+            message_id: "test@example.com".to_string(),
+            prefetch: false,
+            response: tx,
+        };
+        assert_eq!(req.affinity_group(), None);
+    }
+
+    #[test]
+    fn test_only_posting_is_not_idempotent() {
+        let (tx, _rx) = oneshot::channel();
+        let req = NntpRequest::PostArticle {
+            headers: vec![],
+            body: "test".to_string(),
+            response: tx,
+        };
+        assert!(!req.is_idempotent());
+
+        let (tx, _rx) = oneshot::channel();
+        let req = NntpRequest::GetGroupStats {
+            group: "test.group".to_string(),
+            response: tx,
+        };
+        assert!(req.is_idempotent());
+    }
+
+    #[test]
+    fn test_request_abandoned_when_receiver_dropped() {
+        let (tx, rx) = oneshot::channel();
+        let req = NntpRequest::GetArticle {
+            message_id: "<test@example.com>".to_string(),
+            prefetch: false,
+            response: tx,
+        };
+        assert!(!req.is_abandoned());
+        drop(rx);
+        assert!(req.is_abandoned());
+
+        // Posts go out even if nobody waits for the result
+        let (tx, rx) = oneshot::channel();
+        let req = NntpRequest::PostArticle {
+            headers: Vec::new(),
+            body: String::new(),
+            response: tx,
+        };
+        drop(rx);
+        assert!(!req.is_abandoned());
+    }
+
+    #[test]
+    fn test_queued_request_expiry() {
+        let (tx, _rx) = oneshot::channel();
+        let now = Instant::now();
+        let request = NntpRequest::GetGroups { response: tx };
+        let queued = QueuedRequest::new(request, Some(now));
+        assert!(queued.is_expired(now));
+
+        let (tx, _rx) = oneshot::channel();
+        let queued = QueuedRequest::new(NntpRequest::GetGroups { response: tx }, None);
+        assert!(!queued.is_expired(now));
+    }
+
     #[test]
     fn test_priority_display() {
         assert_eq!(format!("{}", Priority::High), "high");
@@ -278,7 +564,58 @@ mod tests {
 
     #[test]
     fn test_nntp_error_display() {
-        let err = NntpError("connection failed".to_string());
+        let err = NntpError::ConnectionLost("connection failed".to_string());
         assert_eq!(format!("{}", err), "connection failed");
     }
+
+    #[test]
+    fn test_nntp_error_classification() {
+        let err = NntpError::from_reply("430 No Such Article Found");
+        assert!(err.is_article_missing());
+        assert!(!err.is_group_missing());
+        assert!(NntpError::from_reply("server error: no such article").is_article_missing());
+        assert!(NntpError::from_reply("no such newsgroup").is_group_missing());
+
+        let err = NntpError::from_reply("411 No such newsgroup");
+        assert!(err.is_group_missing());
+        assert!(!err.is_article_missing());
+
+        assert_eq!(
+            NntpError::from_reply("480 Authentication required").kind(),
+            "auth_required"
+        );
+        assert_eq!(
+            NntpError::from_reply("441 Posting failed"),
+            NntpError::ProtocolError {
+                code: Some(441),
+                message: "441 Posting failed".to_string()
+            }
+        );
+
+        let err = NntpError::from_reply("Connection reset by peer");
+        assert_eq!(err.kind(), "connection_lost");
+        assert!(err.requires_reconnect());
+        assert!(NntpError::from_reply("operation timed out").requires_reconnect());
+        assert!(!NntpError::from_reply("502 Permission denied").requires_reconnect());
+    }
+
+    #[test]
+    fn test_nntp_error_reply_code_opens_the_reply() {
+        assert_eq!(
+            NntpError::from_reply("HDR Date failed: 430 No such article").kind(),
+            "not_found"
+        );
+
+        // Numbers outside the reply are not reply codes
+        let err = NntpError::from_reply("Connection reset by peer (os error 104)");
+        assert_eq!(err.kind(), "connection_lost");
+        assert!(err.requires_reconnect());
+        let err = NntpError::from_reply("Connection timed out (os error 110)");
+        assert_eq!(err.kind(), "timeout");
+        assert!(err.requires_reconnect());
+        assert!(
+            NntpError::from_reply("connect to news.example.com:563 failed").requires_reconnect()
+        );
+        assert!(NntpError::from_reply("GROUP comp.sys.486: broken pipe").requires_reconnect());
+    }
 }
@@ -6,24 +6,62 @@
 //!
 //! Key re-exports:
 //! - [`NntpFederatedService`] - Federated NNTP service for multi-server access
-
+//! - [`spawn_listener`] - Read-only NNTP listener for downstream newsreaders
+
+mod availability;
+mod cancel;
+mod charset;
+mod dates;
+mod dial;
+mod diff;
+mod dns;
+mod duplicates;
 mod federated;
+mod footers;
+mod hotness;
 mod messages;
+mod overview;
+mod participants;
+mod privacy;
+mod quotes;
+mod retry;
+mod ring;
+mod sanitize;
+mod scaling;
+mod server;
 mod service;
+mod socks;
+mod subject;
+mod throttle;
 mod tls;
+mod votes;
+mod wildmat;
 mod worker;
 
+pub use dates::parse_date;
+pub use diff::{diff_lines, extract_diff, find_diff};
 pub use federated::NntpFederatedService;
+pub use participants::Participant;
+pub use privacy::with_from_display;
+pub use quotes::{split_passages, Passage, QuoteLinks};
+pub use sanitize::sanitize_header;
+pub use server::spawn_listener;
+pub use wildmat::wildmat;
 
-use std::collections::HashMap;
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
 
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, NaiveDate, Utc};
 use nntp_rs::OverviewEntry;
 use serde::Serialize;
 
+use privacy::mask_from;
+use sanitize::sanitize_header_block;
+
 use crate::config::{
-    DEFAULT_PREVIEW_LINES, DEFAULT_SUBJECT, PAGINATION_WINDOW, PREVIEW_HARD_LIMIT, SECONDS_PER_DAY,
-    SECONDS_PER_HOUR, SECONDS_PER_MINUTE, SECONDS_PER_MONTH, SECONDS_PER_YEAR,
+    FromDisplay, DEFAULT_PREVIEW_LINES, DEFAULT_SUBJECT, HOT_THREAD_MIN_SCORE, PAGINATION_WINDOW,
+    PREVIEW_HARD_LIMIT, SECONDS_PER_DAY, SECONDS_PER_HOUR, SECONDS_PER_MINUTE, SECONDS_PER_MONTH,
+    SECONDS_PER_YEAR, THREAD_PARTICIPANTS_SHOWN,
 };
 
 /// Pagination state for paginated list views.
@@ -37,6 +75,14 @@ pub struct PaginationInfo {
     pub has_next: bool,
     /// Visible page numbers for navigation (e.g., [1, 2, 3, 4, 5])
     pub visible_pages: Vec<usize>,
+    /// Whether a link to page 1 is needed before the visible window
+    pub show_first: bool,
+    /// Whether pages are skipped between page 1 and the visible window
+    pub first_ellipsis: bool,
+    /// Whether a link to the last page is needed after the visible window
+    pub show_last: bool,
+    /// Whether pages are skipped between the visible window and the last page
+    pub last_ellipsis: bool,
 }
 
 impl PaginationInfo {
@@ -48,6 +94,8 @@ impl PaginationInfo {
         };
 
         let visible_pages = Self::compute_visible_pages(current_page, total_pages);
+        let window_start = visible_pages.first().copied().unwrap_or(1);
+        let window_end = visible_pages.last().copied().unwrap_or(total_pages);
 
         Self {
             current_page,
@@ -57,6 +105,10 @@ impl PaginationInfo {
             has_prev: current_page > 1,
             has_next: current_page < total_pages,
             visible_pages,
+            show_first: window_start > 1,
+            first_ellipsis: window_start > 2,
+            show_last: window_end < total_pages,
+            last_ellipsis: window_end + 1 < total_pages,
         }
     }
 
@@ -71,6 +123,9 @@ impl PaginationInfo {
 #[derive(Debug, Clone, Serialize)]
 pub struct ThreadView {
     pub subject: String,
+    /// Subject with reply prefixes and list tags stripped for display.
+    /// Equal to `subject` until the federated service applies `SubjectRules`.
+    pub normalized_subject: String,
     pub root_message_id: String,
     pub article_count: usize,
     pub root: ThreadNodeView,
@@ -78,6 +133,133 @@ pub struct ThreadView {
     pub last_post_date: Option<String>,
     /// Pre-computed relative time for last post (e.g., "2 hours ago")
     pub last_post_date_relative: Option<String>,
+    /// First posters in the thread with their post counts
+    pub participants: Vec<Participant>,
+    /// Number of distinct posters
+    pub participant_count: usize,
+    /// Recent reply velocity (see `hotness`)
+    pub hotness: f64,
+    /// Whether `hotness` reaches `HOT_THREAD_MIN_SCORE`
+    pub hot: bool,
+}
+
+impl ThreadView {
+    /// Recount the thread's posters and rescore its hotness; call whenever
+    /// articles are added
+    pub fn update_stats(&mut self) {
+        (self.participants, self.participant_count) =
+            participants::collect(&self.root, THREAD_PARTICIPANTS_SHOWN);
+        self.hotness = hotness::score(&self.root, Utc::now());
+        self.hot = self.hotness >= HOT_THREAD_MIN_SCORE;
+    }
+}
+
+/// Filter applied to a group's thread list (`?since=`, `?until=`, `?author=`).
+///
+/// Dates are inclusive calendar days in UTC and are matched against the
+/// thread's last post date; `author` is a case-insensitive substring of the
+/// thread starter's From header as it is displayed, so masked addresses
+/// can't be recovered by probing.
+#[derive(Debug, Clone, Default)]
+pub struct ThreadFilter {
+    pub since: Option<DateTime<Utc>>,
+    pub until: Option<DateTime<Utc>>,
+    pub author: Option<String>,
+    /// Lowercased subject tag including brackets, e.g. "[bug]"
+    pub tag: Option<String>,
+    /// How From headers are displayed (`[ui] from_display`)
+    pub from_display: FromDisplay,
+}
+
+impl ThreadFilter {
+    /// Build a filter from raw query values. Dates use `YYYY-MM-DD`; empty or
+    /// unparseable values are ignored.
+    pub fn from_params(since: Option<&str>, until: Option<&str>, author: Option<&str>) -> Self {
+        let parse_day = |s: &str| NaiveDate::parse_from_str(s.trim(), "%Y-%m-%d").ok();
+
+        Self {
+            since: since
+                .and_then(parse_day)
+                .and_then(|d| d.and_hms_opt(0, 0, 0))
+                .map(|dt| dt.and_utc()),
+            until: until
+                .and_then(parse_day)
+                .and_then(|d| d.and_hms_opt(23, 59, 59))
+                .map(|dt| dt.and_utc()),
+            author: author
+                .map(|a| a.trim().to_lowercase())
+                .filter(|a| !a.is_empty()),
+            tag: None,
+            from_display: FromDisplay::default(),
+        }
+    }
+
+    /// Match `author` against From headers masked the way they are displayed.
+    pub fn with_from_display(mut self, mode: FromDisplay) -> Self {
+        self.from_display = mode;
+        self
+    }
+
+    /// Only match threads whose subject carries this tag.
+    /// Accepts "BUG" or "[BUG]"; matching is case-insensitive.
+    pub fn with_tag(mut self, tag: Option<&str>) -> Self {
+        self.tag = tag
+            .map(|t| {
+                t.trim()
+                    .trim_start_matches('[')
+                    .trim_end_matches(']')
+                    .trim()
+            })
+            .filter(|t| !t.is_empty())
+            .map(|t| format!("[{}]", t.to_lowercase()));
+        self
+    }
+
+    /// Whether any criterion is set.
+    pub fn is_active(&self) -> bool {
+        self.since.is_some() || self.until.is_some() || self.author.is_some() || self.tag.is_some()
+    }
+
+    /// Check whether a thread passes the filter.
+    /// Threads without a parseable last post date never match a date range.
+    pub fn matches(&self, thread: &ThreadView) -> bool {
+        if self.since.is_some() || self.until.is_some() {
+            let Some(last_post) = thread
+                .last_post_date
+                .as_deref()
+                .and_then(parse_date)
+                .map(|d| d.with_timezone(&Utc))
+            else {
+                return false;
+            };
+            if self.since.is_some_and(|since| last_post < since) {
+                return false;
+            }
+            if self.until.is_some_and(|until| last_post > until) {
+                return false;
+            }
+        }
+
+        if let Some(author) = &self.author {
+            let from = thread
+                .root
+                .article
+                .as_ref()
+                .map(|a| mask_from(&a.from, self.from_display).to_lowercase())
+                .unwrap_or_default();
+            if !from.contains(author.as_str()) {
+                return false;
+            }
+        }
+
+        if let Some(tag) = &self.tag {
+            if !thread.subject.to_lowercase().contains(tag.as_str()) {
+                return false;
+            }
+        }
+
+        true
+    }
 }
 
 /// Node in a threaded article tree with child replies.
@@ -101,6 +283,57 @@ pub struct FlatComment {
     pub descendant_count: usize,
     /// Whether this comment starts a collapsed section
     pub starts_collapsed: bool,
+    /// Collapsed comment this one is hidden under, when its body was left
+    /// unfetched until that comment is expanded
+    pub deferred_under: Option<String>,
+    /// The body fetch failed on a server error; reloading may bring it
+    pub body_unavailable: bool,
+    /// Passages of the parent this reply quotes, found by comparing bodies
+    pub quotes: Option<QuoteLinks>,
+}
+
+/// Which comment bodies a thread page fetches
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BodyFetch<'a> {
+    /// Every comment on the page, for layouts that don't collapse
+    All,
+    /// Only comments not hidden under a collapsed comment on the page,
+    /// plus the whole subtree of `expand`
+    Visible { expand: Option<&'a str> },
+}
+
+/// For each comment of a page, the outermost collapsed comment above it on
+/// the same page, or `None` if it starts visible. Comments in the subtree of
+/// `expand` count as visible. Collapsed comments on earlier pages are not
+/// considered: the page's own markup cannot hide anything under them.
+pub fn collapsed_ancestors(comments: &[FlatComment], expand: Option<&str>) -> Vec<Option<String>> {
+    let mut result = Vec::with_capacity(comments.len());
+    // (depth, message ID) of the collapsed comment hiding what follows
+    let mut hidden: Option<(usize, &str)> = None;
+    // Depth of the expanded comment while inside its subtree
+    let mut expanded: Option<usize> = None;
+
+    for comment in comments {
+        if hidden.is_some_and(|(depth, _)| comment.depth <= depth) {
+            hidden = None;
+        }
+        if expanded.is_some_and(|depth| comment.depth <= depth) {
+            expanded = None;
+        }
+
+        result.push(match (hidden, expanded) {
+            (Some((_, id)), None) => Some(id.to_string()),
+            _ => None,
+        });
+
+        if expand == Some(comment.message_id.as_str()) {
+            expanded = Some(comment.depth);
+        } else if hidden.is_none() && expanded.is_none() && comment.starts_collapsed {
+            hidden = Some((comment.depth, &comment.message_id));
+        }
+    }
+
+    result
 }
 
 impl ThreadNodeView {
@@ -138,6 +371,9 @@ impl ThreadNodeView {
                 depth,
                 descendant_count: node.descendant_count,
                 starts_collapsed,
+                deferred_under: None,
+                body_unavailable: false,
+                quotes: None,
             });
 
             // Add replies in reverse order so they're processed in correct order
@@ -183,6 +419,8 @@ impl ThreadNodeView {
 pub struct ArticleView {
     pub message_id: String,
     pub subject: String,
+    /// Serialized according to `[ui] from_display` (see `privacy`)
+    #[serde(serialize_with = "privacy::serialize_from")]
     pub from: String,
     pub date: String,
     /// Pre-computed relative time (e.g., "2 hours ago")
@@ -193,9 +431,70 @@ pub struct ArticleView {
     /// Whether body exceeds preview length
     pub has_more_content: bool,
     /// Raw headers for full header display (only populated for single article view)
+    #[serde(serialize_with = "privacy::serialize_headers")]
     pub headers: Option<String>,
 }
 
+impl ArticleView {
+    /// Groups in the Newsgroups header; empty when headers weren't fetched
+    pub fn newsgroups(&self) -> Vec<String> {
+        self.headers
+            .as_deref()
+            .and_then(|headers| charset::header_value(headers, "Newsgroups"))
+            .map(|value| {
+                value
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|group| !group.is_empty())
+                    .map(str::to_string)
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+}
+
+/// Delivery state of an article posted through September.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DeliveryStatus {
+    /// Accepted by POST but not yet visible on the server
+    Pending,
+    /// Confirmed visible on the server via STAT
+    Confirmed,
+    /// Not visible after the polling window; may still propagate
+    Unconfirmed,
+}
+
+/// Delivery status of a recently posted article, for optimistic UI placeholders.
+#[derive(Debug, Clone, Serialize)]
+pub struct PendingPostView {
+    pub message_id: String,
+    pub status: DeliveryStatus,
+}
+
+/// A recently active thread listed on the home page, with its group.
+#[derive(Debug, Clone, Serialize)]
+pub struct TopThreadView {
+    pub group: String,
+    pub normalized_subject: String,
+    pub root_message_id: String,
+    pub article_count: usize,
+    pub last_post_date: Option<String>,
+    pub hot: bool,
+}
+
+/// What the cache already knows about a page's content, so HEAD requests
+/// can be answered without fetching bodies or rendering.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CachedStatus {
+    /// Cached, with the date of its newest article if that parses
+    Present {
+        last_modified: Option<DateTime<Utc>>,
+    },
+    /// Every server recently reported it missing
+    Missing,
+}
+
 /// Newsgroup metadata including name, description, and article counts.
 #[derive(Debug, Clone, Serialize)]
 pub struct GroupView {
@@ -384,16 +683,20 @@ impl GroupTreeNode {
 }
 
 /// Parse a raw NNTP article into an [`ArticleView`].
+///
+/// Headers and body are decoded with the Content-Type charset (legacy posts
+/// are often ISO-8859-x or KOI8-R), falling back to detection when undeclared.
 pub fn parse_article(article: &nntp_rs::Article) -> ArticleView {
+    let raw_headers = article.raw_headers();
+    let declared = raw_headers.and_then(charset::declared_charset);
+
     // Extract raw headers as string for display
-    let headers = article
-        .raw_headers()
-        .map(|h| String::from_utf8_lossy(h).to_string());
+    let headers = raw_headers.map(|h| sanitize_header_block(&charset::decode(h, declared)));
 
     let date = article.date().unwrap_or_default();
     let date_relative = compute_timeago(&date);
 
-    let body = article.body_text();
+    let body = article.body().map(|b| charset::decode(b, declared));
     let (body_preview, has_more_content) = match &body {
         Some(b) => {
             let (preview, more) = compute_preview(b);
@@ -404,8 +707,12 @@ pub fn parse_article(article: &nntp_rs::Article) -> ArticleView {
 
     ArticleView {
         message_id: article.article_id().to_string(),
-        subject: article.subject().unwrap_or_default(),
-        from: article.from().unwrap_or_default(),
+        subject: charset::repair_header(article.subject(), headers.as_deref(), "subject")
+            .map(|s| sanitize_header(&s))
+            .unwrap_or_default(),
+        from: charset::repair_header(article.from(), headers.as_deref(), "from")
+            .map(|f| sanitize_header(&f))
+            .unwrap_or_default(),
         date,
         date_relative,
         body,
@@ -415,191 +722,29 @@ pub fn parse_article(article: &nntp_rs::Article) -> ArticleView {
     }
 }
 
+/// Message IDs in a References header, oldest first.
+///
+/// The first is the thread root and the last the direct parent. The
+/// article's own ID is dropped, since it would make the article its own
+/// ancestor.
+pub fn parse_references<'a>(references: Option<&'a str>, message_id: &str) -> Vec<&'a str> {
+    references
+        .unwrap_or_default()
+        .split_whitespace()
+        .filter(|id| *id != message_id)
+        .collect()
+}
+
 /// Build a thread list from NNTP OVER command response data.
 ///
 /// Uses the References header to reconstruct thread structure.
 pub fn build_threads_from_overview(entries: Vec<OverviewEntry>) -> Vec<ThreadView> {
-    if entries.is_empty() {
-        return Vec::new();
-    }
-
-    // Build a map of message_id -> OverviewEntry for quick lookup
-    let mut entries_by_id: HashMap<String, &OverviewEntry> = HashMap::new();
-    for entry in &entries {
-        if let Some(msg_id) = entry.message_id() {
-            entries_by_id.insert(msg_id.to_string(), entry);
-        }
-    }
-
-    // Group entries by thread root (first message in references chain, or self if no references)
-    let mut threads_map: HashMap<String, Vec<&OverviewEntry>> = HashMap::new();
-
-    for entry in &entries {
-        let msg_id = match entry.message_id() {
-            Some(id) => id.to_string(),
-            None => continue,
-        };
-
-        // Parse references to find thread root
-        let root_id = if let Some(refs) = entry.references() {
-            if refs.trim().is_empty() {
-                // No references - this is a root message
-                msg_id.clone()
-            } else {
-                // First reference is the thread root
-                refs.split_whitespace()
-                    .next()
-                    .unwrap_or(&msg_id)
-                    .to_string()
-            }
-        } else {
-            // No references field - this is a root message
-            msg_id.clone()
-        };
-
-        threads_map.entry(root_id).or_default().push(entry);
-    }
-
-    // Build ThreadView for each thread
-    let mut thread_views: Vec<ThreadView> = Vec::new();
-
-    for (root_id, thread_entries) in threads_map {
-        // Find the actual root entry (might not be in our entries if it's older/expired)
-        let root_entry = thread_entries
+    build_threads_from_hdr(
+        entries
             .iter()
-            .find(|e| e.message_id() == Some(&root_id));
-
-        // Get subject from root entry if available, otherwise from first available entry
-        let subject = root_entry
-            .or_else(|| thread_entries.first())
-            .and_then(|e| e.subject())
-            .unwrap_or(DEFAULT_SUBJECT)
-            .to_string();
-
-        // Build the tree structure using original root_id
-        // If root article is missing, build_node_from_entry will create a node with article: None
-        let root_node = build_thread_tree(&root_id, &thread_entries, &entries_by_id);
-        let last_post_date = find_latest_date_overview(&thread_entries);
-
-        let last_post_date_relative = last_post_date.as_ref().map(|d| compute_timeago(d));
-
-        thread_views.push(ThreadView {
-            subject,
-            // Always use original root_id so thread can be found even if root article is missing
-            root_message_id: root_id,
-            article_count: thread_entries.len(),
-            root: root_node,
-            last_post_date,
-            last_post_date_relative,
-        });
-    }
-
-    thread_views
-}
-
-/// Build a ThreadNodeView tree from overview entries
-fn build_thread_tree(
-    root_id: &str,
-    entries: &[&OverviewEntry],
-    _entries_by_id: &HashMap<String, &OverviewEntry>,
-) -> ThreadNodeView {
-    // Build parent -> children map from references
-    let mut children_map: HashMap<String, Vec<&OverviewEntry>> = HashMap::new();
-
-    for entry in entries {
-        let _msg_id = match entry.message_id() {
-            Some(id) => id.to_string(),
-            None => continue,
-        };
-
-        // Find direct parent from references (last reference is direct parent)
-        let parent_id = if let Some(refs) = entry.references() {
-            if refs.trim().is_empty() {
-                None // Root message
-            } else {
-                refs.split_whitespace().last().map(|s| s.to_string())
-            }
-        } else {
-            None
-        };
-
-        if let Some(parent) = parent_id {
-            children_map.entry(parent).or_default().push(entry);
-        }
-    }
-
-    // Build tree recursively from root
-    build_node_from_entry(root_id, entries, &children_map)
-}
-
-/// Build a single node and its children
-fn build_node_from_entry(
-    msg_id: &str,
-    entries: &[&OverviewEntry],
-    children_map: &HashMap<String, Vec<&OverviewEntry>>,
-) -> ThreadNodeView {
-    // Find the entry for this message
-    let entry = entries.iter().find(|e| e.message_id() == Some(msg_id));
-
-    let article = entry.map(|e| overview_entry_to_article_view(e));
-
-    // Build child nodes
-    let mut replies: Vec<ThreadNodeView> = Vec::new();
-    if let Some(children) = children_map.get(msg_id) {
-        for child in children {
-            if let Some(child_id) = child.message_id() {
-                let child_node = build_node_from_entry(child_id, entries, children_map);
-                replies.push(child_node);
-            }
-        }
-    }
-
-    // Compute descendant count
-    let descendant_count: usize = replies.iter().map(|r| 1 + r.descendant_count).sum();
-
-    ThreadNodeView {
-        message_id: msg_id.to_string(),
-        article,
-        replies,
-        descendant_count,
-    }
-}
-
-/// Convert OverviewEntry to ArticleView
-fn overview_entry_to_article_view(entry: &OverviewEntry) -> ArticleView {
-    let date = entry.date().unwrap_or("").to_string();
-    let date_relative = compute_timeago(&date);
-
-    ArticleView {
-        message_id: entry.message_id().unwrap_or("").to_string(),
-        subject: entry.subject().unwrap_or(DEFAULT_SUBJECT).to_string(),
-        from: entry.from().unwrap_or("").to_string(),
-        date,
-        date_relative,
-        body: None, // Overview doesn't include body
-        body_preview: None,
-        has_more_content: false,
-        headers: None,
-    }
-}
-
-/// Find the latest date from overview entries
-fn find_latest_date_overview(entries: &[&OverviewEntry]) -> Option<String> {
-    use chrono::DateTime;
-
-    let mut latest: Option<(String, DateTime<chrono::FixedOffset>)> = None;
-
-    for entry in entries {
-        if let Some(date_str) = entry.date() {
-            if let Ok(parsed) = DateTime::parse_from_rfc2822(date_str) {
-                if latest.is_none() || parsed > latest.as_ref().unwrap().1 {
-                    latest = Some((date_str.to_string(), parsed));
-                }
-            }
-        }
-    }
-
-    latest.map(|(s, _)| s)
+            .filter_map(HdrArticleData::from_overview)
+            .collect(),
+    )
 }
 
 /// Merge new articles into an existing thread cache.
@@ -610,7 +755,25 @@ pub fn merge_articles_into_threads(
     existing: &[ThreadView],
     new_entries: Vec<OverviewEntry>,
 ) -> Vec<ThreadView> {
-    if new_entries.is_empty() {
+    merge_into_threads(
+        existing,
+        new_entries
+            .iter()
+            .filter_map(HdrArticleData::from_overview)
+            .collect(),
+    )
+}
+
+/// [`merge_articles_into_threads`] on extracted header fields.
+///
+/// Merging the same articles again changes nothing, the result does not
+/// depend on the order of `new_articles`, and every new article ends up in
+/// exactly one thread.
+fn merge_into_threads(
+    existing: &[ThreadView],
+    new_articles: Vec<HdrArticleData>,
+) -> Vec<ThreadView> {
+    if new_articles.is_empty() {
         return existing.to_vec();
     }
 
@@ -626,75 +789,61 @@ pub fn merge_articles_into_threads(
         collect_message_ids_to_root(&thread.root, &thread.root_message_id, &mut msg_to_root);
     }
 
-    // Group new entries by which thread they belong to
-    let mut updates_by_thread: HashMap<String, Vec<&OverviewEntry>> = HashMap::new();
-    let mut new_roots: Vec<&OverviewEntry> = Vec::new();
-
-    for entry in &new_entries {
-        let msg_id = match entry.message_id() {
-            Some(id) => id.to_string(),
-            None => continue,
-        };
-
-        // Check if this message references a known message
-        let thread_root = if let Some(refs) = entry.references() {
-            refs.split_whitespace()
-                .find_map(|ref_id| msg_to_root.get(ref_id).cloned())
-        } else {
-            None
-        };
+    // Skip articles already in the cache (e.g. injected right after posting).
+    // Placeholders for referenced but unfetched messages don't count.
+    let cached: HashSet<String> = existing
+        .iter()
+        .flat_map(|t| collect_article_ids(&t.root))
+        .collect();
+    let mut pending: Vec<&HdrArticleData> = new_articles
+        .iter()
+        .filter(|a| !cached.contains(&a.message_id))
+        .collect();
 
-        if let Some(root_id) = thread_root {
-            updates_by_thread.entry(root_id).or_default().push(entry);
-            msg_to_root.insert(msg_id, updates_by_thread.keys().last().unwrap().clone());
-        } else {
-            // Check if this is a known root
-            if threads_by_root.contains_key(&msg_id) {
-                updates_by_thread
-                    .entry(msg_id.clone())
-                    .or_default()
-                    .push(entry);
-            } else {
-                // New thread
-                new_roots.push(entry);
+    // Assign each article to the thread of a message it references. A reply
+    // may only reference other new articles, so repeat until nothing moves;
+    // each round only sees the assignments of earlier rounds.
+    let mut updates_by_thread: HashMap<String, Vec<&HdrArticleData>> = HashMap::new();
+    loop {
+        let mut assigned = Vec::new();
+        pending.retain(|article| match known_root(&msg_to_root, article) {
+            Some(root_id) => {
+                assigned.push((*article, root_id));
+                false
             }
+            None => true,
+        });
+        if assigned.is_empty() {
+            break;
+        }
+        for (article, root_id) in assigned {
+            msg_to_root.insert(article.message_id.clone(), root_id.clone());
+            updates_by_thread.entry(root_id).or_default().push(article);
         }
     }
 
     // Update existing threads with new entries
-    for (root_id, entries) in updates_by_thread {
+    for (root_id, articles) in updates_by_thread {
         if let Some(thread) = threads_by_root.get_mut(&root_id) {
-            // Add new entries to the thread
-            for entry in &entries {
-                if let Some(msg_id) = entry.message_id() {
-                    let new_node = ThreadNodeView {
-                        message_id: msg_id.to_string(),
-                        article: Some(overview_entry_to_article_view(entry)),
-                        replies: Vec::new(),
-                        descendant_count: 0,
-                    };
-
-                    // Find parent in references and add as child
-                    if let Some(refs) = entry.references() {
-                        let parent_id = refs.split_whitespace().last();
-                        if let Some(parent) = parent_id {
-                            add_reply_to_node(&mut thread.root, parent, new_node);
-                        }
-                    }
-                }
-            }
+            let had_root = thread.root.article.is_some();
+            attach_replies(&mut thread.root, &articles);
+            thread.article_count += articles.len();
+            thread.update_stats();
+            thread.last_post_date = later_date(
+                thread.last_post_date.take(),
+                find_latest_date_hdr(&articles),
+            );
 
-            // Update article count and last post date
-            thread.article_count += entries.len();
-            if let last_date @ Some(_) = find_latest_date_overview(&entries) {
-                thread.last_post_date = last_date;
+            // The root arrived after its replies: title the thread from it
+            if let (false, Some(root)) = (had_root, &thread.root.article) {
+                thread.subject = root.subject.clone();
+                thread.normalized_subject = root.subject.clone();
             }
         }
     }
 
-    // Build new threads from new roots
-    let new_thread_entries: Vec<OverviewEntry> = new_roots.iter().map(|e| (*e).clone()).collect();
-    let new_threads = build_threads_from_overview(new_thread_entries);
+    // Whatever references no known message starts a new thread
+    let new_threads = build_threads_from_hdr(pending.into_iter().cloned().collect());
 
     // Combine existing (updated) and new threads
     let mut result: Vec<ThreadView> = threads_by_root.into_values().collect();
@@ -711,63 +860,171 @@ pub fn merge_articles_into_thread(
     existing: &ThreadView,
     new_entries: Vec<OverviewEntry>,
 ) -> ThreadView {
-    if new_entries.is_empty() {
+    merge_into_thread(
+        existing,
+        new_entries
+            .iter()
+            .filter_map(HdrArticleData::from_overview)
+            .collect(),
+    )
+}
+
+/// [`merge_articles_into_thread`] on extracted header fields, with the same
+/// guarantees as [`merge_into_threads`] for the articles that belong to it
+fn merge_into_thread(existing: &ThreadView, new_articles: Vec<HdrArticleData>) -> ThreadView {
+    if new_articles.is_empty() {
         return existing.clone();
     }
 
     // Build set of all message IDs in the existing thread for fast lookup
-    let known_ids = collect_all_message_ids(&existing.root);
+    let mut known_ids = collect_all_message_ids(&existing.root);
 
-    // Filter to only entries that reference a known message ID
-    let relevant_entries: Vec<&OverviewEntry> = new_entries
+    // Entries already in the thread (e.g. injected after posting) are skipped
+    let cached = collect_article_ids(&existing.root);
+    let mut pending: Vec<&HdrArticleData> = new_articles
         .iter()
-        .filter(|entry| {
-            if let Some(refs) = entry.references() {
-                refs.split_whitespace()
-                    .any(|ref_id| known_ids.contains(ref_id))
-            } else {
+        .filter(|a| !cached.contains(&a.message_id))
+        .collect();
+
+    // Keep entries that fill a placeholder or reference a message in the
+    // thread, including ones that only reference another new entry that does
+    let mut relevant: Vec<&HdrArticleData> = Vec::new();
+    loop {
+        let mut found = Vec::new();
+        pending.retain(|article| {
+            let references = parse_references(article.references.as_deref(), &article.message_id);
+            if known_ids.contains(&article.message_id)
+                || references.iter().any(|id| known_ids.contains(*id))
+            {
+                found.push(*article);
                 false
+            } else {
+                true
             }
-        })
-        .collect();
+        });
+        if found.is_empty() {
+            break;
+        }
+        known_ids.extend(found.iter().map(|a| a.message_id.clone()));
+        relevant.extend(found);
+    }
 
-    if relevant_entries.is_empty() {
+    if relevant.is_empty() {
         return existing.clone();
     }
 
     // Clone the thread and add new entries
     let mut updated = existing.clone();
+    attach_replies(&mut updated.root, &relevant);
 
-    for entry in &relevant_entries {
-        if let Some(msg_id) = entry.message_id() {
-            // Skip if already in thread
-            if known_ids.contains(msg_id) {
-                continue;
+    // Update article count and last post date
+    updated.article_count += relevant.len();
+    updated.update_stats();
+    updated.last_post_date = later_date(
+        updated.last_post_date.take(),
+        find_latest_date_hdr(&relevant),
+    );
+
+    updated
+}
+
+/// Root of a thread that the article, as a placeholder, or one of its
+/// references is already in
+fn known_root(msg_to_root: &HashMap<String, String>, article: &HdrArticleData) -> Option<String> {
+    std::iter::once(article.message_id.as_str())
+        .chain(parse_references(
+            article.references.as_deref(),
+            &article.message_id,
+        ))
+        .find_map(|id| msg_to_root.get(id).cloned())
+}
+
+/// Attach new articles to a thread tree, each under the last message in its
+/// References that is in the tree.
+///
+/// An article waits while a closer reference is among the articles still to
+/// attach, so the tree doesn't depend on their order. If they only reference
+/// each other, they go under their closest ancestor in the tree, or the root.
+fn attach_replies(root: &mut ThreadNodeView, articles: &[&HdrArticleData]) {
+    // Articles already in the tree as placeholders only need filling in
+    let mut pending: Vec<&HdrArticleData> = articles
+        .iter()
+        .copied()
+        .filter(|article| !fill_placeholder(root, article))
+        .collect();
+    let mut present = collect_all_message_ids(root);
+    let mut stalled = false;
+
+    while !pending.is_empty() {
+        let waiting: HashSet<&str> = if stalled {
+            HashSet::new()
+        } else {
+            pending
+                .iter()
+                .copied()
+                .map(|a| a.message_id.as_str())
+                .collect()
+        };
+
+        let mut ready: Vec<(&HdrArticleData, String)> = Vec::new();
+        pending.retain(|article| {
+            let references = parse_references(article.references.as_deref(), &article.message_id);
+            let closest = references
+                .iter()
+                .rev()
+                .find(|id| present.contains(**id) || waiting.contains(**id));
+            match closest {
+                Some(id) if waiting.contains(*id) => true,
+                Some(id) => {
+                    ready.push((*article, id.to_string()));
+                    false
+                }
+                None => {
+                    ready.push((*article, root.message_id.clone()));
+                    false
+                }
             }
+        });
 
+        stalled = ready.is_empty();
+        ready.sort_by(|(a, _), (b, _)| reply_order(a, b));
+        for (article, parent_id) in ready {
             let new_node = ThreadNodeView {
-                message_id: msg_id.to_string(),
-                article: Some(overview_entry_to_article_view(entry)),
+                message_id: article.message_id.clone(),
+                article: Some(article.to_article_view()),
                 replies: Vec::new(),
                 descendant_count: 0,
             };
-
-            // Find parent in references and add as child
-            if let Some(refs) = entry.references() {
-                if let Some(parent_id) = refs.split_whitespace().last() {
-                    add_reply_to_node(&mut updated.root, parent_id, new_node);
-                }
-            }
+            add_reply_to_node(root, &parent_id, new_node);
+            present.insert(article.message_id.clone());
         }
     }
+}
 
-    // Update article count and last post date
-    updated.article_count += relevant_entries.len();
-    if let Some(latest) = find_latest_date_overview(&relevant_entries) {
-        updated.last_post_date = Some(latest);
+/// Give the placeholder node for a referenced but unfetched message its
+/// article. Returns false if there is no such placeholder.
+fn fill_placeholder(node: &mut ThreadNodeView, article: &HdrArticleData) -> bool {
+    if node.message_id == article.message_id {
+        if node.article.is_none() {
+            node.article = Some(article.to_article_view());
+            return true;
+        }
+        return false;
     }
+    node.replies
+        .iter_mut()
+        .any(|reply| fill_placeholder(reply, article))
+}
 
-    updated
+/// The later of two post dates, keeping `current` when either doesn't parse
+fn later_date(current: Option<String>, candidate: Option<String>) -> Option<String> {
+    match (current, candidate) {
+        (Some(current), Some(candidate)) => match (parse_date(&current), parse_date(&candidate)) {
+            (Some(a), Some(b)) if b > a => Some(candidate),
+            _ => Some(current),
+        },
+        (current, candidate) => current.or(candidate),
+    }
 }
 
 /// Collect all message IDs in a thread tree and map them to the root
@@ -783,8 +1040,8 @@ fn collect_message_ids_to_root(
 }
 
 /// Collect all message IDs in a thread tree into a HashSet for efficient lookup
-fn collect_all_message_ids(node: &ThreadNodeView) -> std::collections::HashSet<String> {
-    let mut ids = std::collections::HashSet::new();
+fn collect_all_message_ids(node: &ThreadNodeView) -> HashSet<String> {
+    let mut ids = HashSet::new();
     let mut stack = vec![node];
 
     while let Some(n) = stack.pop() {
@@ -797,6 +1054,21 @@ fn collect_all_message_ids(node: &ThreadNodeView) -> std::collections::HashSet<S
     ids
 }
 
+/// Message IDs of the articles in a thread tree, without placeholders
+fn collect_article_ids(node: &ThreadNodeView) -> HashSet<String> {
+    let mut ids = HashSet::new();
+    let mut stack = vec![node];
+
+    while let Some(n) = stack.pop() {
+        if n.article.is_some() {
+            ids.insert(n.message_id.clone());
+        }
+        stack.extend(&n.replies);
+    }
+
+    ids
+}
+
 /// Add a reply node to the appropriate parent in the tree.
 /// Returns true if the parent was found and the reply was added.
 pub fn add_reply_to_node(
@@ -822,7 +1094,7 @@ pub fn add_reply_to_node(
     false
 }
 
-/// Raw article data collected from NNTP HDR commands before parsing.
+/// Header fields used to build threads, from HDR, OVER or HEAD responses.
 #[derive(Debug, Clone)]
 pub struct HdrArticleData {
     pub message_id: String,
@@ -832,6 +1104,56 @@ pub struct HdrArticleData {
     pub date: String,
 }
 
+impl HdrArticleData {
+    /// Fields of an overview entry, or None without a Message-ID
+    fn from_overview(entry: &OverviewEntry) -> Option<Self> {
+        Some(Self {
+            message_id: entry.message_id()?.to_string(),
+            references: entry.references().map(str::to_string),
+            subject: entry.subject().unwrap_or(DEFAULT_SUBJECT).to_string(),
+            from: entry.from().unwrap_or_default().to_string(),
+            date: entry.date().unwrap_or_default().to_string(),
+        })
+    }
+
+    /// Article summary for a thread node; there is no body
+    fn to_article_view(&self) -> ArticleView {
+        ArticleView {
+            message_id: self.message_id.clone(),
+            subject: sanitize_header(&self.subject),
+            from: sanitize_header(&self.from),
+            date: self.date.clone(),
+            date_relative: compute_timeago(&self.date),
+            body: None,
+            body_preview: None,
+            has_more_content: false,
+            headers: None,
+        }
+    }
+}
+
+/// Thread-building fields from a raw HEAD response, or None without a
+/// Message-ID. Used when the server supports neither HDR nor OVER.
+pub fn parse_head(raw: &[u8]) -> Option<HdrArticleData> {
+    let headers = charset::decode_headers(raw);
+    let field = |name| charset::header_value(&headers, name).map(Cow::into_owned);
+    Some(HdrArticleData {
+        message_id: field("message-id").filter(|id| !id.is_empty())?,
+        references: field("references"),
+        subject: field("subject").unwrap_or_else(|| DEFAULT_SUBJECT.to_string()),
+        from: field("from").unwrap_or_default(),
+        date: field("date").unwrap_or_default(),
+    })
+}
+
+/// Order of sibling replies: by date, then Message-ID, so the tree doesn't
+/// depend on the order the server listed articles in
+fn reply_order(a: &HdrArticleData, b: &HdrArticleData) -> std::cmp::Ordering {
+    parse_date(&a.date)
+        .cmp(&parse_date(&b.date))
+        .then_with(|| a.message_id.cmp(&b.message_id))
+}
+
 /// Build a thread list from NNTP HDR command response data.
 ///
 /// Uses the References header to reconstruct thread structure.
@@ -840,32 +1162,14 @@ pub fn build_threads_from_hdr(articles: Vec<HdrArticleData>) -> Vec<ThreadView>
         return Vec::new();
     }
 
-    // Build a map of message_id -> HdrArticleData for quick lookup
-    let mut articles_by_id: HashMap<String, &HdrArticleData> = HashMap::new();
-    for article in &articles {
-        articles_by_id.insert(article.message_id.clone(), article);
-    }
-
     // Group articles by thread root (first message in references chain, or self if no references)
     let mut threads_map: HashMap<String, Vec<&HdrArticleData>> = HashMap::new();
 
     for article in &articles {
-        // Parse references to find thread root
-        let root_id = if let Some(refs) = &article.references {
-            if refs.trim().is_empty() {
-                // No references - this is a root message
-                article.message_id.clone()
-            } else {
-                // First reference is the thread root
-                refs.split_whitespace()
-                    .next()
-                    .unwrap_or(&article.message_id)
-                    .to_string()
-            }
-        } else {
-            // No references field - this is a root message
-            article.message_id.clone()
-        };
+        // First reference is the thread root; without references this is a root message
+        let root_id = parse_references(article.references.as_deref(), &article.message_id)
+            .first()
+            .map_or_else(|| article.message_id.clone(), |root| root.to_string());
 
         threads_map.entry(root_id).or_default().push(article);
     }
@@ -877,20 +1181,21 @@ pub fn build_threads_from_hdr(articles: Vec<HdrArticleData>) -> Vec<ThreadView>
         // Find the actual root article (might not be in our articles if it's older/expired)
         let root_article = thread_articles.iter().find(|a| a.message_id == root_id);
 
-        // Get subject from root article if available, otherwise from first available article
+        // Get subject from root article if available, otherwise from the earliest reply
         let subject = root_article
-            .or_else(|| thread_articles.first())
-            .map(|a| a.subject.clone())
+            .or_else(|| thread_articles.iter().min_by(|a, b| reply_order(a, b)))
+            .map(|a| sanitize_header(&a.subject))
             .unwrap_or_else(|| DEFAULT_SUBJECT.to_string());
 
         // Build the tree structure using original root_id
         // If root article is missing, build_node_from_hdr will create a node with article: None
-        let root_node = build_thread_tree_hdr(&root_id, &thread_articles, &articles_by_id);
+        let root_node = build_thread_tree_hdr(&root_id, &thread_articles);
         let last_post_date = find_latest_date_hdr(&thread_articles);
 
         let last_post_date_relative = last_post_date.as_ref().map(|d| compute_timeago(d));
 
-        thread_views.push(ThreadView {
+        let mut thread = ThreadView {
+            normalized_subject: subject.clone(),
             subject,
             // Always use original root_id so thread can be found even if root article is missing
             root_message_id: root_id,
@@ -898,71 +1203,79 @@ pub fn build_threads_from_hdr(articles: Vec<HdrArticleData>) -> Vec<ThreadView>
             root: root_node,
             last_post_date,
             last_post_date_relative,
-        });
+            participants: Vec::new(),
+            participant_count: 0,
+            hotness: 0.0,
+            hot: false,
+        };
+        thread.update_stats();
+        thread_views.push(thread);
     }
 
     thread_views
 }
 
-/// Build a ThreadNodeView tree from HDR article data
-fn build_thread_tree_hdr(
-    root_id: &str,
-    articles: &[&HdrArticleData],
-    _articles_by_id: &HashMap<String, &HdrArticleData>,
-) -> ThreadNodeView {
+/// Build a ThreadNodeView tree from HDR article data.
+///
+/// Each article goes under the last message in its References that is in
+/// the thread, so a missing intermediate reply doesn't drop its
+/// descendants; with none of them present it goes under the root.
+fn build_thread_tree_hdr(root_id: &str, articles: &[&HdrArticleData]) -> ThreadNodeView {
+    let ids: HashSet<&str> = articles.iter().map(|a| a.message_id.as_str()).collect();
+
     // Build parent -> children map from references
     let mut children_map: HashMap<String, Vec<&HdrArticleData>> = HashMap::new();
 
     for article in articles {
-        // Find direct parent from references (last reference is direct parent)
-        let parent_id = if let Some(refs) = &article.references {
-            if refs.trim().is_empty() {
-                None // Root message
-            } else {
-                refs.split_whitespace().last().map(|s| s.to_string())
-            }
-        } else {
-            None
-        };
-
-        if let Some(parent) = parent_id {
-            children_map.entry(parent).or_default().push(article);
+        let references = parse_references(article.references.as_deref(), &article.message_id);
+        if references.is_empty() {
+            continue; // Root message
         }
+        let parent = references
+            .iter()
+            .rev()
+            .find(|id| ids.contains(**id))
+            .unwrap_or(&root_id);
+        children_map
+            .entry(parent.to_string())
+            .or_default()
+            .push(article);
+    }
+    for children in children_map.values_mut() {
+        children.sort_by(|a, b| reply_order(a, b));
     }
 
     // Build tree recursively from root
-    build_node_from_hdr(root_id, articles, &children_map)
+    build_node_from_hdr(root_id, articles, &children_map, &mut HashSet::new())
 }
 
-/// Build a single node and its children from HDR data
+/// Build a single node and its children from HDR data.
+///
+/// `visited` holds the IDs already in the tree: duplicate message IDs can
+/// otherwise close a loop.
 fn build_node_from_hdr(
     msg_id: &str,
     articles: &[&HdrArticleData],
     children_map: &HashMap<String, Vec<&HdrArticleData>>,
+    visited: &mut HashSet<String>,
 ) -> ThreadNodeView {
-    // Find the article for this message
-    let article = articles.iter().find(|a| a.message_id == msg_id);
+    visited.insert(msg_id.to_string());
 
-    let article_view = article.map(|a| {
-        let date_relative = compute_timeago(&a.date);
-        ArticleView {
-            message_id: a.message_id.clone(),
-            subject: a.subject.clone(),
-            from: a.from.clone(),
-            date: a.date.clone(),
-            date_relative,
-            body: None, // HDR doesn't include body
-            body_preview: None,
-            has_more_content: false,
-            headers: None,
-        }
-    });
+    // Find the article for this message
+    let article_view = articles
+        .iter()
+        .find(|a| a.message_id == msg_id)
+        .map(|a| a.to_article_view());
 
     // Build child nodes
     let mut replies: Vec<ThreadNodeView> = Vec::new();
     if let Some(children) = children_map.get(msg_id) {
         for child in children {
-            let child_node = build_node_from_hdr(&child.message_id, articles, children_map);
+            if visited.contains(&child.message_id) {
+                continue;
+            }
+            let child_node =
+                build_node_from_hdr(&child.message_id, articles, children_map, visited);
             replies.push(child_node);
         }
     }
@@ -980,19 +1293,11 @@ fn build_node_from_hdr(
 
 /// Find the latest date from HDR article data
 fn find_latest_date_hdr(articles: &[&HdrArticleData]) -> Option<String> {
-    use chrono::DateTime;
-
-    let mut latest: Option<(String, DateTime<chrono::FixedOffset>)> = None;
-
-    for article in articles {
-        if let Ok(parsed) = DateTime::parse_from_rfc2822(&article.date) {
-            if latest.is_none() || parsed > latest.as_ref().unwrap().1 {
-                latest = Some((article.date.clone(), parsed));
-            }
-        }
-    }
-
-    latest.map(|(s, _)| s)
+    articles
+        .iter()
+        .filter_map(|a| parse_date(&a.date).map(|parsed| (parsed, &a.date)))
+        .max()
+        .map(|(_, date)| date.clone())
 }
 
 // =============================================================================
@@ -1002,59 +1307,99 @@ fn find_latest_date_hdr(articles: &[&HdrArticleData]) -> Option<String> {
 /// Convert a date string to a human-readable relative time (e.g., "2 hours ago").
 /// Returns the original string if parsing fails.
 pub fn compute_timeago(date_str: &str) -> String {
-    let parsed = DateTime::parse_from_rfc2822(date_str)
-        .map(|dt| dt.with_timezone(&Utc))
-        .or_else(|_| DateTime::parse_from_rfc3339(date_str).map(|dt| dt.with_timezone(&Utc)));
-
-    match parsed {
-        Ok(date) => {
-            let now = Utc::now();
-            let duration = now.signed_duration_since(date);
-            let seconds = duration.num_seconds();
-
-            if seconds < 0 {
-                "in the future".to_string()
-            } else if seconds < SECONDS_PER_MINUTE {
-                "just now".to_string()
-            } else if seconds < SECONDS_PER_HOUR {
-                let mins = seconds / SECONDS_PER_MINUTE;
-                if mins == 1 {
-                    "1 minute ago".to_string()
-                } else {
-                    format!("{} minutes ago", mins)
-                }
-            } else if seconds < SECONDS_PER_DAY {
-                let hours = seconds / SECONDS_PER_HOUR;
-                if hours == 1 {
-                    "1 hour ago".to_string()
-                } else {
-                    format!("{} hours ago", hours)
-                }
-            } else if seconds < SECONDS_PER_MONTH {
-                let days = seconds / SECONDS_PER_DAY;
-                if days == 1 {
-                    "1 day ago".to_string()
-                } else {
-                    format!("{} days ago", days)
-                }
-            } else if seconds < SECONDS_PER_YEAR {
-                let months = seconds / SECONDS_PER_MONTH;
-                if months == 1 {
-                    "1 month ago".to_string()
-                } else {
-                    format!("{} months ago", months)
-                }
-            } else {
-                let years = seconds / SECONDS_PER_YEAR;
-                if years == 1 {
-                    "1 year ago".to_string()
-                } else {
-                    format!("{} years ago", years)
-                }
+    match parse_date(date_str) {
+        Some(date) => timeago(date.with_timezone(&Utc)),
+        None => date_str.to_string(),
+    }
+}
+
+/// Relative time from `date` to now, as in [`compute_timeago`]
+fn timeago(date: DateTime<Utc>) -> String {
+    let seconds = Utc::now().signed_duration_since(date).num_seconds();
+
+    if seconds < 0 {
+        "in the future".to_string()
+    } else if seconds < SECONDS_PER_MINUTE {
+        "just now".to_string()
+    } else if seconds < SECONDS_PER_HOUR {
+        let mins = seconds / SECONDS_PER_MINUTE;
+        if mins == 1 {
+            "1 minute ago".to_string()
+        } else {
+            format!("{} minutes ago", mins)
+        }
+    } else if seconds < SECONDS_PER_DAY {
+        let hours = seconds / SECONDS_PER_HOUR;
+        if hours == 1 {
+            "1 hour ago".to_string()
+        } else {
+            format!("{} hours ago", hours)
+        }
+    } else if seconds < SECONDS_PER_MONTH {
+        let days = seconds / SECONDS_PER_DAY;
+        if days == 1 {
+            "1 day ago".to_string()
+        } else {
+            format!("{} days ago", days)
+        }
+    } else if seconds < SECONDS_PER_YEAR {
+        let months = seconds / SECONDS_PER_MONTH;
+        if months == 1 {
+            "1 month ago".to_string()
+        } else {
+            format!("{} months ago", months)
+        }
+    } else {
+        let years = seconds / SECONDS_PER_YEAR;
+        if years == 1 {
+            "1 year ago".to_string()
+        } else {
+            format!("{} years ago", years)
+        }
+    }
+}
+
+/// Marks a relative date left out of a cached rendering
+const DATE_SLOT_OPEN: char = '\u{E000}';
+const DATE_SLOT_CLOSE: char = '\u{E001}';
+
+/// Placeholder standing in for `compute_timeago(date_str)` in HTML that is
+/// cached, so the relative date can be filled in when it is served with
+/// [`fill_date_slots`]. `None` when the date doesn't parse, as its relative
+/// form is then the date itself and never goes stale.
+pub fn date_slot(date_str: &str) -> Option<String> {
+    parse_date(date_str)
+        .map(|date| format!("{DATE_SLOT_OPEN}{}{DATE_SLOT_CLOSE}", date.timestamp()))
+}
+
+/// Replace the placeholders left by [`date_slot`] with the relative date as
+/// of now
+pub fn fill_date_slots(html: &str) -> Cow<'_, str> {
+    if !html.contains(DATE_SLOT_OPEN) {
+        return Cow::Borrowed(html);
+    }
+    let mut out = String::with_capacity(html.len());
+    let mut rest = html;
+    while let Some(open) = rest.find(DATE_SLOT_OPEN) {
+        out.push_str(&rest[..open]);
+        let after = &rest[open + DATE_SLOT_OPEN.len_utf8()..];
+        let slot = after.find(DATE_SLOT_CLOSE).and_then(|close| {
+            let secs: i64 = after[..close].parse().ok()?;
+            Some((DateTime::from_timestamp(secs, 0)?, close))
+        });
+        match slot {
+            Some((date, close)) => {
+                out.push_str(&timeago(date));
+                rest = &after[close + DATE_SLOT_CLOSE.len_utf8()..];
+            }
+            None => {
+                out.push(DATE_SLOT_OPEN);
+                rest = after;
             }
         }
-        Err(_) => date_str.to_string(),
     }
+    out.push_str(rest);
+    Cow::Owned(out)
 }
 
 /// Check if a line is a quote line (starts with >) or a quote attribution line.
@@ -1175,13 +1520,9 @@ pub fn compute_preview(body: &str) -> (String, bool) {
         {
             return (stripped[..PREVIEW_HARD_LIMIT + pos].to_string(), has_more);
         }
-        return (
-            stripped
-                .get(..PREVIEW_HARD_LIMIT)
-                .unwrap_or(&stripped)
-                .to_string(),
-            has_more,
-        );
+        let mut preview = stripped.clone();
+        truncate_at_char_boundary(&mut preview, PREVIEW_HARD_LIMIT);
+        return (preview, has_more);
     }
 
     // Over line limit: take max_lines, extend to next paragraph break
@@ -1194,22 +1535,163 @@ pub fn compute_preview(body: &str) -> (String, bool) {
         result.push('\n');
         result.push_str(line);
         if result.len() >= PREVIEW_HARD_LIMIT {
-            result.truncate(PREVIEW_HARD_LIMIT);
             break;
         }
     }
-
-    if result.len() > PREVIEW_HARD_LIMIT {
-        result.truncate(PREVIEW_HARD_LIMIT);
-    }
+    truncate_at_char_boundary(&mut result, PREVIEW_HARD_LIMIT);
 
     (result, has_more)
 }
 
+/// Shorten `s` to at most `max` bytes without splitting a character
+fn truncate_at_char_boundary(s: &mut String, max: usize) {
+    if s.len() > max {
+        let mut end = max;
+        while !s.is_char_boundary(end) {
+            end -= 1;
+        }
+        s.truncate(end);
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use chrono::Duration;
+    use proptest::prelude::*;
+
+    fn filter_thread(from: &str, last_post_date: Option<&str>) -> ThreadView {
+        ThreadView {
+            subject: "Subject".to_string(),
+            normalized_subject: "Subject".to_string(),
+            root_message_id: "<root@x>".to_string(),
+            article_count: 1,
+            root: ThreadNodeView {
+                message_id: "<root@x>".to_string(),
+                article: Some(ArticleView {
+                    message_id: "<root@x>".to_string(),
+                    subject: "Subject".to_string(),
+                    from: from.to_string(),
+                    date: String::new(),
+                    date_relative: String::new(),
+                    body: None,
+                    body_preview: None,
+                    has_more_content: false,
+                    headers: None,
+                }),
+                replies: Vec::new(),
+                descendant_count: 0,
+            },
+            last_post_date: last_post_date.map(str::to_string),
+            last_post_date_relative: None,
+            participants: Vec::new(),
+            participant_count: 0,
+            hotness: 0.0,
+            hot: false,
+        }
+    }
+
+    #[test]
+    fn test_thread_filter_empty_matches_everything() {
+        let filter = ThreadFilter::from_params(None, Some(""), Some("  "));
+        assert!(!filter.is_active());
+        assert!(filter.matches(&filter_thread("a@example.com", None)));
+    }
+
+    #[test]
+    fn test_thread_filter_date_range_is_inclusive() {
+        let filter = ThreadFilter::from_params(Some("2025-10-01"), Some("2025-10-31"), None);
+        assert!(filter.matches(&filter_thread("x", Some("Wed, 01 Oct 2025 00:00:00 +0000"))));
+        assert!(filter.matches(&filter_thread("x", Some("Fri, 31 Oct 2025 23:00:00 +0000"))));
+        assert!(!filter.matches(&filter_thread("x", Some("Tue, 30 Sep 2025 23:59:00 +0000"))));
+        assert!(!filter.matches(&filter_thread("x", Some("Sat, 01 Nov 2025 00:00:01 +0000"))));
+    }
+
+    #[test]
+    fn test_thread_filter_date_requires_parseable_date() {
+        let filter = ThreadFilter::from_params(Some("2025-10-01"), None, None);
+        assert!(!filter.matches(&filter_thread("x", None)));
+        assert!(!filter.matches(&filter_thread("x", Some("not a date"))));
+    }
+
+    #[test]
+    fn test_thread_filter_invalid_date_is_ignored() {
+        let filter = ThreadFilter::from_params(Some("yesterday"), None, None);
+        assert!(!filter.is_active());
+    }
+
+    #[test]
+    fn test_thread_filter_author_case_insensitive_substring() {
+        let filter = ThreadFilter::from_params(None, None, Some("Alice"));
+        assert!(filter.matches(&filter_thread("alice@example.com (Alice)", None)));
+        assert!(!filter.matches(&filter_thread("bob@example.com", None)));
+    }
+
+    #[test]
+    fn test_thread_filter_author_does_not_match_masked_domain() {
+        let thread = filter_thread("Alice <alice@example.com>", None);
+        for mode in [FromDisplay::Masked, FromDisplay::Name] {
+            let filter = |author| {
+                ThreadFilter::from_params(None, None, Some(author)).with_from_display(mode)
+            };
+            assert!(filter("alice").matches(&thread));
+            assert!(!filter("example.com").matches(&thread));
+            assert!(!filter("@example").matches(&thread));
+        }
+        assert!(ThreadFilter::from_params(None, None, Some("example.com")).matches(&thread));
+    }
+
+    #[test]
+    fn test_thread_filter_tag_matches_bracketed_subject_tag() {
+        let mut thread = filter_thread("x", None);
+        thread.subject = "[Bug] Crash on start".to_string();
+
+        let filter = ThreadFilter::from_params(None, None, None).with_tag(Some("BUG"));
+        assert!(filter.is_active());
+        assert!(filter.matches(&thread));
+        assert!(ThreadFilter::from_params(None, None, None)
+            .with_tag(Some("[bug]"))
+            .matches(&thread));
+        assert!(!ThreadFilter::from_params(None, None, None)
+            .with_tag(Some("ANN"))
+            .matches(&thread));
+        assert!(!ThreadFilter::from_params(None, None, None)
+            .with_tag(Some(" [] "))
+            .is_active());
+    }
+
+    #[test]
+    fn test_pagination_ellipsis_both_sides() {
+        // 1 … 5 6 7 8 9 … 42
+        let p = PaginationInfo::new(7, 420, 10);
+        assert_eq!(p.total_pages, 42);
+        assert_eq!(p.visible_pages, vec![5, 6, 7, 8, 9]);
+        assert!(p.show_first);
+        assert!(p.first_ellipsis);
+        assert!(p.show_last);
+        assert!(p.last_ellipsis);
+    }
+
+    #[test]
+    fn test_pagination_no_ellipsis_when_adjacent() {
+        // 1 2 3 4 5 6 with current=4: window 2..6 touches page 1 directly
+        let p = PaginationInfo::new(4, 60, 10);
+        assert_eq!(p.visible_pages, vec![2, 3, 4, 5, 6]);
+        assert!(p.show_first);
+        assert!(!p.first_ellipsis);
+        assert!(!p.show_last);
+        assert!(!p.last_ellipsis);
+    }
+
+    #[test]
+    fn test_pagination_single_page() {
+        let p = PaginationInfo::new(1, 0, 10);
+        assert_eq!(p.total_pages, 1);
+        assert!(!p.show_first);
+        assert!(!p.first_ellipsis);
+        assert!(!p.show_last);
+        assert!(!p.last_ellipsis);
+    }
 
     #[test]
     fn test_compute_timeago_just_now() {
@@ -1272,4 +1754,296 @@ mod tests {
         let date = (now + Duration::hours(1)).to_rfc2822();
         assert_eq!(compute_timeago(&date), "in the future");
     }
+
+    #[test]
+    fn test_fill_date_slots_uses_the_time_of_serving() {
+        let date = (Utc::now() - Duration::minutes(5)).to_rfc2822();
+        let slot = date_slot(&date).unwrap();
+        let html = format!("<span class=\"date\">{slot}</span>");
+
+        assert_eq!(
+            fill_date_slots(&html),
+            "<span class=\"date\">5 minutes ago</span>"
+        );
+        assert!(date_slot("not a date").is_none());
+        // An unterminated marker is left as it is
+        assert_eq!(fill_date_slots("a\u{E000}12"), "a\u{E000}12");
+    }
+
+    fn flat(message_id: &str, depth: usize, starts_collapsed: bool) -> FlatComment {
+        FlatComment {
+            message_id: message_id.to_string(),
+            article: None,
+            depth,
+            descendant_count: 0,
+            starts_collapsed,
+            deferred_under: None,
+            body_unavailable: false,
+            quotes: None,
+        }
+    }
+
+    #[test]
+    fn test_collapsed_ancestors() {
+        // a
+        //   b (collapsed)
+        //     c
+        //       d (collapsed)
+        //         e
+        //   f
+        let comments = vec![
+            flat("a", 0, false),
+            flat("b", 1, true),
+            flat("c", 2, false),
+            flat("d", 3, true),
+            flat("e", 4, false),
+            flat("f", 1, false),
+        ];
+        let b = Some("b".to_string());
+        assert_eq!(
+            collapsed_ancestors(&comments, None),
+            vec![None, None, b.clone(), b.clone(), b, None]
+        );
+        // Expanding b shows its whole subtree
+        assert_eq!(collapsed_ancestors(&comments, Some("b")), vec![None; 6]);
+        // A page starting inside b's subtree shows it: b is not on the page
+        assert_eq!(
+            collapsed_ancestors(&comments[2..], None),
+            vec![None, None, Some("d".to_string()), None]
+        );
+    }
+
+    fn hdr_article(message_id: &str, references: Option<&str>) -> HdrArticleData {
+        HdrArticleData {
+            message_id: message_id.to_string(),
+            references: references.map(str::to_string),
+            subject: "Subject".to_string(),
+            from: "a@example.com".to_string(),
+            date: String::new(),
+        }
+    }
+
+    #[test]
+    fn test_parse_references_drops_own_id() {
+        assert_eq!(
+            parse_references(Some(" <a@x>\t<b@x>  <c@x> "), "<c@x>"),
+            vec!["<a@x>", "<b@x>"]
+        );
+        assert!(parse_references(None, "<a@x>").is_empty());
+        assert!(parse_references(Some("<a@x>"), "<a@x>").is_empty());
+    }
+
+    #[test]
+    fn test_build_threads_from_hdr_survives_reference_loops() {
+        // A root that references itself, and duplicate IDs whose parents
+        // point at each other
+        let threads = build_threads_from_hdr(vec![
+            hdr_article("<self@x>", Some("<self@x>")),
+            hdr_article("<a@x>", Some("<root@x>")),
+            hdr_article("<b@x>", Some("<root@x> <a@x>")),
+            hdr_article("<a@x>", Some("<root@x> <b@x>")),
+        ]);
+        assert_eq!(threads.len(), 2);
+
+        let looped = threads
+            .iter()
+            .find(|t| t.root_message_id == "<root@x>")
+            .unwrap();
+        assert_eq!(looped.root.descendant_count, 2);
+    }
+
+    #[test]
+    fn test_parse_head_unfolds_and_requires_message_id() {
+        let article =
+            parse_head(b"Subject: Long\r\n subject\r\nMESSAGE-ID: <m@x>\r\nReferences: <r@x>\r\n")
+                .unwrap();
+        assert_eq!(article.message_id, "<m@x>");
+        assert_eq!(article.subject, "Long subject");
+        assert_eq!(article.references.as_deref(), Some("<r@x>"));
+
+        assert!(parse_head(b"Subject: no id\r\n").is_none());
+    }
+
+    #[test]
+    fn test_compute_preview_truncates_on_char_boundary() {
+        // Multibyte text straddling the hard limit at either byte parity,
+        // under and over the line limit
+        for pad in ["", "x"] {
+            let long_line = format!("{}{}", pad, "é".repeat(PREVIEW_HARD_LIMIT));
+            let many_lines = format!("{}{}", "line\n".repeat(DEFAULT_PREVIEW_LINES), long_line);
+            for body in [long_line, many_lines] {
+                let (preview, more) = compute_preview(&body);
+                assert!(more);
+                assert!(preview.len() <= PREVIEW_HARD_LIMIT);
+            }
+        }
+    }
+
+    #[test]
+    fn test_merge_fills_placeholder_root() {
+        // The thread was built from a reply whose root hadn't been fetched
+        let existing = build_threads_from_hdr(vec![hdr_article("<r@x>", Some("<root@x>"))]);
+        assert!(existing[0].root.article.is_none());
+
+        let merged = merge_into_threads(&existing, vec![hdr_article("<root@x>", None)]);
+        assert_eq!(merged.len(), 1);
+        assert!(merged[0].root.article.is_some());
+        assert_eq!(merged[0].article_count, 2);
+    }
+
+    /// Thread structure with sibling order and per-request fields removed
+    #[derive(Debug, PartialEq)]
+    struct Shape {
+        message_id: String,
+        has_article: bool,
+        descendant_count: usize,
+        replies: Vec<Shape>,
+    }
+
+    fn shape(node: &ThreadNodeView) -> Shape {
+        let mut replies: Vec<Shape> = node.replies.iter().map(shape).collect();
+        replies.sort_by(|a, b| a.message_id.cmp(&b.message_id));
+        Shape {
+            message_id: node.message_id.clone(),
+            has_article: node.article.is_some(),
+            descendant_count: node.descendant_count,
+            replies,
+        }
+    }
+
+    type ThreadShape = (String, String, usize, Option<String>, Shape);
+
+    fn thread_shape(thread: &ThreadView) -> ThreadShape {
+        (
+            thread.root_message_id.clone(),
+            thread.subject.clone(),
+            thread.article_count,
+            thread.last_post_date.clone(),
+            shape(&thread.root),
+        )
+    }
+
+    fn shapes(threads: &[ThreadView]) -> Vec<ThreadShape> {
+        let mut shapes: Vec<ThreadShape> = threads.iter().map(thread_shape).collect();
+        shapes.sort_by(|a, b| a.0.cmp(&b.0));
+        shapes
+    }
+
+    /// IDs of the nodes that hold an article, duplicates included
+    fn article_ids(node: &ThreadNodeView) -> Vec<String> {
+        let mut ids: Vec<String> = node.replies.iter().flat_map(article_ids).collect();
+        if node.article.is_some() {
+            ids.push(node.message_id.clone());
+        }
+        ids
+    }
+
+    /// Reply trees: each article optionally replies to an earlier one, with
+    /// References holding the parent's chain, sometimes cut down to its last
+    /// few entries as some servers and clients do
+    fn arb_articles() -> impl Strategy<Value = Vec<HdrArticleData>> {
+        prop::collection::vec(
+            (prop::option::of(any::<prop::sample::Index>()), 0usize..4),
+            1..24,
+        )
+        .prop_map(|specs| {
+            let mut articles: Vec<HdrArticleData> = Vec::new();
+            let mut chains: Vec<Vec<String>> = Vec::new();
+            for (i, (parent, keep)) in specs.into_iter().enumerate() {
+                let chain = match parent {
+                    Some(index) if i > 0 => {
+                        let parent = index.index(i);
+                        let mut chain = chains[parent].clone();
+                        chain.push(articles[parent].message_id.clone());
+                        chain
+                    }
+                    _ => Vec::new(),
+                };
+                let references = match keep {
+                    0 => &chain[..],
+                    keep => &chain[chain.len().saturating_sub(keep)..],
+                };
+                articles.push(HdrArticleData {
+                    message_id: format!("<{}@example.com>", i),
+                    references: (!references.is_empty()).then(|| references.join(" ")),
+                    subject: format!("Subject {}", i),
+                    from: "a@example.com".to_string(),
+                    date: format!("Mon, 01 Jan 2024 {:02}:{:02}:00 +0000", i / 60, i % 60),
+                });
+                chains.push(chain);
+            }
+            articles
+        })
+    }
+
+    /// Articles split into already cached and new, plus the new ones shuffled
+    fn arb_merge() -> impl Strategy<
+        Value = (
+            Vec<HdrArticleData>,
+            Vec<HdrArticleData>,
+            Vec<HdrArticleData>,
+        ),
+    > {
+        arb_articles()
+            .prop_flat_map(|articles| {
+                let count = articles.len();
+                (Just(articles), prop::collection::vec(any::<bool>(), count))
+            })
+            .prop_flat_map(|(articles, cached)| {
+                let (old, new): (Vec<_>, Vec<_>) =
+                    articles.into_iter().zip(cached).partition(|(_, c)| *c);
+                let old: Vec<HdrArticleData> = old.into_iter().map(|(a, _)| a).collect();
+                let new: Vec<HdrArticleData> = new.into_iter().map(|(a, _)| a).collect();
+                (Just(old), Just(new.clone()), Just(new).prop_shuffle())
+            })
+    }
+
+    proptest! {
+        #[test]
+        fn prop_merge_into_threads_is_idempotent((old, new, _) in arb_merge()) {
+            let once = merge_into_threads(&build_threads_from_hdr(old), new.clone());
+            let twice = merge_into_threads(&once, new);
+            prop_assert_eq!(shapes(&once), shapes(&twice));
+        }
+
+        #[test]
+        fn prop_merge_into_threads_ignores_order((old, new, shuffled) in arb_merge()) {
+            let existing = build_threads_from_hdr(old);
+            prop_assert_eq!(
+                shapes(&merge_into_threads(&existing, new)),
+                shapes(&merge_into_threads(&existing, shuffled))
+            );
+        }
+
+        #[test]
+        fn prop_merge_into_threads_keeps_every_article((old, new, _) in arb_merge()) {
+            let mut expected: Vec<String> =
+                old.iter().chain(&new).map(|a| a.message_id.clone()).collect();
+            expected.sort();
+
+            let merged = merge_into_threads(&build_threads_from_hdr(old), new);
+            let mut ids: Vec<String> = merged.iter().flat_map(|t| article_ids(&t.root)).collect();
+            ids.sort();
+            prop_assert_eq!(&ids, &expected);
+            prop_assert_eq!(merged.iter().map(|t| t.article_count).sum::<usize>(), expected.len());
+        }
+
+        #[test]
+        fn prop_merge_into_thread_is_idempotent_and_ignores_order(
+            (old, new, shuffled) in arb_merge()
+        ) {
+            for thread in build_threads_from_hdr(old) {
+                let once = merge_into_thread(&thread, new.clone());
+                prop_assert_eq!(thread_shape(&once), thread_shape(&merge_into_thread(&once, new.clone())));
+                prop_assert_eq!(thread_shape(&once), thread_shape(&merge_into_thread(&thread, shuffled.clone())));
+
+                // Nothing already in the thread is lost, and the count matches the tree
+                let ids = article_ids(&once.root);
+                prop_assert_eq!(ids.len(), once.article_count);
+                for id in article_ids(&thread.root) {
+                    prop_assert!(ids.contains(&id));
+                }
+            }
+        }
+    }
 }
@@ -0,0 +1,178 @@
+//! The overview format a server advertises with LIST OVERVIEW.FMT.
+//!
+//! RFC 3977 puts Subject, From, Date, Message-ID, References, `:bytes` and
+//! `:lines` first, in that order, and `OverviewEntry` reads those fields by
+//! position. Older servers still list the last two as "Bytes:" and
+//! "Lines:", and may add headers after them, some as "Xref:full" (the header
+//! name is repeated in each value). A server whose fields differ from the
+//! standard ones can't be read by position, so the worker fetches threads
+//! with HDR or HEAD instead.
+//!
+//! Only the first five fields feed threading. Byte and line counts are never
+//! shown, since pages render the article itself, and Xref article numbers
+//! belong to one server, which means nothing once results from several
+//! servers are merged. So `:bytes`, `:lines` and any extra headers are
+//! recognised here only to check the order and for logging.
+
+/// One field of the overview format
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OverviewField {
+    Subject,
+    From,
+    Date,
+    MessageId,
+    References,
+    /// `:bytes`, or "Bytes:" from older servers
+    Bytes,
+    /// `:lines`, or "Lines:" from older servers
+    Lines,
+    /// Any other header. `full` is set for "Name:full" fields, whose values
+    /// start with the header name.
+    Header {
+        name: String,
+        full: bool,
+    },
+}
+
+impl OverviewField {
+    /// Parse one line of the LIST OVERVIEW.FMT response
+    fn parse(line: &str) -> Self {
+        let line = line.trim();
+        let (name, full) = match line.rsplit_once(':') {
+            Some((name, "full")) if !name.is_empty() => (name, true),
+            Some((name, "")) => (name, false),
+            _ => (line, false),
+        };
+        match name.to_ascii_lowercase().as_str() {
+            "subject" => Self::Subject,
+            "from" => Self::From,
+            "date" => Self::Date,
+            "message-id" => Self::MessageId,
+            "references" => Self::References,
+            ":bytes" | "bytes" => Self::Bytes,
+            ":lines" | "lines" => Self::Lines,
+            _ => Self::Header {
+                name: name.trim_start_matches(':').to_string(),
+                full,
+            },
+        }
+    }
+}
+
+/// The standard fields, in the order `OverviewEntry` reads them
+const STANDARD_FIELDS: [OverviewField; 7] = [
+    OverviewField::Subject,
+    OverviewField::From,
+    OverviewField::Date,
+    OverviewField::MessageId,
+    OverviewField::References,
+    OverviewField::Bytes,
+    OverviewField::Lines,
+];
+
+/// Fields of an OVER response after the article number, in order
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OverviewFormat {
+    fields: Vec<OverviewField>,
+}
+
+impl OverviewFormat {
+    /// Parse the lines of a LIST OVERVIEW.FMT response
+    pub fn parse<'a>(lines: impl IntoIterator<Item = &'a str>) -> Self {
+        Self {
+            fields: lines
+                .into_iter()
+                .filter(|line| !line.trim().is_empty())
+                .map(OverviewField::parse)
+                .collect(),
+        }
+    }
+
+    /// The RFC 3977 format, for servers without LIST OVERVIEW.FMT
+    pub fn standard() -> Self {
+        Self {
+            fields: STANDARD_FIELDS.to_vec(),
+        }
+    }
+
+    /// Whether Subject, From, Date and Message-ID are where `OverviewEntry`
+    /// expects them, so OVER can list threads at all
+    pub fn has_standard_headers(&self) -> bool {
+        self.fields.len() >= 4 && self.fields[..4] == STANDARD_FIELDS[..4]
+    }
+
+    /// Whether References follows them, so OVER gives everything threading
+    /// needs
+    pub fn has_references(&self) -> bool {
+        self.has_standard_headers() && self.fields.get(4) == Some(&OverviewField::References)
+    }
+
+    /// Headers the server adds after the standard fields, as (name, full).
+    /// These are only logged, see the module docs.
+    pub fn extra_headers(&self) -> impl Iterator<Item = (&str, bool)> {
+        self.fields.iter().filter_map(|field| match field {
+            OverviewField::Header { name, full } => Some((name.as_str(), *full)),
+            _ => None,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_standard_and_legacy_names() {
+        let rfc = OverviewFormat::parse([
+            "Subject:",
+            "From:",
+            "Date:",
+            "Message-ID:",
+            "References:",
+            ":bytes",
+            ":lines",
+        ]);
+        assert_eq!(rfc, OverviewFormat::standard());
+        assert!(rfc.has_references());
+        assert_eq!(rfc.extra_headers().count(), 0);
+
+        // INN and friends before RFC 3977
+        let legacy = OverviewFormat::parse([
+            "Subject:",
+            "From:",
+            "Date:",
+            "Message-ID:",
+            "References:",
+            "Bytes:",
+            "Lines:",
+            "Xref:full",
+        ]);
+        assert!(legacy.has_references());
+        assert_eq!(legacy.fields[6], OverviewField::Lines);
+        assert_eq!(
+            legacy.extra_headers().collect::<Vec<_>>(),
+            vec![("Xref", true)]
+        );
+    }
+
+    #[test]
+    fn test_nonstandard_orders() {
+        let no_references = OverviewFormat::parse([
+            "Subject:",
+            "From:",
+            "Date:",
+            "Message-ID:",
+            "Bytes:",
+            "Lines:",
+        ]);
+        assert!(no_references.has_standard_headers());
+        assert!(!no_references.has_references());
+        assert!(!no_references.fields.contains(&OverviewField::References));
+
+        let reordered =
+            OverviewFormat::parse(["From:", "Subject:", "Date:", "Message-ID:", "References:"]);
+        assert!(!reordered.has_standard_headers());
+        assert!(!reordered.has_references());
+        assert_eq!(reordered.fields[4], OverviewField::References);
+    }
+}
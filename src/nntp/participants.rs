@@ -0,0 +1,145 @@
+//! Distinct posters in a thread, for the thread list.
+//!
+//! A thread list entry reads "5 participants, 23 replies" next to an avatar
+//! stack of the first few posters. Both come from the From headers already
+//! in the cached thread tree, so listing a group fetches nothing more.
+//! Posters are told apart by address and listed in the order they first
+//! appear: the root, then replies depth-first.
+//!
+//! Avatars are initials on a colored disc. The hue is derived from the
+//! address, so a poster keeps one color across threads, while the name
+//! follows `[ui] from_display = "name"` and never shows an address's domain.
+
+use std::collections::HashMap;
+
+use serde::Serialize;
+
+use super::privacy::{mask_from, split_from};
+use super::ThreadNodeView;
+use crate::config::FromDisplay;
+
+/// A poster in a thread
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Participant {
+    /// Display name, or the address's local part when there is none
+    pub name: String,
+    /// Up to two letters for the avatar
+    pub initials: String,
+    /// Avatar hue in degrees (0-359)
+    pub hue: u16,
+    /// Articles the poster has in the thread
+    pub posts: usize,
+}
+
+/// Posters under `root` in order of appearance: the first `limit` with their
+/// post counts, and how many there are in all
+pub fn collect(root: &ThreadNodeView, limit: usize) -> (Vec<Participant>, usize) {
+    let mut participants: Vec<Participant> = Vec::new();
+    let mut by_address: HashMap<String, usize> = HashMap::new();
+    let mut stack = vec![root];
+    while let Some(node) = stack.pop() {
+        stack.extend(node.replies.iter().rev());
+        let Some(article) = &node.article else {
+            continue;
+        };
+        let address = split_from(&article.from).1.to_lowercase();
+        match by_address.get(&address) {
+            Some(&i) => participants[i].posts += 1,
+            None => {
+                let name = mask_from(&article.from, FromDisplay::Name);
+                by_address.insert(address.clone(), participants.len());
+                participants.push(Participant {
+                    initials: initials(&name),
+                    hue: hue(&address),
+                    name,
+                    posts: 1,
+                });
+            }
+        }
+    }
+    let count = participants.len();
+    participants.truncate(limit);
+    (participants, count)
+}
+
+/// First letters of the first and last words of a name ("Ann B. Example"
+/// gives "AE"), or "?" when it has no letters or digits
+fn initials(name: &str) -> String {
+    let mut words = name
+        .split(|c: char| c.is_whitespace() || c == ',')
+        .filter_map(|word| word.chars().find(|c| c.is_alphanumeric()));
+    let Some(first) = words.next() else {
+        return "?".to_string();
+    };
+    std::iter::once(first)
+        .chain(words.next_back())
+        .flat_map(char::to_uppercase)
+        .collect()
+}
+
+/// Stable hue for an address (FNV-1a, so it survives restarts and releases)
+fn hue(address: &str) -> u16 {
+    let hash = address.bytes().fold(0x811c_9dc5_u32, |hash, byte| {
+        (hash ^ u32::from(byte)).wrapping_mul(0x0100_0193)
+    });
+    (hash % 360) as u16
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::nntp::ArticleView;
+
+    fn node(from: &str, replies: Vec<ThreadNodeView>) -> ThreadNodeView {
+        ThreadNodeView {
+            message_id: format!("<{}@example.com>", replies.len()),
+            article: Some(ArticleView {
+                message_id: String::new(),
+                subject: String::new(),
+                from: from.to_string(),
+                date: String::new(),
+                date_relative: String::new(),
+                body: None,
+                body_preview: None,
+                has_more_content: false,
+                headers: None,
+            }),
+            replies,
+            descendant_count: 0,
+        }
+    }
+
+    #[test]
+    fn test_collect_counts_posters_by_address() {
+        let root = node(
+            "Ann Example <ann@example.com>",
+            vec![
+                node(
+                    "bob@example.org (Bob Builder)",
+                    vec![node("\"Ann\" <ANN@example.com>", vec![])],
+                ),
+                node("carol@example.net", vec![]),
+            ],
+        );
+        let (participants, count) = collect(&root, 2);
+        assert_eq!(count, 3);
+        let names: Vec<(&str, &str, usize)> = participants
+            .iter()
+            .map(|p| (p.name.as_str(), p.initials.as_str(), p.posts))
+            .collect();
+        assert_eq!(names, [("Ann Example", "AE", 2), ("Bob Builder", "BB", 1)]);
+        assert_eq!(participants[0].hue, hue("ann@example.com"));
+
+        let (participants, _) = collect(&root, 5);
+        assert_eq!(participants[2].name, "carol");
+        assert_eq!(participants[2].initials, "C");
+    }
+
+    #[test]
+    fn test_initials() {
+        assert_eq!(initials("Ann B. Example"), "AE");
+        assert_eq!(initials("Doe, jane"), "DJ");
+        assert_eq!(initials("émile"), "É");
+        assert_eq!(initials("--"), "?");
+    }
+}
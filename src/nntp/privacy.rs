@@ -0,0 +1,215 @@
+//! From-address masking for public instances.
+//!
+//! With `[ui] from_display` set to `masked` or `name`, poster addresses are
+//! hidden wherever articles leave the process: `ArticleView` serializes its
+//! From and raw headers through this module, so templates and JSON responses
+//! all see the same masked values, and the read-only NNTP listener masks the
+//! headers it serves. Cached articles keep the original value.
+//!
+//! Serialization can't take arguments, so the HTTP layer runs each request
+//! inside `with_from_display` with the mode from its `AppState`, and the
+//! serializers read it from there. Outside such a scope addresses are shown
+//! in full.
+
+use std::future::Future;
+
+use serde::Serializer;
+
+use crate::config::FromDisplay;
+
+tokio::task_local! {
+    /// Display mode for the request being served
+    static FROM_DISPLAY: FromDisplay;
+}
+
+/// Headers whose addresses are masked in raw header blocks
+const ADDRESS_HEADERS: [&str; 4] = ["from:", "reply-to:", "sender:", "x-original-from:"];
+
+/// Run `future` with `mode` as the From display mode for serialization
+pub async fn with_from_display<F: Future>(mode: FromDisplay, future: F) -> F::Output {
+    FROM_DISPLAY.scope(mode, future).await
+}
+
+/// The From display mode of the current scope (`Full` outside one)
+fn from_display() -> FromDisplay {
+    FROM_DISPLAY.try_with(|mode| *mode).unwrap_or_default()
+}
+
+/// Format a From header value for display under the given mode.
+pub fn mask_from(from: &str, mode: FromDisplay) -> String {
+    match mode {
+        FromDisplay::Full => from.to_string(),
+        FromDisplay::Masked => mask_addresses(from),
+        FromDisplay::Name => match split_from(from) {
+            (Some(name), _) => mask_addresses(name),
+            (None, address) => address
+                .split_once('@')
+                .map(|(local, _)| local.to_string())
+                .unwrap_or_else(|| from.to_string()),
+        },
+    }
+}
+
+/// Mask addresses in the address-bearing lines of a raw header block.
+/// Other headers (Message-ID, Path) are left alone.
+pub fn mask_header_block(headers: &str, mode: FromDisplay) -> String {
+    if mode == FromDisplay::Full {
+        return headers.to_string();
+    }
+
+    let mut out = String::with_capacity(headers.len());
+    let mut masking = false;
+    for line in headers.split_inclusive('\n') {
+        let is_continuation = line.starts_with([' ', '\t']);
+        if !is_continuation {
+            let lower = line.to_ascii_lowercase();
+            masking = ADDRESS_HEADERS.iter().any(|h| lower.starts_with(h));
+        }
+        if masking {
+            out.push_str(&mask_addresses(line));
+        } else {
+            out.push_str(line);
+        }
+    }
+    out
+}
+
+/// `serialize_with` helper for `ArticleView::from`
+pub(super) fn serialize_from<S: Serializer>(from: &str, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&mask_from(from, from_display()))
+}
+
+/// `serialize_with` helper for `ArticleView::headers`
+pub(super) fn serialize_headers<S: Serializer>(
+    headers: &Option<String>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    match headers {
+        Some(h) => serializer.serialize_some(&mask_header_block(h, from_display())),
+        None => serializer.serialize_none(),
+    }
+}
+
+/// Split a From value into (display name, address).
+/// Handles `Name <addr>`, `addr (Name)` and bare `addr`.
+pub(super) fn split_from(from: &str) -> (Option<&str>, &str) {
+    let from = from.trim();
+    let (name, address) = if let Some((name, rest)) = from.split_once('<') {
+        (name, rest.split_once('>').map_or(rest, |(addr, _)| addr))
+    } else if let Some((address, rest)) = from.split_once('(') {
+        (
+            rest.rsplit_once(')').map_or(rest, |(name, _)| name),
+            address,
+        )
+    } else {
+        ("", from)
+    };
+
+    let name = name.trim().trim_matches('"').trim();
+    ((!name.is_empty()).then_some(name), address.trim())
+}
+
+/// Replace the domain of every `local@domain` in the text with "…".
+fn mask_addresses(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+    let mut prev: Option<char> = None;
+
+    while let Some(c) = chars.next() {
+        out.push(c);
+        let has_local = prev.is_some_and(|p| !p.is_whitespace() && p != '<');
+        let has_domain = chars.peek().is_some_and(|n| n.is_alphanumeric());
+        if c == '@' && has_local && has_domain {
+            while chars
+                .peek()
+                .is_some_and(|n| n.is_alphanumeric() || matches!(n, '.' | '-' | '_'))
+            {
+                chars.next();
+            }
+            out.push('…');
+        }
+        prev = Some(c);
+    }
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mask_from_masked() {
+        let mode = FromDisplay::Masked;
+        assert_eq!(
+            mask_from("Jane Doe <jane@example.com>", mode),
+            "Jane Doe <jane@…>"
+        );
+        assert_eq!(
+            mask_from("jane@example.com (Jane Doe)", mode),
+            "jane@… (Jane Doe)"
+        );
+        assert_eq!(
+            mask_from("\"jane@example.com\" <jane@example.com>", mode),
+            "\"jane@…\" <jane@…>"
+        );
+        assert_eq!(mask_from("Anonymous", mode), "Anonymous");
+    }
+
+    #[test]
+    fn test_mask_from_name_only() {
+        let mode = FromDisplay::Name;
+        assert_eq!(
+            mask_from("\"Jane Doe\" <jane@example.com>", mode),
+            "Jane Doe"
+        );
+        assert_eq!(mask_from("jane@example.com (Jane Doe)", mode), "Jane Doe");
+        assert_eq!(mask_from("jane@example.com", mode), "jane");
+        assert_eq!(mask_from("<jane@example.com>", mode), "jane");
+    }
+
+    #[test]
+    fn test_mask_from_full_is_unchanged() {
+        let from = "Jane Doe <jane@example.com>";
+        assert_eq!(mask_from(from, FromDisplay::Full), from);
+    }
+
+    #[test]
+    fn test_serialization_follows_the_scoped_mode() {
+        let article = crate::nntp::ArticleView {
+            message_id: "<m@x>".to_string(),
+            subject: "Hello".to_string(),
+            from: "Jane <jane@example.com>".to_string(),
+            date: String::new(),
+            date_relative: String::new(),
+            body: None,
+            body_preview: None,
+            has_more_content: false,
+            headers: None,
+        };
+        let from = |mode| {
+            FROM_DISPLAY.sync_scope(mode, || {
+                serde_json::to_value(&article).unwrap()["from"].clone()
+            })
+        };
+        assert_eq!(from(FromDisplay::Masked), "Jane <jane@…>");
+        assert_eq!(from(FromDisplay::Name), "Jane");
+        assert_eq!(
+            serde_json::to_value(&article).unwrap()["from"],
+            "Jane <jane@example.com>"
+        );
+    }
+
+    #[test]
+    fn test_mask_header_block_only_touches_address_headers() {
+        let headers = "From: Jane <jane@example.com>\r\n\
+                       Message-ID: <abc@news.example.com>\r\n\
+                       Reply-To: Jane\r\n <jane@example.org>\r\n";
+        assert_eq!(
+            mask_header_block(headers, FromDisplay::Name),
+            "From: Jane <jane@…>\r\n\
+             Message-ID: <abc@news.example.com>\r\n\
+             Reply-To: Jane\r\n <jane@…>\r\n"
+        );
+    }
+}
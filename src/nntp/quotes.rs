@@ -0,0 +1,309 @@
+//! Passages of article bodies, and which passage of its parent a reply quotes.
+//!
+//! A body is split into passages at blank lines and where quoted lines give
+//! way to unquoted ones (see [`split_passages`]). Each passage has an ID from
+//! a hash of its text, which article pages use as its anchor.
+//!
+//! While a thread page is built, each reply whose parent's body is on the
+//! page is compared with it: the reply's first-level quoted lines, with the
+//! `>` removed and rewrapping ignored, are looked up in the parent's own
+//! passages. The IDs found let the page link each quote to
+//! `/a/{parent}#{passage}`, where the article page highlights it.
+
+use std::collections::HashMap;
+
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+
+use crate::config::{PASSAGE_ID_HASH_BYTES, QUOTE_MATCH_MIN_CHARS};
+
+use super::diff::find_diff;
+use super::{is_quote_line, FlatComment};
+
+/// A paragraph or quote of an article body, with a fragment ID to link to it
+#[derive(Debug, PartialEq, Serialize)]
+pub struct Passage {
+    pub id: String,
+    pub text: String,
+    /// Quoted lines, with their attribution
+    pub quote: bool,
+    /// Part of a unified diff (see [`find_diff`])
+    pub diff: bool,
+}
+
+/// Split an article body into passages for per-paragraph permalinks.
+///
+/// Blank lines separate passages, and so does a change between quoted and
+/// unquoted lines, so a quote can be linked apart from the reply under it,
+/// or between a diff and the text around it.
+/// The ID is "p-" and the start of the SHA-256 of the passage's lines with
+/// trailing whitespace removed: it depends on nothing but the passage, so a
+/// link keeps working however the page around it is laid out. A passage
+/// that repeats within the body gets "-2", "-3" and so on.
+pub fn split_passages(body: &str) -> Vec<Passage> {
+    let diff = find_diff(body).unwrap_or_default();
+    let mut blocks: Vec<(Vec<&str>, Kind)> = Vec::new();
+    let mut current: Vec<&str> = Vec::new();
+    let mut current_kind = Kind::default();
+    let mut offset = 0;
+    for raw in body.split_inclusive('\n') {
+        let line = raw.trim_end();
+        let kind = Kind {
+            quote: is_quote_line(line) && !diff.contains(&offset),
+            diff: diff.contains(&offset),
+        };
+        offset += raw.len();
+        if line.is_empty() || (!current.is_empty() && kind != current_kind) {
+            if !current.is_empty() {
+                blocks.push((std::mem::take(&mut current), current_kind));
+            }
+            if line.is_empty() {
+                continue;
+            }
+        }
+        current.push(line);
+        current_kind = kind;
+    }
+    if !current.is_empty() {
+        blocks.push((current, current_kind));
+    }
+
+    let mut seen: HashMap<String, usize> = HashMap::new();
+    blocks
+        .into_iter()
+        .map(|(lines, kind)| {
+            let text = lines.join("\n");
+            let digest = Sha256::digest(text.as_bytes());
+            let hash: String = digest[..PASSAGE_ID_HASH_BYTES]
+                .iter()
+                .map(|b| format!("{:02x}", b))
+                .collect();
+            let count = seen.entry(hash.clone()).or_insert(0);
+            *count += 1;
+            let id = match *count {
+                1 => format!("p-{}", hash),
+                n => format!("p-{}-{}", hash, n),
+            };
+            Passage {
+                id,
+                text,
+                quote: kind.quote,
+                diff: kind.diff,
+            }
+        })
+        .collect()
+}
+
+/// What sets a passage apart from its neighbours, besides blank lines
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+struct Kind {
+    quote: bool,
+    diff: bool,
+}
+
+/// The parent passages a reply quotes
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct QuoteLinks {
+    /// Message-ID of the parent
+    pub parent: String,
+    /// IDs of the parent's passages, in the order the reply quotes them
+    pub passages: Vec<String>,
+}
+
+/// Link the quotes of `comments[from..]` to their parents. A flattened
+/// thread lists each comment after its parent, so the parent is the nearest
+/// earlier comment one level up; it needs its body filled in.
+pub fn link_quotes(comments: &mut [FlatComment], from: usize) {
+    for i in from..comments.len() {
+        let depth = comments[i].depth;
+        let links = comments[..i]
+            .iter()
+            .rev()
+            .find(|c| c.depth < depth)
+            .filter(|parent| parent.depth + 1 == depth)
+            .and_then(|parent| {
+                let reply = body(&comments[i])?;
+                let passages = quote_sources(reply, body(parent)?);
+                (!passages.is_empty()).then(|| QuoteLinks {
+                    parent: parent.message_id.clone(),
+                    passages,
+                })
+            });
+        comments[i].quotes = links;
+    }
+}
+
+fn body(comment: &FlatComment) -> Option<&str> {
+    comment.article.as_ref()?.body.as_deref()
+}
+
+/// IDs of the passages of `parent` that `reply` quotes, in order, each once.
+/// Only the parent's own text counts: what it quoted in turn shows up as a
+/// deeper quote in the reply and is skipped.
+pub fn quote_sources(reply: &str, parent: &str) -> Vec<String> {
+    let parent_passages: Vec<(String, String)> = split_passages(parent)
+        .into_iter()
+        .filter(|p| !p.quote)
+        .map(|p| (p.id, normalize(&p.text)))
+        .collect();
+
+    let mut sources: Vec<String> = Vec::new();
+    for passage in split_passages(reply).into_iter().filter(|p| p.quote) {
+        for run in quoted_runs(&passage.text) {
+            if run.len() < QUOTE_MATCH_MIN_CHARS {
+                continue;
+            }
+            let found = parent_passages
+                .iter()
+                .find(|(_, text)| text.contains(run.as_str()));
+            if let Some((id, _)) = found {
+                if !sources.contains(id) {
+                    sources.push(id.clone());
+                }
+            }
+        }
+    }
+    sources
+}
+
+/// First-level quoted text of a quote passage, split where the quote has a
+/// blank line (the parent's paragraph breaks), each run normalized
+fn quoted_runs(passage: &str) -> Vec<String> {
+    let mut runs = Vec::new();
+    let mut current: Vec<&str> = Vec::new();
+    for line in passage.lines() {
+        // Attribution lines aren't quoted text
+        let Some(rest) = line.trim_start().strip_prefix('>') else {
+            continue;
+        };
+        let rest = rest.strip_prefix(' ').unwrap_or(rest);
+        if rest.trim().is_empty() || rest.starts_with('>') {
+            if !current.is_empty() {
+                runs.push(normalize(&current.join(" ")));
+                current.clear();
+            }
+            continue;
+        }
+        current.push(rest);
+    }
+    if !current.is_empty() {
+        runs.push(normalize(&current.join(" ")));
+    }
+    runs
+}
+
+/// Words separated by single spaces, so a quote rewrapped by the replier's
+/// client still matches
+fn normalize(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::nntp::ArticleView;
+
+    #[test]
+    fn test_split_passages() {
+        let body = "On Mon, 1 Jan 2024, Someone wrote:\n> First point\n> continues\nI disagree.  \n\n\nSecond paragraph\n\n-- \nsig\n\nSecond paragraph\n";
+        let passages = split_passages(body);
+        let texts: Vec<_> = passages
+            .iter()
+            .map(|p| (p.text.as_str(), p.quote))
+            .collect();
+        assert_eq!(
+            texts,
+            vec![
+                (
+                    "On Mon, 1 Jan 2024, Someone wrote:\n> First point\n> continues",
+                    true
+                ),
+                ("I disagree.", false),
+                ("Second paragraph", false),
+                ("--\nsig", false),
+                ("Second paragraph", false),
+            ]
+        );
+
+        // IDs come from the passage alone, with repeats numbered
+        assert!(passages[2].id.starts_with("p-"));
+        assert_eq!(passages[2].id.len(), 2 + 2 * PASSAGE_ID_HASH_BYTES);
+        assert_eq!(passages[4].id, format!("{}-2", passages[2].id));
+        assert_eq!(
+            split_passages("Intro\n\nSecond paragraph")[1].id,
+            passages[2].id
+        );
+        assert!(split_passages("\n\n").is_empty());
+    }
+
+    #[test]
+    fn test_split_passages_sets_diffs_apart() {
+        let body = "Patch below.\n--- a/x\n+++ b/x\n@@ -1 +1 @@\n-old\n+new\nThanks";
+        let passages: Vec<_> = split_passages(body)
+            .into_iter()
+            .map(|p| (p.text, p.diff))
+            .collect();
+        assert_eq!(
+            passages,
+            vec![
+                ("Patch below.".to_string(), false),
+                (
+                    "--- a/x\n+++ b/x\n@@ -1 +1 @@\n-old\n+new".to_string(),
+                    true
+                ),
+                ("Thanks".to_string(), false),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_quote_sources_finds_rewrapped_quotes() {
+        let parent = "On Mon, 1 Jan 2024, Ann wrote:\n> Older text\n\nFirst paragraph of the\nparent post.\n\nSecond paragraph, which makes a point.\n\nThird.";
+        let reply = "Bob wrote:\n> Second paragraph, which makes\n> a point.\n\nAgreed.\n\n> First paragraph of the parent post.\n>\n> > Older text\n> Second paragraph\n\nAnd again.";
+        let ids: Vec<String> = split_passages(parent).into_iter().map(|p| p.id).collect();
+        // Second, then first; the deeper quote and repeat add nothing
+        assert_eq!(
+            quote_sources(reply, parent),
+            vec![ids[2].clone(), ids[1].clone()]
+        );
+        assert!(quote_sources("> Third.\n\nShort quote", parent).is_empty());
+        assert!(quote_sources("No quotes here", parent).is_empty());
+    }
+
+    #[test]
+    fn test_link_quotes_uses_parent_on_page() {
+        let comment = |id: &str, depth: usize, body: &str| FlatComment {
+            message_id: id.to_string(),
+            article: Some(ArticleView {
+                message_id: id.to_string(),
+                subject: String::new(),
+                from: String::new(),
+                date: String::new(),
+                date_relative: String::new(),
+                body: Some(body.to_string()),
+                body_preview: None,
+                has_more_content: false,
+                headers: None,
+            }),
+            depth,
+            descendant_count: 0,
+            starts_collapsed: false,
+            deferred_under: None,
+            body_unavailable: false,
+            quotes: None,
+        };
+        let mut comments = vec![
+            comment("<a@x>", 0, "The original question goes here."),
+            comment("<b@x>", 1, "> The original question goes here.\n\nAnswer"),
+            comment("<c@x>", 2, "> The original question goes here.\n\nSame"),
+            comment("<d@x>", 1, "> Answer\n\nToo short to match"),
+        ];
+        link_quotes(&mut comments, 1);
+        let links = comments[1].quotes.as_ref().unwrap();
+        assert_eq!(links.parent, "<a@x>");
+        assert_eq!(links.passages.len(), 1);
+        // Quoting the grandparent's text isn't quoting the parent
+        assert!(comments[2].quotes.is_none());
+        assert!(comments[3].quotes.is_none());
+    }
+}
@@ -0,0 +1,162 @@
+//! Retry policy for NNTP requests.
+//!
+//! Idempotent reads (ARTICLE, STAT, GROUP, OVER/HDR, LIST) are retried after
+//! transient failures: timeouts, lost connections and 400/403 replies. Each
+//! retry waits an exponentially growing, jittered backoff so a struggling
+//! server isn't hit by every caller at once. Posts are never retried, and
+//! definitive answers such as "no such article" are returned straight away.
+//!
+//! Workers back off the same way between connection attempts, with
+//! `RetryPolicy::RECONNECT`, so workers whose server went down don't all
+//! knock on it again in step.
+
+use std::time::Duration;
+
+use uuid::Uuid;
+
+use crate::config::{
+    NNTP_RECONNECT_BASE_DELAY_SECS, NNTP_RECONNECT_MAX_DELAY_SECS, NNTP_RETRY_ATTEMPTS_HIGH,
+    NNTP_RETRY_ATTEMPTS_LOW, NNTP_RETRY_ATTEMPTS_NORMAL, NNTP_RETRY_BASE_DELAY_MS,
+    NNTP_RETRY_MAX_DELAY_MS,
+};
+
+use super::messages::{NntpError, NntpRequest, Priority};
+
+/// How often and how patiently one request is retried
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// Attempts including the first; 1 means no retries
+    pub attempts: u32,
+    /// Backoff before the first retry, doubled for each further retry
+    pub base_delay: Duration,
+    /// Upper bound on a single backoff
+    pub max_delay: Duration,
+}
+
+impl RetryPolicy {
+    /// A single attempt
+    pub const NONE: RetryPolicy = RetryPolicy {
+        attempts: 1,
+        base_delay: Duration::ZERO,
+        max_delay: Duration::ZERO,
+    };
+
+    /// Backoff between a worker's connection attempts, which never give up
+    pub const RECONNECT: RetryPolicy = RetryPolicy {
+        attempts: u32::MAX,
+        base_delay: Duration::from_secs(NNTP_RECONNECT_BASE_DELAY_SECS),
+        max_delay: Duration::from_secs(NNTP_RECONNECT_MAX_DELAY_SECS),
+    };
+
+    /// Policy for a request: by priority for idempotent reads, none for posts
+    pub fn for_request(request: &NntpRequest) -> Self {
+        if request.is_idempotent() {
+            Self::for_reads(request.priority())
+        } else {
+            Self::NONE
+        }
+    }
+
+    /// Policy for idempotent reads of a priority. User-facing requests get
+    /// fewer attempts since someone is waiting.
+    pub fn for_reads(priority: Priority) -> Self {
+        let attempts = match priority {
+            Priority::High => NNTP_RETRY_ATTEMPTS_HIGH,
+            Priority::Normal => NNTP_RETRY_ATTEMPTS_NORMAL,
+            Priority::Low => NNTP_RETRY_ATTEMPTS_LOW,
+        };
+        Self {
+            attempts,
+            base_delay: Duration::from_millis(NNTP_RETRY_BASE_DELAY_MS),
+            max_delay: Duration::from_millis(NNTP_RETRY_MAX_DELAY_MS),
+        }
+    }
+
+    /// Whether `error` after attempt number `attempt` (1-based) is worth another try
+    pub fn should_retry(&self, attempt: u32, error: &NntpError) -> bool {
+        attempt < self.attempts && error.is_transient()
+    }
+
+    /// Backoff after attempt number `attempt` (1-based). The capped exponential
+    /// delay is scaled into its upper half by `unit` in [0, 1).
+    pub fn delay(&self, attempt: u32, unit: f64) -> Duration {
+        let exponential = self
+            .base_delay
+            .saturating_mul(1u32 << attempt.saturating_sub(1).min(16));
+        exponential.min(self.max_delay).mul_f64(0.5 + unit / 2.0)
+    }
+
+    /// Longest a caller can wait for all attempts of a request that each
+    /// time out after `request_timeout`, backoffs included
+    pub fn budget(&self, request_timeout: Duration) -> Duration {
+        let waits = (1..self.attempts).map(|attempt| self.delay(attempt, 1.0));
+        request_timeout * self.attempts + waits.sum::<Duration>()
+    }
+}
+
+/// Uniform random number in [0, 1), from the random bits of a v4 UUID
+pub fn random_unit() -> f64 {
+    const BITS: u32 = 53;
+    let random = Uuid::new_v4().as_u128() as u64 & ((1 << BITS) - 1);
+    random as f64 / (1u64 << BITS) as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn policy(attempts: u32) -> RetryPolicy {
+        RetryPolicy {
+            attempts,
+            base_delay: Duration::from_millis(200),
+            max_delay: Duration::from_millis(1000),
+        }
+    }
+
+    #[test]
+    fn test_retry_delay_grows_and_is_capped() {
+        let policy = policy(5);
+        assert_eq!(policy.delay(1, 0.0), Duration::from_millis(100));
+        assert_eq!(policy.delay(1, 1.0), Duration::from_millis(200));
+        assert_eq!(policy.delay(2, 1.0), Duration::from_millis(400));
+        assert_eq!(policy.delay(4, 1.0), Duration::from_millis(1000));
+        assert_eq!(policy.delay(60, 1.0), Duration::from_millis(1000));
+    }
+
+    #[test]
+    fn test_reconnect_backoff_spreads_and_caps() {
+        let reconnect = RetryPolicy::RECONNECT;
+        let base = Duration::from_secs(NNTP_RECONNECT_BASE_DELAY_SECS);
+        let max = Duration::from_secs(NNTP_RECONNECT_MAX_DELAY_SECS);
+        // Jitter keeps workers that failed together from retrying together
+        assert!(reconnect.delay(1, 0.0) < reconnect.delay(1, 0.9));
+        assert_eq!(reconnect.delay(1, 1.0), base);
+        assert_eq!(reconnect.delay(3, 1.0), base * 4);
+        // A server down for hours is still tried every few minutes
+        assert_eq!(reconnect.delay(1_000, 1.0), max);
+        assert!(reconnect.should_retry(1_000, &NntpError::Timeout("Connect".into())));
+    }
+
+    #[test]
+    fn test_retry_only_transient_errors_within_attempts() {
+        let policy = policy(3);
+        let lost = NntpError::ConnectionLost("Connection reset by peer".into());
+        assert!(policy.should_retry(1, &lost));
+        assert!(policy.should_retry(2, &lost));
+        assert!(!policy.should_retry(3, &lost));
+        assert!(policy.should_retry(1, &NntpError::from_reply("403 Internal fault")));
+        assert!(!policy.should_retry(1, &NntpError::from_reply("430 No such article")));
+        assert!(!policy.should_retry(1, &NntpError::from_reply("480 Auth required")));
+        assert!(!RetryPolicy::NONE.should_retry(1, &lost));
+    }
+
+    #[test]
+    fn test_retry_budget_covers_all_attempts() {
+        let budget = policy(3).budget(Duration::from_secs(10));
+        assert_eq!(budget, Duration::from_millis(30_600));
+        assert_eq!(
+            RetryPolicy::NONE.budget(Duration::from_secs(10)),
+            Duration::from_secs(10)
+        );
+    }
+}
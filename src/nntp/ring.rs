@@ -0,0 +1,94 @@
+//! Consistent hashing of newsgroups onto a server's workers.
+//!
+//! Each worker owns `NNTP_RING_POINTS_PER_WORKER` points on a hash ring and a
+//! group belongs to the first point at or after its own hash. Requests for a
+//! group therefore keep landing on the same connection, which keeps the
+//! group selected there and lets the upstream server reuse its per-session
+//! caches. When a worker is down its groups move to the next point on the
+//! ring; every other group stays where it was.
+
+use std::hash::{DefaultHasher, Hash, Hasher};
+
+use crate::config::NNTP_RING_POINTS_PER_WORKER;
+
+/// Hash ring over worker ids
+#[derive(Debug, Clone)]
+pub struct WorkerRing {
+    /// (point, worker id), sorted by point
+    points: Vec<(u64, usize)>,
+}
+
+impl WorkerRing {
+    /// Build a ring for workers `0..workers`
+    pub fn new(workers: usize) -> Self {
+        let mut points: Vec<(u64, usize)> = (0..workers)
+            .flat_map(|worker| {
+                (0..NNTP_RING_POINTS_PER_WORKER).map(move |point| (hash(&(worker, point)), worker))
+            })
+            .collect();
+        points.sort_unstable();
+        Self { points }
+    }
+
+    /// Worker that owns `group`, skipping workers for which `is_live` is false.
+    /// Returns `None` when no worker is live.
+    pub fn worker_for(&self, group: &str, is_live: impl Fn(usize) -> bool) -> Option<usize> {
+        if self.points.is_empty() {
+            return None;
+        }
+        let start = self
+            .points
+            .partition_point(|(point, _)| *point < hash(group));
+        (0..self.points.len())
+            .map(|offset| self.points[(start + offset) % self.points.len()].1)
+            .find(|&worker| is_live(worker))
+    }
+}
+
+fn hash<T: Hash + ?Sized>(value: &T) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    value.hash(&mut hasher);
+    hasher.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn groups() -> Vec<String> {
+        (0..500).map(|n| format!("alt.test.{}", n)).collect()
+    }
+
+    #[test]
+    fn test_ring_is_stable_and_spreads_groups() {
+        let ring = WorkerRing::new(4);
+        let mut per_worker = [0usize; 4];
+        for group in groups() {
+            let worker = ring.worker_for(&group, |_| true).unwrap();
+            assert_eq!(ring.worker_for(&group, |_| true), Some(worker));
+            per_worker[worker] += 1;
+        }
+        // Every worker gets a reasonable share of 500 groups
+        assert!(
+            per_worker.iter().all(|&count| count > 50),
+            "{:?}",
+            per_worker
+        );
+    }
+
+    #[test]
+    fn test_ring_only_moves_groups_of_down_worker() {
+        let ring = WorkerRing::new(4);
+        for group in groups() {
+            let home = ring.worker_for(&group, |_| true).unwrap();
+            let fallback = ring.worker_for(&group, |worker| worker != 2).unwrap();
+            if home == 2 {
+                assert_ne!(fallback, 2);
+            } else {
+                assert_eq!(fallback, home);
+            }
+        }
+        assert_eq!(ring.worker_for("alt.test", |_| false), None);
+        assert_eq!(WorkerRing::new(0).worker_for("alt.test", |_| true), None);
+    }
+}
@@ -0,0 +1,185 @@
+//! Display sanitization for header values.
+//!
+//! From and Subject are attacker-controlled and rendered in thread lists and
+//! page titles. Characters that can spoof or garble them are removed:
+//! ANSI escape sequences and other control characters, bidirectional
+//! overrides and isolates (which can reverse "moc.elpmaxe@" into a trusted
+//! address), invisible zero-width and filler characters, and runs of stacked
+//! combining marks. Emoji, including ZWJ and tag sequences, are kept intact.
+
+use crate::config::HEADER_MAX_COMBINING_MARKS;
+
+/// Sanitize a single header value for display.
+/// Line breaks and tabs become spaces and the result is trimmed.
+pub fn sanitize_header(value: &str) -> String {
+    sanitize(value, false).trim().to_string()
+}
+
+/// Sanitize a raw header block for display, keeping its line breaks.
+pub fn sanitize_header_block(headers: &str) -> String {
+    sanitize(headers, true)
+}
+
+fn sanitize(value: &str, keep_newlines: bool) -> String {
+    let mut out = String::with_capacity(value.len());
+    let mut chars = value.chars().peekable();
+    let mut combining_run = 0usize;
+    let mut prev: Option<char> = None;
+
+    while let Some(c) = chars.next() {
+        // ESC sequences: CSI ("ESC [ ... final"), OSC ("ESC ] ... BEL/ST"),
+        // or a two-character escape
+        if c == '\u{1B}' || c == '\u{9B}' {
+            let kind = if c == '\u{9B}' {
+                Some('[')
+            } else {
+                chars.next()
+            };
+            match kind {
+                Some('[') => {
+                    for c in chars.by_ref() {
+                        if ('\u{40}'..='\u{7E}').contains(&c) {
+                            break;
+                        }
+                    }
+                }
+                Some(']') => {
+                    while let Some(c) = chars.next() {
+                        if c == '\u{07}' {
+                            break;
+                        }
+                        if c == '\u{1B}' && chars.peek() == Some(&'\\') {
+                            chars.next();
+                            break;
+                        }
+                    }
+                }
+                _ => {}
+            }
+            continue;
+        }
+
+        if c == '\r' || c == '\n' {
+            if keep_newlines {
+                if c == '\n' {
+                    out.push('\n');
+                }
+            } else if prev != Some(' ') {
+                out.push(' ');
+                prev = Some(' ');
+            }
+            combining_run = 0;
+            continue;
+        }
+        if c == '\t' {
+            if prev != Some(' ') {
+                out.push(' ');
+                prev = Some(' ');
+            }
+            combining_run = 0;
+            continue;
+        }
+        if c.is_control() || is_invisible(c, prev) {
+            continue;
+        }
+
+        if is_combining_mark(c) {
+            combining_run += 1;
+            if combining_run > HEADER_MAX_COMBINING_MARKS {
+                continue;
+            }
+        } else {
+            combining_run = 0;
+        }
+
+        out.push(c);
+        prev = Some(c);
+    }
+
+    out
+}
+
+/// Bidi controls, zero-width characters and fillers that render as nothing.
+/// ZWJ and tag characters are allowed directly after emoji, where they form
+/// sequences such as family and subdivision-flag emoji.
+fn is_invisible(c: char, prev: Option<char>) -> bool {
+    match c {
+        // Bidi embeddings, overrides, isolates and marks
+        '\u{202A}'..='\u{202E}' | '\u{2066}'..='\u{2069}' | '\u{200E}' | '\u{200F}' => true,
+        '\u{061C}' => true,
+        // Zero-width space, word joiner, invisible operators, BOM
+        '\u{200B}' | '\u{2060}'..='\u{2064}' | '\u{FEFF}' | '\u{180E}' => true,
+        // Hangul fillers (used for "blank" names)
+        '\u{115F}' | '\u{1160}' | '\u{3164}' | '\u{FFA0}' => true,
+        '\u{200D}' => !prev.is_some_and(is_emoji),
+        '\u{E0000}'..='\u{E007F}' => {
+            !prev.is_some_and(|p| p == '\u{1F3F4}' || ('\u{E0020}'..='\u{E007E}').contains(&p))
+        }
+        _ => false,
+    }
+}
+
+/// Rough emoji test, enough to keep ZWJ sequences joined
+fn is_emoji(c: char) -> bool {
+    matches!(c, '\u{1F000}'..='\u{1FAFF}' | '\u{2600}'..='\u{27BF}' | '\u{FE0F}')
+}
+
+/// Combining diacritical mark blocks (stacked marks produce "Zalgo" text)
+fn is_combining_mark(c: char) -> bool {
+    matches!(
+        c,
+        '\u{0300}'..='\u{036F}'
+            | '\u{1AB0}'..='\u{1AFF}'
+            | '\u{1DC0}'..='\u{1DFF}'
+            | '\u{20D0}'..='\u{20FF}'
+            | '\u{FE20}'..='\u{FE2F}'
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sanitize_strips_ansi_escapes() {
+        assert_eq!(sanitize_header("\u{1B}[31mRed\u{1B}[0m alert"), "Red alert");
+        assert_eq!(sanitize_header("\u{1B}]0;title\u{07}Hello"), "Hello");
+        assert_eq!(sanitize_header("\u{1B}]8;;http://x\u{1B}\\link"), "link");
+    }
+
+    #[test]
+    fn test_sanitize_strips_bidi_and_zero_width() {
+        assert_eq!(
+            sanitize_header("admin\u{202E}moc.elpmaxe@"),
+            "adminmoc.elpmaxe@"
+        );
+        assert_eq!(sanitize_header("pay\u{200B}pal\u{2066}"), "paypal");
+        assert_eq!(sanitize_header("\u{3164}"), "");
+    }
+
+    #[test]
+    fn test_sanitize_folds_line_breaks_and_controls() {
+        assert_eq!(sanitize_header("Hello\r\n\tworld\u{7}\u{0}"), "Hello world");
+        assert_eq!(
+            sanitize_header_block("A: 1\r\nB: \u{202E}2\r\n"),
+            "A: 1\nB: 2\n"
+        );
+    }
+
+    #[test]
+    fn test_sanitize_keeps_emoji_sequences() {
+        let family = "\u{1F468}\u{200D}\u{1F469}\u{200D}\u{1F467}";
+        assert_eq!(sanitize_header(family), family);
+        let england = "\u{1F3F4}\u{E0067}\u{E0062}\u{E0065}\u{E006E}\u{E0067}\u{E007F}";
+        assert_eq!(sanitize_header(england), england);
+        assert_eq!(sanitize_header("a\u{200D}b"), "ab");
+        assert_eq!(sanitize_header("Caf\u{E9} \u{2615}"), "Caf\u{E9} \u{2615}");
+    }
+
+    #[test]
+    fn test_sanitize_limits_stacked_combining_marks() {
+        let zalgo = format!("Z{}", "\u{0301}".repeat(20));
+        let expected = format!("Z{}", "\u{0301}".repeat(HEADER_MAX_COMBINING_MARKS));
+        assert_eq!(sanitize_header(&zalgo), expected);
+    }
+}
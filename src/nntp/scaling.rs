@@ -0,0 +1,75 @@
+//! Load-driven sizing of a server's worker pool.
+//!
+//! A server with `max_workers` above `worker_count` starts with
+//! `worker_count` workers, which stay connected for good. A controller task
+//! samples the pool every `NNTP_SCALE_INTERVAL_MS`: how many requests wait in
+//! the shared queues and the longest any request waited before a worker took
+//! it. When requests are waiting and either there are at least as many as
+//! workers or one waited `NNTP_SCALE_UP_WAIT_MS`, it adds a worker, one per
+//! sample, up to `max_workers`. Added workers get no affinity queue or place
+//! on the hash ring; they serve the shared queues and disconnect after
+//! `NNTP_SCALED_WORKER_IDLE_SECS` without a request, or when they fail to
+//! connect, so a server refusing more connections isn't pressed for them.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+use crate::config::NNTP_SCALE_UP_WAIT_MS;
+
+/// Queue waits seen by a server's workers, shared with its controller
+#[derive(Default)]
+pub struct PoolLoad {
+    /// Longest wait since the controller last looked, in milliseconds
+    max_wait_ms: AtomicU64,
+}
+
+impl PoolLoad {
+    /// Record how long a request waited before a worker took it
+    pub fn record_wait(&self, wait: Duration) {
+        let ms = u64::try_from(wait.as_millis()).unwrap_or(u64::MAX);
+        self.max_wait_ms.fetch_max(ms, Ordering::Relaxed);
+    }
+
+    /// Longest wait since the last call, starting a new sample
+    pub fn take_max_wait(&self) -> Duration {
+        Duration::from_millis(self.max_wait_ms.swap(0, Ordering::Relaxed))
+    }
+}
+
+/// Whether a pool of `workers` (at most `max`) should add one, given the
+/// requests waiting in its shared queues and the longest recent wait
+pub fn should_grow(queued: usize, max_wait: Duration, workers: usize, max: usize) -> bool {
+    workers < max
+        && queued > 0
+        && (queued >= workers || max_wait >= Duration::from_millis(NNTP_SCALE_UP_WAIT_MS))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_should_grow() {
+        let slow = Duration::from_millis(NNTP_SCALE_UP_WAIT_MS);
+        let fast = Duration::from_millis(10);
+        // A backlog as deep as the pool, or requests waiting too long
+        assert!(should_grow(4, fast, 4, 8));
+        assert!(should_grow(1, slow, 4, 8));
+        // Nothing waiting now, whatever happened before
+        assert!(!should_grow(0, slow, 4, 8));
+        // A short, quickly served queue
+        assert!(!should_grow(2, fast, 4, 8));
+        // At the limit
+        assert!(!should_grow(10, slow, 8, 8));
+    }
+
+    #[test]
+    fn test_pool_load_keeps_longest_wait_per_sample() {
+        let load = PoolLoad::default();
+        load.record_wait(Duration::from_millis(30));
+        load.record_wait(Duration::from_millis(120));
+        load.record_wait(Duration::from_millis(50));
+        assert_eq!(load.take_max_wait(), Duration::from_millis(120));
+        assert_eq!(load.take_max_wait(), Duration::ZERO);
+    }
+}
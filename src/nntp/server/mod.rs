@@ -0,0 +1,85 @@
+//! Read-only NNTP listener for downstream newsreaders.
+//!
+//! Lets traditional newsreaders (slrn, Thunderbird) point at September as
+//! their news server. Groups and articles are served from the federated
+//! service's caches; posting is refused.
+//!
+//! Article numbers are synthesized per group from the cached thread list
+//! (see `session`), since the underlying articles may come from several
+//! upstream servers with unrelated numbering. They are shared by all
+//! connections so a newsreader's read marks survive reconnecting.
+
+mod session;
+
+use std::sync::Arc;
+
+use tokio::io::{AsyncWriteExt, BufReader};
+use tokio::net::TcpListener;
+use tokio::sync::Semaphore;
+use tracing::Instrument;
+
+use self::session::ArticleNumbers;
+use super::NntpFederatedService;
+use crate::config::{FromDisplay, PeeringConfig};
+
+/// Bind the listener and spawn its accept loop.
+///
+/// Binding happens before returning so configuration errors surface at
+/// startup. Connections beyond `max_connections` get a 400 and are closed.
+/// Poster addresses are served according to `from_display`.
+pub async fn spawn_listener(
+    config: &PeeringConfig,
+    nntp: NntpFederatedService,
+    from_display: FromDisplay,
+) -> std::io::Result<()> {
+    let listener = TcpListener::bind((config.host.as_str(), config.port)).await?;
+    let connection_limit = Arc::new(Semaphore::new(config.max_connections));
+    let numbers = ArticleNumbers::default();
+
+    tracing::info!(
+        host = %config.host,
+        port = config.port,
+        max_connections = config.max_connections,
+        "Started read-only NNTP listener"
+    );
+
+    tokio::spawn(async move {
+        loop {
+            let (stream, peer) = match listener.accept().await {
+                Ok(connection) => connection,
+                Err(e) => {
+                    tracing::warn!(error = %e, "Failed to accept NNTP reader connection");
+                    continue;
+                }
+            };
+
+            let permit = connection_limit.clone().try_acquire_owned();
+            let nntp = nntp.clone();
+            let numbers = numbers.clone();
+            let span = tracing::info_span!("nntp.server.session", %peer);
+
+            tokio::spawn(
+                async move {
+                    let (reader, mut writer) = stream.into_split();
+                    let Ok(_permit) = permit else {
+                        tracing::debug!("Rejecting reader connection, limit reached");
+                        let _ = writer
+                            .write_all(b"400 Too many connections, try again later\r\n")
+                            .await;
+                        return;
+                    };
+
+                    tracing::debug!("Reader connected");
+                    let reader = BufReader::new(reader);
+                    match session::run(nntp, from_display, numbers, reader, writer).await {
+                        Ok(()) => tracing::debug!("Reader disconnected"),
+                        Err(e) => tracing::debug!(error = %e, "Reader connection closed"),
+                    }
+                }
+                .instrument(span),
+            );
+        }
+    });
+
+    Ok(())
+}
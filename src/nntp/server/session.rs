@@ -0,0 +1,1007 @@
+//! Per-connection command handling for the read-only NNTP listener.
+//!
+//! Implements the reader subset of RFC 3977 that newsreaders need to browse:
+//! CAPABILITIES, MODE READER, LIST (ACTIVE, NEWSGROUPS, OVERVIEW.FMT), GROUP,
+//! LISTGROUP, OVER/XOVER, ARTICLE, HEAD, BODY, STAT, NEXT, LAST, DATE and QUIT.
+//! Every command is answered from NntpFederatedService, so readers share the
+//! web interface's caches and request coalescing.
+
+use std::collections::HashMap;
+use std::io;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use chrono::{DateTime, FixedOffset, Utc};
+use moka::future::Cache;
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+use crate::config::{
+    FromDisplay, PEERING_IDLE_TIMEOUT_SECS, PEERING_MAX_LINE_LENGTH, PEERING_NUMBERED_ARTICLES_MAX,
+};
+use crate::error::AppError;
+use crate::nntp::privacy::{mask_from, mask_header_block};
+use crate::nntp::{
+    parse_date, wildmat, ArticleView, NntpFederatedService, ThreadNodeView, ThreadView,
+};
+
+/// Fields advertised by LIST OVERVIEW.FMT, in OVER output order.
+const OVERVIEW_FORMAT: [&str; 7] = [
+    "Subject:",
+    "From:",
+    "Date:",
+    "Message-ID:",
+    "References:",
+    ":bytes",
+    ":lines",
+];
+
+/// Article numbers handed out per group, shared by every reader connection.
+///
+/// Each group keeps the numbers of the articles in its latest listing, so
+/// an article keeps its number while the cached thread list is refreshed.
+/// The maps are held in a cache capped at `PEERING_NUMBERED_ARTICLES_MAX`
+/// Message-IDs, and the high-water marks are kept apart from them, so an
+/// article that drops out and comes back, or a group that was evicted, gets
+/// new numbers above the old ones instead of reusing any.
+#[derive(Clone)]
+pub(super) struct ArticleNumbers {
+    /// Message-ID to number, per group, weighed by article count
+    groups: Cache<String, Arc<Mutex<HashMap<String, u64>>>>,
+    /// Highest number handed out per group (one entry per group that exists)
+    highs: Arc<Mutex<HashMap<String, u64>>>,
+}
+
+impl Default for ArticleNumbers {
+    fn default() -> Self {
+        Self::with_capacity(PEERING_NUMBERED_ARTICLES_MAX)
+    }
+}
+
+impl ArticleNumbers {
+    fn with_capacity(max_articles: u64) -> Self {
+        Self {
+            groups: Cache::builder()
+                .max_capacity(max_articles)
+                .weigher(|_, numbers: &Arc<Mutex<HashMap<String, u64>>>| {
+                    let len = numbers.lock().unwrap_or_else(|e| e.into_inner()).len();
+                    u32::try_from(len).unwrap_or(u32::MAX).max(1)
+                })
+                .build(),
+            highs: Arc::default(),
+        }
+    }
+
+    /// Number a group's cached thread list, returning the articles and the
+    /// group's high-water mark. Articles seen before keep their numbers; new
+    /// ones get the next free numbers, oldest first.
+    async fn number(&self, group: &str, threads: &[ThreadView]) -> (Vec<NumberedArticle>, u64) {
+        let numbers = self
+            .groups
+            .get_with(group.to_string(), async { Arc::default() })
+            .await;
+        let (articles, high) = {
+            let mut by_message_id = numbers.lock().unwrap_or_else(|e| e.into_inner());
+            let mut highs = self.highs.lock().unwrap_or_else(|e| e.into_inner());
+            let high = highs.entry(group.to_string()).or_default();
+            (number_articles(threads, &mut by_message_id, high), *high)
+        };
+        // Inserting again makes the cache weigh the group at its new size
+        self.groups.insert(group.to_string(), numbers).await;
+        (articles, high)
+    }
+
+    /// Highest number handed out in a group (0 if none yet)
+    fn high(&self, group: &str) -> u64 {
+        let highs = self.highs.lock().unwrap_or_else(|e| e.into_inner());
+        highs.get(group).copied().unwrap_or(0)
+    }
+}
+
+/// An article in the selected group with its synthesized article number.
+#[derive(Debug, Clone)]
+struct NumberedArticle {
+    number: u64,
+    message_id: String,
+    subject: String,
+    /// Unmasked; OVER masks it
+    from: String,
+    date: String,
+    /// Space-separated ancestor Message-IDs, oldest first
+    references: String,
+}
+
+/// The group selected with GROUP or LISTGROUP.
+struct SelectedGroup {
+    name: String,
+    articles: Vec<NumberedArticle>,
+    /// Highest number handed out in the group, which the last article may
+    /// no longer have
+    high: u64,
+    /// Index into `articles` of the current article, if any
+    current: Option<usize>,
+}
+
+impl SelectedGroup {
+    fn low(&self) -> u64 {
+        self.articles.first().map_or(self.high + 1, |a| a.number)
+    }
+
+    fn index_of_number(&self, number: u64) -> Option<usize> {
+        self.articles
+            .binary_search_by_key(&number, |a| a.number)
+            .ok()
+    }
+
+    fn index_of_message_id(&self, message_id: &str) -> Option<usize> {
+        self.articles
+            .iter()
+            .position(|a| a.message_id == message_id)
+    }
+
+    fn in_range(&self, (low, high): (u64, u64)) -> impl Iterator<Item = &NumberedArticle> {
+        self.articles
+            .iter()
+            .filter(move |a| a.number >= low && a.number <= high)
+    }
+}
+
+/// What to send back for one command.
+enum Reply {
+    /// Send the response and wait for the next command
+    Continue(String),
+    /// Send the response and close the connection
+    Close(String),
+}
+
+/// Which parts of an article to return.
+#[derive(Clone, Copy)]
+enum ArticlePart {
+    Whole,
+    Head,
+    Body,
+    Stat,
+}
+
+/// State for one downstream reader connection.
+struct Session {
+    nntp: NntpFederatedService,
+    /// How poster addresses are shown (`[ui] from_display`)
+    from_display: FromDisplay,
+    numbers: ArticleNumbers,
+    group: Option<SelectedGroup>,
+}
+
+/// Serve one reader connection until QUIT, EOF, idle timeout or a protocol error.
+pub(super) async fn run<R, W>(
+    nntp: NntpFederatedService,
+    from_display: FromDisplay,
+    numbers: ArticleNumbers,
+    mut reader: R,
+    mut writer: W,
+) -> io::Result<()>
+where
+    R: AsyncBufRead + Unpin,
+    W: AsyncWrite + Unpin,
+{
+    writer
+        .write_all(b"201 September news gateway ready (read-only, posting prohibited)\r\n")
+        .await?;
+    writer.flush().await?;
+
+    let mut session = Session {
+        nntp,
+        from_display,
+        numbers,
+        group: None,
+    };
+    let mut line = String::new();
+
+    loop {
+        line.clear();
+        let read = tokio::time::timeout(
+            Duration::from_secs(PEERING_IDLE_TIMEOUT_SECS),
+            (&mut reader)
+                .take(PEERING_MAX_LINE_LENGTH as u64 + 1)
+                .read_line(&mut line),
+        )
+        .await;
+
+        match read {
+            Err(_) => {
+                writer
+                    .write_all(b"400 Idle timeout, closing connection\r\n")
+                    .await?;
+                return writer.flush().await;
+            }
+            Ok(Ok(0)) => return Ok(()),
+            Ok(Ok(_)) => {}
+            Ok(Err(e)) => return Err(e),
+        }
+
+        if line.len() > PEERING_MAX_LINE_LENGTH {
+            writer.write_all(b"501 Command line too long\r\n").await?;
+            return writer.flush().await;
+        }
+
+        match session.handle(line.trim_end_matches(['\r', '\n'])).await {
+            Reply::Continue(response) => {
+                writer.write_all(response.as_bytes()).await?;
+                writer.flush().await?;
+            }
+            Reply::Close(response) => {
+                writer.write_all(response.as_bytes()).await?;
+                return writer.flush().await;
+            }
+        }
+    }
+}
+
+impl Session {
+    /// Dispatch a single command line.
+    async fn handle(&mut self, line: &str) -> Reply {
+        let mut words = line.split_whitespace();
+        let Some(command) = words.next() else {
+            return Reply::Continue(status("500 Empty command"));
+        };
+        let command = command.to_ascii_uppercase();
+        let args: Vec<&str> = words.collect();
+        tracing::debug!(%command, "NNTP reader command");
+
+        let response = match command.as_str() {
+            "CAPABILITIES" => capabilities(),
+            "MODE"
+                if args
+                    .first()
+                    .is_some_and(|a| a.eq_ignore_ascii_case("READER")) =>
+            {
+                status("201 Reader mode, posting prohibited")
+            }
+            "QUIT" => return Reply::Close(status("205 Connection closing")),
+            "DATE" => status(&format!("111 {}", Utc::now().format("%Y%m%d%H%M%S"))),
+            "LIST" => self.list(&args).await,
+            "GROUP" => match args.first() {
+                Some(name) => self.group(name).await,
+                None => status("501 Syntax: GROUP newsgroup"),
+            },
+            "LISTGROUP" => self.listgroup(&args).await,
+            "OVER" | "XOVER" => self.over(args.first().copied()),
+            "ARTICLE" => {
+                self.article(ArticlePart::Whole, args.first().copied())
+                    .await
+            }
+            "HEAD" => self.article(ArticlePart::Head, args.first().copied()).await,
+            "BODY" => self.article(ArticlePart::Body, args.first().copied()).await,
+            "STAT" => self.article(ArticlePart::Stat, args.first().copied()).await,
+            "NEXT" => self.step(true),
+            "LAST" => self.step(false),
+            "POST" => status("440 Posting not permitted"),
+            "IHAVE" => status("435 Article not wanted"),
+            _ => status("500 Unknown command"),
+        };
+        Reply::Continue(response)
+    }
+
+    /// LIST [ACTIVE|NEWSGROUPS [wildmat]] or LIST OVERVIEW.FMT
+    async fn list(&self, args: &[&str]) -> String {
+        let keyword = args
+            .first()
+            .map(|k| k.to_ascii_uppercase())
+            .unwrap_or_else(|| "ACTIVE".to_string());
+        let pattern = args.get(1).copied();
+
+        if keyword == "OVERVIEW.FMT" {
+            return multiline(
+                "215 Order of fields in overview database",
+                OVERVIEW_FORMAT.iter().map(|f| f.to_string()),
+            );
+        }
+        if keyword != "ACTIVE" && keyword != "NEWSGROUPS" {
+            return status("501 Unsupported LIST keyword");
+        }
+
+        let groups = match self.nntp.get_groups().await {
+            Ok(groups) => groups,
+            Err(e) => return internal_fault(&e),
+        };
+        let groups = groups
+            .into_iter()
+            .filter(|g| pattern.map(|p| wildmat(p, &g.name)).unwrap_or(true));
+
+        if keyword == "ACTIVE" {
+            let mut lines = Vec::new();
+            for g in groups {
+                let (articles, high) = match self.nntp.cached_threads(&g.name).await {
+                    Some(threads) => self.numbers.number(&g.name, &threads).await,
+                    None => (Vec::new(), self.numbers.high(&g.name)),
+                };
+                lines.push(active_line(&g.name, &articles, high));
+            }
+            multiline("215 List of newsgroups follows", lines)
+        } else {
+            multiline(
+                "215 List of newsgroups follows",
+                groups.map(|g| {
+                    let description = g.description.as_deref().unwrap_or("");
+                    format!("{}\t{}", g.name, sanitize_field(description))
+                }),
+            )
+        }
+    }
+
+    /// GROUP newsgroup
+    async fn group(&mut self, name: &str) -> String {
+        match self.select_group(name).await {
+            Ok(group) => status(&format!(
+                "211 {} {} {} {}",
+                group.articles.len(),
+                group.low(),
+                group.high,
+                group.name
+            )),
+            Err(response) => response,
+        }
+    }
+
+    /// LISTGROUP [newsgroup [range]]
+    async fn listgroup(&mut self, args: &[&str]) -> String {
+        if let Some(name) = args.first() {
+            if let Err(response) = self.select_group(name).await {
+                return response;
+            }
+        }
+        let Some(group) = self.group.as_ref() else {
+            return status("412 No newsgroup selected");
+        };
+
+        let range = match args.get(1) {
+            Some(arg) => match parse_range(arg) {
+                Some(range) => range,
+                None => return status("501 Invalid range"),
+            },
+            None => (0, u64::MAX),
+        };
+
+        multiline(
+            &format!(
+                "211 {} {} {} {} list follows",
+                group.articles.len(),
+                group.low(),
+                group.high,
+                group.name
+            ),
+            group.in_range(range).map(|a| a.number.to_string()),
+        )
+    }
+
+    /// OVER/XOVER [range|message-id]
+    fn over(&self, arg: Option<&str>) -> String {
+        if let Some(message_id) = arg.filter(|a| a.starts_with('<')) {
+            return match self
+                .group
+                .as_ref()
+                .and_then(|g| g.index_of_message_id(message_id).map(|i| &g.articles[i]))
+            {
+                Some(article) => multiline(
+                    "224 Overview information follows",
+                    std::iter::once(overview_line(article, self.from_display)),
+                ),
+                None => status("430 No article with that message-id"),
+            };
+        }
+
+        let Some(group) = self.group.as_ref() else {
+            return status("412 No newsgroup selected");
+        };
+
+        let articles: Vec<&NumberedArticle> = match arg {
+            Some(arg) => match parse_range(arg) {
+                Some(range) => group.in_range(range).collect(),
+                None => return status("501 Invalid range"),
+            },
+            None => match group.current {
+                Some(index) => vec![&group.articles[index]],
+                None => return status("420 Current article number is invalid"),
+            },
+        };
+
+        if articles.is_empty() {
+            return status("423 No articles in that range");
+        }
+        multiline(
+            "224 Overview information follows",
+            articles
+                .into_iter()
+                .map(|a| overview_line(a, self.from_display)),
+        )
+    }
+
+    /// ARTICLE, HEAD, BODY and STAT [number|message-id]
+    async fn article(&mut self, part: ArticlePart, arg: Option<&str>) -> String {
+        let by_message_id = arg.map(|a| a.starts_with('<')).unwrap_or(false);
+
+        let (number, message_id) = if let Some(message_id) = arg.filter(|_| by_message_id) {
+            let number = self
+                .group
+                .as_ref()
+                .and_then(|g| {
+                    g.index_of_message_id(message_id)
+                        .map(|i| g.articles[i].number)
+                })
+                .unwrap_or(0);
+            (number, message_id.to_string())
+        } else {
+            let Some(group) = self.group.as_mut() else {
+                return status("412 No newsgroup selected");
+            };
+            let index = match arg {
+                Some(arg) => match arg.parse::<u64>() {
+                    Ok(number) => match group.index_of_number(number) {
+                        Some(index) => index,
+                        None => return status("423 No article with that number"),
+                    },
+                    Err(_) => return status("501 Expected article number or message-id"),
+                },
+                None => match group.current {
+                    Some(index) => index,
+                    None => return status("420 Current article number is invalid"),
+                },
+            };
+            // Selecting an article by number makes it the current article
+            group.current = Some(index);
+            let article = &group.articles[index];
+            (article.number, article.message_id.clone())
+        };
+
+        // Numbered STAT is answered from the overview; no need to fetch the body
+        if matches!(part, ArticlePart::Stat) && !by_message_id {
+            return status(&format!("223 {} {} Article exists", number, message_id));
+        }
+
+        let article = match self.nntp.get_article(&message_id).await {
+            Ok(article) => article,
+            Err(AppError::ArticleNotFound(_)) if by_message_id => {
+                return status("430 No article with that message-id");
+            }
+            Err(AppError::ArticleNotFound(_)) => return status("423 No article with that number"),
+            Err(e) => return internal_fault(&e),
+        };
+
+        match part {
+            ArticlePart::Whole => {
+                let mut lines = header_lines(&article, self.from_display);
+                lines.push(String::new());
+                lines.extend(body_lines(&article));
+                multiline(
+                    &format!("220 {} {} Article follows", number, message_id),
+                    lines,
+                )
+            }
+            ArticlePart::Head => multiline(
+                &format!("221 {} {} Headers follow", number, message_id),
+                header_lines(&article, self.from_display),
+            ),
+            ArticlePart::Body => multiline(
+                &format!("222 {} {} Body follows", number, message_id),
+                body_lines(&article),
+            ),
+            ArticlePart::Stat => status(&format!("223 {} {} Article exists", number, message_id)),
+        }
+    }
+
+    /// NEXT (forward) or LAST (backward)
+    fn step(&mut self, forward: bool) -> String {
+        let Some(group) = self.group.as_mut() else {
+            return status("412 No newsgroup selected");
+        };
+        let Some(current) = group.current else {
+            return status("420 Current article number is invalid");
+        };
+
+        let next = if forward {
+            Some(current + 1).filter(|&i| i < group.articles.len())
+        } else {
+            current.checked_sub(1)
+        };
+
+        match next {
+            Some(index) => {
+                group.current = Some(index);
+                let article = &group.articles[index];
+                status(&format!(
+                    "223 {} {} Article found",
+                    article.number, article.message_id
+                ))
+            }
+            None if forward => status("421 No next article in this group"),
+            None => status("422 No previous article in this group"),
+        }
+    }
+
+    /// Load a group's cached thread list and number its articles.
+    /// On failure, returns the response to send instead.
+    async fn select_group(&mut self, name: &str) -> Result<&SelectedGroup, String> {
+        let name = self.nntp.canonical_group(name).to_string();
+
+        let groups = self
+            .nntp
+            .get_groups()
+            .await
+            .map_err(|e| internal_fault(&e))?;
+        if !groups.iter().any(|g| g.name == name) {
+            return Err(status("411 No such newsgroup"));
+        }
+
+        let threads = match self.nntp.get_threads(&name, 0).await {
+            Ok(threads) => threads,
+            Err(AppError::GroupNotFound(_)) => return Err(status("411 No such newsgroup")),
+            Err(e) => return Err(internal_fault(&e)),
+        };
+
+        let (articles, high) = self.numbers.number(&name, &threads).await;
+        let current = if articles.is_empty() { None } else { Some(0) };
+        Ok(self.group.insert(SelectedGroup {
+            name,
+            articles,
+            high,
+            current,
+        }))
+    }
+}
+
+/// CAPABILITIES response
+fn capabilities() -> String {
+    multiline(
+        "101 Capability list follows",
+        [
+            "VERSION 2".to_string(),
+            "READER".to_string(),
+            "LIST ACTIVE NEWSGROUPS OVERVIEW.FMT".to_string(),
+            "OVER MSGID".to_string(),
+            format!("IMPLEMENTATION September {}", env!("CARGO_PKG_VERSION")),
+        ],
+    )
+}
+
+/// Number every article in a thread list, in number order.
+///
+/// Numbers are synthesized because articles may come from several upstream
+/// servers with unrelated numbering. Articles already in `by_message_id`
+/// keep their number; the rest are appended in date order after `high`.
+/// Articles that dropped out of the list leave gaps and are removed from
+/// `by_message_id`, so it never outgrows the listing.
+fn number_articles(
+    threads: &[ThreadView],
+    by_message_id: &mut HashMap<String, u64>,
+    high: &mut u64,
+) -> Vec<NumberedArticle> {
+    let mut collected = Vec::new();
+    let mut ancestors = Vec::new();
+    for thread in threads {
+        collect_articles(&thread.root, &mut ancestors, &mut collected);
+    }
+
+    // Undated articles go last; the stable sort keeps them in thread order
+    collected.sort_by_key(|(date, _)| (date.is_none(), *date));
+    let mut articles: Vec<NumberedArticle> = collected
+        .into_iter()
+        .map(|(_, article)| {
+            let number = *by_message_id
+                .entry(article.message_id.clone())
+                .or_insert_with(|| {
+                    *high += 1;
+                    *high
+                });
+            NumberedArticle { number, ..article }
+        })
+        .collect();
+    by_message_id.retain(|message_id, _| articles.iter().any(|a| &a.message_id == message_id));
+    articles.sort_by_key(|a| a.number);
+    articles
+}
+
+/// LIST ACTIVE line for a group, with the watermarks GROUP would report. A
+/// group whose thread list isn't cached is listed as empty (low above high)
+/// rather than fetched, so listing every group doesn't load every group.
+fn active_line(name: &str, articles: &[NumberedArticle], high: u64) -> String {
+    let low = articles.first().map_or(high + 1, |a| a.number);
+    format!("{} {} {} n", name, high, low)
+}
+
+/// Walk a thread tree, recording each present article with its references.
+fn collect_articles(
+    node: &ThreadNodeView,
+    ancestors: &mut Vec<String>,
+    out: &mut Vec<(Option<DateTime<FixedOffset>>, NumberedArticle)>,
+) {
+    if let Some(article) = &node.article {
+        out.push((
+            parse_date(&article.date),
+            NumberedArticle {
+                number: 0,
+                message_id: node.message_id.clone(),
+                subject: article.subject.clone(),
+                from: article.from.clone(),
+                date: article.date.clone(),
+                references: ancestors.join(" "),
+            },
+        ));
+    }
+
+    ancestors.push(node.message_id.clone());
+    for reply in &node.replies {
+        collect_articles(reply, ancestors, out);
+    }
+    ancestors.pop();
+}
+
+/// Format one OVER line. Byte and line counts aren't tracked, so those fields are empty.
+fn overview_line(article: &NumberedArticle, mode: FromDisplay) -> String {
+    format!(
+        "{}\t{}\t{}\t{}\t{}\t{}\t\t",
+        article.number,
+        sanitize_field(&article.subject),
+        sanitize_field(&mask_from(&article.from, mode)),
+        sanitize_field(&article.date),
+        article.message_id,
+        article.references
+    )
+}
+
+/// Header block for ARTICLE/HEAD, falling back to the parsed fields
+/// when raw headers weren't kept.
+///
+/// Bodies are stored decoded to UTF-8 whatever charset they were posted in,
+/// so Content-Type is rewritten to declare UTF-8.
+fn header_lines(article: &ArticleView, mode: FromDisplay) -> Vec<String> {
+    match &article.headers {
+        Some(raw) => {
+            let masked = mask_header_block(raw, mode);
+            let mut lines: Vec<String> = Vec::new();
+            let mut in_content_type = false;
+            for line in masked.lines().map(|l| l.trim_end_matches('\r')) {
+                if line.is_empty() {
+                    continue;
+                }
+                let is_continuation = line.starts_with([' ', '\t']);
+                match lines.last_mut() {
+                    // Unfold Content-Type so its parameters can be rewritten
+                    Some(last) if is_continuation && in_content_type => {
+                        last.push(' ');
+                        last.push_str(line.trim_start());
+                    }
+                    _ => {
+                        if !is_continuation {
+                            in_content_type = line
+                                .get(..13)
+                                .is_some_and(|name| name.eq_ignore_ascii_case("content-type:"));
+                        }
+                        lines.push(line.to_string());
+                    }
+                }
+            }
+            for line in &mut lines {
+                if line
+                    .get(..13)
+                    .is_some_and(|name| name.eq_ignore_ascii_case("content-type:"))
+                {
+                    *line = format!("Content-Type: {}", utf8_content_type(&line[13..]));
+                }
+            }
+            lines
+        }
+        None => vec![
+            format!("From: {}", mask_from(&article.from, mode)),
+            format!("Subject: {}", article.subject),
+            format!("Date: {}", article.date),
+            format!("Message-ID: {}", article.message_id),
+        ],
+    }
+}
+
+/// A Content-Type value with its charset parameter set to UTF-8. Text types
+/// without one gain it; other types (multipart, application) are unchanged.
+fn utf8_content_type(value: &str) -> String {
+    let mut params: Vec<String> = value
+        .split(';')
+        .map(str::trim)
+        .filter(|p| !p.is_empty())
+        .map(str::to_string)
+        .collect();
+    let is_text = params
+        .first()
+        .is_some_and(|media| media.to_ascii_lowercase().starts_with("text/"));
+
+    let mut found = false;
+    for param in params.iter_mut().skip(1) {
+        let name = param.split('=').next().unwrap_or("").trim();
+        if name.eq_ignore_ascii_case("charset") {
+            *param = "charset=UTF-8".to_string();
+            found = true;
+        }
+    }
+    if !found && is_text {
+        params.push("charset=UTF-8".to_string());
+    }
+    params.join("; ")
+}
+
+/// Body lines for ARTICLE/BODY.
+fn body_lines(article: &ArticleView) -> Vec<String> {
+    article
+        .body
+        .as_deref()
+        .unwrap_or("")
+        .lines()
+        .map(|l| l.trim_end_matches('\r').to_string())
+        .collect()
+}
+
+/// Replace tabs and line breaks, which would corrupt tab-separated output.
+fn sanitize_field(value: &str) -> String {
+    value.replace(['\t', '\r', '\n'], " ")
+}
+
+/// Parse an RFC 3977 range: `n`, `n-` (to the end) or `n-m`.
+fn parse_range(arg: &str) -> Option<(u64, u64)> {
+    match arg.split_once('-') {
+        Some((low, "")) => Some((low.parse().ok()?, u64::MAX)),
+        Some((low, high)) => Some((low.parse().ok()?, high.parse().ok()?)),
+        None => {
+            let number = arg.parse().ok()?;
+            Some((number, number))
+        }
+    }
+}
+
+/// Single-line response.
+fn status(line: &str) -> String {
+    format!("{}\r\n", line)
+}
+
+/// Multi-line response: status line, dot-stuffed data lines, terminating dot.
+fn multiline(first: &str, lines: impl IntoIterator<Item = String>) -> String {
+    let mut out = status(first);
+    for line in lines {
+        if line.starts_with('.') {
+            out.push('.');
+        }
+        out.push_str(&line);
+        out.push_str("\r\n");
+    }
+    out.push_str(".\r\n");
+    out
+}
+
+/// 403 response for upstream or cache failures.
+fn internal_fault(error: &AppError) -> String {
+    tracing::warn!(%error, "NNTP reader command failed");
+    status("403 Internal fault")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn article(message_id: &str, date: &str) -> ArticleView {
+        ArticleView {
+            message_id: message_id.to_string(),
+            subject: format!("Subject of {}", message_id),
+            from: "poster@example.com".to_string(),
+            date: date.to_string(),
+            date_relative: String::new(),
+            body: None,
+            body_preview: None,
+            has_more_content: false,
+            headers: None,
+        }
+    }
+
+    fn node(message_id: &str, date: &str, replies: Vec<ThreadNodeView>) -> ThreadNodeView {
+        ThreadNodeView {
+            message_id: message_id.to_string(),
+            article: Some(article(message_id, date)),
+            descendant_count: replies.len(),
+            replies,
+        }
+    }
+
+    fn thread(root: ThreadNodeView) -> ThreadView {
+        ThreadView {
+            subject: String::new(),
+            normalized_subject: String::new(),
+            root_message_id: root.message_id.clone(),
+            article_count: 1 + root.descendant_count,
+            root,
+            last_post_date: None,
+            last_post_date_relative: None,
+            participants: Vec::new(),
+            participant_count: 0,
+            hotness: 0.0,
+            hot: false,
+        }
+    }
+
+    #[test]
+    fn test_number_articles_orders_by_date_with_references() {
+        let threads = vec![
+            thread(node(
+                "<a@x>",
+                "Mon, 1 Jan 2024 10:00:00 +0000",
+                vec![node("<c@x>", "Mon, 1 Jan 2024 12:00:00 +0000", vec![])],
+            )),
+            thread(node("<b@x>", "Mon, 1 Jan 2024 11:00:00 +0000", vec![])),
+        ];
+
+        let numbered = number_articles(&threads, &mut HashMap::new(), &mut 0);
+        let ids: Vec<_> = numbered.iter().map(|a| a.message_id.as_str()).collect();
+        assert_eq!(ids, ["<a@x>", "<b@x>", "<c@x>"]);
+        assert_eq!(numbered[0].number, 1);
+        assert_eq!(numbered[2].number, 3);
+        assert_eq!(numbered[2].references, "<a@x>");
+    }
+
+    #[test]
+    fn test_number_articles_puts_undated_articles_last() {
+        let threads = vec![
+            thread(node("<u1@x>", "not a date", vec![])),
+            thread(node("<a@x>", "Mon, 1 Jan 2024 10:00:00 +0000", vec![])),
+            thread(node("<u2@x>", "", vec![])),
+            thread(node("<b@x>", "Mon, 1 Jan 2024 09:00:00 +0000", vec![])),
+        ];
+
+        let numbered = number_articles(&threads, &mut HashMap::new(), &mut 0);
+        let ids: Vec<_> = numbered.iter().map(|a| a.message_id.as_str()).collect();
+        assert_eq!(ids, ["<b@x>", "<a@x>", "<u1@x>", "<u2@x>"]);
+    }
+
+    #[test]
+    fn test_number_articles_references_include_missing_parents() {
+        let root = ThreadNodeView {
+            message_id: "<gone@x>".to_string(),
+            article: None,
+            replies: vec![node("<reply@x>", "Mon, 1 Jan 2024 10:00:00 +0000", vec![])],
+            descendant_count: 1,
+        };
+
+        let numbered = number_articles(&[thread(root)], &mut HashMap::new(), &mut 0);
+        assert_eq!(numbered.len(), 1);
+        assert_eq!(numbered[0].references, "<gone@x>");
+    }
+
+    #[tokio::test]
+    async fn test_article_numbers_are_kept_across_refreshes() {
+        let numbers = ArticleNumbers::default();
+        let a = || thread(node("<a@x>", "Mon, 1 Jan 2024 10:00:00 +0000", vec![]));
+        let b = || thread(node("<b@x>", "Mon, 1 Jan 2024 11:00:00 +0000", vec![]));
+        numbers.number("alt.test", &[a(), b()]).await;
+
+        // A late-arriving older article is appended instead of shifting the
+        // others, and an expired one leaves a gap
+        let late = thread(node("<late@x>", "Mon, 1 Jan 2024 09:00:00 +0000", vec![]));
+        let (articles, high) = numbers.number("alt.test", &[b(), late]).await;
+        let numbered: Vec<_> = articles
+            .iter()
+            .map(|a| (a.number, a.message_id.as_str()))
+            .collect();
+        assert_eq!(numbered, [(2, "<b@x>"), (3, "<late@x>")]);
+        assert_eq!(high, 3);
+
+        // Groups are numbered independently
+        assert_eq!(numbers.number("alt.other", &[b()]).await.0[0].number, 1);
+
+        // An article that dropped out gets a new number when it returns
+        let (articles, _) = numbers.number("alt.test", &[a(), b()]).await;
+        assert_eq!(articles[1].message_id, "<a@x>");
+        assert_eq!(articles[1].number, 4);
+    }
+
+    #[tokio::test]
+    async fn test_article_numbers_are_capped() {
+        let numbers = ArticleNumbers::with_capacity(2);
+        let two = || {
+            [
+                thread(node("<a@x>", "Mon, 1 Jan 2024 10:00:00 +0000", vec![])),
+                thread(node("<b@x>", "Mon, 1 Jan 2024 11:00:00 +0000", vec![])),
+            ]
+        };
+        numbers.number("alt.one", &two()).await;
+        numbers.number("alt.two", &two()).await;
+        numbers.groups.run_pending_tasks().await;
+        assert!(numbers.groups.weighted_size() <= 2);
+
+        // An evicted group is numbered above its old high-water mark
+        let (articles, high) = numbers.number("alt.one", &two()).await;
+        assert!(articles[0].number == 1 || articles[0].number == 3);
+        assert!(high == 2 || high == 4);
+    }
+
+    #[tokio::test]
+    async fn test_active_line_matches_group_numbering() {
+        let numbers = ArticleNumbers::default();
+        let threads = vec![
+            thread(node(
+                "<a@x>",
+                "Mon, 1 Jan 2024 10:00:00 +0000",
+                vec![node("<c@x>", "Mon, 1 Jan 2024 12:00:00 +0000", vec![])],
+            )),
+            thread(node("<b@x>", "Mon, 1 Jan 2024 11:00:00 +0000", vec![])),
+        ];
+        let (articles, high) = numbers.number("alt.test", &threads).await;
+
+        assert_eq!(active_line("alt.test", &articles, high), "alt.test 3 1 n");
+        // Not cached: empty, but keeping the high-water mark
+        assert_eq!(active_line("alt.test", &[], high), "alt.test 3 4 n");
+        assert_eq!(active_line("alt.new", &[], 0), "alt.new 0 1 n");
+    }
+
+    #[test]
+    fn test_header_lines_declare_utf8_for_decoded_latin1_body() {
+        let mut latin1 = article("<l@x>", "Mon, 1 Jan 2024 10:00:00 +0000");
+        // Posted as ISO-8859-1, stored decoded
+        latin1.body = Some("Grüße aus Köln".to_string());
+        latin1.headers = Some(
+            "From: poster@example.com\r\n\
+             Content-Type: text/plain;\r\n \tcharset=\"ISO-8859-1\"; format=flowed\r\n\
+             Subject: Grüße\r\n"
+                .to_string(),
+        );
+
+        let lines = header_lines(&latin1, FromDisplay::Full);
+        assert_eq!(
+            lines,
+            [
+                "From: poster@example.com",
+                "Content-Type: text/plain; charset=UTF-8; format=flowed",
+                "Subject: Grüße",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_utf8_content_type() {
+        assert_eq!(
+            utf8_content_type(" text/plain"),
+            "text/plain; charset=UTF-8"
+        );
+        assert_eq!(
+            utf8_content_type(" text/plain; CHARSET=koi8-r"),
+            "text/plain; charset=UTF-8"
+        );
+        assert_eq!(
+            utf8_content_type(" multipart/mixed; boundary=\"b\""),
+            "multipart/mixed; boundary=\"b\""
+        );
+    }
+
+    #[test]
+    fn test_parse_range() {
+        assert_eq!(parse_range("5"), Some((5, 5)));
+        assert_eq!(parse_range("5-"), Some((5, u64::MAX)));
+        assert_eq!(parse_range("5-9"), Some((5, 9)));
+        assert_eq!(parse_range("abc"), None);
+        assert_eq!(parse_range("-9"), None);
+    }
+
+    #[test]
+    fn test_multiline_dot_stuffs_and_terminates() {
+        let response = multiline("222 body", vec![".hidden".to_string(), "text".to_string()]);
+        assert_eq!(response, "222 body\r\n..hidden\r\ntext\r\n.\r\n");
+    }
+
+    #[test]
+    fn test_overview_line_sanitizes_fields() {
+        let article = NumberedArticle {
+            number: 7,
+            message_id: "<m@x>".to_string(),
+            subject: "tab\there".to_string(),
+            from: "a@x".to_string(),
+            date: "Mon, 1 Jan 2024 10:00:00 +0000".to_string(),
+            references: "<p@x>".to_string(),
+        };
+        assert_eq!(
+            overview_line(&article, FromDisplay::Full),
+            "7\ttab here\ta@x\tMon, 1 Jan 2024 10:00:00 +0000\t<m@x>\t<p@x>\t\t"
+        );
+        assert!(overview_line(&article, FromDisplay::Masked).contains("\ta@…\t"));
+    }
+}
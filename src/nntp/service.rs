@@ -3,26 +3,36 @@
 //! Provides communication with a single NNTP server through a worker pool.
 //! Request coalescing prevents duplicate requests for the same resource.
 //! Requests are prioritized to ensure user-facing operations are processed
-//! before background tasks. Caching is handled at the federated service level.
+//! before background tasks. Background requests for a group prefer the
+//! worker that already has it selected. With `max_workers` set, the pool
+//! grows under load and shrinks again when idle. Caching is handled at the
+//! federated service level.
 
 use std::collections::HashMap;
-use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 
 use async_channel::{Receiver, Sender};
+use tokio::sync::broadcast::error::RecvError;
 use tokio::sync::{broadcast, oneshot, Mutex};
 use tracing::instrument;
 
 use nntp_rs::OverviewEntry;
 
 use crate::config::{
-    NntpServerConfig, NntpSettings, BROADCAST_CHANNEL_CAPACITY, NNTP_HIGH_PRIORITY_QUEUE_CAPACITY,
-    NNTP_LOW_PRIORITY_QUEUE_CAPACITY, NNTP_NORMAL_PRIORITY_QUEUE_CAPACITY,
+    NntpServerConfig, NntpSettings, BROADCAST_CHANNEL_CAPACITY, NNTP_AFFINITY_QUEUE_CAPACITY,
+    NNTP_HIGH_PRIORITY_QUEUE_CAPACITY, NNTP_LOW_PRIORITY_QUEUE_CAPACITY,
+    NNTP_NORMAL_PRIORITY_QUEUE_CAPACITY, NNTP_SCALED_WORKER_IDLE_SECS, NNTP_SCALE_INTERVAL_MS,
 };
-
-use super::messages::{GroupStatsView, NntpError, NntpRequest, Priority};
-use super::worker::{NntpWorker, WorkerCounters, WorkerQueues};
+use crate::deadline;
+
+use super::cancel::OnCancel;
+use super::messages::{GroupStatsView, NntpError, NntpRequest, Priority, QueuedRequest};
+use super::retry::{random_unit, RetryPolicy};
+use super::scaling::{should_grow, PoolLoad};
+use super::throttle::{Throttle, ThrottleHandle};
+use super::worker::{GroupAffinity, NntpWorker, WorkerCounters, WorkerQueues};
 use super::{ArticleView, GroupView, ThreadView};
 
 /// Pending request with timestamp for timeout checking
@@ -36,6 +46,22 @@ fn unwrap_arc<T: Clone>(arc: Arc<T>) -> T {
     Arc::try_unwrap(arc).unwrap_or_else(|arc| (*arc).clone())
 }
 
+/// Wait for a fetch started by another caller. Returns `None` if that caller
+/// was dropped before it finished, in which case the waiter fetches itself.
+async fn join_pending<T: Clone>(
+    mut rx: broadcast::Receiver<Result<T, NntpError>>,
+    budget: Duration,
+) -> Option<Result<T, NntpError>> {
+    match tokio::time::timeout(budget, rx.recv()).await {
+        Ok(Ok(result)) => Some(result),
+        Ok(Err(RecvError::Closed)) => None,
+        Ok(Err(RecvError::Lagged(_))) => Some(Err(NntpError::ConnectionLost(
+            "Broadcast channel lagged".into(),
+        ))),
+        Err(_) => Some(Err(NntpError::Timeout("Request timeout".into()))),
+    }
+}
+
 /// Pending request tracking for coalescing
 struct PendingRequests {
     articles: Mutex<HashMap<String, PendingEntry<ArticleView>>>,
@@ -52,14 +78,18 @@ pub struct NntpService {
     /// Server name for logging
     name: String,
     /// High-priority request queue (user-facing: GetArticle, PostArticle)
-    high_tx: Sender<NntpRequest>,
-    high_rx: Receiver<NntpRequest>,
+    high_tx: Sender<QueuedRequest>,
+    high_rx: Receiver<QueuedRequest>,
     /// Normal-priority request queue (page load: GetThreads, GetGroups)
-    normal_tx: Sender<NntpRequest>,
-    normal_rx: Receiver<NntpRequest>,
+    normal_tx: Sender<QueuedRequest>,
+    normal_rx: Receiver<QueuedRequest>,
     /// Low-priority request queue (background: GetGroupStats, GetNewArticles)
-    low_tx: Sender<NntpRequest>,
-    low_rx: Receiver<NntpRequest>,
+    low_tx: Sender<QueuedRequest>,
+    low_rx: Receiver<QueuedRequest>,
+    /// Per-worker affinity queues, indexed by worker id
+    affinity_queues: Arc<Vec<AffinityQueues>>,
+    /// Which worker each group's requests are routed to
+    affinity: GroupAffinity,
     /// Server configuration
     server_config: Arc<NntpServerConfig>,
     /// Global NNTP settings
@@ -72,6 +102,29 @@ pub struct NntpService {
     connected_workers: Arc<AtomicUsize>,
     /// Count of workers whose connections allow posting
     posting_workers: Arc<AtomicUsize>,
+    /// Bandwidth limit shared by all workers, if `max_kbps` is set
+    throttle: Option<Arc<Throttle>>,
+    /// Queue waits reported by workers, for scaling the pool
+    load: Arc<PoolLoad>,
+    /// Count of workers added under load that are still running
+    scaled_workers: Arc<AtomicUsize>,
+    /// Set by workers while connection attempts keep failing
+    unhealthy: Arc<AtomicBool>,
+}
+
+/// One worker's affinity queues, one per routed priority level
+struct AffinityQueues {
+    normal: (Sender<QueuedRequest>, Receiver<QueuedRequest>),
+    low: (Sender<QueuedRequest>, Receiver<QueuedRequest>),
+}
+
+impl AffinityQueues {
+    fn new() -> Self {
+        Self {
+            normal: async_channel::bounded(NNTP_AFFINITY_QUEUE_CAPACITY),
+            low: async_channel::bounded(NNTP_AFFINITY_QUEUE_CAPACITY),
+        }
+    }
 }
 
 impl NntpService {
@@ -81,9 +134,14 @@ impl NntpService {
         let (high_tx, high_rx) = async_channel::bounded(NNTP_HIGH_PRIORITY_QUEUE_CAPACITY);
         let (normal_tx, normal_rx) = async_channel::bounded(NNTP_NORMAL_PRIORITY_QUEUE_CAPACITY);
         let (low_tx, low_rx) = async_channel::bounded(NNTP_LOW_PRIORITY_QUEUE_CAPACITY);
+        let worker_count = server_config.worker_count();
+        let affinity_queues = (0..worker_count).map(|_| AffinityQueues::new()).collect();
 
         let request_timeout =
             Duration::from_secs(server_config.request_timeout_seconds(&global_settings));
+        let throttle = server_config
+            .max_kbps
+            .map(|kbps| Arc::new(Throttle::from_kbps(kbps)));
 
         Self {
             name: server_config.name.clone(),
@@ -93,6 +151,8 @@ impl NntpService {
             normal_rx,
             low_tx,
             low_rx,
+            affinity_queues: Arc::new(affinity_queues),
+            affinity: GroupAffinity::new(worker_count),
             server_config: Arc::new(server_config),
             global_settings: Arc::new(global_settings),
             request_timeout,
@@ -104,6 +164,10 @@ impl NntpService {
             }),
             connected_workers: Arc::new(AtomicUsize::new(0)),
             posting_workers: Arc::new(AtomicUsize::new(0)),
+            throttle,
+            load: Arc::new(PoolLoad::default()),
+            scaled_workers: Arc::new(AtomicUsize::new(0)),
+            unhealthy: Arc::new(AtomicBool::new(false)),
         }
     }
 
@@ -112,43 +176,211 @@ impl NntpService {
         &self.name
     }
 
-    /// Check if posting is allowed (at least one worker has a posting-capable connection)
+    /// Check if the server is reachable (at least one worker holds a live connection)
+    pub fn is_connected(&self) -> bool {
+        self.connected_workers.load(Ordering::Relaxed) > 0
+    }
+
+    /// Whether the server is accepting connections. It is unhealthy once a
+    /// worker's attempts have failed `NNTP_UNHEALTHY_AFTER_FAILURES` times
+    /// in a row with none connected, until one connects again.
+    pub fn is_healthy(&self) -> bool {
+        !self.unhealthy.load(Ordering::Relaxed)
+    }
+
+    /// Check if posting is allowed (at least one worker has a posting-capable
+    /// connection and the server isn't configured read-only)
     pub fn is_posting_allowed(&self) -> bool {
-        self.posting_workers.load(Ordering::Relaxed) > 0
+        !self.is_read_only() && self.posting_workers.load(Ordering::Relaxed) > 0
+    }
+
+    /// Whether the server is configured `read_only`: posts never go to it
+    pub fn is_read_only(&self) -> bool {
+        self.server_config.read_only
     }
 
     /// Send a request to the appropriate priority queue
-    async fn send_request(&self, request: NntpRequest) -> Result<(), NntpError> {
-        let priority = request.priority();
+    async fn send_request(&self, request: QueuedRequest) -> Result<(), NntpError> {
+        let Some(request) = self.route_to_group_worker(request).await else {
+            return Ok(());
+        };
+        let priority = request.request.priority();
         let result = match priority {
             Priority::High => self.high_tx.send(request).await,
             Priority::Normal => self.normal_tx.send(request).await,
             Priority::Low => self.low_tx.send(request).await,
         };
-        result.map_err(|_| NntpError("Worker pool closed".into()))
+        result.map_err(|_| NntpError::ConnectionLost("Worker pool closed".into()))
     }
 
-    /// Spawn worker tasks for this server
-    pub fn spawn_workers(&self) {
-        let count = self.server_config.worker_count();
-        for id in 0..count {
-            let worker = NntpWorker::new(
-                id,
-                (*self.server_config).clone(),
-                (*self.global_settings).clone(),
-                WorkerQueues {
-                    high: self.high_rx.clone(),
-                    normal: self.normal_rx.clone(),
-                    low: self.low_rx.clone(),
-                },
-                WorkerCounters {
-                    connected: self.connected_workers.clone(),
-                    posting: self.posting_workers.clone(),
+    /// Send a request and wait for the worker's answer, retrying transient
+    /// failures of idempotent reads as the request's `RetryPolicy` allows.
+    /// `make_request` builds a fresh request around each attempt's channel.
+    ///
+    /// Requests made while serving an HTTP request carry its deadline: they
+    /// are neither sent nor retried past it, and workers skip them once it
+    /// has passed while they were queued.
+    async fn call<T>(
+        &self,
+        make_request: impl Fn(oneshot::Sender<Result<T, NntpError>>) -> NntpRequest,
+    ) -> Result<T, NntpError> {
+        let deadline = deadline::current();
+        let mut attempt = 1;
+        loop {
+            let (resp_tx, resp_rx) = oneshot::channel();
+            let request = make_request(resp_tx);
+            let policy = RetryPolicy::for_request(&request);
+            let wait = match deadline {
+                Some(deadline) => match deadline.checked_duration_since(Instant::now()) {
+                    Some(remaining) => remaining.min(self.request_timeout),
+                    None => return Err(NntpError::Timeout("Request deadline passed".into())),
                 },
-            );
-            tokio::spawn(worker.run());
+                None => self.request_timeout,
+            };
+            self.send_request(QueuedRequest::new(request, deadline))
+                .await?;
+
+            let result = match tokio::time::timeout(wait, resp_rx).await {
+                Ok(Ok(result)) => result,
+                Ok(Err(_)) => Err(NntpError::ConnectionLost("Worker dropped request".into())),
+                Err(_) => Err(NntpError::Timeout("Request timeout".into())),
+            };
+            match result {
+                Err(e) if policy.should_retry(attempt, &e) => {
+                    let delay = policy.delay(attempt, random_unit());
+                    tracing::debug!(
+                        server = %self.name,
+                        attempt,
+                        error_kind = e.kind(),
+                        error = %e,
+                        delay_ms = delay.as_millis() as u64,
+                        "Retrying NNTP request"
+                    );
+                    tokio::time::sleep(delay).await;
+                    attempt += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+
+    /// How long a coalesced caller waits for the in-flight request it joined,
+    /// covering all of its attempts
+    fn wait_budget(&self, priority: Priority) -> Duration {
+        RetryPolicy::for_reads(priority).budget(self.request_timeout)
+    }
+
+    /// Hand a group request to the worker that serves the group: the one that
+    /// has it selected, or else its home on the consistent hash ring.
+    ///
+    /// Returns the request when no worker is live or the chosen worker's
+    /// affinity queue is full, so it goes to the shared queue instead. A
+    /// thread list someone is waiting for only goes to an idle worker with
+    /// nothing queued, as no other worker takes from its affinity queue.
+    async fn route_to_group_worker(&self, request: QueuedRequest) -> Option<QueuedRequest> {
+        let Some(group) = request.request.affinity_group() else {
+            return Some(request);
+        };
+        let Some(worker) = self.affinity.route(group).await else {
+            return Some(request);
+        };
+        let Some(queues) = self.affinity_queues.get(worker) else {
+            return Some(request);
+        };
+        let (tx, _) = match request.request.priority() {
+            Priority::Low => &queues.low,
+            _ if !self.affinity.is_idle(worker) || !queues.normal.0.is_empty() => {
+                return Some(request);
+            }
+            _ => &queues.normal,
+        };
+        match tx.try_send(request) {
+            Ok(()) => {
+                tracing::trace!(server = %self.name, worker, "Routed request by group affinity");
+                None
+            }
+            Err(e) => Some(e.into_inner()),
+        }
+    }
+
+    /// Spawn worker tasks for this server, and the controller that adds
+    /// workers under load if `max_workers` allows more
+    pub fn spawn_workers(&self) {
+        let count = self.affinity_queues.len();
+        for (id, affinity) in self.affinity_queues.iter().enumerate() {
+            tokio::spawn(self.new_worker(id, affinity).run());
         }
         tracing::info!(server = %self.name, count, "Spawned NNTP workers");
+
+        let max = self.server_config.max_worker_count();
+        if max > count {
+            tokio::spawn(self.clone().scale_workers(max));
+            tracing::info!(server = %self.name, max, "NNTP worker pool may grow under load");
+        }
+    }
+
+    /// Worker `id` taking requests from the shared queues and `affinity`
+    fn new_worker(&self, id: usize, affinity: &AffinityQueues) -> NntpWorker {
+        NntpWorker::new(
+            id,
+            (*self.server_config).clone(),
+            (*self.global_settings).clone(),
+            WorkerQueues {
+                high: self.high_rx.clone(),
+                normal: self.normal_rx.clone(),
+                low: self.low_rx.clone(),
+                affinity_normal: affinity.normal.1.clone(),
+                affinity_low: affinity.low.1.clone(),
+            },
+            WorkerCounters {
+                connected: self.connected_workers.clone(),
+                posting: self.posting_workers.clone(),
+                load: self.load.clone(),
+                unhealthy: self.unhealthy.clone(),
+            },
+            self.affinity.clone(),
+            self.throttle.clone().map(ThrottleHandle::new),
+        )
+    }
+
+    /// Add workers, up to `max` in all, while requests back up in the
+    /// shared queues. Added workers retire themselves when idle.
+    async fn scale_workers(self, max: usize) {
+        let fixed = self.affinity_queues.len();
+        let mut next_id = fixed;
+        let mut interval = tokio::time::interval(Duration::from_millis(NNTP_SCALE_INTERVAL_MS));
+        loop {
+            interval.tick().await;
+            let queued = self.high_tx.len() + self.normal_tx.len() + self.low_tx.len();
+            let max_wait = self.load.take_max_wait();
+            let workers = fixed + self.scaled_workers.load(Ordering::Relaxed);
+            // More connections won't help a server that can't be reached
+            if !self.is_connected() || !should_grow(queued, max_wait, workers, max) {
+                continue;
+            }
+
+            // Nothing is routed to an added worker, but its affinity queues
+            // must stay open for as long as it runs
+            let affinity = AffinityQueues::new();
+            let worker = self
+                .new_worker(next_id, &affinity)
+                .retire_when_idle(Duration::from_secs(NNTP_SCALED_WORKER_IDLE_SECS));
+            let scaled_workers = self.scaled_workers.clone();
+            scaled_workers.fetch_add(1, Ordering::Relaxed);
+            tokio::spawn(async move {
+                worker.run().await;
+                drop(affinity);
+                scaled_workers.fetch_sub(1, Ordering::Relaxed);
+            });
+            tracing::info!(
+                server = %self.name,
+                workers = workers + 1,
+                queued,
+                max_wait_ms = max_wait.as_millis() as u64,
+                "Added NNTP worker under load"
+            );
+            next_id += 1;
+        }
     }
 
     /// Fetch an article by message ID
@@ -159,19 +391,20 @@ impl NntpService {
     )]
     pub async fn get_article(&self, message_id: &str) -> Result<ArticleView, NntpError> {
         let start = Instant::now();
+        let budget = self.wait_budget(Priority::High);
         // Check for pending request (coalesce if not timed out)
         let mut pending = self.pending.articles.lock().await;
         if let Some((tx, started_at)) = pending.get(message_id) {
-            if started_at.elapsed() < self.request_timeout {
-                let mut rx = tx.subscribe();
+            if started_at.elapsed() < budget {
+                let rx = tx.subscribe();
                 drop(pending); // Release lock while waiting
                 tracing::Span::current().record("coalesced", true);
 
-                return match tokio::time::timeout(self.request_timeout, rx.recv()).await {
-                    Ok(Ok(result)) => result,
-                    Ok(Err(_)) => Err(NntpError("Broadcast channel closed".into())),
-                    Err(_) => Err(NntpError("Request timeout".into())),
-                };
+                if let Some(result) = join_pending(rx, budget).await {
+                    return result;
+                }
+                // The caller we joined went away; fetch it ourselves
+                pending = self.pending.articles.lock().await;
             } else {
                 // Pending request timed out, remove it and start fresh
                 tracing::debug!(server = %self.name, %message_id, "Pending request timed out, starting new request");
@@ -183,30 +416,49 @@ impl NntpService {
         let (tx, _) = broadcast::channel(BROADCAST_CHANNEL_CAPACITY);
         pending.insert(message_id.to_string(), (tx.clone(), Instant::now()));
         drop(pending);
-
-        let (resp_tx, resp_rx) = oneshot::channel();
-        self.send_request(NntpRequest::GetArticle {
-            message_id: message_id.to_string(),
-            response: resp_tx,
-        })
-        .await?;
-
-        // Wait for result with timeout
-        let result = match tokio::time::timeout(self.request_timeout, resp_rx).await {
-            Ok(Ok(result)) => result,
-            Ok(Err(_)) => Err(NntpError("Worker dropped request".into())),
-            Err(_) => Err(NntpError("Request timeout".into())),
+        let cancel = {
+            let pending = self.pending.clone();
+            let message_id = message_id.to_string();
+            OnCancel::new(async move {
+                pending.articles.lock().await.remove(&message_id);
+            })
         };
 
+        let result = self
+            .call(|response| NntpRequest::GetArticle {
+                message_id: message_id.to_string(),
+                prefetch: false,
+                response,
+            })
+            .await;
+
         // Broadcast to waiters and cleanup pending in one lock acquisition
         // Remove first to minimize time holding lock, then broadcast
         self.pending.articles.lock().await.remove(message_id);
+        cancel.disarm();
         let _ = tx.send(result.clone());
 
         tracing::Span::current().record("duration_ms", start.elapsed().as_millis() as u64);
         result
     }
 
+    /// Fetch an article at low priority to warm the cache, e.g. for the next
+    /// page of a thread. Not coalesced with `get_article`: a reader waiting
+    /// for it must not join a request sitting in the low queue.
+    #[instrument(
+        name = "nntp.service.prefetch_article",
+        skip(self),
+        fields(server = %self.name)
+    )]
+    pub async fn prefetch_article(&self, message_id: &str) -> Result<ArticleView, NntpError> {
+        self.call(|response| NntpRequest::GetArticle {
+            message_id: message_id.to_string(),
+            prefetch: true,
+            response,
+        })
+        .await
+    }
+
     /// Fetch recent threads from a newsgroup
     #[instrument(
         name = "nntp.service.get_threads",
@@ -215,21 +467,21 @@ impl NntpService {
     )]
     pub async fn get_threads(&self, group: &str, count: u64) -> Result<Vec<ThreadView>, NntpError> {
         let start = Instant::now();
+        let budget = self.wait_budget(Priority::Normal);
         let cache_key = format!("{}:{}", group, count);
 
         // Check for pending request (coalesce if not timed out)
         let mut pending = self.pending.threads.lock().await;
         if let Some((tx, started_at)) = pending.get(&cache_key) {
-            if started_at.elapsed() < self.request_timeout {
-                let mut rx = tx.subscribe();
+            if started_at.elapsed() < budget {
+                let rx = tx.subscribe();
                 drop(pending);
                 tracing::Span::current().record("coalesced", true);
 
-                return match tokio::time::timeout(self.request_timeout, rx.recv()).await {
-                    Ok(Ok(result)) => result.map(unwrap_arc),
-                    Ok(Err(_)) => Err(NntpError("Broadcast channel closed".into())),
-                    Err(_) => Err(NntpError("Request timeout".into())),
-                };
+                if let Some(result) = join_pending(rx, budget).await {
+                    return result.map(unwrap_arc);
+                }
+                pending = self.pending.threads.lock().await;
             } else {
                 tracing::debug!(server = %self.name, %group, %count, "Pending request timed out, starting new request");
                 pending.remove(&cache_key);
@@ -240,24 +492,25 @@ impl NntpService {
         let (tx, _) = broadcast::channel(BROADCAST_CHANNEL_CAPACITY);
         pending.insert(cache_key.clone(), (tx.clone(), Instant::now()));
         drop(pending);
-
-        let (resp_tx, resp_rx) = oneshot::channel();
-        self.send_request(NntpRequest::GetThreads {
-            group: group.to_string(),
-            count,
-            response: resp_tx,
-        })
-        .await?;
-
-        // Wait for result with timeout
-        let result = match tokio::time::timeout(self.request_timeout, resp_rx).await {
-            Ok(Ok(result)) => result,
-            Ok(Err(_)) => Err(NntpError("Worker dropped request".into())),
-            Err(_) => Err(NntpError("Request timeout".into())),
+        let cancel = {
+            let pending = self.pending.clone();
+            let cache_key = cache_key.clone();
+            OnCancel::new(async move {
+                pending.threads.lock().await.remove(&cache_key);
+            })
         };
 
+        let result = self
+            .call(|response| NntpRequest::GetThreads {
+                group: group.to_string(),
+                count,
+                response,
+            })
+            .await;
+
         // Broadcast Arc-wrapped result to waiters, then cleanup pending
         self.pending.threads.lock().await.remove(&cache_key);
+        cancel.disarm();
         let _ = tx.send(
             result
                 .as_ref()
@@ -277,19 +530,19 @@ impl NntpService {
     )]
     pub async fn get_groups(&self) -> Result<Vec<GroupView>, NntpError> {
         let start = Instant::now();
+        let budget = self.wait_budget(Priority::Normal);
         // Check for pending request (coalesce if not timed out)
         let mut pending = self.pending.groups.lock().await;
         if let Some((tx, started_at)) = pending.as_ref() {
-            if started_at.elapsed() < self.request_timeout {
-                let mut rx = tx.subscribe();
+            if started_at.elapsed() < budget {
+                let rx = tx.subscribe();
                 drop(pending);
                 tracing::Span::current().record("coalesced", true);
 
-                return match tokio::time::timeout(self.request_timeout, rx.recv()).await {
-                    Ok(Ok(result)) => result.map(unwrap_arc),
-                    Ok(Err(_)) => Err(NntpError("Broadcast channel closed".into())),
-                    Err(_) => Err(NntpError("Request timeout".into())),
-                };
+                if let Some(result) = join_pending(rx, budget).await {
+                    return result.map(unwrap_arc);
+                }
+                pending = self.pending.groups.lock().await;
             } else {
                 tracing::debug!(server = %self.name, "Pending groups request timed out, starting new request");
                 *pending = None;
@@ -300,20 +553,20 @@ impl NntpService {
         let (tx, _) = broadcast::channel(BROADCAST_CHANNEL_CAPACITY);
         *pending = Some((tx.clone(), Instant::now()));
         drop(pending);
-
-        let (resp_tx, resp_rx) = oneshot::channel();
-        self.send_request(NntpRequest::GetGroups { response: resp_tx })
-            .await?;
-
-        // Wait for result with timeout
-        let result = match tokio::time::timeout(self.request_timeout, resp_rx).await {
-            Ok(Ok(result)) => result,
-            Ok(Err(_)) => Err(NntpError("Worker dropped request".into())),
-            Err(_) => Err(NntpError("Request timeout".into())),
+        let cancel = {
+            let pending = self.pending.clone();
+            OnCancel::new(async move {
+                *pending.groups.lock().await = None;
+            })
         };
 
+        let result = self
+            .call(|response| NntpRequest::GetGroups { response })
+            .await;
+
         // Broadcast Arc-wrapped result to waiters, then cleanup pending
         *self.pending.groups.lock().await = None;
+        cancel.disarm();
         let _ = tx.send(
             result
                 .as_ref()
@@ -333,19 +586,19 @@ impl NntpService {
     )]
     pub async fn get_group_stats(&self, group: &str) -> Result<GroupStatsView, NntpError> {
         let start = Instant::now();
+        let budget = self.wait_budget(Priority::Low);
         // Check for pending request (coalesce if not timed out)
         let mut pending = self.pending.group_stats.lock().await;
         if let Some((tx, started_at)) = pending.get(group) {
-            if started_at.elapsed() < self.request_timeout {
-                let mut rx = tx.subscribe();
+            if started_at.elapsed() < budget {
+                let rx = tx.subscribe();
                 drop(pending);
                 tracing::Span::current().record("coalesced", true);
 
-                return match tokio::time::timeout(self.request_timeout, rx.recv()).await {
-                    Ok(Ok(result)) => result,
-                    Ok(Err(_)) => Err(NntpError("Broadcast channel closed".into())),
-                    Err(_) => Err(NntpError("Request timeout".into())),
-                };
+                if let Some(result) = join_pending(rx, budget).await {
+                    return result;
+                }
+                pending = self.pending.group_stats.lock().await;
             } else {
                 tracing::debug!(server = %self.name, %group, "Pending group stats request timed out, starting new request");
                 pending.remove(group);
@@ -356,24 +609,25 @@ impl NntpService {
         let (tx, _) = broadcast::channel(BROADCAST_CHANNEL_CAPACITY);
         pending.insert(group.to_string(), (tx.clone(), Instant::now()));
         drop(pending);
-
-        let (resp_tx, resp_rx) = oneshot::channel();
-        self.send_request(NntpRequest::GetGroupStats {
-            group: group.to_string(),
-            response: resp_tx,
-        })
-        .await?;
-
-        // Wait for result with timeout
-        let result = match tokio::time::timeout(self.request_timeout, resp_rx).await {
-            Ok(Ok(result)) => result,
-            Ok(Err(_)) => Err(NntpError("Worker dropped request".into())),
-            Err(_) => Err(NntpError("Request timeout".into())),
+        let cancel = {
+            let pending = self.pending.clone();
+            let group = group.to_string();
+            OnCancel::new(async move {
+                pending.group_stats.lock().await.remove(&group);
+            })
         };
 
+        let result = self
+            .call(|response| NntpRequest::GetGroupStats {
+                group: group.to_string(),
+                response,
+            })
+            .await;
+
         // Broadcast to waiters and cleanup pending in one lock acquisition
         // Remove first to minimize time holding lock, then broadcast
         self.pending.group_stats.lock().await.remove(group);
+        cancel.disarm();
         let _ = tx.send(result.clone());
 
         tracing::Span::current().record("duration_ms", start.elapsed().as_millis() as u64);
@@ -393,23 +647,16 @@ impl NntpService {
         since_article_number: u64,
     ) -> Result<Vec<OverviewEntry>, NntpError> {
         let start = Instant::now();
-        let (resp_tx, resp_rx) = oneshot::channel();
-        self.send_request(NntpRequest::GetNewArticles {
-            group: group.to_string(),
-            since_article_number,
-            response: resp_tx,
-        })
-        .await?;
+        let result = self
+            .call(|response| NntpRequest::GetNewArticles {
+                group: group.to_string(),
+                since_article_number,
+                response,
+            })
+            .await;
 
-        // Wait for result with timeout
-        match tokio::time::timeout(self.request_timeout, resp_rx).await {
-            Ok(Ok(result)) => {
-                tracing::Span::current().record("duration_ms", start.elapsed().as_millis() as u64);
-                result
-            }
-            Ok(Err(_)) => Err(NntpError("Worker dropped request".into())),
-            Err(_) => Err(NntpError("Request timeout".into())),
-        }
+        tracing::Span::current().record("duration_ms", start.elapsed().as_millis() as u64);
+        result
     }
 
     /// Post an article to the server
@@ -425,20 +672,14 @@ impl NntpService {
     ) -> Result<(), NntpError> {
         let start = Instant::now();
 
-        let (resp_tx, resp_rx) = oneshot::channel();
-        self.send_request(NntpRequest::PostArticle {
-            headers,
-            body,
-            response: resp_tx,
-        })
-        .await?;
-
-        // Wait for result with timeout
-        let result = match tokio::time::timeout(self.request_timeout, resp_rx).await {
-            Ok(Ok(result)) => result,
-            Ok(Err(_)) => Err(NntpError("Worker dropped request".into())),
-            Err(_) => Err(NntpError("Request timeout".into())),
-        };
+        // Posts are not idempotent, so this is a single attempt
+        let result = self
+            .call(|response| NntpRequest::PostArticle {
+                headers: headers.clone(),
+                body: body.clone(),
+                response,
+            })
+            .await;
 
         tracing::Span::current().record("duration_ms", start.elapsed().as_millis() as u64);
         result
@@ -457,19 +698,12 @@ impl NntpService {
     pub async fn check_article_exists(&self, message_id: &str) -> Result<bool, NntpError> {
         let start = Instant::now();
 
-        let (resp_tx, resp_rx) = oneshot::channel();
-        self.send_request(NntpRequest::CheckArticleExists {
-            message_id: message_id.to_string(),
-            response: resp_tx,
-        })
-        .await?;
-
-        // Wait for result with timeout
-        let result = match tokio::time::timeout(self.request_timeout, resp_rx).await {
-            Ok(Ok(result)) => result,
-            Ok(Err(_)) => Err(NntpError("Worker dropped request".into())),
-            Err(_) => Err(NntpError("Request timeout".into())),
-        };
+        let result = self
+            .call(|response| NntpRequest::CheckArticleExists {
+                message_id: message_id.to_string(),
+                response,
+            })
+            .await;
 
         tracing::Span::current().record("duration_ms", start.elapsed().as_millis() as u64);
         result
@@ -0,0 +1,285 @@
+//! SOCKS5 client for reaching NNTP servers through a proxy (RFC 1928).
+//!
+//! Servers with `proxy` set are dialled through it: the TCP connection goes
+//! to the proxy, and a CONNECT request asks it for the NNTP server. Host
+//! names are handed to the proxy unresolved, so nothing about the server is
+//! looked up locally and a Tor proxy can reach `.onion` addresses. A proxy
+//! that wants a username and password gets them as RFC 1929 describes. The
+//! TLS handshake then runs through the tunnel as on a direct connection.
+
+use std::io;
+use std::net::IpAddr;
+
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::net::TcpStream;
+
+use crate::config::ProxyConfig;
+
+use super::dial;
+
+const SOCKS_VERSION: u8 = 5;
+const METHOD_NO_AUTH: u8 = 0x00;
+const METHOD_USER_PASS: u8 = 0x02;
+/// Version of the RFC 1929 username/password exchange
+const USER_PASS_VERSION: u8 = 1;
+const CMD_CONNECT: u8 = 1;
+const ATYP_IPV4: u8 = 1;
+const ATYP_DOMAIN: u8 = 3;
+const ATYP_IPV6: u8 = 4;
+
+/// Connect to `addr` (`host:port`, IPv6 literals in brackets) through `proxy`
+pub async fn connect(proxy: &ProxyConfig, addr: &str) -> io::Result<TcpStream> {
+    let (host, port) = dial::split_host_port(addr)?;
+    let mut stream = dial::connect(&proxy.address()).await?;
+    handshake(&mut stream, host, port, proxy.credentials()).await?;
+    tracing::debug!(proxy = %proxy.address(), %addr, "Connected through SOCKS5 proxy");
+    Ok(stream)
+}
+
+/// Negotiate authentication and ask the proxy to connect to `host:port`
+async fn handshake<S: AsyncRead + AsyncWrite + Unpin>(
+    stream: &mut S,
+    host: &str,
+    port: u16,
+    credentials: Option<(&str, &str)>,
+) -> io::Result<()> {
+    // Offer username/password only when we have them
+    let greeting: &[u8] = match credentials {
+        Some(_) => &[SOCKS_VERSION, 2, METHOD_NO_AUTH, METHOD_USER_PASS],
+        None => &[SOCKS_VERSION, 1, METHOD_NO_AUTH],
+    };
+    stream.write_all(greeting).await?;
+
+    let mut choice = [0u8; 2];
+    stream.read_exact(&mut choice).await?;
+    if choice[0] != SOCKS_VERSION {
+        return Err(protocol_error("proxy is not a SOCKS5 proxy"));
+    }
+    match (choice[1], credentials) {
+        (METHOD_NO_AUTH, _) => {}
+        (METHOD_USER_PASS, Some((username, password))) => {
+            authenticate(stream, username, password).await?
+        }
+        _ => {
+            return Err(io::Error::new(
+                io::ErrorKind::PermissionDenied,
+                "SOCKS5 proxy accepted none of the offered authentication methods",
+            ))
+        }
+    }
+
+    stream.write_all(&connect_request(host, port)?).await?;
+    read_reply(stream).await
+}
+
+/// RFC 1929 username/password exchange
+async fn authenticate<S: AsyncRead + AsyncWrite + Unpin>(
+    stream: &mut S,
+    username: &str,
+    password: &str,
+) -> io::Result<()> {
+    // Lengths are checked when the configuration is loaded
+    let mut request = Vec::with_capacity(3 + username.len() + password.len());
+    request.push(USER_PASS_VERSION);
+    request.push(username.len() as u8);
+    request.extend_from_slice(username.as_bytes());
+    request.push(password.len() as u8);
+    request.extend_from_slice(password.as_bytes());
+    stream.write_all(&request).await?;
+
+    let mut status = [0u8; 2];
+    stream.read_exact(&mut status).await?;
+    if status[1] != 0 {
+        return Err(io::Error::new(
+            io::ErrorKind::PermissionDenied,
+            "SOCKS5 proxy rejected the username or password",
+        ));
+    }
+    Ok(())
+}
+
+/// CONNECT request for `host:port`: IP literals as addresses, anything else
+/// as a domain name for the proxy to resolve
+fn connect_request(host: &str, port: u16) -> io::Result<Vec<u8>> {
+    let mut request = vec![SOCKS_VERSION, CMD_CONNECT, 0];
+    match host.parse::<IpAddr>() {
+        Ok(IpAddr::V4(ip)) => {
+            request.push(ATYP_IPV4);
+            request.extend_from_slice(&ip.octets());
+        }
+        Ok(IpAddr::V6(ip)) => {
+            request.push(ATYP_IPV6);
+            request.extend_from_slice(&ip.octets());
+        }
+        Err(_) => {
+            let len = u8::try_from(host.len())
+                .ok()
+                .filter(|len| *len > 0)
+                .ok_or_else(|| {
+                    io::Error::new(
+                        io::ErrorKind::InvalidInput,
+                        format!("host name {} can't be sent to a SOCKS5 proxy", host),
+                    )
+                })?;
+            request.push(ATYP_DOMAIN);
+            request.push(len);
+            request.extend_from_slice(host.as_bytes());
+        }
+    }
+    request.extend_from_slice(&port.to_be_bytes());
+    Ok(request)
+}
+
+/// Read the reply to CONNECT, failing unless the proxy connected
+async fn read_reply<S: AsyncRead + Unpin>(stream: &mut S) -> io::Result<()> {
+    let mut header = [0u8; 4];
+    stream.read_exact(&mut header).await?;
+    if header[0] != SOCKS_VERSION {
+        return Err(protocol_error("proxy is not a SOCKS5 proxy"));
+    }
+    if header[1] != 0 {
+        let kind = match header[1] {
+            0x05 => io::ErrorKind::ConnectionRefused,
+            _ => io::ErrorKind::Other,
+        };
+        return Err(io::Error::new(
+            kind,
+            format!(
+                "SOCKS5 proxy could not connect: {}",
+                reply_message(header[1])
+            ),
+        ));
+    }
+
+    // The address the proxy bound for us is of no use; read past it
+    let address_len = match header[3] {
+        ATYP_IPV4 => 4,
+        ATYP_IPV6 => 16,
+        ATYP_DOMAIN => {
+            let mut len = [0u8; 1];
+            stream.read_exact(&mut len).await?;
+            usize::from(len[0])
+        }
+        _ => return Err(protocol_error("unknown address type in SOCKS5 reply")),
+    };
+    let mut bound = vec![0u8; address_len + 2];
+    stream.read_exact(&mut bound).await?;
+    Ok(())
+}
+
+/// Meaning of a failure code in a CONNECT reply (RFC 1928 section 6)
+fn reply_message(code: u8) -> &'static str {
+    match code {
+        0x01 => "general failure",
+        0x02 => "connection not allowed by ruleset",
+        0x03 => "network unreachable",
+        0x04 => "host unreachable",
+        0x05 => "connection refused",
+        0x06 => "TTL expired",
+        0x07 => "command not supported",
+        0x08 => "address type not supported",
+        _ => "unknown error",
+    }
+}
+
+fn protocol_error(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::duplex;
+
+    /// Play the proxy's side: expect each request in turn and send its reply
+    async fn proxy(
+        mut stream: tokio::io::DuplexStream,
+        exchanges: Vec<(Vec<u8>, Vec<u8>)>,
+    ) -> io::Result<()> {
+        for (expected, reply) in exchanges {
+            let mut request = vec![0u8; expected.len()];
+            stream.read_exact(&mut request).await?;
+            assert_eq!(request, expected);
+            stream.write_all(&reply).await?;
+        }
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_handshake_sends_host_name_unresolved() {
+        let (mut client, server) = duplex(256);
+        let mut connect = vec![SOCKS_VERSION, CMD_CONNECT, 0, ATYP_DOMAIN, 16];
+        connect.extend_from_slice(b"news.example.com");
+        connect.extend_from_slice(&119u16.to_be_bytes());
+        let proxy = tokio::spawn(proxy(
+            server,
+            vec![
+                (
+                    vec![SOCKS_VERSION, 1, METHOD_NO_AUTH],
+                    vec![SOCKS_VERSION, METHOD_NO_AUTH],
+                ),
+                (
+                    connect,
+                    vec![SOCKS_VERSION, 0, 0, ATYP_IPV4, 10, 0, 0, 1, 0x1f, 0x90],
+                ),
+            ],
+        ));
+
+        handshake(&mut client, "news.example.com", 119, None)
+            .await
+            .unwrap();
+        proxy.await.unwrap().unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_handshake_authenticates_and_reports_failure() {
+        let (mut client, server) = duplex(256);
+        let mut connect = vec![SOCKS_VERSION, CMD_CONNECT, 0, ATYP_IPV6];
+        connect.extend_from_slice(
+            &"2001:db8::1"
+                .parse::<std::net::Ipv6Addr>()
+                .unwrap()
+                .octets(),
+        );
+        connect.extend_from_slice(&563u16.to_be_bytes());
+        let proxy = tokio::spawn(proxy(
+            server,
+            vec![
+                (
+                    vec![SOCKS_VERSION, 2, METHOD_NO_AUTH, METHOD_USER_PASS],
+                    vec![SOCKS_VERSION, METHOD_USER_PASS],
+                ),
+                (
+                    b"\x01\x04user\x06secret".to_vec(),
+                    vec![USER_PASS_VERSION, 0],
+                ),
+                (connect, vec![SOCKS_VERSION, 0x05, 0, ATYP_IPV4]),
+            ],
+        ));
+
+        let err = handshake(&mut client, "2001:db8::1", 563, Some(("user", "secret")))
+            .await
+            .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::ConnectionRefused);
+        assert!(err.to_string().contains("connection refused"));
+        proxy.await.unwrap().unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_handshake_rejects_unoffered_method() {
+        let (mut client, server) = duplex(256);
+        let proxy = tokio::spawn(proxy(
+            server,
+            vec![(
+                vec![SOCKS_VERSION, 1, METHOD_NO_AUTH],
+                vec![SOCKS_VERSION, METHOD_USER_PASS],
+            )],
+        ));
+
+        let err = handshake(&mut client, "news.example.com", 119, None)
+            .await
+            .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::PermissionDenied);
+        proxy.await.unwrap().unwrap();
+    }
+}
@@ -0,0 +1,336 @@
+//! Subject normalization and subject-based thread grouping.
+//!
+//! Thread lists are cluttered by "Re:", "AW:", "SV:" and "[list-tag]"
+//! prefixes, and by replies from clients that drop the References header,
+//! which show up as new threads. `SubjectRules` strips the configured
+//! prefixes for display and folds such replies into the thread they answer.
+
+use std::collections::HashMap;
+
+use super::{parse_date, ThreadView};
+use crate::config::SubjectConfig;
+
+/// Configured subject normalization rules
+#[derive(Debug, Clone)]
+pub struct SubjectRules {
+    reply_prefixes: Vec<String>,
+    strip_tags: bool,
+    group_by_subject: bool,
+}
+
+impl SubjectRules {
+    /// Build rules from configuration. Empty prefixes are ignored.
+    pub fn new(config: &SubjectConfig) -> Self {
+        Self {
+            reply_prefixes: config
+                .reply_prefixes
+                .iter()
+                .map(|p| p.trim().to_string())
+                .filter(|p| !p.is_empty())
+                .collect(),
+            strip_tags: config.strip_tags,
+            group_by_subject: config.group_by_subject,
+        }
+    }
+
+    /// Strip reply prefixes and list tags from the start of a subject.
+    /// Returns the subject unchanged if nothing would be left.
+    pub fn normalize<'a>(&self, subject: &'a str) -> &'a str {
+        self.strip(subject).0
+    }
+
+    /// Whether the subject carries a reply prefix (after any list tags)
+    pub fn is_reply(&self, subject: &str) -> bool {
+        self.strip(subject).1
+    }
+
+    /// Fill in `normalized_subject` on a thread
+    pub fn apply(&self, thread: &mut ThreadView) {
+        thread.normalized_subject = self.normalize(&thread.subject).to_string();
+    }
+
+    /// Normalize every thread in a list and, if enabled, fold reply-looking
+    /// roots into the thread with the same normalized subject.
+    ///
+    /// A root is folded only when exactly one non-reply thread has its
+    /// normalized subject and that thread started no later than the reply.
+    pub fn group_threads(&self, mut threads: Vec<ThreadView>) -> Vec<ThreadView> {
+        for thread in &mut threads {
+            self.apply(thread);
+        }
+        if !self.group_by_subject {
+            return threads;
+        }
+
+        // Normalized subject -> index of the single original thread (None if ambiguous)
+        let mut originals: HashMap<String, Option<usize>> = HashMap::new();
+        for (i, thread) in threads.iter().enumerate() {
+            if thread.root.article.is_some() && !self.is_reply(&thread.subject) {
+                originals
+                    .entry(thread.normalized_subject.to_lowercase())
+                    .and_modify(|slot| *slot = None)
+                    .or_insert(Some(i));
+            }
+        }
+
+        let mut folds: Vec<(usize, usize)> = Vec::new();
+        for (i, thread) in threads.iter().enumerate() {
+            if thread.root.article.is_none() || !self.is_reply(&thread.subject) {
+                continue;
+            }
+            let key = thread.normalized_subject.to_lowercase();
+            if let Some(Some(target)) = originals.get(&key) {
+                if started_no_later(&threads[*target], thread) {
+                    folds.push((i, *target));
+                }
+            }
+        }
+        if folds.is_empty() {
+            return threads;
+        }
+
+        let mut slots: Vec<Option<ThreadView>> = threads.into_iter().map(Some).collect();
+        for (orphan, target) in folds {
+            if let Some(orphan) = slots[orphan].take() {
+                if let Some(target) = slots[target].as_mut() {
+                    fold_into(target, orphan);
+                }
+            }
+        }
+        slots.into_iter().flatten().collect()
+    }
+
+    /// Fold matching reply-looking roots from `candidates` into a single thread.
+    /// Used for the thread view, so it shows what the listing counted.
+    pub fn attach_orphans(&self, thread: &mut ThreadView, candidates: &[ThreadView]) {
+        self.apply(thread);
+        if !self.group_by_subject || thread.root.article.is_none() || self.is_reply(&thread.subject)
+        {
+            return;
+        }
+
+        let key = thread.normalized_subject.to_lowercase();
+        let matches_key = |t: &ThreadView| {
+            t.root.article.is_some()
+                && self.normalize(&t.subject).to_lowercase() == key
+                && t.root_message_id != thread.root_message_id
+        };
+
+        // Same ambiguity rule as group_threads: another original means no folding
+        if candidates
+            .iter()
+            .any(|t| matches_key(t) && !self.is_reply(&t.subject))
+        {
+            return;
+        }
+
+        let orphans: Vec<ThreadView> = candidates
+            .iter()
+            .filter(|t| matches_key(t) && self.is_reply(&t.subject))
+            .filter(|t| started_no_later(thread, t))
+            .filter(|t| !thread.root.contains_message_id(&t.root_message_id))
+            .cloned()
+            .collect();
+        for orphan in orphans {
+            fold_into(thread, orphan);
+        }
+    }
+
+    /// Returns (normalized subject, whether a reply prefix was found)
+    fn strip<'a>(&self, subject: &'a str) -> (&'a str, bool) {
+        let mut rest = subject.trim();
+        let mut replied = false;
+        loop {
+            if self.strip_tags && rest.starts_with('[') {
+                if let Some(end) = rest.find(']') {
+                    rest = rest[end + 1..].trim_start();
+                    continue;
+                }
+            }
+            if let Some(after) = self.strip_reply_prefix(rest) {
+                rest = after.trim_start();
+                replied = true;
+                continue;
+            }
+            break;
+        }
+
+        if rest.is_empty() {
+            (subject.trim(), replied)
+        } else {
+            (rest, replied)
+        }
+    }
+
+    /// Strip one reply prefix such as "Re:", "re :", "Re[2]:" or "Re^2:"
+    fn strip_reply_prefix<'a>(&self, subject: &'a str) -> Option<&'a str> {
+        self.reply_prefixes.iter().find_map(|prefix| {
+            let head = subject.get(..prefix.len())?;
+            if !head.eq_ignore_ascii_case(prefix) {
+                return None;
+            }
+            let mut after = &subject[prefix.len()..];
+            // Optional reply counter
+            if let Some(counter) = after.strip_prefix('^') {
+                after = counter.trim_start_matches(|c: char| c.is_ascii_digit());
+            } else if let Some(counter) = after.strip_prefix(['[', '(']) {
+                let digits = counter.trim_start_matches(|c: char| c.is_ascii_digit());
+                after = digits.strip_prefix([']', ')'])?;
+            }
+            after.trim_start().strip_prefix(':')
+        })
+    }
+}
+
+/// Whether `original` was started no later than `reply` (unknown dates pass)
+fn started_no_later(original: &ThreadView, reply: &ThreadView) -> bool {
+    let date = |t: &ThreadView| t.root.article.as_ref().and_then(|a| parse_date(&a.date));
+    match (date(original), date(reply)) {
+        (Some(original), Some(reply)) => original <= reply,
+        _ => true,
+    }
+}
+
+/// Attach a folded thread's tree as a reply to the target thread's root
+fn fold_into(target: &mut ThreadView, orphan: ThreadView) {
+    target.article_count += orphan.article_count;
+    target.root.descendant_count += orphan.article_count;
+
+    let newer = match (
+        target.last_post_date.as_deref().and_then(parse_date),
+        orphan.last_post_date.as_deref().and_then(parse_date),
+    ) {
+        (Some(current), Some(candidate)) => candidate > current,
+        (None, Some(_)) => true,
+        _ => false,
+    };
+    if newer {
+        target.last_post_date = orphan.last_post_date;
+        target.last_post_date_relative = orphan.last_post_date_relative;
+    }
+
+    target.root.replies.push(orphan.root);
+    target.update_stats();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::nntp::{ArticleView, ThreadNodeView};
+
+    fn rules() -> SubjectRules {
+        SubjectRules::new(&SubjectConfig::default())
+    }
+
+    fn thread(message_id: &str, subject: &str, date: &str) -> ThreadView {
+        ThreadView {
+            subject: subject.to_string(),
+            normalized_subject: subject.to_string(),
+            root_message_id: message_id.to_string(),
+            article_count: 1,
+            root: ThreadNodeView {
+                message_id: message_id.to_string(),
+                article: Some(ArticleView {
+                    message_id: message_id.to_string(),
+                    subject: subject.to_string(),
+                    from: "a@example.com".to_string(),
+                    date: date.to_string(),
+                    date_relative: String::new(),
+                    body: None,
+                    body_preview: None,
+                    has_more_content: false,
+                    headers: None,
+                }),
+                replies: Vec::new(),
+                descendant_count: 0,
+            },
+            last_post_date: Some(date.to_string()),
+            last_post_date_relative: None,
+            participants: Vec::new(),
+            participant_count: 0,
+            hotness: 0.0,
+            hot: false,
+        }
+    }
+
+    #[test]
+    fn test_normalize_strips_prefixes_and_tags() {
+        let rules = rules();
+        assert_eq!(rules.normalize("Re: Hello"), "Hello");
+        assert_eq!(rules.normalize("AW: Re: SV: Hello"), "Hello");
+        assert_eq!(rules.normalize("RE : Hello"), "Hello");
+        assert_eq!(rules.normalize("Re[2]: Hello"), "Hello");
+        assert_eq!(rules.normalize("Re^3: Hello"), "Hello");
+        assert_eq!(rules.normalize("[rust-users] Re: [ANN] Hello"), "Hello");
+        assert_eq!(rules.normalize("Regarding: Hello"), "Regarding: Hello");
+        assert_eq!(rules.normalize("Re:"), "Re:");
+    }
+
+    #[test]
+    fn test_normalize_respects_config() {
+        let rules = SubjectRules::new(&SubjectConfig {
+            reply_prefixes: vec!["Re".to_string()],
+            strip_tags: false,
+            group_by_subject: false,
+        });
+        assert_eq!(rules.normalize("[list] Re: Hello"), "[list] Re: Hello");
+        assert_eq!(rules.normalize("AW: Hello"), "AW: Hello");
+        assert!(rules.is_reply("re: Hello"));
+    }
+
+    #[test]
+    fn test_group_threads_folds_reply_without_references() {
+        let threads = vec![
+            thread("<reply@x>", "Re: Hello", "Tue, 2 Jan 2024 10:00:00 +0000"),
+            thread("<orig@x>", "Hello", "Mon, 1 Jan 2024 10:00:00 +0000"),
+            thread(
+                "<other@x>",
+                "Re: Something else",
+                "Tue, 2 Jan 2024 10:00:00 +0000",
+            ),
+        ];
+        let grouped = rules().group_threads(threads);
+
+        assert_eq!(grouped.len(), 2);
+        let orig = grouped
+            .iter()
+            .find(|t| t.root_message_id == "<orig@x>")
+            .unwrap();
+        assert_eq!(orig.article_count, 2);
+        assert!(orig.root.contains_message_id("<reply@x>"));
+        assert_eq!(
+            orig.last_post_date.as_deref(),
+            Some("Tue, 2 Jan 2024 10:00:00 +0000")
+        );
+
+        let other = grouped
+            .iter()
+            .find(|t| t.root_message_id == "<other@x>")
+            .unwrap();
+        assert_eq!(other.normalized_subject, "Something else");
+    }
+
+    #[test]
+    fn test_group_threads_skips_ambiguous_and_earlier_replies() {
+        let threads = vec![
+            thread("<a@x>", "Hello", "Mon, 1 Jan 2024 10:00:00 +0000"),
+            thread("<b@x>", "Hello", "Mon, 1 Jan 2024 11:00:00 +0000"),
+            thread("<r@x>", "Re: Hello", "Tue, 2 Jan 2024 10:00:00 +0000"),
+            thread("<c@x>", "World", "Wed, 3 Jan 2024 10:00:00 +0000"),
+            thread("<early@x>", "Re: World", "Tue, 2 Jan 2024 10:00:00 +0000"),
+        ];
+        assert_eq!(rules().group_threads(threads).len(), 5);
+    }
+
+    #[test]
+    fn test_attach_orphans_matches_listing() {
+        let mut orig = thread("<orig@x>", "Hello", "Mon, 1 Jan 2024 10:00:00 +0000");
+        let candidates = vec![
+            orig.clone(),
+            thread("<reply@x>", "Re: Hello", "Tue, 2 Jan 2024 10:00:00 +0000"),
+        ];
+        rules().attach_orphans(&mut orig, &candidates);
+        assert_eq!(orig.article_count, 2);
+        assert!(orig.root.contains_message_id("<reply@x>"));
+    }
+}
@@ -0,0 +1,164 @@
+//! Per-server bandwidth throttling.
+//!
+//! Servers configured with `max_kbps` get one token bucket shared by all of
+//! their workers. Every read from the connection spends tokens for the bytes
+//! received, and the bucket refills at the configured rate up to one second's
+//! worth. A reader that drives the balance below its priority's floor sleeps
+//! until the bucket has refilled to it. High-priority reads only wait once the
+//! bucket is empty, while normal and low-priority reads keep a quarter and
+//! half of the bucket in reserve, so a page load isn't stuck behind
+//! background refreshes when the budget is tight.
+
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use super::messages::Priority;
+
+/// Token bucket limiting the bytes read from one server
+pub struct Throttle {
+    /// Refill rate in bytes per second
+    rate: f64,
+    /// Largest balance the bucket holds, in bytes
+    capacity: f64,
+    bucket: Mutex<Bucket>,
+}
+
+struct Bucket {
+    /// May go negative: reads are charged after they happen
+    tokens: f64,
+    updated: Instant,
+}
+
+impl Throttle {
+    /// Bucket for `kbps` kilobits per second, starting full
+    pub fn from_kbps(kbps: u32) -> Self {
+        let rate = f64::from(kbps.max(1)) * 1000.0 / 8.0;
+        Self {
+            rate,
+            capacity: rate,
+            bucket: Mutex::new(Bucket {
+                tokens: rate,
+                updated: Instant::now(),
+            }),
+        }
+    }
+
+    /// Charge `bytes` read at `priority`, returning how long the reader
+    /// should pause before reading again
+    fn charge(&self, bytes: usize, priority: Priority, now: Instant) -> Duration {
+        let mut bucket = self.bucket.lock().unwrap_or_else(|e| e.into_inner());
+        let elapsed = now.saturating_duration_since(bucket.updated).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * self.rate).min(self.capacity);
+        bucket.updated = bucket.updated.max(now);
+        bucket.tokens -= bytes as f64;
+
+        let floor = self.capacity * reserve(priority);
+        if bucket.tokens >= floor {
+            Duration::ZERO
+        } else {
+            Duration::from_secs_f64((floor - bucket.tokens) / self.rate)
+        }
+    }
+}
+
+/// Share of the bucket a read at `priority` leaves for higher priorities
+fn reserve(priority: Priority) -> f64 {
+    match priority {
+        Priority::High => 0.0,
+        Priority::Normal => 0.25,
+        Priority::Low => 0.5,
+    }
+}
+
+/// One worker's view of its server's throttle: the shared bucket and the
+/// priority of the request the worker is currently serving
+#[derive(Clone)]
+pub struct ThrottleHandle {
+    throttle: Arc<Throttle>,
+    priority: Arc<AtomicU8>,
+}
+
+impl ThrottleHandle {
+    pub fn new(throttle: Arc<Throttle>) -> Self {
+        Self {
+            throttle,
+            priority: Arc::new(AtomicU8::new(Priority::High as u8)),
+        }
+    }
+
+    /// Charge reads from now on to `priority`
+    pub fn set_priority(&self, priority: Priority) {
+        self.priority.store(priority as u8, Ordering::Relaxed);
+    }
+
+    fn priority(&self) -> Priority {
+        match self.priority.load(Ordering::Relaxed) {
+            p if p == Priority::High as u8 => Priority::High,
+            p if p == Priority::Normal as u8 => Priority::Normal,
+            _ => Priority::Low,
+        }
+    }
+
+    /// Account for `bytes` just read, sleeping if the budget is spent
+    pub async fn consume(&self, bytes: usize) {
+        let wait = self.throttle.charge(bytes, self.priority(), Instant::now());
+        if !wait.is_zero() {
+            tracing::trace!(
+                bytes,
+                wait_ms = wait.as_millis() as u64,
+                priority = %self.priority(),
+                "Throttling NNTP read"
+            );
+            tokio::time::sleep(wait).await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_throttle_allows_burst_then_waits_for_refill() {
+        // 80 kbps = 10,000 bytes per second
+        let throttle = Throttle::from_kbps(80);
+        let now = Instant::now();
+
+        assert_eq!(throttle.charge(10_000, Priority::High, now), Duration::ZERO);
+        assert_eq!(
+            throttle.charge(5_000, Priority::High, now),
+            Duration::from_millis(500)
+        );
+        // Half a second later the debt is paid off
+        assert_eq!(
+            throttle.charge(0, Priority::High, now + Duration::from_millis(500)),
+            Duration::ZERO
+        );
+    }
+
+    #[test]
+    fn test_throttle_keeps_reserve_for_higher_priorities() {
+        let throttle = Throttle::from_kbps(80);
+        let now = Instant::now();
+
+        // Low priority must leave half the bucket
+        assert_eq!(throttle.charge(5_000, Priority::Low, now), Duration::ZERO);
+        assert_eq!(
+            throttle.charge(1_000, Priority::Low, now),
+            Duration::from_millis(100)
+        );
+        // High priority can still spend what is left
+        assert_eq!(throttle.charge(4_000, Priority::High, now), Duration::ZERO);
+    }
+
+    #[test]
+    fn test_throttle_handle_priority_round_trips() {
+        let handle = ThrottleHandle::new(Arc::new(Throttle::from_kbps(8)));
+        assert_eq!(handle.priority(), Priority::High);
+        for priority in [Priority::Normal, Priority::Low, Priority::High] {
+            handle.set_priority(priority);
+            assert_eq!(handle.priority(), priority);
+        }
+    }
+}
@@ -0,0 +1,198 @@
+//! Vote tallies for FAQ-style and proposal threads.
+//!
+//! Some groups decide things by reply: "+1" / "-1", or a structured
+//! "Vote: yes" line. In groups enabled by `[votes] groups`, the thread view
+//! reads the replies and shows an aggregate tally in the thread header.
+//!
+//! Only the poster's own text counts: quoted lines, attribution lines and
+//! signatures are skipped, so quoting someone else's "+1" is not a vote.
+//! Each From address counts once and a later vote replaces an earlier one.
+
+use std::collections::HashMap;
+
+use serde::Serialize;
+
+use super::{parse_date, ArticleView};
+use crate::config::VOTE_MAX_CHOICE_LEN;
+
+/// Number of voters for one choice
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct VoteCount {
+    pub choice: String,
+    pub count: usize,
+}
+
+/// Aggregated votes for a thread
+#[derive(Debug, Clone, Serialize)]
+pub struct VoteTally {
+    /// Choices, most votes first
+    pub choices: Vec<VoteCount>,
+    /// Number of distinct voters
+    pub voters: usize,
+}
+
+/// Extract the vote cast in an article body, if any.
+///
+/// A "Vote: <choice>" line anywhere in the poster's own text wins; otherwise
+/// the first line of their own text may start with "+1", "-1", "+0" or "-0".
+pub fn parse_vote(body: &str) -> Option<String> {
+    let mut first_line: Option<&str> = None;
+    for line in body.lines() {
+        if line == "-- " || line == "--" {
+            break;
+        }
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('>') || is_attribution(line) {
+            continue;
+        }
+        if let Some(choice) = structured_vote(line) {
+            return Some(choice);
+        }
+        first_line.get_or_insert(line);
+    }
+    first_line.and_then(numeric_vote)
+}
+
+/// Tally the votes cast in a set of replies.
+/// Returns `None` when nobody voted.
+pub fn tally<'a>(articles: impl IntoIterator<Item = &'a ArticleView>) -> Option<VoteTally> {
+    // Voter -> (posting date, choice); undated votes count as the latest seen
+    let mut ballots: HashMap<String, (Option<i64>, String)> = HashMap::new();
+    for article in articles {
+        let Some(choice) = article.body.as_deref().and_then(parse_vote) else {
+            continue;
+        };
+        let posted = parse_date(&article.date).map(|d| d.timestamp());
+        let voter = article.from.trim().to_lowercase();
+        match ballots.get(&voter) {
+            Some((Some(previous), _)) if posted.is_some_and(|p| p < *previous) => {}
+            _ => {
+                ballots.insert(voter, (posted, choice));
+            }
+        }
+    }
+    if ballots.is_empty() {
+        return None;
+    }
+
+    let mut counts: HashMap<String, usize> = HashMap::new();
+    for (_, choice) in ballots.values() {
+        *counts.entry(choice.clone()).or_default() += 1;
+    }
+    let mut choices: Vec<VoteCount> = counts
+        .into_iter()
+        .map(|(choice, count)| VoteCount { choice, count })
+        .collect();
+    choices.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.choice.cmp(&b.choice)));
+
+    Some(VoteTally {
+        choices,
+        voters: ballots.len(),
+    })
+}
+
+/// "On <date>, <name> wrote:" style lines introducing a quote
+fn is_attribution(line: &str) -> bool {
+    line.ends_with("wrote:") || line.ends_with("writes:")
+}
+
+/// "Vote: <choice>", case-insensitive, normalized to lowercase
+fn structured_vote(line: &str) -> Option<String> {
+    let head = line.get(..5)?;
+    if !head.eq_ignore_ascii_case("vote:") {
+        return None;
+    }
+    let choice: String = line[5..]
+        .trim()
+        .trim_end_matches(['.', '!'])
+        .chars()
+        .take(VOTE_MAX_CHOICE_LEN)
+        .collect::<String>()
+        .to_lowercase();
+    (!choice.is_empty()).then_some(choice)
+}
+
+/// "+1", "-1", "+0" or "-0" at the start of a line, not followed by more digits
+fn numeric_vote(line: &str) -> Option<String> {
+    let vote = line.get(..2)?;
+    if !matches!(vote, "+1" | "-1" | "+0" | "-0") {
+        return None;
+    }
+    let rest = &line[2..];
+    let digit = |s: &str| s.starts_with(|c: char| c.is_ascii_digit());
+    let continues_number = digit(rest) || (rest.starts_with(['.', ',']) && digit(&rest[1..]));
+    (!continues_number).then(|| vote.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn reply(from: &str, date: &str, body: &str) -> ArticleView {
+        ArticleView {
+            message_id: format!("<{}@x>", from),
+            subject: "Re: Proposal".to_string(),
+            from: from.to_string(),
+            date: date.to_string(),
+            date_relative: String::new(),
+            body: Some(body.to_string()),
+            body_preview: None,
+            has_more_content: false,
+            headers: None,
+        }
+    }
+
+    #[test]
+    fn test_parse_vote_numeric_and_structured() {
+        assert_eq!(parse_vote("+1, ship it\n").as_deref(), Some("+1"));
+        assert_eq!(
+            parse_vote("-1\n\nThis breaks things.").as_deref(),
+            Some("-1")
+        );
+        assert_eq!(
+            parse_vote("Looks fine.\nVote: Yes!\n").as_deref(),
+            Some("yes")
+        );
+        assert_eq!(parse_vote("+10 would read again").as_deref(), None);
+        assert_eq!(parse_vote("-1.5 degrees outside").as_deref(), None);
+        assert_eq!(parse_vote("I agree.\n+1").as_deref(), None);
+    }
+
+    #[test]
+    fn test_parse_vote_ignores_quotes_and_signature() {
+        let body = "Jane wrote:\n> +1\n> Vote: no\n\n-1 from me\n-- \nVote: yes\n";
+        assert_eq!(parse_vote(body).as_deref(), Some("-1"));
+        assert_eq!(
+            parse_vote("> +1\n\nThanks for the summary.").as_deref(),
+            None
+        );
+    }
+
+    #[test]
+    fn test_tally_counts_latest_vote_per_poster() {
+        let replies = vec![
+            reply("a@example.com", "Mon, 1 Jan 2024 10:00:00 +0000", "-1"),
+            reply("b@example.com", "Mon, 1 Jan 2024 11:00:00 +0000", "+1"),
+            reply(
+                "A@example.com",
+                "Tue, 2 Jan 2024 10:00:00 +0000",
+                "+1 after all",
+            ),
+            reply("c@example.com", "Tue, 2 Jan 2024 11:00:00 +0000", "-1"),
+            reply(
+                "d@example.com",
+                "Tue, 2 Jan 2024 12:00:00 +0000",
+                "No opinion.",
+            ),
+        ];
+        let result = tally(&replies).unwrap();
+        assert_eq!(result.voters, 3);
+        let counts: Vec<(&str, usize)> = result
+            .choices
+            .iter()
+            .map(|c| (c.choice.as_str(), c.count))
+            .collect();
+        assert_eq!(counts, vec![("+1", 2), ("-1", 1)]);
+        assert!(tally(&replies[4..]).is_none());
+    }
+}
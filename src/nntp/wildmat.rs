@@ -0,0 +1,68 @@
+//! RFC 3977 wildmat matching for newsgroup names.
+//!
+//! Used by the NNTP listener's LIST commands and by config rules keyed by
+//! group pattern.
+
+/// Match a group name against an RFC 3977 wildmat: comma-separated patterns
+/// with `*` and `?` wildcards, where a leading `!` negates. The last matching
+/// pattern decides.
+pub fn wildmat(pattern: &str, name: &str) -> bool {
+    let mut matched = false;
+    for part in pattern.split(',') {
+        let (negate, glob) = match part.strip_prefix('!') {
+            Some(glob) => (true, glob),
+            None => (false, part),
+        };
+        if glob_match(glob.as_bytes(), name.as_bytes()) {
+            matched = !negate;
+        }
+    }
+    matched
+}
+
+/// Wildcard match with single-star backtracking (linear in practice, no recursion).
+fn glob_match(pattern: &[u8], text: &[u8]) -> bool {
+    let (mut p, mut t) = (0, 0);
+    let mut star: Option<(usize, usize)> = None;
+
+    while t < text.len() {
+        match pattern.get(p) {
+            Some(b'*') => {
+                star = Some((p, t));
+                p += 1;
+            }
+            Some(&c) if c == b'?' || c == text[t] => {
+                p += 1;
+                t += 1;
+            }
+            _ => match star {
+                Some((star_p, star_t)) => {
+                    p = star_p + 1;
+                    t = star_t + 1;
+                    star = Some((star_p, star_t + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+
+    pattern[p..].iter().all(|&c| c == b'*')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_wildmat() {
+        assert!(wildmat("comp.*", "comp.lang.rust"));
+        assert!(!wildmat("comp.*", "alt.test"));
+        assert!(wildmat("comp.lang.?", "comp.lang.c"));
+        assert!(!wildmat("comp.*,!comp.lang.*", "comp.lang.rust"));
+        assert!(wildmat(
+            "comp.*,!comp.lang.*,comp.lang.rust",
+            "comp.lang.rust"
+        ));
+        assert!(wildmat("*", "anything"));
+    }
+}
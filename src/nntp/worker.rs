@@ -5,6 +5,17 @@
 //! processed before normal and low-priority requests. Aging prevents
 //! starvation of low-priority requests under sustained high load.
 //!
+//! Requests for a group are routed to one worker through its own affinity
+//! queues: the worker that has the group selected, or else the group's
+//! place on a consistent hash ring. Each connection remembers the group it
+//! has selected, and incremental fetches there skip the GROUP command.
+//!
+//! Workers added when the pool grows under load (see `scaling`) retire once
+//! they have been idle for a while or can't connect.
+//!
+//! An idle connection gets a keepalive command every `keepalive_seconds`, so
+//! servers that drop quiet sessions don't make the next request reconnect.
+//!
 //! Connection strategy:
 //! - Try TLS first for all connections
 //! - If credentials are configured, TLS is required (no fallback)
@@ -12,25 +23,36 @@
 
 use std::collections::HashMap;
 use std::collections::HashSet;
-use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 
 use async_channel::Receiver;
 use nntp_rs::net_client::NntpClient;
+use tokio::sync::Mutex;
 use tokio::time::timeout;
 
 use tracing::{instrument, Span};
 
 use crate::config::{
-    NntpServerConfig, NntpSettings, DEFAULT_SUBJECT, NNTP_MAX_ARTICLES_HEAD_FALLBACK,
-    NNTP_MAX_ARTICLES_PER_REQUEST, NNTP_PRIORITY_AGING_SECS, NNTP_RECONNECT_DELAY_SECS,
+    NntpServerConfig, NntpSettings, NntpTlsMode, DEFAULT_SUBJECT, NNTP_GROUP_RESELECT_SECS,
+    NNTP_MAX_ARTICLES_HEAD_FALLBACK, NNTP_MAX_ARTICLES_PER_REQUEST, NNTP_PRIORITY_AGING_SECS,
+    NNTP_TRAFFIC_SAMPLE_ARTICLES, NNTP_UNHEALTHY_AFTER_FAILURES,
 };
 
-use super::messages::{GroupStatsView, NntpError, NntpRequest, NntpResponse};
+use super::charset;
+use super::messages::{
+    GroupStatsView, NntpError, NntpRequest, NntpResponse, Priority, QueuedRequest,
+};
+use super::overview::OverviewFormat;
+use super::retry::{random_unit, RetryPolicy};
+use super::ring::WorkerRing;
+use super::scaling::PoolLoad;
+use super::throttle::ThrottleHandle;
 use super::tls::NntpStream;
 use super::{
-    build_threads_from_hdr, build_threads_from_overview, parse_article, GroupView, HdrArticleData,
+    build_threads_from_hdr, build_threads_from_overview, parse_article, parse_date, parse_head,
+    GroupView, HdrArticleData,
 };
 
 /// Method to use for fetching thread data
@@ -53,14 +75,19 @@ struct ServerCapabilities {
     hdr_supported: bool,
     /// Whether OVER command is supported
     over_supported: bool,
-    /// Whether References field is in the overview format
-    references_in_overview: bool,
+    /// Overview format from LIST OVERVIEW.FMT, `None` if not retrieved
+    overview_format: Option<OverviewFormat>,
     /// Whether capabilities were successfully retrieved
     retrieved: bool,
     /// Whether POST command is supported (from CAPABILITIES)
     post_supported: bool,
     /// Whether the greeting/MODE READER allows posting
     greeting_allows_post: bool,
+    /// Whether READER is advertised: reading commands work without MODE READER
+    reader_advertised: bool,
+    /// Whether MODE-READER is advertised: a mode-switching server that needs
+    /// MODE READER before reading commands
+    mode_reader_advertised: bool,
 }
 
 impl ServerCapabilities {
@@ -70,6 +97,8 @@ impl ServerCapabilities {
         let mut hdr_supported = false;
         let mut over_supported = false;
         let mut post_supported = false;
+        let mut reader_advertised = false;
+        let mut mode_reader_advertised = false;
 
         for cap in caps {
             let cap_upper = cap.to_uppercase();
@@ -88,6 +117,10 @@ impl ServerCapabilities {
                 over_supported = true;
             } else if cap_upper == "POST" || cap_upper.starts_with("POST ") {
                 post_supported = true;
+            } else if cap_upper == "READER" {
+                reader_advertised = true;
+            } else if cap_upper == "MODE-READER" {
+                mode_reader_advertised = true;
             }
         }
 
@@ -95,24 +128,35 @@ impl ServerCapabilities {
             list_variants,
             hdr_supported,
             over_supported,
-            references_in_overview: false, // Will be set after LIST OVERVIEW.FMT
+            overview_format: None, // Will be set after LIST OVERVIEW.FMT
             retrieved: true,
             post_supported,
             greeting_allows_post: false, // Will be set from client.is_posting_allowed()
+            reader_advertised,
+            mode_reader_advertised,
         }
     }
 
+    /// Whether MODE READER should be sent before reading commands.
+    /// Servers that advertise READER without MODE-READER are already in
+    /// reader mode; without capabilities we can't tell, so send it.
+    fn needs_mode_reader(&self) -> bool {
+        self.mode_reader_advertised || !self.reader_advertised
+    }
+
     /// Determine the best method for fetching thread data
     /// Prefers OVER (1 round-trip) over HDR (5 round-trips) for latency
     fn thread_fetch_method(&self) -> ThreadFetchMethod {
-        if self.over_supported && self.references_in_overview {
-            ThreadFetchMethod::Over
-        } else if self.over_supported {
-            // OVER supported but References not confirmed - still try it
-            // as most servers include References by default
+        let format = self.overview_format.as_ref();
+        if self.over_supported && format.is_none_or(OverviewFormat::has_references) {
+            // Without OVERVIEW.FMT, assume the standard format as most
+            // servers use it
             ThreadFetchMethod::Over
         } else if self.hdr_supported {
             ThreadFetchMethod::Hdr
+        } else if self.over_supported && format.is_some_and(OverviewFormat::has_standard_headers) {
+            // No References: threads come out flat, but HEAD is far slower
+            ThreadFetchMethod::Over
         } else {
             ThreadFetchMethod::Head
         }
@@ -146,6 +190,14 @@ impl ServerCapabilities {
         vec!["LIST ACTIVE", "LIST NEWSGROUPS"]
     }
 
+    /// Whether to follow LIST ACTIVE with LIST NEWSGROUPS for descriptions:
+    /// when advertised, or when we can't tell
+    fn lists_descriptions(&self) -> bool {
+        !self.retrieved
+            || self.list_variants.is_empty()
+            || self.list_variants.contains("NEWSGROUPS")
+    }
+
     /// Check if posting is supported
     /// Requires both the greeting/MODE READER to allow posting AND POST in CAPABILITIES
     fn can_post(&self) -> bool {
@@ -158,11 +210,15 @@ impl ServerCapabilities {
 /// Groups the three priority-level queue receivers that workers pull requests from.
 pub struct WorkerQueues {
     /// High-priority request queue (user-facing: GetArticle, PostArticle)
-    pub high: Receiver<NntpRequest>,
+    pub high: Receiver<QueuedRequest>,
     /// Normal-priority request queue (page load: GetThreads, GetGroups)
-    pub normal: Receiver<NntpRequest>,
+    pub normal: Receiver<QueuedRequest>,
     /// Low-priority request queue (background: GetGroupStats, GetNewArticles)
-    pub low: Receiver<NntpRequest>,
+    pub low: Receiver<QueuedRequest>,
+    /// Normal-priority group requests routed to this worker
+    pub affinity_normal: Receiver<QueuedRequest>,
+    /// Low-priority group requests routed to this worker
+    pub affinity_low: Receiver<QueuedRequest>,
 }
 
 /// Shared counters for tracking worker pool status.
@@ -174,6 +230,124 @@ pub struct WorkerCounters {
     pub connected: Arc<AtomicUsize>,
     /// Count of workers whose connections allow posting
     pub posting: Arc<AtomicUsize>,
+    /// How long requests waited in the queues, for scaling the pool
+    pub load: Arc<PoolLoad>,
+    /// Set while the server refuses connections, so the federated layer
+    /// tries it after the others
+    pub unhealthy: Arc<AtomicBool>,
+}
+
+/// Where each group's requests should go, shared by a server's workers.
+///
+/// Positions (which worker has which group selected) hold at most one entry
+/// per worker. They are a hint: the service may route a request to a worker
+/// that has moved on, which then just selects again.
+#[derive(Clone)]
+pub struct GroupAffinity {
+    positions: Arc<Mutex<HashMap<String, usize>>>,
+    /// Consistent hash ring giving each group a home worker
+    ring: Arc<WorkerRing>,
+    /// Whether each worker currently holds a ready connection
+    live: Arc<Vec<AtomicBool>>,
+    /// Whether each worker is running a request right now
+    busy: Arc<Vec<AtomicBool>>,
+}
+
+impl GroupAffinity {
+    /// Affinity for workers `0..workers`, none of them connected yet
+    pub fn new(workers: usize) -> Self {
+        Self {
+            positions: Arc::new(Mutex::new(HashMap::new())),
+            ring: Arc::new(WorkerRing::new(workers)),
+            live: Arc::new((0..workers).map(|_| AtomicBool::new(false)).collect()),
+            busy: Arc::new((0..workers).map(|_| AtomicBool::new(false)).collect()),
+        }
+    }
+
+    /// Worker whose connection has `group` selected, if any
+    pub async fn worker_for(&self, group: &str) -> Option<usize> {
+        self.positions.lock().await.get(group).copied()
+    }
+
+    /// Worker that should serve `group`: the live worker that has it
+    /// selected, or else its home on the hash ring among live workers
+    pub async fn route(&self, group: &str) -> Option<usize> {
+        match self.worker_for(group).await {
+            Some(worker) if self.is_live(worker) => Some(worker),
+            _ => self.ring.worker_for(group, |worker| self.is_live(worker)),
+        }
+    }
+
+    fn is_live(&self, worker: usize) -> bool {
+        self.live
+            .get(worker)
+            .is_some_and(|live| live.load(Ordering::Relaxed))
+    }
+
+    /// Whether `worker` is between requests
+    pub fn is_idle(&self, worker: usize) -> bool {
+        self.busy
+            .get(worker)
+            .is_some_and(|busy| !busy.load(Ordering::Relaxed))
+    }
+
+    /// Mark `worker` as running a request, or done with it
+    fn set_busy(&self, worker: usize, busy: bool) {
+        if let Some(flag) = self.busy.get(worker) {
+            flag.store(busy, Ordering::Relaxed);
+        }
+    }
+
+    /// Mark `worker` as ready to take routed requests
+    fn connected(&self, worker: usize) {
+        if let Some(live) = self.live.get(worker) {
+            live.store(true, Ordering::Relaxed);
+        }
+    }
+
+    /// Stop routing to `worker` and forget its position, e.g. when its connection drops
+    async fn disconnected(&self, worker: usize) {
+        if let Some(live) = self.live.get(worker) {
+            live.store(false, Ordering::Relaxed);
+        }
+        self.forget(worker).await;
+    }
+
+    /// Record that `worker` selected `group`, replacing its previous group
+    async fn record(&self, worker: usize, group: &str) {
+        let mut positions = self.positions.lock().await;
+        positions.retain(|_, id| *id != worker);
+        positions.insert(group.to_string(), worker);
+    }
+
+    /// Forget the position of `worker`, e.g. when its connection drops
+    async fn forget(&self, worker: usize) {
+        self.positions.lock().await.retain(|_, id| *id != worker);
+    }
+}
+
+/// Why a worker waiting for a request stopped waiting
+enum Wakeup {
+    /// A request, or the queues closing
+    Request(Result<QueuedRequest, async_channel::RecvError>),
+    /// The connection has been quiet for the keepalive interval
+    KeepAlive,
+    /// A worker added under load has been idle for its idle timeout
+    Retire,
+}
+
+/// Group selected on a worker's connection
+struct SelectedGroup {
+    name: String,
+    selected_at: Instant,
+}
+
+impl SelectedGroup {
+    /// Whether `group` is selected recently enough to skip GROUP
+    fn is_current(&self, group: &str) -> bool {
+        self.name == group
+            && self.selected_at.elapsed() < Duration::from_secs(NNTP_GROUP_RESELECT_SECS)
+    }
 }
 
 /// Worker that processes NNTP requests from priority queues
@@ -186,6 +360,15 @@ pub struct NntpWorker {
     queues: WorkerQueues,
     /// Shared worker pool counters
     counters: WorkerCounters,
+    /// Shared record of which worker has which group selected
+    affinity: GroupAffinity,
+    /// The server's bandwidth limit, if `max_kbps` is set
+    throttle: Option<ThrottleHandle>,
+    /// Idle time after which a worker added under load retires (none for
+    /// the pool's fixed workers)
+    idle_timeout: Option<Duration>,
+    /// Quiet time on the connection before a keepalive command, if any
+    keepalive: Option<Duration>,
 }
 
 impl NntpWorker {
@@ -196,27 +379,113 @@ impl NntpWorker {
         global_settings: NntpSettings,
         queues: WorkerQueues,
         counters: WorkerCounters,
+        affinity: GroupAffinity,
+        throttle: Option<ThrottleHandle>,
     ) -> Self {
         Self {
             id,
             server_name: server_config.name.clone(),
+            keepalive: server_config.keepalive(&global_settings),
             server_config,
             global_settings,
             queues,
             counters,
+            affinity,
+            throttle,
+            idle_timeout: None,
+        }
+    }
+
+    /// Retire after `idle` without a request, and when a connection attempt
+    /// fails instead of retrying, as workers added under load do
+    pub fn retire_when_idle(mut self, idle: Duration) -> Self {
+        self.idle_timeout = Some(idle);
+        self
+    }
+
+    /// After a failed connection attempt, wait before the next one, longer
+    /// after each consecutive failure (`failures`, reset once connected).
+    /// Once enough attempts have failed with no worker connected, the server
+    /// is marked unhealthy. Returns false for a worker added under load,
+    /// which retires instead.
+    async fn wait_to_reconnect(&self, failures: &mut u32) -> bool {
+        if self.idle_timeout.is_some() {
+            tracing::info!("Could not connect, retiring added worker");
+            return false;
+        }
+        *failures = failures.saturating_add(1);
+        if *failures >= NNTP_UNHEALTHY_AFTER_FAILURES
+            && self.counters.connected.load(Ordering::Relaxed) == 0
+            && !self.counters.unhealthy.swap(true, Ordering::Relaxed)
+        {
+            tracing::warn!(failures = *failures, "Server unreachable, marked unhealthy");
+        }
+        let delay = RetryPolicy::RECONNECT.delay(*failures, random_unit());
+        tracing::debug!(
+            failures = *failures,
+            delay_ms = delay.as_millis() as u64,
+            "Waiting to reconnect"
+        );
+        tokio::time::sleep(delay).await;
+        true
+    }
+
+    /// Take this worker's connection out of the pool's counters and routing
+    async fn release(&self, can_post: bool) {
+        self.counters.connected.fetch_sub(1, Ordering::Relaxed);
+        if can_post {
+            self.counters.posting.fetch_sub(1, Ordering::Relaxed);
+        }
+        self.affinity.disconnected(self.id).await;
+    }
+
+    /// Try this worker's affinity queue, then the shared queue, at normal priority
+    fn try_recv_normal(&self) -> Option<QueuedRequest> {
+        self.queues
+            .affinity_normal
+            .try_recv()
+            .or_else(|_| self.queues.normal.try_recv())
+            .ok()
+    }
+
+    /// Try this worker's affinity queue, then the shared queue, at low priority
+    fn try_recv_low(&self) -> Option<QueuedRequest> {
+        self.queues
+            .affinity_low
+            .try_recv()
+            .or_else(|_| self.queues.low.try_recv())
+            .ok()
+    }
+
+    /// Record `group` as selected on this worker's connection
+    async fn mark_selected(&self, selected: &mut Option<SelectedGroup>, group: &str) {
+        *selected = Some(SelectedGroup {
+            name: group.to_string(),
+            selected_at: Instant::now(),
+        });
+        self.affinity.record(self.id, group).await;
+    }
+
+    /// Fail requests waiting in the affinity queues so they don't wait for a reconnect
+    fn drain_affinity_queues(&self) {
+        for queue in [&self.queues.affinity_normal, &self.queues.affinity_low] {
+            while let Ok(queued) = queue.try_recv() {
+                let error = NntpError::ConnectionLost("Worker reconnecting".into());
+                queued.request.respond(Err(error));
+            }
         }
     }
 
     /// Receive the next request, respecting priority with aging to prevent starvation.
     ///
-    /// Priority order: High > Normal > Low
+    /// Priority order: High > Normal > Low, each level's affinity queue ahead of the shared one
     /// Aging: If low-priority requests have been waiting longer than NNTP_PRIORITY_AGING_SECS,
     /// process one low-priority request to prevent indefinite starvation.
     #[allow(clippy::never_loop)] // Loop is intentional for tokio::select! pattern
     async fn recv_prioritized(
         &self,
         last_low_process: &mut Instant,
-    ) -> Result<NntpRequest, async_channel::RecvError> {
+    ) -> Result<QueuedRequest, async_channel::RecvError> {
         loop {
             // Check for aging: if low-priority queue is non-empty and hasn't been
             // serviced recently, process one low-priority request
@@ -224,7 +493,7 @@ impl NntpWorker {
                 last_low_process.elapsed().as_secs() >= NNTP_PRIORITY_AGING_SECS;
 
             if should_check_aging {
-                if let Ok(req) = self.queues.low.try_recv() {
+                if let Some(req) = self.try_recv_low() {
                     *last_low_process = Instant::now();
                     tracing::trace!(
                         priority = "low",
@@ -241,12 +510,12 @@ impl NntpWorker {
             }
 
             // Try normal priority (non-blocking)
-            if let Ok(req) = self.queues.normal.try_recv() {
+            if let Some(req) = self.try_recv_normal() {
                 return Ok(req);
             }
 
             // Try low priority (non-blocking)
-            if let Ok(req) = self.queues.low.try_recv() {
+            if let Some(req) = self.try_recv_low() {
                 *last_low_process = Instant::now();
                 return Ok(req);
             }
@@ -257,7 +526,12 @@ impl NntpWorker {
                 biased;
 
                 result = self.queues.high.recv() => return result,
+                result = self.queues.affinity_normal.recv() => return result,
                 result = self.queues.normal.recv() => return result,
+                result = self.queues.affinity_low.recv() => {
+                    *last_low_process = Instant::now();
+                    return result;
+                }
                 result = self.queues.low.recv() => {
                     *last_low_process = Instant::now();
                     return result;
@@ -266,6 +540,95 @@ impl NntpWorker {
         }
     }
 
+    /// Wait for the next request, but no longer than until a keepalive is
+    /// due (`keepalive` after the last command) or, for a worker added under
+    /// load, until it has been idle too long (`idle_timeout` after the last
+    /// request)
+    async fn wait_for_request(
+        &self,
+        last_low_process: &mut Instant,
+        last_request: Instant,
+        last_command: Instant,
+    ) -> Wakeup {
+        let retire_at = self.idle_timeout.map(|idle| last_request + idle);
+        let wake_at = [self.keepalive.map(|every| last_command + every), retire_at]
+            .into_iter()
+            .flatten()
+            .min();
+        let Some(wake_at) = wake_at else {
+            return Wakeup::Request(self.recv_prioritized(last_low_process).await);
+        };
+
+        let recv = self.recv_prioritized(last_low_process);
+        match tokio::time::timeout_at(wake_at.into(), recv).await {
+            Ok(received) => Wakeup::Request(received),
+            Err(_) if retire_at.is_some_and(|at| Instant::now() >= at) => Wakeup::Retire,
+            Err(_) => Wakeup::KeepAlive,
+        }
+    }
+
+    /// Send a cheap command on an idle connection so the server keeps it:
+    /// GROUP for the selected group, which also refreshes the selection, or
+    /// else CAPABILITIES. Bounded by the request timeout, as a connection
+    /// the server already dropped may never answer.
+    async fn keep_alive(
+        &self,
+        client: &mut NntpClient<NntpStream>,
+        selected: &mut Option<SelectedGroup>,
+    ) -> Result<(), NntpError> {
+        if let Some(throttle) = &self.throttle {
+            throttle.set_priority(Priority::Low);
+        }
+        let limit = Duration::from_secs(
+            self.server_config
+                .request_timeout_seconds(&self.global_settings),
+        );
+        let group = selected.as_ref().map(|s| s.name.clone());
+        let sent = timeout(limit, async {
+            match &group {
+                Some(group) => client.group(group).await.map(|_| ()),
+                None => client.capabilities().await.map(|_| ()),
+            }
+        })
+        .await;
+        match sent {
+            Ok(Ok(())) => {}
+            Ok(Err(e)) => return Err(NntpError::from(e)),
+            Err(_) => return Err(NntpError::Timeout("Keepalive timeout".into())),
+        }
+        if let Some(group) = group {
+            self.mark_selected(selected, &group).await;
+        }
+        tracing::trace!("Keepalive sent");
+        Ok(())
+    }
+
+    /// Query CAPABILITIES, falling back to defaults if the server doesn't
+    /// support it
+    async fn query_capabilities(client: &mut NntpClient<NntpStream>) -> ServerCapabilities {
+        match client.capabilities().await {
+            Ok(caps) => {
+                let server_caps = ServerCapabilities::from_capabilities(&caps);
+                tracing::trace!(
+                    list_variants = ?server_caps.list_variants,
+                    hdr_supported = server_caps.hdr_supported,
+                    over_supported = server_caps.over_supported,
+                    reader_advertised = server_caps.reader_advertised,
+                    mode_reader_advertised = server_caps.mode_reader_advertised,
+                    "Parsed server capabilities"
+                );
+                server_caps
+            }
+            Err(e) => {
+                tracing::trace!(
+                    error = %e,
+                    "Failed to get capabilities, will use fallback behavior"
+                );
+                ServerCapabilities::default()
+            }
+        }
+    }
+
     /// Run the worker loop - connects to NNTP and processes requests
     #[instrument(
         name = "nntp.worker",
@@ -275,16 +638,28 @@ impl NntpWorker {
     pub async fn run(self) {
         tracing::info!("Worker starting");
 
+        // Consecutive failed connection attempts
+        let mut failures = 0u32;
+
         loop {
             // Connect/reconnect to NNTP server
-            let addr = format!("{}:{}", self.server_config.host, self.server_config.port);
+            let addr = self.server_config.address();
             let connect_timeout =
                 Duration::from_secs(self.server_config.timeout_seconds(&self.global_settings));
             let has_credentials = self.server_config.has_credentials();
             let requires_tls = self.server_config.requires_tls_for_credentials();
 
-            // Set TLS requirement flag (credentials require TLS unless allow_insecure_auth is set)
+            // Set TLS requirement flag (credentials require TLS unless allow_insecure_auth is set;
+            // pinned certificates and a ca_file require it regardless, see TlsTrust)
             super::tls::set_tls_required(requires_tls);
+            // Connection setup is charged at high priority
+            if let Some(throttle) = &self.throttle {
+                throttle.set_priority(Priority::High);
+            }
+            super::tls::set_throttle(self.throttle.clone());
+            super::tls::set_proxy(self.server_config.proxy.clone());
+            super::tls::set_tls_mode(self.server_config.tls_mode);
+            super::tls::set_trust(super::tls::TlsTrust::from_config(&self.server_config));
 
             // Connect using NntpClient with our TLS-aware NntpStream
             let mut client =
@@ -300,12 +675,16 @@ impl NntpWorker {
                     }
                     Ok(Err(e)) => {
                         tracing::error!(error = %e, "Failed to connect");
-                        tokio::time::sleep(Duration::from_secs(NNTP_RECONNECT_DELAY_SECS)).await;
+                        if !self.wait_to_reconnect(&mut failures).await {
+                            return;
+                        }
                         continue;
                     }
                     Err(_) => {
                         tracing::error!("Connection timeout");
-                        tokio::time::sleep(Duration::from_secs(NNTP_RECONNECT_DELAY_SECS)).await;
+                        if !self.wait_to_reconnect(&mut failures).await {
+                            return;
+                        }
                         continue;
                     }
                 };
@@ -327,63 +706,67 @@ impl NntpWorker {
                     }
                     Err(e) => {
                         tracing::error!(error = %e, "Authentication failed");
-                        tokio::time::sleep(Duration::from_secs(NNTP_RECONNECT_DELAY_SECS)).await;
+                        if !self.wait_to_reconnect(&mut failures).await {
+                            return;
+                        }
                         continue;
                     }
                 }
             }
 
+            // Query server capabilities to determine supported commands
+            let mut capabilities = Self::query_capabilities(&mut client).await;
+
             // Switch to reader mode (RFC 3977 Section 5.3)
             // MODE READER may update posting capability based on authentication state
-            match client.mode_reader().await {
-                Ok(_status) => {
-                    tracing::debug!("MODE READER completed");
-                }
-                Err(e) => {
-                    // MODE READER is required per RFC 3977; failure is fatal for this connection
-                    tracing::error!(error = %e, "MODE READER failed");
-                    tokio::time::sleep(Duration::from_secs(NNTP_RECONNECT_DELAY_SECS)).await;
-                    continue;
+            let mut mode_reader_answered = false;
+            if capabilities.needs_mode_reader() {
+                match client.mode_reader().await {
+                    Ok(_status) => {
+                        tracing::debug!("MODE READER completed");
+                        mode_reader_answered = true;
+                        // Capabilities may change after MODE READER, so ask again
+                        capabilities = Self::query_capabilities(&mut client).await;
+                    }
+                    Err(e) if capabilities.reader_advertised => {
+                        // The server already reads articles; keep going without it
+                        tracing::warn!(error = %e, "MODE READER failed, server advertises READER");
+                    }
+                    Err(e) => {
+                        // MODE READER is required per RFC 3977; failure is fatal for this connection
+                        tracing::error!(error = %e, "MODE READER failed");
+                        if !self.wait_to_reconnect(&mut failures).await {
+                            return;
+                        }
+                        continue;
+                    }
                 }
+            } else {
+                tracing::debug!("Server advertises READER, skipping MODE READER");
             }
 
-            // Query server capabilities to determine supported commands
-            let mut capabilities = match client.capabilities().await {
-                Ok(caps) => {
-                    let server_caps = ServerCapabilities::from_capabilities(&caps);
-                    tracing::trace!(
-                        list_variants = ?server_caps.list_variants,
-                        hdr_supported = server_caps.hdr_supported,
-                        over_supported = server_caps.over_supported,
-                        "Parsed server capabilities"
-                    );
-                    server_caps
-                }
-                Err(e) => {
-                    tracing::trace!(
-                        error = %e,
-                        "Failed to get capabilities, will use fallback behavior"
-                    );
-                    ServerCapabilities::default()
-                }
-            };
-
-            // If OVER is supported, check if References is in overview format
+            // If OVER is supported, check the overview format: OverviewEntry
+            // reads fields by position, so a nonstandard order means HDR.
+            // Fields past References are only logged (see overview.rs)
             // We need this even if HDR is supported since we prefer OVER for latency
             if capabilities.over_supported {
-                if capabilities.list_variants.contains("OVERVIEW.FMT") {
+                let format = if capabilities.list_variants.contains("OVERVIEW.FMT") {
                     match client.list_overview_fmt().await {
                         Ok(format) => {
-                            // Check if References is in the overview format
-                            // Format fields are like "Subject:", "From:", "References:", etc.
-                            capabilities.references_in_overview = format
-                                .iter()
-                                .any(|field| field.eq_ignore_ascii_case("References:"));
+                            let format = OverviewFormat::parse(format.iter().map(|f| f.as_str()));
                             tracing::trace!(
-                                fields = ?format.iter().collect::<Vec<_>>(),
-                                references_found = capabilities.references_in_overview,
+                                ?format,
+                                references_found = format.has_references(),
+                                extra = ?format.extra_headers().collect::<Vec<_>>(),
                                 "OVERVIEW.FMT retrieved"
                             );
+                            if !format.has_references() {
+                                tracing::debug!(
+                                    ?format,
+                                    "Overview format has no References in its standard place"
+                                );
+                            }
+                            format
                         }
                         Err(e) => {
                             tracing::trace!(
@@ -391,20 +774,30 @@ impl NntpWorker {
                                 "Failed to get OVERVIEW.FMT, assuming standard format"
                             );
                             // Standard RFC 3977 format includes References
-                            capabilities.references_in_overview = true;
+                            OverviewFormat::standard()
                         }
                     }
                 } else {
                     // No OVERVIEW.FMT in capabilities, assume standard format
-                    capabilities.references_in_overview = true;
-                }
+                    OverviewFormat::standard()
+                };
+                capabilities.overview_format = Some(format);
             }
 
-            // Set greeting_allows_post from the client's tracking of greeting/MODE READER response
-            capabilities.greeting_allows_post = client.is_posting_allowed();
+            // Set greeting_allows_post from the client's tracking of greeting/MODE READER response.
+            // After STARTTLS the client saw a stand-in greeting, as the real one came before
+            // TLS (RFC 4642 section 2.2.2); without a MODE READER answer over TLS, posting
+            // rests on CAPABILITIES listing POST, which was asked over TLS.
+            capabilities.greeting_allows_post = client.is_posting_allowed()
+                || (self.server_config.tls_mode == Some(NntpTlsMode::Starttls)
+                    && !mode_reader_answered);
 
             // Increment connection counters now that setup is complete
             self.counters.connected.fetch_add(1, Ordering::Relaxed);
+            failures = 0;
+            if self.counters.unhealthy.swap(false, Ordering::Relaxed) {
+                tracing::info!("Server reachable again, marked healthy");
+            }
             let can_post = capabilities.can_post();
             if can_post {
                 self.counters.posting.fetch_add(1, Ordering::Relaxed);
@@ -415,50 +808,120 @@ impl NntpWorker {
                 can_post = can_post,
                 "Worker ready"
             );
+            self.affinity.connected(self.id);
 
             // Track when we last processed a low-priority request (for aging)
             let mut last_low_process = Instant::now();
 
+            // A fresh connection has no group selected
+            let mut selected: Option<SelectedGroup> = None;
+
+            // When the last request arrived and the last command was sent,
+            // for retiring idle added workers and timing keepalives
+            let mut last_request = Instant::now();
+            let mut last_command = last_request;
+
             // Process requests until connection fails or channel closes
             loop {
-                let request = match self.recv_prioritized(&mut last_low_process).await {
+                let wakeup = self
+                    .wait_for_request(&mut last_low_process, last_request, last_command)
+                    .await;
+                let received = match wakeup {
+                    Wakeup::Request(received) => received,
+                    Wakeup::KeepAlive => {
+                        last_command = Instant::now();
+                        match self.keep_alive(&mut client, &mut selected).await {
+                            Err(e) if e.requires_reconnect() => {
+                                self.release(can_post).await;
+                                self.drain_affinity_queues();
+                                tracing::warn!(error = %e, "Keepalive failed, will reconnect");
+                                break;
+                            }
+                            Err(e) => {
+                                tracing::debug!(error_kind = e.kind(), error = %e, "Keepalive refused");
+                            }
+                            Ok(()) => {}
+                        }
+                        continue;
+                    }
+                    Wakeup::Retire => {
+                        self.release(can_post).await;
+                        tracing::info!("Idle, retiring added worker");
+                        return;
+                    }
+                };
+                let queued = match received {
                     Ok(req) => req,
                     Err(_) => {
                         // Decrement counters before shutting down
-                        self.counters.connected.fetch_sub(1, Ordering::Relaxed);
-                        if can_post {
-                            self.counters.posting.fetch_sub(1, Ordering::Relaxed);
-                        }
+                        self.release(can_post).await;
                         tracing::info!("Request channels closed, worker shutting down");
                         return;
                     }
                 };
+                self.counters.load.record_wait(queued.queued_at.elapsed());
+                last_request = Instant::now();
+
+                // Skip work nobody is waiting for any more
+                if queued.request.is_abandoned() {
+                    tracing::debug!(
+                        priority = %queued.request.priority(),
+                        "Skipping request abandoned by its caller"
+                    );
+                    continue;
+                }
+
+                // Skip work for callers whose deadline passed while it was queued
+                if queued.is_expired(Instant::now()) {
+                    tracing::debug!(
+                        priority = %queued.request.priority(),
+                        "Skipping request past its deadline"
+                    );
+                    let error = NntpError::Timeout("Request deadline passed in queue".into());
+                    queued.request.respond(Err(error));
+                    continue;
+                }
+                let request = queued.request;
 
                 // Log queue depths at trace level for monitoring
                 tracing::trace!(
                     high_depth = self.queues.high.len(),
                     normal_depth = self.queues.normal.len(),
                     low_depth = self.queues.low.len(),
+                    affinity_depth =
+                        self.queues.affinity_normal.len() + self.queues.affinity_low.len(),
                     priority = %request.priority(),
                     "Processing request"
                 );
 
+                if let Some(throttle) = &self.throttle {
+                    throttle.set_priority(request.priority());
+                }
+                last_command = Instant::now();
+                self.affinity.set_busy(self.id, true);
                 let result = self
-                    .handle_request(&mut client, &request, &capabilities)
+                    .handle_request(&mut client, &request, &capabilities, &mut selected)
                     .await;
+                self.affinity.set_busy(self.id, false);
 
-                // Check if this was a connection error that requires reconnect
-                let should_reconnect = result.is_err();
+                // Only transport failures require a reconnect; replies such as
+                // "no such article" leave the connection usable
+                let should_reconnect = match &result {
+                    Err(e) if e.requires_reconnect() => true,
+                    Err(e) => {
+                        tracing::debug!(error_kind = e.kind(), error = %e, "Request failed");
+                        false
+                    }
+                    Ok(_) => false,
+                };
 
                 // Send response
                 request.respond(result);
 
                 if should_reconnect {
                     // Decrement counters before reconnecting
-                    self.counters.connected.fetch_sub(1, Ordering::Relaxed);
-                    if can_post {
-                        self.counters.posting.fetch_sub(1, Ordering::Relaxed);
-                    }
+                    self.release(can_post).await;
+                    self.drain_affinity_queues();
                     tracing::warn!("Connection error, will reconnect");
                     break;
                 }
@@ -469,7 +932,7 @@ impl NntpWorker {
     /// Handle a single request
     #[instrument(
         name = "nntp.worker.handle_request",
-        skip(self, client, request, capabilities),
+        skip(self, client, request, capabilities, selected),
         fields(operation, duration_ms)
     )]
     async fn handle_request(
@@ -477,10 +940,11 @@ impl NntpWorker {
         client: &mut NntpClient<NntpStream>,
         request: &NntpRequest,
         capabilities: &ServerCapabilities,
+        selected: &mut Option<SelectedGroup>,
     ) -> Result<NntpResponse, NntpError> {
         let start = Instant::now();
         let result = self
-            .handle_request_inner(client, request, capabilities)
+            .handle_request_inner(client, request, capabilities, selected)
             .await;
         tracing::Span::current().record("duration_ms", start.elapsed().as_millis() as u64);
         result
@@ -492,6 +956,7 @@ impl NntpWorker {
         client: &mut NntpClient<NntpStream>,
         request: &NntpRequest,
         capabilities: &ServerCapabilities,
+        selected: &mut Option<SelectedGroup>,
     ) -> Result<NntpResponse, NntpError> {
         match request {
             NntpRequest::GetGroups { .. } => {
@@ -536,7 +1001,23 @@ impl NntpWorker {
                     };
 
                     match result {
-                        Ok(group_views) => {
+                        Ok(mut group_views) => {
+                            // LIST ACTIVE carries no descriptions; a failed
+                            // second pass just leaves them out
+                            if method_name == "LIST ACTIVE" && capabilities.lists_descriptions() {
+                                match client.list_newsgroups(None).await {
+                                    Ok(descriptions) => merge_descriptions(
+                                        &mut group_views,
+                                        descriptions
+                                            .iter()
+                                            .map(|g| (g.name.as_str(), g.description.as_str())),
+                                    ),
+                                    Err(e) => tracing::debug!(
+                                        error = %e,
+                                        "LIST NEWSGROUPS failed, groups have no descriptions"
+                                    ),
+                                }
+                            }
                             tracing::debug!(
                                 variant = method_name,
                                 count = group_views.len(),
@@ -556,7 +1037,7 @@ impl NntpWorker {
                 }
 
                 // All methods failed
-                Err(NntpError(format!(
+                Err(NntpError::from_reply(format!(
                     "Server does not support listing groups. Last error: {}",
                     last_error.unwrap_or_default()
                 )))
@@ -567,11 +1048,9 @@ impl NntpWorker {
                 let method = capabilities.thread_fetch_method();
                 tracing::debug!(%group, %count, ?method, "Fetching threads");
 
-                // Select group first
-                let stats = client
-                    .group(group)
-                    .await
-                    .map_err(|e| NntpError(e.to_string()))?;
+                // Select group first; always re-issued for fresh article counts
+                let stats = client.group(group).await.map_err(NntpError::from)?;
+                self.mark_selected(selected, group).await;
 
                 // Calculate range for recent articles
                 // Use bounded range to avoid timeout with large groups
@@ -590,10 +1069,8 @@ impl NntpWorker {
                                     error = %e,
                                     "HDR fetch failed, falling back to OVER"
                                 );
-                                let entries = client
-                                    .over(Some(range))
-                                    .await
-                                    .map_err(|e| NntpError(e.to_string()))?;
+                                let entries =
+                                    client.over(Some(range)).await.map_err(NntpError::from)?;
                                 build_threads_from_overview(entries.to_vec())
                             }
                         }
@@ -603,7 +1080,7 @@ impl NntpWorker {
                         let entries = client
                             .over(Some(range.clone()))
                             .await
-                            .map_err(|e| NntpError(e.to_string()))?;
+                            .map_err(NntpError::from)?;
                         build_threads_from_overview(entries.to_vec())
                     }
                     ThreadFetchMethod::Head => {
@@ -614,21 +1091,18 @@ impl NntpWorker {
                 };
 
                 // Sort by last post date (newest first)
-                thread_views.sort_by(|a, b| {
-                    use chrono::DateTime;
-                    match (&b.last_post_date, &a.last_post_date) {
-                        (Some(b_d), Some(a_d)) => {
-                            let bp = DateTime::parse_from_rfc2822(b_d);
-                            let ap = DateTime::parse_from_rfc2822(a_d);
-                            match (bp, ap) {
-                                (Ok(b), Ok(a)) => b.cmp(&a),
-                                _ => std::cmp::Ordering::Equal,
-                            }
+                thread_views.sort_by(|a, b| match (&b.last_post_date, &a.last_post_date) {
+                    (Some(b_d), Some(a_d)) => {
+                        let bp = parse_date(b_d);
+                        let ap = parse_date(a_d);
+                        match (bp, ap) {
+                            (Some(b), Some(a)) => b.cmp(&a),
+                            _ => std::cmp::Ordering::Equal,
                         }
-                        (Some(_), None) => std::cmp::Ordering::Less,
-                        (None, Some(_)) => std::cmp::Ordering::Greater,
-                        (None, None) => std::cmp::Ordering::Equal,
                     }
+                    (Some(_), None) => std::cmp::Ordering::Less,
+                    (None, Some(_)) => std::cmp::Ordering::Greater,
+                    (None, None) => std::cmp::Ordering::Equal,
                 });
 
                 Ok(NntpResponse::Threads(thread_views))
@@ -640,7 +1114,7 @@ impl NntpWorker {
                 let article = client
                     .article(nntp_rs::ArticleSpec::MessageId(message_id.clone()))
                     .await
-                    .map_err(|e| NntpError(e.to_string()))?;
+                    .map_err(NntpError::from)?;
 
                 Ok(NntpResponse::Article(parse_article(&article)))
             }
@@ -650,10 +1124,8 @@ impl NntpWorker {
                 tracing::debug!(%group, "Fetching group stats");
 
                 // Select the group to get article range
-                let stats = client
-                    .group(group)
-                    .await
-                    .map_err(|e| NntpError(e.to_string()))?;
+                let stats = client.group(group).await.map_err(NntpError::from)?;
+                self.mark_selected(selected, group).await;
 
                 // Get the date header for the last article
                 let last_article_date = if stats.last > 0 {
@@ -676,11 +1148,9 @@ impl NntpWorker {
                             {
                                 Ok(headers_raw) => {
                                     // Parse Date header from raw headers
-                                    let headers_str = String::from_utf8_lossy(&headers_raw);
-                                    headers_str
-                                        .lines()
-                                        .find(|line| line.to_lowercase().starts_with("date:"))
-                                        .map(|line| line[5..].trim().to_string())
+                                    let headers_str = charset::decode_headers(&headers_raw);
+                                    charset::header_value(&headers_str, "date")
+                                        .map(|date| date.into_owned())
                                 }
                                 Err(e) => {
                                     tracing::warn!(
@@ -697,9 +1167,29 @@ impl NntpWorker {
                     None
                 };
 
+                // Estimate traffic from the date of an article further back.
+                // HDR only: without it, the fetch window stays at its maximum.
+                let span = NNTP_TRAFFIC_SAMPLE_ARTICLES.min(stats.count.saturating_sub(1));
+                let posts_per_day = match &last_article_date {
+                    Some(last_date) if span > 0 && capabilities.hdr_supported => {
+                        let sample = (stats.last - span).to_string();
+                        match client.hdr("Date".to_string(), Some(sample)).await {
+                            Ok(headers) => headers
+                                .first()
+                                .and_then(|h| estimate_posts_per_day(span, &h.value, last_date)),
+                            Err(e) => {
+                                tracing::debug!(%group, error = %e, "Traffic sample failed");
+                                None
+                            }
+                        }
+                    }
+                    _ => None,
+                };
+
                 Ok(NntpResponse::GroupStats(GroupStatsView {
                     last_article_date,
                     last_article_number: stats.last,
+                    posts_per_day,
                 }))
             }
 
@@ -711,21 +1201,25 @@ impl NntpWorker {
                 Span::current().record("operation", "get_new_articles");
                 tracing::debug!(%group, %since_article_number, "Fetching new articles");
 
-                // Select the group to get current article range
-                let stats = client
-                    .group(group)
-                    .await
-                    .map_err(|e| NntpError(e.to_string()))?;
-
-                if stats.last <= *since_article_number {
-                    // No new articles
-                    tracing::debug!(
-                        %group,
-                        last = stats.last,
-                        since = *since_article_number,
-                        "No new articles"
-                    );
-                    return Ok(NntpResponse::NewArticles(vec![]));
+                // Skip GROUP when this connection already has the group selected
+                let reused = selected.as_ref().is_some_and(|s| s.is_current(group));
+                if reused {
+                    tracing::debug!(%group, "Group already selected, skipping GROUP");
+                } else {
+                    // Select the group to get current article range
+                    let stats = client.group(group).await.map_err(NntpError::from)?;
+                    self.mark_selected(selected, group).await;
+
+                    if stats.last <= *since_article_number {
+                        // No new articles
+                        tracing::debug!(
+                            %group,
+                            last = stats.last,
+                            since = *since_article_number,
+                            "No new articles"
+                        );
+                        return Ok(NntpResponse::NewArticles(vec![]));
+                    }
                 }
 
                 // Fetch only new articles using OVER command with range
@@ -736,10 +1230,15 @@ impl NntpWorker {
                     "Fetching overview for range"
                 );
 
-                let entries = client
-                    .over(Some(range))
-                    .await
-                    .map_err(|e| NntpError(e.to_string()))?;
+                let entries = match client.over(Some(range)).await {
+                    Ok(entries) => entries.to_vec(),
+                    // Without GROUP the high-water mark is unknown, so an empty
+                    // range comes back as 423 rather than being skipped above
+                    Err(e) => match NntpError::from(e) {
+                        e if reused && e.is_article_missing() => Vec::new(),
+                        e => return Err(e),
+                    },
+                };
 
                 tracing::debug!(
                     %group,
@@ -747,7 +1246,7 @@ impl NntpWorker {
                     "Fetched new article overview entries"
                 );
 
-                Ok(NntpResponse::NewArticles(entries.to_vec()))
+                Ok(NntpResponse::NewArticles(entries))
             }
 
             NntpRequest::PostArticle { headers, body, .. } => {
@@ -785,7 +1284,7 @@ impl NntpWorker {
                 client
                     .post(article_content)
                     .await
-                    .map_err(|e| NntpError(e.to_string()))?;
+                    .map_err(NntpError::from)?;
 
                 Ok(NntpResponse::PostResult)
             }
@@ -799,18 +1298,10 @@ impl NntpWorker {
                     .await
                 {
                     Ok(_) => Ok(NntpResponse::ArticleExists(true)),
-                    Err(e) => {
-                        // Check if this is a "not found" error (430 or 423)
-                        let err_str = e.to_string();
-                        if err_str.contains("430")
-                            || err_str.contains("423")
-                            || err_str.to_lowercase().contains("no such article")
-                        {
-                            Ok(NntpResponse::ArticleExists(false))
-                        } else {
-                            Err(NntpError(err_str))
-                        }
-                    }
+                    Err(e) => match NntpError::from(e) {
+                        e if e.is_article_missing() => Ok(NntpResponse::ArticleExists(false)),
+                        e => Err(e),
+                    },
                 }
             }
         }
@@ -829,27 +1320,27 @@ impl NntpWorker {
         let message_ids = client
             .hdr("Message-ID".to_string(), Some(range.to_string()))
             .await
-            .map_err(|e| NntpError(format!("HDR Message-ID failed: {}", e)))?;
+            .map_err(|e| NntpError::from_reply(format!("HDR Message-ID failed: {}", e)))?;
 
         let references = client
             .hdr("References".to_string(), Some(range.to_string()))
             .await
-            .map_err(|e| NntpError(format!("HDR References failed: {}", e)))?;
+            .map_err(|e| NntpError::from_reply(format!("HDR References failed: {}", e)))?;
 
         let subjects = client
             .hdr("Subject".to_string(), Some(range.to_string()))
             .await
-            .map_err(|e| NntpError(format!("HDR Subject failed: {}", e)))?;
+            .map_err(|e| NntpError::from_reply(format!("HDR Subject failed: {}", e)))?;
 
         let froms = client
             .hdr("From".to_string(), Some(range.to_string()))
             .await
-            .map_err(|e| NntpError(format!("HDR From failed: {}", e)))?;
+            .map_err(|e| NntpError::from_reply(format!("HDR From failed: {}", e)))?;
 
         let dates = client
             .hdr("Date".to_string(), Some(range.to_string()))
             .await
-            .map_err(|e| NntpError(format!("HDR Date failed: {}", e)))?;
+            .map_err(|e| NntpError::from_reply(format!("HDR Date failed: {}", e)))?;
 
         tracing::trace!(
             message_id_count = message_ids.len(),
@@ -948,39 +1439,7 @@ impl NntpWorker {
                 .await
             {
                 Ok(headers_raw) => {
-                    let headers_str = String::from_utf8_lossy(&headers_raw);
-
-                    // Parse headers
-                    let mut message_id = String::new();
-                    let mut references = None;
-                    let mut subject = DEFAULT_SUBJECT.to_string();
-                    let mut from = String::new();
-                    let mut date = String::new();
-
-                    for line in headers_str.lines() {
-                        let line_lower = line.to_lowercase();
-                        if line_lower.starts_with("message-id:") {
-                            message_id = line[11..].trim().to_string();
-                        } else if line_lower.starts_with("references:") {
-                            references = Some(line[11..].trim().to_string());
-                        } else if line_lower.starts_with("subject:") {
-                            subject = line[8..].trim().to_string();
-                        } else if line_lower.starts_with("from:") {
-                            from = line[5..].trim().to_string();
-                        } else if line_lower.starts_with("date:") {
-                            date = line[5..].trim().to_string();
-                        }
-                    }
-
-                    if !message_id.is_empty() {
-                        articles.push(HdrArticleData {
-                            message_id,
-                            references,
-                            subject,
-                            from,
-                            date,
-                        });
-                    }
+                    articles.extend(parse_head(&headers_raw));
                 }
                 Err(e) => {
                     // Article might be deleted, skip it
@@ -1002,10 +1461,133 @@ impl NntpWorker {
     }
 }
 
+/// Posting rate from the dates of two articles `span` numbers apart.
+///
+/// Number gaps from cancelled articles make this a slight overestimate.
+/// Spans under an hour count as an hour so a burst doesn't look endless.
+fn estimate_posts_per_day(span: u64, earlier: &str, later: &str) -> Option<f64> {
+    let seconds = (parse_date(later)? - parse_date(earlier)?).num_seconds();
+    if seconds < 0 {
+        return None;
+    }
+    let days = (seconds as f64 / 86_400.0).max(1.0 / 24.0);
+    Some(span as f64 / days)
+}
+
+/// Fill in group descriptions from LIST NEWSGROUPS (name, description)
+/// pairs, skipping blank ones
+fn merge_descriptions<'a>(
+    groups: &mut [GroupView],
+    descriptions: impl IntoIterator<Item = (&'a str, &'a str)>,
+) {
+    let descriptions: HashMap<&str, &str> = descriptions
+        .into_iter()
+        .map(|(name, description)| (name, description.trim()))
+        .filter(|(_, description)| !description.is_empty())
+        .collect();
+    for group in groups {
+        if let Some(description) = descriptions.get(group.name.as_str()) {
+            group.description = Some(description.to_string());
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[tokio::test]
+    async fn test_group_affinity_tracks_one_group_per_worker() {
+        let affinity = GroupAffinity::new(2);
+        affinity.record(0, "comp.lang.rust").await;
+        affinity.record(1, "comp.lang.c").await;
+        affinity.record(0, "comp.lang.go").await;
+
+        assert_eq!(affinity.worker_for("comp.lang.rust").await, None);
+        assert_eq!(affinity.worker_for("comp.lang.go").await, Some(0));
+        assert_eq!(affinity.worker_for("comp.lang.c").await, Some(1));
+
+        affinity.forget(1).await;
+        assert_eq!(affinity.worker_for("comp.lang.c").await, None);
+    }
+
+    #[tokio::test]
+    async fn test_group_affinity_routes_to_live_workers() {
+        let affinity = GroupAffinity::new(2);
+        assert_eq!(affinity.route("comp.lang.rust").await, None);
+
+        affinity.connected(0);
+        affinity.connected(1);
+        let home = affinity.route("comp.lang.rust").await.unwrap();
+        assert_eq!(affinity.route("comp.lang.rust").await, Some(home));
+
+        // A worker that has the group selected wins over its ring position
+        affinity.record(1 - home, "comp.lang.rust").await;
+        assert_eq!(affinity.route("comp.lang.rust").await, Some(1 - home));
+
+        // Down workers are skipped
+        affinity.disconnected(1 - home).await;
+        assert_eq!(affinity.route("comp.lang.rust").await, Some(home));
+        affinity.disconnected(home).await;
+        assert_eq!(affinity.route("comp.lang.rust").await, None);
+    }
+
+    #[test]
+    fn test_group_affinity_tracks_idle_workers() {
+        let affinity = GroupAffinity::new(2);
+        assert!(affinity.is_idle(0));
+        affinity.set_busy(0, true);
+        assert!(!affinity.is_idle(0));
+        assert!(affinity.is_idle(1));
+        affinity.set_busy(0, false);
+        assert!(affinity.is_idle(0));
+        // Workers added under load are never routed to
+        assert!(!affinity.is_idle(2));
+    }
+
+    #[test]
+    fn test_selected_group_expires() {
+        let selected = SelectedGroup {
+            name: "comp.lang.rust".to_string(),
+            selected_at: Instant::now(),
+        };
+        assert!(selected.is_current("comp.lang.rust"));
+        assert!(!selected.is_current("comp.lang.c"));
+
+        let stale = SelectedGroup {
+            name: "comp.lang.rust".to_string(),
+            selected_at: Instant::now() - Duration::from_secs(NNTP_GROUP_RESELECT_SECS + 1),
+        };
+        assert!(!stale.is_current("comp.lang.rust"));
+    }
+
+    #[test]
+    fn test_estimate_posts_per_day() {
+        let rate = estimate_posts_per_day(
+            100,
+            "Mon, 1 Jan 2024 00:00:00 +0000",
+            "Thu, 11 Jan 2024 00:00:00 +0000",
+        );
+        assert_eq!(rate, Some(10.0));
+
+        // A burst within minutes counts as an hour
+        let burst = estimate_posts_per_day(
+            10,
+            "Mon, 1 Jan 2024 00:00:00 +0000",
+            "Mon, 1 Jan 2024 00:05:00 +0000",
+        );
+        assert_eq!(burst, Some(240.0));
+
+        // Clock skew and garbage give no estimate
+        let skewed = estimate_posts_per_day(
+            10,
+            "Tue, 2 Jan 2024 00:00:00 +0000",
+            "Mon, 1 Jan 2024 00:00:00 +0000",
+        );
+        assert_eq!(skewed, None);
+        assert_eq!(estimate_posts_per_day(10, "soon", "later"), None);
+    }
+
     // =============================================================================
     // ServerCapabilities tests
     // =============================================================================
@@ -1015,7 +1597,7 @@ mod tests {
         let caps = ServerCapabilities {
             over_supported: true,
             hdr_supported: true,
-            references_in_overview: true,
+            overview_format: Some(OverviewFormat::standard()),
             ..Default::default()
         };
         assert_eq!(caps.thread_fetch_method(), ThreadFetchMethod::Over);
@@ -1027,12 +1609,35 @@ mod tests {
         let caps = ServerCapabilities {
             over_supported: true,
             hdr_supported: true,
-            references_in_overview: false,
+            overview_format: None,
             ..Default::default()
         };
         assert_eq!(caps.thread_fetch_method(), ThreadFetchMethod::Over);
     }
 
+    #[test]
+    fn test_thread_fetch_method_nonstandard_overview_format() {
+        let no_references = OverviewFormat::parse(["Subject:", "From:", "Date:", "Message-ID:"]);
+        let reordered = OverviewFormat::parse(["From:", "Subject:", "Date:", "Message-ID:"]);
+
+        // HDR when OVER can't give References
+        let mut caps = ServerCapabilities {
+            over_supported: true,
+            hdr_supported: true,
+            overview_format: Some(no_references.clone()),
+            ..Default::default()
+        };
+        assert_eq!(caps.thread_fetch_method(), ThreadFetchMethod::Hdr);
+
+        // Without HDR, flat threads from OVER beat HEAD per article
+        caps.hdr_supported = false;
+        assert_eq!(caps.thread_fetch_method(), ThreadFetchMethod::Over);
+
+        // Fields OverviewEntry would read from the wrong place
+        caps.overview_format = Some(reordered);
+        assert_eq!(caps.thread_fetch_method(), ThreadFetchMethod::Head);
+    }
+
     #[test]
     fn test_thread_fetch_method_hdr_fallback() {
         // When OVER is not available, fall back to HDR
@@ -1077,13 +1682,68 @@ mod tests {
 
     #[test]
     fn test_server_capabilities_from_capabilities_parses_list_variants() {
-        let caps =
-            ServerCapabilities::from_capabilities(&["LIST ACTIVE NEWSGROUPS OVERVIEW.FMT".to_string()]);
+        let caps = ServerCapabilities::from_capabilities(&[
+            "LIST ACTIVE NEWSGROUPS OVERVIEW.FMT".to_string()
+        ]);
         assert!(caps.list_variants.contains("ACTIVE"));
         assert!(caps.list_variants.contains("NEWSGROUPS"));
         assert!(caps.list_variants.contains("OVERVIEW.FMT"));
     }
 
+    #[test]
+    fn test_server_capabilities_lists_descriptions() {
+        let caps = ServerCapabilities::from_capabilities(&["LIST ACTIVE".to_string()]);
+        assert!(!caps.lists_descriptions());
+        let caps = ServerCapabilities::from_capabilities(&["LIST ACTIVE NEWSGROUPS".to_string()]);
+        assert!(caps.lists_descriptions());
+        assert!(ServerCapabilities::default().lists_descriptions());
+    }
+
+    #[test]
+    fn test_merge_descriptions() {
+        let group = |name: &str| GroupView {
+            name: name.to_string(),
+            description: None,
+            article_count: None,
+        };
+        let mut groups = vec![
+            group("comp.lang.rust"),
+            group("comp.lang.c"),
+            group("misc.test"),
+        ];
+        merge_descriptions(
+            &mut groups,
+            [
+                ("comp.lang.rust", "The Rust language.\t"),
+                ("comp.lang.c", "  "),
+                ("alt.unlisted", "Not in LIST ACTIVE"),
+            ],
+        );
+        assert_eq!(groups[0].description.as_deref(), Some("The Rust language."));
+        assert_eq!(groups[1].description, None);
+        assert_eq!(groups[2].description, None);
+    }
+
+    #[test]
+    fn test_server_capabilities_needs_mode_reader() {
+        // Reader-only server: already in reader mode
+        let caps =
+            ServerCapabilities::from_capabilities(&["VERSION 2".to_string(), "READER".to_string()]);
+        assert!(caps.reader_advertised);
+        assert!(!caps.needs_mode_reader());
+
+        // Mode-switching server
+        let caps = ServerCapabilities::from_capabilities(&[
+            "VERSION 2".to_string(),
+            "MODE-READER".to_string(),
+        ]);
+        assert!(caps.mode_reader_advertised);
+        assert!(caps.needs_mode_reader());
+
+        // No capabilities: can't tell, so switch
+        assert!(ServerCapabilities::default().needs_mode_reader());
+    }
+
     #[test]
     fn test_server_capabilities_can_post_requires_both() {
         let mut caps = ServerCapabilities::default();
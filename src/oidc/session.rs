@@ -5,8 +5,12 @@
 //! - `AuthFlowState`: Temporary state during OAuth2 authorization flow
 //! - `CsrfToken`: Token for CSRF protection on forms
 
+use axum_extra::extract::cookie::{Cookie, SameSite};
 use serde::{Deserialize, Serialize};
 use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use time::Duration as TimeDuration;
+
+use crate::config::{POSTING_NAME_MAX_LEN, REPLY_TO_MAX_LEN};
 
 /// Authenticated user information.
 ///
@@ -26,6 +30,61 @@ pub struct User {
     /// CSRF token for form protection
     #[serde(default = "generate_csrf_token")]
     pub csrf_token: String,
+    /// Version of the posting guidelines the user accepted, if any
+    #[serde(default)]
+    pub accepted_guidelines: Option<String>,
+    /// Display name for the From header of posts, instead of `name`
+    #[serde(default)]
+    pub posting_name: Option<String>,
+    /// Address for the Reply-To header of posts
+    #[serde(default)]
+    pub reply_to: Option<String>,
+}
+
+/// A display name goes unquoted before the address in From, so it may not
+/// contain RFC 5322 specials or control characters
+fn validate_posting_name(name: &str) -> Result<String, String> {
+    if name.chars().count() > POSTING_NAME_MAX_LEN {
+        return Err(format!(
+            "Display name too long (max {} characters)",
+            POSTING_NAME_MAX_LEN
+        ));
+    }
+    if let Some(c) = name
+        .chars()
+        .find(|c| c.is_control() || "()<>[]:;@\\,\"".contains(*c))
+    {
+        return Err(format!(
+            "Display name may not contain '{}'",
+            c.escape_default()
+        ));
+    }
+    Ok(name.to_string())
+}
+
+/// Accept a single plain address (`local@domain`), without a name or brackets
+fn validate_reply_to(address: &str) -> Result<String, String> {
+    let invalid = || format!("'{}' is not a valid email address", address);
+    if address.len() > REPLY_TO_MAX_LEN
+        || !address.is_ascii()
+        || address
+            .chars()
+            .any(|c| c.is_ascii_control() || c.is_whitespace() || "()<>[]:;,\\\"".contains(c))
+    {
+        return Err(invalid());
+    }
+    match address.split_once('@') {
+        Some((local, domain))
+            if !local.is_empty()
+                && !domain.contains('@')
+                && domain.contains('.')
+                && !domain.starts_with('.')
+                && !domain.ends_with('.') =>
+        {
+            Ok(address.to_string())
+        }
+        _ => Err(invalid()),
+    }
 }
 
 /// Generate a random CSRF token
@@ -67,6 +126,9 @@ impl User {
             provider,
             expires_at,
             csrf_token: generate_csrf_token(),
+            accepted_guidelines: None,
+            posting_name: None,
+            reply_to: None,
         }
     }
 
@@ -110,6 +172,50 @@ impl User {
             .unwrap_or(&self.sub)
     }
 
+    /// Name to put in the From header of posts
+    pub fn posting_display_name(&self) -> Option<&str> {
+        self.posting_name.as_deref().or(self.name.as_deref())
+    }
+
+    /// Set the display name and Reply-To address used on posts. Blank values
+    /// clear them. Nothing changes if either is invalid.
+    pub fn set_posting_identity(&mut self, name: &str, reply_to: &str) -> Result<(), String> {
+        let name = name.trim();
+        let reply_to = reply_to.trim();
+        let name = if name.is_empty() {
+            None
+        } else {
+            Some(validate_posting_name(name)?)
+        };
+        let reply_to = if reply_to.is_empty() {
+            None
+        } else {
+            Some(validate_reply_to(reply_to)?)
+        };
+        self.posting_name = name;
+        self.reply_to = reply_to;
+        Ok(())
+    }
+
+    /// Build a session cookie holding this user, valid until the session expires.
+    /// Used by handlers that change session state (guideline acceptance, posting identity).
+    pub fn to_cookie(&self) -> Result<Cookie<'static>, serde_json::Error> {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        let remaining = self.expires_at.saturating_sub(now);
+
+        Ok(
+            Cookie::build((cookie_names::SESSION, serde_json::to_string(self)?))
+                .path("/")
+                .http_only(true)
+                .same_site(SameSite::Lax)
+                .max_age(TimeDuration::seconds(remaining as i64))
+                .build(),
+        )
+    }
+
     /// Validate a CSRF token against the session's token
     pub fn validate_csrf(&self, token: &str) -> bool {
         // Use constant-time comparison to prevent timing attacks
@@ -369,6 +475,38 @@ mod tests {
         assert!(!user.validate_csrf("this_is_a_very_long_token_that_is_longer_than_expected"));
     }
 
+    #[test]
+    fn test_user_set_posting_identity() {
+        let mut user = User::new(
+            "sub123".to_string(),
+            Some("John Doe".to_string()),
+            Some("john@example.com".to_string()),
+            "google".to_string(),
+            Duration::from_secs(3600),
+        );
+        assert_eq!(user.posting_display_name(), Some("John Doe"));
+
+        user.set_posting_identity("  J. Doe ", "replies@example.org")
+            .unwrap();
+        assert_eq!(user.posting_display_name(), Some("J. Doe"));
+        assert_eq!(user.reply_to.as_deref(), Some("replies@example.org"));
+
+        // Invalid values leave the identity unchanged
+        assert!(user.set_posting_identity("Doe <x@y.z>", "").is_err());
+        assert!(user.set_posting_identity("Ok", "not an address").is_err());
+        assert!(user.set_posting_identity("Ok", "a@b@example.org").is_err());
+        assert!(user.set_posting_identity("Ok", "a@localhost").is_err());
+        assert!(user
+            .set_posting_identity(&"x".repeat(POSTING_NAME_MAX_LEN + 1), "")
+            .is_err());
+        assert_eq!(user.posting_display_name(), Some("J. Doe"));
+
+        // Blank values clear it
+        user.set_posting_identity("", " ").unwrap();
+        assert_eq!(user.posting_display_name(), Some("John Doe"));
+        assert_eq!(user.reply_to, None);
+    }
+
     #[test]
     fn test_auth_flow_state_new_sets_expiry() {
         let state = AuthFlowState::new(
@@ -0,0 +1,426 @@
+//! Moderator review of new users' first posts.
+//!
+//! With `[posting] review_first_posts` set, a user's posts are held until a
+//! moderator has approved that many of them, so a new account can't damage
+//! the instance's posting reputation before anyone has looked at it. Held
+//! posts wait in a queue that the subjects in `[posting] moderators` work
+//! through on `/admin/review`: approving sends the post to the NNTP server
+//! as it was submitted, rejecting drops it. Moderators' own posts are never
+//! held.
+//!
+//! The queue and the number of approved posts per user are kept in the JSON
+//! file at `review_queue_path`, rewritten after every change, so held posts
+//! survive a restart. The file also counts posts held, approved and rejected
+//! per month, without saying whose, for the transparency report.
+
+use std::collections::{BTreeMap, HashMap};
+use std::io;
+use std::path::PathBuf;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use tokio::sync::Mutex;
+use uuid::Uuid;
+
+use crate::config::{ConfigError, TRANSPARENCY_REPORT_MONTHS};
+
+/// A post waiting for review
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HeldPost {
+    /// Queue entry ID, used in the approve and reject URLs
+    pub id: String,
+    /// The poster as "provider:sub"
+    pub poster: String,
+    pub submitted_at: DateTime<Utc>,
+    #[serde(flatten)]
+    pub article: HeldArticle,
+}
+
+impl HeldPost {
+    /// Give the article a queue ID and a submission time
+    pub fn new(poster: String, article: HeldArticle) -> Self {
+        Self {
+            id: Uuid::new_v4().simple().to_string(),
+            poster,
+            submitted_at: Utc::now(),
+            article,
+        }
+    }
+}
+
+/// Everything needed to send a held post later, as it was submitted
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HeldArticle {
+    pub group: String,
+    pub subject: String,
+    pub body: String,
+    /// From header, built when the post was submitted
+    pub from: String,
+    pub reply_to: Option<String>,
+    pub references: Option<String>,
+    pub root_message_id: Option<String>,
+    pub parent_message_id: Option<String>,
+}
+
+/// Contents of the queue file
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct QueueFile {
+    #[serde(default)]
+    held: Vec<HeldPost>,
+    /// Approved posts per "provider:sub"
+    #[serde(default)]
+    approved: HashMap<String, usize>,
+    /// Review decisions per month ("2026-05"), the last
+    /// `TRANSPARENCY_REPORT_MONTHS` only
+    #[serde(default)]
+    tallies: BTreeMap<String, ReviewTally>,
+}
+
+impl QueueFile {
+    /// The tally for the month of `now`, dropping months too old to report
+    fn tally(&mut self, now: DateTime<Utc>) -> &mut ReviewTally {
+        let month = now.format("%Y-%m").to_string();
+        while self.tallies.len() >= TRANSPARENCY_REPORT_MONTHS && !self.tallies.contains_key(&month)
+        {
+            self.tallies.pop_first();
+        }
+        self.tallies.entry(month).or_default()
+    }
+}
+
+/// Review decisions in one month
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReviewTally {
+    pub held: u64,
+    pub approved: u64,
+    pub rejected: u64,
+}
+
+/// A month's review decisions, for the transparency report
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct MonthlyTally {
+    /// "2026-05"
+    pub month: String,
+    #[serde(flatten)]
+    pub tally: ReviewTally,
+}
+
+/// What the queue stores about one poster, for their account export
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct PosterReview {
+    /// Their posts waiting for review
+    pub held: Vec<HeldPost>,
+    /// Their posts a moderator has approved
+    pub approved: usize,
+}
+
+/// The review queue and its file
+pub struct ReviewQueue {
+    /// Approved posts needed before a user's posts go out directly
+    threshold: usize,
+    path: Option<PathBuf>,
+    /// Held while the file is written, so writes can't overtake each other
+    contents: Mutex<QueueFile>,
+}
+
+impl ReviewQueue {
+    /// No review, for instances without `review_first_posts`
+    pub fn disabled() -> Self {
+        Self {
+            threshold: 0,
+            path: None,
+            contents: Mutex::new(QueueFile::default()),
+        }
+    }
+
+    /// Read the queue at `path`. A missing file is an empty queue; one that
+    /// doesn't parse is a configuration error.
+    pub fn load(path: &str, threshold: usize) -> Result<Self, ConfigError> {
+        let path = PathBuf::from(path);
+        let contents = match std::fs::read_to_string(&path) {
+            Ok(source) => serde_json::from_str(&source).map_err(|e| {
+                ConfigError::Validation(format!("Review queue '{}': {}", path.display(), e))
+            })?,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => QueueFile::default(),
+            Err(e) => {
+                return Err(ConfigError::Validation(format!(
+                    "Failed to read review queue '{}': {}",
+                    path.display(),
+                    e
+                )))
+            }
+        };
+        Ok(Self {
+            threshold,
+            path: Some(path),
+            contents: Mutex::new(contents),
+        })
+    }
+
+    /// Whether posts are reviewed at all
+    pub fn is_enabled(&self) -> bool {
+        self.threshold > 0
+    }
+
+    /// Whether posts by `poster` ("provider:sub") still need review
+    pub async fn needs_review(&self, poster: &str) -> bool {
+        if self.threshold == 0 {
+            return false;
+        }
+        let contents = self.contents.lock().await;
+        contents.approved.get(poster).copied().unwrap_or(0) < self.threshold
+    }
+
+    /// Posts waiting for review, oldest first
+    pub async fn held(&self) -> Vec<HeldPost> {
+        self.contents.lock().await.held.clone()
+    }
+
+    /// Number of posts waiting for review
+    pub async fn held_count(&self) -> usize {
+        self.contents.lock().await.held.len()
+    }
+
+    /// Number of posts by `poster` waiting for review
+    pub async fn held_by(&self, poster: &str) -> usize {
+        let contents = self.contents.lock().await;
+        contents
+            .held
+            .iter()
+            .filter(|post| post.poster == poster)
+            .count()
+    }
+
+    /// Add a post to the end of the queue
+    pub async fn hold(&self, post: HeldPost) -> io::Result<()> {
+        let mut contents = self.contents.lock().await;
+        contents.held.push(post);
+        contents.tally(Utc::now()).held += 1;
+        if let Err(e) = self.save(&contents).await {
+            contents.held.pop();
+            contents.tally(Utc::now()).held -= 1;
+            return Err(e);
+        }
+        Ok(())
+    }
+
+    /// Remove a post from the queue, to send or drop it
+    pub async fn take(&self, id: &str) -> io::Result<Option<HeldPost>> {
+        let mut contents = self.contents.lock().await;
+        let Some(index) = contents.held.iter().position(|post| post.id == id) else {
+            return Ok(None);
+        };
+        let post = contents.held.remove(index);
+        if let Err(e) = self.save(&contents).await {
+            contents.held.insert(index, post);
+            return Err(e);
+        }
+        Ok(Some(post))
+    }
+
+    /// Put back a post whose approval failed to send, at the front
+    pub async fn restore(&self, post: HeldPost) -> io::Result<()> {
+        let mut contents = self.contents.lock().await;
+        contents.held.insert(0, post);
+        self.save(&contents).await
+    }
+
+    /// Count an approved post towards its poster's threshold
+    pub async fn record_approved(&self, poster: &str) -> io::Result<()> {
+        let mut contents = self.contents.lock().await;
+        *contents.approved.entry(poster.to_string()).or_insert(0) += 1;
+        contents.tally(Utc::now()).approved += 1;
+        self.save(&contents).await
+    }
+
+    /// Count a rejected post in this month's tally
+    pub async fn record_rejected(&self) -> io::Result<()> {
+        let mut contents = self.contents.lock().await;
+        contents.tally(Utc::now()).rejected += 1;
+        self.save(&contents).await
+    }
+
+    /// Held posts and the approval count of `poster` ("provider:sub")
+    pub async fn export(&self, poster: &str) -> PosterReview {
+        let contents = self.contents.lock().await;
+        PosterReview {
+            held: contents
+                .held
+                .iter()
+                .filter(|post| post.poster == poster)
+                .cloned()
+                .collect(),
+            approved: contents.approved.get(poster).copied().unwrap_or(0),
+        }
+    }
+
+    /// Drop `poster`'s held posts and approval count, when they delete their
+    /// account data. The anonymous monthly tallies are kept.
+    pub async fn forget(&self, poster: &str) -> io::Result<()> {
+        let mut contents = self.contents.lock().await;
+        if !contents.approved.contains_key(poster)
+            && !contents.held.iter().any(|post| post.poster == poster)
+        {
+            return Ok(());
+        }
+        let previous = contents.clone();
+        contents.held.retain(|post| post.poster != poster);
+        contents.approved.remove(poster);
+        if let Err(e) = self.save(&contents).await {
+            *contents = previous;
+            return Err(e);
+        }
+        Ok(())
+    }
+
+    /// Review decisions per month, newest first
+    pub async fn monthly_tallies(&self) -> Vec<MonthlyTally> {
+        let contents = self.contents.lock().await;
+        contents
+            .tallies
+            .iter()
+            .rev()
+            .map(|(month, tally)| MonthlyTally {
+                month: month.clone(),
+                tally: *tally,
+            })
+            .collect()
+    }
+
+    /// Replace the file with `contents`, through a temporary file so a
+    /// crash never leaves it half written
+    async fn save(&self, contents: &QueueFile) -> io::Result<()> {
+        let Some(path) = self.path.clone() else {
+            return Ok(());
+        };
+        let json = serde_json::to_vec_pretty(contents)?;
+        tokio::task::spawn_blocking(move || {
+            let tmp = path.with_extension("json.tmp");
+            std::fs::write(&tmp, json)?;
+            std::fs::rename(&tmp, &path)
+        })
+        .await
+        .map_err(io::Error::other)?
+    }
+}
+
+/// How held posts identify their poster: "provider:sub"
+pub fn poster_id(provider: &str, sub: &str) -> String {
+    format!("{}:{}", provider, sub)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn article(subject: &str) -> HeldArticle {
+        HeldArticle {
+            group: "test.group".to_string(),
+            subject: subject.to_string(),
+            body: "Hello".to_string(),
+            from: "New User <new@example.com>".to_string(),
+            reply_to: None,
+            references: None,
+            root_message_id: None,
+            parent_message_id: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_posts_need_review_until_threshold_approved() {
+        let queue = ReviewQueue {
+            threshold: 2,
+            path: None,
+            contents: Mutex::new(QueueFile::default()),
+        };
+        let poster = poster_id("google", "123");
+        assert!(queue.needs_review(&poster).await);
+
+        let first = HeldPost::new(poster.clone(), article("First"));
+        let second = HeldPost::new(poster.clone(), article("Second"));
+        queue.hold(first.clone()).await.unwrap();
+        queue.hold(second.clone()).await.unwrap();
+        assert_eq!(queue.held_count().await, 2);
+        assert_eq!(queue.held_by(&poster).await, 2);
+
+        let taken = queue.take(&first.id).await.unwrap().unwrap();
+        assert_eq!(taken, first);
+        assert!(queue.take(&first.id).await.unwrap().is_none());
+        queue.record_approved(&poster).await.unwrap();
+        assert!(queue.needs_review(&poster).await);
+
+        queue.take(&second.id).await.unwrap();
+        queue.record_approved(&poster).await.unwrap();
+        assert!(!queue.needs_review(&poster).await);
+        assert!(queue.needs_review(&poster_id("google", "456")).await);
+        assert!(!ReviewQueue::disabled().needs_review(&poster).await);
+    }
+
+    #[tokio::test]
+    async fn test_forget_drops_posters_held_posts_and_approvals() {
+        let queue = ReviewQueue {
+            threshold: 2,
+            path: None,
+            contents: Mutex::new(QueueFile::default()),
+        };
+        let poster = poster_id("google", "123");
+        let other = HeldPost::new(poster_id("google", "456"), article("Other"));
+        let held = HeldPost::new(poster.clone(), article("Mine"));
+        queue.hold(held.clone()).await.unwrap();
+        queue.hold(other.clone()).await.unwrap();
+        queue.record_approved(&poster).await.unwrap();
+        assert_eq!(
+            queue.export(&poster).await,
+            PosterReview {
+                held: vec![held],
+                approved: 1,
+            }
+        );
+
+        queue.forget(&poster).await.unwrap();
+        assert_eq!(queue.export(&poster).await, PosterReview::default());
+        assert_eq!(queue.held().await, vec![other]);
+        assert_eq!(queue.monthly_tallies().await[0].tally.held, 2);
+    }
+
+    #[tokio::test]
+    async fn test_queue_survives_reload() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("review.json");
+        let path_str = path.to_str().unwrap();
+
+        let queue = ReviewQueue::load(path_str, 1).unwrap();
+        let post = HeldPost::new(poster_id("github", "42"), article("Held"));
+        queue.hold(post.clone()).await.unwrap();
+
+        let reloaded = ReviewQueue::load(path_str, 1).unwrap();
+        assert_eq!(reloaded.held().await, vec![post]);
+    }
+
+    #[test]
+    fn test_tallies_keep_recent_months() {
+        use chrono::TimeZone;
+
+        let mut file = QueueFile::default();
+        let month = |n: usize| {
+            Utc.with_ymd_and_hms(2024 + (n / 12) as i32, (n % 12) as u32 + 1, 15, 0, 0, 0)
+                .unwrap()
+        };
+        // One month more than the report keeps
+        for n in 0..=TRANSPARENCY_REPORT_MONTHS {
+            file.tally(month(n)).held += 1;
+        }
+        file.tally(month(TRANSPARENCY_REPORT_MONTHS)).approved += 1;
+
+        assert_eq!(file.tallies.len(), TRANSPARENCY_REPORT_MONTHS);
+        assert!(!file.tallies.contains_key("2024-01"));
+        assert_eq!(
+            file.tallies.values().next_back(),
+            Some(&ReviewTally {
+                held: 1,
+                approved: 1,
+                rejected: 0
+            })
+        );
+    }
+}
@@ -0,0 +1,236 @@
+//! Instance information pages.
+//!
+//! Routes:
+//! - GET /about - Description, rules, contact and policies from `[instance]`
+//! - GET /about/stats - Instance-level numbers from the metrics registry
+//! - GET /about/status - Upstream availability over the last 30 days
+//! - GET /about/transparency - Warrant canary and moderation counts
+
+use axum::{
+    extract::State,
+    response::{Html, IntoResponse, Response},
+    Extension,
+};
+use chrono::Utc;
+use http::StatusCode;
+use pulldown_cmark::{html, Options, Parser};
+use tracing::instrument;
+
+use super::{insert_auth_context, insert_canonical_url};
+use crate::config::{
+    AVAILABILITY_HISTORY_DAYS, SECONDS_PER_DAY, SECONDS_PER_HOUR, SECONDS_PER_MINUTE,
+    TRANSPARENCY_REPORT_MONTHS,
+};
+use crate::error::{AppError, AppErrorResponse, ResultExt};
+use crate::metrics::metrics;
+use crate::middleware::{CurrentUser, RenderMode, RequestId};
+use crate::state::AppState;
+use crate::templates::render;
+
+/// About page. Sections for `[instance]` settings that aren't configured
+/// are left out.
+#[instrument(
+    name = "about::index",
+    skip(state, request_id, current_user, render_mode)
+)]
+pub async fn index(
+    State(state): State<AppState>,
+    Extension(request_id): Extension<RequestId>,
+    Extension(current_user): Extension<CurrentUser>,
+    Extension(render_mode): Extension<RenderMode>,
+) -> Result<Html<String>, AppErrorResponse> {
+    let instance = &state.config.instance;
+    let cache = &state.config.cache;
+
+    let mut context = tera::Context::new();
+    context.insert("config", &state.config.ui);
+    context.insert("instance", instance);
+    context.insert(
+        "description_html",
+        &instance.description.as_deref().map(render_markdown),
+    );
+    context.insert("servers", &state.nntp.server_names());
+    context.insert(
+        "article_cache_period",
+        &format_uptime(cache.article_ttl_seconds as i64),
+    );
+    context.insert(
+        "thread_cache_period",
+        &format_uptime(cache.threads_ttl_seconds as i64),
+    );
+    context.insert("transparency_report", &has_transparency_report(&state));
+
+    insert_canonical_url(&mut context, &state, "/about");
+    insert_auth_context(&mut context, &state, &current_user, false);
+
+    let html = render(state.tera_for(render_mode), "about/index.html", &context)
+        .map_err(AppError::from)
+        .with_request_id(&request_id)?;
+    Ok(Html(html))
+}
+
+/// Instance statistics page. Upstream servers are listed by configured
+/// name only; hosts and ports are not shown.
+#[instrument(
+    name = "about::stats",
+    skip(state, request_id, current_user, render_mode)
+)]
+pub async fn stats(
+    State(state): State<AppState>,
+    Extension(request_id): Extension<RequestId>,
+    Extension(current_user): Extension<CurrentUser>,
+    Extension(render_mode): Extension<RenderMode>,
+) -> Result<Html<String>, AppErrorResponse> {
+    let metrics = metrics();
+
+    let mut context = tera::Context::new();
+    context.insert("config", &state.config.ui);
+    context.insert("groups_tracked", &state.nntp.cached_group_count().await);
+    context.insert("articles_cached", &state.nntp.cached_article_count());
+    context.insert("requests_served", &metrics.requests_served());
+    context.insert("uptime", &format_uptime(metrics.uptime_secs() as i64));
+    context.insert("caches", &metrics.cache_stats());
+    context.insert("renders", &metrics.render_stats());
+    context.insert("servers", &state.nntp.server_names());
+    context.insert("instance", &state.config.instance);
+
+    insert_canonical_url(&mut context, &state, "/about/stats");
+    insert_auth_context(&mut context, &state, &current_user, false);
+
+    let html = render(state.tera_for(render_mode), "about/stats.html", &context)
+        .map_err(AppError::from)
+        .with_request_id(&request_id)?;
+    Ok(Html(html))
+}
+
+/// Upstream status page. 404 when `[ui] status_page` is off.
+#[instrument(
+    name = "about::status",
+    skip(state, request_id, current_user, render_mode)
+)]
+pub async fn status(
+    State(state): State<AppState>,
+    Extension(request_id): Extension<RequestId>,
+    Extension(current_user): Extension<CurrentUser>,
+    Extension(render_mode): Extension<RenderMode>,
+) -> Result<Response, AppErrorResponse> {
+    if !state.config.ui.status_page {
+        return Ok(StatusCode::NOT_FOUND.into_response());
+    }
+
+    let mut context = tera::Context::new();
+    context.insert("config", &state.config.ui);
+    context.insert("servers", &state.nntp.availability_report().await);
+    context.insert("history_days", &AVAILABILITY_HISTORY_DAYS);
+
+    insert_canonical_url(&mut context, &state, "/about/status");
+    insert_auth_context(&mut context, &state, &current_user, false);
+
+    let html = render(state.tera_for(render_mode), "about/status.html", &context)
+        .map_err(AppError::from)
+        .with_request_id(&request_id)?;
+    Ok(Html(html).into_response())
+}
+
+/// Transparency report: the warrant canary from `[instance] warrant_canary`
+/// and how many held posts moderators approved and rejected per month. It
+/// is generated on each request, so the date on it is always current; a
+/// canary the operator stops renewing is removed from the config instead.
+/// 404 when there is neither a canary nor post review.
+#[instrument(
+    name = "about::transparency",
+    skip(state, request_id, current_user, render_mode)
+)]
+pub async fn transparency(
+    State(state): State<AppState>,
+    Extension(request_id): Extension<RequestId>,
+    Extension(current_user): Extension<CurrentUser>,
+    Extension(render_mode): Extension<RenderMode>,
+) -> Result<Response, AppErrorResponse> {
+    if !has_transparency_report(&state) {
+        return Ok(StatusCode::NOT_FOUND.into_response());
+    }
+
+    let mut context = tera::Context::new();
+    context.insert("config", &state.config.ui);
+    context.insert("instance", &state.config.instance);
+    context.insert(
+        "canary_html",
+        &state
+            .config
+            .instance
+            .warrant_canary
+            .as_deref()
+            .map(render_markdown),
+    );
+    context.insert("review_enabled", &state.review.is_enabled());
+    context.insert(
+        "review_first_posts",
+        &state.config.posting.review_first_posts,
+    );
+    context.insert("tallies", &state.review.monthly_tallies().await);
+    context.insert("report_months", &TRANSPARENCY_REPORT_MONTHS);
+    context.insert(
+        "generated_at",
+        &Utc::now().format("%Y-%m-%d %H:%M UTC").to_string(),
+    );
+
+    insert_canonical_url(&mut context, &state, "/about/transparency");
+    insert_auth_context(&mut context, &state, &current_user, false);
+
+    let html = render(
+        state.tera_for(render_mode),
+        "about/transparency.html",
+        &context,
+    )
+    .map_err(AppError::from)
+    .with_request_id(&request_id)?;
+    Ok(Html(html).into_response())
+}
+
+/// Whether there is anything for the transparency report to show
+fn has_transparency_report(state: &AppState) -> bool {
+    state.config.instance.warrant_canary.is_some() || state.review.is_enabled()
+}
+
+/// Render operator-supplied Markdown. The source comes from the config
+/// file, so the HTML is trusted like the guidelines document.
+fn render_markdown(source: &str) -> String {
+    let mut out = String::with_capacity(source.len() * 3 / 2);
+    html::push_html(&mut out, Parser::new_ext(source, Options::all()));
+    out
+}
+
+/// Format a period as "3d 4h", "5h 12m" or "12m"
+fn format_uptime(secs: i64) -> String {
+    let (days, hours) = (
+        secs / SECONDS_PER_DAY,
+        secs % SECONDS_PER_DAY / SECONDS_PER_HOUR,
+    );
+    let minutes = secs % SECONDS_PER_HOUR / SECONDS_PER_MINUTE;
+    if days > 0 {
+        format!("{}d {}h", days, hours)
+    } else if hours > 0 {
+        format!("{}h {}m", hours, minutes)
+    } else {
+        format!("{}m", minutes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_markdown() {
+        let html = render_markdown("Run by **volunteers**.");
+        assert!(html.contains("<strong>volunteers</strong>"));
+    }
+
+    #[test]
+    fn test_format_uptime() {
+        assert_eq!(format_uptime(59), "0m");
+        assert_eq!(format_uptime(3 * 3600 + 125), "3h 2m");
+        assert_eq!(format_uptime(2 * 86400 + 5 * 3600 + 59), "2d 5h");
+    }
+}
@@ -0,0 +1,331 @@
+//! Account data routes.
+//!
+//! Routes:
+//! - GET /account - Show what is stored about the logged-in user
+//! - GET /account/export - Download that data as JSON (Range requests supported)
+//! - POST /account/delete - Delete that data and end the session
+//! - POST /account/invite - Redeem an invite code (invite-only posting)
+//! - POST /account/identity - Set the display name and Reply-To used on posts
+//!
+//! Per-user data lives in the encrypted session cookie, which also holds the
+//! posting identity: there is no server-side store of preferences,
+//! bookmarks, history or drafts. The exceptions are a redeemed invite code,
+//! recorded in the invites file so it outlasts the session (see
+//! `crate::invites`), and posts held for review with the count of approved
+//! ones, kept in the review queue (see `crate::review`). Both are exported,
+//! and deleting the account data removes the cookie and those records;
+//! posts already sent to NNTP servers are outside the gateway's control.
+
+use axum::{
+    extract::{Query, State},
+    response::{Html, Redirect, Response},
+    Extension, Form,
+};
+use axum_extra::extract::cookie::{Cookie, PrivateCookieJar};
+use chrono::{DateTime, Utc};
+use http::{header::CONTENT_DISPOSITION, HeaderMap, HeaderValue};
+use serde::{Deserialize, Serialize};
+use time::Duration as TimeDuration;
+use tracing::instrument;
+
+use super::insert_auth_context;
+use crate::error::{AppError, AppErrorResponse, ResultExt};
+use crate::http::range::ranged;
+use crate::middleware::{CurrentUser, RenderMode, RequestId, RequireAuth};
+use crate::oidc::session::{cookie_names, User};
+use crate::review::{poster_id, PosterReview};
+use crate::state::AppState;
+use crate::templates::render;
+
+/// Query parameters for the account page
+#[derive(Debug, Deserialize)]
+pub struct AccountQuery {
+    /// Set to "invalid" after a rejected invite code
+    pub invite: Option<String>,
+    /// Set to "saved" after the posting identity was updated
+    pub identity: Option<String>,
+}
+
+/// Form data for account deletion
+#[derive(Debug, Deserialize)]
+pub struct DeleteForm {
+    pub csrf_token: String,
+}
+
+/// Form data for invite code redemption
+#[derive(Debug, Deserialize)]
+pub struct InviteForm {
+    pub csrf_token: String,
+    pub code: String,
+}
+
+/// Form data for the posting identity
+#[derive(Debug, Deserialize)]
+pub struct IdentityForm {
+    pub csrf_token: String,
+    #[serde(default)]
+    pub posting_name: String,
+    #[serde(default)]
+    pub reply_to: String,
+}
+
+/// Exported account data. The CSRF token is deliberately left out, and
+/// there is no export time, so the same data always serializes to the same
+/// bytes and an interrupted download can be resumed.
+#[derive(Debug, Serialize)]
+pub struct AccountExport {
+    pub sub: String,
+    pub name: Option<String>,
+    pub email: Option<String>,
+    pub provider: String,
+    pub invited: bool,
+    /// Posts held for review and the number approved
+    pub review: PosterReview,
+    pub accepted_guidelines: Option<String>,
+    pub posting_name: Option<String>,
+    pub reply_to: Option<String>,
+    pub session_expires_at: Option<DateTime<Utc>>,
+}
+
+impl AccountExport {
+    /// `invited`: whether the user has redeemed an invite code
+    fn from_user(user: &User, invited: bool, review: PosterReview) -> Self {
+        Self {
+            sub: user.sub.clone(),
+            name: user.name.clone(),
+            email: user.email.clone(),
+            provider: user.provider.clone(),
+            invited,
+            review,
+            accepted_guidelines: user.accepted_guidelines.clone(),
+            posting_name: user.posting_name.clone(),
+            reply_to: user.reply_to.clone(),
+            session_expires_at: DateTime::from_timestamp(user.expires_at as i64, 0),
+        }
+    }
+}
+
+/// Account overview page
+#[instrument(
+    name = "account::view",
+    skip(state, request_id, current_user, render_mode, auth)
+)]
+pub async fn view(
+    State(state): State<AppState>,
+    Extension(request_id): Extension<RequestId>,
+    Extension(current_user): Extension<CurrentUser>,
+    Extension(render_mode): Extension<RenderMode>,
+    auth: RequireAuth,
+    Query(query): Query<AccountQuery>,
+) -> Result<Html<String>, AppErrorResponse> {
+    let mut context = tera::Context::new();
+    context.insert("config", &state.config.ui);
+    let poster = poster_id(&auth.user.provider, &auth.user.sub);
+    let invited = state.invites.has_redeemed(&poster).await;
+    let review = state.review.export(&poster).await;
+    context.insert(
+        "account",
+        &AccountExport::from_user(&auth.user, invited, review),
+    );
+    context.insert("invite_only", &state.invites.is_enabled());
+    context.insert("can_post", &state.can_post(&auth.user).await);
+    context.insert(
+        "invite_invalid",
+        &(query.invite.as_deref() == Some("invalid")),
+    );
+    if state
+        .config
+        .posting
+        .is_moderator(&auth.user.provider, &auth.user.sub)
+    {
+        context.insert("review_held", &state.review.held_count().await);
+        context.insert("manage_invites", &state.invites.is_enabled());
+    }
+    context.insert(
+        "identity_saved",
+        &(query.identity.as_deref() == Some("saved")),
+    );
+
+    insert_auth_context(&mut context, &state, &current_user, true);
+
+    let html = render(state.tera_for(render_mode), "account.html", &context)
+        .map_err(AppError::from)
+        .with_request_id(&request_id)?;
+    Ok(Html(html))
+}
+
+/// Download the stored account data as a JSON attachment. Range requests
+/// are honoured, and the ETag only changes when the data does.
+#[instrument(name = "account::export", skip(state, request_id, auth, headers))]
+pub async fn export(
+    State(state): State<AppState>,
+    Extension(request_id): Extension<RequestId>,
+    auth: RequireAuth,
+    headers: HeaderMap,
+) -> Result<Response, AppErrorResponse> {
+    let poster = poster_id(&auth.user.provider, &auth.user.sub);
+    let invited = state.invites.has_redeemed(&poster).await;
+    let review = state.review.export(&poster).await;
+    let body = serde_json::to_vec(&AccountExport::from_user(&auth.user, invited, review))
+        .map_err(|e| AppError::Internal(format!("Failed to serialize account data: {}", e)))
+        .with_request_id(&request_id)?;
+    let mut response = ranged(&headers, body, "application/json");
+    response.headers_mut().insert(
+        CONTENT_DISPOSITION,
+        HeaderValue::from_static("attachment; filename=\"september-account.json\""),
+    );
+    Ok(response)
+}
+
+/// Delete the stored account data by removing the session cookie, any
+/// invite redemption, and held posts and approvals in the review queue
+#[instrument(name = "account::delete", skip(state, request_id, auth, jar, form))]
+pub async fn delete(
+    State(state): State<AppState>,
+    Extension(request_id): Extension<RequestId>,
+    auth: RequireAuth,
+    jar: PrivateCookieJar,
+    Form(form): Form<DeleteForm>,
+) -> Result<(PrivateCookieJar, Redirect), AppErrorResponse> {
+    if !auth.user.validate_csrf(&form.csrf_token) {
+        return Err(AppError::Internal(
+            "Invalid form submission. Please try again.".into(),
+        ))
+        .with_request_id(&request_id);
+    }
+
+    let poster = poster_id(&auth.user.provider, &auth.user.sub);
+    state
+        .invites
+        .forget(&poster)
+        .await
+        .map_err(|e| AppError::Internal(format!("Failed to update invites: {}", e)))
+        .with_request_id(&request_id)?;
+    state
+        .review
+        .forget(&poster)
+        .await
+        .map_err(|e| AppError::Internal(format!("Failed to update review queue: {}", e)))
+        .with_request_id(&request_id)?;
+
+    let remove_cookie = Cookie::build((cookie_names::SESSION, ""))
+        .path("/")
+        .max_age(TimeDuration::ZERO)
+        .build();
+
+    tracing::info!(provider = %auth.user.provider, "Account data deleted");
+    Ok((jar.remove(remove_cookie), Redirect::to("/")))
+}
+
+/// Redeem an invite code, granting posting access to the account. The
+/// redemption is kept in the invites file, so it outlasts the session.
+#[instrument(name = "account::invite", skip(state, request_id, auth, form))]
+pub async fn invite(
+    State(state): State<AppState>,
+    Extension(request_id): Extension<RequestId>,
+    auth: RequireAuth,
+    Form(form): Form<InviteForm>,
+) -> Result<Redirect, AppErrorResponse> {
+    let user = auth.user;
+    if !user.validate_csrf(&form.csrf_token) {
+        return Err(AppError::Internal(
+            "Invalid form submission. Please try again.".into(),
+        ))
+        .with_request_id(&request_id);
+    }
+
+    let redeemed = state
+        .invites
+        .redeem(&form.code, &poster_id(&user.provider, &user.sub))
+        .await
+        .map_err(|e| AppError::Internal(format!("Failed to update invites: {}", e)))
+        .with_request_id(&request_id)?;
+    if !redeemed {
+        tracing::info!(provider = %user.provider, "Invalid invite code");
+        return Ok(Redirect::to("/account?invite=invalid"));
+    }
+
+    tracing::info!(provider = %user.provider, "Invite code redeemed");
+    Ok(Redirect::to("/account"))
+}
+
+/// Set the display name and Reply-To address used on posts. They live in
+/// the session cookie like the rest of the account data.
+#[instrument(name = "account::identity", skip(request_id, auth, jar, form))]
+pub async fn identity(
+    Extension(request_id): Extension<RequestId>,
+    auth: RequireAuth,
+    jar: PrivateCookieJar,
+    Form(form): Form<IdentityForm>,
+) -> Result<(PrivateCookieJar, Redirect), AppErrorResponse> {
+    let mut user = auth.user;
+    if !user.validate_csrf(&form.csrf_token) {
+        return Err(AppError::Internal(
+            "Invalid form submission. Please try again.".into(),
+        ))
+        .with_request_id(&request_id);
+    }
+
+    user.set_posting_identity(&form.posting_name, &form.reply_to)
+        .map_err(AppError::BadRequest)
+        .with_request_id(&request_id)?;
+    let session_cookie = user
+        .to_cookie()
+        .map_err(|e| AppError::Internal(format!("Failed to serialize user: {}", e)))
+        .with_request_id(&request_id)?;
+
+    tracing::info!(provider = %user.provider, "Posting identity updated");
+    Ok((
+        jar.add(session_cookie),
+        Redirect::to("/account?identity=saved"),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use http::header::{IF_RANGE, RANGE};
+    use http::StatusCode;
+
+    use super::*;
+
+    fn export_body(user: &User) -> Vec<u8> {
+        serde_json::to_vec(&AccountExport::from_user(
+            user,
+            true,
+            PosterReview::default(),
+        ))
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_export_resumes_with_range() {
+        let user = User::new(
+            "sub123".to_string(),
+            Some("Test User".to_string()),
+            Some("test@example.com".to_string()),
+            "google".to_string(),
+            Duration::from_secs(3600),
+        );
+        let first = ranged(&HeaderMap::new(), export_body(&user), "application/json");
+        let etag = first.headers()["etag"].clone();
+        let first = axum::body::to_bytes(first.into_body(), usize::MAX)
+            .await
+            .unwrap();
+
+        // A later request for the rest, as a resuming client sends it
+        let mut headers = HeaderMap::new();
+        headers.insert(RANGE, HeaderValue::from_static("bytes=20-"));
+        headers.insert(IF_RANGE, etag);
+        let rest = ranged(&headers, export_body(&user), "application/json");
+        assert_eq!(rest.status(), StatusCode::PARTIAL_CONTENT);
+        let rest = axum::body::to_bytes(rest.into_body(), usize::MAX)
+            .await
+            .unwrap();
+
+        let joined = [&first[..20], &rest[..]].concat();
+        let export: serde_json::Value = serde_json::from_slice(&joined).unwrap();
+        assert_eq!(export["sub"], "sub123");
+    }
+}
@@ -0,0 +1,80 @@
+//! Announcement dismissal.
+//!
+//! Routes:
+//! - POST /announcements/{id}/dismiss - Hide an announcement for this browser
+
+use axum::{
+    extract::{Path, State},
+    http::{header::REFERER, HeaderMap},
+    response::Redirect,
+};
+use axum_extra::extract::cookie::{Cookie, CookieJar, SameSite};
+use time::Duration as TimeDuration;
+
+use super::auth::validate_return_to;
+use crate::announcements::parse_dismissed;
+use crate::config::{ANNOUNCEMENT_DISMISS_COOKIE, ANNOUNCEMENT_DISMISS_MAX_AGE_SECS};
+use crate::state::AppState;
+
+/// Add the announcement to the dismissal cookie and go back to the page it
+/// was dismissed on. Nothing is stored server-side, so there is no CSRF
+/// check: a forged request can only hide a banner.
+pub async fn dismiss(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    jar: CookieJar,
+    headers: HeaderMap,
+) -> (CookieJar, Redirect) {
+    let redirect_url = headers
+        .get(REFERER)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|referer| referer.parse::<http::Uri>().ok())
+        .and_then(|uri| validate_return_to(uri.path_and_query().map(|pq| pq.as_str())))
+        .unwrap_or_else(|| "/".to_string());
+
+    if !state.announcements.is_dismissible(&id) {
+        return (jar, Redirect::to(&redirect_url));
+    }
+
+    let value = dismissed_cookie_value(
+        jar.get(ANNOUNCEMENT_DISMISS_COOKIE).map(|c| c.value()),
+        &id,
+        &state.announcements.ids(),
+    );
+    let cookie = Cookie::build((ANNOUNCEMENT_DISMISS_COOKIE, value))
+        .path("/")
+        .same_site(SameSite::Lax)
+        .http_only(true)
+        .max_age(TimeDuration::seconds(ANNOUNCEMENT_DISMISS_MAX_AGE_SECS))
+        .build();
+    (jar.add(cookie), Redirect::to(&redirect_url))
+}
+
+/// New cookie value: the previous IDs that still exist, plus `id`. Dropping
+/// removed announcements keeps the cookie from growing forever.
+fn dismissed_cookie_value(previous: Option<&str>, id: &str, existing: &[String]) -> String {
+    let mut ids: Vec<&str> = previous
+        .map(parse_dismissed)
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|previous| *previous != id && existing.iter().any(|e| e == previous))
+        .collect();
+    ids.push(id);
+    ids.join(",")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dismissed_cookie_value_drops_removed_ids() {
+        let existing = vec!["a".to_string(), "b".to_string(), "c".to_string()];
+        assert_eq!(dismissed_cookie_value(None, "a", &existing), "a");
+        assert_eq!(
+            dismissed_cookie_value(Some("old,b,a"), "c", &existing),
+            "b,a,c"
+        );
+        assert_eq!(dismissed_cookie_value(Some("a,b"), "a", &existing), "b,a");
+    }
+}
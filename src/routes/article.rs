@@ -1,24 +1,28 @@
-//! Handler for viewing a single article by message-id.
+//! Handlers for a single article by message-id.
 //!
-//! Used for direct article links independent of thread context.
+//! Used for direct article links independent of thread context, and for
+//! downloading the patch in an article.
 
 use axum::{
-    extract::{Path, Query, State},
-    response::Html,
+    extract::{Query, State},
+    response::{Html, Response},
     Extension,
 };
+use http::{header::CONTENT_DISPOSITION, HeaderMap, HeaderValue};
 use serde::Deserialize;
 use tracing::instrument;
 
-use super::{can_post_to_group, insert_auth_context};
+use super::extract::MessageId;
+use super::{
+    can_post_to_group, insert_auth_context, insert_canonical_url, insert_group_context,
+    insert_guidelines_url,
+};
 use crate::error::{AppError, AppErrorResponse, ResultExt};
-use crate::middleware::{CurrentUser, RequestId};
+use crate::http::range::ranged;
+use crate::middleware::{CurrentUser, RenderMode, RequestId};
+use crate::nntp::extract_diff;
 use crate::state::AppState;
-
-#[derive(Debug, Deserialize)]
-pub struct ViewPath {
-    pub message_id: String,
-}
+use crate::templates::render;
 
 #[derive(Deserialize)]
 pub struct ViewParams {
@@ -28,20 +32,21 @@ pub struct ViewParams {
 /// Fetches and displays a single article.
 #[instrument(
     name = "article::view",
-    skip(state, params, request_id, current_user),
-    fields(message_id = %path.message_id)
+    skip(state, params, request_id, current_user, render_mode),
+    fields(message_id = %message_id)
 )]
 pub async fn view(
     State(state): State<AppState>,
     Extension(request_id): Extension<RequestId>,
     Extension(current_user): Extension<CurrentUser>,
-    Path(path): Path<ViewPath>,
+    Extension(render_mode): Extension<RenderMode>,
+    message_id: MessageId,
     Query(params): Query<ViewParams>,
 ) -> Result<Html<String>, AppErrorResponse> {
     // Fetch article (cached + coalesced)
-    let article = state
+    let mut article = state
         .nntp
-        .get_article(&path.message_id)
+        .get_article(&message_id)
         .await
         .with_request_id(&request_id)?;
 
@@ -55,6 +60,11 @@ pub async fn view(
         None => ("/".to_string(), "Back".to_string(), None),
     };
 
+    state
+        .nntp
+        .strip_footers(group.as_deref(), &mut article)
+        .await;
+
     // Check if user can post (needs group and email)
     let can_post = if let Some(ref g) = group {
         can_post_to_group(&current_user, &state, g).await
@@ -68,20 +78,94 @@ pub async fn view(
     context.insert("back_url", &back_url);
     context.insert("back_label", &back_label);
     context.insert("can_post", &can_post);
+    insert_canonical_url(
+        &mut context,
+        &state,
+        &format!("/a/{}", message_id.path_segment()),
+    );
+    // Body display rules follow the group the reader came from, else the
+    // groups the article was posted to
+    let body_groups = match &group {
+        Some(g) => vec![g.clone()],
+        None => article.newsgroups(),
+    };
+    context.insert("body_groups", &body_groups);
     if let Some(ref g) = group {
-        context.insert("group", g);
+        insert_group_context(&mut context, &state, g);
+        // Replying needs the group, which comes from the back link
+        let path = format!(
+            "/a/{}?back={}",
+            message_id.path_segment(),
+            urlencoding::encode(&back_url)
+        );
+        insert_guidelines_url(&mut context, &state, &current_user, &path).await;
+    } else {
+        let diffs = body_groups.iter().any(|g| state.config.body.shows_diffs(g));
+        context.insert("diffs", &diffs);
     }
 
     insert_auth_context(&mut context, &state, &current_user, true);
 
-    let html = state
-        .tera
-        .render("article/view.html", &context)
+    let html = render(state.tera_for(render_mode), "article/view.html", &context)
         .map_err(AppError::from)
         .with_request_id(&request_id)?;
     Ok(Html(html))
 }
 
+/// The unified diff in an article, without the message around it, as a
+/// `.patch` attachment. Articles without one are 404.
+#[instrument(
+    name = "article::patch",
+    skip(state, request_id, headers),
+    fields(message_id = %message_id)
+)]
+pub async fn patch(
+    State(state): State<AppState>,
+    Extension(request_id): Extension<RequestId>,
+    message_id: MessageId,
+    headers: HeaderMap,
+) -> Result<Response, AppErrorResponse> {
+    let article = state
+        .nntp
+        .get_article(&message_id)
+        .await
+        .with_request_id(&request_id)?;
+    let diff = article
+        .body
+        .as_deref()
+        .and_then(extract_diff)
+        .ok_or_else(|| AppError::ArticleNotFound(format!("no patch in {}", &*message_id)))
+        .with_request_id(&request_id)?;
+
+    let mut response = ranged(
+        &headers,
+        diff.as_bytes().to_vec(),
+        "text/x-diff; charset=utf-8",
+    );
+    let disposition = format!("attachment; filename=\"{}\"", patch_filename(&message_id));
+    if let Ok(value) = HeaderValue::from_str(&disposition) {
+        response.headers_mut().insert(CONTENT_DISPOSITION, value);
+    }
+    Ok(response)
+}
+
+/// File name for an article's patch: the message ID's local part, keeping
+/// only characters that are safe in a file name
+fn patch_filename(message_id: &str) -> String {
+    let local = message_id
+        .trim_start_matches('<')
+        .split('@')
+        .next()
+        .unwrap_or_default();
+    let stem: String = local
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '_'))
+        .take(64)
+        .collect();
+    let stem = stem.trim_matches('.');
+    format!("{}.patch", if stem.is_empty() { "article" } else { stem })
+}
+
 /// Extract a human-readable label from the back URL
 fn extract_back_label(back: &str) -> String {
     if back.starts_with("/g/") {
@@ -109,3 +193,18 @@ fn extract_group_from_back(back: &str) -> Option<String> {
     }
     None
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_patch_filename() {
+        assert_eq!(
+            patch_filename("<20240101.1234-1-ann@example.com>"),
+            "20240101.1234-1-ann.patch"
+        );
+        assert_eq!(patch_filename("<../..@example.com>"), "article.patch");
+        assert_eq!(patch_filename("<a\"b/c@x>"), "abc.patch");
+    }
+}
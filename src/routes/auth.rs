@@ -9,7 +9,7 @@
 use axum::{
     extract::{Path, Query, State},
     response::{Html, IntoResponse, Redirect, Response},
-    Form,
+    Extension, Form,
 };
 use axum_extra::extract::{
     cookie::{Cookie, PrivateCookieJar, SameSite},
@@ -21,8 +21,10 @@ use serde::Deserialize;
 use time::Duration as TimeDuration;
 use tracing::instrument;
 
+use crate::middleware::RenderMode;
 use crate::oidc::session::{cookie_names, AuthFlowState, User};
 use crate::state::AppState;
+use crate::templates::render;
 
 /// Query parameters for login initiation
 #[derive(Debug, Deserialize)]
@@ -49,7 +51,7 @@ pub struct LogoutForm {
 
 /// Validate a return_to URL to prevent open redirects.
 /// Only allows relative paths starting with "/" and not containing "//".
-fn validate_return_to(return_to: Option<&str>) -> Option<String> {
+pub(crate) fn validate_return_to(return_to: Option<&str>) -> Option<String> {
     let url = return_to?;
     let trimmed = url.trim();
 
@@ -73,7 +75,7 @@ fn validate_return_to(return_to: Option<&str>) -> Option<String> {
 
 /// Detect if the request is using HTTPS based on headers and scheme.
 /// Checks X-Forwarded-Proto header first (for reverse proxies), then request scheme.
-fn detect_https(headers: &HeaderMap) -> bool {
+pub(super) fn detect_https(headers: &HeaderMap) -> bool {
     // Check X-Forwarded-Proto header (set by reverse proxies)
     if let Some(proto) = headers.get("x-forwarded-proto") {
         if let Ok(proto_str) = proto.to_str() {
@@ -92,9 +94,10 @@ fn detect_https(headers: &HeaderMap) -> bool {
 }
 
 /// Show provider selection page or redirect to single provider
-#[instrument(name = "auth::login", skip(state, _jar))]
+#[instrument(name = "auth::login", skip(state, render_mode, _jar))]
 pub async fn login(
     State(state): State<AppState>,
+    Extension(render_mode): Extension<RenderMode>,
     _jar: PrivateCookieJar,
     Query(query): Query<LoginQuery>,
 ) -> Result<Response, AuthError> {
@@ -137,9 +140,7 @@ pub async fn login(
     context.insert("providers", &provider_list);
     context.insert("return_to", &query.return_to);
 
-    let html = state
-        .tera
-        .render("auth/login.html", &context)
+    let html = render(state.tera_for(render_mode), "auth/login.html", &context)
         .map_err(|e| AuthError::Internal(format!("Template error: {}", e)))?;
 
     Ok(Html(html).into_response())
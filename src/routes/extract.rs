@@ -0,0 +1,366 @@
+//! Validated path parameters.
+//!
+//! Group names and message IDs arrive straight from the URL and end up as
+//! NNTP command arguments. The `GroupName` and `MessageId` extractors check
+//! them before a handler runs, so malformed input gets a 400 page in the
+//! request's theme instead of reaching the server.
+//!
+//! Message IDs may contain `/`, `%`, `?` and other characters that routing
+//! and proxies mangle even when percent-encoded, so links carry them as
+//! base64url (see [`message_id_segment`]). Older percent-encoded links are
+//! still accepted and redirected to the canonical form.
+
+use std::fmt;
+use std::ops::Deref;
+
+use axum::{
+    extract::{FromRequestParts, RawPathParams},
+    response::{Html, IntoResponse, Response},
+};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine as _};
+use http::{
+    header::{CACHE_CONTROL, LOCATION},
+    request::Parts,
+    Method, StatusCode, Uri,
+};
+use serde::Serialize;
+
+use super::insert_auth_context;
+use crate::config::{CACHE_CONTROL_ERROR, GROUP_NAME_MAX_LEN, MESSAGE_ID_MAX_LEN};
+use crate::error::{AppError, AppErrorResponse};
+use crate::middleware::{CurrentUser, RenderMode, RequestId};
+use crate::state::AppState;
+use crate::templates::render;
+
+/// Newsgroup name from the `{group}` path parameter.
+///
+/// Dot-separated components of letters, digits, `+`, `-` and `_` (RFC 5536).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(transparent)]
+pub struct GroupName(String);
+
+impl GroupName {
+    pub fn parse(raw: &str) -> Result<Self, AppError> {
+        let invalid =
+            |reason: &str| AppError::BadRequest(format!("Invalid group name: {}", reason));
+        if raw.is_empty() {
+            return Err(invalid("empty"));
+        }
+        if raw.len() > GROUP_NAME_MAX_LEN {
+            return Err(invalid("too long"));
+        }
+        if raw.split('.').any(str::is_empty) {
+            return Err(invalid("empty component"));
+        }
+        if !raw
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '+' | '-' | '_'))
+        {
+            return Err(invalid("unexpected character"));
+        }
+        Ok(Self(raw.to_string()))
+    }
+}
+
+/// Message ID from the `{message_id}` path parameter, with angle brackets.
+///
+/// The parameter is normally the canonical base64url form. Legacy
+/// percent-encoded IDs are accepted with or without brackets; GET and HEAD
+/// requests using them are redirected to the canonical URL. The ID itself
+/// must be printable ASCII without brackets or whitespace (RFC 3977).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(transparent)]
+pub struct MessageId(String);
+
+impl MessageId {
+    pub fn parse(raw: &str) -> Result<Self, AppError> {
+        let invalid =
+            |reason: &str| AppError::BadRequest(format!("Invalid message ID: {}", reason));
+        let inner = match raw.strip_prefix('<') {
+            Some(rest) => rest
+                .strip_suffix('>')
+                .ok_or_else(|| invalid("unbalanced angle brackets"))?,
+            None => raw,
+        };
+        if inner.is_empty() {
+            return Err(invalid("empty"));
+        }
+        if inner.len() + 2 > MESSAGE_ID_MAX_LEN {
+            return Err(invalid("too long"));
+        }
+        if !inner
+            .chars()
+            .all(|c| c.is_ascii_graphic() && !matches!(c, '<' | '>'))
+        {
+            return Err(invalid("unexpected character"));
+        }
+        Ok(Self(format!("<{}>", inner)))
+    }
+
+    /// Parse a path segment, returning whether it was in canonical form.
+    ///
+    /// Real message IDs contain `@` (RFC 5536) and base64url never does, so
+    /// a segment with `@` or a leading `<` is taken as a legacy ID.
+    pub fn from_path_segment(raw: &str) -> Result<(Self, bool), AppError> {
+        if !raw.starts_with('<') && !raw.contains('@') {
+            let decoded = URL_SAFE_NO_PAD
+                .decode(raw)
+                .ok()
+                .and_then(|bytes| String::from_utf8(bytes).ok());
+            if let Some(Ok(message_id)) = decoded.as_deref().map(Self::parse) {
+                return Ok((message_id, true));
+            }
+        }
+        Self::parse(raw).map(|message_id| (message_id, false))
+    }
+
+    /// Canonical path segment for this ID
+    pub fn path_segment(&self) -> String {
+        message_id_segment(&self.0)
+    }
+}
+
+/// Canonical URL path segment for a message ID: base64url without padding
+/// of the ID without its angle brackets. Also available to templates as the
+/// `message_id_url` filter.
+pub fn message_id_segment(message_id: &str) -> String {
+    let inner = message_id
+        .strip_prefix('<')
+        .and_then(|rest| rest.strip_suffix('>'))
+        .unwrap_or(message_id);
+    URL_SAFE_NO_PAD.encode(inner)
+}
+
+impl Deref for GroupName {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Display for GroupName {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl FromRequestParts<AppState> for GroupName {
+    type Rejection = Response;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &AppState,
+    ) -> Result<Self, Self::Rejection> {
+        let raw = path_param(parts, state, "group").await?;
+        GroupName::parse(&raw).map_err(|e| bad_request(parts, state, e))
+    }
+}
+
+impl Deref for MessageId {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Display for MessageId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl FromRequestParts<AppState> for MessageId {
+    type Rejection = Response;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &AppState,
+    ) -> Result<Self, Self::Rejection> {
+        let raw = path_param(parts, state, "message_id").await?;
+        let (message_id, canonical) =
+            MessageId::from_path_segment(&raw).map_err(|e| bad_request(parts, state, e))?;
+        if !canonical && matches!(parts.method, Method::GET | Method::HEAD) {
+            if let Some(location) = canonical_location(&parts.uri, &raw, &message_id) {
+                return Err((StatusCode::MOVED_PERMANENTLY, [(LOCATION, location)]).into_response());
+            }
+        }
+        Ok(message_id)
+    }
+}
+
+/// The request URI with the legacy message ID segment `raw` replaced by its
+/// canonical form, keeping the query string
+fn canonical_location(uri: &Uri, raw: &str, message_id: &MessageId) -> Option<String> {
+    let segments: Vec<&str> = uri.path().split('/').collect();
+    let index = segments
+        .iter()
+        .position(|segment| urlencoding::decode(segment).is_ok_and(|decoded| decoded == raw))?;
+    let segment = message_id.path_segment();
+    let mut location = segments
+        .iter()
+        .enumerate()
+        .map(|(i, s)| if i == index { segment.as_str() } else { *s })
+        .collect::<Vec<_>>()
+        .join("/");
+    if let Some(query) = uri.query() {
+        location.push('?');
+        location.push_str(query);
+    }
+    Some(location)
+}
+
+/// Percent-decoded value of the named path parameter
+async fn path_param(parts: &mut Parts, state: &AppState, name: &str) -> Result<String, Response> {
+    let params = RawPathParams::from_request_parts(parts, state)
+        .await
+        .map_err(IntoResponse::into_response)?;
+    let value = params
+        .iter()
+        .find(|(key, _)| *key == name)
+        .map(|(_, value)| value.to_string());
+    value.ok_or_else(|| {
+        let error = AppError::Internal(format!("Route has no {{{}}} parameter", name));
+        AppErrorResponse::new(error, request_id(parts)).into_response()
+    })
+}
+
+fn request_id(parts: &Parts) -> Option<uuid::Uuid> {
+    parts.extensions.get::<RequestId>().map(|id| id.0)
+}
+
+/// 400 page rendered with the request's theme, falling back to the plain
+/// error page if the template fails
+fn bad_request(parts: &Parts, state: &AppState, error: AppError) -> Response {
+    let render_mode = parts
+        .extensions
+        .get::<RenderMode>()
+        .copied()
+        .unwrap_or_default();
+    let current_user = parts
+        .extensions
+        .get::<CurrentUser>()
+        .cloned()
+        .unwrap_or(CurrentUser(None));
+    let request_id = request_id(parts);
+
+    let mut context = tera::Context::new();
+    context.insert("config", &state.config.ui);
+    context.insert("status", &StatusCode::BAD_REQUEST.as_u16());
+    context.insert("message", &error.to_string());
+    context.insert("request_id", &request_id.map(|id| id.to_string()));
+    insert_auth_context(&mut context, state, &current_user, false);
+
+    match render(state.tera_for(render_mode), "error.html", &context) {
+        Ok(html) => (
+            StatusCode::BAD_REQUEST,
+            [(CACHE_CONTROL, CACHE_CONTROL_ERROR)],
+            Html(html),
+        )
+            .into_response(),
+        Err(e) => {
+            tracing::error!(error = %e, "Failed to render error template");
+            AppErrorResponse::new(error, request_id).into_response()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_group_name_validation() {
+        assert_eq!(
+            &*GroupName::parse("comp.lang.rust").unwrap(),
+            "comp.lang.rust"
+        );
+        assert!(GroupName::parse("alt.binaries.x-files_2+").is_ok());
+
+        for bad in [
+            "",
+            "comp..lang",
+            ".comp",
+            "comp.",
+            "comp lang",
+            "comp.*",
+            "a\r\nQUIT",
+        ] {
+            assert!(
+                matches!(GroupName::parse(bad), Err(AppError::BadRequest(_))),
+                "{:?}",
+                bad
+            );
+        }
+        assert!(GroupName::parse(&"a".repeat(GROUP_NAME_MAX_LEN + 1)).is_err());
+    }
+
+    #[test]
+    fn test_message_id_adds_missing_brackets() {
+        assert_eq!(
+            &*MessageId::parse("<abc@example.com>").unwrap(),
+            "<abc@example.com>"
+        );
+        assert_eq!(
+            &*MessageId::parse("abc@example.com").unwrap(),
+            "<abc@example.com>"
+        );
+
+        for bad in [
+            "",
+            "<>",
+            "<abc@example.com",
+            "<a<b@example.com>",
+            "a b@example.com",
+            "a\r\nQUIT",
+        ] {
+            assert!(
+                matches!(MessageId::parse(bad), Err(AppError::BadRequest(_))),
+                "{:?}",
+                bad
+            );
+        }
+        assert!(MessageId::parse(&"a".repeat(MESSAGE_ID_MAX_LEN - 1)).is_err());
+        assert!(MessageId::parse(&"a".repeat(MESSAGE_ID_MAX_LEN - 2)).is_ok());
+    }
+
+    #[test]
+    fn test_message_id_path_segment_round_trip() {
+        for id in [
+            "<abc@example.com>",
+            "<a/b%2F?c#d@example.com>",
+            "<x+y=z@host>",
+        ] {
+            let segment = message_id_segment(id);
+            assert!(segment
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_'));
+            let (parsed, canonical) = MessageId::from_path_segment(&segment).unwrap();
+            assert_eq!(&*parsed, id);
+            assert!(canonical);
+        }
+
+        // Legacy forms still parse, but aren't canonical
+        for legacy in ["<abc@example.com>", "abc@example.com"] {
+            let (parsed, canonical) = MessageId::from_path_segment(legacy).unwrap();
+            assert_eq!(&*parsed, "<abc@example.com>");
+            assert!(!canonical);
+        }
+    }
+
+    #[test]
+    fn test_canonical_location_replaces_legacy_segment() {
+        let uri: Uri = "/g/comp.lang.rust/thread/%3Ca%2Fb%40example.com%3E?page=2"
+            .parse()
+            .unwrap();
+        let message_id = MessageId::parse("<a/b@example.com>").unwrap();
+        assert_eq!(
+            canonical_location(&uri, "<a/b@example.com>", &message_id),
+            Some(format!(
+                "/g/comp.lang.rust/thread/{}?page=2",
+                message_id.path_segment()
+            ))
+        );
+    }
+}
@@ -0,0 +1,104 @@
+//! Posting guidelines routes.
+//!
+//! Routes:
+//! - GET /guidelines - Show the configured guidelines document
+//! - POST /guidelines/accept - Record acceptance in the session and continue
+
+use axum::{
+    extract::{Query, State},
+    response::{Html, IntoResponse, Redirect, Response},
+    Extension, Form,
+};
+use axum_extra::extract::cookie::PrivateCookieJar;
+use http::StatusCode;
+use serde::Deserialize;
+use tracing::instrument;
+
+use super::auth::validate_return_to;
+use super::insert_auth_context;
+use crate::error::{AppError, AppErrorResponse, ResultExt};
+use crate::middleware::{CurrentUser, RenderMode, RequestId, RequireAuth};
+use crate::state::AppState;
+use crate::templates::render;
+
+/// Query parameters for the guidelines page
+#[derive(Debug, Deserialize)]
+pub struct GuidelinesQuery {
+    /// Page to continue to after accepting
+    pub return_to: Option<String>,
+}
+
+/// Form data for accepting the guidelines
+#[derive(Debug, Deserialize)]
+pub struct AcceptForm {
+    pub csrf_token: String,
+    pub return_to: Option<String>,
+}
+
+/// Guidelines page handler. 404 when no guidelines are configured.
+#[instrument(
+    name = "guidelines::view",
+    skip(state, request_id, current_user, render_mode, query)
+)]
+pub async fn view(
+    State(state): State<AppState>,
+    Extension(request_id): Extension<RequestId>,
+    Extension(current_user): Extension<CurrentUser>,
+    Extension(render_mode): Extension<RenderMode>,
+    Query(query): Query<GuidelinesQuery>,
+) -> Result<Response, AppErrorResponse> {
+    let Some(guidelines) = state.guidelines.as_ref() else {
+        return Ok(StatusCode::NOT_FOUND.into_response());
+    };
+
+    let needs_acceptance = current_user
+        .0
+        .as_ref()
+        .is_some_and(|user| state.needs_guidelines_acceptance(user));
+
+    let mut context = tera::Context::new();
+    context.insert("config", &state.config.ui);
+    context.insert("guidelines_html", &guidelines.html);
+    context.insert("needs_acceptance", &needs_acceptance);
+    context.insert("return_to", &validate_return_to(query.return_to.as_deref()));
+
+    insert_auth_context(&mut context, &state, &current_user, true);
+
+    let html = render(state.tera_for(render_mode), "guidelines.html", &context)
+        .map_err(AppError::from)
+        .with_request_id(&request_id)?;
+    Ok(Html(html).into_response())
+}
+
+/// Record that the user accepted the current guidelines
+#[instrument(name = "guidelines::accept", skip(state, request_id, auth, jar, form))]
+pub async fn accept(
+    State(state): State<AppState>,
+    Extension(request_id): Extension<RequestId>,
+    auth: RequireAuth,
+    jar: PrivateCookieJar,
+    Form(form): Form<AcceptForm>,
+) -> Result<(PrivateCookieJar, Redirect), AppErrorResponse> {
+    let mut user = auth.user;
+    if !user.validate_csrf(&form.csrf_token) {
+        return Err(AppError::Internal(
+            "Invalid form submission. Please try again.".into(),
+        ))
+        .with_request_id(&request_id);
+    }
+
+    let redirect_url =
+        validate_return_to(form.return_to.as_deref()).unwrap_or_else(|| "/".to_string());
+    let Some(guidelines) = state.guidelines.as_ref() else {
+        return Ok((jar, Redirect::to(&redirect_url)));
+    };
+
+    user.accepted_guidelines = Some(guidelines.version.clone());
+    let session_cookie = user
+        .to_cookie()
+        .map_err(|e| AppError::Internal(format!("Failed to serialize user: {}", e)))
+        .with_request_id(&request_id)?;
+
+    tracing::info!(version = %guidelines.version, "Posting guidelines accepted");
+    Ok((jar.add(session_cookie), Redirect::to(&redirect_url)))
+}
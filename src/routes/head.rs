@@ -0,0 +1,324 @@
+//! HEAD and conditional GET handlers for the NNTP-backed pages.
+//!
+//! Routes:
+//! - GET/HEAD /a/{message_id} - Article
+//! - GET/HEAD /g/{group} - Thread list
+//! - GET/HEAD /g/{group}/thread/{message_id} - Thread view
+//!
+//! Crawlers and caches use HEAD to check that a page exists and whether it
+//! changed. When the cache already holds the article, thread or thread list,
+//! the answer comes from it: status, content type and `Last-Modified` (the
+//! newest post), or 304 when `If-Modified-Since` is not older. Nothing is
+//! fetched or rendered. Otherwise the request goes to the GET handler and
+//! axum drops the body. GET carries the same `Last-Modified` and answers the
+//! same 304, so a client can validate with HEAD and then fetch
+//! conditionally. Other GET routes render from cached data and leave HEAD
+//! to axum.
+
+use axum::{
+    extract::{Request, State},
+    handler::Handler,
+    http::{
+        header::{CONTENT_TYPE, IF_MODIFIED_SINCE, LAST_MODIFIED},
+        HeaderMap, HeaderValue, StatusCode,
+    },
+    response::{IntoResponse, Response},
+};
+use chrono::{DateTime, Utc};
+
+use super::extract::{GroupName, MessageId};
+use super::{article, threads};
+use crate::nntp::CachedStatus;
+use crate::state::AppState;
+
+/// HTTP-date layout for `Last-Modified` (RFC 9110 IMF-fixdate)
+const HTTP_DATE_FORMAT: &str = "%a, %d %b %Y %H:%M:%S GMT";
+
+/// HEAD for an article
+pub async fn article_view(
+    State(state): State<AppState>,
+    message_id: MessageId,
+    request: Request,
+) -> Response {
+    match state.nntp.cached_article_status(&message_id).await {
+        Some(status) => cached_response(status, request.headers(), Utc::now()),
+        None => Handler::call(article::view, request, state).await,
+    }
+}
+
+/// GET for an article
+pub async fn get_article_view(
+    State(state): State<AppState>,
+    message_id: MessageId,
+    request: Request,
+) -> Response {
+    let now = Utc::now();
+    if let Some(response) = not_modified(
+        state.nntp.cached_article_status(&message_id).await,
+        request.headers(),
+        now,
+    ) {
+        return response;
+    }
+    let mut response = Handler::call(article::view, request, state.clone()).await;
+    // Rendering cached the article, so its date is known now
+    set_last_modified(
+        &mut response,
+        state.nntp.cached_article_status(&message_id).await,
+        now,
+    );
+    response
+}
+
+/// HEAD for a group's thread list
+pub async fn thread_list(
+    State(state): State<AppState>,
+    group: GroupName,
+    request: Request,
+) -> Response {
+    match state.nntp.cached_threads_status(&group).await {
+        Some(status) => cached_response(status, request.headers(), Utc::now()),
+        None => Handler::call(threads::list, request, state).await,
+    }
+}
+
+/// GET for a group's thread list
+pub async fn get_thread_list(
+    State(state): State<AppState>,
+    group: GroupName,
+    request: Request,
+) -> Response {
+    let now = Utc::now();
+    if let Some(response) = not_modified(
+        state.nntp.cached_threads_status(&group).await,
+        request.headers(),
+        now,
+    ) {
+        return response;
+    }
+    let mut response = Handler::call(threads::list, request, state.clone()).await;
+    set_last_modified(
+        &mut response,
+        state.nntp.cached_threads_status(&group).await,
+        now,
+    );
+    response
+}
+
+/// HEAD for a thread
+pub async fn thread_view(
+    State(state): State<AppState>,
+    group: GroupName,
+    message_id: MessageId,
+    request: Request,
+) -> Response {
+    match state.nntp.cached_thread_status(&group, &message_id).await {
+        Some(status) => cached_response(status, request.headers(), Utc::now()),
+        None => Handler::call(threads::view, request, state).await,
+    }
+}
+
+/// GET for a thread
+pub async fn get_thread_view(
+    State(state): State<AppState>,
+    group: GroupName,
+    message_id: MessageId,
+    request: Request,
+) -> Response {
+    let now = Utc::now();
+    if let Some(response) = not_modified(
+        state.nntp.cached_thread_status(&group, &message_id).await,
+        request.headers(),
+        now,
+    ) {
+        return response;
+    }
+    let mut response = Handler::call(threads::view, request, state.clone()).await;
+    set_last_modified(
+        &mut response,
+        state.nntp.cached_thread_status(&group, &message_id).await,
+        now,
+    );
+    response
+}
+
+/// Headers the GET response would carry, from the cache alone
+fn cached_response(status: CachedStatus, headers: &HeaderMap, now: DateTime<Utc>) -> Response {
+    if status == CachedStatus::Missing {
+        return StatusCode::NOT_FOUND.into_response();
+    }
+    let mut response = not_modified(Some(status), headers, now)
+        .unwrap_or_else(|| [(CONTENT_TYPE, "text/html; charset=utf-8")].into_response());
+    set_last_modified(&mut response, Some(status), now);
+    response
+}
+
+/// 304 with `Last-Modified` when the cached content is not newer than
+/// `If-Modified-Since`
+fn not_modified(
+    status: Option<CachedStatus>,
+    headers: &HeaderMap,
+    now: DateTime<Utc>,
+) -> Option<Response> {
+    let modified = last_modified(status, now)?;
+    let since = if_modified_since(headers)?;
+    // HTTP dates have whole seconds
+    if modified.timestamp() > since.timestamp() {
+        return None;
+    }
+    let mut response = StatusCode::NOT_MODIFIED.into_response();
+    set_last_modified(&mut response, status, now);
+    Some(response)
+}
+
+/// Add `Last-Modified` to a successful response from what the cache holds
+fn set_last_modified(response: &mut Response, status: Option<CachedStatus>, now: DateTime<Utc>) {
+    if !(response.status().is_success() || response.status() == StatusCode::NOT_MODIFIED) {
+        return;
+    }
+    if let Some(value) = last_modified(status, now)
+        .and_then(|modified| HeaderValue::from_str(&http_date(modified)).ok())
+    {
+        response.headers_mut().insert(LAST_MODIFIED, value);
+    }
+}
+
+fn last_modified(status: Option<CachedStatus>, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+    match status? {
+        // A Date header in the future must not become a future Last-Modified
+        CachedStatus::Present { last_modified } => last_modified.map(|d| d.min(now)),
+        CachedStatus::Missing => None,
+    }
+}
+
+fn if_modified_since(headers: &HeaderMap) -> Option<DateTime<Utc>> {
+    let value = headers.get(IF_MODIFIED_SINCE)?.to_str().ok()?;
+    DateTime::parse_from_rfc2822(value.trim())
+        .ok()
+        .map(|d| d.with_timezone(&Utc))
+}
+
+fn http_date(date: DateTime<Utc>) -> String {
+    date.format(HTTP_DATE_FORMAT).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(timestamp: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(timestamp)
+            .unwrap()
+            .with_timezone(&Utc)
+    }
+
+    #[test]
+    fn test_cached_response_headers() {
+        let now = at("2026-05-01T00:00:00Z");
+        let present = CachedStatus::Present {
+            last_modified: Some(at("2026-04-30T08:15:30Z")),
+        };
+
+        let response = cached_response(present, &HeaderMap::new(), now);
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            response.headers()[LAST_MODIFIED],
+            "Thu, 30 Apr 2026 08:15:30 GMT"
+        );
+        assert_eq!(response.headers()[CONTENT_TYPE], "text/html; charset=utf-8");
+
+        let missing = cached_response(CachedStatus::Missing, &HeaderMap::new(), now);
+        assert_eq!(missing.status(), StatusCode::NOT_FOUND);
+
+        // Future dates are clamped to now
+        let future = CachedStatus::Present {
+            last_modified: Some(at("2030-01-01T00:00:00Z")),
+        };
+        let response = cached_response(future, &HeaderMap::new(), now);
+        assert_eq!(
+            response.headers()[LAST_MODIFIED],
+            "Fri, 01 May 2026 00:00:00 GMT"
+        );
+    }
+
+    #[test]
+    fn test_cached_response_honours_if_modified_since() {
+        let now = at("2026-05-01T00:00:00Z");
+        let present = CachedStatus::Present {
+            last_modified: Some(at("2026-04-30T08:15:30Z")),
+        };
+        let mut headers = HeaderMap::new();
+
+        headers.insert(
+            IF_MODIFIED_SINCE,
+            HeaderValue::from_static("Thu, 30 Apr 2026 08:15:30 GMT"),
+        );
+        assert_eq!(
+            cached_response(present, &headers, now).status(),
+            StatusCode::NOT_MODIFIED
+        );
+
+        headers.insert(
+            IF_MODIFIED_SINCE,
+            HeaderValue::from_static("Wed, 29 Apr 2026 00:00:00 GMT"),
+        );
+        assert_eq!(
+            cached_response(present, &headers, now).status(),
+            StatusCode::OK
+        );
+
+        // Without a date there is nothing to compare
+        let undated = CachedStatus::Present {
+            last_modified: None,
+        };
+        let response = cached_response(undated, &headers, now);
+        assert_eq!(response.status(), StatusCode::OK);
+        assert!(response.headers().get(LAST_MODIFIED).is_none());
+    }
+
+    #[test]
+    fn test_set_last_modified_only_on_success() {
+        let now = at("2026-05-01T00:00:00Z");
+        let present = Some(CachedStatus::Present {
+            last_modified: Some(at("2026-04-30T08:15:30Z")),
+        });
+
+        let mut page = "<html></html>".into_response();
+        set_last_modified(&mut page, present, now);
+        assert_eq!(
+            page.headers()[LAST_MODIFIED],
+            "Thu, 30 Apr 2026 08:15:30 GMT"
+        );
+
+        let mut error = StatusCode::BAD_GATEWAY.into_response();
+        set_last_modified(&mut error, present, now);
+        assert!(error.headers().get(LAST_MODIFIED).is_none());
+
+        // Nothing cached, nothing to send
+        let mut uncached = "<html></html>".into_response();
+        set_last_modified(&mut uncached, None, now);
+        assert!(uncached.headers().get(LAST_MODIFIED).is_none());
+    }
+
+    #[test]
+    fn test_not_modified_needs_cached_date() {
+        let now = at("2026-05-01T00:00:00Z");
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            IF_MODIFIED_SINCE,
+            HeaderValue::from_static("Thu, 30 Apr 2026 08:15:30 GMT"),
+        );
+        let present = Some(CachedStatus::Present {
+            last_modified: Some(at("2026-04-30T08:15:30Z")),
+        });
+
+        let response = not_modified(present, &headers, now).unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+        assert_eq!(
+            response.headers()[LAST_MODIFIED],
+            "Thu, 30 Apr 2026 08:15:30 GMT"
+        );
+        assert!(not_modified(None, &headers, now).is_none());
+        assert!(not_modified(Some(CachedStatus::Missing), &headers, now).is_none());
+    }
+}
@@ -1,7 +1,9 @@
 //! Handlers for home page and newsgroup browsing.
 //!
-//! Displays a hierarchical group tree with statistics.
-//! Prefetches group stats in the background for uncached groups.
+//! The home page is made of the blocks listed in `[home] blocks`: the
+//! hierarchical group tree with statistics, pinned groups, top threads,
+//! announcements and the group filter. Browse pages show the filter and
+//! tree only. Prefetches group stats in the background for uncached groups.
 
 use std::collections::HashMap;
 
@@ -10,13 +12,55 @@ use axum::{
     response::Html,
     Extension,
 };
+use serde::Serialize;
 use tracing::instrument;
 
-use super::insert_auth_context;
+use super::{insert_auth_context, insert_canonical_url};
+use crate::config::HomeBlock;
 use crate::error::{AppError, AppErrorResponse, ResultExt};
-use crate::middleware::{CurrentUser, RequestId};
-use crate::nntp::GroupTreeNode;
+use crate::middleware::{CurrentUser, RenderMode, RequestId};
+use crate::nntp::{GroupTreeNode, GroupView, TopThreadView};
 use crate::state::AppState;
+use crate::templates::render;
+
+/// A page block with the data it shows, tagged with its `kind` for templates
+#[derive(Debug, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+enum BlockView {
+    GroupTree,
+    PinnedGroups { groups: Vec<PinnedGroupView> },
+    TopThreads { threads: Vec<TopThreadView> },
+    Announcements,
+    Search,
+}
+
+/// A pinned group with whatever statistics are cached
+#[derive(Debug, Serialize)]
+struct PinnedGroupView {
+    name: String,
+    description: Option<String>,
+    thread_count: Option<usize>,
+    last_post_date: Option<String>,
+}
+
+/// Pinned groups the server carries, in configured order
+fn pinned_group_views(
+    pinned: &[String],
+    groups: &[GroupView],
+    thread_counts: &HashMap<String, usize>,
+    group_stats: &HashMap<String, Option<String>>,
+) -> Vec<PinnedGroupView> {
+    pinned
+        .iter()
+        .filter_map(|name| groups.iter().find(|g| &g.name == name))
+        .map(|group| PinnedGroupView {
+            name: group.name.clone(),
+            description: group.description.clone(),
+            thread_count: thread_counts.get(&group.name).copied(),
+            last_post_date: group_stats.get(&group.name).cloned().flatten(),
+        })
+        .collect()
+}
 
 /// Extract all group names from a list of tree nodes (recursively including children)
 fn extract_all_group_names(nodes: &[GroupTreeNode]) -> Vec<String> {
@@ -60,11 +104,15 @@ async fn get_stats_for_groups(
 
 /// Home page handler showing all newsgroups in a tree hierarchy.
 /// Only fetches stats for top-level groups, similar to /browse/{prefix}.
-#[instrument(name = "home::index", skip(state, request_id, current_user))]
+#[instrument(
+    name = "home::index",
+    skip(state, request_id, current_user, render_mode)
+)]
 pub async fn index(
     State(state): State<AppState>,
     Extension(request_id): Extension<RequestId>,
     Extension(current_user): Extension<CurrentUser>,
+    Extension(render_mode): Extension<RenderMode>,
 ) -> Result<Html<String>, AppErrorResponse> {
     // Fetch all groups (cached + coalesced)
     let groups = state.nntp.get_groups().await.with_request_id(&request_id)?;
@@ -72,13 +120,21 @@ pub async fn index(
     // Build tree hierarchy
     let tree = GroupTreeNode::build_tree(&groups);
 
-    // Only get stats for top-level groups (visible at root level)
+    // Only get stats for top-level groups (visible at root level) and pinned groups
     // This matches the behavior of /browse/{prefix} which only stats visible nodes
-    let top_level_group_names = extract_top_level_group_names(&tree);
+    let home = &state.config.home;
+    let mut stat_group_names = extract_top_level_group_names(&tree);
+    if home.blocks.contains(&HomeBlock::PinnedGroups) {
+        for name in &home.pinned_groups {
+            if !stat_group_names.contains(name) {
+                stat_group_names.push(name.clone());
+            }
+        }
+    }
 
     // Get cached stats + identify what needs prefetching
     let (group_stats, thread_counts, needs_prefetch) =
-        get_stats_for_groups(&state, &top_level_group_names).await;
+        get_stats_for_groups(&state, &stat_group_names).await;
 
     // Trigger background prefetch for uncached groups
     if !needs_prefetch.is_empty() {
@@ -89,6 +145,27 @@ pub async fn index(
     let tree_with_stats =
         GroupTreeNode::build_tree_with_stats(&groups, &thread_counts, &group_stats);
 
+    // Dispatch each configured block to the data it shows
+    let mut blocks = Vec::with_capacity(home.blocks.len());
+    for block in &home.blocks {
+        blocks.push(match block {
+            HomeBlock::GroupTree => BlockView::GroupTree,
+            HomeBlock::PinnedGroups => BlockView::PinnedGroups {
+                groups: pinned_group_views(
+                    &home.pinned_groups,
+                    &groups,
+                    &thread_counts,
+                    &group_stats,
+                ),
+            },
+            HomeBlock::TopThreads => BlockView::TopThreads {
+                threads: state.nntp.cached_top_threads(home.top_threads).await,
+            },
+            HomeBlock::Announcements => BlockView::Announcements,
+            HomeBlock::Search => BlockView::Search,
+        });
+    }
+
     let mut context = tera::Context::new();
     context.insert("config", &state.config.ui);
     context.insert("groups", &groups);
@@ -97,23 +174,29 @@ pub async fn index(
     context.insert("breadcrumbs", &Vec::<(&str, &str)>::new());
     context.insert("group_stats", &group_stats);
     context.insert("thread_counts", &thread_counts);
+    context.insert("blocks", &blocks);
+    insert_canonical_url(&mut context, &state, "/");
+    // Shown in their block rather than above the page
+    context.insert(
+        "announcements_inline",
+        &home.blocks.contains(&HomeBlock::Announcements),
+    );
 
     insert_auth_context(&mut context, &state, &current_user, false);
 
-    let html = state
-        .tera
-        .render("home.html", &context)
+    let html = render(state.tera_for(render_mode), "home.html", &context)
         .map_err(AppError::from)
         .with_request_id(&request_id)?;
     Ok(Html(html))
 }
 
 /// Browse handler for navigating into group hierarchy by prefix path.
-#[instrument(name = "home::browse", skip(state, request_id, current_user), fields(prefix = %prefix))]
+#[instrument(name = "home::browse", skip(state, request_id, current_user, render_mode), fields(prefix = %prefix))]
 pub async fn browse(
     State(state): State<AppState>,
     Extension(request_id): Extension<RequestId>,
     Extension(current_user): Extension<CurrentUser>,
+    Extension(render_mode): Extension<RenderMode>,
     Path(prefix): Path<String>,
 ) -> Result<Html<String>, AppErrorResponse> {
     // Fetch all groups (cached + coalesced)
@@ -178,13 +261,61 @@ pub async fn browse(
     context.insert("current_node", &current_node);
     context.insert("group_stats", &group_stats);
     context.insert("thread_counts", &thread_counts);
+    context.insert("blocks", &[BlockView::Search, BlockView::GroupTree]);
+    insert_canonical_url(&mut context, &state, &format!("/browse/{}", prefix));
 
     insert_auth_context(&mut context, &state, &current_user, false);
 
-    let html = state
-        .tera
-        .render("home.html", &context)
+    let html = render(state.tera_for(render_mode), "home.html", &context)
         .map_err(AppError::from)
         .with_request_id(&request_id)?;
     Ok(Html(html))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn group(name: &str) -> GroupView {
+        GroupView {
+            name: name.to_string(),
+            description: Some(format!("About {}", name)),
+            article_count: None,
+        }
+    }
+
+    #[test]
+    fn test_pinned_group_views_keep_order_and_skip_unknown() {
+        let groups = vec![group("comp.lang.rust"), group("local.general")];
+        let thread_counts = HashMap::from([("local.general".to_string(), 12)]);
+        let group_stats = HashMap::from([(
+            "local.general".to_string(),
+            Some("Mon, 01 Jan 2024 00:00:00 +0000".to_string()),
+        )]);
+        let pinned = vec![
+            "local.general".to_string(),
+            "alt.missing".to_string(),
+            "comp.lang.rust".to_string(),
+        ];
+
+        let views = pinned_group_views(&pinned, &groups, &thread_counts, &group_stats);
+        let names: Vec<&str> = views.iter().map(|v| v.name.as_str()).collect();
+        assert_eq!(names, vec!["local.general", "comp.lang.rust"]);
+        assert_eq!(views[0].thread_count, Some(12));
+        assert!(views[0].last_post_date.is_some());
+        assert_eq!(views[1].thread_count, None);
+    }
+
+    #[test]
+    fn test_block_view_is_tagged_by_kind() {
+        let json = serde_json::to_value(BlockView::TopThreads {
+            threads: Vec::new(),
+        })
+        .unwrap();
+        assert_eq!(json["kind"], "top_threads");
+        assert_eq!(
+            serde_json::to_value(BlockView::GroupTree).unwrap()["kind"],
+            "group_tree"
+        );
+    }
+}
@@ -0,0 +1,193 @@
+//! Moderator management of invite-only posting.
+//!
+//! Routes:
+//! - GET /admin/invites - Allowed subjects, invite codes and redemptions
+//! - POST /admin/invites/allow - Let a subject post without a code
+//! - POST /admin/invites/revoke - Take posting access from a subject
+//! - POST /admin/invites/codes - Create an invite code
+//! - POST /admin/invites/codes/{code}/delete - Delete an invite code
+//!
+//! Only subjects in `[posting] moderators` may use these, and only on
+//! invite-only instances. Subjects and codes from the config file are shown
+//! but can't be changed here.
+
+use axum::{
+    extract::{Path, State},
+    response::{Html, Redirect},
+    Extension, Form,
+};
+use serde::Deserialize;
+use tracing::instrument;
+
+use super::insert_auth_context;
+use super::review::check_moderator;
+use crate::error::{AppError, AppErrorResponse, ResultExt};
+use crate::middleware::{CurrentUser, RenderMode, RequestId, RequireAuth};
+use crate::oidc::session::User;
+use crate::state::AppState;
+use crate::templates::render;
+
+/// Form data for allowing or revoking a subject
+#[derive(Debug, Deserialize)]
+pub struct SubjectForm {
+    pub csrf_token: String,
+    /// "provider:sub"
+    pub subject: String,
+}
+
+/// Form data for creating an invite code
+#[derive(Debug, Deserialize)]
+pub struct CreateCodeForm {
+    pub csrf_token: String,
+    pub uses: u32,
+}
+
+/// Form data for deleting an invite code
+#[derive(Debug, Deserialize)]
+pub struct DeleteCodeForm {
+    pub csrf_token: String,
+}
+
+/// Refuse anyone who isn't a moderator, and everyone when posting is open
+fn check_invites_admin(
+    state: &AppState,
+    user: &User,
+    csrf_token: Option<&str>,
+) -> Result<(), AppError> {
+    if !state.invites.is_enabled() {
+        return Err(AppError::Forbidden(
+            "Posting on this site is not invite-only".into(),
+        ));
+    }
+    check_moderator(state, user, csrf_token)
+}
+
+/// List allowed subjects, invite codes and redemptions
+#[instrument(
+    name = "invites::overview",
+    skip(state, request_id, current_user, render_mode, auth)
+)]
+pub async fn overview(
+    State(state): State<AppState>,
+    Extension(request_id): Extension<RequestId>,
+    Extension(current_user): Extension<CurrentUser>,
+    Extension(render_mode): Extension<RenderMode>,
+    auth: RequireAuth,
+) -> Result<Html<String>, AppErrorResponse> {
+    check_invites_admin(&state, &auth.user, None).with_request_id(&request_id)?;
+
+    let mut context = tera::Context::new();
+    context.insert("config", &state.config.ui);
+    context.insert("invites", &state.invites.overview().await);
+
+    insert_auth_context(&mut context, &state, &current_user, true);
+
+    let html = render(state.tera_for(render_mode), "admin/invites.html", &context)
+        .map_err(AppError::from)
+        .with_request_id(&request_id)?;
+    Ok(Html(html))
+}
+
+/// Let a subject post without redeeming a code
+#[instrument(name = "invites::allow", skip(state, request_id, auth, form))]
+pub async fn allow(
+    State(state): State<AppState>,
+    Extension(request_id): Extension<RequestId>,
+    auth: RequireAuth,
+    Form(form): Form<SubjectForm>,
+) -> Result<Redirect, AppErrorResponse> {
+    check_invites_admin(&state, &auth.user, Some(&form.csrf_token)).with_request_id(&request_id)?;
+
+    let subject = form.subject.trim();
+    if !subject
+        .split_once(':')
+        .is_some_and(|(p, s)| !p.is_empty() && !s.is_empty())
+    {
+        return Err(AppError::BadRequest(format!(
+            "'{}' must be written as provider:sub",
+            subject
+        )))
+        .with_request_id(&request_id);
+    }
+    state
+        .invites
+        .allow(subject)
+        .await
+        .map_err(save_error)
+        .with_request_id(&request_id)?;
+
+    tracing::info!(%subject, "Subject allowed to post");
+    Ok(Redirect::to("/admin/invites"))
+}
+
+/// Take posting access from a subject allowed here or through a code
+#[instrument(name = "invites::revoke", skip(state, request_id, auth, form))]
+pub async fn revoke(
+    State(state): State<AppState>,
+    Extension(request_id): Extension<RequestId>,
+    auth: RequireAuth,
+    Form(form): Form<SubjectForm>,
+) -> Result<Redirect, AppErrorResponse> {
+    check_invites_admin(&state, &auth.user, Some(&form.csrf_token)).with_request_id(&request_id)?;
+
+    let subject = form.subject.trim();
+    state
+        .invites
+        .revoke(subject)
+        .await
+        .map_err(save_error)
+        .with_request_id(&request_id)?;
+
+    tracing::info!(%subject, "Posting access revoked");
+    Ok(Redirect::to("/admin/invites"))
+}
+
+/// Create an invite code that can be redeemed the given number of times
+#[instrument(name = "invites::create_code", skip(state, request_id, auth, form))]
+pub async fn create_code(
+    State(state): State<AppState>,
+    Extension(request_id): Extension<RequestId>,
+    auth: RequireAuth,
+    Form(form): Form<CreateCodeForm>,
+) -> Result<Redirect, AppErrorResponse> {
+    check_invites_admin(&state, &auth.user, Some(&form.csrf_token)).with_request_id(&request_id)?;
+
+    let code = state
+        .invites
+        .create_code(form.uses)
+        .await
+        .map_err(save_error)
+        .with_request_id(&request_id)?;
+
+    tracing::info!(uses = code.uses_left, "Invite code created");
+    Ok(Redirect::to("/admin/invites"))
+}
+
+/// Delete an invite code; access already granted through it stays
+#[instrument(
+    name = "invites::delete_code",
+    skip(state, request_id, auth, code, form)
+)]
+pub async fn delete_code(
+    State(state): State<AppState>,
+    Extension(request_id): Extension<RequestId>,
+    auth: RequireAuth,
+    Path(code): Path<String>,
+    Form(form): Form<DeleteCodeForm>,
+) -> Result<Redirect, AppErrorResponse> {
+    check_invites_admin(&state, &auth.user, Some(&form.csrf_token)).with_request_id(&request_id)?;
+
+    state
+        .invites
+        .delete_code(&code)
+        .await
+        .map_err(save_error)
+        .with_request_id(&request_id)?;
+
+    tracing::info!("Invite code deleted");
+    Ok(Redirect::to("/admin/invites"))
+}
+
+fn save_error(e: std::io::Error) -> AppError {
+    AppError::Internal(format!("Failed to update invites: {}", e))
+}
@@ -7,28 +7,41 @@
 //! Request tracing is enabled via middleware that generates a unique request ID
 //! for each incoming request, allowing correlation of all logs within a request.
 
+pub mod about;
+pub mod account;
+pub mod announcements;
 pub mod article;
 pub mod auth;
+pub mod extract;
+pub mod guidelines;
+pub mod head;
 pub mod health;
 pub mod home;
+pub mod invites;
 pub mod post;
 pub mod privacy;
+pub mod review;
 pub mod threads;
+pub mod well_known;
 
 use axum::{
     middleware,
     routing::{get, post},
     Router,
 };
-use http::header::{HeaderValue, CACHE_CONTROL};
+use http::header::{HeaderValue, CACHE_CONTROL, SERVER};
 use tower_http::set_header::SetResponseHeaderLayer;
 
+use crate::announcements::current as current_announcements;
 use crate::config::{
     CACHE_CONTROL_ARTICLE, CACHE_CONTROL_HOME, CACHE_CONTROL_STATIC, CACHE_CONTROL_THREAD_LIST,
     CACHE_CONTROL_THREAD_VIEW,
 };
 use crate::http::static_files::create_static_service;
-use crate::middleware::{auth_layer, request_id_layer, CurrentUser};
+use crate::middleware::{
+    announcement_layer, auth_layer, canonical_redirect_layer, deadline_layer, from_display_layer,
+    render_mode_layer, request_id_layer, CurrentUser,
+};
 use crate::state::AppState;
 
 /// Insert authentication-related context for template rendering.
@@ -37,6 +50,7 @@ use crate::state::AppState;
 /// - `oidc_enabled`: Whether OIDC authentication is configured
 /// - `user.display_name`: The authenticated user's display name (if logged in)
 /// - `csrf_token`: CSRF token for form submissions (if `include_csrf` is true)
+/// - `announcements`: Site announcements picked for this request (if any)
 ///
 /// # Arguments
 /// * `context` - The Tera template context to modify
@@ -61,13 +75,53 @@ pub fn insert_auth_context(
             context.insert("csrf_token", &user.csrf_token);
         }
     }
+    if let Some(announcements) = current_announcements() {
+        context.insert("announcements", announcements.as_slice());
+    }
+}
+
+/// Insert the group name and its `[[theme.group]]` override for template rendering.
+///
+/// - `group`: The newsgroup name
+/// - `group_theme`: Stylesheet, body class and banner for the group (if a rule matches)
+/// - `diffs`: Whether bodies in the group highlight diffs (`[body] diff_groups`)
+pub fn insert_group_context(context: &mut tera::Context, state: &AppState, group: &str) {
+    context.insert("group", group);
+    context.insert("diffs", &state.config.body.shows_diffs(group));
+    if let Some(rule) = state.config.theme.rule_for(group) {
+        context.insert("group_theme", rule);
+    }
+}
+
+/// Insert the page's canonical URL, emitted as `<link rel="canonical">`.
+///
+/// `path` is the canonical path (see `canonical_redirect_layer` for the
+/// forms that redirect to it). It is made absolute with `[instance] url`
+/// when that is set.
+pub fn insert_canonical_url(context: &mut tera::Context, state: &AppState, path: &str) {
+    let url = match &state.config.instance.url {
+        Some(base) => format!("{}{}", base.trim_end_matches('/'), path),
+        None => path.to_string(),
+    };
+    context.insert("canonical_url", &url);
+}
+
+/// `path` for page `page` of a paginated listing; page 1 has no parameter.
+pub fn paged_path(path: &str, page: usize) -> String {
+    if page > 1 {
+        format!("{}?page={}", path, page)
+    } else {
+        path.to_string()
+    }
 }
 
 /// Check if the current user can post to a group.
 ///
-/// This combines two checks:
+/// This combines four checks:
 /// 1. The user must be authenticated with a valid email address
-/// 2. The group must allow posting (checked via NNTP server capabilities)
+/// 2. On invite-only instances, the user must be allowlisted or invited
+/// 3. The user must have accepted the current posting guidelines
+/// 4. The group must allow posting (checked via NNTP server capabilities)
 ///
 /// # Arguments
 /// * `current_user` - The current user extracted from session
@@ -77,23 +131,53 @@ pub fn insert_auth_context(
 /// # Returns
 /// `true` if the user can post to the group, `false` otherwise.
 pub async fn can_post_to_group(current_user: &CurrentUser, state: &AppState, group: &str) -> bool {
-    if current_user
-        .0
-        .as_ref()
-        .map(|u| u.email.is_some())
-        .unwrap_or(false)
-    {
-        state.nntp.can_post_to_group(group).await
-    } else {
-        false
+    match &current_user.0 {
+        Some(u)
+            if u.email.is_some()
+                && !state.needs_guidelines_acceptance(u)
+                && state.can_post(u).await =>
+        {
+            state.nntp.can_post_to_group(group).await
+        }
+        _ => false,
+    }
+}
+
+/// Insert `guidelines_url` when the only thing keeping the current user from
+/// posting is accepting the posting guidelines, so pages can link there in
+/// place of the forms that [`can_post_to_group`] hides.
+///
+/// - `path`: The page to return to after accepting
+pub async fn insert_guidelines_url(
+    context: &mut tera::Context,
+    state: &AppState,
+    current_user: &CurrentUser,
+    path: &str,
+) {
+    let Some(u) = &current_user.0 else {
+        return;
+    };
+    if u.email.is_some() && state.needs_guidelines_acceptance(u) && state.can_post(u).await {
+        context.insert(
+            "guidelines_url",
+            &format!("/guidelines?return_to={}", urlencoding::encode(path)),
+        );
     }
 }
 
 /// Creates the Axum router with all routes and cache headers.
 pub fn create_router(state: AppState) -> Router {
+    // Validated at config load to hold no control characters
+    let server_header = HeaderValue::from_str(&state.config.instance.product())
+        .unwrap_or_else(|_| HeaderValue::from_static("September"));
+
     // Articles - longest cache, content is immutable
     let article_routes = Router::new()
-        .route("/a/{message_id}", get(article::view))
+        .route(
+            "/a/{message_id}",
+            get(head::get_article_view).head(head::article_view),
+        )
+        .route("/a/{message_id}/patch", get(article::patch))
         .layer(SetResponseHeaderLayer::if_not_present(
             CACHE_CONTROL,
             HeaderValue::from_static(CACHE_CONTROL_ARTICLE),
@@ -101,19 +185,29 @@ pub fn create_router(state: AppState) -> Router {
 
     // Thread view - medium cache, may get new replies
     let thread_view_routes = Router::new()
-        .route("/g/{group}/thread/{message_id}", get(threads::view))
+        .route(
+            "/g/{group}/thread/{message_id}",
+            get(head::get_thread_view).head(head::thread_view),
+        )
+        .route(
+            "/g/{group}/thread/{message_id}/subtree",
+            get(threads::subtree),
+        )
         .layer(SetResponseHeaderLayer::if_not_present(
             CACHE_CONTROL,
             HeaderValue::from_static(CACHE_CONTROL_THREAD_VIEW),
         ));
 
     // Thread list - shorter cache, new threads appear regularly
-    let thread_list_routes = Router::new().route("/g/{group}", get(threads::list)).layer(
-        SetResponseHeaderLayer::if_not_present(
+    let thread_list_routes = Router::new()
+        .route(
+            "/g/{group}",
+            get(head::get_thread_list).head(head::thread_list),
+        )
+        .layer(SetResponseHeaderLayer::if_not_present(
             CACHE_CONTROL,
             HeaderValue::from_static(CACHE_CONTROL_THREAD_LIST),
-        ),
-    );
+        ));
 
     // Home/browse - moderate cache
     let home_routes = Router::new()
@@ -139,11 +233,42 @@ pub fn create_router(state: AppState) -> Router {
         .route("/auth/callback/{provider}", get(auth::callback))
         .route("/auth/logout", post(auth::logout));
 
+    // Account routes - no caching (per-user data)
+    let account_routes = Router::new()
+        .route("/account", get(account::view))
+        .route("/account/export", get(account::export))
+        .route("/account/delete", post(account::delete))
+        .route("/account/invite", post(account::invite))
+        .route("/account/identity", post(account::identity));
+
+    // Guidelines routes - no caching (acceptance state is per-user)
+    let guidelines_routes = Router::new()
+        .route("/guidelines", get(guidelines::view))
+        .route("/guidelines/accept", post(guidelines::accept));
+
     // Post routes - no caching (stateful)
     let post_routes = Router::new()
         .route("/g/{group}/compose", get(post::compose))
         .route("/g/{group}/post", post(post::submit))
-        .route("/a/{message_id}/reply", post(post::reply));
+        .route("/a/{message_id}/reply", post(post::reply))
+        .route("/g/{group}/thread/{message_id}/pending", get(post::pending));
+
+    // Moderator review of held posts - no caching (stateful)
+    let review_routes = Router::new()
+        .route("/admin/review", get(review::queue))
+        .route("/admin/review/{id}/approve", post(review::approve))
+        .route("/admin/review/{id}/reject", post(review::reject));
+
+    // Moderator management of invite-only posting - no caching (stateful)
+    let invite_routes = Router::new()
+        .route("/admin/invites", get(invites::overview))
+        .route("/admin/invites/allow", post(invites::allow))
+        .route("/admin/invites/revoke", post(invites::revoke))
+        .route("/admin/invites/codes", post(invites::create_code))
+        .route(
+            "/admin/invites/codes/{code}/delete",
+            post(invites::delete_code),
+        );
 
     // Privacy policy - static content, can use home cache duration
     let privacy_routes = Router::new()
@@ -153,6 +278,31 @@ pub fn create_router(state: AppState) -> Router {
             HeaderValue::from_static(CACHE_CONTROL_HOME),
         ));
 
+    // About, statistics and status - counters change constantly, but a minute's delay is fine
+    let about_routes = Router::new()
+        .route("/about", get(about::index))
+        .route("/about/stats", get(about::stats))
+        .route("/about/status", get(about::status))
+        .route("/about/transparency", get(about::transparency))
+        .layer(SetResponseHeaderLayer::if_not_present(
+            CACHE_CONTROL,
+            HeaderValue::from_static(CACHE_CONTROL_HOME),
+        ));
+
+    // Discovery documents - configuration and usage counters, same delay as /about
+    let well_known_routes = Router::new()
+        .route("/.well-known/security.txt", get(well_known::security_txt))
+        .route("/.well-known/nodeinfo", get(well_known::nodeinfo_links))
+        .route("/nodeinfo/2.1", get(well_known::nodeinfo))
+        .layer(SetResponseHeaderLayer::if_not_present(
+            CACHE_CONTROL,
+            HeaderValue::from_static(CACHE_CONTROL_HOME),
+        ));
+
+    // Announcement dismissal - sets a cookie and redirects back
+    let announcement_routes =
+        Router::new().route("/announcements/{id}/dismiss", post(announcements::dismiss));
+
     // Health check - no caching, always fresh for liveness probes
     let health_routes = Router::new().route("/health", get(health::health));
 
@@ -162,13 +312,44 @@ pub fn create_router(state: AppState) -> Router {
         .merge(thread_list_routes)
         .merge(home_routes)
         .merge(auth_routes)
+        .merge(account_routes)
+        .merge(guidelines_routes)
         .merge(post_routes)
+        .merge(review_routes)
+        .merge(invite_routes)
         .merge(privacy_routes)
+        .merge(about_routes)
+        .merge(well_known_routes)
+        .merge(announcement_routes)
         .merge(health_routes)
         .merge(static_routes)
         .with_state(state.clone())
+        // Canonical redirect layer - trailing slashes, uppercase and renamed groups
+        .layer(middleware::from_fn_with_state(
+            state.clone(),
+            canonical_redirect_layer,
+        ))
+        // From display layer - masks poster addresses in serialized articles
+        .layer(middleware::from_fn_with_state(
+            state.clone(),
+            from_display_layer,
+        ))
+        // Deadline layer - times out slow requests and their queued NNTP work
+        .layer(middleware::from_fn_with_state(
+            state.clone(),
+            deadline_layer,
+        ))
+        // Announcement layer - picks the banners to show, minus dismissed ones
+        .layer(middleware::from_fn_with_state(
+            state.clone(),
+            announcement_layer,
+        ))
         // Auth layer - extracts user from session cookie and handles session refresh
         .layer(middleware::from_fn_with_state(state, auth_layer))
+        // Render mode layer - selects standard or basic HTML templates
+        .layer(middleware::from_fn(render_mode_layer))
         // Request ID middleware - creates root span with request_id for correlation
         .layer(middleware::from_fn(request_id_layer))
+        // Server header - identifies the software and instance URL
+        .layer(SetResponseHeaderLayer::overriding(SERVER, server_header))
 }
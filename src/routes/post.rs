@@ -1,23 +1,34 @@
 //! Handlers for posting new articles and replies.
 //!
 //! Requires authentication with a valid email address.
-//! Posts are submitted via NNTP POST command.
+//! Posts are submitted via NNTP POST command, or held for a moderator while
+//! the poster's first posts are reviewed (see `crate::review`).
 //! All post forms are protected by CSRF tokens.
 
 use axum::{
-    extract::{Path, State},
-    response::{Html, Redirect},
-    Extension, Form,
+    extract::State,
+    response::{Html, IntoResponse, Redirect, Response},
+    Extension, Form, Json,
 };
 use chrono::Utc;
+use http::StatusCode;
 use serde::Deserialize;
 use tracing::instrument;
 use uuid::Uuid;
 
+use super::extract::{GroupName, MessageId};
+use super::{insert_auth_context, insert_group_context};
+use crate::config::REVIEW_MAX_HELD_PER_POSTER;
 use crate::error::{AppError, AppErrorResponse, ResultExt};
-use crate::middleware::{RequestId, RequireAuthWithEmail};
-use crate::nntp::{compute_preview, compute_timeago, ArticleView};
+use crate::metrics::metrics;
+use crate::middleware::{CurrentUser, RenderMode, RequestId, RequireAuth, RequireAuthWithEmail};
+use crate::nntp::{
+    compute_preview, compute_timeago, sanitize_header, ArticleView, PendingPostView,
+};
+use crate::oidc::session::User;
+use crate::review::{poster_id, HeldArticle, HeldPost};
 use crate::state::AppState;
+use crate::templates::render;
 
 /// Maximum length for subject line (characters)
 const MAX_SUBJECT_LENGTH: usize = 500;
@@ -31,6 +42,8 @@ pub struct ComposeForm {
     pub body: String,
     /// CSRF token for form protection
     pub csrf_token: String,
+    /// Set when resubmitting after a tag suggestion, to post as is
+    pub tags_confirmed: Option<String>,
 }
 
 /// Form data for replying to an article
@@ -48,14 +61,48 @@ pub struct ReplyForm {
 }
 
 /// Parameters for posting an article and updating cache
-struct PostArticleParams<'a> {
+pub(super) struct PostArticleParams<'a> {
     group: &'a str,
     subject: String,
     body: String,
     from: String,
+    reply_to: Option<String>,
     references: Option<String>,
     root_message_id: Option<&'a str>,
     parent_message_id: Option<&'a str>,
+    /// Who is posting, as "provider:sub"
+    poster: String,
+}
+
+impl<'a> PostArticleParams<'a> {
+    /// Parameters for sending a post a moderator approved
+    pub(super) fn from_held(post: &'a HeldPost) -> Self {
+        let article = &post.article;
+        Self {
+            group: &article.group,
+            subject: article.subject.clone(),
+            body: article.body.clone(),
+            from: article.from.clone(),
+            reply_to: article.reply_to.clone(),
+            references: article.references.clone(),
+            root_message_id: article.root_message_id.as_deref(),
+            parent_message_id: article.parent_message_id.as_deref(),
+            poster: post.poster.clone(),
+        }
+    }
+
+    fn to_held(&self) -> HeldArticle {
+        HeldArticle {
+            group: self.group.to_string(),
+            subject: self.subject.clone(),
+            body: self.body.clone(),
+            from: self.from.clone(),
+            reply_to: self.reply_to.clone(),
+            references: self.references.clone(),
+            root_message_id: self.root_message_id.map(str::to_string),
+            parent_message_id: self.parent_message_id.map(str::to_string),
+        }
+    }
 }
 
 /// Format the From header from user info
@@ -127,9 +174,9 @@ fn validate_input_lengths(subject: &str, body: &str) -> Result<(), AppError> {
 /// 1. Generates message ID and date
 /// 2. Posts the article to NNTP server
 /// 3. Builds an ArticleView from local data
-/// 4. Waits for STAT confirmation that article is indexed
+/// 4. Injects it into caches (STAT confirmation happens in the background)
 /// 5. Updates cache for immediate visibility after redirect
-async fn post_and_update_cache(
+pub(super) async fn post_and_update_cache(
     state: &AppState,
     params: PostArticleParams<'_>,
 ) -> Result<(), AppError> {
@@ -144,13 +191,13 @@ async fn post_and_update_cache(
         ("Message-ID".to_string(), message_id.clone()),
         ("Date".to_string(), date.clone()),
     ];
+    if let Some(reply_to) = &params.reply_to {
+        headers.push(("Reply-To".to_string(), reply_to.clone()));
+    }
     if let Some(refs) = &params.references {
         headers.push(("References".to_string(), refs.clone()));
     }
-    headers.push((
-        "User-Agent".to_string(),
-        format!("September/{}", env!("CARGO_PKG_VERSION")),
-    ));
+    headers.push(("User-Agent".to_string(), state.config.instance.user_agent()));
 
     // Post the article
     state
@@ -158,13 +205,14 @@ async fn post_and_update_cache(
         .post_article(params.group, headers, params.body.clone())
         .await
         .map_err(|e| AppError::Internal(format!("Failed to post: {}", e)))?;
+    metrics().record_post();
 
     // Build ArticleView from local data (no network fetch needed)
     let (body_preview, has_more_content) = compute_preview(&params.body);
     let article = ArticleView {
         message_id,
-        subject: params.subject,
-        from: params.from,
+        subject: sanitize_header(&params.subject),
+        from: sanitize_header(&params.from),
         date: date.clone(),
         date_relative: compute_timeago(&date),
         body: Some(params.body),
@@ -173,12 +221,13 @@ async fn post_and_update_cache(
         headers: None,
     };
 
-    // Inject into cache after confirming existence via STAT
+    // Inject into caches now; existence is confirmed via STAT in the background
     state
         .nntp
         .inject_posted_article(
             params.group,
             article,
+            &params.poster,
             params.root_message_id,
             params.parent_message_id,
         )
@@ -187,17 +236,72 @@ async fn post_and_update_cache(
     Ok(())
 }
 
+/// Post the article, or hold it for a moderator while the poster's first
+/// posts are reviewed. Returns whether it was held.
+async fn post_or_hold(
+    state: &AppState,
+    user: &User,
+    params: PostArticleParams<'_>,
+) -> Result<bool, AppError> {
+    let poster = poster_id(&user.provider, &user.sub);
+    if state.config.posting.is_moderator(&user.provider, &user.sub)
+        || !state.review.needs_review(&poster).await
+    {
+        post_and_update_cache(state, params).await?;
+        return Ok(false);
+    }
+
+    // One account shouldn't be able to bury the queue
+    if state.review.held_by(&poster).await >= REVIEW_MAX_HELD_PER_POSTER {
+        return Err(AppError::BadRequest(format!(
+            "You already have {} posts waiting for review",
+            REVIEW_MAX_HELD_PER_POSTER
+        )));
+    }
+
+    let post = HeldPost::new(poster, params.to_held());
+    let id = post.id.clone();
+    state
+        .review
+        .hold(post)
+        .await
+        .map_err(|e| AppError::Internal(format!("Failed to hold post for review: {}", e)))?;
+    tracing::info!(group = %params.group, %id, "Post held for review");
+    Ok(true)
+}
+
+/// Page telling the poster their post waits for a moderator
+fn render_held(
+    state: &AppState,
+    render_mode: RenderMode,
+    group: &str,
+    user: &User,
+) -> Result<Response, AppError> {
+    let mut context = tera::Context::new();
+    context.insert("config", &state.config.ui);
+    insert_group_context(&mut context, state, group);
+    context.insert(
+        "review_first_posts",
+        &state.config.posting.review_first_posts,
+    );
+    insert_auth_context(&mut context, state, &CurrentUser(Some(user.clone())), false);
+
+    let html = render(state.tera_for(render_mode), "post_held.html", &context)?;
+    Ok((StatusCode::ACCEPTED, Html(html)).into_response())
+}
+
 /// Handler for compose form (new post)
 #[instrument(
     name = "post::compose",
-    skip(state, request_id, auth),
+    skip(state, request_id, render_mode, auth),
     fields(group = %group)
 )]
 pub async fn compose(
     State(state): State<AppState>,
     Extension(request_id): Extension<RequestId>,
+    Extension(render_mode): Extension<RenderMode>,
     auth: RequireAuthWithEmail,
-    Path(group): Path<String>,
+    group: GroupName,
 ) -> Result<Html<String>, AppErrorResponse> {
     let RequireAuthWithEmail { user, email } = auth;
 
@@ -210,41 +314,88 @@ pub async fn compose(
         .with_request_id(&request_id);
     }
 
+    let body = state
+        .config
+        .posting
+        .rule_for(&group)
+        .and_then(|rule| rule.body_template.as_deref())
+        .unwrap_or_default();
+    let draft = ComposeDraft {
+        subject: "",
+        body,
+        error: None,
+        suggestion: false,
+    };
+    let html = render_compose(&state, render_mode, &group, &user, &email, draft)
+        .with_request_id(&request_id)?;
+
+    Ok(Html(html))
+}
+
+/// Values to fill the compose form with
+struct ComposeDraft<'a> {
+    subject: &'a str,
+    body: &'a str,
+    /// Why the previous submission was rejected
+    error: Option<&'a str>,
+    /// The error is a tag suggestion the poster may dismiss by resubmitting
+    suggestion: bool,
+}
+
+/// Render the compose form with the group's posting rule (template, tags, guidelines)
+fn render_compose(
+    state: &AppState,
+    render_mode: RenderMode,
+    group: &str,
+    user: &User,
+    email: &str,
+    draft: ComposeDraft<'_>,
+) -> Result<String, AppError> {
     let mut context = tera::Context::new();
     context.insert("config", &state.config.ui);
-    context.insert("group", &group);
+    insert_group_context(&mut context, state, group);
     context.insert(
         "user",
         &serde_json::json!({
             "display_name": user.display_name(),
             "email": email,
+            "from": format_from_header(user.posting_display_name(), email),
+            "reply_to": user.reply_to,
         }),
     );
     context.insert("csrf_token", &user.csrf_token);
     context.insert("oidc_enabled", &state.oidc.is_some());
-
-    let html = state
-        .tera
-        .render("compose.html", &context)
-        .map_err(AppError::from)
-        .with_request_id(&request_id)?;
-
-    Ok(Html(html))
+    if let Some(rule) = state.config.posting.rule_for(group) {
+        context.insert("required_tags", &rule.required_tags);
+        context.insert("known_tags", &rule.known_tags());
+        context.insert("group_guidelines", &rule.guidelines);
+    }
+    context.insert("subject", draft.subject);
+    context.insert("body", draft.body);
+    context.insert("error", &draft.error);
+    context.insert("suggestion", &draft.suggestion);
+
+    Ok(render(
+        state.tera_for(render_mode),
+        "compose.html",
+        &context,
+    )?)
 }
 
 /// Handler for submitting a new post
 #[instrument(
     name = "post::submit",
-    skip(state, request_id, auth, form),
+    skip(state, request_id, render_mode, auth, form),
     fields(group = %group)
 )]
 pub async fn submit(
     State(state): State<AppState>,
     Extension(request_id): Extension<RequestId>,
+    Extension(render_mode): Extension<RenderMode>,
     auth: RequireAuthWithEmail,
-    Path(group): Path<String>,
+    group: GroupName,
     Form(form): Form<ComposeForm>,
-) -> Result<Redirect, AppErrorResponse> {
+) -> Result<Response, AppErrorResponse> {
     let RequireAuthWithEmail { user, email } = auth;
 
     // Validate CSRF token
@@ -265,39 +416,74 @@ pub async fn submit(
             .with_request_id(&request_id);
     }
 
-    // Post and update cache
-    post_and_update_cache(
+    // Enforce the group's posting rule (tag rules first, so auto-added tags
+    // count towards required tags), showing the form again on failure
+    let mut subject = form.subject.trim().to_string();
+    if let Some(rule) = state.config.posting.rule_for(&group) {
+        let checked = rule
+            .apply_tag_rules(&subject, &form.body, form.tags_confirmed.is_some())
+            .map_err(|r| (r.message, r.suggestion))
+            .and_then(|tagged| {
+                rule.validate(&tagged, &form.body)
+                    .map(|()| tagged)
+                    .map_err(|message| (message, false))
+            });
+        match checked {
+            Ok(tagged) => subject = tagged,
+            Err((message, suggestion)) => {
+                let draft = ComposeDraft {
+                    subject: &form.subject,
+                    body: &form.body,
+                    error: Some(&message),
+                    suggestion,
+                };
+                let html = render_compose(&state, render_mode, &group, &user, &email, draft)
+                    .with_request_id(&request_id)?;
+                return Ok((StatusCode::UNPROCESSABLE_ENTITY, Html(html)).into_response());
+            }
+        }
+    }
+
+    // Post and update cache, unless the post waits for review
+    let held = post_or_hold(
         &state,
+        &user,
         PostArticleParams {
             group: &group,
-            subject: form.subject.trim().to_string(),
+            subject,
             body: form.body,
-            from: format_from_header(user.name.as_deref(), &email),
+            from: format_from_header(user.posting_display_name(), &email),
+            reply_to: user.reply_to.clone(),
             references: None,
             root_message_id: None,
             parent_message_id: None,
+            poster: poster_id(&user.provider, &user.sub),
         },
     )
     .await
     .with_request_id(&request_id)?;
+    if held {
+        return render_held(&state, render_mode, &group, &user).with_request_id(&request_id);
+    }
 
     tracing::info!(group = %group, "New article posted successfully");
-    Ok(Redirect::to(&format!("/g/{}", group)))
+    Ok(Redirect::to(&format!("/g/{}", group)).into_response())
 }
 
 /// Handler for submitting a reply
 #[instrument(
     name = "post::reply",
-    skip(state, request_id, auth, form),
+    skip(state, request_id, render_mode, auth, form),
     fields(message_id = %message_id)
 )]
 pub async fn reply(
     State(state): State<AppState>,
     Extension(request_id): Extension<RequestId>,
+    Extension(render_mode): Extension<RenderMode>,
     auth: RequireAuthWithEmail,
-    Path(message_id): Path<String>,
+    message_id: MessageId,
     Form(form): Form<ReplyForm>,
-) -> Result<Redirect, AppErrorResponse> {
+) -> Result<Response, AppErrorResponse> {
     let RequireAuthWithEmail { user, email } = auth;
 
     // Validate CSRF token
@@ -309,6 +495,7 @@ pub async fn reply(
     }
 
     // Validate input
+    let group = GroupName::parse(&form.group).with_request_id(&request_id)?;
     validate_input_lengths(&form.subject, &form.body).with_request_id(&request_id)?;
     if form.body.trim().is_empty() {
         return Err(AppError::Internal("Message body is required".into()))
@@ -317,14 +504,14 @@ pub async fn reply(
 
     // Build references chain: parent's References + parent's Message-ID
     let references = if form.references.trim().is_empty() {
-        message_id.clone()
+        message_id.to_string()
     } else {
         format!("{} {}", form.references.trim(), message_id)
     };
 
     // Determine thread root (first in references chain, or parent if direct reply)
     let root_message_id = if form.references.trim().is_empty() {
-        message_id.clone()
+        message_id.to_string()
     } else {
         form.references
             .split_whitespace()
@@ -333,26 +520,55 @@ pub async fn reply(
             .to_string()
     };
 
-    // Post and update cache
-    post_and_update_cache(
+    // Post and update cache, unless the reply waits for review
+    let held = post_or_hold(
         &state,
+        &user,
         PostArticleParams {
-            group: &form.group,
+            group: &group,
             subject: form.subject.trim().to_string(),
             body: form.body,
-            from: format_from_header(user.name.as_deref(), &email),
+            from: format_from_header(user.posting_display_name(), &email),
+            reply_to: user.reply_to.clone(),
             references: Some(references),
             root_message_id: Some(&root_message_id),
-            parent_message_id: Some(&message_id),
+            parent_message_id: Some(&*message_id),
+            poster: poster_id(&user.provider, &user.sub),
         },
     )
     .await
     .with_request_id(&request_id)?;
+    if held {
+        return render_held(&state, render_mode, &group, &user).with_request_id(&request_id);
+    }
 
-    tracing::info!(parent = %message_id, group = %form.group, "Reply posted successfully");
-    let encoded_parent = urlencoding::encode(&message_id);
+    tracing::info!(parent = %message_id, group = %group, "Reply posted successfully");
     Ok(Redirect::to(&format!(
         "/g/{}/thread/{}",
-        form.group, encoded_parent
-    )))
+        group,
+        message_id.path_segment()
+    ))
+    .into_response())
+}
+
+/// Delivery status of the caller's articles recently posted to a thread.
+///
+/// Polled by the thread view to replace "delivering" placeholders once the
+/// server confirms the article. Returns an empty list when nothing is pending.
+/// Only the caller's posts are listed, since only the poster gets
+/// placeholders. The articles themselves are already visible to every reader
+/// from the shared caches.
+pub async fn pending(
+    State(state): State<AppState>,
+    auth: RequireAuth,
+    group: GroupName,
+    root_message_id: MessageId,
+) -> Json<Vec<PendingPostView>> {
+    let poster = poster_id(&auth.user.provider, &auth.user.sub);
+    Json(
+        state
+            .nntp
+            .pending_posts(&group, &root_message_id, &poster)
+            .await,
+    )
 }
@@ -3,26 +3,30 @@
 use axum::{extract::State, response::Html, Extension};
 use tracing::instrument;
 
-use super::insert_auth_context;
+use super::{insert_auth_context, insert_canonical_url};
 use crate::error::{AppError, AppErrorResponse, ResultExt};
-use crate::middleware::{CurrentUser, RequestId};
+use crate::middleware::{CurrentUser, RenderMode, RequestId};
 use crate::state::AppState;
+use crate::templates::render;
 
 /// Privacy policy page handler.
-#[instrument(name = "privacy::privacy", skip(state, request_id, current_user))]
+#[instrument(
+    name = "privacy::privacy",
+    skip(state, request_id, current_user, render_mode)
+)]
 pub async fn privacy(
     State(state): State<AppState>,
     Extension(request_id): Extension<RequestId>,
     Extension(current_user): Extension<CurrentUser>,
+    Extension(render_mode): Extension<RenderMode>,
 ) -> Result<Html<String>, AppErrorResponse> {
     let mut context = tera::Context::new();
     context.insert("config", &state.config.ui);
+    insert_canonical_url(&mut context, &state, "/privacy");
 
     insert_auth_context(&mut context, &state, &current_user, false);
 
-    let html = state
-        .tera
-        .render("privacy.html", &context)
+    let html = render(state.tera_for(render_mode), "privacy.html", &context)
         .map_err(AppError::from)
         .with_request_id(&request_id)?;
     Ok(Html(html))
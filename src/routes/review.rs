@@ -0,0 +1,139 @@
+//! Moderator review of held posts.
+//!
+//! Routes:
+//! - GET /admin/review - Posts waiting for review
+//! - POST /admin/review/{id}/approve - Send a held post to the NNTP server
+//! - POST /admin/review/{id}/reject - Drop a held post
+//!
+//! Only subjects in `[posting] moderators` may use these; anyone else gets
+//! 403. Rejected posters are not notified: there is nowhere to tell them.
+
+use axum::{
+    extract::{Path, State},
+    response::{Html, Redirect},
+    Extension, Form,
+};
+use serde::Deserialize;
+use tracing::instrument;
+
+use super::insert_auth_context;
+use super::post::{post_and_update_cache, PostArticleParams};
+use crate::error::{AppError, AppErrorResponse, ResultExt};
+use crate::middleware::{CurrentUser, RenderMode, RequestId, RequireAuth};
+use crate::oidc::session::User;
+use crate::review::HeldPost;
+use crate::state::AppState;
+use crate::templates::render;
+
+/// Form data for approving or rejecting a held post
+#[derive(Debug, Deserialize)]
+pub struct ReviewForm {
+    pub csrf_token: String,
+}
+
+/// Refuse anyone who isn't a moderator, and forms without a valid CSRF token
+pub(super) fn check_moderator(
+    state: &AppState,
+    user: &User,
+    csrf_token: Option<&str>,
+) -> Result<(), AppError> {
+    if !state.config.posting.is_moderator(&user.provider, &user.sub) {
+        return Err(AppError::Forbidden(
+            "Only moderators can use this page".into(),
+        ));
+    }
+    if csrf_token.is_some_and(|token| !user.validate_csrf(token)) {
+        return Err(AppError::Internal(
+            "Invalid form submission. Please try again.".into(),
+        ));
+    }
+    Ok(())
+}
+
+/// List the held posts, oldest first
+#[instrument(
+    name = "review::queue",
+    skip(state, request_id, current_user, render_mode, auth)
+)]
+pub async fn queue(
+    State(state): State<AppState>,
+    Extension(request_id): Extension<RequestId>,
+    Extension(current_user): Extension<CurrentUser>,
+    Extension(render_mode): Extension<RenderMode>,
+    auth: RequireAuth,
+) -> Result<Html<String>, AppErrorResponse> {
+    check_moderator(&state, &auth.user, None).with_request_id(&request_id)?;
+
+    let mut context = tera::Context::new();
+    context.insert("config", &state.config.ui);
+    context.insert("held", &state.review.held().await);
+    context.insert(
+        "review_first_posts",
+        &state.config.posting.review_first_posts,
+    );
+
+    insert_auth_context(&mut context, &state, &current_user, true);
+
+    let html = render(state.tera_for(render_mode), "admin/review.html", &context)
+        .map_err(AppError::from)
+        .with_request_id(&request_id)?;
+    Ok(Html(html))
+}
+
+/// Send a held post as it was submitted, and count it towards the poster's
+/// first posts. If sending fails the post goes back to the queue.
+#[instrument(name = "review::approve", skip(state, request_id, auth, form))]
+pub async fn approve(
+    State(state): State<AppState>,
+    Extension(request_id): Extension<RequestId>,
+    auth: RequireAuth,
+    Path(id): Path<String>,
+    Form(form): Form<ReviewForm>,
+) -> Result<Redirect, AppErrorResponse> {
+    check_moderator(&state, &auth.user, Some(&form.csrf_token)).with_request_id(&request_id)?;
+
+    // Taking it first keeps two moderators from sending it twice
+    let Some(post) = take(&state, &id).await.with_request_id(&request_id)? else {
+        return Ok(Redirect::to("/admin/review"));
+    };
+    if let Err(e) = post_and_update_cache(&state, PostArticleParams::from_held(&post)).await {
+        if let Err(restore) = state.review.restore(post).await {
+            tracing::error!(%id, error = %restore, "Failed to return held post to the queue");
+        }
+        return Err(e).with_request_id(&request_id);
+    }
+    if let Err(e) = state.review.record_approved(&post.poster).await {
+        tracing::warn!(%id, error = %e, "Failed to save approval count");
+    }
+
+    tracing::info!(%id, group = %post.article.group, "Held post approved");
+    Ok(Redirect::to("/admin/review"))
+}
+
+/// Drop a held post
+#[instrument(name = "review::reject", skip(state, request_id, auth, form))]
+pub async fn reject(
+    State(state): State<AppState>,
+    Extension(request_id): Extension<RequestId>,
+    auth: RequireAuth,
+    Path(id): Path<String>,
+    Form(form): Form<ReviewForm>,
+) -> Result<Redirect, AppErrorResponse> {
+    check_moderator(&state, &auth.user, Some(&form.csrf_token)).with_request_id(&request_id)?;
+
+    if let Some(post) = take(&state, &id).await.with_request_id(&request_id)? {
+        if let Err(e) = state.review.record_rejected().await {
+            tracing::warn!(%id, error = %e, "Failed to save rejection count");
+        }
+        tracing::info!(%id, group = %post.article.group, "Held post rejected");
+    }
+    Ok(Redirect::to("/admin/review"))
+}
+
+async fn take(state: &AppState, id: &str) -> Result<Option<HeldPost>, AppError> {
+    state
+        .review
+        .take(id)
+        .await
+        .map_err(|e| AppError::Internal(format!("Failed to update review queue: {}", e)))
+}
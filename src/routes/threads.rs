@@ -1,46 +1,101 @@
 //! Handlers for thread listing and thread viewing.
 //!
-//! Supports pagination for both thread lists and article comments.
+//! Supports pagination for both thread lists and article comments, and
+//! date range / author / subject tag filtering for thread lists.
 
 use axum::{
-    extract::{Path, Query, State},
-    response::Html,
+    extract::{Query, State},
+    response::{Html, IntoResponse, Response},
     Extension,
 };
 use serde::Deserialize;
+use std::sync::Arc;
 use tracing::instrument;
 
-use super::{can_post_to_group, insert_auth_context};
+use super::extract::{message_id_segment, GroupName, MessageId};
+use super::{
+    can_post_to_group, insert_auth_context, insert_canonical_url, insert_group_context,
+    insert_guidelines_url, paged_path,
+};
+use crate::config::STREAM_RENDER_MIN_COMMENTS;
 use crate::error::{AppError, AppErrorResponse, ResultExt};
-use crate::middleware::{CurrentUser, RequestId};
-use crate::state::AppState;
+use crate::metrics::metrics;
+use crate::middleware::{CurrentUser, RenderMode, RequestId};
+use crate::nntp::{
+    date_slot, fill_date_slots, BodyFetch, DeliveryStatus, PendingPostView, ThreadFilter,
+};
+use crate::review::poster_id;
+use crate::state::{AppState, ThreadFragmentKey};
+use crate::templates::{render, render_stream};
 
 /// Query parameters for thread list pagination.
 #[derive(Deserialize)]
 pub struct ListParams {
     pub page: Option<usize>,
+    /// Page size override, bounded by `max_per_page`
+    pub per_page: Option<usize>,
+    /// Thread list snapshot token from a previous page, for stable ordering
+    pub snapshot: Option<String>,
+    /// Only threads with activity on or after this day (YYYY-MM-DD)
+    pub since: Option<String>,
+    /// Only threads with activity on or before this day (YYYY-MM-DD)
+    pub until: Option<String>,
+    /// Only threads started by an author matching this text
+    pub author: Option<String>,
+    /// Only threads whose subject carries this tag ("BUG" or "[BUG]")
+    pub tag: Option<String>,
+}
+
+impl ListParams {
+    /// Filter parameters re-encoded for pagination links (HTML-escaped `&amp;`).
+    fn filter_query(&self) -> String {
+        [
+            ("since", &self.since),
+            ("until", &self.until),
+            ("author", &self.author),
+            ("tag", &self.tag),
+        ]
+        .iter()
+        .filter_map(|(name, value)| {
+            value
+                .as_deref()
+                .filter(|v| !v.trim().is_empty())
+                .map(|v| format!("{}={}", name, urlencoding::encode(v)))
+        })
+        .collect::<Vec<_>>()
+        .join("&amp;")
+    }
 }
 
 /// Handler for paginated thread list in a newsgroup.
 #[instrument(
     name = "threads::list",
-    skip(state, params, request_id, current_user),
+    skip(state, params, request_id, current_user, render_mode),
     fields(group = %group)
 )]
 pub async fn list(
     State(state): State<AppState>,
     Extension(request_id): Extension<RequestId>,
     Extension(current_user): Extension<CurrentUser>,
-    Path(group): Path<String>,
+    Extension(render_mode): Extension<RenderMode>,
+    group: GroupName,
     Query(params): Query<ListParams>,
 ) -> Result<Html<String>, AppErrorResponse> {
     let page = params.page.unwrap_or(1).max(1);
-    let per_page = state.config.nntp.defaults.threads_per_page;
+    let defaults = &state.config.nntp.defaults;
+    let per_page = defaults.per_page(params.per_page, defaults.threads_per_page);
+    let filter = ThreadFilter::from_params(
+        params.since.as_deref(),
+        params.until.as_deref(),
+        params.author.as_deref(),
+    )
+    .with_tag(params.tag.as_deref())
+    .with_from_display(state.config.ui.from_display);
 
     // Fetch paginated threads
-    let (threads, pagination) = state
+    let (threads, pagination, snapshot) = state
         .nntp
-        .get_threads_paginated(&group, page, per_page)
+        .get_threads_paginated(&group, page, per_page, params.snapshot.as_deref(), &filter)
         .await
         .with_request_id(&request_id)?;
 
@@ -55,83 +110,281 @@ pub async fn list(
     // Check if user can post to this group
     let can_post = can_post_to_group(&current_user, &state, &group).await;
 
+    // Near-duplicate roots (link-spam) are flagged or collapsed in the listing
+    let duplicates = state.nntp.duplicate_roots(&threads).await;
+
     let mut context = tera::Context::new();
     context.insert("config", &state.config.ui);
-    context.insert("group", &group);
+    insert_group_context(&mut context, &state, &group);
+    let path = paged_path(&format!("/g/{}", &*group), pagination.current_page);
+    insert_canonical_url(&mut context, &state, &path);
+    insert_guidelines_url(&mut context, &state, &current_user, &path).await;
     context.insert("threads", &threads);
+    context.insert("duplicates", &duplicates);
+    context.insert(
+        "collapse_duplicates",
+        &state.config.spam.collapse_duplicates,
+    );
     context.insert("pagination", &pagination);
+    context.insert("per_page", &params.per_page.map(|_| per_page));
+    context.insert("snapshot", &snapshot);
+    context.insert(
+        "filter",
+        &serde_json::json!({
+            "active": filter.is_active(),
+            "since": params.since.as_deref().unwrap_or_default(),
+            "until": params.until.as_deref().unwrap_or_default(),
+            "author": params.author.as_deref().unwrap_or_default(),
+            "tag": params.tag.as_deref().unwrap_or_default(),
+        }),
+    );
+    // Tags from the group's posting rule, offered as filter links
+    let known_tags = state
+        .config
+        .posting
+        .rule_for(&group)
+        .map(|rule| rule.known_tags())
+        .unwrap_or_default();
+    context.insert("known_tags", &known_tags);
+    context.insert("filter_query", &params.filter_query());
     context.insert("can_post", &can_post);
 
     insert_auth_context(&mut context, &state, &current_user, false);
 
-    let html = state
-        .tera
-        .render("threads/list.html", &context)
+    let html = render(state.tera_for(render_mode), "threads/list.html", &context)
         .map_err(AppError::from)
         .with_request_id(&request_id)?;
     Ok(Html(html))
 }
 
-/// Path parameters for thread view (group and message_id).
-#[derive(Debug, Deserialize)]
-pub struct ViewPath {
-    pub group: String,
-    pub message_id: String,
-}
-
 /// Query parameters for thread view pagination.
 #[derive(Deserialize)]
 pub struct ViewParams {
     pub page: Option<usize>,
+    /// Page size override, bounded by `max_per_page`
+    pub per_page: Option<usize>,
+    /// Message ID of a collapsed comment to expand server-side (basic HTML mode)
+    pub expand: Option<String>,
 }
 
 /// Handler for viewing a thread with paginated comments.
 #[instrument(
     name = "threads::view",
-    skip(state, params, request_id, current_user),
-    fields(group = %path.group, message_id = %path.message_id)
+    skip(state, params, request_id, current_user, render_mode),
+    fields(group = %group, message_id = %message_id)
 )]
 pub async fn view(
     State(state): State<AppState>,
     Extension(request_id): Extension<RequestId>,
     Extension(current_user): Extension<CurrentUser>,
-    Path(path): Path<ViewPath>,
+    Extension(render_mode): Extension<RenderMode>,
+    group: GroupName,
+    message_id: MessageId,
     Query(params): Query<ViewParams>,
-) -> Result<Html<String>, AppErrorResponse> {
+) -> Result<Response, AppErrorResponse> {
     let page = params.page.unwrap_or(1).max(1);
-    let per_page = state.config.nntp.defaults.articles_per_page;
+    let defaults = &state.config.nntp.defaults;
+    let per_page = defaults.per_page(params.per_page, defaults.articles_per_page);
     let collapse_threshold = state.config.ui.collapse_threshold;
 
+    // The text layout shows every comment; the others leave the bodies
+    // under collapsed comments for the subtree fragment
+    let fetch = match render_mode {
+        RenderMode::Text => BodyFetch::All,
+        _ => BodyFetch::Visible {
+            expand: params.expand.as_deref(),
+        },
+    };
+
     // Fetch thread with paginated article bodies
     let (thread, comments, pagination) = state
         .nntp
         .get_thread_paginated(
-            &path.group,
-            &path.message_id,
+            &group,
+            &message_id,
             page,
             per_page,
             collapse_threshold,
+            fetch,
         )
         .await
         .with_request_id(&request_id)?;
 
     // Check if user can post to this group
-    let can_post = can_post_to_group(&current_user, &state, &path.group).await;
+    let can_post = can_post_to_group(&current_user, &state, &group).await;
+
+    // Vote tally for groups that decide by reply
+    let votes = state.nntp.vote_tally(&group, &thread).await;
+
+    // The viewer's recently posted articles still awaiting server confirmation
+    let pending: Vec<PendingPostView> = match &current_user.0 {
+        Some(user) => state
+            .nntp
+            .pending_posts(
+                &group,
+                &thread.root_message_id,
+                &poster_id(&user.provider, &user.sub),
+            )
+            .await
+            .into_iter()
+            .filter(|p| p.status != DeliveryStatus::Confirmed)
+            .collect(),
+        None => Vec::new(),
+    };
 
     let mut context = tera::Context::new();
     context.insert("config", &state.config.ui);
-    context.insert("group", &path.group);
+    insert_group_context(&mut context, &state, &group);
+    let path = paged_path(
+        &format!(
+            "/g/{}/thread/{}",
+            &*group,
+            message_id_segment(&thread.root_message_id)
+        ),
+        pagination.current_page,
+    );
+    insert_canonical_url(&mut context, &state, &path);
+    insert_guidelines_url(&mut context, &state, &current_user, &path).await;
     context.insert("thread", &thread);
     context.insert("comments", &comments);
     context.insert("pagination", &pagination);
+    context.insert("per_page", &params.per_page.map(|_| per_page));
     context.insert("can_post", &can_post);
+    context.insert("expand", &params.expand);
+    context.insert("pending", &pending);
+    context.insert("votes", &votes);
 
     insert_auth_context(&mut context, &state, &current_user, true);
 
-    let html = state
-        .tera
-        .render("threads/view.html", &context)
+    // Without reply forms, delivery notices or bodies to retry, a page of
+    // comments is the same for every visitor using this mode, so it is
+    // rendered once per version of the thread
+    let fragment_key =
+        if can_post || !pending.is_empty() || comments.iter().any(|c| c.body_unavailable) {
+            None
+        } else {
+            Some(ThreadFragmentKey {
+                mode: render_mode,
+                group: group.to_string(),
+                group_version: state.nntp.group_version(&group).await,
+                root_message_id: thread.root_message_id.clone(),
+                comment_count: comments.len(),
+                last_post_date: thread.last_post_date.clone(),
+                page: pagination.current_page,
+                per_page: params.per_page.map(|_| per_page),
+                expand: params.expand.clone(),
+            })
+        };
+
+    // A long page that isn't cached is streamed as it renders, with the
+    // comments inline, rather than built up in memory first
+    if fragment_key.is_none() && comments.len() >= STREAM_RENDER_MIN_COMMENTS {
+        let body = render_stream(
+            state.tera_for(render_mode).clone(),
+            "threads/view.html",
+            context,
+        )
+        .await
+        .map_err(AppError::from)
+        .with_request_id(&request_id)?;
+        return Ok(Html(body).into_response());
+    }
+
+    let cached = match &fragment_key {
+        Some(key) => {
+            let cached = state.thread_fragments.get(key).await;
+            metrics().thread_fragments.record(cached.is_some());
+            cached
+        }
+        None => None,
+    };
+    let comments_html = match cached {
+        Some(html) => html,
+        None => {
+            // A cached rendering outlives the relative dates in it, so those
+            // are left as slots and filled in each time it is served
+            let fragment_context = fragment_key.is_some().then(|| {
+                let mut slotted = comments.clone();
+                for article in slotted.iter_mut().filter_map(|c| c.article.as_mut()) {
+                    if let Some(slot) = date_slot(&article.date) {
+                        article.date_relative = slot;
+                    }
+                }
+                let mut fragment = context.clone();
+                fragment.insert("comments", &slotted);
+                fragment
+            });
+            let html: Arc<str> = render(
+                state.tera_for(render_mode),
+                "threads/comments.html",
+                fragment_context.as_ref().unwrap_or(&context),
+            )
+            .map_err(AppError::from)
+            .with_request_id(&request_id)?
+            .into();
+            if let Some(key) = fragment_key {
+                state.thread_fragments.insert(key, html.clone()).await;
+            }
+            html
+        }
+    };
+    context.insert("comments_html", &*fill_date_slots(&comments_html));
+
+    let html = render(state.tera_for(render_mode), "threads/view.html", &context)
         .map_err(AppError::from)
         .with_request_id(&request_id)?;
+    Ok(Html(html).into_response())
+}
+
+/// Handler for the comment bodies under `?expand=` on one thread page, as an
+/// HTML fragment. The thread page loads it when a collapsed comment whose
+/// replies were left without bodies is expanded.
+#[instrument(
+    name = "threads::subtree",
+    skip(state, params, request_id, render_mode),
+    fields(group = %group, message_id = %message_id)
+)]
+pub async fn subtree(
+    State(state): State<AppState>,
+    Extension(request_id): Extension<RequestId>,
+    Extension(render_mode): Extension<RenderMode>,
+    group: GroupName,
+    message_id: MessageId,
+    Query(params): Query<ViewParams>,
+) -> Result<Html<String>, AppErrorResponse> {
+    let expand = params
+        .expand
+        .as_deref()
+        .ok_or_else(|| AppError::BadRequest("Missing expand parameter".into()))
+        .with_request_id(&request_id)?;
+    let page = params.page.unwrap_or(1).max(1);
+    let defaults = &state.config.nntp.defaults;
+    let per_page = defaults.per_page(params.per_page, defaults.articles_per_page);
+
+    let comments = state
+        .nntp
+        .get_thread_subtree(&group, &message_id, expand, page, per_page)
+        .await
+        .with_request_id(&request_id)?;
+
+    let mut back_url = format!("/g/{}/thread/{}", &*group, message_id.path_segment());
+    if page > 1 {
+        back_url.push_str(&format!("%3Fpage%3D{}", page));
+    }
+
+    let mut context = tera::Context::new();
+    context.insert("config", &state.config.ui);
+    context.insert("group", &*group);
+    context.insert("diffs", &state.config.body.shows_diffs(&group));
+    context.insert("comments", &comments);
+    context.insert("back_url", &back_url);
+
+    let html = render(
+        state.tera_for(render_mode),
+        "threads/subtree.html",
+        &context,
+    )
+    .map_err(AppError::from)
+    .with_request_id(&request_id)?;
     Ok(Html(html))
 }
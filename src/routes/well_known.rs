@@ -0,0 +1,241 @@
+//! Discovery documents under `/.well-known/`.
+//!
+//! Routes:
+//! - GET /.well-known/security.txt - Security contact (RFC 9116) from `[instance.security_txt]`
+//! - GET /.well-known/nodeinfo - NodeInfo discovery links
+//! - GET /nodeinfo/2.1 - NodeInfo 2.1 document: software, version and usage
+
+use axum::{
+    extract::State,
+    http::{header::CONTENT_TYPE, HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use axum_extra::extract::Host;
+use chrono::{DateTime, Duration, SecondsFormat, Utc};
+use serde::Serialize;
+use serde_json::json;
+
+use super::auth::detect_https;
+use crate::config::{InstanceConfig, SECURITY_TXT_DEFAULT_EXPIRY_DAYS};
+use crate::metrics::metrics;
+use crate::state::AppState;
+
+/// NodeInfo schema implemented by `/nodeinfo/2.1`
+const NODEINFO_SCHEMA: &str = "http://nodeinfo.diaspora.software/ns/schema/2.1";
+
+/// security.txt, or 404 when no contact is configured
+pub async fn security_txt(State(state): State<AppState>) -> Response {
+    match render_security_txt(&state.config.instance, Utc::now()) {
+        Some(body) => ([(CONTENT_TYPE, "text/plain; charset=utf-8")], body).into_response(),
+        None => StatusCode::NOT_FOUND.into_response(),
+    }
+}
+
+/// Build security.txt. Contacts default to `[instance] admin_contact`, and
+/// `Expires` to `SECURITY_TXT_DEFAULT_EXPIRY_DAYS` from `now`.
+fn render_security_txt(instance: &InstanceConfig, now: DateTime<Utc>) -> Option<String> {
+    let config = &instance.security_txt;
+    let contacts: Vec<String> = if config.contact.is_empty() {
+        instance
+            .admin_contact
+            .iter()
+            .map(|c| contact_uri(c))
+            .collect()
+    } else {
+        config.contact.iter().map(|c| contact_uri(c)).collect()
+    };
+    if contacts.is_empty() {
+        return None;
+    }
+
+    let expires = config
+        .expires
+        .as_deref()
+        .and_then(|e| DateTime::parse_from_rfc3339(e).ok())
+        .map(|e| e.with_timezone(&Utc))
+        .unwrap_or_else(|| now + Duration::days(SECURITY_TXT_DEFAULT_EXPIRY_DAYS));
+
+    let mut lines: Vec<String> = contacts.iter().map(|c| format!("Contact: {}", c)).collect();
+    lines.push(format!(
+        "Expires: {}",
+        expires.to_rfc3339_opts(SecondsFormat::Secs, true)
+    ));
+    let optional = [
+        ("Encryption", &config.encryption),
+        ("Policy", &config.policy),
+        ("Acknowledgments", &config.acknowledgments),
+        ("Preferred-Languages", &config.preferred_languages),
+    ];
+    for (field, value) in optional {
+        if let Some(value) = value {
+            lines.push(format!("{}: {}", field, value));
+        }
+    }
+    if let Some(url) = &instance.url {
+        lines.push(format!(
+            "Canonical: {}/.well-known/security.txt",
+            url.trim_end_matches('/')
+        ));
+    }
+
+    let mut body = lines.join("\n");
+    body.push('\n');
+    Some(body)
+}
+
+/// Contacts must be URIs; bare email addresses get a `mailto:` scheme
+fn contact_uri(contact: &str) -> String {
+    if contact.contains(':') || !contact.contains('@') {
+        contact.to_string()
+    } else {
+        format!("mailto:{}", contact)
+    }
+}
+
+/// NodeInfo discovery: where to find the document for each supported schema
+pub async fn nodeinfo_links(
+    State(state): State<AppState>,
+    Host(host): Host,
+    headers: HeaderMap,
+) -> Json<serde_json::Value> {
+    let base = match &state.config.instance.url {
+        Some(url) => url.trim_end_matches('/').to_string(),
+        None => {
+            let scheme = if detect_https(&headers) {
+                "https"
+            } else {
+                "http"
+            };
+            format!("{}://{}", scheme, host)
+        }
+    };
+    Json(json!({
+        "links": [{
+            "rel": NODEINFO_SCHEMA,
+            "href": format!("{}/nodeinfo/2.1", base),
+        }]
+    }))
+}
+
+/// NodeInfo 2.1 document
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct NodeInfo {
+    version: &'static str,
+    software: Software,
+    /// NodeInfo only defines federation protocols; NNTP isn't one of them
+    protocols: Vec<&'static str>,
+    services: Services,
+    open_registrations: bool,
+    usage: Usage,
+    metadata: serde_json::Value,
+}
+
+#[derive(Serialize)]
+struct Software {
+    name: &'static str,
+    version: &'static str,
+    repository: &'static str,
+}
+
+#[derive(Serialize)]
+struct Services {
+    inbound: Vec<&'static str>,
+    outbound: Vec<&'static str>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Usage {
+    /// Accounts live with the identity providers, so there is nothing to count
+    users: serde_json::Map<String, serde_json::Value>,
+    /// Articles posted through this instance since it started
+    local_posts: u64,
+}
+
+/// NodeInfo 2.1: software, registration policy and usage since startup
+pub async fn nodeinfo(State(state): State<AppState>) -> Response {
+    let config = &state.config;
+    let metrics = metrics();
+    let document = NodeInfo {
+        version: "2.1",
+        software: Software {
+            name: "september",
+            version: env!("CARGO_PKG_VERSION"),
+            repository: env!("CARGO_PKG_REPOSITORY"),
+        },
+        protocols: Vec::new(),
+        services: Services {
+            inbound: Vec::new(),
+            outbound: Vec::new(),
+        },
+        open_registrations: config.oidc.is_some() && !config.posting.invite_only,
+        usage: Usage {
+            users: serde_json::Map::new(),
+            local_posts: metrics.posts_submitted(),
+        },
+        metadata: json!({
+            "nodeName": config.instance.name,
+            "adminContact": config.instance.admin_contact,
+            "groupsTracked": state.nntp.cached_group_count().await,
+            "articlesCached": state.nntp.cached_article_count(),
+            "upstreamServers": state.nntp.server_names(),
+            "uptimeSeconds": metrics.uptime_secs(),
+        }),
+    };
+
+    let content_type = format!("application/json; profile=\"{}#\"", NODEINFO_SCHEMA);
+    ([(CONTENT_TYPE, content_type)], Json(document)).into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::SecurityTxtConfig;
+
+    #[test]
+    fn test_security_txt_defaults_to_admin_contact() {
+        let mut instance = InstanceConfig::default();
+        let now = DateTime::parse_from_rfc3339("2026-01-01T00:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        assert_eq!(render_security_txt(&instance, now), None);
+
+        instance.admin_contact = Some("security@example.com".to_string());
+        instance.url = Some("https://news.example.com/".to_string());
+        let body = render_security_txt(&instance, now).unwrap();
+        assert!(body.starts_with("Contact: mailto:security@example.com\n"));
+        assert!(body.contains(&format!(
+            "Expires: {}\n",
+            (now + Duration::days(SECURITY_TXT_DEFAULT_EXPIRY_DAYS))
+                .to_rfc3339_opts(SecondsFormat::Secs, true)
+        )));
+        assert!(body.contains("Canonical: https://news.example.com/.well-known/security.txt\n"));
+    }
+
+    #[test]
+    fn test_security_txt_uses_configured_fields() {
+        let instance = InstanceConfig {
+            admin_contact: Some("admin@example.com".to_string()),
+            security_txt: SecurityTxtConfig {
+                contact: vec![
+                    "https://example.com/security".to_string(),
+                    "tel:+1-201-555-0123".to_string(),
+                ],
+                expires: Some("2027-06-30T12:00:00Z".to_string()),
+                policy: Some("https://example.com/disclosure".to_string()),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let body = render_security_txt(&instance, Utc::now()).unwrap();
+        assert_eq!(
+            body,
+            "Contact: https://example.com/security\n\
+             Contact: tel:+1-201-555-0123\n\
+             Expires: 2027-06-30T12:00:00Z\n\
+             Policy: https://example.com/disclosure\n"
+        );
+    }
+}
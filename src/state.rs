@@ -2,23 +2,47 @@
 
 use axum::extract::FromRef;
 use axum_extra::extract::cookie::Key;
+use moka::future::Cache;
 use std::sync::Arc;
+use std::time::Duration;
 use tera::Tera;
 
-use crate::config::AppConfig;
+use crate::announcements::Announcements;
+use crate::config::{AppConfig, THREAD_FRAGMENT_CACHE_MAX_ENTRIES, THREAD_FRAGMENT_CACHE_TTL_SECS};
+use crate::guidelines::Guidelines;
+use crate::invites::InviteStore;
+use crate::middleware::RenderMode;
 use crate::nntp::NntpFederatedService;
+use crate::oidc::session::User;
 use crate::oidc::OidcManager;
+use crate::review::ReviewQueue;
 
 /// Shared application state, cloneable across handlers via Arc-wrapped fields.
 ///
-/// Contains the application configuration, Tera template engine, and the
-/// federated NNTP service for accessing newsgroup servers.
+/// Contains the application configuration, Tera template engines (one per
+/// rendering mode), and the federated NNTP service for accessing newsgroup servers.
 #[derive(Clone)]
 pub struct AppState {
     pub config: Arc<AppConfig>,
     pub tera: Arc<Tera>,
+    /// Templates for the basic HTML rendering mode (basic theme over the active theme)
+    pub basic_tera: Arc<Tera>,
+    /// Templates for text browsers (text theme over the active theme)
+    pub text_tera: Arc<Tera>,
     pub nntp: NntpFederatedService,
     pub oidc: Option<OidcManager>,
+    /// Posting guidelines users must accept before posting (optional)
+    pub guidelines: Option<Arc<Guidelines>>,
+    /// Site-wide announcements, empty unless `[ui] announcements_path` is set
+    pub announcements: Arc<Announcements>,
+    /// Posts held for moderator review, empty unless
+    /// `[posting] review_first_posts` is set
+    pub review: Arc<ReviewQueue>,
+    /// Who may post on invite-only instances; lets everyone post unless
+    /// `[posting] invite_only` is set
+    pub invites: Arc<InviteStore>,
+    /// Rendered comments of thread pages seen without reply forms
+    pub thread_fragments: Cache<ThreadFragmentKey, Arc<str>>,
     /// Cookie signing key for session cookies.
     /// Generated randomly if OIDC is not configured.
     cookie_key: Key,
@@ -29,6 +53,8 @@ impl AppState {
     pub fn new(
         config: AppConfig,
         tera: Tera,
+        basic_tera: Tera,
+        text_tera: Tera,
         nntp: NntpFederatedService,
         oidc: Option<OidcManager>,
     ) -> Self {
@@ -41,11 +67,86 @@ impl AppState {
         Self {
             config: Arc::new(config),
             tera: Arc::new(tera),
+            basic_tera: Arc::new(basic_tera),
+            text_tera: Arc::new(text_tera),
             nntp,
             oidc,
+            guidelines: None,
+            announcements: Arc::new(Announcements::disabled()),
+            review: Arc::new(ReviewQueue::disabled()),
+            invites: Arc::new(InviteStore::disabled()),
+            thread_fragments: Cache::builder()
+                .max_capacity(THREAD_FRAGMENT_CACHE_MAX_ENTRIES)
+                .time_to_live(Duration::from_secs(THREAD_FRAGMENT_CACHE_TTL_SECS))
+                .build(),
             cookie_key,
         }
     }
+
+    /// Ask posters to accept `guidelines` before posting
+    pub fn with_guidelines(mut self, guidelines: Guidelines) -> Self {
+        self.guidelines = Some(Arc::new(guidelines));
+        self
+    }
+
+    /// Show `announcements` at the top of pages
+    pub fn with_announcements(mut self, announcements: Arc<Announcements>) -> Self {
+        self.announcements = announcements;
+        self
+    }
+
+    /// Use `review` as the queue of posts held for moderators
+    pub fn with_review_queue(mut self, review: ReviewQueue) -> Self {
+        self.review = Arc::new(review);
+        self
+    }
+
+    /// Use `invites` to decide who may post
+    pub fn with_invites(mut self, invites: InviteStore) -> Self {
+        self.invites = Arc::new(invites);
+        self
+    }
+
+    /// Returns the template engine for the given rendering mode.
+    pub fn tera_for(&self, mode: RenderMode) -> &Arc<Tera> {
+        match mode {
+            RenderMode::Standard => &self.tera,
+            RenderMode::Basic => &self.basic_tera,
+            RenderMode::Text => &self.text_tera,
+        }
+    }
+
+    /// Whether the user may post: always on open instances, and with an
+    /// allowed subject or a redeemed invite code on invite-only ones.
+    pub async fn can_post(&self, user: &User) -> bool {
+        self.invites.can_post(&user.provider, &user.sub).await
+    }
+
+    /// Whether the user still has to accept the current posting guidelines.
+    pub fn needs_guidelines_acceptance(&self, user: &User) -> bool {
+        self.guidelines
+            .as_ref()
+            .is_some_and(|g| user.accepted_guidelines.as_deref() != Some(g.version.as_str()))
+    }
+}
+
+/// What a page of rendered thread comments depends on. A new reply changes
+/// the comment count or last post date, and purging the group bumps its
+/// version, so a changed thread never matches an old entry.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct ThreadFragmentKey {
+    pub mode: RenderMode,
+    pub group: String,
+    pub group_version: u64,
+    pub root_message_id: String,
+    /// Comments in the whole thread, replies that lost References included
+    pub comment_count: usize,
+    pub last_post_date: Option<String>,
+    pub page: usize,
+    /// Page size from the query, as the pagination links carry it
+    pub per_page: Option<usize>,
+    /// Comment expanded server-side (basic HTML mode)
+    pub expand: Option<String>,
 }
 
 /// Implement FromRef to allow axum-extra's PrivateCookieJar to extract the Key from AppState
@@ -3,15 +3,134 @@
 //! Sets up the Tera template engine with custom filters for text processing,
 //! date formatting, and article preview generation. Supports theme layering
 //! where the active theme can selectively override templates from the default theme.
+//!
+//! Pages are rendered through [`render`], which times each render into the
+//! metrics registry and logs those slower than `SLOW_RENDER_MS`. Very long
+//! pages go through [`render_stream`] instead, which sends the HTML in chunks
+//! as it is produced rather than holding all of it in memory.
+//!
+//! Body text goes through the `linkify` filter, which escapes it and links
+//! the references `[[body.link]]` rules match for the page's group.
+
+use std::fmt::Write as _;
+use std::io::{self, Write};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 
-use chrono::{DateTime, Utc};
-use tera::Tera;
+use axum::body::{Body, Bytes};
+use chrono::Utc;
+use futures::StreamExt;
+use tera::{Context, Tera};
+use tokio::sync::mpsc;
 
 use crate::config::{
-    ThemeConfig, DEFAULT_PREVIEW_LINES, DEFAULT_TRUNCATE_WORDS, PREVIEW_HARD_LIMIT,
-    SECONDS_PER_DAY, SECONDS_PER_HOUR, SECONDS_PER_MINUTE, SECONDS_PER_MONTH, SECONDS_PER_YEAR,
+    BodyConfig, LinkRule, ThemeConfig, DEFAULT_PREVIEW_LINES, DEFAULT_TRUNCATE_WORDS,
+    PREVIEW_HARD_LIMIT, SECONDS_PER_DAY, SECONDS_PER_HOUR, SECONDS_PER_MINUTE, SECONDS_PER_MONTH,
+    SECONDS_PER_YEAR, SLOW_RENDER_MS, STREAM_RENDER_BUFFERED_CHUNKS, STREAM_RENDER_CHUNK_BYTES,
 };
 use crate::error::AppError;
+use crate::metrics::metrics;
+use crate::nntp::{diff_lines, find_diff, parse_date, split_passages, wildmat};
+use crate::routes::extract::message_id_segment;
+
+/// Render `template`, recording how long it took per template name.
+/// Every render is logged at debug level with its duration, and slow ones
+/// as warnings, so theme authors can find expensive templates and filters.
+pub fn render(tera: &Tera, template: &str, context: &Context) -> tera::Result<String> {
+    let start = Instant::now();
+    let result = tera.render(template, context);
+    record_render(template, start.elapsed());
+    result
+}
+
+/// Render `template` on a blocking thread, sending the output as a response
+/// body in `STREAM_RENDER_CHUNK_BYTES` chunks. At most
+/// `STREAM_RENDER_BUFFERED_CHUNKS` wait for the client, so a slow reader
+/// holds back the render instead of the server buffering the whole page.
+///
+/// Waits for the first chunk, so a template that fails straight away is
+/// still an error page. A failure after that can only cut the response
+/// short; it is logged.
+pub async fn render_stream(
+    tera: Arc<Tera>,
+    template: &'static str,
+    context: Context,
+) -> tera::Result<Body> {
+    let (tx, mut rx) = mpsc::channel(STREAM_RENDER_BUFFERED_CHUNKS);
+    tokio::task::spawn_blocking(move || {
+        let start = Instant::now();
+        let mut writer = ChunkWriter {
+            tx,
+            buf: Vec::with_capacity(STREAM_RENDER_CHUNK_BYTES),
+            waited: Duration::ZERO,
+        };
+        let result = tera
+            .render_to(template, &context, &mut writer)
+            .and_then(|()| writer.flush().map_err(tera::Error::from));
+        // Time spent waiting for the client isn't the template's
+        record_render(template, start.elapsed().saturating_sub(writer.waited));
+        if let Err(e) = result {
+            // Nobody is listening once the client has gone
+            if !writer.tx.is_closed() {
+                tracing::error!(template, error = %e, "Streamed template render failed");
+            }
+            let _ = writer.tx.blocking_send(Err(e));
+        }
+    });
+
+    let first = match rx.recv().await {
+        Some(chunk) => chunk?,
+        None => Bytes::new(),
+    };
+    let rest = futures::stream::unfold(rx, |mut rx| async move {
+        let chunk = rx.recv().await?;
+        Some((chunk.map_err(io::Error::other), rx))
+    });
+    let body = futures::stream::once(async move { Ok(first) }).chain(rest);
+    Ok(Body::from_stream(body))
+}
+
+/// Count a render in the metrics and log it, slow ones as warnings
+fn record_render(template: &str, elapsed: Duration) {
+    metrics().record_render(template, elapsed);
+
+    let duration_ms = elapsed.as_millis() as u64;
+    if elapsed >= Duration::from_millis(SLOW_RENDER_MS) {
+        tracing::warn!(template, duration_ms, "Slow template render");
+    } else {
+        tracing::debug!(template, duration_ms, "Rendered template");
+    }
+}
+
+/// Collects render output into chunks for [`render_stream`]
+struct ChunkWriter {
+    tx: mpsc::Sender<tera::Result<Bytes>>,
+    buf: Vec<u8>,
+    /// Time spent blocked on a full channel
+    waited: Duration,
+}
+
+impl Write for ChunkWriter {
+    fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+        self.buf.extend_from_slice(data);
+        if self.buf.len() >= STREAM_RENDER_CHUNK_BYTES {
+            self.flush()?;
+        }
+        Ok(data.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        if self.buf.is_empty() {
+            return Ok(());
+        }
+        let chunk = std::mem::replace(&mut self.buf, Vec::with_capacity(STREAM_RENDER_CHUNK_BYTES));
+        let start = Instant::now();
+        let sent = self.tx.blocking_send(Ok(Bytes::from(chunk)));
+        self.waited += start.elapsed();
+        // A client that went away ends the render
+        sent.map_err(|_| io::Error::new(io::ErrorKind::BrokenPipe, "client disconnected"))
+    }
+}
 
 /// Initialize the Tera template engine with theme support.
 ///
@@ -19,7 +138,7 @@ use crate::error::AppError;
 /// templates on top (if different from default). This allows themes to selectively
 /// override individual templates while falling back to the default for any
 /// templates not provided by the theme.
-pub fn init_templates(theme: &ThemeConfig) -> Result<Tera, AppError> {
+pub fn init_templates(theme: &ThemeConfig, body: &BodyConfig) -> Result<Tera, AppError> {
     let default_path = theme.templates_path("default");
     let default_glob = format!("{}/**/*", default_path.display());
 
@@ -57,6 +176,45 @@ pub fn init_templates(theme: &ThemeConfig) -> Result<Tera, AppError> {
     tera.register_filter("timeago", timeago_filter);
     tera.register_filter("preview", preview_filter);
     tera.register_filter("has_more_lines", has_more_lines_filter);
+    tera.register_filter("message_id_url", message_id_url_filter);
+    tera.register_filter("passages", passages_filter);
+    tera.register_filter("diff_lines", diff_lines_filter);
+    tera.register_filter("has_diff", has_diff_filter);
+    tera.register_filter("linkify", Linkify::new(body));
+
+    Ok(tera)
+}
+
+/// Initialize templates for an alternate rendering mode (e.g. basic HTML).
+///
+/// The mode theme is loaded first and the regular theme stack from
+/// [`init_templates`] is merged underneath it. `Tera::extend` never replaces
+/// templates that already exist, so mode templates win and any template the
+/// mode theme does not provide falls through to the active/default theme.
+pub fn init_mode_templates(
+    theme: &ThemeConfig,
+    body: &BodyConfig,
+    mode_theme: &str,
+) -> Result<Tera, AppError> {
+    let mode_path = theme.templates_path(mode_theme);
+    let mode_glob = format!("{}/**/*", mode_path.display());
+
+    let mut tera = Tera::new(&mode_glob).map_err(|e| {
+        AppError::Internal(format!(
+            "Failed to load '{}' mode templates from {}: {}",
+            mode_theme,
+            mode_path.display(),
+            e
+        ))
+    })?;
+
+    let base = init_templates(theme, body)?;
+    tera.extend(&base).map_err(|e| {
+        AppError::Internal(format!(
+            "Failed to merge '{}' mode templates: {}",
+            mode_theme, e
+        ))
+    })?;
 
     Ok(tera)
 }
@@ -84,6 +242,17 @@ fn truncate_words_filter(
     }
 }
 
+/// Canonical URL path segment for a message ID (see `message_id_segment`)
+fn message_id_url_filter(
+    value: &tera::Value,
+    _args: &std::collections::HashMap<String, tera::Value>,
+) -> tera::Result<tera::Value> {
+    let message_id = value
+        .as_str()
+        .ok_or_else(|| tera::Error::msg("message_id_url filter expects a string"))?;
+    Ok(tera::Value::String(message_id_segment(message_id)))
+}
+
 /// Convert a date string to a human-readable relative time (e.g., "2 hours ago")
 fn timeago_filter(
     value: &tera::Value,
@@ -93,13 +262,9 @@ fn timeago_filter(
         .as_str()
         .ok_or_else(|| tera::Error::msg("timeago filter expects a string"))?;
 
-    // Try to parse the date string (RFC 2822 format from NNTP)
-    let parsed = DateTime::parse_from_rfc2822(date_str)
-        .map(|dt| dt.with_timezone(&Utc))
-        .or_else(|_| DateTime::parse_from_rfc3339(date_str).map(|dt| dt.with_timezone(&Utc)));
-
-    match parsed {
-        Ok(date) => {
+    // Parse the date string (RFC 2822 from NNTP, with lenient fallbacks)
+    match parse_date(date_str).map(|dt| dt.with_timezone(&Utc)) {
+        Some(date) => {
             let now = Utc::now();
             let duration = now.signed_duration_since(date);
 
@@ -147,7 +312,7 @@ fn timeago_filter(
 
             Ok(tera::Value::String(result))
         }
-        Err(_) => {
+        None => {
             // If parsing fails, return the original string
             Ok(tera::Value::String(date_str.to_string()))
         }
@@ -259,6 +424,135 @@ fn strip_block_quotes(s: &str) -> String {
     lines[start..end].join("\n")
 }
 
+/// The passages of an article body, each with `id`, `text`, `quote` and
+/// `diff`, for themes that render paragraphs with their own anchors.
+fn passages_filter(
+    value: &tera::Value,
+    _: &std::collections::HashMap<String, tera::Value>,
+) -> tera::Result<tera::Value> {
+    let s = value
+        .as_str()
+        .ok_or_else(|| tera::Error::msg("passages filter expects a string"))?;
+    tera::to_value(split_passages(s)).map_err(tera::Error::msg)
+}
+
+/// The lines of a diff passage, each with `kind` ("file", "hunk", "add",
+/// "remove" or "context") and `text`, for highlighting.
+fn diff_lines_filter(
+    value: &tera::Value,
+    _: &std::collections::HashMap<String, tera::Value>,
+) -> tera::Result<tera::Value> {
+    let s = value
+        .as_str()
+        .ok_or_else(|| tera::Error::msg("diff_lines filter expects a string"))?;
+    tera::to_value(diff_lines(s)).map_err(tera::Error::msg)
+}
+
+/// Filter escaping body text as HTML and linking the references matched by
+/// the `[[body.link]]` rules for a group, given as `group` (a name or a list
+/// of names, as for an article cross-posted to several). Where matches
+/// overlap, the one starting first wins, then the earlier rule.
+struct Linkify {
+    rules: Arc<Vec<LinkRule>>,
+}
+
+impl Linkify {
+    fn new(config: &BodyConfig) -> Self {
+        Self {
+            rules: Arc::new(config.links.clone()),
+        }
+    }
+
+    fn linkify(&self, text: &str, groups: &[&str]) -> String {
+        let mut links: Vec<(std::ops::Range<usize>, String)> = Vec::new();
+        for rule in self
+            .rules
+            .iter()
+            .filter(|rule| groups.iter().any(|group| wildmat(&rule.pattern, group)))
+        {
+            for captures in rule.matches.captures_iter(text) {
+                let Some(reference) = captures.get(0).filter(|m| !m.is_empty()) else {
+                    continue;
+                };
+                let mut url = String::new();
+                captures.expand(&rule.url, &mut url);
+                links.push((reference.range(), url));
+            }
+        }
+        // Stable, so the earlier rule wins a tie
+        links.sort_by_key(|(range, _)| range.start);
+
+        let mut html = String::with_capacity(text.len());
+        let mut at = 0;
+        for (range, url) in links {
+            if range.start < at {
+                continue;
+            }
+            html.push_str(&tera::escape_html(&text[at..range.start]));
+            let _ = write!(
+                html,
+                "<a href=\"{}\" class=\"reference\">{}</a>",
+                escape_attribute(&url),
+                tera::escape_html(&text[range.clone()])
+            );
+            at = range.end;
+        }
+        html.push_str(&tera::escape_html(&text[at..]));
+        html
+    }
+}
+
+/// Escape a double-quoted attribute value. Unlike `tera::escape_html`, this
+/// leaves `/` alone so link targets stay readable in the page source.
+fn escape_attribute(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#x27;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+impl tera::Filter for Linkify {
+    fn filter(
+        &self,
+        value: &tera::Value,
+        args: &std::collections::HashMap<String, tera::Value>,
+    ) -> tera::Result<tera::Value> {
+        let s = value
+            .as_str()
+            .ok_or_else(|| tera::Error::msg("linkify filter expects a string"))?;
+        let groups: Vec<&str> = match args.get("group") {
+            Some(tera::Value::String(group)) => vec![group.as_str()],
+            Some(tera::Value::Array(groups)) => groups.iter().filter_map(|g| g.as_str()).collect(),
+            _ => Vec::new(),
+        };
+        Ok(tera::Value::String(self.linkify(s, &groups)))
+    }
+
+    /// The output is escaped here, so autoescaping must leave it alone
+    fn is_safe(&self) -> bool {
+        true
+    }
+}
+
+/// Whether an article body contains a unified diff, for a patch download link.
+fn has_diff_filter(
+    value: &tera::Value,
+    _: &std::collections::HashMap<String, tera::Value>,
+) -> tera::Result<tera::Value> {
+    let s = value
+        .as_str()
+        .ok_or_else(|| tera::Error::msg("has_diff filter expects a string"))?;
+    Ok(tera::Value::Bool(find_diff(s).is_some()))
+}
+
 /// Extracts the first N non-quote lines from an article body for preview display.
 ///
 /// Strips leading and trailing block quotes, then returns up to the specified
@@ -356,6 +650,36 @@ fn has_more_lines_filter(
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_linkify_escapes_and_links_per_group() {
+        let body: BodyConfig = toml::from_str(
+            r#"
+            [[link]]
+            pattern = "*"
+            matches = '\bRFC ?(\d{1,5})\b'
+            url = "https://www.rfc-editor.org/rfc/rfc$1"
+
+            [[link]]
+            pattern = "gmane.comp.foo"
+            matches = '#(?P<id>\d+)\b'
+            url = "https://bugs.example.org/show?id=${id}&x=1"
+            "#,
+        )
+        .unwrap();
+        let linkify = Linkify::new(&body);
+
+        assert_eq!(
+            linkify.linkify("See RFC 3977 & #12 <here>", &["comp.misc"]),
+            "See <a href=\"https://www.rfc-editor.org/rfc/rfc3977\" class=\"reference\">RFC 3977</a> &amp; #12 &lt;here&gt;"
+        );
+        assert_eq!(
+            linkify.linkify("Fixes #12", &["comp.misc", "gmane.comp.foo"]),
+            "Fixes <a href=\"https://bugs.example.org/show?id=12&amp;x=1\" class=\"reference\">#12</a>"
+        );
+        // No group, no rules: escaped text only
+        assert_eq!(linkify.linkify("RFC 1 <b>", &[]), "RFC 1 &lt;b&gt;");
+    }
+
     #[test]
     fn test_strip_block_quotes_simple() {
         let input = "> quoted line\nActual content";
@@ -440,4 +764,31 @@ mod tests {
         assert!(!is_quote_line("On vacation"));
         assert!(!is_quote_line("Something wrote something"));
     }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_render_stream_matches_render() {
+        let mut tera = Tera::default();
+        tera.add_raw_templates([
+            (
+                "long.html",
+                "{% for i in range(end=n) %}<p>Comment {{ i }}</p>\n{% endfor %}",
+            ),
+            ("broken.html", "{{ missing.field }}"),
+        ])
+        .unwrap();
+        let tera = Arc::new(tera);
+        let mut context = Context::new();
+        // Several chunks' worth
+        context.insert("n", &5000);
+
+        let expected = render(&tera, "long.html", &context).unwrap();
+        assert!(expected.len() > 2 * STREAM_RENDER_CHUNK_BYTES);
+        let body = render_stream(tera.clone(), "long.html", context.clone())
+            .await
+            .unwrap();
+        let streamed = axum::body::to_bytes(body, usize::MAX).await.unwrap();
+        assert_eq!(streamed, expected.as_bytes());
+
+        assert!(render_stream(tera, "broken.html", context).await.is_err());
+    }
 }
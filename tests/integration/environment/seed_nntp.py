@@ -2,8 +2,8 @@
 """
 Seed the NNTP server with test data for integration tests.
 
-This script connects to the Renews NNTP server and creates:
-- Test newsgroups (via newgroup control messages)
+This script connects to the mock NNTP server and creates:
+- Checks the test newsgroups exist
 - Test articles with threading (replies using References header)
 
 Usage:
@@ -86,7 +86,7 @@ def create_newsgroup(nntp: nntplib.NNTP, group: str, description: str = "") -> b
     """
     Verify a newsgroup exists.
 
-    Note: Groups are created via the renews admin CLI in docker-compose.
+    Note: Groups are created by the mock server's command line in docker-compose.
     This function just verifies they exist.
     """
     try:
@@ -17,7 +17,7 @@ TEST_USER_EMAIL = "testuser@example.com"
 TEST_USER_PASSWORD = "password"  # bcrypt hash in dex.yaml is for "password"
 TEST_USER_NAME = "testuser"
 
-# NNTP credentials (matches renews admin add-user in docker-compose.yml)
+# NNTP credentials (the mock NNTP server accepts any)
 NNTP_USERNAME = "testposter"
 NNTP_PASSWORD = "testpassword"
 
@@ -52,7 +52,7 @@ def analyze_failure(capture: TestLogCapture, exception: BaseException | None) ->
             log.service == "nntp" and "error" in log.level.lower() for log in error_logs
         ):
             analysis["error_type"] = "service_error"
-            analysis["likely_cause"] = "NNTP server (renews) encountered an error"
+            analysis["likely_cause"] = "NNTP server encountered an error"
             analysis["recommendations"].append("Check NNTP error logs for details")
         else:
             analysis["error_type"] = "test_issue"
@@ -93,7 +93,7 @@ def analyze_failure(capture: TestLogCapture, exception: BaseException | None) ->
             analysis["likely_cause"] = "September application error"
         elif "nntp" in service_errors:
             analysis["error_type"] = "nntp_error"
-            analysis["likely_cause"] = "NNTP server (renews) error"
+            analysis["likely_cause"] = "NNTP server error"
         elif "dex" in service_errors:
             analysis["error_type"] = "dex_error"
             analysis["likely_cause"] = "Dex OIDC provider error"
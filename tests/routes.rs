@@ -0,0 +1,170 @@
+//! Route tests against a mock NNTP server.
+
+mod support;
+
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine as _};
+use reqwest::StatusCode;
+
+use support::{MockArticle, MockNntp, September};
+
+const GROUP: &str = "hermetic.rust";
+
+/// Canonical path segment for a message ID, as the gateway links it
+fn segment(message_id: &str) -> String {
+    URL_SAFE_NO_PAD.encode(message_id.trim_start_matches('<').trim_end_matches('>'))
+}
+
+async fn fixture() -> MockNntp {
+    let root = MockArticle::new("<root@example.com>", "Borrowing across awaits")
+        .body("Why does this not compile?");
+    let reply = MockArticle::new("<reply@example.com>", "Re: Borrowing across awaits")
+        .date("Mon, 01 Jan 2024 13:00:00 +0000")
+        .body("Hold the guard in a smaller scope.")
+        .reply_to(&root);
+    MockNntp::builder()
+        .group(GROUP, "Rust discussion", vec![root, reply])
+        .group("hermetic.general", "Everything else", Vec::new())
+        .start()
+        .await
+}
+
+#[tokio::test]
+async fn test_home_lists_groups() {
+    let nntp = fixture().await;
+    let september = September::start(&nntp).await;
+
+    let response = september
+        .client()
+        .get(september.url("/"))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert!(response.text().await.unwrap().contains("hermetic"));
+    assert!(nntp.count("LIST") > 0);
+}
+
+#[tokio::test]
+async fn test_thread_list_and_thread_view() {
+    let nntp = fixture().await;
+    let september = September::start(&nntp).await;
+    let client = september.client();
+
+    let response = client
+        .get(september.url(&format!("/g/{}", GROUP)))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert!(response
+        .text()
+        .await
+        .unwrap()
+        .contains("Borrowing across awaits"));
+
+    let response = client
+        .get(september.url(&format!(
+            "/g/{}/thread/{}",
+            GROUP,
+            segment("<root@example.com>")
+        )))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = response.text().await.unwrap();
+    assert!(body.contains("Why does this not compile?"));
+    assert!(body.contains("Hold the guard in a smaller scope."));
+}
+
+#[tokio::test]
+async fn test_article_view() {
+    let nntp = fixture().await;
+    let september = September::start(&nntp).await;
+
+    let response = september
+        .client()
+        .get(september.url(&format!("/a/{}", segment("<reply@example.com>"))))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert!(response
+        .text()
+        .await
+        .unwrap()
+        .contains("Hold the guard in a smaller scope."));
+    assert!(nntp
+        .commands()
+        .iter()
+        .any(|c| c == "ARTICLE <reply@example.com>"));
+}
+
+#[tokio::test]
+async fn test_missing_article_and_group() {
+    let nntp = fixture().await;
+    let september = September::start(&nntp).await;
+    let client = september.client();
+
+    let response = client
+        .get(september.url(&format!("/a/{}", segment("<gone@example.com>"))))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+    let response = client
+        .get(september.url("/g/hermetic.missing"))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn test_invalid_path_parameters_never_reach_the_server() {
+    let nntp = fixture().await;
+    let september = September::start(&nntp).await;
+    let client = september.client();
+
+    let response = client
+        .get(september.url("/g/hermetic..rust"))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    assert!(!nntp.commands().iter().any(|c| c.contains("hermetic..rust")));
+}
+
+#[tokio::test]
+async fn test_legacy_message_id_redirects() {
+    let nntp = fixture().await;
+    let september = September::start(&nntp).await;
+
+    let response = september
+        .client()
+        .get(september.url("/a/%3Croot%40example.com%3E"))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::MOVED_PERMANENTLY);
+    assert_eq!(
+        response.headers()["location"],
+        format!("/a/{}", segment("<root@example.com>")).as_str()
+    );
+}
+
+#[tokio::test]
+async fn test_scripted_failure_is_reported() {
+    let nntp = fixture().await;
+    let september = September::start(&nntp).await;
+    nntp.respond("ARTICLE", "503 Program fault");
+
+    let response = september
+        .client()
+        .get(september.url(&format!("/a/{}", segment("<root@example.com>"))))
+        .send()
+        .await
+        .unwrap();
+    assert!(response.status().is_server_error());
+}
@@ -0,0 +1,644 @@
+//! Scriptable in-process NNTP server.
+//!
+//! Serves a fixed set of groups and articles over plain TCP on a loopback
+//! port, enough of RFC 3977 for the gateway's workers: CAPABILITIES,
+//! MODE READER, AUTHINFO, LIST ACTIVE/NEWSGROUPS/OVERVIEW.FMT, GROUP, OVER,
+//! HDR, HEAD, BODY, ARTICLE, STAT, POST, DATE and QUIT. Responses can be
+//! overridden per command prefix to script failures, and every command
+//! received is logged so tests can assert on the traffic the gateway
+//! generated.
+//!
+//! `examples/mock_nntp.rs` runs the same server standalone for the browser
+//! tests in `tests/integration/`.
+
+use std::collections::BTreeMap;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::task::JoinHandle;
+
+/// TLS record type of a ClientHello; workers try TLS before plain TCP
+const TLS_HANDSHAKE: u8 = 0x16;
+
+/// One article in a mock group
+#[derive(Debug, Clone)]
+pub struct MockArticle {
+    pub message_id: String,
+    pub subject: String,
+    pub from: String,
+    pub date: String,
+    pub references: Vec<String>,
+    pub body: String,
+}
+
+impl MockArticle {
+    /// Article with placeholder author, date and body
+    pub fn new(message_id: &str, subject: &str) -> Self {
+        Self {
+            message_id: message_id.to_string(),
+            subject: subject.to_string(),
+            from: "Test User <test@example.com>".to_string(),
+            date: "Mon, 01 Jan 2024 12:00:00 +0000".to_string(),
+            references: Vec::new(),
+            body: format!("Body of {}", message_id),
+        }
+    }
+
+    /// Make this a reply to `parent`, continuing its References chain
+    pub fn reply_to(mut self, parent: &MockArticle) -> Self {
+        self.references = parent.references.clone();
+        self.references.push(parent.message_id.clone());
+        self
+    }
+
+    pub fn from(mut self, from: &str) -> Self {
+        self.from = from.to_string();
+        self
+    }
+
+    pub fn date(mut self, date: &str) -> Self {
+        self.date = date.to_string();
+        self
+    }
+
+    pub fn body(mut self, body: &str) -> Self {
+        self.body = body.to_string();
+        self
+    }
+
+    fn header(&self, name: &str) -> Option<String> {
+        match name.to_ascii_lowercase().as_str() {
+            "subject" => Some(self.subject.clone()),
+            "from" => Some(self.from.clone()),
+            "date" => Some(self.date.clone()),
+            "message-id" => Some(self.message_id.clone()),
+            "references" => Some(self.references.join(" ")),
+            _ => None,
+        }
+    }
+
+    fn head(&self, group: &str) -> String {
+        let mut head = format!(
+            "Path: mock!not-for-mail\r\nFrom: {}\r\nNewsgroups: {}\r\nSubject: {}\r\n\
+             Date: {}\r\nMessage-ID: {}\r\n",
+            self.from, group, self.subject, self.date, self.message_id
+        );
+        if !self.references.is_empty() {
+            head.push_str(&format!("References: {}\r\n", self.references.join(" ")));
+        }
+        head
+    }
+
+    fn body_lines(&self) -> String {
+        dot_stuff(&self.body)
+    }
+
+    fn overview(&self, number: u64) -> String {
+        format!(
+            "{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}",
+            number,
+            self.subject,
+            self.from,
+            self.date,
+            self.message_id,
+            self.references.join(" "),
+            self.body.len(),
+            self.body.lines().count()
+        )
+    }
+}
+
+#[derive(Debug, Clone)]
+struct MockGroup {
+    description: String,
+    /// Articles numbered from 1 in insertion order
+    articles: Vec<MockArticle>,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    groups: BTreeMap<String, MockGroup>,
+    /// Canned responses by upper-case command prefix, checked in order
+    overrides: Vec<(String, String)>,
+    posting_allowed: bool,
+    /// File posted articles into their groups
+    keep_posts: bool,
+    commands: Vec<String>,
+    posted: Vec<String>,
+}
+
+impl State {
+    fn find_article(&self, message_id: &str) -> Option<(&str, &MockArticle)> {
+        self.groups.iter().find_map(|(name, group)| {
+            group
+                .articles
+                .iter()
+                .find(|a| a.message_id == message_id)
+                .map(|a| (name.as_str(), a))
+        })
+    }
+}
+
+/// Builder for a [`MockNntp`] server
+pub struct MockNntpBuilder {
+    state: State,
+    listen: SocketAddr,
+}
+
+impl MockNntpBuilder {
+    /// Add a group with its articles, numbered from 1
+    pub fn group(mut self, name: &str, description: &str, articles: Vec<MockArticle>) -> Self {
+        self.state.groups.insert(
+            name.to_string(),
+            MockGroup {
+                description: description.to_string(),
+                articles,
+            },
+        );
+        self
+    }
+
+    /// Answer commands starting with `prefix` (case-insensitive) with
+    /// `response` instead of the built-in handling
+    pub fn respond(mut self, prefix: &str, response: &str) -> Self {
+        self.state
+            .overrides
+            .push((prefix.to_ascii_uppercase(), response.to_string()));
+        self
+    }
+
+    /// Greet with 201 and refuse POST
+    pub fn read_only(mut self) -> Self {
+        self.state.posting_allowed = false;
+        self
+    }
+
+    /// Add posted articles to the groups in their Newsgroups header, so
+    /// they are served back like on a real server
+    pub fn keep_posts(mut self) -> Self {
+        self.state.keep_posts = true;
+        self
+    }
+
+    /// Listen on `addr` instead of a free loopback port
+    pub fn listen(mut self, addr: SocketAddr) -> Self {
+        self.listen = addr;
+        self
+    }
+
+    /// Bind and start accepting connections
+    pub async fn start(self) -> MockNntp {
+        let listener = TcpListener::bind(self.listen)
+            .await
+            .expect("bind mock NNTP listener");
+        let addr = listener.local_addr().expect("mock NNTP address");
+        let state = Arc::new(Mutex::new(self.state));
+
+        let accept_state = state.clone();
+        let task = tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                tokio::spawn(serve(stream, accept_state.clone()));
+            }
+        });
+
+        MockNntp { addr, state, task }
+    }
+}
+
+/// A running mock server; stops accepting connections when dropped
+pub struct MockNntp {
+    addr: SocketAddr,
+    state: Arc<Mutex<State>>,
+    task: JoinHandle<()>,
+}
+
+impl MockNntp {
+    pub fn builder() -> MockNntpBuilder {
+        MockNntpBuilder {
+            state: State {
+                posting_allowed: true,
+                ..State::default()
+            },
+            listen: SocketAddr::from(([127, 0, 0, 1], 0)),
+        }
+    }
+
+    pub fn addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    /// Every command received so far, across all connections, in order
+    pub fn commands(&self) -> Vec<String> {
+        self.state.lock().unwrap().commands.clone()
+    }
+
+    /// Number of received commands starting with `prefix` (case-insensitive)
+    pub fn count(&self, prefix: &str) -> usize {
+        let prefix = prefix.to_ascii_uppercase();
+        self.state
+            .lock()
+            .unwrap()
+            .commands
+            .iter()
+            .filter(|c| c.to_ascii_uppercase().starts_with(&prefix))
+            .count()
+    }
+
+    /// Articles received via POST, as sent (headers and body, unstuffed)
+    pub fn posted(&self) -> Vec<String> {
+        self.state.lock().unwrap().posted.clone()
+    }
+
+    /// Script a response while the server is running; see
+    /// [`MockNntpBuilder::respond`]
+    pub fn respond(&self, prefix: &str, response: &str) {
+        self.state
+            .lock()
+            .unwrap()
+            .overrides
+            .push((prefix.to_ascii_uppercase(), response.to_string()));
+    }
+}
+
+impl Drop for MockNntp {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+/// Serve one connection until QUIT or disconnect
+async fn serve(stream: TcpStream, state: Arc<Mutex<State>>) {
+    let (read, mut write) = stream.into_split();
+    let mut reader = BufReader::new(read);
+
+    let greeting = if state.lock().unwrap().posting_allowed {
+        "200 mock NNTP service ready, posting allowed\r\n"
+    } else {
+        "201 mock NNTP service ready, no posting\r\n"
+    };
+    if write.write_all(greeting.as_bytes()).await.is_err() {
+        return;
+    }
+
+    let mut selected: Option<String> = None;
+    let mut line = Vec::new();
+    loop {
+        line.clear();
+        match reader.read_until(b'\n', &mut line).await {
+            Ok(0) | Err(_) => return,
+            Ok(_) => {}
+        }
+        // A TLS attempt; the client gives up on it and reconnects in plain text
+        if line.first() == Some(&TLS_HANDSHAKE) {
+            return;
+        }
+        let command = String::from_utf8_lossy(&line).trim_end().to_string();
+        state.lock().unwrap().commands.push(command.clone());
+
+        let response = if command.eq_ignore_ascii_case("POST") {
+            receive_post(&mut reader, &mut write, &state).await
+        } else {
+            let state = state.lock().unwrap();
+            respond(&command, &mut selected, &state)
+        };
+        let Some(response) = response else {
+            return;
+        };
+        if write.write_all(response.as_bytes()).await.is_err() {
+            return;
+        }
+        if command.eq_ignore_ascii_case("QUIT") {
+            return;
+        }
+    }
+}
+
+/// Run the 340 exchange and store the article. `None` if the client
+/// disconnected mid-article.
+async fn receive_post(
+    reader: &mut BufReader<tokio::net::tcp::OwnedReadHalf>,
+    write: &mut tokio::net::tcp::OwnedWriteHalf,
+    state: &Arc<Mutex<State>>,
+) -> Option<String> {
+    let scripted = scripted(&state.lock().unwrap(), "POST");
+    if let Some(response) = scripted {
+        return Some(response);
+    }
+    if !state.lock().unwrap().posting_allowed {
+        return Some("440 Posting not permitted\r\n".to_string());
+    }
+    write.write_all(b"340 Send article\r\n").await.ok()?;
+
+    let mut article = String::new();
+    let mut line = Vec::new();
+    loop {
+        line.clear();
+        if reader.read_until(b'\n', &mut line).await.ok()? == 0 {
+            return None;
+        }
+        let text = String::from_utf8_lossy(&line);
+        let text = text.trim_end_matches(['\r', '\n']);
+        if text == "." {
+            break;
+        }
+        article.push_str(text.strip_prefix('.').unwrap_or(text));
+        article.push_str("\r\n");
+    }
+    let mut state = state.lock().unwrap();
+    if state.keep_posts {
+        file_post(&mut state, &article);
+    }
+    state.posted.push(article);
+    Some("240 Article received\r\n".to_string())
+}
+
+/// Add a posted article to each known group it names
+fn file_post(state: &mut State, article: &str) {
+    let (head, body) = article.split_once("\r\n\r\n").unwrap_or((article, ""));
+    let mut headers: Vec<(String, String)> = Vec::new();
+    for line in head.split("\r\n") {
+        match headers.last_mut() {
+            Some((_, value)) if line.starts_with([' ', '\t']) => {
+                value.push(' ');
+                value.push_str(line.trim());
+            }
+            _ => {
+                if let Some((name, value)) = line.split_once(':') {
+                    headers.push((name.trim().to_ascii_lowercase(), value.trim().to_string()));
+                }
+            }
+        }
+    }
+    let header = |name: &str| {
+        headers
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, v)| v.clone())
+            .unwrap_or_default()
+    };
+
+    let message_id = match header("message-id") {
+        id if id.is_empty() => format!("<post-{}@mock>", state.posted.len() + 1),
+        id => id,
+    };
+    let article = MockArticle {
+        message_id,
+        subject: header("subject"),
+        from: header("from"),
+        date: header("date"),
+        references: header("references")
+            .split_whitespace()
+            .map(String::from)
+            .collect(),
+        body: body.trim_end_matches("\r\n").replace("\r\n", "\n"),
+    };
+    for name in header("newsgroups").split(',') {
+        if let Some(group) = state.groups.get_mut(name.trim()) {
+            group.articles.push(article.clone());
+        }
+    }
+}
+
+fn scripted(state: &State, command: &str) -> Option<String> {
+    let upper = command.to_ascii_uppercase();
+    state
+        .overrides
+        .iter()
+        .find(|(prefix, _)| upper.starts_with(prefix.as_str()))
+        .map(|(_, response)| {
+            if response.ends_with("\r\n") {
+                response.clone()
+            } else {
+                format!("{}\r\n", response)
+            }
+        })
+}
+
+/// Built-in handling of every command except POST
+fn respond(command: &str, selected: &mut Option<String>, state: &State) -> Option<String> {
+    if let Some(response) = scripted(state, command) {
+        return Some(response);
+    }
+
+    let mut words = command.split_whitespace();
+    let verb = words.next().unwrap_or_default().to_ascii_uppercase();
+    let args: Vec<&str> = words.collect();
+
+    let response = match verb.as_str() {
+        "CAPABILITIES" => multiline(
+            "101 Capability list follows",
+            [
+                "VERSION 2",
+                "READER",
+                "POST",
+                "OVER",
+                "HDR",
+                "LIST ACTIVE NEWSGROUPS OVERVIEW.FMT",
+            ]
+            .map(String::from),
+        ),
+        "MODE"
+            if args
+                .first()
+                .is_some_and(|a| a.eq_ignore_ascii_case("READER")) =>
+        {
+            if state.posting_allowed {
+                "200 Posting allowed\r\n".to_string()
+            } else {
+                "201 Posting prohibited\r\n".to_string()
+            }
+        }
+        // Any credentials are accepted
+        "AUTHINFO" => match args.first().map(|a| a.to_ascii_uppercase()).as_deref() {
+            Some("USER") => "381 Password required\r\n".to_string(),
+            Some("PASS") => "281 Authentication accepted\r\n".to_string(),
+            _ => "501 Syntax error\r\n".to_string(),
+        },
+        "LIST" => list(args.first().copied().unwrap_or("ACTIVE"), state),
+        "GROUP" => match args
+            .first()
+            .and_then(|name| state.groups.get_key_value(*name))
+        {
+            Some((name, group)) => {
+                *selected = Some(name.clone());
+                let count = group.articles.len();
+                format!(
+                    "211 {} {} {} {}\r\n",
+                    count,
+                    if count == 0 { 0 } else { 1 },
+                    count,
+                    name
+                )
+            }
+            None => "411 No such newsgroup\r\n".to_string(),
+        },
+        "OVER" | "XOVER" => match in_range(selected, state, args.first().copied()) {
+            Ok(articles) if articles.is_empty() => "423 No articles in that range\r\n".to_string(),
+            Ok(articles) => multiline(
+                "224 Overview information follows",
+                articles.into_iter().map(|(n, a)| a.overview(n)),
+            ),
+            Err(response) => response,
+        },
+        "HDR" | "XHDR" => {
+            let field = args.first().copied().unwrap_or_default();
+            match in_range(selected, state, args.get(1).copied()) {
+                Ok(articles) if articles.is_empty() => {
+                    "423 No articles in that range\r\n".to_string()
+                }
+                Ok(articles) => multiline(
+                    "225 Headers follow",
+                    articles
+                        .into_iter()
+                        .map(|(n, a)| format!("{} {}", n, a.header(field).unwrap_or_default())),
+                ),
+                Err(response) => response,
+            }
+        }
+        "ARTICLE" | "HEAD" | "BODY" | "STAT" => {
+            match lookup(selected, state, args.first().copied()) {
+                Ok((group, number, article)) => {
+                    let (code, content) = match verb.as_str() {
+                        "ARTICLE" => (
+                            220,
+                            Some(format!(
+                                "{}\r\n{}",
+                                article.head(group),
+                                article.body_lines()
+                            )),
+                        ),
+                        "HEAD" => (221, Some(article.head(group))),
+                        "BODY" => (222, Some(article.body_lines())),
+                        _ => (223, None),
+                    };
+                    let status = format!("{} {} {}", code, number, article.message_id);
+                    match content {
+                        Some(content) => format!("{}\r\n{}.\r\n", status, content),
+                        None => format!("{}\r\n", status),
+                    }
+                }
+                Err(response) => response,
+            }
+        }
+        "DATE" => "111 20240101120000\r\n".to_string(),
+        "QUIT" => "205 Bye\r\n".to_string(),
+        _ => "500 Unknown command\r\n".to_string(),
+    };
+    Some(response)
+}
+
+fn list(keyword: &str, state: &State) -> String {
+    match keyword.to_ascii_uppercase().as_str() {
+        "ACTIVE" => multiline(
+            "215 List of newsgroups follows",
+            state.groups.iter().map(|(name, group)| {
+                let count = group.articles.len();
+                format!("{} {} {} y", name, count, if count == 0 { 0 } else { 1 })
+            }),
+        ),
+        "NEWSGROUPS" => multiline(
+            "215 Descriptions follow",
+            state
+                .groups
+                .iter()
+                .map(|(name, group)| format!("{}\t{}", name, group.description)),
+        ),
+        "OVERVIEW.FMT" => multiline(
+            "215 Order of fields in overview database",
+            [
+                "Subject:",
+                "From:",
+                "Date:",
+                "Message-ID:",
+                "References:",
+                ":bytes",
+                ":lines",
+            ]
+            .map(String::from),
+        ),
+        _ => "501 Unsupported LIST keyword\r\n".to_string(),
+    }
+}
+
+/// Articles of the selected group within an `N`, `N-` or `N-M` range
+fn in_range<'a>(
+    selected: &Option<String>,
+    state: &'a State,
+    range: Option<&str>,
+) -> Result<Vec<(u64, &'a MockArticle)>, String> {
+    let Some(group) = selected.as_ref().and_then(|name| state.groups.get(name)) else {
+        return Err("412 No newsgroup selected\r\n".to_string());
+    };
+    let last = group.articles.len() as u64;
+    let (low, high) = match range {
+        None => (1, last),
+        Some(range) => match range.split_once('-') {
+            Some((low, "")) => (low.parse().unwrap_or(1), last),
+            Some((low, high)) => (low.parse().unwrap_or(1), high.parse().unwrap_or(last)),
+            None => {
+                let n = range.parse().unwrap_or(0);
+                (n, n)
+            }
+        },
+    };
+    Ok(group
+        .articles
+        .iter()
+        .zip(1u64..)
+        .filter(|(_, n)| (low..=high).contains(n))
+        .map(|(a, n)| (n, a))
+        .collect())
+}
+
+/// Resolve an ARTICLE-style argument: a message-id, or a number in the
+/// selected group
+fn lookup<'a>(
+    selected: &'a Option<String>,
+    state: &'a State,
+    arg: Option<&str>,
+) -> Result<(&'a str, u64, &'a MockArticle), String> {
+    match arg {
+        Some(id) if id.starts_with('<') => state
+            .find_article(id)
+            .map(|(group, article)| (group, 0, article))
+            .ok_or_else(|| "430 No such article\r\n".to_string()),
+        arg => {
+            let Some((name, group)) = selected
+                .as_ref()
+                .and_then(|name| state.groups.get_key_value(name))
+            else {
+                return Err("412 No newsgroup selected\r\n".to_string());
+            };
+            let number: u64 = arg.and_then(|n| n.parse().ok()).unwrap_or(0);
+            number
+                .checked_sub(1)
+                .and_then(|i| group.articles.get(i as usize))
+                .map(|article| (name.as_str(), number, article))
+                .ok_or_else(|| "423 No article with that number\r\n".to_string())
+        }
+    }
+}
+
+fn multiline(status: &str, lines: impl IntoIterator<Item = String>) -> String {
+    let mut response = format!("{}\r\n", status);
+    for line in lines {
+        response.push_str(&dot_stuff(&line));
+    }
+    response.push_str(".\r\n");
+    response
+}
+
+/// CRLF-terminate each line, doubling leading dots
+fn dot_stuff(text: &str) -> String {
+    text.lines()
+        .map(|line| {
+            if line.starts_with('.') {
+                format!(".{}\r\n", line)
+            } else {
+                format!("{}\r\n", line)
+            }
+        })
+        .collect()
+}
@@ -0,0 +1,145 @@
+//! Shared harness for hermetic integration tests.
+//!
+//! [`September::start`] runs the built binary against a [`MockNntp`] server
+//! with a throwaway config, so tests exercise the real router, templates and
+//! federated service without Docker or a live NNTP server.
+
+// Each test crate uses a different subset of the harness
+#![allow(dead_code)]
+
+pub mod mock_nntp;
+
+use std::io::Write;
+use std::net::TcpListener;
+use std::process::{Child, Command, Stdio};
+use std::time::Duration;
+
+use tempfile::NamedTempFile;
+
+pub use mock_nntp::{MockArticle, MockNntp};
+
+/// How long to wait for the gateway to answer /health
+const STARTUP_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// A running gateway process, killed when dropped
+pub struct September {
+    child: Child,
+    base_url: String,
+    // Kept alive for the lifetime of the process
+    _config: NamedTempFile,
+}
+
+impl September {
+    /// Start the gateway with `nntp` as its only server
+    pub async fn start(nntp: &MockNntp) -> Self {
+        Self::start_with(nntp, "").await
+    }
+
+    /// Start the gateway, appending `extra` TOML to the generated config
+    pub async fn start_with(nntp: &MockNntp, extra: &str) -> Self {
+        let port = free_port();
+        let config = format!(
+            r#"
+[http]
+host = "127.0.0.1"
+port = {port}
+request_timeout_seconds = 10
+
+[http.tls]
+mode = "none"
+
+[nntp]
+timeout_seconds = 5
+request_timeout_seconds = 5
+threads_per_page = 25
+articles_per_page = 20
+
+[[server]]
+name = "mock"
+host = "127.0.0.1"
+port = {nntp_port}
+worker_count = 2
+
+[ui]
+site_name = "Mock Gateway"
+collapse_threshold = 5
+
+[theme]
+name = "default"
+themes_dir = "{themes_dir}"
+
+{extra}
+"#,
+            port = port,
+            nntp_port = nntp.addr().port(),
+            themes_dir = concat!(env!("CARGO_MANIFEST_DIR"), "/dist/themes"),
+            extra = extra,
+        );
+
+        let mut file = NamedTempFile::new().expect("create config file");
+        file.write_all(config.as_bytes())
+            .expect("write config file");
+
+        let child = Command::new(env!("CARGO_BIN_EXE_september"))
+            .arg("--config")
+            .arg(file.path())
+            .arg("--log-level")
+            .arg("warn")
+            .stdout(Stdio::null())
+            .spawn()
+            .expect("spawn september");
+
+        let server = Self {
+            child,
+            base_url: format!("http://127.0.0.1:{}", port),
+            _config: file,
+        };
+        server.wait_until_ready().await;
+        server
+    }
+
+    /// Absolute URL for `path`
+    pub fn url(&self, path: &str) -> String {
+        format!("{}{}", self.base_url, path)
+    }
+
+    /// Client that leaves redirects for the test to inspect
+    pub fn client(&self) -> reqwest::Client {
+        reqwest::Client::builder()
+            .redirect(reqwest::redirect::Policy::none())
+            .build()
+            .expect("build HTTP client")
+    }
+
+    async fn wait_until_ready(&self) {
+        let client = self.client();
+        let deadline = tokio::time::Instant::now() + STARTUP_TIMEOUT;
+        while tokio::time::Instant::now() < deadline {
+            if let Ok(response) = client.get(self.url("/health")).send().await {
+                if response.status().is_success() {
+                    return;
+                }
+            }
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+        panic!(
+            "september did not become ready within {:?}",
+            STARTUP_TIMEOUT
+        );
+    }
+}
+
+impl Drop for September {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+/// A loopback port nothing is listening on
+fn free_port() -> u16 {
+    TcpListener::bind("127.0.0.1:0")
+        .and_then(|listener| listener.local_addr())
+        .map(|addr| addr.port())
+        .expect("find free port")
+}